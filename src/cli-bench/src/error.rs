@@ -29,6 +29,9 @@ pub enum BenchMarkError {
     #[error("Parsing error: {0}")]
     ParseError(#[from] serde_json::Error),
 
+    #[error("MQTT error: {0}")]
+    MqttError(#[from] paho_mqtt::Error),
+
     #[error("Common error: {0}")]
     CommonError(#[from] Box<CommonError>),
 
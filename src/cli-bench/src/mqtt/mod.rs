@@ -13,9 +13,223 @@
 // limitations under the License.
 
 pub mod publish;
+pub mod report;
 pub mod subscribe;
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use clap::Parser;
+use common_base::runtime::create_runtime;
+use futures::future;
+use paho_mqtt::{AsyncClient, ConnectOptionsBuilder, CreateOptionsBuilder};
+use publish::run_publisher;
+use report::{BenchStats, MqttBenchReport};
+use subscribe::run_subscriber;
+use tokio::time::Instant;
+
+use crate::{BenchMark, BenchMarkError};
+
+// Time the subscribers keep receiving after the last message was published
+const DRAIN_SECS: u64 = 3;
+
+#[derive(Debug, Clone, Parser)]
+pub struct MqttBenchArgs {
+    /// The address of the MQTT broker
+    #[clap(long, default_value = "tcp://127.0.0.1:1883")]
+    pub server: String,
+
+    #[clap(long)]
+    pub username: Option<String>,
+
+    #[clap(long)]
+    pub password: Option<String>,
+
+    /// The number of publishing connections
+    #[clap(long, default_value = "10")]
+    pub publishers: usize,
+
+    /// The number of subscribing connections
+    #[clap(long, default_value = "10")]
+    pub subscribers: usize,
+
+    /// The time over which the connections are opened, subscribers first
+    #[clap(long, default_value = "0")]
+    pub ramp_up_secs: u64,
+
+    /// How long the publishers publish once all connections are opened
+    #[clap(long, default_value = "60")]
+    pub duration_secs: u64,
+
+    /// Messages per second of each publisher, 0 publishes as fast as the broker acknowledges
+    #[clap(long, default_value = "10")]
+    pub rate: u64,
+
+    /// The size of each payload in bytes, the first 8 bytes hold the send time
+    #[clap(long, default_value = "256")]
+    pub payload_size: usize,
+
+    #[clap(long, default_value = "0", value_parser = clap::value_parser!(i32).range(0..=2))]
+    pub qos: i32,
+
+    /// The topic of each publisher, %i is replaced by its index and %c by its client id
+    #[clap(long, default_value = "bench/%i")]
+    pub topic: String,
+
+    /// The topic filter of each subscriber, %i and %c are replaced as in --topic.
+    /// Shared subscriptions such as $share/group/bench/# spread the messages over the subscribers
+    #[clap(long, default_value = "bench/#")]
+    pub sub_topic: String,
+
+    #[clap(long, default_value = "robust-bench")]
+    pub client_id_prefix: String,
+
+    /// The number of worker threads to run the benchmark
+    #[clap(long, default_value = "4")]
+    pub worker_threads: usize,
+
+    /// The format of the result: table, csv or json
+    #[clap(long, default_value = "table", value_parser = ["table", "csv", "json"])]
+    pub output: String,
+
+    /// Write the result to this file instead of stdout
+    #[clap(long)]
+    pub output_file: Option<String>,
+}
+
+pub fn handle_mqtt_bench(args: MqttBenchArgs) -> Result<(), BenchMarkError> {
+    let rt = create_runtime("bench-mqtt", args.worker_threads);
+    rt.block_on(args.do_bench())
+}
+
+#[axum::async_trait]
+impl BenchMark for MqttBenchArgs {
+    fn validate(&self) -> Result<(), BenchMarkError> {
+        if self.publishers == 0 && self.subscribers == 0 {
+            return Err(BenchMarkError::InvalidConfiguration(
+                "at least one publisher or subscriber is required".to_string(),
+            ));
+        }
+        if self.payload_size < publish::TIMESTAMP_LEN {
+            return Err(BenchMarkError::InvalidConfiguration(format!(
+                "payload size must be at least {} bytes",
+                publish::TIMESTAMP_LEN
+            )));
+        }
+        if self.duration_secs == 0 {
+            return Err(BenchMarkError::InvalidConfiguration(
+                "duration must be at least 1 second".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn do_bench(&self) -> Result<(), BenchMarkError> {
+        self.validate()?;
+
+        println!(
+            "Starting MQTT Benchmark with {} publishers, {} subscribers, rate: {}/s, payload size: {}, qos: {}, topic: {}, sub topic: {}, server: {}",
+            self.publishers,
+            self.subscribers,
+            self.rate,
+            self.payload_size,
+            self.qos,
+            self.topic,
+            self.sub_topic,
+            self.server
+        );
+
+        let args = Arc::new(self.clone());
+        let stats = Arc::new(BenchStats::default());
+
+        // Connection k of all connections is opened at k * ramp_up / total
+        let total = self.subscribers + self.publishers;
+        let ramp_up = Duration::from_secs(self.ramp_up_secs);
+        let start = Instant::now();
+        let start_at = |k: usize| start + ramp_up.mul_f64(k as f64 / total as f64);
+        let deadline = start + ramp_up + Duration::from_secs(self.duration_secs);
+        let stop_at = deadline + Duration::from_secs(DRAIN_SECS);
+
+        let mut sub_handles = Vec::with_capacity(self.subscribers);
+        for index in 0..self.subscribers {
+            sub_handles.push(tokio::spawn(run_subscriber(
+                args.clone(),
+                index,
+                start_at(index),
+                stop_at,
+                stats.clone(),
+            )));
+        }
+
+        let mut pub_handles = Vec::with_capacity(self.publishers);
+        for index in 0..self.publishers {
+            pub_handles.push(tokio::spawn(run_publisher(
+                args.clone(),
+                index,
+                start_at(self.subscribers + index),
+                deadline,
+                stats.clone(),
+            )));
+        }
+
+        future::join_all(pub_handles).await;
+        let elapsed = start.elapsed();
+
+        let mut latencies = Vec::new();
+        for list in future::join_all(sub_handles).await.into_iter().flatten() {
+            latencies.extend(list);
+        }
+
+        let report = MqttBenchReport::new(&args, &stats, latencies, elapsed);
+        report.output(&args.output, args.output_file.as_deref())
+    }
+}
+
+pub(crate) fn topic_name(pattern: &str, index: usize, client_id: &str) -> String {
+    pattern
+        .replace("%i", &index.to_string())
+        .replace("%c", client_id)
+}
+
+pub(crate) fn build_client(
+    args: &MqttBenchArgs,
+    client_id: &str,
+) -> Result<AsyncClient, BenchMarkError> {
+    let create_opts = CreateOptionsBuilder::new()
+        .server_uri(args.server.as_str())
+        .client_id(client_id)
+        .finalize();
+    Ok(AsyncClient::new(create_opts)?)
+}
+
+/// Connects the client and records how long it took.
+pub(crate) async fn connect(
+    args: &MqttBenchArgs,
+    client: &AsyncClient,
+    stats: &BenchStats,
+) -> Result<(), BenchMarkError> {
+    let mut builder = ConnectOptionsBuilder::new_v5();
+    builder
+        .keep_alive_interval(Duration::from_secs(60))
+        .clean_start(true)
+        .connect_timeout(Duration::from_secs(30));
+    if let Some(username) = &args.username {
+        builder.user_name(username.as_str());
+    }
+    if let Some(password) = &args.password {
+        builder.password(password.as_str());
+    }
 
-#[derive(Debug, Parser)]
-pub struct MqttBenchArgs {}
+    let now = Instant::now();
+    match client.connect(builder.finalize()).await {
+        Ok(_) => {
+            stats.record_connect(now.elapsed());
+            Ok(())
+        }
+        Err(e) => {
+            stats.record_connect_failure();
+            eprintln!("Client {} failed to connect: {}", client.client_id(), e);
+            Err(e.into())
+        }
+    }
+}
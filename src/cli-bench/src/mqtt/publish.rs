@@ -11,3 +11,77 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::tools::now_nanos;
+use paho_mqtt::Message;
+use tokio::time::{interval, sleep_until, Instant, MissedTickBehavior};
+
+use super::report::BenchStats;
+use super::{build_client, connect, topic_name, MqttBenchArgs};
+
+// The send time in microseconds since the epoch, at the start of each payload
+pub const TIMESTAMP_LEN: usize = 8;
+
+pub async fn run_publisher(
+    args: Arc<MqttBenchArgs>,
+    index: usize,
+    start_at: Instant,
+    deadline: Instant,
+    stats: Arc<BenchStats>,
+) {
+    sleep_until(start_at).await;
+
+    let client_id = format!("{}-pub-{}", args.client_id_prefix, index);
+    let client = match build_client(&args, &client_id) {
+        Ok(client) => client,
+        Err(e) => {
+            stats.record_connect_failure();
+            eprintln!("Failed to create client {}: {}", client_id, e);
+            return;
+        }
+    };
+    if connect(&args, &client, &stats).await.is_err() {
+        return;
+    }
+
+    let topic = topic_name(&args.topic, index, &client_id);
+    let mut ticker = if args.rate > 0 {
+        let mut ticker = interval(Duration::from_secs_f64(1.0 / args.rate as f64));
+        // A publisher that falls behind does not catch up in bursts
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        Some(ticker)
+    } else {
+        None
+    };
+
+    while Instant::now() < deadline {
+        if let Some(ticker) = ticker.as_mut() {
+            ticker.tick().await;
+        }
+        let message = Message::new(&topic, build_payload(args.payload_size), args.qos);
+        match client.publish(message).await {
+            Ok(_) => stats.record_publish(),
+            Err(_) => stats.record_publish_failure(),
+        }
+    }
+
+    let _ = client.disconnect(None).await;
+}
+
+pub fn build_payload(payload_size: usize) -> Vec<u8> {
+    let mut payload = vec![0; payload_size.max(TIMESTAMP_LEN)];
+    let now = (now_nanos() / 1000) as u64;
+    payload[..TIMESTAMP_LEN].copy_from_slice(&now.to_be_bytes());
+    payload
+}
+
+/// The time since the payload was built, `None` for payloads that were not sent by the benchmark.
+pub fn payload_latency_micros(payload: &[u8]) -> Option<u64> {
+    let bytes: [u8; TIMESTAMP_LEN] = payload.get(..TIMESTAMP_LEN)?.try_into().ok()?;
+    let send_time = u64::from_be_bytes(bytes);
+    let now = (now_nanos() / 1000) as u64;
+    now.checked_sub(send_time)
+}
@@ -0,0 +1,238 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use prettytable::{row, Table};
+use serde::Serialize;
+
+use super::MqttBenchArgs;
+use crate::BenchMarkError;
+
+#[derive(Default)]
+pub struct BenchStats {
+    connect_success: AtomicU64,
+    connect_failed: AtomicU64,
+    publish_success: AtomicU64,
+    publish_failed: AtomicU64,
+    received: AtomicU64,
+    // Connect latencies in microseconds
+    connect_latencies: Mutex<Vec<u64>>,
+}
+
+impl BenchStats {
+    pub fn record_connect(&self, latency: Duration) {
+        self.connect_success.fetch_add(1, Ordering::Relaxed);
+        self.connect_latencies
+            .lock()
+            .unwrap()
+            .push(latency.as_micros() as u64);
+    }
+
+    pub fn record_connect_failure(&self) {
+        self.connect_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_publish(&self) {
+        self.publish_success.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_publish_failure(&self) {
+        self.publish_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_receive(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The result of a run. Latencies are in microseconds, rates in messages per second averaged
+/// over the whole run, ramp-up included.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct MqttBenchReport {
+    pub publishers: usize,
+    pub subscribers: usize,
+    pub qos: i32,
+    pub payload_size: usize,
+    pub elapsed_secs: f64,
+    pub connect_success: u64,
+    pub connect_failed: u64,
+    pub connect_p50_us: u64,
+    pub connect_p99_us: u64,
+    pub publish_success: u64,
+    pub publish_failed: u64,
+    pub publish_rate: f64,
+    pub received: u64,
+    pub receive_rate: f64,
+    pub latency_min_us: u64,
+    pub latency_avg_us: u64,
+    pub latency_p50_us: u64,
+    pub latency_p90_us: u64,
+    pub latency_p99_us: u64,
+    pub latency_p999_us: u64,
+    pub latency_max_us: u64,
+}
+
+impl MqttBenchReport {
+    pub fn new(
+        args: &MqttBenchArgs,
+        stats: &BenchStats,
+        mut latencies: Vec<u64>,
+        elapsed: Duration,
+    ) -> Self {
+        let mut connect_latencies = stats.connect_latencies.lock().unwrap().clone();
+        connect_latencies.sort_unstable();
+        latencies.sort_unstable();
+
+        let elapsed_secs = elapsed.as_secs_f64();
+        let publish_success = stats.publish_success.load(Ordering::Relaxed);
+        let received = stats.received.load(Ordering::Relaxed);
+        MqttBenchReport {
+            publishers: args.publishers,
+            subscribers: args.subscribers,
+            qos: args.qos,
+            payload_size: args.payload_size,
+            elapsed_secs,
+            connect_success: stats.connect_success.load(Ordering::Relaxed),
+            connect_failed: stats.connect_failed.load(Ordering::Relaxed),
+            connect_p50_us: percentile(&connect_latencies, 0.5),
+            connect_p99_us: percentile(&connect_latencies, 0.99),
+            publish_success,
+            publish_failed: stats.publish_failed.load(Ordering::Relaxed),
+            publish_rate: publish_success as f64 / elapsed_secs,
+            received,
+            receive_rate: received as f64 / elapsed_secs,
+            latency_min_us: latencies.first().copied().unwrap_or_default(),
+            latency_avg_us: if latencies.is_empty() {
+                0
+            } else {
+                latencies.iter().sum::<u64>() / latencies.len() as u64
+            },
+            latency_p50_us: percentile(&latencies, 0.5),
+            latency_p90_us: percentile(&latencies, 0.9),
+            latency_p99_us: percentile(&latencies, 0.99),
+            latency_p999_us: percentile(&latencies, 0.999),
+            latency_max_us: latencies.last().copied().unwrap_or_default(),
+        }
+    }
+
+    pub fn output(&self, format: &str, file: Option<&str>) -> Result<(), BenchMarkError> {
+        let content = match format {
+            "json" => serde_json::to_string_pretty(self)?,
+            "csv" => self.to_csv(),
+            _ => self.to_table().to_string(),
+        };
+        match file {
+            Some(path) => {
+                fs::write(path, content)?;
+                println!("Benchmark result written to {}", path);
+            }
+            None => println!("{}", content),
+        }
+        Ok(())
+    }
+
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("publishers", self.publishers.to_string()),
+            ("subscribers", self.subscribers.to_string()),
+            ("qos", self.qos.to_string()),
+            ("payload_size", self.payload_size.to_string()),
+            ("elapsed_secs", format!("{:.3}", self.elapsed_secs)),
+            ("connect_success", self.connect_success.to_string()),
+            ("connect_failed", self.connect_failed.to_string()),
+            ("connect_p50_us", self.connect_p50_us.to_string()),
+            ("connect_p99_us", self.connect_p99_us.to_string()),
+            ("publish_success", self.publish_success.to_string()),
+            ("publish_failed", self.publish_failed.to_string()),
+            ("publish_rate", format!("{:.2}", self.publish_rate)),
+            ("received", self.received.to_string()),
+            ("receive_rate", format!("{:.2}", self.receive_rate)),
+            ("latency_min_us", self.latency_min_us.to_string()),
+            ("latency_avg_us", self.latency_avg_us.to_string()),
+            ("latency_p50_us", self.latency_p50_us.to_string()),
+            ("latency_p90_us", self.latency_p90_us.to_string()),
+            ("latency_p99_us", self.latency_p99_us.to_string()),
+            ("latency_p999_us", self.latency_p999_us.to_string()),
+            ("latency_max_us", self.latency_max_us.to_string()),
+        ]
+    }
+
+    fn to_csv(&self) -> String {
+        let fields = self.fields();
+        let header: Vec<&str> = fields.iter().map(|(name, _)| *name).collect();
+        let values: Vec<&str> = fields.iter().map(|(_, value)| value.as_str()).collect();
+        format!("{}\n{}\n", header.join(","), values.join(","))
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.set_titles(row!["metric", "value"]);
+        for (name, value) in self.fields() {
+            table.add_row(row![name, value]);
+        }
+        table
+    }
+}
+
+/// The value below which `p` of the sorted values fall, 0 when there are none.
+pub fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let index = ((sorted.len() as f64 * p) as usize).min(sorted.len() - 1);
+    sorted[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mqtt::publish::{build_payload, payload_latency_micros};
+
+    #[test]
+    fn percentile_of_sorted_values() {
+        let values: Vec<u64> = (1..=1000).collect();
+        assert_eq!(percentile(&values, 0.5), 501);
+        assert_eq!(percentile(&values, 0.99), 991);
+        assert_eq!(percentile(&values, 1.0), 1000);
+        assert_eq!(percentile(&[], 0.5), 0);
+    }
+
+    #[test]
+    fn payload_carries_send_time() {
+        let payload = build_payload(64);
+        assert_eq!(payload.len(), 64);
+        assert!(payload_latency_micros(&payload).unwrap() < 1_000_000);
+        assert!(payload_latency_micros(b"abc").is_none());
+    }
+
+    #[test]
+    fn csv_output() {
+        let report = MqttBenchReport {
+            publishers: 2,
+            received: 10,
+            ..Default::default()
+        };
+        let csv = report.to_csv();
+        let mut lines = csv.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("publishers,subscribers,qos"));
+        assert!(lines.next().unwrap().starts_with("2,0,0"));
+    }
+}
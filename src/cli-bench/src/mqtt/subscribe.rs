@@ -11,3 +11,72 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::sync::Arc;
+
+use futures::StreamExt;
+use tokio::select;
+use tokio::time::{sleep_until, Instant};
+
+use super::publish::payload_latency_micros;
+use super::report::BenchStats;
+use super::{build_client, connect, topic_name, MqttBenchArgs};
+
+/// Receives messages until `stop_at` and returns their end-to-end latencies in microseconds.
+pub async fn run_subscriber(
+    args: Arc<MqttBenchArgs>,
+    index: usize,
+    start_at: Instant,
+    stop_at: Instant,
+    stats: Arc<BenchStats>,
+) -> Vec<u64> {
+    sleep_until(start_at).await;
+
+    let mut latencies = Vec::new();
+    let client_id = format!("{}-sub-{}", args.client_id_prefix, index);
+    let mut client = match build_client(&args, &client_id) {
+        Ok(client) => client,
+        Err(e) => {
+            stats.record_connect_failure();
+            eprintln!("Failed to create client {}: {}", client_id, e);
+            return latencies;
+        }
+    };
+    let mut stream = client.get_stream(10000);
+    if connect(&args, &client, &stats).await.is_err() {
+        return latencies;
+    }
+
+    let topic = topic_name(&args.sub_topic, index, &client_id);
+    if let Err(e) = client.subscribe(topic.as_str(), args.qos).await {
+        eprintln!("Client {} failed to subscribe {}: {}", client_id, topic, e);
+        let _ = client.disconnect(None).await;
+        return latencies;
+    }
+
+    loop {
+        select! {
+            message = stream.next() => {
+                match message {
+                    Some(Some(message)) => {
+                        stats.record_receive();
+                        if let Some(latency) = payload_latency_micros(message.payload()) {
+                            latencies.push(latency);
+                        }
+                    }
+                    // The connection was lost
+                    Some(None) | None => {
+                        eprintln!("Client {} lost its connection", client_id);
+                        break;
+                    }
+                }
+            }
+            _ = sleep_until(stop_at) => {
+                break;
+            }
+        }
+    }
+
+    let _ = client.disconnect(None).await;
+    latencies
+}
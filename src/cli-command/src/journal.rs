@@ -0,0 +1,93 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use grpc_clients::journal::admin::call::{
+    journal_admin_get_log_level, journal_admin_set_log_level,
+};
+use grpc_clients::pool::ClientPool;
+use protocol::journal_server::journal_admin::{GetLogLevelRequest, SetLogLevelRequest};
+
+use crate::{error_info, grpc_addr, log_level_info};
+
+#[derive(Clone)]
+pub struct JournalCliCommandParam {
+    pub server: String,
+    pub action: JournalActionType,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum JournalActionType {
+    SetLogLevel(SetLogLevelRequest),
+    GetLogLevel,
+}
+
+pub struct JournalServerCommand {}
+
+impl Default for JournalServerCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JournalServerCommand {
+    pub fn new() -> Self {
+        JournalServerCommand {}
+    }
+
+    pub async fn start(&self, params: JournalCliCommandParam) {
+        let client_pool = Arc::new(ClientPool::new(100));
+
+        match params.action {
+            JournalActionType::SetLogLevel(ref request) => {
+                self.set_log_level(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            JournalActionType::GetLogLevel => {
+                self.get_log_level(&client_pool, params).await;
+            }
+        }
+    }
+
+    async fn set_log_level(
+        &self,
+        client_pool: &ClientPool,
+        params: JournalCliCommandParam,
+        cli_request: SetLogLevelRequest,
+    ) {
+        match journal_admin_set_log_level(client_pool, &grpc_addr(params.server), cli_request).await
+        {
+            Ok(reply) => {
+                println!("Set log level successfully!");
+                log_level_info(reply.filter, reply.revert_at);
+            }
+            Err(e) => {
+                println!("Journal server set log level exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn get_log_level(&self, client_pool: &ClientPool, params: JournalCliCommandParam) {
+        let request = GetLogLevelRequest {};
+        match journal_admin_get_log_level(client_pool, &grpc_addr(params.server), request).await {
+            Ok(reply) => log_level_info(reply.filter, reply.revert_at),
+            Err(e) => {
+                println!("Journal server get log level exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+}
@@ -15,6 +15,7 @@
 #![allow(clippy::result_large_err)]
 use std::process;
 use std::time::Duration;
+pub mod journal;
 pub mod mqtt;
 pub mod placement;
 pub mod template;
@@ -22,6 +23,7 @@
     Client, ConnectOptions, ConnectOptionsBuilder, CreateOptions, CreateOptionsBuilder, Properties,
     PropertyCode, SslOptionsBuilder,
 };
+use prettytable::{row, Table};
 pub(crate) fn error_info(err: String) {
     println!("Exception:{}", err);
 }
@@ -30,6 +32,20 @@ pub(crate) fn grpc_addr(addr: String) -> Vec<String> {
     addr.split(",").map(|raw| raw.to_owned()).collect()
 }
 
+pub(crate) fn log_level_info(filter: String, revert_at: u64) {
+    let mut table = Table::new();
+    table.set_titles(row!["filter", "revert_at"]);
+    if filter.is_empty() {
+        table.add_row(row!["(configured levels)", "-"]);
+    } else if revert_at == 0 {
+        table.add_row(row![filter, "-"]);
+    } else {
+        table.add_row(row![filter, revert_at]);
+    }
+    // output cmd
+    table.printstd();
+}
+
 pub fn connect_server5(
     client_id: &str,
     username: String,
@@ -13,23 +13,28 @@
 // limitations under the License.
 
 use crate::template::{PublishArgsRequest, SubscribeArgsRequest};
-use crate::{connect_server5, error_info, grpc_addr};
+use crate::{connect_server5, error_info, grpc_addr, log_level_info};
 use common_base::enum_type::sort_type::SortType;
 use common_base::tools::{now_second, unique_id};
 use common_config::mqtt::config::BrokerMqttConfig;
 use grpc_clients::mqtt::admin::call::{
-    mqtt_broker_bind_schema, mqtt_broker_cluster_overview_metrics, mqtt_broker_cluster_status,
-    mqtt_broker_create_acl, mqtt_broker_create_blacklist, mqtt_broker_create_connector,
-    mqtt_broker_create_schema, mqtt_broker_create_topic_rewrite_rule, mqtt_broker_create_user,
-    mqtt_broker_delete_acl, mqtt_broker_delete_auto_subscribe_rule, mqtt_broker_delete_blacklist,
-    mqtt_broker_delete_connector, mqtt_broker_delete_schema, mqtt_broker_delete_topic_rewrite_rule,
+    mqtt_broker_bind_schema, mqtt_broker_clear_alarm, mqtt_broker_cluster_overview_metrics,
+    mqtt_broker_cluster_status, mqtt_broker_create_acl, mqtt_broker_create_blacklist,
+    mqtt_broker_create_connector, mqtt_broker_create_schema, mqtt_broker_create_topic_rewrite_rule,
+    mqtt_broker_create_user, mqtt_broker_delete_acl, mqtt_broker_delete_auto_subscribe_rule,
+    mqtt_broker_delete_blacklist, mqtt_broker_delete_connector, mqtt_broker_delete_rule,
+    mqtt_broker_delete_schema, mqtt_broker_delete_tenant, mqtt_broker_delete_topic_rewrite_rule,
     mqtt_broker_delete_user, mqtt_broker_enable_flapping_detect, mqtt_broker_get_cluster_config,
-    mqtt_broker_list_acl, mqtt_broker_list_auto_subscribe_rule, mqtt_broker_list_bind_schema,
-    mqtt_broker_list_blacklist, mqtt_broker_list_connection, mqtt_broker_list_connector,
+    mqtt_broker_get_log_level, mqtt_broker_list_acl, mqtt_broker_list_alarm,
+    mqtt_broker_list_auto_subscribe_rule, mqtt_broker_list_bind_schema, mqtt_broker_list_blacklist,
+    mqtt_broker_list_connection, mqtt_broker_list_connector, mqtt_broker_list_rule,
     mqtt_broker_list_schema, mqtt_broker_list_session, mqtt_broker_list_slow_subscribe,
-    mqtt_broker_list_system_alarm, mqtt_broker_list_topic, mqtt_broker_list_user,
-    mqtt_broker_set_auto_subscribe_rule, mqtt_broker_set_cluster_config,
-    mqtt_broker_set_system_alarm_config, mqtt_broker_unbind_schema, mqtt_broker_update_connector,
+    mqtt_broker_list_system_alarm, mqtt_broker_list_tenant, mqtt_broker_list_topic,
+    mqtt_broker_list_topic_metrics, mqtt_broker_list_trace, mqtt_broker_list_trace_record,
+    mqtt_broker_list_user, mqtt_broker_set_auto_subscribe_rule, mqtt_broker_set_cluster_config,
+    mqtt_broker_set_log_level, mqtt_broker_set_rule, mqtt_broker_set_shared_subscription_strategy,
+    mqtt_broker_set_system_alarm_config, mqtt_broker_set_tenant, mqtt_broker_start_trace,
+    mqtt_broker_stop_trace, mqtt_broker_unbind_schema, mqtt_broker_update_connector,
     mqtt_broker_update_schema,
 };
 use grpc_clients::pool::ClientPool;
@@ -39,17 +44,21 @@
 use paho_mqtt::{DisconnectOptionsBuilder, MessageBuilder, Properties, PropertyCode, ReasonCode};
 use prettytable::{row, Table};
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterOverviewMetricsRequest, ClusterStatusRequest, CreateAclRequest, CreateBlacklistRequest,
-    CreateTopicRewriteRuleRequest, CreateUserRequest, DeleteAclRequest,
-    DeleteAutoSubscribeRuleRequest, DeleteBlacklistRequest, DeleteTopicRewriteRuleRequest,
-    DeleteUserRequest, EnableFlappingDetectRequest, GetClusterConfigRequest, ListAclRequest,
-    ListAutoSubscribeRuleRequest, ListBlacklistRequest, ListConnectionRequest, ListSessionRequest,
-    ListSlowSubscribeRequest, ListSystemAlarmRequest, ListTopicRequest, ListUserRequest,
-    MqttBindSchemaRequest, MqttCreateConnectorRequest, MqttCreateSchemaRequest,
+    ClearAlarmRequest, ClusterOverviewMetricsRequest, ClusterStatusRequest, CreateAclRequest,
+    CreateBlacklistRequest, CreateTopicRewriteRuleRequest, CreateUserRequest, DeleteAclRequest,
+    DeleteAutoSubscribeRuleRequest, DeleteBlacklistRequest, DeleteRuleRequest, DeleteTenantRequest,
+    DeleteTopicRewriteRuleRequest, DeleteUserRequest, EnableFlappingDetectRequest,
+    GetClusterConfigRequest, GetLogLevelRequest, ListAclRequest, ListAlarmRequest,
+    ListAutoSubscribeRuleRequest, ListBlacklistRequest, ListConnectionRequest, ListRuleRequest,
+    ListSessionRequest, ListSlowSubscribeRequest, ListSystemAlarmRequest, ListTenantRequest,
+    ListTopicMetricsRequest, ListTopicRequest, ListTraceRecordRequest, ListTraceRequest,
+    ListUserRequest, MqttBindSchemaRequest, MqttCreateConnectorRequest, MqttCreateSchemaRequest,
     MqttDeleteConnectorRequest, MqttDeleteSchemaRequest, MqttListBindSchemaRequest,
     MqttListConnectorRequest, MqttListSchemaRequest, MqttUnbindSchemaRequest,
     MqttUpdateConnectorRequest, MqttUpdateSchemaRequest, SetAutoSubscribeRuleRequest,
-    SetClusterConfigRequest, SetSystemAlarmConfigRequest,
+    SetClusterConfigRequest, SetLogLevelRequest, SetRuleRequest,
+    SetSharedSubscriptionStrategyRequest, SetSystemAlarmConfigRequest, SetTenantRequest,
+    StartTraceRequest, StopTraceRequest,
 };
 use std::str::FromStr;
 use std::sync::Arc;
@@ -75,6 +84,10 @@ pub enum MqttActionType {
     // cluster config
     GetClusterConfig,
 
+    // log level
+    SetLogLevel(SetLogLevelRequest),
+    GetLogLevel,
+
     // session
     ListSession,
 
@@ -106,6 +119,17 @@ pub enum MqttActionType {
     // system alarm
     SetSystemAlarmConfig(SetSystemAlarmConfigRequest),
     ListSystemAlarm(ListSystemAlarmRequest),
+    ListAlarm(ListAlarmRequest),
+    ClearAlarm(ClearAlarmRequest),
+
+    // live trace
+    StartTrace(StartTraceRequest),
+    StopTrace(StopTraceRequest),
+    ListTrace(ListTraceRequest),
+    ListTraceRecord(ListTraceRecordRequest),
+
+    // topic metrics
+    ListTopicMetrics(ListTopicMetricsRequest),
 
     // topic rewrite rule
     CreateTopicRewriteRule(CreateTopicRewriteRuleRequest),
@@ -138,6 +162,17 @@ pub enum MqttActionType {
     ListAutoSubscribeRule(ListAutoSubscribeRuleRequest),
     SetAutoSubscribeRule(SetAutoSubscribeRuleRequest),
     DeleteAutoSubscribeRule(DeleteAutoSubscribeRuleRequest),
+
+    // tenant
+    ListTenant(ListTenantRequest),
+    SetTenant(SetTenantRequest),
+    DeleteTenant(DeleteTenantRequest),
+    // rule engine
+    ListRule(ListRuleRequest),
+    SetRule(SetRuleRequest),
+    DeleteRule(DeleteRuleRequest),
+    // shared subscription
+    SetSharedSubscriptionStrategy(SetSharedSubscriptionStrategyRequest),
 }
 
 pub struct MqttBrokerCommand {}
@@ -166,6 +201,15 @@ pub async fn start(&self, params: MqttCliCommandParam) {
                 self.get_cluster_config(&client_pool, params.clone()).await;
             }
 
+            // log level
+            MqttActionType::SetLogLevel(ref request) => {
+                self.set_log_level(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::GetLogLevel => {
+                self.get_log_level(&client_pool, params.clone()).await;
+            }
+
             // cluster status
             MqttActionType::Status => {
                 self.status(&client_pool, params.clone()).await;
@@ -309,6 +353,74 @@ pub async fn start(&self, params: MqttCliCommandParam) {
                 self.list_system_alarm(&client_pool, params.clone(), *request)
                     .await;
             }
+            MqttActionType::ListAlarm(ref request) => {
+                self.list_alarm(&client_pool, params.clone(), *request)
+                    .await;
+            }
+            MqttActionType::ClearAlarm(ref request) => {
+                self.clear_alarm(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+
+            // live trace
+            MqttActionType::StartTrace(ref request) => {
+                self.start_trace(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::StopTrace(ref request) => {
+                self.stop_trace(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::ListTrace(ref request) => {
+                self.list_trace(&client_pool, params.clone(), *request)
+                    .await;
+            }
+            MqttActionType::ListTraceRecord(ref request) => {
+                self.list_trace_record(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+
+            // topic metrics
+            MqttActionType::ListTopicMetrics(ref request) => {
+                self.list_topic_metrics(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+
+            // tenant
+            MqttActionType::ListTenant(ref request) => {
+                self.list_tenant(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::SetTenant(ref request) => {
+                self.set_tenant(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::DeleteTenant(ref request) => {
+                self.delete_tenant(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            // rule engine
+            MqttActionType::ListRule(ref request) => {
+                self.list_rule(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::SetRule(ref request) => {
+                self.set_rule(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::DeleteRule(ref request) => {
+                self.delete_rule(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            // shared subscription
+            MqttActionType::SetSharedSubscriptionStrategy(ref request) => {
+                self.set_shared_subscription_strategy(
+                    &client_pool,
+                    params.clone(),
+                    request.clone(),
+                )
+                .await;
+            }
         }
     }
     async fn publish(&self, params: MqttCliCommandParam, args: PublishArgsRequest) {
@@ -469,6 +581,35 @@ async fn set_cluster_config(
         }
     }
 
+    async fn set_log_level(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: SetLogLevelRequest,
+    ) {
+        match mqtt_broker_set_log_level(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(data) => {
+                println!("Set log level successfully!");
+                log_level_info(data.filter, data.revert_at);
+            }
+            Err(e) => {
+                println!("MQTT broker set log level exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn get_log_level(&self, client_pool: &ClientPool, params: MqttCliCommandParam) {
+        let request = GetLogLevelRequest {};
+        match mqtt_broker_get_log_level(client_pool, &grpc_addr(params.server), request).await {
+            Ok(data) => log_level_info(data.filter, data.revert_at),
+            Err(e) => {
+                println!("MQTT broker get log level exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
     async fn get_cluster_config(&self, client_pool: &ClientPool, params: MqttCliCommandParam) {
         let request = GetClusterConfigRequest {};
         match mqtt_broker_get_cluster_config(
@@ -663,9 +804,13 @@ async fn list_user(&self, client_pool: &ClientPool, params: MqttCliCommandParam)
             Ok(data) => {
                 // format table
                 let mut table = Table::new();
-                table.set_titles(row!["username", "is_superuser"]);
+                table.set_titles(row!["username", "is_superuser", "tenant"]);
                 for user in data.users {
-                    table.add_row(row![user.username.as_str(), user.is_superuser]);
+                    table.add_row(row![
+                        user.username.as_str(),
+                        user.is_superuser,
+                        user.tenant.as_str()
+                    ]);
                 }
                 // output cmd
                 table.printstd()
@@ -1049,6 +1194,239 @@ async fn list_system_alarm(
         }
     }
 
+    async fn list_alarm(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ListAlarmRequest,
+    ) {
+        match mqtt_broker_list_alarm(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(data) => {
+                println!("alarm list result:");
+                let mut table = Table::new();
+                table.set_titles(row![
+                    "broker_id",
+                    "name",
+                    "severity",
+                    "message",
+                    "value",
+                    "activate_at",
+                    "deactivate_at",
+                    "activated"
+                ]);
+                for alarm in data.alarms {
+                    table.add_row(row![
+                        alarm.broker_id,
+                        alarm.name,
+                        alarm.severity,
+                        alarm.message,
+                        alarm.value,
+                        alarm.activate_at,
+                        alarm.deactivate_at,
+                        alarm.activated
+                    ]);
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker list alarm exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn clear_alarm(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ClearAlarmRequest,
+    ) {
+        match mqtt_broker_clear_alarm(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => println!("Cleared alarm successfully!"),
+            Err(e) => {
+                println!("MQTT broker clear alarm exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    // ------------------ live trace ----------------
+    async fn start_trace(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: StartTraceRequest,
+    ) {
+        match mqtt_broker_start_trace(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => println!("Started trace successfully!"),
+            Err(e) => {
+                println!("MQTT broker start trace exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn stop_trace(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: StopTraceRequest,
+    ) {
+        match mqtt_broker_stop_trace(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => println!("Stopped trace successfully!"),
+            Err(e) => {
+                println!("MQTT broker stop trace exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn list_trace(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ListTraceRequest,
+    ) {
+        match mqtt_broker_list_trace(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(data) => {
+                println!("trace list result:");
+                let mut table = Table::new();
+                table.set_titles(row![
+                    "name",
+                    "target_type",
+                    "target",
+                    "start_at",
+                    "end_at",
+                    "running",
+                    "record_num"
+                ]);
+                for trace in data.traces {
+                    table.add_row(row![
+                        trace.name,
+                        trace.target_type,
+                        trace.target,
+                        trace.start_at,
+                        trace.end_at,
+                        trace.running,
+                        trace.record_num
+                    ]);
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker list trace exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn list_trace_record(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ListTraceRecordRequest,
+    ) {
+        match mqtt_broker_list_trace_record(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(data) => {
+                println!("trace record list result:");
+                let mut table = Table::new();
+                table.set_titles(row![
+                    "time",
+                    "direction",
+                    "client_id",
+                    "username",
+                    "source_ip",
+                    "packet_type",
+                    "topic",
+                    "detail",
+                    "payload"
+                ]);
+                for record in data.records {
+                    table.add_row(row![
+                        record.time,
+                        record.direction,
+                        record.client_id,
+                        record.username,
+                        record.source_ip,
+                        record.packet_type,
+                        record.topic,
+                        record.detail,
+                        record.payload
+                    ]);
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker list trace record exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    // ------------------ topic metrics ----------------
+    async fn list_topic_metrics(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ListTopicMetricsRequest,
+    ) {
+        match mqtt_broker_list_topic_metrics(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(data) => {
+                if !data.enable {
+                    println!("Topic metrics are not enabled on this broker, set topic_metrics.enable in its configuration.");
+                    return;
+                }
+                println!("topic metrics result:");
+                let mut table = Table::new();
+                table.set_titles(row![
+                    "topic",
+                    "messages_in",
+                    "messages_out",
+                    "bytes_in",
+                    "bytes_out",
+                    "messages_dropped",
+                    "qos0_in",
+                    "qos1_in",
+                    "qos2_in",
+                    "update_time"
+                ]);
+                for topic in data.topics {
+                    table.add_row(row![
+                        topic.topic,
+                        topic.messages_in,
+                        topic.messages_out,
+                        topic.bytes_in,
+                        topic.bytes_out,
+                        topic.messages_dropped,
+                        topic.qos0_in,
+                        topic.qos1_in,
+                        topic.qos2_in,
+                        topic.update_time
+                    ]);
+                }
+                // output cmd
+                table.printstd();
+                if data.overflow_num > 0 {
+                    println!(
+                        "{} topic counters were evicted because the topic limit was reached.",
+                        data.overflow_num
+                    );
+                }
+            }
+            Err(e) => {
+                println!("MQTT broker list topic metrics exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
     // ------------------ connectors ----------------
     async fn list_connectors(
         &self,
@@ -1453,6 +1831,188 @@ async fn list_auto_subscribe_rule(
             }
         }
     }
+
+    // ------------------ tenant ----------------
+    async fn list_tenant(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ListTenantRequest,
+    ) {
+        match mqtt_broker_list_tenant(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(data) => {
+                // format table
+                let mut table = Table::new();
+                table.set_titles(row![
+                    "tenant_name",
+                    "desc",
+                    "connections",
+                    "subscriptions",
+                    "max_messages_per_second",
+                    "max_storage_bytes_per_second",
+                    "create_time",
+                ]);
+                for tenant in data.tenants {
+                    table.add_row(row![
+                        tenant.tenant_name,
+                        tenant.desc,
+                        format!("{}/{}", tenant.connection_num, tenant.max_connections),
+                        format!("{}/{}", tenant.subscribe_num, tenant.max_subscriptions),
+                        tenant.max_messages_per_second,
+                        tenant.max_storage_bytes_per_second,
+                        tenant.create_time
+                    ]);
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker list tenant exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn set_tenant(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: SetTenantRequest,
+    ) {
+        match mqtt_broker_set_tenant(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!("Set successfully!")
+            }
+            Err(e) => {
+                println!("MQTT broker set tenant exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn delete_tenant(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: DeleteTenantRequest,
+    ) {
+        match mqtt_broker_delete_tenant(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!("Deleted successfully!");
+            }
+            Err(e) => {
+                println!("MQTT broker delete tenant exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    // ------------------ rule engine ----------------
+    async fn list_rule(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ListRuleRequest,
+    ) {
+        match mqtt_broker_list_rule(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(data) => {
+                // format table
+                let mut table = Table::new();
+                table.set_titles(row![
+                    "rule_name",
+                    "sql",
+                    "actions",
+                    "enable",
+                    "matched",
+                    "passed",
+                    "filtered",
+                    "failed",
+                    "action_success",
+                    "action_failed",
+                    "desc",
+                ]);
+                for rule in data.rules {
+                    table.add_row(row![
+                        rule.rule_name,
+                        rule.sql,
+                        rule.actions,
+                        rule.enable,
+                        rule.matched,
+                        rule.passed,
+                        rule.filtered,
+                        rule.failed,
+                        rule.action_success,
+                        rule.action_failed,
+                        rule.desc
+                    ]);
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker list rule exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn set_rule(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: SetRuleRequest,
+    ) {
+        match mqtt_broker_set_rule(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!("Set successfully!")
+            }
+            Err(e) => {
+                println!("MQTT broker set rule exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn delete_rule(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: DeleteRuleRequest,
+    ) {
+        match mqtt_broker_delete_rule(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(_) => {
+                println!("Deleted successfully!");
+            }
+            Err(e) => {
+                println!("MQTT broker delete rule exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    // ------------------ shared subscription ----------------
+    async fn set_shared_subscription_strategy(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: SetSharedSubscriptionStrategyRequest,
+    ) {
+        match mqtt_broker_set_shared_subscription_strategy(
+            client_pool,
+            &grpc_addr(params.server),
+            cli_request,
+        )
+        .await
+        {
+            Ok(_) => {
+                println!("Set successfully!")
+            }
+            Err(e) => {
+                println!("MQTT broker set shared subscription strategy exception");
+                error_info(e.to_string());
+            }
+        }
+    }
 }
 
 #[cfg(test)]
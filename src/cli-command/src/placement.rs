@@ -12,19 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fs;
 use std::sync::Arc;
 
-use grpc_clients::placement::inner::call::cluster_status;
+use grpc_clients::placement::inner::call::{
+    backup_metadata, cluster_status, get_log_level, restore_metadata, set_log_level,
+};
 use grpc_clients::placement::openraft::call::{
     placement_openraft_add_learner, placement_openraft_change_membership,
 };
 use grpc_clients::pool::ClientPool;
-use protocol::placement_center::placement_center_inner::ClusterStatusRequest;
+use protocol::placement_center::placement_center_inner::{
+    BackupMetadataRequest, ClusterStatusRequest, GetLogLevelRequest, RestoreMetadataRequest,
+    SetLogLevelRequest,
+};
 use protocol::placement_center::placement_center_openraft::{
     AddLearnerRequest, ChangeMembershipRequest,
 };
 
-use crate::{error_info, grpc_addr};
+use crate::{error_info, grpc_addr, log_level_info};
 
 #[derive(Clone)]
 pub struct PlacementCliCommandParam {
@@ -37,6 +43,22 @@ pub enum PlacementActionType {
     Status,
     AddLearner(AddLearnerRequest),
     ChangeMembership(ChangeMembershipRequest),
+    Backup(BackupParams),
+    Restore(RestoreParams),
+    SetLogLevel(SetLogLevelRequest),
+    GetLogLevel,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct BackupParams {
+    pub output: String,
+    pub resources: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RestoreParams {
+    pub input: String,
+    pub force: bool,
 }
 
 pub struct PlacementCenterCommand {}
@@ -66,6 +88,21 @@ pub async fn start(&self, params: PlacementCliCommandParam) {
                 self.change_membership(&client_pool, params.clone(), request.clone())
                     .await;
             }
+            PlacementActionType::Backup(ref request) => {
+                self.backup(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            PlacementActionType::Restore(ref request) => {
+                self.restore(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            PlacementActionType::SetLogLevel(ref request) => {
+                self.set_log_level(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            PlacementActionType::GetLogLevel => {
+                self.get_log_level(&client_pool, params).await;
+            }
         }
     }
 
@@ -82,6 +119,35 @@ async fn status(&self, client_pool: &ClientPool, params: PlacementCliCommandPara
         }
     }
 
+    async fn set_log_level(
+        &self,
+        client_pool: &ClientPool,
+        params: PlacementCliCommandParam,
+        cli_request: SetLogLevelRequest,
+    ) {
+        match set_log_level(client_pool, &grpc_addr(params.server), cli_request).await {
+            Ok(reply) => {
+                println!("Set log level successfully!");
+                log_level_info(reply.filter, reply.revert_at);
+            }
+            Err(e) => {
+                println!("Placement center set log level exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn get_log_level(&self, client_pool: &ClientPool, params: PlacementCliCommandParam) {
+        let request = GetLogLevelRequest {};
+        match get_log_level(client_pool, &grpc_addr(params.server), request).await {
+            Ok(reply) => log_level_info(reply.filter, reply.revert_at),
+            Err(e) => {
+                println!("Placement center get log level exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
     async fn add_learner(
         &self,
         client_pool: &ClientPool,
@@ -123,4 +189,67 @@ async fn change_membership(
             }
         }
     }
+
+    async fn backup(
+        &self,
+        client_pool: &ClientPool,
+        params: PlacementCliCommandParam,
+        backup_params: BackupParams,
+    ) {
+        let request = BackupMetadataRequest {
+            resources: backup_params.resources,
+        };
+        let reply = match backup_metadata(client_pool, &grpc_addr(params.server), request).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                println!("Placement center metadata backup exception");
+                error_info(e.to_string());
+                return;
+            }
+        };
+
+        if let Err(e) = fs::write(&backup_params.output, &reply.data) {
+            println!("Placement center metadata backup exception");
+            error_info(e.to_string());
+            return;
+        }
+        println!(
+            "Placement center metadata backup successfully, {} records written to {}",
+            reply.records, backup_params.output
+        );
+    }
+
+    async fn restore(
+        &self,
+        client_pool: &ClientPool,
+        params: PlacementCliCommandParam,
+        restore_params: RestoreParams,
+    ) {
+        let data = match fs::read(&restore_params.input) {
+            Ok(data) => data,
+            Err(e) => {
+                println!("Placement center metadata restore exception");
+                error_info(format!("{}: {}", restore_params.input, e));
+                return;
+            }
+        };
+
+        let request = RestoreMetadataRequest {
+            data,
+            force: restore_params.force,
+        };
+        match restore_metadata(client_pool, &grpc_addr(params.server), request).await {
+            Ok(reply) => {
+                println!(
+                    "Placement center metadata restore successfully, {} records restored from backup created at {}",
+                    reply.records,
+                    reply.create_time
+                );
+            }
+            Err(e) => {
+                println!("Placement center metadata restore exception");
+                error_info(e.to_string());
+            }
+        }
+    }
 }
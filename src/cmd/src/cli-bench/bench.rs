@@ -13,7 +13,10 @@
 // limitations under the License.
 
 use clap::Parser;
-use cli_bench::{kv::handle_kv_bench, BenchMarkError, RobustMQBench, RobustMQBenchCommand};
+use cli_bench::{
+    kv::handle_kv_bench, mqtt::handle_mqtt_bench, BenchMarkError, RobustMQBench,
+    RobustMQBenchCommand,
+};
 
 fn main() -> Result<(), BenchMarkError> {
     let args = RobustMQBench::parse();
@@ -21,8 +24,8 @@ fn main() -> Result<(), BenchMarkError> {
         RobustMQBenchCommand::Kafka(_) => {
             unimplemented!();
         }
-        RobustMQBenchCommand::Mqtt(_) => {
-            unimplemented!();
+        RobustMQBenchCommand::Mqtt(mqtt_args) => {
+            handle_mqtt_bench(mqtt_args)?;
         }
         RobustMQBenchCommand::Kv(kv_args) => {
             handle_kv_bench(kv_args)?;
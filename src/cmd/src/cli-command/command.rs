@@ -15,32 +15,38 @@
 pub(crate) mod mqtt;
 
 use clap::{arg, Parser, Subcommand};
+use cli_command::journal::{JournalActionType, JournalCliCommandParam, JournalServerCommand};
 use cli_command::mqtt::{MqttActionType, MqttBrokerCommand, MqttCliCommandParam};
 use cli_command::placement::{
-    PlacementActionType, PlacementCenterCommand, PlacementCliCommandParam,
+    BackupParams, PlacementActionType, PlacementCenterCommand, PlacementCliCommandParam,
+    RestoreParams,
 };
 use mqtt::admin::{
-    process_auto_subscribe_args, process_config_args, process_connection_args,
-    process_session_args, AutoSubscribeRuleCommand, BindSchemaArgs, ClusterConfigArgs,
-    ConnectionArgs, CreateSchemaArgs, DeleteSchemaArgs, ListBindSchemaArgs, ListSchemaArgs,
-    SessionArgs, UnbindSchemaArgs, UpdateSchemaArgs,
+    process_auto_subscribe_args, process_config_args, process_connection_args, process_rule_args,
+    process_session_args, process_shared_subscription_args, process_tenant_args,
+    AutoSubscribeRuleCommand, BindSchemaArgs, ClusterConfigArgs, ConnectionArgs, CreateSchemaArgs,
+    DeleteSchemaArgs, ListBindSchemaArgs, ListSchemaArgs, RuleArgs, SessionArgs,
+    SharedSubscriptionArgs, TenantArgs, UnbindSchemaArgs, UpdateSchemaArgs,
 };
 use mqtt::publish::process_subscribe_args;
 use protocol::broker_mqtt::broker_mqtt_admin::{
     EnableFlappingDetectRequest, MqttBindSchemaRequest, MqttCreateSchemaRequest,
     MqttDeleteSchemaRequest, MqttListBindSchemaRequest, MqttListSchemaRequest,
-    MqttUnbindSchemaRequest, MqttUpdateSchemaRequest,
+    MqttUnbindSchemaRequest, MqttUpdateSchemaRequest, SetLogLevelRequest,
 };
 
+use protocol::journal_server::journal_admin::SetLogLevelRequest as JournalSetLogLevelRequest;
+use protocol::placement_center::placement_center_inner::SetLogLevelRequest as PlacementSetLogLevelRequest;
 use protocol::placement_center::placement_center_openraft::{
     AddLearnerRequest, ChangeMembershipRequest, Node,
 };
 
 use crate::mqtt::admin::{
     process_acl_args, process_blacklist_args, process_connector_args, process_slow_sub_args,
-    process_system_alarm_args, process_topic_rewrite_args, process_user_args, AclArgs,
-    BlacklistArgs, ConnectorArgs, FlappingDetectArgs, SlowSubArgs, SystemAlarmArgs,
-    TopicRewriteArgs, UserArgs,
+    process_system_alarm_args, process_topic_metrics_args, process_topic_rewrite_args,
+    process_trace_args, process_user_args, AclArgs, BlacklistArgs, ConnectorArgs,
+    FlappingDetectArgs, SlowSubArgs, SystemAlarmArgs, TopicMetricsArgs, TopicRewriteArgs,
+    TraceArgs, UserArgs,
 };
 use crate::mqtt::publish::{process_publish_args, PubSubArgs};
 
@@ -105,6 +111,12 @@ enum MQTTAction {
     SlowSub(SlowSubArgs),
     // ---- system alarm ----
     SystemAlarm(SystemAlarmArgs),
+    // ---- live trace ----
+    Trace(TraceArgs),
+    // ---- topic metrics ----
+    TopicMetrics(TopicMetricsArgs),
+    // ---- log level ----
+    LogLevel(LogLevelArgs),
     // list topic
     ListTopic,
     // topic rewrite rule
@@ -124,6 +136,15 @@ enum MQTTAction {
     //auto subscribe
     AutoSubscribeRule(AutoSubscribeRuleCommand),
 
+    // tenant
+    Tenant(TenantArgs),
+
+    // rule engine
+    Rule(RuleArgs),
+
+    // shared subscription
+    SharedSubscription(SharedSubscriptionArgs),
+
     Publish(PubSubArgs),
     Subscribe(PubSubArgs),
 }
@@ -144,6 +165,9 @@ enum PlacementAction {
     Status,
     AddLearner(AddLearnerArgs),
     ChangeMembership(ChangeMembershipArgs),
+    Backup(BackupArgs),
+    Restore(RestoreArgs),
+    LogLevel(LogLevelArgs),
 }
 
 #[derive(clap::Args, Debug)]
@@ -171,15 +195,74 @@ struct ChangeMembershipArgs {
     retain: bool,
 }
 
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ",  about="action: backup placement center metadata", long_about = None)]
+#[command(next_line_help = true)]
+struct BackupArgs {
+    #[arg(short, long, required = true)]
+    output: String,
+
+    // comma separated: cluster,mqtt,journal,offset,idempotent. All resources when not set.
+    #[arg(short, long, value_delimiter = ',')]
+    resources: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ",  about="action: restore placement center metadata", long_about = None)]
+#[command(next_line_help = true)]
+struct RestoreArgs {
+    #[arg(short, long, required = true)]
+    input: String,
+
+    #[arg(short, long, default_value_t = false)]
+    force: bool,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ",  about="related operations of the log level of a node", long_about = None)]
+#[command(next_line_help = true)]
+struct LogLevelArgs {
+    #[command(subcommand)]
+    action: LogLevelAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum LogLevelAction {
+    #[command(author = "RobustMQ", about = "action: show the log filter set at runtime", long_about = None)]
+    Get,
+    #[command(author = "RobustMQ", about = "action: change the log level or filter at runtime", long_about = None)]
+    Set(SetLogLevelArgs),
+    #[command(author = "RobustMQ", about = "action: restore the levels of the log configuration", long_about = None)]
+    Reset,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ",  about="action: change the log level or filter at runtime", long_about = None)]
+#[command(next_line_help = true)]
+struct SetLogLevelArgs {
+    // a level or EnvFilter directives, e.g. debug or info,mqtt_broker::handler=debug
+    #[arg(short, long, required = true)]
+    filter: String,
+
+    // restore the configured levels after this many seconds, never when 0
+    #[arg(short, long, default_value_t = 0)]
+    revert_after_secs: u64,
+}
+
 #[derive(clap::Args, Debug)]
 #[command(author="RobustMQ", about="Command line tool for journal engine", long_about = None)]
 #[command(next_line_help = true)]
 struct JournalArgs {
-    #[arg(short, long,default_value_t =String::from("127.0.0.1:1228"))]
+    #[arg(short, long,default_value_t =String::from("127.0.0.1:2228"))]
     server: String,
 
-    #[arg(short, long,default_value_t =String::from("status"))]
-    action: String,
+    #[clap(subcommand)]
+    action: JournalAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum JournalAction {
+    LogLevel(LogLevelArgs),
 }
 
 #[tokio::main]
@@ -190,7 +273,9 @@ async fn main() {
         RobustMQCliCommand::Place(args) => {
             handle_placement(args, PlacementCenterCommand::new()).await
         }
-        RobustMQCliCommand::Journal(args) => handle_journal(args).await,
+        RobustMQCliCommand::Journal(args) => {
+            handle_journal(args, JournalServerCommand::new()).await
+        }
     }
 }
 
@@ -221,6 +306,19 @@ async fn handle_mqtt(args: MqttArgs, cmd: MqttBrokerCommand) {
             }
             // system alarm
             MQTTAction::SystemAlarm(args) => process_system_alarm_args(args),
+            // live trace
+            MQTTAction::Trace(args) => process_trace_args(args),
+            // topic metrics
+            MQTTAction::TopicMetrics(args) => process_topic_metrics_args(args),
+            // log level
+            MQTTAction::LogLevel(args) => match args.action {
+                LogLevelAction::Get => MqttActionType::GetLogLevel,
+                LogLevelAction::Set(arg) => MqttActionType::SetLogLevel(SetLogLevelRequest {
+                    filter: arg.filter,
+                    revert_after_secs: arg.revert_after_secs,
+                }),
+                LogLevelAction::Reset => MqttActionType::SetLogLevel(SetLogLevelRequest::default()),
+            },
             // Connections
             MQTTAction::Connection(args) => process_connection_args(args),
             // connector
@@ -274,6 +372,9 @@ async fn handle_mqtt(args: MqttArgs, cmd: MqttBrokerCommand) {
                 })
             }
             MQTTAction::AutoSubscribeRule(args) => process_auto_subscribe_args(args),
+            MQTTAction::Tenant(args) => process_tenant_args(args),
+            MQTTAction::Rule(args) => process_rule_args(args),
+            MQTTAction::SharedSubscription(args) => process_shared_subscription_args(args),
         },
     };
     cmd.start(params).await;
@@ -300,12 +401,48 @@ async fn handle_placement(args: PlacementArgs, cmd: PlacementCenterCommand) {
                     retain: arg.retain,
                 })
             }
+            PlacementAction::Backup(arg) => PlacementActionType::Backup(BackupParams {
+                output: arg.output,
+                resources: arg.resources,
+            }),
+            PlacementAction::Restore(arg) => PlacementActionType::Restore(RestoreParams {
+                input: arg.input,
+                force: arg.force,
+            }),
+            PlacementAction::LogLevel(args) => match args.action {
+                LogLevelAction::Get => PlacementActionType::GetLogLevel,
+                LogLevelAction::Set(arg) => {
+                    PlacementActionType::SetLogLevel(PlacementSetLogLevelRequest {
+                        filter: arg.filter,
+                        revert_after_secs: arg.revert_after_secs,
+                    })
+                }
+                LogLevelAction::Reset => {
+                    PlacementActionType::SetLogLevel(PlacementSetLogLevelRequest::default())
+                }
+            },
         },
     };
     cmd.start(params).await;
 }
 
-// TODO: implement journal engine
-async fn handle_journal(args: JournalArgs) {
-    println!("{:?}", args);
+async fn handle_journal(args: JournalArgs, cmd: JournalServerCommand) {
+    let params = JournalCliCommandParam {
+        server: args.server,
+        action: match args.action {
+            JournalAction::LogLevel(args) => match args.action {
+                LogLevelAction::Get => JournalActionType::GetLogLevel,
+                LogLevelAction::Set(arg) => {
+                    JournalActionType::SetLogLevel(JournalSetLogLevelRequest {
+                        filter: arg.filter,
+                        revert_after_secs: arg.revert_after_secs,
+                    })
+                }
+                LogLevelAction::Reset => {
+                    JournalActionType::SetLogLevel(JournalSetLogLevelRequest::default())
+                }
+            },
+        },
+    };
+    cmd.start(params).await;
 }
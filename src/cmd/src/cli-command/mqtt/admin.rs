@@ -20,6 +20,12 @@
 use common_base::enum_type::feature_type::FeatureType;
 use common_base::enum_type::sort_type::SortType;
 use core::option::Option::Some;
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    ClearAlarmRequest, DeleteRuleRequest, DeleteTenantRequest, ListAlarmRequest, ListRuleRequest,
+    ListSlowSubscribeRequest, ListTenantRequest, ListTopicMetricsRequest, ListTraceRecordRequest,
+    ListTraceRequest, SetRuleRequest, SetSharedSubscriptionStrategyRequest,
+    SetSystemAlarmConfigRequest, SetTenantRequest, StartTraceRequest, StopTraceRequest,
+};
 use protocol::broker_mqtt::broker_mqtt_admin::{
     CreateAclRequest, CreateBlacklistRequest, CreateTopicRewriteRuleRequest, CreateUserRequest,
     DeleteAclRequest, DeleteAutoSubscribeRuleRequest, DeleteBlacklistRequest,
@@ -28,9 +34,6 @@
     MqttListConnectorRequest, MqttUpdateConnectorRequest, SetAutoSubscribeRuleRequest,
     SetClusterConfigRequest,
 };
-use protocol::broker_mqtt::broker_mqtt_admin::{
-    ListSlowSubscribeRequest, SetSystemAlarmConfigRequest,
-};
 
 // session
 #[derive(clap::Args, Debug)]
@@ -106,6 +109,8 @@ pub(crate) struct CreateUserArgs {
     pub(crate) password: String,
     #[arg(short, long, default_value_t = false)]
     pub(crate) is_superuser: bool,
+    #[arg(short, long, default_value_t = String::new())]
+    pub(crate) tenant: String,
 }
 
 #[derive(clap::Args, Debug)]
@@ -338,6 +343,108 @@ pub enum SystemAlarmActionType {
     Set(SetSystemAlarmArgs),
     #[command(author = "RobustMQ", about = "action: list system alarm", long_about = None)]
     List,
+    #[command(author = "RobustMQ", about = "action: list active alarms", long_about = None)]
+    Active,
+    #[command(author = "RobustMQ", about = "action: list alarm history", long_about = None)]
+    History(AlarmHistoryArgs),
+    #[command(author = "RobustMQ", about = "action: clear an active alarm", long_about = None)]
+    Clear(ClearAlarmArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: list alarm history", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct AlarmHistoryArgs {
+    #[arg(short, long, default_value_t = 20)]
+    pub(crate) limit: u32,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: clear an active alarm", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct ClearAlarmArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) name: String,
+}
+
+// ---- live trace ----
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "related operations of live traces of the packets of a client, user, ip or topic", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct TraceArgs {
+    #[command(subcommand)]
+    pub action: TraceActionType,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum TraceActionType {
+    #[command(author = "RobustMQ", about = "action: start a trace", long_about = None)]
+    Start(StartTraceArgs),
+    #[command(author = "RobustMQ", about = "action: stop a running trace", long_about = None)]
+    Stop(StopTraceArgs),
+    #[command(author = "RobustMQ", about = "action: list traces", long_about = None)]
+    List,
+    #[command(author = "RobustMQ", about = "action: list the records of a trace", long_about = None)]
+    Records(ListTraceRecordArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: start a trace", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct StartTraceArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) name: String,
+    #[arg(long, required = true, value_parser = ["client_id", "username", "ip", "topic"])]
+    pub(crate) target_type: String,
+    #[arg(short, long, required = true)]
+    pub(crate) target: String,
+    #[arg(short, long, default_value_t = 300)]
+    pub(crate) duration_secs: u64,
+    #[arg(long, default_value_t = 256)]
+    pub(crate) payload_max_len: u32,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: stop a running trace", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct StopTraceArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) name: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: list the records of a trace", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct ListTraceRecordArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) name: String,
+    #[arg(short, long, default_value_t = 100)]
+    pub(crate) limit: u32,
+}
+
+// ---- topic metrics ----
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "related operations of the message counters of topics", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct TopicMetricsArgs {
+    #[command(subcommand)]
+    pub action: TopicMetricsActionType,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum TopicMetricsActionType {
+    #[command(author = "RobustMQ", about = "action: list the hottest topics", long_about = None)]
+    Top(TopTopicMetricsArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: list the hottest topics", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct TopTopicMetricsArgs {
+    #[arg(short, long, default_value = "messages_in", value_parser = ["messages_in", "messages_out", "bytes_in", "bytes_out", "messages_dropped"])]
+    pub(crate) sort_by: String,
+    #[arg(short, long, default_value_t = 10)]
+    pub(crate) limit: u32,
 }
 
 #[derive(clap::Args, Debug)]
@@ -547,6 +654,48 @@ pub fn process_system_alarm_args(args: SystemAlarmArgs) -> MqttActionType {
             })
         }
         SystemAlarmActionType::List => MqttActionType::ListSystemAlarm(ListSystemAlarmRequest {}),
+        SystemAlarmActionType::Active => MqttActionType::ListAlarm(ListAlarmRequest {
+            history: false,
+            limit: 0,
+        }),
+        SystemAlarmActionType::History(arg) => MqttActionType::ListAlarm(ListAlarmRequest {
+            history: true,
+            limit: arg.limit,
+        }),
+        SystemAlarmActionType::Clear(arg) => {
+            MqttActionType::ClearAlarm(ClearAlarmRequest { name: arg.name })
+        }
+    }
+}
+
+pub fn process_trace_args(args: TraceArgs) -> MqttActionType {
+    match args.action {
+        TraceActionType::Start(arg) => MqttActionType::StartTrace(StartTraceRequest {
+            name: arg.name,
+            target_type: arg.target_type,
+            target: arg.target,
+            duration_secs: arg.duration_secs,
+            payload_max_len: arg.payload_max_len,
+        }),
+        TraceActionType::Stop(arg) => {
+            MqttActionType::StopTrace(StopTraceRequest { name: arg.name })
+        }
+        TraceActionType::List => MqttActionType::ListTrace(ListTraceRequest {}),
+        TraceActionType::Records(arg) => MqttActionType::ListTraceRecord(ListTraceRecordRequest {
+            name: arg.name,
+            limit: arg.limit,
+        }),
+    }
+}
+
+pub fn process_topic_metrics_args(args: TopicMetricsArgs) -> MqttActionType {
+    match args.action {
+        TopicMetricsActionType::Top(arg) => {
+            MqttActionType::ListTopicMetrics(ListTopicMetricsRequest {
+                sort_by: arg.sort_by,
+                limit: arg.limit,
+            })
+        }
     }
 }
 
@@ -569,6 +718,7 @@ pub fn process_user_args(args: UserArgs) -> MqttActionType {
             username: arg.username,
             password: arg.password,
             is_superuser: arg.is_superuser,
+            tenant: arg.tenant,
         }),
         UserActionType::Delete(arg) => MqttActionType::DeleteUser(DeleteUserRequest {
             username: arg.username,
@@ -807,3 +957,207 @@ async fn test_process_slow_sub_args_function_filed_is_enable_none_2() {
         )
     }
 }
+
+// tenant
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "related operations of mqtt tenant, such as listing, setting, and deleting", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct TenantArgs {
+    #[command(subcommand)]
+    pub action: TenantActionType,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum TenantActionType {
+    #[command(author = "RobustMQ", about = "action: tenant list", long_about = None)]
+    List(ListTenantArgs),
+    #[command(author = "RobustMQ", about = "action: create or update tenant", long_about = None)]
+    Set(SetTenantArgs),
+    #[command(author = "RobustMQ", about = "action: delete tenant", long_about = None)]
+    Delete(DeleteTenantArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: tenant list", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct ListTenantArgs {
+    #[arg(short, long, default_value_t = String::new())]
+    pub(crate) tenant_name: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: create or update tenant, a quota of 0 means unlimited", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct SetTenantArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) tenant_name: String,
+    #[arg(short, long, default_value_t = String::new())]
+    pub(crate) desc: String,
+    #[arg(short = 'c', long, default_value_t = 0)]
+    pub(crate) max_connections: u64,
+    #[arg(short = 's', long, default_value_t = 0)]
+    pub(crate) max_subscriptions: u64,
+    #[arg(short = 'm', long, default_value_t = 0)]
+    pub(crate) max_messages_per_second: u64,
+    #[arg(short = 'b', long, default_value_t = 0)]
+    pub(crate) max_storage_bytes_per_second: u64,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: delete tenant", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct DeleteTenantArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) tenant_name: String,
+}
+
+pub fn process_tenant_args(args: TenantArgs) -> MqttActionType {
+    match args.action {
+        TenantActionType::List(arg) => MqttActionType::ListTenant(ListTenantRequest {
+            tenant_name: arg.tenant_name,
+        }),
+        TenantActionType::Set(arg) => MqttActionType::SetTenant(SetTenantRequest {
+            tenant_name: arg.tenant_name,
+            desc: arg.desc,
+            max_connections: arg.max_connections,
+            max_subscriptions: arg.max_subscriptions,
+            max_messages_per_second: arg.max_messages_per_second,
+            max_storage_bytes_per_second: arg.max_storage_bytes_per_second,
+        }),
+        TenantActionType::Delete(arg) => MqttActionType::DeleteTenant(DeleteTenantRequest {
+            tenant_name: arg.tenant_name,
+        }),
+    }
+}
+
+// rule engine
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "related operations of mqtt rule engine, such as listing, setting, and deleting", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct RuleArgs {
+    #[command(subcommand)]
+    pub action: RuleActionType,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum RuleActionType {
+    #[command(author = "RobustMQ", about = "action: rule list", long_about = None)]
+    List(ListRuleArgs),
+    #[command(author = "RobustMQ", about = "action: create or update rule", long_about = None)]
+    Set(SetRuleArgs),
+    #[command(author = "RobustMQ", about = "action: delete rule", long_about = None)]
+    Delete(DeleteRuleArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: rule list", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct ListRuleArgs {
+    #[arg(short, long, default_value_t = String::new())]
+    pub(crate) rule_name: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: create or update rule", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct SetRuleArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) rule_name: String,
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = r#"e.g. SELECT payload.temp AS t FROM "sensors/+/data" WHERE payload.temp > 80"#
+    )]
+    pub(crate) sql: String,
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = r#"JSON array, e.g. [{"type":"republish","topic":"alerts/${clientid}","qos":1}]"#
+    )]
+    pub(crate) actions: String,
+    #[arg(
+        short,
+        long,
+        value_parser = BoolishValueParser::new(),
+        default_value_t = true,
+        action = ArgAction::Set,
+        help = "Enable or disable the rule"
+    )]
+    pub(crate) enable: bool,
+    #[arg(short, long, default_value_t = String::new())]
+    pub(crate) desc: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: delete rule", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct DeleteRuleArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) rule_name: String,
+}
+
+pub fn process_rule_args(args: RuleArgs) -> MqttActionType {
+    match args.action {
+        RuleActionType::List(arg) => MqttActionType::ListRule(ListRuleRequest {
+            rule_name: arg.rule_name,
+        }),
+        RuleActionType::Set(arg) => MqttActionType::SetRule(SetRuleRequest {
+            rule_name: arg.rule_name,
+            sql: arg.sql,
+            actions: arg.actions,
+            enable: arg.enable,
+            desc: arg.desc,
+        }),
+        RuleActionType::Delete(arg) => MqttActionType::DeleteRule(DeleteRuleRequest {
+            rule_name: arg.rule_name,
+        }),
+    }
+}
+
+// shared subscription
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "related operations of mqtt shared subscriptions, such as setting the dispatch strategy", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct SharedSubscriptionArgs {
+    #[command(subcommand)]
+    pub action: SharedSubscriptionActionType,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SharedSubscriptionActionType {
+    #[command(author = "RobustMQ", about = "action: set the dispatch strategy of a share group", long_about = None)]
+    SetStrategy(SetSharedSubscriptionStrategyArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author="RobustMQ", about="action: set the dispatch strategy of a share group", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct SetSharedSubscriptionStrategyArgs {
+    #[arg(
+        short,
+        long,
+        default_value_t = String::new(),
+        help = "Share group name, leave empty to set the default strategy"
+    )]
+    pub(crate) group_name: String,
+    #[arg(
+        short,
+        long,
+        default_value_t = String::new(),
+        help = "round_robin, random, sticky, hash_client_id, hash_topic or least_inflight, leave empty to remove the group strategy"
+    )]
+    pub(crate) strategy: String,
+}
+
+pub fn process_shared_subscription_args(args: SharedSubscriptionArgs) -> MqttActionType {
+    match args.action {
+        SharedSubscriptionActionType::SetStrategy(arg) => {
+            MqttActionType::SetSharedSubscriptionStrategy(SetSharedSubscriptionStrategyRequest {
+                group_name: arg.group_name,
+                strategy: arg.strategy,
+            })
+        }
+    }
+}
@@ -26,6 +26,9 @@ pub enum LogConfigError {
     #[error(transparent)]
     RollingFileAppenderInit(#[from] tracing_appender::rolling::InitError),
 
+    #[error("failed to open the log file in {0}: {1}")]
+    RollingFileOpen(String, std::io::Error),
+
     #[error(transparent)]
     Addr(#[from] std::net::AddrParseError),
 }
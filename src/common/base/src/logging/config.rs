@@ -26,8 +26,6 @@
     },
 };
 
-// TODO: implement size based rotation
-
 pub(super) trait AppenderConfig<S = Registry>
 where
     S: tracing::Subscriber,
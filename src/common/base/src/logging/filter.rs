@@ -0,0 +1,242 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::time::Duration;
+
+use tokio::runtime::Handle;
+use tokio::task::AbortHandle;
+use tokio::time::sleep;
+use tracing::{info, Subscriber};
+use tracing_subscriber::{reload, EnvFilter};
+
+use crate::error::common::CommonError;
+use crate::logging::config::Level;
+use crate::tools::now_second;
+
+type ReloadFn = Box<dyn Fn(EnvFilter) -> Result<(), reload::Error> + Send + Sync>;
+
+struct AppenderFilter {
+    level: Level,
+    reload: ReloadFn,
+}
+
+#[derive(Default)]
+struct RuntimeFilter {
+    appenders: Vec<AppenderFilter>,
+    // The directives set at runtime, None while the configured levels apply
+    directives: Option<String>,
+    revert_at: u64,
+    // The task restoring the configured levels, aborted by the next change
+    revert_task: Option<AbortHandle>,
+    // Incremented on every change, so a pending revert does not undo a newer change
+    generation: u64,
+}
+
+static RUNTIME_FILTER: LazyLock<Mutex<RuntimeFilter>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogFilterInfo {
+    // Empty while the levels of the log configuration file apply
+    pub directives: String,
+    // When the configured levels are restored, 0 means they are not restored automatically
+    pub revert_at: u64,
+}
+
+/// Creates the filter of an appender, which can be replaced at runtime by `set_log_filter`.
+pub(super) fn reloadable_filter<S>(level: Level) -> reload::Layer<EnvFilter, S>
+where
+    S: Subscriber,
+{
+    let (filter, handle) = reload::Layer::new(level_filter(level));
+    lock().appenders.push(AppenderFilter {
+        level,
+        reload: Box::new(move |filter| handle.reload(filter)),
+    });
+    filter
+}
+
+/// Replaces the level of the appenders that log at info or a more verbose level with the
+/// `EnvFilter` directives, e.g. `debug` or `info,mqtt_broker::handler=debug`. Appenders limited
+/// to warnings or errors keep their level. With a `revert_after_secs` greater than 0 the
+/// configured levels are restored after that time. Empty directives restore them at once.
+pub fn set_log_filter(
+    directives: &str,
+    revert_after_secs: u64,
+) -> Result<LogFilterInfo, CommonError> {
+    let directives = directives.trim();
+    if directives.is_empty() {
+        return reset_log_filter();
+    }
+    EnvFilter::try_new(directives).map_err(|e| {
+        CommonError::CommonError(format!("invalid log filter {}: {}", directives, e))
+    })?;
+    let runtime = if revert_after_secs > 0 {
+        Some(Handle::try_current().map_err(|e| {
+            CommonError::CommonError(format!("the log filter can not be reverted: {}", e))
+        })?)
+    } else {
+        None
+    };
+
+    let mut state = lock();
+    state.reload_appenders(|level| (level >= Level::Info).then(|| EnvFilter::new(directives)))?;
+    state.directives = Some(directives.to_owned());
+    state.next_generation();
+    state.revert_at = 0;
+    if let Some(runtime) = runtime {
+        let generation = state.generation;
+        let task = runtime.spawn(async move {
+            sleep(Duration::from_secs(revert_after_secs)).await;
+            revert(generation);
+        });
+        state.revert_task = Some(task.abort_handle());
+        state.revert_at = now_second() + revert_after_secs;
+    }
+    let info = state.info();
+    drop(state);
+
+    info!(
+        "Log filter set to {} at runtime, revert at {}",
+        info.directives, info.revert_at
+    );
+    Ok(info)
+}
+
+/// Restores the levels of the log configuration file.
+pub fn reset_log_filter() -> Result<LogFilterInfo, CommonError> {
+    lock().reset()
+}
+
+pub fn get_log_filter() -> LogFilterInfo {
+    lock().info()
+}
+
+impl RuntimeFilter {
+    // Starts a new change, the revert pending for the previous one is cancelled
+    fn next_generation(&mut self) {
+        self.generation += 1;
+        if let Some(task) = self.revert_task.take() {
+            task.abort();
+        }
+    }
+
+    fn reset(&mut self) -> Result<LogFilterInfo, CommonError> {
+        self.reload_appenders(|level| Some(level_filter(level)))?;
+        let was_set = self.directives.take().is_some();
+        self.revert_at = 0;
+        self.next_generation();
+
+        if was_set {
+            info!("Log filter restored to the configured levels");
+        }
+        Ok(self.info())
+    }
+
+    // Replaces the filter of the appenders `filter` returns one for. Appenders whose subscriber
+    // was dropped, e.g. by a test or a re-initialized logger, are removed instead of failing
+    // every later change.
+    fn reload_appenders<F>(&mut self, filter: F) -> Result<(), CommonError>
+    where
+        F: Fn(Level) -> Option<EnvFilter>,
+    {
+        let mut result = Ok(());
+        self.appenders.retain(|appender| {
+            let Some(new_filter) = filter(appender.level) else {
+                return true;
+            };
+            match (appender.reload)(new_filter) {
+                Ok(()) => true,
+                Err(e) if e.is_dropped() => false,
+                Err(e) => {
+                    result = Err(reload_error(e));
+                    true
+                }
+            }
+        });
+        result
+    }
+
+    fn info(&self) -> LogFilterInfo {
+        LogFilterInfo {
+            directives: self.directives.clone().unwrap_or_default(),
+            revert_at: self.revert_at,
+        }
+    }
+}
+
+// The generation is compared and the filter reset under the same lock, so a newer change
+// is never undone
+fn revert(generation: u64) {
+    let mut state = lock();
+    if state.generation != generation {
+        return;
+    }
+    let _ = state.reset();
+}
+
+fn level_filter(level: Level) -> EnvFilter {
+    EnvFilter::new(tracing::Level::from(level).to_string())
+}
+
+fn reload_error(e: reload::Error) -> CommonError {
+    CommonError::CommonError(format!("failed to reload the log filter: {}", e))
+}
+
+fn lock() -> MutexGuard<'static, RuntimeFilter> {
+    RUNTIME_FILTER.lock().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::Registry;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_and_reset_log_filter() {
+        let _info_filter = reloadable_filter::<Registry>(Level::Info);
+        let _warn_filter = reloadable_filter::<Registry>(Level::Warn);
+        // The subscriber of this appender is gone, it is removed by the next change
+        drop(reloadable_filter::<Registry>(Level::Debug));
+
+        assert!(set_log_filter("info,mqtt_broker=[", 0).is_err());
+        assert_eq!(get_log_filter(), LogFilterInfo::default());
+
+        let info = set_log_filter("info,mqtt_broker::handler=debug", 60).unwrap();
+        assert_eq!(info.directives, "info,mqtt_broker::handler=debug");
+        assert!(info.revert_at >= now_second() + 59);
+        assert_eq!(lock().appenders.len(), 2);
+
+        // The revert of the first change must not undo the second one
+        let generation = lock().generation;
+        set_log_filter("debug", 0).unwrap();
+        revert(generation);
+        assert_eq!(get_log_filter().directives, "debug");
+        assert_eq!(get_log_filter().revert_at, 0);
+        assert!(lock().revert_task.is_none());
+
+        let info = set_log_filter("", 0).unwrap();
+        assert_eq!(info, LogFilterInfo::default());
+
+        // A newer change cancels the pending revert
+        set_log_filter("debug", 1).unwrap();
+        set_log_filter("info", 0).unwrap();
+        assert!(lock().revert_task.is_none());
+
+        set_log_filter("debug", 1).unwrap();
+        sleep(Duration::from_millis(1500)).await;
+        assert_eq!(get_log_filter(), LogFilterInfo::default());
+    }
+}
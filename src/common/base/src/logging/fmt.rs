@@ -13,10 +13,12 @@
 // limitations under the License.
 
 use serde::Deserialize;
-use tracing::level_filters::LevelFilter;
 use tracing_subscriber::{fmt::MakeWriter, registry::LookupSpan, Layer};
 
-use crate::logging::config::{BoxedLayer, Level};
+use crate::logging::{
+    config::{BoxedLayer, Level},
+    filter::reloadable_filter,
+};
 
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
 pub(super) enum Formatter {
@@ -39,13 +41,13 @@ pub(super) fn create_layer<S, W>(&self, writer: W, level: Level) -> BoxedLayer<S
         S: tracing::Subscriber + for<'a> LookupSpan<'a>,
         W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
     {
-        let level: tracing::Level = level.into();
         let mut layer = tracing_subscriber::fmt::layer().with_writer(writer);
 
         let ansi = self.ansi.unwrap_or(true);
         layer = layer.with_ansi(ansi);
 
-        let filter = LevelFilter::from(level);
+        // The level can be changed at runtime, see set_log_filter
+        let filter = reloadable_filter::<S>(level);
         match self.formatter {
             Some(Formatter::Compact) => layer.compact().with_filter(filter).boxed(),
             Some(Formatter::Pretty) => layer.pretty().with_filter(filter).boxed(),
@@ -20,10 +20,14 @@
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
+pub use filter::{get_log_filter, reset_log_filter, set_log_filter, LogFilterInfo};
+
 mod config;
 mod console;
+mod filter;
 mod fmt;
 mod rolling_file;
+mod rolling_writer;
 mod tokio_console;
 
 /// Initializes the tracing subscriber with the specified log configuration file
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::path::PathBuf;
+
 use serde::Deserialize;
 use tracing::Subscriber;
 use tracing_appender::non_blocking::WorkerGuard;
@@ -22,11 +24,12 @@
     logging::{
         config::{AppenderConfig, BoxedLayer, Level},
         fmt::FmtLayerConfig,
+        rolling_writer::{FileSize, RollingFileOptions, RollingFileWriter},
     },
 };
 
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
-enum Rotation {
+pub(super) enum Rotation {
     Minutely,
     Hourly,
     Daily,
@@ -53,6 +56,12 @@ pub(super) struct RollingFileAppenderConfig {
     prefix: Option<String>,
     suffix: Option<String>,
     max_log_files: Option<usize>,
+    // Rotate when the file would grow beyond this size, in addition to the rotation period
+    max_file_size: Option<FileSize>,
+    // Delete the oldest files when all files of the appender together exceed this size
+    max_total_size: Option<FileSize>,
+    // Gzip rotated files
+    compress: Option<bool>,
 
     #[serde(flatten)]
     fmt: FmtLayerConfig,
@@ -65,6 +74,27 @@ impl<S> AppenderConfig<S> for RollingFileAppenderConfig
     fn create_layer_and_guard(
         &self,
     ) -> Result<(BoxedLayer<S>, Option<WorkerGuard>), LogConfigError> {
+        if self.max_file_size.is_some()
+            || self.max_total_size.is_some()
+            || self.compress.unwrap_or(false)
+        {
+            let writer = RollingFileWriter::new(RollingFileOptions {
+                directory: PathBuf::from(&self.directory),
+                prefix: self.prefix.clone(),
+                suffix: self.suffix.clone(),
+                rotation: self.rotation,
+                max_file_size: self.max_file_size.map(|size| size.0),
+                max_log_files: self.max_log_files,
+                max_total_size: self.max_total_size.map(|size| size.0),
+                compress: self.compress.unwrap_or(false),
+            })
+            .map_err(|e| LogConfigError::RollingFileOpen(self.directory.clone(), e))?;
+
+            let (non_blocking, guard) = tracing_appender::non_blocking(writer);
+            let fmt_layer = self.fmt.create_layer(non_blocking, self.level);
+            return Ok((fmt_layer, Some(guard)));
+        }
+
         let mut builder = tracing_appender::rolling::Builder::new();
 
         // Optional fields
@@ -136,10 +166,40 @@ fn test_deserialize_rolling_file_appender_config_custom_fmt() {
         assert_eq!(config.prefix, Some("myapp-".to_string()));
         assert_eq!(config.suffix, Some(".log".to_string()));
         assert_eq!(config.max_log_files, Some(5));
+        assert_eq!(config.max_file_size, None);
         assert_eq!(config.fmt.ansi, Some(true));
         assert_eq!(
             config.fmt.formatter,
             Some(crate::logging::fmt::Formatter::Pretty)
         );
     }
+
+    #[test]
+    fn test_deserialize_rolling_file_appender_config_size_rotation() {
+        let toml_str = r#"
+            level = "Info"
+            kind = "RollingFile"
+            rotation = "Daily"
+            directory = "/var/log/myapp"
+            prefix = "myapp"
+            suffix = "log"
+            max_log_files = 20
+            max_file_size = "100MB"
+            max_total_size = 1073741824
+            compress = true
+            formatter = "Json"
+        "#;
+
+        let config: RollingFileAppenderConfig =
+            toml::from_str(toml_str).expect("Failed to deserialize config");
+
+        assert_eq!(config.rotation, Rotation::Daily);
+        assert_eq!(config.max_file_size, Some(FileSize(100 * 1024 * 1024)));
+        assert_eq!(config.max_total_size, Some(FileSize(1024 * 1024 * 1024)));
+        assert_eq!(config.compress, Some(true));
+        assert_eq!(
+            config.fmt.formatter,
+            Some(crate::logging::fmt::Formatter::Json)
+        );
+    }
 }
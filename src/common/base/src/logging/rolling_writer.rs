@@ -0,0 +1,460 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread::{self, JoinHandle};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+
+use crate::logging::rolling_file::Rotation;
+
+const COMPRESSED_EXTENSION: &str = "gz";
+
+/// A file size in bytes, configured as a number of bytes or a string such as "100MB".
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "RawFileSize")]
+pub(super) struct FileSize(pub(super) u64);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawFileSize {
+    Bytes(u64),
+    Text(String),
+}
+
+impl TryFrom<RawFileSize> for FileSize {
+    type Error = String;
+
+    fn try_from(value: RawFileSize) -> Result<Self, Self::Error> {
+        match value {
+            RawFileSize::Bytes(bytes) => Ok(FileSize(bytes)),
+            RawFileSize::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl FromStr for FileSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("invalid file size {}", s))?;
+        let multiplier = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "K" | "KB" => 1024,
+            "M" | "MB" => 1024 * 1024,
+            "G" | "GB" => 1024 * 1024 * 1024,
+            _ => return Err(format!("invalid file size unit in {}", s)),
+        };
+        number
+            .checked_mul(multiplier)
+            .map(FileSize)
+            .ok_or_else(|| format!("file size {} is too large", s))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct RollingFileOptions {
+    pub(super) directory: PathBuf,
+    pub(super) prefix: Option<String>,
+    pub(super) suffix: Option<String>,
+    pub(super) rotation: Rotation,
+    pub(super) max_file_size: Option<u64>,
+    pub(super) max_log_files: Option<usize>,
+    pub(super) max_total_size: Option<u64>,
+    pub(super) compress: bool,
+}
+
+/// Writes to `<prefix>.<suffix>` and renames it to `<prefix>.<start time>.<suffix>` when the
+/// rotation period ends or the file would grow beyond `max_file_size`. Rotated files are
+/// compressed and pruned by a background thread, so writing is not blocked by them.
+pub(super) struct RollingFileWriter {
+    options: RollingFileOptions,
+    file: File,
+    size: u64,
+    opened_at: DateTime<Utc>,
+    period: Option<String>,
+    cleanup: Option<JoinHandle<()>>,
+}
+
+impl RollingFileWriter {
+    pub(super) fn new(options: RollingFileOptions) -> io::Result<Self> {
+        fs::create_dir_all(&options.directory)?;
+        let path = active_path(&options);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+
+        // A file left by a previous run belongs to the period it was last written in
+        let opened_at = if metadata.len() > 0 {
+            metadata
+                .modified()
+                .map(DateTime::<Utc>::from)
+                .unwrap_or_else(|_| Utc::now())
+        } else {
+            Utc::now()
+        };
+        let period = period_key(options.rotation, opened_at);
+
+        apply_retention(&options);
+        Ok(RollingFileWriter {
+            options,
+            file,
+            size: metadata.len(),
+            opened_at,
+            period,
+            cleanup: None,
+        })
+    }
+
+    fn should_rotate(&self, now: DateTime<Utc>, incoming: usize) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        if period_key(self.options.rotation, now) != self.period {
+            return true;
+        }
+        self.options
+            .max_file_size
+            .is_some_and(|max| self.size + incoming as u64 > max)
+    }
+
+    fn rotate(&mut self, now: DateTime<Utc>) -> io::Result<()> {
+        self.file.flush()?;
+        let active = active_path(&self.options);
+        let rotated = rotated_path(&self.options, self.opened_at);
+        fs::rename(&active, &rotated)?;
+        self.file = OpenOptions::new().create(true).append(true).open(&active)?;
+        self.reset(now);
+
+        // One cleanup at a time, they would otherwise race over the same files
+        self.wait_for_cleanup();
+        let options = self.options.clone();
+        self.cleanup = Some(thread::spawn(move || {
+            if options.compress {
+                if let Err(e) = compress(&rotated) {
+                    eprintln!("Failed to compress log file {:?}: {}", rotated, e);
+                }
+            }
+            apply_retention(&options);
+        }));
+        Ok(())
+    }
+
+    fn reset(&mut self, now: DateTime<Utc>) {
+        self.size = 0;
+        self.opened_at = now;
+        self.period = period_key(self.options.rotation, now);
+    }
+
+    fn wait_for_cleanup(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            let _ = cleanup.join();
+        }
+    }
+}
+
+impl Write for RollingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let now = Utc::now();
+        if self.should_rotate(now, buf.len()) {
+            if let Err(e) = self.rotate(now) {
+                // Keep writing to the current file and retry after another period or file size
+                eprintln!(
+                    "Failed to rotate log file {:?}: {}",
+                    active_path(&self.options),
+                    e
+                );
+                self.reset(now);
+            }
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for RollingFileWriter {
+    fn drop(&mut self) {
+        self.wait_for_cleanup();
+    }
+}
+
+fn period_key(rotation: Rotation, time: DateTime<Utc>) -> Option<String> {
+    let format = match rotation {
+        Rotation::Minutely => "%Y-%m-%d-%H-%M",
+        Rotation::Hourly => "%Y-%m-%d-%H",
+        Rotation::Daily => "%Y-%m-%d",
+        Rotation::Never => return None,
+    };
+    Some(time.format(format).to_string())
+}
+
+fn prefix(options: &RollingFileOptions) -> &str {
+    options.prefix.as_deref().unwrap_or("robustmq")
+}
+
+fn file_name(options: &RollingFileOptions, stamp: Option<&str>) -> String {
+    let mut parts = vec![prefix(options)];
+    parts.extend(stamp);
+    parts.extend(options.suffix.as_deref());
+    parts.join(".")
+}
+
+fn active_path(options: &RollingFileOptions) -> PathBuf {
+    options.directory.join(file_name(options, None))
+}
+
+fn rotated_path(options: &RollingFileOptions, opened_at: DateTime<Utc>) -> PathBuf {
+    let stamp = opened_at.format("%Y-%m-%d-%H-%M-%S").to_string();
+    let mut path = options.directory.join(file_name(options, Some(&stamp)));
+    let mut index = 1;
+    while path.exists() || compressed_path(&path).exists() {
+        let stamp = format!("{}.{}", stamp, index);
+        path = options.directory.join(file_name(options, Some(&stamp)));
+        index += 1;
+    }
+    path
+}
+
+fn compressed_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(COMPRESSED_EXTENSION);
+    PathBuf::from(name)
+}
+
+fn compress(path: &Path) -> io::Result<()> {
+    let target = compressed_path(path);
+    // Written under another name first, so retention never sees a partial archive
+    let mut partial = target.clone().into_os_string();
+    partial.push(".tmp");
+
+    let mut input = File::open(path)?;
+    let mut encoder = GzEncoder::new(File::create(&partial)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::rename(&partial, &target)?;
+    fs::remove_file(path)
+}
+
+/// Whether `name` is a file rotated from the active file, compressed or not. Files written by
+/// the time based appender (`<prefix>.<date>.<suffix>`) match as well.
+fn is_rotated_file(options: &RollingFileOptions, name: &str) -> bool {
+    let Some(rest) = name.strip_prefix(prefix(options)) else {
+        return false;
+    };
+    let Some(rest) = rest.strip_prefix('.') else {
+        return false;
+    };
+    let rest = rest
+        .strip_suffix(COMPRESSED_EXTENSION)
+        .and_then(|rest| rest.strip_suffix('.'))
+        .unwrap_or(rest);
+    let stamp = match options.suffix.as_deref() {
+        Some(suffix) => match rest
+            .strip_suffix(suffix)
+            .and_then(|rest| rest.strip_suffix('.'))
+        {
+            Some(stamp) => stamp,
+            None => return false,
+        },
+        None => rest,
+    };
+    stamp.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Deletes the oldest rotated files until `max_log_files`, which counts the active file too,
+/// and `max_total_size` are respected.
+fn apply_retention(options: &RollingFileOptions) {
+    if options.max_log_files.is_none() && options.max_total_size.is_none() {
+        return;
+    }
+    let entries = match fs::read_dir(&options.directory) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!(
+                "Failed to list log directory {:?}: {}",
+                options.directory, e
+            );
+            return;
+        }
+    };
+
+    let mut rotated: Vec<(SystemTime, String, u64)> = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_rotated_file(options, &name) {
+            continue;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            if metadata.is_file() {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                rotated.push((modified, name, metadata.len()));
+            }
+        }
+    }
+    rotated.sort();
+
+    let active_size = fs::metadata(active_path(options))
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let mut total_size = active_size + rotated.iter().map(|(_, _, size)| size).sum::<u64>();
+    let mut remaining = rotated.len();
+    for (_, name, size) in rotated {
+        let too_many = options
+            .max_log_files
+            .is_some_and(|max| remaining + 1 > max.max(1));
+        let too_large = options.max_total_size.is_some_and(|max| total_size > max);
+        if !too_many && !too_large {
+            break;
+        }
+        let path = options.directory.join(&name);
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                eprintln!("Failed to delete log file {:?}: {}", path, e);
+                continue;
+            }
+        }
+        remaining -= 1;
+        total_size -= size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn options(directory: &Path) -> RollingFileOptions {
+        RollingFileOptions {
+            directory: directory.to_path_buf(),
+            prefix: Some("server".to_string()),
+            suffix: Some("log".to_string()),
+            rotation: Rotation::Never,
+            max_file_size: None,
+            max_log_files: None,
+            max_total_size: None,
+            compress: false,
+        }
+    }
+
+    fn rotated_files(options: &RollingFileOptions) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(&options.directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| is_rotated_file(options, name))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_parse_file_size() {
+        assert_eq!("1024".parse::<FileSize>().unwrap(), FileSize(1024));
+        assert_eq!("10KB".parse::<FileSize>().unwrap(), FileSize(10 * 1024));
+        assert_eq!("100 mb".parse::<FileSize>().unwrap(), FileSize(100 << 20));
+        assert_eq!("2G".parse::<FileSize>().unwrap(), FileSize(2 << 30));
+        assert!("MB".parse::<FileSize>().is_err());
+        assert!("10TB".parse::<FileSize>().is_err());
+
+        #[derive(Deserialize)]
+        struct Config {
+            size: FileSize,
+        }
+        let config: Config = toml::from_str("size = 512").unwrap();
+        assert_eq!(config.size, FileSize(512));
+        let config: Config = toml::from_str(r#"size = "1MB""#).unwrap();
+        assert_eq!(config.size, FileSize(1 << 20));
+    }
+
+    #[test]
+    fn test_is_rotated_file() {
+        let dir = tempdir().unwrap();
+        let options = options(dir.path());
+        assert!(is_rotated_file(&options, "server.2024-01-01-10-00-00.log"));
+        assert!(is_rotated_file(
+            &options,
+            "server.2024-01-01-10-00-00.1.log.gz"
+        ));
+        assert!(is_rotated_file(&options, "server.2024-01-01.log"));
+        assert!(!is_rotated_file(&options, "server.log"));
+        assert!(!is_rotated_file(&options, "server.2024-01-01.log.gz.tmp"));
+        assert!(!is_rotated_file(&options, "error.2024-01-01.log"));
+    }
+
+    #[test]
+    fn test_rotate_by_size_with_retention() {
+        let dir = tempdir().unwrap();
+        let mut options = options(dir.path());
+        options.max_file_size = Some(64);
+        options.max_log_files = Some(3);
+
+        let mut writer = RollingFileWriter::new(options.clone()).unwrap();
+        for i in 0..20 {
+            writer
+                .write_all(format!("log line number {:04}\n", i).as_bytes())
+                .unwrap();
+        }
+        writer.flush().unwrap();
+        writer.wait_for_cleanup();
+
+        let active = fs::metadata(active_path(&options)).unwrap();
+        assert!(active.len() <= 64);
+        assert_eq!(rotated_files(&options).len(), 2);
+    }
+
+    #[test]
+    fn test_compress_rotated_files() {
+        let dir = tempdir().unwrap();
+        let mut options = options(dir.path());
+        options.max_file_size = Some(16);
+        options.compress = true;
+
+        let mut writer = RollingFileWriter::new(options.clone()).unwrap();
+        writer.write_all(b"first log line\n").unwrap();
+        writer.write_all(b"second log line\n").unwrap();
+        drop(writer);
+
+        let rotated = rotated_files(&options);
+        assert_eq!(rotated.len(), 1);
+        assert!(rotated[0].ends_with(".log.gz"));
+
+        let mut content = String::new();
+        GzDecoder::new(File::open(dir.path().join(&rotated[0])).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "first log line\n");
+    }
+}
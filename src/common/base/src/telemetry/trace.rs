@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::config::broker_mqtt::BrokerMqttConfig;
-use opentelemetry::{global, propagation::Extractor, trace::noop::NoopTracerProvider};
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector},
+    trace::noop::NoopTracerProvider,
+    Context,
+};
 use opentelemetry_otlp::{SpanExporter, WithExportConfig};
 use opentelemetry_sdk::{propagation::TraceContextPropagator, trace as sdktrace, Resource};
 use std::{collections::HashMap, sync::OnceLock};
@@ -31,16 +35,16 @@ pub enum TraceExporterProvider {
 
 static GLOBAL_PROVIDER: OnceLock<TraceExporterProvider> = OnceLock::new();
 
-pub async fn init_tracer_provider(broker_config: &'static BrokerMqttConfig) {
-    if !broker_config.telemetry.enable {
+pub async fn init_tracer_provider(enable: bool, exporter_type: &str, exporter_endpoint: &str) {
+    if !enable {
         global::set_tracer_provider(NoopTracerProvider::new());
         return;
     }
-    match broker_config.telemetry.exporter_type.as_str() {
+    match exporter_type {
         "otlp" => {
             let exporter = SpanExporter::builder()
                 .with_tonic()
-                .with_endpoint(broker_config.telemetry.exporter_endpoint.as_str())
+                .with_endpoint(exporter_endpoint)
                 .build()
                 .unwrap();
             global::set_text_map_propagator(TraceContextPropagator::new());
@@ -59,6 +63,11 @@ pub async fn init_tracer_provider(broker_config: &'static BrokerMqttConfig) {
     }
 }
 
+// Spans are only worth creating when they are exported
+pub fn is_tracer_enabled() -> bool {
+    matches!(GLOBAL_PROVIDER.get(), Some(TraceExporterProvider::Otlp(_)))
+}
+
 pub async fn stop_tracer_provider() {
     if let Some(provider) = GLOBAL_PROVIDER.get() {
         match provider {
@@ -94,12 +103,29 @@ fn keys(&self) -> Vec<&str> {
     }
 }
 
+impl Injector for CustomContext {
+    fn set(&mut self, key: &str, value: String) {
+        self.inner.insert(key.to_owned(), value);
+    }
+}
+
 impl CustomContext {
     pub fn new() -> Self {
         CustomContext {
             inner: HashMap::new(),
         }
     }
+
+    // Parent context carried by the entries, e.g. a W3C traceparent
+    pub fn extract(&self) -> Context {
+        global::get_text_map_propagator(|propagator| propagator.extract(self))
+    }
+
+    pub fn inject(cx: &Context) -> Self {
+        let mut carrier = CustomContext::new();
+        global::get_text_map_propagator(|propagator| propagator.inject_context(cx, &mut carrier));
+        carrier
+    }
 }
 
 impl Default for CustomContext {
@@ -110,31 +136,49 @@ fn default() -> Self {
 
 #[cfg(test)]
 mod tests {
-    use crate::config::broker_mqtt::broker_mqtt_conf;
-    use crate::config::broker_mqtt::init_broker_mqtt_conf_by_path;
-    use std::path::PathBuf;
+    use opentelemetry::trace::{
+        SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState,
+    };
+    use opentelemetry::Context;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
 
-    use super::init_tracer_provider;
+    use super::{init_tracer_provider, is_tracer_enabled, CustomContext};
+
+    #[tokio::test]
+    async fn telemetry_test_init() {
+        init_tracer_provider(false, "otlp", "grpc://127.0.0.1:4317").await;
+        assert!(!is_tracer_enabled());
+    }
 
     #[test]
-    fn telemetry_config_test() {
-        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        path.push("../../../example/mqtt-cluster/mqtt-server/node-1.toml");
-        init_broker_mqtt_conf_by_path(path.to_str().unwrap());
-        let telemetry_config = &broker_mqtt_conf().telemetry;
-        assert!(!telemetry_config.enable);
+    fn trace_context_propagation_test() {
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let mut carrier = CustomContext::new();
+        carrier.inner.insert(
+            "traceparent".to_string(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+        );
+        let cx = carrier.extract();
+        let span_context = cx.span().span_context().clone();
+        assert!(span_context.is_valid());
         assert_eq!(
-            telemetry_config.exporter_endpoint,
-            "grpc://127.0.0.1:4317".to_string()
+            span_context.trace_id(),
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap()
         );
-        assert_eq!(telemetry_config.exporter_type, "otlp".to_string());
-    }
 
-    #[tokio::test]
-    async fn telemetry_test_init() {
-        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        path.push("../../../example/mqtt-cluster/mqtt-server/node-1.toml");
-        init_broker_mqtt_conf_by_path(path.to_str().unwrap());
-        init_tracer_provider(broker_mqtt_conf()).await
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("b7ad6b7169203331").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let cx = Context::new().with_remote_span_context(span_context);
+        let carrier = CustomContext::inject(&cx);
+        assert_eq!(
+            carrier.inner.get("traceparent").unwrap(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-b7ad6b7169203331-01"
+        );
     }
 }
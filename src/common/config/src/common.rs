@@ -64,6 +64,13 @@ pub struct Pprof {
     pub port: u16,
     #[serde(default = "default_pprof_frequency")]
     pub frequency: i32,
+    #[serde(default = "default_pprof_bind")]
+    pub bind: String,
+    // When not empty, requests must carry it in the Authorization header as a bearer token
+    #[serde(default)]
+    pub token: String,
+    #[serde(default = "default_pprof_max_seconds")]
+    pub max_seconds: u64,
 }
 
 pub fn default_prometheus() -> Prometheus {
@@ -82,6 +89,9 @@ pub fn default_pprof() -> Pprof {
         enable: false,
         port: default_pprof_port(),
         frequency: default_pprof_frequency(),
+        bind: default_pprof_bind(),
+        token: "".to_string(),
+        max_seconds: default_pprof_max_seconds(),
     }
 }
 
@@ -101,6 +111,14 @@ pub fn default_pprof_frequency() -> i32 {
     100
 }
 
+pub fn default_pprof_bind() -> String {
+    "127.0.0.1".to_string()
+}
+
+pub fn default_pprof_max_seconds() -> u64 {
+    300
+}
+
 /** `override_default_by_env` Cover the content based on the environment variables
 
 ```
@@ -21,7 +21,7 @@
     default_max_segment_size, default_network, default_network_tcp_port, default_network_tcps_port,
     default_shard, default_shard_replica_num, default_storage, default_system, default_tcp_thread,
 };
-use crate::common::{default_prometheus, Log, Prometheus};
+use crate::common::{default_pprof, default_prometheus, Log, Pprof, Prometheus};
 use common_base::tools::{read_file, try_create_fold};
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -42,6 +42,8 @@ pub struct JournalServerConfig {
     pub tcp_thread: TcpThread,
     #[serde(default = "default_prometheus")]
     pub prometheus: Prometheus,
+    #[serde(default = "default_pprof")]
+    pub pprof: Pprof,
     #[serde(default = "default_log")]
     pub log: Log,
 }
@@ -186,5 +188,10 @@ fn journal_server_toml_test() {
         assert_eq!(conf.prometheus.model, "pull".to_string());
         assert_eq!(conf.prometheus.port, 9092);
         assert_eq!(conf.prometheus.interval, 10);
+
+        assert!(!conf.pprof.enable);
+        assert_eq!(conf.pprof.port, 6061);
+        assert_eq!(conf.pprof.bind, "127.0.0.1".to_string());
+        assert_eq!(conf.pprof.max_seconds, 300);
     }
 }
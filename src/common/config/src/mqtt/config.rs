@@ -13,17 +13,25 @@
 // limitations under the License.
 
 use super::default::{
-    default_auth_storage, default_feature, default_flapping_detect, default_grpc_port,
+    default_alarm, default_alarm_rule_enable, default_alarm_severity, default_auth_storage,
+    default_cluster_route, default_feature, default_flapping_detect, default_grpc_port,
     default_heartbeat_timeout, default_log, default_message_storage, default_network_port,
     default_network_quic_port, default_network_tcp_port, default_network_tcps_port,
     default_network_thread, default_network_websocket_port, default_network_websockets_port,
-    default_offline_message, default_placement_center, default_protocol, default_schema,
-    default_security, default_slow_sub, default_system, default_system_monitor, default_telemetry,
+    default_offline_drop_policy, default_offline_message, default_placement_center,
+    default_protocol, default_queue_max_inflight, default_queue_subscription,
+    default_retain_message_storage, default_schema, default_security, default_shared_subscription,
+    default_slow_sub, default_system, default_system_monitor, default_telemetry,
+    default_topic_metrics, default_topic_metrics_max_topic_num, default_webhook,
+    default_webhook_batch_interval_ms, default_webhook_batch_size, default_webhook_max_retries,
+    default_webhook_retry_backoff_ms, default_webhook_spool_dir, default_webhook_spool_max_bytes,
+    default_webhook_timeout_ms,
 };
 use crate::common::{
     default_pprof, default_prometheus, AvailableFlag, Log, Pprof, Prometheus, Telemetry,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct BrokerMqttConfig {
@@ -59,6 +67,10 @@ pub struct BrokerMqttConfig {
     #[serde(default = "default_auth_storage")]
     pub auth_storage: AuthStorage,
 
+    // retain message storage
+    #[serde(default = "default_retain_message_storage")]
+    pub retain_message_storage: RetainMessageStorage,
+
     // log
     #[serde(default = "default_log")]
     pub log: Log,
@@ -106,6 +118,30 @@ pub struct BrokerMqttConfig {
     // system monitor
     #[serde(default = "default_system_monitor")]
     pub system_monitor: SystemMonitor,
+
+    // shared subscription
+    #[serde(default = "default_shared_subscription")]
+    pub shared_subscription: SharedSubscription,
+
+    // cluster route
+    #[serde(default = "default_cluster_route")]
+    pub cluster_route: ClusterRoute,
+
+    // queue subscription
+    #[serde(default = "default_queue_subscription")]
+    pub queue_subscription: QueueSubscription,
+
+    // alarm
+    #[serde(default = "default_alarm")]
+    pub alarm: Alarm,
+
+    // webhook
+    #[serde(default = "default_webhook")]
+    pub webhook: Webhook,
+
+    // topic metrics
+    #[serde(default = "default_topic_metrics")]
+    pub topic_metrics: TopicMetrics,
 }
 
 // MQTT cluster protocol related dynamic configuration
@@ -142,6 +178,13 @@ pub fn encode(&self) -> Vec<u8> {
     }
 }
 
+// Where retained messages are persisted: "placement" keeps them on the topic metadata in the
+// placement center, "message_storage" writes them to the message storage adapter
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct RetainMessageStorage {
+    pub storage_type: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
 pub struct AuthStorage {
     pub storage_type: String,
@@ -275,14 +318,166 @@ pub struct System {
     pub heartbeat_timeout: String,
 }
 
+// How a share group dispatches messages to its members. Strategies are round_robin, random,
+// sticky, hash_client_id, hash_topic and least_inflight.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct SharedSubscription {
+    // Strategy of the groups that have no strategy of their own
+    pub strategy: String,
+    // (group_name, strategy)
+    #[serde(default)]
+    pub group_strategy: HashMap<String, String>,
+}
+
+impl SharedSubscription {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+
+    pub fn group_strategy(&self, group_name: &str) -> &str {
+        self.group_strategy
+            .get(group_name)
+            .map(|strategy| strategy.as_str())
+            .unwrap_or(&self.strategy)
+    }
+}
+
+// Forwarding of published messages to the brokers that hold non-shared subscriptions on
+// the topic, so they are pushed without waiting for the next read of the message storage.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct ClusterRoute {
+    pub enable: bool,
+    // How often the route table is published to and loaded from the placement center
+    #[serde(default)]
+    pub sync_interval_ms: u64,
+    // Maximum number of messages sent to a broker in one forward request
+    #[serde(default)]
+    pub forward_batch_size: usize,
+}
+
+// Delivery of `$queue/` subscriptions. Every message is handed to one member and has to be
+// acknowledged (PUBACK or PUBCOMP) within the visibility timeout, otherwise it is delivered
+// again. After max_delivery_attempts it is moved to the dead letter topic.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct QueueSubscription {
+    pub visibility_timeout_ms: u64,
+    pub max_delivery_attempts: u32,
+    // Messages of a queue group delivered at the same time
+    #[serde(default = "default_queue_max_inflight")]
+    pub max_inflight: u32,
+    // Topic of the poison messages, `{topic}` is replaced by the topic of the queue
+    pub dead_letter_topic: String,
+}
+
+// Alarms checked periodically by the broker. Rules configured here override the built-in rule
+// of the same name, built-in rules that are not listed keep their defaults.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Alarm {
+    #[serde(default)]
+    pub enable: bool,
+    #[serde(default)]
+    pub check_interval_ms: u64,
+    // Number of activation and deactivation records kept in the alarm history
+    #[serde(default)]
+    pub history_max_num: usize,
+    #[serde(default)]
+    pub rules: HashMap<String, AlarmRule>,
+}
+
+// An alarm is activated when the checked value reaches activate_threshold and deactivated
+// once it falls to deactivate_threshold, the gap between both avoids flapping.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AlarmRule {
+    #[serde(default = "default_alarm_rule_enable")]
+    pub enable: bool,
+    // info, warning or critical
+    #[serde(default = "default_alarm_severity")]
+    pub severity: String,
+    pub activate_threshold: f64,
+    pub deactivate_threshold: f64,
+}
+
+// HTTP endpoints notified of client lifecycle and message events
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Webhook {
+    #[serde(default)]
+    pub enable: bool,
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpoint>,
+}
+
+// Events are posted to the url as a JSON array. A batch that fails is spooled to
+// {spool_dir}/{name}.{segment}.spool and retried in the background, max_retries times doubling
+// retry_backoff_ms after each attempt and then every 30 seconds until the endpoint is back.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct WebhookEndpoint {
+    pub name: String,
+    pub url: String,
+    // Event types posted to the endpoint, empty means all of them
+    #[serde(default)]
+    pub events: Vec<String>,
+    // Topic filters the message and subscription events must match, empty means all topics.
+    // Events without a topic are not filtered.
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    // A batch is posted once it is full or batch_interval_ms after the last post
+    #[serde(default = "default_webhook_batch_size")]
+    pub batch_size: usize,
+    #[serde(default = "default_webhook_batch_interval_ms")]
+    pub batch_interval_ms: u64,
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_webhook_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_webhook_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    #[serde(default = "default_webhook_spool_dir")]
+    pub spool_dir: String,
+    // Events that do not fit into the spool any more are dropped
+    #[serde(default = "default_webhook_spool_max_bytes")]
+    pub spool_max_bytes: u64,
+}
+
+// Per-topic message counters, off by default. With topic_filters the messages are counted per
+// matching filter, otherwise per topic. At most max_topic_num topics or filters are counted,
+// the least active counters are evicted to make room for new topics.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct TopicMetrics {
+    #[serde(default)]
+    pub enable: bool,
+    #[serde(default = "default_topic_metrics_max_topic_num")]
+    pub max_topic_num: usize,
+    #[serde(default)]
+    pub topic_filters: Vec<String>,
+}
+
+impl QueueSubscription {
+    pub fn dead_letter_topic(&self, topic_name: &str) -> String {
+        self.dead_letter_topic
+            .replace("{topic}", topic_name.trim_start_matches('/'))
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct OfflineMessage {
     #[serde(default)]
     pub enable: bool,
     #[serde(default)]
     pub expire_ms: u32,
+    // Maximum number of messages queued for an offline persistent session, 0 means no limit
     #[serde(default)]
     pub max_messages_num: u32,
+    // Maximum payload bytes queued for an offline persistent session, 0 means no limit
+    #[serde(default)]
+    pub max_bytes: u64,
+    // What is dropped when the queue of a session is full: drop_oldest or drop_newest
+    #[serde(default = "default_offline_drop_policy")]
+    pub drop_policy: String,
+    // QoS 0 messages are not kept for offline sessions
+    #[serde(default)]
+    pub drop_qos0: bool,
 }
 
 impl OfflineMessage {
@@ -19,9 +19,12 @@
 use crate::{
     common::{AvailableFlag, Log, Telemetry},
     mqtt::config::{
-        AuthStorage, MessageDataStorage, Schema, SchemaFailedOperation, SchemaStrategy,
+        Alarm, AlarmRule, AuthStorage, ClusterRoute, MessageDataStorage, QueueSubscription,
+        RetainMessageStorage, Schema, SchemaFailedOperation, SchemaStrategy, SharedSubscription,
+        TopicMetrics, Webhook,
     },
 };
+use std::collections::HashMap;
 
 pub fn default_grpc_port() -> u32 {
     9981
@@ -116,6 +119,47 @@ pub fn default_offline_message() -> OfflineMessage {
         enable: false,
         expire_ms: 0,
         max_messages_num: 0,
+        max_bytes: 0,
+        drop_policy: default_offline_drop_policy(),
+        drop_qos0: false,
+    }
+}
+
+pub fn default_offline_drop_policy() -> String {
+    "drop_oldest".to_string()
+}
+
+pub fn default_cluster_route() -> ClusterRoute {
+    ClusterRoute {
+        enable: false,
+        sync_interval_ms: 1000,
+        forward_batch_size: 100,
+    }
+}
+
+pub fn default_queue_subscription() -> QueueSubscription {
+    QueueSubscription {
+        visibility_timeout_ms: 30000,
+        max_delivery_attempts: 5,
+        max_inflight: default_queue_max_inflight(),
+        dead_letter_topic: "dead_letter/{topic}".to_string(),
+    }
+}
+
+pub fn default_queue_max_inflight() -> u32 {
+    32
+}
+
+pub fn default_shared_subscription() -> SharedSubscription {
+    SharedSubscription {
+        strategy: "round_robin".to_string(),
+        group_strategy: HashMap::new(),
+    }
+}
+
+pub fn default_retain_message_storage() -> RetainMessageStorage {
+    RetainMessageStorage {
+        storage_type: "placement".to_string(),
     }
 }
 
@@ -194,3 +238,94 @@ pub fn default_schema() -> Schema {
         log_level: "info".to_string(),
     }
 }
+
+pub fn default_alarm() -> Alarm {
+    Alarm {
+        enable: true,
+        check_interval_ms: 10000,
+        history_max_num: 1000,
+        rules: default_alarm_rules(),
+    }
+}
+
+pub fn default_alarm_rules() -> HashMap<String, AlarmRule> {
+    let rule = |severity: &str, activate_threshold: f64, deactivate_threshold: f64| AlarmRule {
+        enable: true,
+        severity: severity.to_string(),
+        activate_threshold,
+        deactivate_threshold,
+    };
+    HashMap::from([
+        (
+            "ConnectionCountHigh".to_string(),
+            rule("warning", 50000.0, 45000.0),
+        ),
+        (
+            "SubscriptionCountHigh".to_string(),
+            rule("warning", 100000.0, 90000.0),
+        ),
+        // failures counted within one check interval
+        (
+            "StorageWriteFailure".to_string(),
+            rule("critical", 1.0, 0.0),
+        ),
+        ("ConnectorFailure".to_string(), rule("critical", 1.0, 0.0)),
+        // the largest number of unacknowledged messages of a single client
+        ("InflightHigh".to_string(), rule("warning", 1000.0, 800.0)),
+    ])
+}
+
+pub fn default_alarm_rule_enable() -> bool {
+    true
+}
+
+pub fn default_alarm_severity() -> String {
+    "warning".to_string()
+}
+
+pub fn default_webhook() -> Webhook {
+    Webhook {
+        enable: false,
+        endpoints: Vec::new(),
+    }
+}
+
+pub fn default_webhook_batch_size() -> usize {
+    100
+}
+
+pub fn default_webhook_batch_interval_ms() -> u64 {
+    1000
+}
+
+pub fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
+pub fn default_webhook_max_retries() -> u32 {
+    3
+}
+
+pub fn default_webhook_retry_backoff_ms() -> u64 {
+    500
+}
+
+pub fn default_webhook_spool_dir() -> String {
+    "./data/mqtt-broker/webhook".to_string()
+}
+
+pub fn default_webhook_spool_max_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+pub fn default_topic_metrics() -> TopicMetrics {
+    TopicMetrics {
+        enable: false,
+        max_topic_num: default_topic_metrics_max_topic_num(),
+        topic_filters: Vec::new(),
+    }
+}
+
+pub fn default_topic_metrics_max_topic_num() -> usize {
+    1000
+}
@@ -26,7 +26,9 @@
     default_max_open_files, default_network, default_node, default_node_id, default_nodes,
     default_rocksdb, default_runtime_work_threads, default_system,
 };
-use crate::common::{default_prometheus, override_default_by_env, Log, Prometheus};
+use crate::common::{
+    default_pprof, default_prometheus, override_default_by_env, Log, Pprof, Prometheus,
+};
 use common_base::tools::{read_file, try_create_fold};
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -47,6 +49,8 @@ pub struct PlacementCenterConfig {
     pub log: Log,
     #[serde(default = "default_prometheus")]
     pub prometheus: Prometheus,
+    #[serde(default = "default_pprof")]
+    pub pprof: Pprof,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -207,5 +211,7 @@ fn meta_default() {
         assert_eq!(config.rocksdb.max_open_files, Some(10000_i32));
         assert_eq!(config.heartbeat.heartbeat_timeout_ms, 5000);
         assert_eq!(config.heartbeat.heartbeat_check_time_ms, 1000);
+        assert!(!config.pprof.enable);
+        assert_eq!(config.pprof.port, 6062);
     }
 }
@@ -0,0 +1,48 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use serde::{Deserialize, Serialize};
+
+/// Forwards messages to a remote MQTT broker.
+/// `remote_topic` is a template, `${topic}` is replaced by the topic the message was published to.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MqttBridgeConnectorConfig {
+    pub server: String,
+    #[serde(default)]
+    pub client_id_prefix: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_remote_topic")]
+    pub remote_topic: String,
+    #[serde(default = "default_qos")]
+    pub qos: i32,
+    // None keeps the retain flag of the original message
+    #[serde(default)]
+    pub retain: Option<bool>,
+    #[serde(default = "default_keep_alive")]
+    pub keep_alive: u64,
+}
+
+fn default_remote_topic() -> String {
+    "${topic}".to_string()
+}
+
+fn default_qos() -> i32 {
+    1
+}
+
+fn default_keep_alive() -> u64 {
+    60
+}
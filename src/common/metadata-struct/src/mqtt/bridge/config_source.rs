@@ -0,0 +1,96 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use serde::{Deserialize, Serialize};
+
+/// Where a source connector publishes what it consumes.
+/// `topic` is a template, `${topic}` is replaced by the Kafka topic or remote MQTT topic the
+/// message was read from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SourcePublishConfig {
+    pub topic: String,
+    #[serde(default = "default_qos")]
+    pub qos: u8,
+    // For MQTT sources None keeps the retain flag of the remote message
+    #[serde(default)]
+    pub retain: Option<bool>,
+}
+
+/// Consumes a Kafka topic. Offsets are committed to `group_id` after the message was published.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KafkaSourceConnectorConfig {
+    pub bootstrap_servers: String,
+    pub topic: String,
+    // Defaults to the connector name
+    #[serde(default)]
+    pub group_id: Option<String>,
+    pub publish: SourcePublishConfig,
+}
+
+/// Subscribes to `remote_topic` on a remote MQTT broker.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MqttSourceConnectorConfig {
+    pub server: String,
+    #[serde(default)]
+    pub client_id_prefix: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    pub remote_topic: String,
+    #[serde(default = "default_qos")]
+    pub remote_qos: u8,
+    #[serde(default = "default_keep_alive")]
+    pub keep_alive: u64,
+    pub publish: SourcePublishConfig,
+}
+
+/// Tails a local file, every new line is published as one message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FileSourceConnectorConfig {
+    pub local_file_path: String,
+    // Start from the beginning of the file instead of only reading lines appended later
+    #[serde(default)]
+    pub from_beginning: bool,
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    pub publish: SourcePublishConfig,
+}
+
+fn default_qos() -> u8 {
+    1
+}
+
+fn default_keep_alive() -> u64 {
+    60
+}
+
+fn default_poll_interval_ms() -> u64 {
+    500
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KafkaSourceConnectorConfig;
+
+    #[test]
+    fn kafka_source_config_default_test() {
+        let config: KafkaSourceConnectorConfig = serde_json::from_str(
+            r#"{"bootstrap_servers":"127.0.0.1:9092","topic":"commands","publish":{"topic":"device/${topic}"}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.group_id, None);
+        assert_eq!(config.publish.qos, 1);
+        assert_eq!(config.publish.retain, None);
+    }
+}
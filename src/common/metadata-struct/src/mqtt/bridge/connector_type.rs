@@ -21,6 +21,23 @@ pub enum ConnectorType {
     #[default]
     Kafka,
     LocalFile,
+    Mqtt,
+    Http,
+    MySql,
+    Postgres,
+    KafkaSource,
+    MqttSource,
+    FileSource,
+}
+
+impl ConnectorType {
+    // Source connectors publish into RobustMQ topics instead of reading the connector topic
+    pub fn is_source(&self) -> bool {
+        matches!(
+            self,
+            ConnectorType::KafkaSource | ConnectorType::MqttSource | ConnectorType::FileSource
+        )
+    }
 }
 
 impl Display for ConnectorType {
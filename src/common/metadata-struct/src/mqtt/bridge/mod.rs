@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod config_http;
 pub mod config_kafka;
 pub mod config_local_file;
+pub mod config_mqtt;
+pub mod config_rdb;
+pub mod config_source;
 pub mod connector;
 pub mod connector_type;
 pub mod status;
@@ -12,10 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::atomic::{AtomicIsize, AtomicU16, Ordering};
 use std::sync::Arc;
 
 use common_base::tools::now_second;
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 
@@ -31,6 +32,8 @@ pub struct MQTTConnection {
     pub source_ip_addr: String,
     // The user name of the client that initiated the connection
     pub login_user: String,
+    // The tenant the login user belongs to, empty means the default tenant
+    pub tenant: String,
     // When the client does not report a heartbeat, the maximum survival time of the connection,
     pub keep_alive: u16,
     // Records the Topic alias information for the connection dimension
@@ -41,6 +44,14 @@ pub struct MQTTConnection {
     pub max_packet_size: u32,
     // Record the maximum number of connection dimensions and topic aliases. The default value ranges from 0 to 65535
     pub topic_alias_max: u16,
+    // The Topic Alias Maximum of the CONNECT packet, the highest alias the broker may use in the
+    // PUBLISH packets it sends to the client. 0 means the client does not accept topic aliases.
+    pub client_topic_alias_max: u16,
+    // (topic_name, ServerTopicAlias) aliases the broker assigned for PUBLISH packets sent to the client
+    pub server_topic_alias: DashMap<String, ServerTopicAlias>,
+    // The last alias the broker assigned
+    #[serde(skip_serializing, skip_deserializing)]
+    pub server_topic_alias_seq: Arc<AtomicU16>,
     // Flags whether to return a detailed error message to the client when an error occurs.
     pub request_problem_info: u8,
     // Flow control part keeps track of how many QOS 1 and QOS 2 messages are still pending on the connection
@@ -53,12 +64,21 @@ pub struct MQTTConnection {
     pub create_time: u64,
 }
 
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServerTopicAlias {
+    pub alias: u16,
+    // Whether a PUBLISH carrying both the topic name and the alias was written to the client.
+    // Until then the topic name has to be sent, a concurrent push may overtake that packet.
+    pub established: bool,
+}
+
 pub struct ConnectionConfig {
     pub connect_id: u64,
     pub client_id: String,
     pub receive_maximum: u16,
     pub max_packet_size: u32,
     pub topic_alias_max: u16,
+    pub client_topic_alias_max: u16,
     pub request_problem_info: u8,
     pub keep_alive: u16,
     pub source_ip_addr: String,
@@ -75,6 +95,9 @@ pub fn new(config: ConnectionConfig) -> MQTTConnection {
             max_packet_size: config.max_packet_size,
             topic_alias: DashMap::with_capacity(2),
             topic_alias_max: config.topic_alias_max,
+            client_topic_alias_max: config.client_topic_alias_max,
+            server_topic_alias: DashMap::with_capacity(2),
+            server_topic_alias_seq: Arc::new(AtomicU16::new(0)),
             request_problem_info: config.request_problem_info,
             receive_qos_message: Arc::new(AtomicIsize::new(0)),
             sender_qos_message: Arc::new(AtomicIsize::new(0)),
@@ -84,6 +107,49 @@ pub fn new(config: ConnectionConfig) -> MQTTConnection {
         }
     }
 
+    // The alias to send with a PUBLISH of the topic, assigning a new one while any are left.
+    // Returns the alias and whether the topic name can be left out of the packet.
+    pub fn outbound_topic_alias(&self, topic_name: &str) -> Option<(u16, bool)> {
+        if self.client_topic_alias_max == 0 || topic_name.is_empty() {
+            return None;
+        }
+
+        if let Some(alias) = self.server_topic_alias.get(topic_name) {
+            return Some((alias.alias, alias.established));
+        }
+
+        // The alias is taken while the entry is held, so concurrent callers for the same topic
+        // do not use up aliases nobody keeps
+        let max = self.client_topic_alias_max;
+        match self.server_topic_alias.entry(topic_name.to_owned()) {
+            Entry::Occupied(entry) => Some((entry.get().alias, entry.get().established)),
+            Entry::Vacant(entry) => {
+                let alias = self
+                    .server_topic_alias_seq
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |seq| {
+                        if seq < max {
+                            Some(seq + 1)
+                        } else {
+                            None
+                        }
+                    })
+                    .ok()?
+                    + 1;
+                entry.insert(ServerTopicAlias {
+                    alias,
+                    established: false,
+                });
+                Some((alias, false))
+            }
+        }
+    }
+
+    pub fn establish_outbound_topic_alias(&self, topic_name: &str) {
+        if let Some(mut alias) = self.server_topic_alias.get_mut(topic_name) {
+            alias.established = true;
+        }
+    }
+
     pub fn login_success(&mut self, user_name: String) {
         self.is_login = true;
         self.login_user = user_name;
@@ -117,3 +183,60 @@ pub fn send_qos_message_decr(&self) {
         self.sender_qos_message.fetch_add(-1, Ordering::Relaxed);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{ConnectionConfig, MQTTConnection};
+
+    fn connection(client_topic_alias_max: u16) -> MQTTConnection {
+        MQTTConnection::new(ConnectionConfig {
+            connect_id: 1,
+            client_id: "c1".to_string(),
+            receive_maximum: 10,
+            max_packet_size: 1024,
+            topic_alias_max: 10,
+            client_topic_alias_max,
+            request_problem_info: 0,
+            keep_alive: 60,
+            source_ip_addr: "127.0.0.1".to_string(),
+        })
+    }
+
+    #[test]
+    fn outbound_topic_alias_test() {
+        let conn = connection(2);
+        assert_eq!(conn.outbound_topic_alias("t1"), Some((1, false)));
+        assert_eq!(conn.outbound_topic_alias("t1"), Some((1, false)));
+        conn.establish_outbound_topic_alias("t1");
+        assert_eq!(conn.outbound_topic_alias("t1"), Some((1, true)));
+
+        assert_eq!(conn.outbound_topic_alias("t2"), Some((2, false)));
+        // All aliases are in use
+        assert_eq!(conn.outbound_topic_alias("t3"), None);
+        assert_eq!(conn.outbound_topic_alias("t1"), Some((1, true)));
+    }
+
+    #[test]
+    fn outbound_topic_alias_concurrent_test() {
+        let conn = Arc::new(connection(2));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let conn = conn.clone();
+                std::thread::spawn(move || conn.outbound_topic_alias("t1"))
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), Some((1, false)));
+        }
+        // Racing for one topic took a single alias
+        assert_eq!(conn.outbound_topic_alias("t2"), Some((2, false)));
+    }
+
+    #[test]
+    fn outbound_topic_alias_disabled_test() {
+        let conn = connection(0);
+        assert_eq!(conn.outbound_topic_alias("t1"), None);
+    }
+}
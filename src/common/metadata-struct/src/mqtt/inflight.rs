@@ -0,0 +1,186 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use bytes::Bytes;
+use common_base::error::common::CommonError;
+use common_base::tools::now_mills;
+use protocol::mqtt::common::{Publish, PublishProperties, QoS};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub enum InflightState {
+    // The PUBLISH has been sent and is waiting for PUBACK (QoS1) or PUBREC (QoS2)
+    #[default]
+    Publish,
+    // PUBREC has been received, the PUBREL has been sent and is waiting for PUBCOMP
+    PubRel,
+}
+
+// An outbound QoS1/QoS2 message of a persistent session that the client has not
+// acknowledged yet. It is kept outside the broker so that it can be resent after
+// the client reconnects, even if it reconnects to another broker.
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct InflightMessage {
+    pub client_id: String,
+    pub pkid: u16,
+    pub qos: QoS,
+    pub state: InflightState,
+    pub retain: bool,
+    pub topic: Bytes,
+    pub payload: Bytes,
+    pub contain_properties: bool,
+    pub format_indicator: Option<u8>,
+    pub message_expiry_interval: Option<u32>,
+    pub response_topic: Option<String>,
+    pub correlation_data: Option<Bytes>,
+    pub user_properties: Vec<(String, String)>,
+    pub subscription_identifiers: Vec<usize>,
+    pub content_type: Option<String>,
+    pub create_time: u128,
+}
+
+impl InflightMessage {
+    pub fn build(
+        client_id: &str,
+        publish: &Publish,
+        publish_properties: &Option<PublishProperties>,
+        state: InflightState,
+    ) -> InflightMessage {
+        let mut message = InflightMessage {
+            client_id: client_id.to_owned(),
+            pkid: publish.pkid,
+            qos: publish.qos,
+            state,
+            retain: publish.retain,
+            topic: publish.topic.clone(),
+            payload: publish.payload.clone(),
+            create_time: now_mills(),
+            ..Default::default()
+        };
+        if let Some(properties) = publish_properties {
+            message.contain_properties = true;
+            message.format_indicator = properties.payload_format_indicator;
+            message.message_expiry_interval = properties.message_expiry_interval;
+            message.response_topic = properties.response_topic.clone();
+            message.correlation_data = properties.correlation_data.clone();
+            message.user_properties = properties.user_properties.clone();
+            message.subscription_identifiers = properties.subscription_identifiers.clone();
+            message.content_type = properties.content_type.clone();
+        }
+        message
+    }
+
+    // Rebuild the packet for redelivery, the DUP flag is always set because the
+    // client may already have received the original.
+    pub fn to_publish(&self) -> (Publish, Option<PublishProperties>) {
+        let publish = Publish {
+            dup: true,
+            qos: self.qos,
+            pkid: self.pkid,
+            retain: self.retain,
+            topic: self.topic.clone(),
+            payload: self.payload.clone(),
+        };
+
+        // Topic aliases are scoped to a network connection, so they are never restored
+        let properties = if self.contain_properties {
+            Some(PublishProperties {
+                payload_format_indicator: self.format_indicator,
+                message_expiry_interval: self.message_expiry_interval,
+                topic_alias: None,
+                response_topic: self.response_topic.clone(),
+                correlation_data: self.correlation_data.clone(),
+                user_properties: self.user_properties.clone(),
+                subscription_identifiers: self.subscription_identifiers.clone(),
+                content_type: self.content_type.clone(),
+            })
+        } else {
+            None
+        };
+        (publish, properties)
+    }
+
+    pub fn encode(&self) -> String {
+        serde_json::to_string(&self).unwrap()
+    }
+
+    pub fn decode(data: &str) -> Result<InflightMessage, CommonError> {
+        match serde_json::from_str::<InflightMessage>(data) {
+            Ok(message) => Ok(message),
+            Err(e) => Err(CommonError::CommonError(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use protocol::mqtt::common::{Publish, PublishProperties, QoS};
+
+    use super::{InflightMessage, InflightState};
+
+    #[test]
+    fn inflight_message_round_trip_test() {
+        let publish = Publish {
+            dup: false,
+            qos: QoS::ExactlyOnce,
+            pkid: 12,
+            retain: true,
+            topic: Bytes::from("sensor/1"),
+            payload: Bytes::from("hello"),
+        };
+        let properties = Some(PublishProperties {
+            payload_format_indicator: Some(1),
+            message_expiry_interval: Some(60),
+            topic_alias: Some(3),
+            response_topic: Some("reply/1".to_string()),
+            correlation_data: Some(Bytes::from("c1")),
+            user_properties: vec![("k".to_string(), "v".to_string())],
+            subscription_identifiers: vec![7],
+            content_type: Some("text/plain".to_string()),
+        });
+
+        let message = InflightMessage::build("c1", &publish, &properties, InflightState::Publish);
+        let decoded = InflightMessage::decode(&message.encode()).unwrap();
+        assert_eq!(decoded, message);
+
+        let (resend, resend_properties) = decoded.to_publish();
+        assert!(resend.dup);
+        assert_eq!(resend.pkid, 12);
+        assert_eq!(resend.qos, QoS::ExactlyOnce);
+        assert!(resend.retain);
+        assert_eq!(resend.topic, publish.topic);
+        assert_eq!(resend.payload, publish.payload);
+
+        let resend_properties = resend_properties.unwrap();
+        assert_eq!(resend_properties.topic_alias, None);
+        assert_eq!(resend_properties.message_expiry_interval, Some(60));
+        assert_eq!(resend_properties.correlation_data, Some(Bytes::from("c1")));
+        assert_eq!(resend_properties.subscription_identifiers, vec![7]);
+    }
+
+    #[test]
+    fn inflight_message_without_properties_test() {
+        let publish = Publish {
+            dup: false,
+            qos: QoS::AtLeastOnce,
+            pkid: 1,
+            retain: false,
+            topic: Bytes::from("t"),
+            payload: Bytes::from("p"),
+        };
+        let message = InflightMessage::build("c1", &publish, &None, InflightState::Publish);
+        let (_, properties) = message.to_publish();
+        assert!(properties.is_none());
+    }
+}
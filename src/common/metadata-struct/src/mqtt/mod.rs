@@ -15,11 +15,16 @@
 pub mod auto_subscribe_rule;
 pub mod bridge;
 pub mod connection;
+pub mod inflight;
 pub mod lastwill;
 pub mod message;
 pub mod node_extend;
+pub mod offline_queue;
+pub mod route;
+pub mod rule;
 pub mod session;
 pub mod subscribe_data;
+pub mod tenant;
 pub mod topic;
 pub mod topic_rewrite_rule;
 pub mod user;
@@ -0,0 +1,110 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::{HashMap, VecDeque};
+
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MqttOfflineQueuedMessage {
+    pub topic_id: String,
+    pub offset: u64,
+    pub size: u64,
+}
+
+// Offsets [start, end) of a topic dropped from the queue of a session in one offline period
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MqttOfflineDroppedRange {
+    pub start: u64,
+    pub end: u64,
+    pub period: u64,
+}
+
+// The offline queue of a persistent session, kept by the broker the session went offline on.
+// Messages are stored once per topic, so the queue only indexes the offsets the session has
+// not received yet and the offsets dropped from it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MqttOfflineQueue {
+    pub client_id: String,
+    pub broker_id: u64,
+    // 0 while the session is connected
+    pub offline_since: u64,
+    // Number of the current offline period
+    pub period: u64,
+    // Last offline period of the session, QoS 0 messages created in it are not pushed
+    pub qos0_drop_window: Option<(u64, u64)>,
+    pub messages: VecDeque<MqttOfflineQueuedMessage>,
+    pub bytes: u64,
+    // (subscribe path, (topic_id, dropped ranges))
+    pub dropped: HashMap<String, HashMap<String, Vec<MqttOfflineDroppedRange>>>,
+    pub overflowed: bool,
+}
+
+impl MqttOfflineQueue {
+    pub fn encode(&self) -> String {
+        serde_json::to_string(&self).unwrap()
+    }
+
+    pub fn decode(data: &str) -> Result<MqttOfflineQueue, CommonError> {
+        match serde_json::from_str::<MqttOfflineQueue>(data) {
+            Ok(queue) => Ok(queue),
+            Err(e) => Err(CommonError::CommonError(e.to_string())),
+        }
+    }
+
+    // Nothing is left to skip or count for the session
+    pub fn is_empty(&self) -> bool {
+        self.offline_since == 0 && self.qos0_drop_window.is_none() && self.dropped.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{MqttOfflineDroppedRange, MqttOfflineQueue, MqttOfflineQueuedMessage};
+
+    #[test]
+    fn offline_queue_encode_decode_test() {
+        let queue = MqttOfflineQueue {
+            client_id: "c1".to_string(),
+            broker_id: 1,
+            offline_since: 100,
+            period: 1,
+            qos0_drop_window: Some((10, 20)),
+            messages: [MqttOfflineQueuedMessage {
+                topic_id: "t1".to_string(),
+                offset: 3,
+                size: 10,
+            }]
+            .into(),
+            bytes: 10,
+            dropped: HashMap::from([(
+                "a/#".to_string(),
+                HashMap::from([(
+                    "t1".to_string(),
+                    vec![MqttOfflineDroppedRange {
+                        start: 1,
+                        end: 3,
+                        period: 1,
+                    }],
+                )]),
+            )]),
+            overflowed: true,
+        };
+        assert_eq!(MqttOfflineQueue::decode(&queue.encode()).unwrap(), queue);
+        assert!(!queue.is_empty());
+        assert!(MqttOfflineQueue::default().is_empty());
+    }
+}
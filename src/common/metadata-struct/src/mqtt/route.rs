@@ -0,0 +1,92 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+
+use crate::adapter::record::Record;
+
+// The topics a broker currently has subscribers for, published by every broker so that
+// the others know where to forward new messages of a topic.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MqttBrokerRoute {
+    pub broker_id: u64,
+    pub topics: Vec<String>,
+    pub update_time: u64,
+}
+
+impl MqttBrokerRoute {
+    pub fn encode(&self) -> String {
+        serde_json::to_string(&self).unwrap()
+    }
+
+    pub fn decode(data: &str) -> Result<MqttBrokerRoute, CommonError> {
+        match serde_json::from_str::<MqttBrokerRoute>(data) {
+            Ok(route) => Ok(route),
+            Err(e) => Err(CommonError::CommonError(e.to_string())),
+        }
+    }
+}
+
+// A message forwarded to another broker right after it was persisted. The record keeps
+// the offset it was stored at, so the receiver can tell it apart from what it reads itself.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MqttForwardRecord {
+    pub topic_name: String,
+    pub record: Record,
+}
+
+impl MqttForwardRecord {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<MqttForwardRecord, CommonError> {
+        match serde_json::from_slice::<MqttForwardRecord>(data) {
+            Ok(record) => Ok(record),
+            Err(e) => Err(CommonError::CommonError(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MqttBrokerRoute, MqttForwardRecord};
+    use crate::adapter::record::Record;
+
+    #[test]
+    fn route_encode_decode_test() {
+        let route = MqttBrokerRoute {
+            broker_id: 2,
+            topics: vec!["t1".to_string(), "t2".to_string()],
+            update_time: 100,
+        };
+        let decoded = MqttBrokerRoute::decode(&route.encode()).unwrap();
+        assert_eq!(decoded, route);
+    }
+
+    #[test]
+    fn forward_record_encode_decode_test() {
+        let mut record = Record::build_byte(b"hello".to_vec());
+        record.offset = Some(7);
+        let forward = MqttForwardRecord {
+            topic_name: "t1".to_string(),
+            record,
+        };
+        let decoded = MqttForwardRecord::decode(&forward.encode()).unwrap();
+        assert_eq!(decoded.topic_name, "t1");
+        assert_eq!(decoded.record.offset, Some(7));
+        assert_eq!(decoded.record.data, b"hello".to_vec());
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use serde::{Deserialize, Serialize};
+
+/// A rule selects fields from messages published on the topics in its FROM clause,
+/// and runs its actions on every message that satisfies the WHERE condition.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct MqttRule {
+    pub rule_name: String,
+    pub sql: String,
+    pub actions: Vec<MqttRuleAction>,
+    pub enable: bool,
+    pub desc: String,
+    pub create_time: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MqttRuleAction {
+    // Publish the selected fields as a JSON payload, the topic may contain ${field} placeholders
+    Republish {
+        topic: String,
+        #[serde(default)]
+        qos: u8,
+        #[serde(default)]
+        retain: bool,
+    },
+    // Hand the selected fields over to a connector, next to the records of the topic it reads
+    Connector {
+        connector_name: String,
+    },
+    // Discard the original message, it is neither stored nor delivered to subscribers
+    Drop,
+}
+
+impl MqttRule {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MqttRuleAction;
+
+    #[test]
+    fn rule_action_serde_test() {
+        let actions: Vec<MqttRuleAction> = serde_json::from_str(
+            r#"[{"type":"republish","topic":"alerts/${clientid}","qos":1},{"type":"connector","connector_name":"c1"},{"type":"drop"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                MqttRuleAction::Republish {
+                    topic: "alerts/${clientid}".to_string(),
+                    qos: 1,
+                    retain: false
+                },
+                MqttRuleAction::Connector {
+                    connector_name: "c1".to_string()
+                },
+                MqttRuleAction::Drop
+            ]
+        );
+    }
+}
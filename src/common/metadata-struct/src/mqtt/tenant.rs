@@ -0,0 +1,77 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::HashMap;
+
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+
+/// A tenant groups users and their clients into an isolated topic namespace.
+/// A quota of 0 means the dimension is not limited.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct MqttTenant {
+    pub tenant_name: String,
+    pub desc: String,
+    pub max_connections: u64,
+    pub max_subscriptions: u64,
+    pub max_messages_per_second: u64,
+    pub max_storage_bytes_per_second: u64,
+    pub create_time: u64,
+}
+
+impl MqttTenant {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// The connections a broker holds for every tenant, published by every broker so that
+// tenant connection quotas are enforced over the whole cluster.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MqttTenantUsage {
+    pub broker_id: u64,
+    // (tenant_name, connection_num)
+    pub connections: HashMap<String, u64>,
+    pub update_time: u64,
+}
+
+impl MqttTenantUsage {
+    pub fn encode(&self) -> String {
+        serde_json::to_string(&self).unwrap()
+    }
+
+    pub fn decode(data: &str) -> Result<MqttTenantUsage, CommonError> {
+        match serde_json::from_str::<MqttTenantUsage>(data) {
+            Ok(usage) => Ok(usage),
+            Err(e) => Err(CommonError::CommonError(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::MqttTenantUsage;
+
+    #[test]
+    fn tenant_usage_encode_decode_test() {
+        let usage = MqttTenantUsage {
+            broker_id: 2,
+            connections: HashMap::from([("acme".to_string(), 3)]),
+            update_time: 100,
+        };
+        let decoded = MqttTenantUsage::decode(&usage.encode()).unwrap();
+        assert_eq!(decoded, usage);
+    }
+}
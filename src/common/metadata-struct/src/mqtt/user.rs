@@ -19,6 +19,9 @@ pub struct MqttUser {
     pub username: String,
     pub password: String,
     pub is_superuser: bool,
+    // Tenant the user belongs to, empty means the default tenant
+    #[serde(default)]
+    pub tenant: String,
 }
 
 impl MqttUser {
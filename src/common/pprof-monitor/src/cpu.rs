@@ -0,0 +1,88 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use pprof::protos::Message;
+use pprof::ProfilerGuardBuilder;
+
+// The profiler samples the whole process, only one profile can be taken at a time
+static PROFILING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum CpuProfileFormat {
+    Pprof,
+    Flamegraph,
+}
+
+struct RunningProfile;
+
+impl RunningProfile {
+    fn acquire() -> Option<Self> {
+        PROFILING
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| RunningProfile)
+    }
+}
+
+impl Drop for RunningProfile {
+    fn drop(&mut self) {
+        PROFILING.store(false, Ordering::Release);
+    }
+}
+
+/// Samples the CPU usage of the process for `seconds` at `frequency` Hz.
+pub(crate) async fn cpu_profile(
+    seconds: u64,
+    frequency: i32,
+    format: CpuProfileFormat,
+) -> Result<Vec<u8>, (StatusCode, String)> {
+    let running = RunningProfile::acquire().ok_or((
+        StatusCode::CONFLICT,
+        "Another CPU profile is being taken".to_string(),
+    ))?;
+
+    tokio::task::spawn_blocking(move || {
+        let _running = running;
+        let guard = ProfilerGuardBuilder::default()
+            .frequency(frequency)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .map_err(internal_error)?;
+        thread::sleep(Duration::from_secs(seconds));
+        let report = guard.report().build().map_err(internal_error)?;
+
+        let mut body = Vec::new();
+        match format {
+            CpuProfileFormat::Pprof => {
+                let profile = report.pprof().map_err(internal_error)?;
+                profile.encode(&mut body).map_err(internal_error)?;
+            }
+            CpuProfileFormat::Flamegraph => {
+                report.flamegraph(&mut body).map_err(internal_error)?;
+            }
+        }
+        Ok(body)
+    })
+    .await
+    .map_err(internal_error)?
+}
+
+fn internal_error(e: impl ToString) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
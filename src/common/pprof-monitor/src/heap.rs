@@ -0,0 +1,106 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "jemalloc")]
+pub(crate) use jemalloc::{dump_heap_profile, memory_stats};
+#[cfg(not(feature = "jemalloc"))]
+pub(crate) use unsupported::{dump_heap_profile, memory_stats};
+
+#[cfg(not(feature = "jemalloc"))]
+mod unsupported {
+    use axum::http::StatusCode;
+    use serde_json::Value;
+
+    pub(crate) fn dump_heap_profile() -> Result<Vec<u8>, (StatusCode, String)> {
+        Err(not_built_with_jemalloc())
+    }
+
+    pub(crate) fn memory_stats() -> Result<Value, (StatusCode, String)> {
+        Err(not_built_with_jemalloc())
+    }
+
+    fn not_built_with_jemalloc() -> (StatusCode, String) {
+        (
+            StatusCode::NOT_IMPLEMENTED,
+            "Heap profiles require a build with the jemalloc feature".to_string(),
+        )
+    }
+}
+
+#[cfg(feature = "jemalloc")]
+mod jemalloc {
+    use std::ffi::{c_char, CString};
+    use std::fs;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use tikv_jemalloc_ctl::{epoch, raw, stats};
+    use tikv_jemallocator::Jemalloc;
+
+    #[global_allocator]
+    static GLOBAL: Jemalloc = Jemalloc;
+
+    #[repr(transparent)]
+    pub struct MallocConf(*const c_char);
+
+    unsafe impl Sync for MallocConf {}
+
+    // Sample an allocation about every 512KiB from the start, so a heap profile can be
+    // dumped at any time at a small cost
+    #[export_name = "_rjem_malloc_conf"]
+    pub static MALLOC_CONF: MallocConf =
+        MallocConf(c"prof:true,prof_active:true,lg_prof_sample:19".as_ptr());
+
+    static DUMP_ID: AtomicU64 = AtomicU64::new(0);
+
+    pub(crate) fn dump_heap_profile() -> Result<Vec<u8>, (StatusCode, String)> {
+        // Fails as well when jemalloc was built without profiling support
+        let active = unsafe { raw::read::<bool>(b"prof.active\0") }.unwrap_or(false);
+        if !active {
+            return Err((
+                StatusCode::NOT_IMPLEMENTED,
+                "jemalloc heap profiling is not active".to_string(),
+            ));
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "robustmq-heap-{}-{}.prof",
+            std::process::id(),
+            DUMP_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let c_path = CString::new(path.to_string_lossy().as_bytes()).map_err(internal_error)?;
+        unsafe { raw::write(b"prof.dump\0", c_path.as_ptr()) }.map_err(internal_error)?;
+        let body = fs::read(&path).map_err(internal_error);
+        let _ = fs::remove_file(&path);
+        body
+    }
+
+    pub(crate) fn memory_stats() -> Result<Value, (StatusCode, String)> {
+        // The statistics are cached by jemalloc until the epoch is advanced
+        epoch::advance().map_err(internal_error)?;
+        Ok(json!({
+            "allocated": stats::allocated::read().map_err(internal_error)?,
+            "active": stats::active::read().map_err(internal_error)?,
+            "resident": stats::resident::read().map_err(internal_error)?,
+            "mapped": stats::mapped::read().map_err(internal_error)?,
+            "retained": stats::retained::read().map_err(internal_error)?,
+            "metadata": stats::metadata::read().map_err(internal_error)?,
+        }))
+    }
+
+    fn internal_error(e: impl ToString) -> (StatusCode, String) {
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
@@ -12,4 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod cpu;
+mod heap;
 pub mod pprof_monitor;
+mod tasks;
@@ -12,58 +12,243 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use axum::http::header;
-use axum::response::Response;
-use axum::{routing::get, Router};
-use bytes::Bytes;
-use pprof::ProfilerGuard;
-use std::{net::SocketAddr, sync::Arc};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::{routing::get, Json, Router};
+use common_config::common::Pprof;
+use serde::Deserialize;
+use tokio::runtime::Handle;
 use tracing::{error, info};
 
-pub async fn start_pprof_monitor(port: u16, frequency: i32) -> Arc<ProfilerGuard<'static>> {
+use crate::cpu::{cpu_profile, CpuProfileFormat};
+use crate::heap::{dump_heap_profile, memory_stats};
+use crate::tasks::dump_tasks;
+
+const DEFAULT_PROFILE_SECONDS: u64 = 30;
+const MAX_PROFILE_FREQUENCY: i32 = 1000;
+
+#[derive(Clone)]
+struct MonitorState {
+    token: String,
+    frequency: i32,
+    max_seconds: u64,
+    runtimes: Arc<Vec<(String, Handle)>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProfileParams {
+    seconds: Option<u64>,
+    frequency: Option<i32>,
+}
+
+/// Serves profiles of the process on demand:
+/// - `/debug/pprof/profile`: CPU profile in the pprof protobuf format
+/// - `/debug/pprof/flamegraph`: CPU profile as a flamegraph SVG
+/// - `/debug/pprof/heap`: jemalloc heap profile, readable with jeprof
+/// - `/debug/pprof/memstats`: jemalloc memory statistics
+/// - `/debug/pprof/tasks`: dump of the tasks of the given tokio runtimes
+///
+/// When a token is configured, requests must carry it as `Authorization: Bearer <token>`.
+pub async fn start_pprof_monitor(conf: Pprof, runtimes: Vec<(String, Handle)>) {
     info!("Starting pprof HTTP server...");
-    let guard = Arc::new(ProfilerGuard::new(frequency).expect("Failed to start ProfilerGuard"));
-    let guard_clone = Arc::clone(&guard);
+    let state = MonitorState {
+        token: conf.token.clone(),
+        frequency: conf.frequency,
+        max_seconds: conf.max_seconds.max(1),
+        runtimes: Arc::new(runtimes),
+    };
 
-    let app = Router::new().route(
-        "/flamegraph",
-        get(move || generate_flamegraph(guard_clone.clone())),
-    );
+    let app = Router::new()
+        .route("/debug/pprof/profile", get(profile))
+        .route("/debug/pprof/flamegraph", get(flamegraph))
+        .route("/flamegraph", get(flamegraph))
+        .route("/debug/pprof/heap", get(heap))
+        .route("/debug/pprof/memstats", get(memstats))
+        .route("/debug/pprof/tasks", get(tasks))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state);
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let addr = match conf.bind.parse() {
+        Ok(ip) => SocketAddr::new(ip, conf.port),
+        Err(e) => {
+            error!("Invalid pprof bind address {}: {}", conf.bind, e);
+            return;
+        }
+    };
     let listener = match tokio::net::TcpListener::bind(addr).await {
         Ok(listener) => {
             info!(
-                "Pprof HTTP Server started successfully, listening port: {}",
-                port
+                "Pprof HTTP Server started successfully, listening address: {}",
+                addr
             );
             listener
         }
         Err(e) => {
             error!("Failed to bind pprof server: {}", e);
-            return guard;
+            return;
         }
     };
     if let Err(e) = axum::serve(listener, app).await {
         error!("pprof HTTP server failed: {}", e);
     }
-    guard
 }
 
-async fn generate_flamegraph(guard: Arc<ProfilerGuard<'static>>) -> Response {
-    if let Ok(report) = guard.report().build() {
-        let mut buf = Vec::new();
-        if report.flamegraph(&mut buf).is_ok() {
-            return Response::builder()
-                .header(header::CONTENT_TYPE, "image/svg+xml")
-                .body(axum::body::Body::from(buf))
-                .unwrap();
+async fn authorize(State(state): State<MonitorState>, request: Request, next: Next) -> Response {
+    if is_authorized(&state.token, request.headers()) {
+        return next.run(request).await;
+    }
+    (StatusCode::UNAUTHORIZED, "A valid pprof token is required").into_response()
+}
+
+fn is_authorized(token: &str, headers: &HeaderMap) -> bool {
+    if token.is_empty() {
+        return true;
+    }
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.as_bytes().strip_prefix(b"Bearer "))
+        .is_some_and(|bearer| constant_time_eq(bearer, token.as_bytes()))
+}
+
+/// Compares two byte strings in a time that does not depend on where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+impl MonitorState {
+    /// The duration and sampling frequency of a CPU profile, bounded by the configuration.
+    fn profile_options(&self, params: &ProfileParams) -> (u64, i32) {
+        let seconds = params
+            .seconds
+            .unwrap_or(DEFAULT_PROFILE_SECONDS)
+            .clamp(1, self.max_seconds);
+        let frequency = params
+            .frequency
+            .unwrap_or(self.frequency)
+            .clamp(1, MAX_PROFILE_FREQUENCY);
+        (seconds, frequency)
+    }
+}
+
+async fn profile(
+    State(state): State<MonitorState>,
+    Query(params): Query<ProfileParams>,
+) -> Response {
+    let (seconds, frequency) = state.profile_options(&params);
+    match cpu_profile(seconds, frequency, CpuProfileFormat::Pprof).await {
+        Ok(body) => (
+            [
+                (header::CONTENT_TYPE, "application/octet-stream"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"profile.pb\"",
+                ),
+            ],
+            body,
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn flamegraph(
+    State(state): State<MonitorState>,
+    Query(params): Query<ProfileParams>,
+) -> Response {
+    let (seconds, frequency) = state.profile_options(&params);
+    match cpu_profile(seconds, frequency, CpuProfileFormat::Flamegraph).await {
+        Ok(body) => ([(header::CONTENT_TYPE, "image/svg+xml")], body).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn heap() -> Response {
+    match tokio::task::spawn_blocking(dump_heap_profile).await {
+        Ok(Ok(body)) => (
+            [
+                (header::CONTENT_TYPE, "application/octet-stream"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"heap.prof\"",
+                ),
+            ],
+            body,
+        )
+            .into_response(),
+        Ok(Err(e)) => e.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn memstats() -> Response {
+    match memory_stats() {
+        Ok(stats) => Json(stats).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn tasks(State(state): State<MonitorState>) -> Response {
+    let max_wait = Duration::from_secs(state.max_seconds);
+    dump_tasks(&state.runtimes, max_wait).await.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn state() -> MonitorState {
+        MonitorState {
+            token: "secret".to_string(),
+            frequency: 100,
+            max_seconds: 60,
+            runtimes: Arc::new(Vec::new()),
         }
     }
-    Response::builder()
-        .status(500)
-        .body(axum::body::Body::from(Bytes::from(
-            "Failed to generate flamegraph",
-        )))
-        .unwrap()
+
+    #[test]
+    fn test_is_authorized() {
+        let mut headers = HeaderMap::new();
+        assert!(is_authorized("", &headers));
+        assert!(!is_authorized("secret", &headers));
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret"),
+        );
+        assert!(is_authorized("secret", &headers));
+        for value in ["Bearer other", "Bearer secrets", "Bearer ", "secret"] {
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_static(value));
+            assert!(!is_authorized("secret", &headers));
+        }
+    }
+
+    #[test]
+    fn test_profile_options() {
+        let state = state();
+        assert_eq!(
+            state.profile_options(&ProfileParams::default()),
+            (DEFAULT_PROFILE_SECONDS, 100)
+        );
+        let params = ProfileParams {
+            seconds: Some(600),
+            frequency: Some(5000),
+        };
+        assert_eq!(state.profile_options(&params), (60, MAX_PROFILE_FREQUENCY));
+        let params = ProfileParams {
+            seconds: Some(0),
+            frequency: Some(0),
+        };
+        assert_eq!(state.profile_options(&params), (1, 1));
+    }
 }
@@ -0,0 +1,73 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use tokio::runtime::Handle;
+
+/// Dumps the backtrace of every task of the runtimes. Needs tokio task dump support, which is
+/// built with `RUSTFLAGS="--cfg tokio_unstable --cfg tokio_taskdump"` on Linux.
+#[cfg(all(
+    tokio_unstable,
+    tokio_taskdump,
+    target_os = "linux",
+    any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64")
+))]
+pub(crate) async fn dump_tasks(runtimes: &[(String, Handle)], max_wait: Duration) -> String {
+    let mut output = String::new();
+    for (name, handle) in runtimes {
+        // Awaited in place: a dump spawned onto the runtime itself never completes.
+        match tokio::time::timeout(max_wait, handle.dump()).await {
+            Ok(dump) => {
+                let tasks: Vec<_> = dump.tasks().iter().collect();
+                let _ = writeln!(output, "runtime {}: {} tasks", name, tasks.len());
+                for (index, task) in tasks.into_iter().enumerate() {
+                    let _ = writeln!(output, "\ntask {}:\n{}", index, task.trace());
+                }
+            }
+            Err(_) => {
+                let _ = writeln!(output, "runtime {}: task dump timed out", name);
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Without task dump support only the task counts of the runtimes are available.
+#[cfg(not(all(
+    tokio_unstable,
+    tokio_taskdump,
+    target_os = "linux",
+    any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64")
+)))]
+pub(crate) async fn dump_tasks(runtimes: &[(String, Handle)], _max_wait: Duration) -> String {
+    let mut output = String::new();
+    for (name, handle) in runtimes {
+        let metrics = handle.metrics();
+        let _ = writeln!(
+            output,
+            "runtime {}: workers={} alive_tasks={} global_queue_depth={}",
+            name,
+            metrics.num_workers(),
+            metrics.num_alive_tasks(),
+            metrics.global_queue_depth()
+        );
+    }
+    output.push_str(
+        "\nTask backtraces require a build with RUSTFLAGS=\"--cfg tokio_unstable --cfg tokio_taskdump\" on Linux.\n",
+    );
+    output
+}
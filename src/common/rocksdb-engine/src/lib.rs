@@ -200,6 +200,21 @@ pub fn exist(&self, cf: Arc<BoundColumnFamily>, key: &str) -> bool {
         self.db.key_may_exist_cf(&cf, key)
     }
 
+    /// Whether any key starts with `prefix`, only the first key at or after it is read.
+    pub fn exist_prefix(
+        &self,
+        cf: Arc<BoundColumnFamily>,
+        prefix: &str,
+    ) -> Result<bool, CommonError> {
+        let mut iter = self.db.raw_iterator_cf(&cf);
+        iter.seek(prefix);
+        if let Some(key) = iter.key() {
+            return Ok(key.starts_with(prefix.as_bytes()));
+        }
+        iter.status()?;
+        Ok(false)
+    }
+
     pub fn cf_handle(&self, name: &str) -> Option<Arc<BoundColumnFamily>> {
         if let Some(cf) = self.db.cf_handle(name) {
             return Some(cf);
@@ -398,5 +413,9 @@ async fn read_prefix() {
 
         let result = rs.read_prefix(cf.clone(), "/v4").unwrap();
         assert_eq!(result.len(), 1);
+
+        assert!(rs.exist_prefix(cf.clone(), "/v2/tmp_test").unwrap());
+        assert!(!rs.exist_prefix(cf.clone(), "/v0").unwrap());
+        assert!(!rs.exist_prefix(cf.clone(), "/v5").unwrap());
     }
 }
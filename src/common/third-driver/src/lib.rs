@@ -14,3 +14,4 @@
 
 #![allow(clippy::result_large_err)]
 pub mod mysql;
+pub mod postgres;
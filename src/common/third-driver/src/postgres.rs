@@ -0,0 +1,28 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use common_base::error::common::CommonError;
+use tokio_postgres::{Client, NoTls};
+
+// The connection drives the socket in the background and ends when the client is dropped
+pub async fn build_postgres_client(addr: &str) -> Result<Client, CommonError> {
+    let (client, connection) = match tokio_postgres::connect(addr, NoTls).await {
+        Ok(data) => data,
+        Err(e) => return Err(CommonError::CommonError(e.to_string())),
+    };
+
+    tokio::spawn(async move {
+        let _ = connection.await;
+    });
+    Ok(client)
+}
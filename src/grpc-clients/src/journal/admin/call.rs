@@ -14,7 +14,8 @@
 
 use common_base::error::common::CommonError;
 use protocol::journal_server::journal_admin::{
-    ListSegmentReply, ListSegmentRequest, ListShardReply, ListShardRequest,
+    GetLogLevelReply, GetLogLevelRequest, ListSegmentReply, ListSegmentRequest, ListShardReply,
+    ListShardRequest, SetLogLevelReply, SetLogLevelRequest,
 };
 
 use crate::pool::ClientPool;
@@ -44,3 +45,17 @@ pub async fn $fn_name(
     ListSegmentReply,
     ListSegment
 );
+
+generate_journal_admin_service_call!(
+    journal_admin_set_log_level,
+    SetLogLevelRequest,
+    SetLogLevelReply,
+    SetLogLevel
+);
+
+generate_journal_admin_service_call!(
+    journal_admin_get_log_level,
+    GetLogLevelRequest,
+    GetLogLevelReply,
+    GetLogLevel
+);
@@ -16,7 +16,8 @@
 use mobc::Manager;
 use protocol::journal_server::journal_admin::journal_server_admin_service_client::JournalServerAdminServiceClient;
 use protocol::journal_server::journal_admin::{
-    ListSegmentReply, ListSegmentRequest, ListShardReply, ListShardRequest,
+    GetLogLevelReply, GetLogLevelRequest, ListSegmentReply, ListSegmentRequest, ListShardReply,
+    ListShardRequest, SetLogLevelReply, SetLogLevelRequest,
 };
 use tonic::transport::Channel;
 
@@ -76,3 +77,19 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     journal_admin_services_client,
     list_segment
 );
+
+impl_retriable_request!(
+    SetLogLevelRequest,
+    JournalServerAdminServiceClient<Channel>,
+    SetLogLevelReply,
+    journal_admin_services_client,
+    set_log_level
+);
+
+impl_retriable_request!(
+    GetLogLevelRequest,
+    JournalServerAdminServiceClient<Channel>,
+    GetLogLevelReply,
+    journal_admin_services_client,
+    get_log_level
+);
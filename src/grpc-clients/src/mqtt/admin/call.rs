@@ -14,27 +14,35 @@
 
 use common_base::error::common::CommonError;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterOverviewMetricsReply, ClusterOverviewMetricsRequest, ClusterStatusReply,
-    ClusterStatusRequest, CreateAclReply, CreateAclRequest, CreateBlacklistReply,
-    CreateBlacklistRequest, CreateTopicRewriteRuleReply, CreateTopicRewriteRuleRequest,
-    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest,
-    DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest, DeleteBlacklistReply,
-    DeleteBlacklistRequest, DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest,
-    DeleteUserReply, DeleteUserRequest, EnableFlappingDetectReply, EnableFlappingDetectRequest,
-    GetClusterConfigReply, GetClusterConfigRequest, ListAclReply, ListAclRequest,
+    ClearAlarmReply, ClearAlarmRequest, ClusterOverviewMetricsReply, ClusterOverviewMetricsRequest,
+    ClusterStatusReply, ClusterStatusRequest, CreateAclReply, CreateAclRequest,
+    CreateBlacklistReply, CreateBlacklistRequest, CreateTopicRewriteRuleReply,
+    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
+    DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
+    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteRuleReply, DeleteRuleRequest,
+    DeleteTenantReply, DeleteTenantRequest, DeleteTopicRewriteRuleReply,
+    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, EnableFlappingDetectReply,
+    EnableFlappingDetectRequest, GetClusterConfigReply, GetClusterConfigRequest, GetLogLevelReply,
+    GetLogLevelRequest, ListAclReply, ListAclRequest, ListAlarmReply, ListAlarmRequest,
     ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListBlacklistReply,
-    ListBlacklistRequest, ListConnectionReply, ListConnectionRequest, ListSessionReply,
-    ListSessionRequest, ListSlowSubscribeReply, ListSlowSubscribeRequest, ListSystemAlarmReply,
-    ListSystemAlarmRequest, ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest,
-    MqttBindSchemaReply, MqttBindSchemaRequest, MqttCreateConnectorReply,
-    MqttCreateConnectorRequest, MqttCreateSchemaReply, MqttCreateSchemaRequest,
-    MqttDeleteConnectorReply, MqttDeleteConnectorRequest, MqttDeleteSchemaReply,
-    MqttDeleteSchemaRequest, MqttListBindSchemaReply, MqttListBindSchemaRequest,
-    MqttListConnectorReply, MqttListConnectorRequest, MqttListSchemaReply, MqttListSchemaRequest,
-    MqttUnbindSchemaReply, MqttUnbindSchemaRequest, MqttUpdateConnectorReply,
-    MqttUpdateConnectorRequest, MqttUpdateSchemaReply, MqttUpdateSchemaRequest,
-    SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest, SetClusterConfigReply,
-    SetClusterConfigRequest, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    ListBlacklistRequest, ListConnectionReply, ListConnectionRequest, ListRuleReply,
+    ListRuleRequest, ListSessionReply, ListSessionRequest, ListSlowSubscribeReply,
+    ListSlowSubscribeRequest, ListSystemAlarmReply, ListSystemAlarmRequest, ListTenantReply,
+    ListTenantRequest, ListTopicMetricsReply, ListTopicMetricsRequest, ListTopicReply,
+    ListTopicRequest, ListTraceRecordReply, ListTraceRecordRequest, ListTraceReply,
+    ListTraceRequest, ListUserReply, ListUserRequest, MqttBindSchemaReply, MqttBindSchemaRequest,
+    MqttCreateConnectorReply, MqttCreateConnectorRequest, MqttCreateSchemaReply,
+    MqttCreateSchemaRequest, MqttDeleteConnectorReply, MqttDeleteConnectorRequest,
+    MqttDeleteSchemaReply, MqttDeleteSchemaRequest, MqttListBindSchemaReply,
+    MqttListBindSchemaRequest, MqttListConnectorReply, MqttListConnectorRequest,
+    MqttListSchemaReply, MqttListSchemaRequest, MqttUnbindSchemaReply, MqttUnbindSchemaRequest,
+    MqttUpdateConnectorReply, MqttUpdateConnectorRequest, MqttUpdateSchemaReply,
+    MqttUpdateSchemaRequest, SendRequestReply, SendRequestRequest, SetAutoSubscribeRuleReply,
+    SetAutoSubscribeRuleRequest, SetClusterConfigReply, SetClusterConfigRequest, SetLogLevelReply,
+    SetLogLevelRequest, SetRuleReply, SetRuleRequest, SetSharedSubscriptionStrategyReply,
+    SetSharedSubscriptionStrategyRequest, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    SetTenantReply, SetTenantRequest, StartTraceReply, StartTraceRequest, StopTraceReply,
+    StopTraceRequest,
 };
 
 use crate::pool::ClientPool;
@@ -66,6 +74,20 @@ pub async fn $fn_name(
     GetClusterConfig
 );
 
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_log_level,
+    SetLogLevelRequest,
+    SetLogLevelReply,
+    SetLogLevel
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_log_level,
+    GetLogLevelRequest,
+    GetLogLevelReply,
+    GetLogLevel
+);
+
 // ---- cluster ------
 generate_mqtt_admin_service_call!(
     mqtt_broker_cluster_status,
@@ -174,6 +196,55 @@ pub async fn $fn_name(
     ListSystemAlarm
 );
 
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_alarm,
+    ListAlarmRequest,
+    ListAlarmReply,
+    ListAlarm
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_clear_alarm,
+    ClearAlarmRequest,
+    ClearAlarmReply,
+    ClearAlarm
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_start_trace,
+    StartTraceRequest,
+    StartTraceReply,
+    StartTrace
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_stop_trace,
+    StopTraceRequest,
+    StopTraceReply,
+    StopTrace
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_trace,
+    ListTraceRequest,
+    ListTraceReply,
+    ListTrace
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_trace_record,
+    ListTraceRecordRequest,
+    ListTraceRecordReply,
+    ListTraceRecord
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_topic_metrics,
+    ListTopicMetricsRequest,
+    ListTopicMetricsReply,
+    ListTopicMetrics
+);
+
 generate_mqtt_admin_service_call!(
     mqtt_broker_list_topic,
     ListTopicRequest,
@@ -181,6 +252,13 @@ pub async fn $fn_name(
     ListTopic
 );
 
+generate_mqtt_admin_service_call!(
+    mqtt_broker_send_request,
+    SendRequestRequest,
+    SendRequestReply,
+    SendRequest
+);
+
 generate_mqtt_admin_service_call!(
     mqtt_broker_create_topic_rewrite_rule,
     CreateTopicRewriteRuleRequest,
@@ -308,3 +386,47 @@ pub async fn $fn_name(
     ClusterOverviewMetricsReply,
     ClusterOverviewMetrics
 );
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_tenant,
+    ListTenantRequest,
+    ListTenantReply,
+    ListTenant
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_tenant,
+    SetTenantRequest,
+    SetTenantReply,
+    SetTenant
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_delete_tenant,
+    DeleteTenantRequest,
+    DeleteTenantReply,
+    DeleteTenant
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_rule,
+    ListRuleRequest,
+    ListRuleReply,
+    ListRule
+);
+
+generate_mqtt_admin_service_call!(mqtt_broker_set_rule, SetRuleRequest, SetRuleReply, SetRule);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_delete_rule,
+    DeleteRuleRequest,
+    DeleteRuleReply,
+    DeleteRule
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_shared_subscription_strategy,
+    SetSharedSubscriptionStrategyRequest,
+    SetSharedSubscriptionStrategyReply,
+    SetSharedSubscriptionStrategy
+);
@@ -16,15 +16,23 @@
 use mobc::Manager;
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_client::MqttBrokerAdminServiceClient;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterOverviewMetricsReply, ClusterOverviewMetricsRequest, ClusterStatusReply,
-    ClusterStatusRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
-    GetClusterConfigReply, GetClusterConfigRequest, ListAutoSubscribeRuleReply,
-    ListAutoSubscribeRuleRequest, ListSessionReply, ListSessionRequest, ListSystemAlarmReply,
-    ListSystemAlarmRequest, MqttCreateConnectorReply, MqttCreateConnectorRequest,
-    MqttDeleteConnectorReply, MqttDeleteConnectorRequest, MqttListConnectorReply,
-    MqttListConnectorRequest, MqttUpdateConnectorReply, MqttUpdateConnectorRequest,
-    SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest, SetClusterConfigReply,
-    SetClusterConfigRequest, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    ClearAlarmReply, ClearAlarmRequest, ClusterOverviewMetricsReply, ClusterOverviewMetricsRequest,
+    ClusterStatusReply, ClusterStatusRequest, DeleteAutoSubscribeRuleReply,
+    DeleteAutoSubscribeRuleRequest, DeleteRuleReply, DeleteRuleRequest, DeleteTenantReply,
+    DeleteTenantRequest, GetClusterConfigReply, GetClusterConfigRequest, GetLogLevelReply,
+    GetLogLevelRequest, ListAlarmReply, ListAlarmRequest, ListAutoSubscribeRuleReply,
+    ListAutoSubscribeRuleRequest, ListRuleReply, ListRuleRequest, ListSessionReply,
+    ListSessionRequest, ListSystemAlarmReply, ListSystemAlarmRequest, ListTenantReply,
+    ListTenantRequest, ListTopicMetricsReply, ListTopicMetricsRequest, ListTraceRecordReply,
+    ListTraceRecordRequest, ListTraceReply, ListTraceRequest, MqttCreateConnectorReply,
+    MqttCreateConnectorRequest, MqttDeleteConnectorReply, MqttDeleteConnectorRequest,
+    MqttListConnectorReply, MqttListConnectorRequest, MqttUpdateConnectorReply,
+    MqttUpdateConnectorRequest, SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest,
+    SetClusterConfigReply, SetClusterConfigRequest, SetLogLevelReply, SetLogLevelRequest,
+    SetRuleReply, SetRuleRequest, SetSharedSubscriptionStrategyReply,
+    SetSharedSubscriptionStrategyRequest, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    SetTenantReply, SetTenantRequest, StartTraceReply, StartTraceRequest, StopTraceReply,
+    StopTraceRequest,
 };
 use protocol::broker_mqtt::broker_mqtt_admin::{
     CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest,
@@ -38,6 +46,7 @@
     MqttCreateSchemaReply, MqttCreateSchemaRequest, MqttDeleteSchemaReply, MqttDeleteSchemaRequest,
     MqttListBindSchemaReply, MqttListBindSchemaRequest, MqttListSchemaReply, MqttListSchemaRequest,
     MqttUnbindSchemaReply, MqttUnbindSchemaRequest, MqttUpdateSchemaReply, MqttUpdateSchemaRequest,
+    SendRequestReply, SendRequestRequest,
 };
 use tonic::transport::Channel;
 
@@ -92,6 +101,22 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     mqtt_broker_get_cluster_config
 );
 
+impl_retriable_request!(
+    SetLogLevelRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetLogLevelReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_log_level
+);
+
+impl_retriable_request!(
+    GetLogLevelRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetLogLevelReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_log_level
+);
+
 impl_retriable_request!(
     ClusterStatusRequest,
     MqttBrokerAdminServiceClient<Channel>,
@@ -216,6 +241,62 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     mqtt_broker_list_system_alarm
 );
 
+impl_retriable_request!(
+    ListAlarmRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListAlarmReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_alarm
+);
+
+impl_retriable_request!(
+    ClearAlarmRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ClearAlarmReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_clear_alarm
+);
+
+impl_retriable_request!(
+    StartTraceRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    StartTraceReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_start_trace
+);
+
+impl_retriable_request!(
+    StopTraceRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    StopTraceReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_stop_trace
+);
+
+impl_retriable_request!(
+    ListTraceRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListTraceReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_trace
+);
+
+impl_retriable_request!(
+    ListTraceRecordRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListTraceRecordReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_trace_record
+);
+
+impl_retriable_request!(
+    ListTopicMetricsRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListTopicMetricsReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_topic_metrics
+);
+
 impl_retriable_request!(
     ListTopicRequest,
     MqttBrokerAdminServiceClient<Channel>,
@@ -224,6 +305,14 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     mqtt_broker_list_topic
 );
 
+impl_retriable_request!(
+    SendRequestRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SendRequestReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_send_request
+);
+
 impl_retriable_request!(
     CreateTopicRewriteRuleRequest,
     MqttBrokerAdminServiceClient<Channel>,
@@ -369,3 +458,59 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     mqtt_broker_admin_services_client,
     cluster_overview_metrics
 );
+
+impl_retriable_request!(
+    ListTenantRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListTenantReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_tenant
+);
+
+impl_retriable_request!(
+    SetTenantRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetTenantReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_tenant
+);
+
+impl_retriable_request!(
+    DeleteTenantRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteTenantReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_tenant
+);
+
+impl_retriable_request!(
+    ListRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_rule
+);
+
+impl_retriable_request!(
+    SetRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_rule
+);
+
+impl_retriable_request!(
+    DeleteRuleRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteRuleReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_rule
+);
+
+impl_retriable_request!(
+    SetSharedSubscriptionStrategyRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetSharedSubscriptionStrategyReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_shared_subscription_strategy
+);
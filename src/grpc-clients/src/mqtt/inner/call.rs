@@ -14,8 +14,9 @@
 
 use common_base::error::common::CommonError;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
-    UpdateMqttCacheReply, UpdateMqttCacheRequest,
+    DeleteSessionReply, DeleteSessionRequest, ForwardMessageReply, ForwardMessageRequest,
+    SendLastWillMessageReply, SendLastWillMessageRequest, SessionTakeoverReply,
+    SessionTakeoverRequest, UpdateMqttCacheReply, UpdateMqttCacheRequest,
 };
 
 use crate::pool::ClientPool;
@@ -52,3 +53,17 @@ pub async fn $fn_name(
     SendLastWillMessageReply,
     SendLastWillMessage
 );
+
+generate_mqtt_inner_service_call!(
+    broker_mqtt_session_takeover,
+    SessionTakeoverRequest,
+    SessionTakeoverReply,
+    SessionTakeover
+);
+
+generate_mqtt_inner_service_call!(
+    broker_mqtt_forward_message,
+    ForwardMessageRequest,
+    ForwardMessageReply,
+    ForwardMessage
+);
@@ -16,8 +16,9 @@
 use mobc::Manager;
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_client::MqttBrokerInnerServiceClient;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
-    UpdateMqttCacheReply, UpdateMqttCacheRequest,
+    DeleteSessionReply, DeleteSessionRequest, ForwardMessageReply, ForwardMessageRequest,
+    SendLastWillMessageReply, SendLastWillMessageRequest, SessionTakeoverReply,
+    SessionTakeoverRequest, UpdateMqttCacheReply, UpdateMqttCacheRequest,
 };
 use tonic::transport::Channel;
 
@@ -83,3 +84,19 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     mqtt_broker_mqtt_services_client,
     send_last_will_message
 );
+
+impl_retriable_request!(
+    SessionTakeoverRequest,
+    MqttBrokerInnerServiceClient<Channel>,
+    SessionTakeoverReply,
+    mqtt_broker_mqtt_services_client,
+    session_takeover
+);
+
+impl_retriable_request!(
+    ForwardMessageRequest,
+    MqttBrokerInnerServiceClient<Channel>,
+    ForwardMessageReply,
+    mqtt_broker_mqtt_services_client,
+    forward_message
+);
@@ -14,14 +14,16 @@
 
 use common_base::error::common::CommonError;
 use protocol::placement_center::placement_center_inner::{
-    BindSchemaReply, BindSchemaRequest, ClusterStatusReply, ClusterStatusRequest,
-    CreateSchemaReply, CreateSchemaRequest, DeleteIdempotentDataReply, DeleteIdempotentDataRequest,
-    DeleteResourceConfigReply, DeleteResourceConfigRequest, DeleteSchemaReply, DeleteSchemaRequest,
-    ExistsIdempotentDataReply, ExistsIdempotentDataRequest, GetOffsetDataReply,
+    BackupMetadataReply, BackupMetadataRequest, BindSchemaReply, BindSchemaRequest,
+    ClusterStatusReply, ClusterStatusRequest, CreateSchemaReply, CreateSchemaRequest,
+    DeleteIdempotentDataReply, DeleteIdempotentDataRequest, DeleteResourceConfigReply,
+    DeleteResourceConfigRequest, DeleteSchemaReply, DeleteSchemaRequest, ExistsIdempotentDataReply,
+    ExistsIdempotentDataRequest, GetLogLevelReply, GetLogLevelRequest, GetOffsetDataReply,
     GetOffsetDataRequest, GetResourceConfigReply, GetResourceConfigRequest, HeartbeatReply,
     HeartbeatRequest, ListBindSchemaReply, ListBindSchemaRequest, ListSchemaReply,
     ListSchemaRequest, NodeListReply, NodeListRequest, RegisterNodeReply, RegisterNodeRequest,
-    SaveOffsetDataReply, SaveOffsetDataRequest, SetIdempotentDataReply, SetIdempotentDataRequest,
+    RestoreMetadataReply, RestoreMetadataRequest, SaveOffsetDataReply, SaveOffsetDataRequest,
+    SetIdempotentDataReply, SetIdempotentDataRequest, SetLogLevelReply, SetLogLevelRequest,
     SetResourceConfigReply, SetResourceConfigRequest, UnBindSchemaReply, UnBindSchemaRequest,
     UnRegisterNodeReply, UnRegisterNodeRequest, UpdateSchemaReply, UpdateSchemaRequest,
 };
@@ -46,6 +48,30 @@ pub async fn $fn_name(
     ClusterStatusReply,
     ClusterStatus
 );
+generate_placement_service_call!(
+    set_log_level,
+    SetLogLevelRequest,
+    SetLogLevelReply,
+    SetLogLevel
+);
+generate_placement_service_call!(
+    get_log_level,
+    GetLogLevelRequest,
+    GetLogLevelReply,
+    GetLogLevel
+);
+generate_placement_service_call!(
+    backup_metadata,
+    BackupMetadataRequest,
+    BackupMetadataReply,
+    BackupMetadata
+);
+generate_placement_service_call!(
+    restore_metadata,
+    RestoreMetadataRequest,
+    RestoreMetadataReply,
+    RestoreMetadata
+);
 generate_placement_service_call!(node_list, NodeListRequest, NodeListReply, ListNode);
 generate_placement_service_call!(
     register_node,
@@ -16,14 +16,16 @@
 use mobc::Manager;
 use protocol::placement_center::placement_center_inner::placement_center_service_client::PlacementCenterServiceClient;
 use protocol::placement_center::placement_center_inner::{
-    BindSchemaReply, BindSchemaRequest, ClusterStatusReply, ClusterStatusRequest,
-    CreateSchemaReply, CreateSchemaRequest, DeleteIdempotentDataReply, DeleteIdempotentDataRequest,
-    DeleteResourceConfigReply, DeleteResourceConfigRequest, DeleteSchemaReply, DeleteSchemaRequest,
-    ExistsIdempotentDataReply, ExistsIdempotentDataRequest, GetOffsetDataReply,
+    BackupMetadataReply, BackupMetadataRequest, BindSchemaReply, BindSchemaRequest,
+    ClusterStatusReply, ClusterStatusRequest, CreateSchemaReply, CreateSchemaRequest,
+    DeleteIdempotentDataReply, DeleteIdempotentDataRequest, DeleteResourceConfigReply,
+    DeleteResourceConfigRequest, DeleteSchemaReply, DeleteSchemaRequest, ExistsIdempotentDataReply,
+    ExistsIdempotentDataRequest, GetLogLevelReply, GetLogLevelRequest, GetOffsetDataReply,
     GetOffsetDataRequest, GetResourceConfigReply, GetResourceConfigRequest, HeartbeatReply,
     HeartbeatRequest, ListBindSchemaReply, ListBindSchemaRequest, ListSchemaReply,
     ListSchemaRequest, NodeListReply, NodeListRequest, RegisterNodeReply, RegisterNodeRequest,
-    SaveOffsetDataReply, SaveOffsetDataRequest, SetIdempotentDataReply, SetIdempotentDataRequest,
+    RestoreMetadataReply, RestoreMetadataRequest, SaveOffsetDataReply, SaveOffsetDataRequest,
+    SetIdempotentDataReply, SetIdempotentDataRequest, SetLogLevelReply, SetLogLevelRequest,
     SetResourceConfigReply, SetResourceConfigRequest, UnBindSchemaReply, UnBindSchemaRequest,
     UnRegisterNodeReply, UnRegisterNodeRequest, UpdateSchemaReply, UpdateSchemaRequest,
 };
@@ -77,6 +79,40 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     true
 );
 
+impl_retriable_request!(
+    SetLogLevelRequest,
+    PlacementCenterServiceClient<Channel>,
+    SetLogLevelReply,
+    placement_center_inner_services_client,
+    set_log_level
+);
+
+impl_retriable_request!(
+    GetLogLevelRequest,
+    PlacementCenterServiceClient<Channel>,
+    GetLogLevelReply,
+    placement_center_inner_services_client,
+    get_log_level
+);
+
+impl_retriable_request!(
+    BackupMetadataRequest,
+    PlacementCenterServiceClient<Channel>,
+    BackupMetadataReply,
+    placement_center_inner_services_client,
+    backup_metadata,
+    true
+);
+
+impl_retriable_request!(
+    RestoreMetadataRequest,
+    PlacementCenterServiceClient<Channel>,
+    RestoreMetadataReply,
+    placement_center_inner_services_client,
+    restore_metadata,
+    true
+);
+
 impl_retriable_request!(
     NodeListRequest,
     PlacementCenterServiceClient<Channel>,
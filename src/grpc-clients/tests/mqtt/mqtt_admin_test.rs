@@ -64,6 +64,7 @@ async fn user_test() {
             username: user_name.clone(),
             password: password.clone(),
             is_superuser: false,
+            tenant: String::new(),
         };
 
         match mqtt_broker_create_user(&client_pool, &addrs, user.clone()).await {
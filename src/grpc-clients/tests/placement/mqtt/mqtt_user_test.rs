@@ -40,6 +40,7 @@ async fn mqtt_user_test() {
             username: user_name.clone(),
             password: password.clone(),
             is_superuser: false,
+            tenant: String::new(),
         };
 
         let request: CreateUserRequest = CreateUserRequest {
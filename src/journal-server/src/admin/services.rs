@@ -17,8 +17,10 @@
 use crate::core::cache::CacheManager;
 use crate::core::error::JournalServerError;
 use crate::segment::SegmentIdentity;
+use common_base::logging::{get_log_filter, set_log_filter};
 use protocol::journal_server::journal_admin::{
-    ListSegmentReply, ListSegmentRequest, ListShardReply, ListShardRequest,
+    GetLogLevelReply, ListSegmentReply, ListSegmentRequest, ListShardReply, ListShardRequest,
+    SetLogLevelReply, SetLogLevelRequest,
 };
 
 /// List shards based on the request parameters
@@ -76,3 +78,23 @@ pub async fn list_segment_by_req(
 
     Ok(ListSegmentReply { segments })
 }
+
+/// Change the log level or filter of this node at runtime
+pub fn set_log_level_by_req(
+    request: &SetLogLevelRequest,
+) -> Result<SetLogLevelReply, JournalServerError> {
+    let info = set_log_filter(&request.filter, request.revert_after_secs)?;
+    Ok(SetLogLevelReply {
+        filter: info.directives,
+        revert_at: info.revert_at,
+    })
+}
+
+/// Get the log filter set at runtime on this node
+pub fn get_log_level_by_req() -> Result<GetLogLevelReply, JournalServerError> {
+    let info = get_log_filter();
+    Ok(GetLogLevelReply {
+        filter: info.directives,
+        revert_at: info.revert_at,
+    })
+}
@@ -0,0 +1,249 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use prometheus_client::encoding::EncodeLabelSet;
+
+use crate::segment::SegmentIdentity;
+use crate::server::connection::NetworkConnectionType;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct ShardLabel {
+    namespace: String,
+    shard_name: String,
+}
+
+impl ShardLabel {
+    fn new(namespace: &str, shard_name: &str) -> Self {
+        ShardLabel {
+            namespace: namespace.to_string(),
+            shard_name: shard_name.to_string(),
+        }
+    }
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct ShardBytesLabel {
+    namespace: String,
+    shard_name: String,
+    direction: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct SegmentLabel {
+    namespace: String,
+    shard_name: String,
+    segment: String,
+}
+
+impl SegmentLabel {
+    fn new(segment_iden: &SegmentIdentity) -> Self {
+        SegmentLabel {
+            namespace: segment_iden.namespace.clone(),
+            shard_name: segment_iden.shard_name.clone(),
+            segment: segment_iden.segment_seq.to_string(),
+        }
+    }
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct NetworkLabel {
+    network: String,
+}
+
+common_base::register_histogram_metric!(
+    JOURNAL_WRITE_MS,
+    "journal_write_ms",
+    "The duration of writing a batch of records to a shard",
+    ShardLabel,
+    0.5,
+    2.0,
+    12
+);
+
+common_base::register_histogram_metric!(
+    JOURNAL_READ_MS,
+    "journal_read_ms",
+    "The duration of reading a batch of records from a shard",
+    ShardLabel,
+    0.5,
+    2.0,
+    12
+);
+
+common_base::register_counter_metric!(
+    JOURNAL_SHARD_BYTES,
+    "journal_shard_bytes",
+    "Number of record bytes written to or read from a shard",
+    ShardBytesLabel
+);
+
+common_base::register_gauge_metric!(
+    JOURNAL_SEGMENT_NUM,
+    "journal_segment_num",
+    "Number of segments of a shard held by this node",
+    ShardLabel
+);
+
+common_base::register_gauge_metric!(
+    JOURNAL_SEGMENT_LAST_OFFSET,
+    "journal_segment_last_offset",
+    "The offset of the last record written to a segment",
+    SegmentLabel
+);
+
+common_base::register_gauge_metric!(
+    JOURNAL_INDEX_BUILD_LAG,
+    "journal_index_build_lag",
+    "Number of records written to a segment but not yet indexed",
+    SegmentLabel
+);
+
+common_base::register_gauge_metric!(
+    JOURNAL_ACTIVE_CONNECTIONS,
+    "journal_active_connections",
+    "Number of active client connections",
+    NetworkLabel
+);
+
+pub fn metrics_write_ms(namespace: &str, shard_name: &str, ms: f64) {
+    let label = ShardLabel::new(namespace, shard_name);
+    common_base::histogram_metric_observe!(JOURNAL_WRITE_MS, ms, label);
+}
+
+pub fn metrics_read_ms(namespace: &str, shard_name: &str, ms: f64) {
+    let label = ShardLabel::new(namespace, shard_name);
+    common_base::histogram_metric_observe!(JOURNAL_READ_MS, ms, label);
+}
+
+pub fn metrics_write_bytes(namespace: &str, shard_name: &str, bytes: u64) {
+    record_shard_bytes(namespace, shard_name, "write", bytes);
+}
+
+pub fn metrics_read_bytes(namespace: &str, shard_name: &str, bytes: u64) {
+    record_shard_bytes(namespace, shard_name, "read", bytes);
+}
+
+fn record_shard_bytes(namespace: &str, shard_name: &str, direction: &str, bytes: u64) {
+    let label = ShardBytesLabel {
+        namespace: namespace.to_string(),
+        shard_name: shard_name.to_string(),
+        direction: direction.to_string(),
+    };
+    JOURNAL_SHARD_BYTES
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .inc_by(bytes);
+}
+
+pub fn metrics_segment_num_incr(namespace: &str, shard_name: &str) {
+    let label = ShardLabel::new(namespace, shard_name);
+    common_base::gauge_metric_inc!(JOURNAL_SEGMENT_NUM, label);
+}
+
+pub fn metrics_segment_num_decr(namespace: &str, shard_name: &str) {
+    let label = ShardLabel::new(namespace, shard_name);
+    JOURNAL_SEGMENT_NUM
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .dec();
+}
+
+pub fn metrics_segment_last_offset(segment_iden: &SegmentIdentity, offset: u64) {
+    let label = SegmentLabel::new(segment_iden);
+    JOURNAL_SEGMENT_LAST_OFFSET
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .set(offset as i64);
+}
+
+/// Records how far index building trails the last written offset of the segment.
+pub fn metrics_index_build_lag(segment_iden: &SegmentIdentity, last_build_offset: u64) {
+    let label = SegmentLabel::new(segment_iden);
+    let mut last_offset = 0;
+    common_base::gauge_metric_get!(JOURNAL_SEGMENT_LAST_OFFSET, label, last_offset);
+    let lag = (last_offset - last_build_offset as i64).max(0);
+    JOURNAL_INDEX_BUILD_LAG
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .set(lag);
+}
+
+pub fn metrics_segment_remove(segment_iden: &SegmentIdentity) {
+    let label = SegmentLabel::new(segment_iden);
+    JOURNAL_SEGMENT_LAST_OFFSET.write().unwrap().remove(&label);
+    JOURNAL_INDEX_BUILD_LAG.write().unwrap().remove(&label);
+}
+
+pub fn metrics_connection_incr(connection_type: &NetworkConnectionType) {
+    let label = NetworkLabel {
+        network: connection_type.to_string(),
+    };
+    common_base::gauge_metric_inc!(JOURNAL_ACTIVE_CONNECTIONS, label);
+}
+
+pub fn metrics_connection_decr(connection_type: &NetworkConnectionType) {
+    let label = NetworkLabel {
+        network: connection_type.to_string(),
+    };
+    JOURNAL_ACTIVE_CONNECTIONS
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .dec();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_build_lag_test() {
+        let segment_iden = SegmentIdentity::new("n1", "lag_shard", 0);
+        let label = SegmentLabel::new(&segment_iden);
+
+        metrics_segment_last_offset(&segment_iden, 150);
+        metrics_index_build_lag(&segment_iden, 100);
+        let mut lag = 0;
+        common_base::gauge_metric_get!(JOURNAL_INDEX_BUILD_LAG, label, lag);
+        assert_eq!(lag, 50);
+
+        metrics_index_build_lag(&segment_iden, 150);
+        common_base::gauge_metric_get!(JOURNAL_INDEX_BUILD_LAG, label, lag);
+        assert_eq!(lag, 0);
+
+        metrics_segment_remove(&segment_iden);
+        assert!(JOURNAL_INDEX_BUILD_LAG
+            .read()
+            .unwrap()
+            .get(&label)
+            .is_none());
+    }
+
+    #[test]
+    fn active_connections_test() {
+        let label = NetworkLabel {
+            network: NetworkConnectionType::Tls.to_string(),
+        };
+        metrics_connection_incr(&NetworkConnectionType::Tls);
+        metrics_connection_incr(&NetworkConnectionType::Tls);
+        metrics_connection_decr(&NetworkConnectionType::Tls);
+        let mut num = 0;
+        common_base::gauge_metric_get!(JOURNAL_ACTIVE_CONNECTIONS, label, num);
+        assert_eq!(num, 1);
+    }
+}
@@ -18,6 +18,7 @@
 pub mod consts;
 pub mod error;
 pub mod log;
+pub mod metrics;
 pub mod notification;
 pub mod segment;
 pub mod segment_meta;
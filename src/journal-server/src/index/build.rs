@@ -33,6 +33,7 @@
 use crate::core::cache::CacheManager;
 use crate::core::consts::{BUILD_INDE_PER_RECORD_NUM, DB_COLUMN_FAMILY_INDEX};
 use crate::core::error::JournalServerError;
+use crate::core::metrics::metrics_index_build_lag;
 use crate::index::IndexData;
 use crate::segment::file::{open_segment_write, ReadData};
 use crate::segment::manager::SegmentFileManager;
@@ -178,6 +179,7 @@ async fn start_segment_build_index_thread(
                                 error!("Failure to save last_offset_build_index information with error message :{}",e);
                                 continue;
                             }
+                            metrics_index_build_lag(&segment_iden, last_build_offset);
 
 
                         }
@@ -29,6 +29,7 @@
 use common_config::journal::config::{journal_server_conf, JournalServerConfig};
 use grpc_clients::pool::ClientPool;
 use index::engine::{column_family_list, storage_data_fold};
+use pprof_monitor::pprof_monitor::start_pprof_monitor;
 use rocksdb_engine::RocksDBEngine;
 use segment::manager::{
     load_local_segment_cache, metadata_and_local_segment_diff_check, SegmentFileManager,
@@ -105,6 +106,8 @@ pub fn start(&self) {
 
         self.start_prometheus();
 
+        self.start_pprof_monitor();
+
         self.init_node();
 
         self.start_daemon_thread();
@@ -157,6 +160,25 @@ fn start_prometheus(&self) {
         }
     }
 
+    fn start_pprof_monitor(&self) {
+        if self.config.pprof.enable {
+            let conf = self.config.pprof.clone();
+            let runtimes = vec![
+                (
+                    "storage-engine-server-runtime".to_string(),
+                    self.server_runtime.handle().clone(),
+                ),
+                (
+                    "daemon-runtime".to_string(),
+                    self.daemon_runtime.handle().clone(),
+                ),
+            ];
+            self.daemon_runtime.spawn(async move {
+                start_pprof_monitor(conf, runtimes).await;
+            });
+        }
+    }
+
     fn start_daemon_thread(&self) {
         let client_pool = self.client_pool.clone();
         let cache_manager = self.cache_manager.clone();
@@ -26,6 +26,9 @@
 use super::SegmentIdentity;
 use crate::core::cache::CacheManager;
 use crate::core::error::JournalServerError;
+use crate::core::metrics::{
+    metrics_segment_num_decr, metrics_segment_num_incr, metrics_segment_remove,
+};
 use crate::index::engine::storage_data_fold;
 use crate::index::offset::OffsetIndexManager;
 use crate::index::time::TimestampIndexManager;
@@ -63,7 +66,11 @@ pub fn add_segment_file(&self, segment_file: SegmentFileMetadata) {
             &segment_file.shard_name,
             segment_file.segment_no,
         );
-        self.segment_files.insert(key, segment_file);
+        let namespace = segment_file.namespace.clone();
+        let shard_name = segment_file.shard_name.clone();
+        if self.segment_files.insert(key, segment_file).is_none() {
+            metrics_segment_num_incr(&namespace, &shard_name);
+        }
     }
 
     pub fn get_segment_file(&self, segment_iden: &SegmentIdentity) -> Option<SegmentFileMetadata> {
@@ -74,7 +81,10 @@ pub fn get_segment_file(&self, segment_iden: &SegmentIdentity) -> Option<Segment
     }
 
     pub fn remove_segment_file(&self, segment_iden: &SegmentIdentity) {
-        self.segment_files.remove(&segment_iden.name());
+        if self.segment_files.remove(&segment_iden.name()).is_some() {
+            metrics_segment_num_decr(&segment_iden.namespace, &segment_iden.shard_name);
+            metrics_segment_remove(segment_iden);
+        }
     }
 
     pub fn get_end_offset(&self, segment_iden: &SegmentIdentity) -> Option<i64> {
@@ -14,6 +14,7 @@
 
 use std::sync::Arc;
 
+use common_base::tools::now_mills;
 use protocol::journal_server::journal_engine::{
     ReadReqBody, ReadReqFilter, ReadReqOptions, ReadRespMessage, ReadRespSegmentMessage, ReadType,
 };
@@ -23,6 +24,7 @@
 use super::SegmentIdentity;
 use crate::core::cache::CacheManager;
 use crate::core::error::JournalServerError;
+use crate::core::metrics::{metrics_read_bytes, metrics_read_ms};
 use crate::index::offset::OffsetIndexManager;
 use crate::index::tag::TagIndexManager;
 
@@ -90,6 +92,7 @@ pub async fn read_data_req(
             }
         };
 
+        let start_ms = now_mills();
         let read_data_list = match raw.ready_type() {
             ReadType::Offset => {
                 read_by_offset(
@@ -126,8 +129,10 @@ pub async fn read_data_req(
         };
 
         let mut record_message = Vec::new();
+        let mut read_bytes = 0;
         for read_data in read_data_list {
             let record = read_data.record;
+            read_bytes += record.content.len() as u64;
             record_message.push(ReadRespMessage {
                 offset: record.offset as u64,
                 key: record.key,
@@ -138,6 +143,17 @@ pub async fn read_data_req(
         }
         shard_message.messages = record_message;
 
+        metrics_read_ms(
+            &segment_iden.namespace,
+            &segment_iden.shard_name,
+            (now_mills() - start_ms) as f64,
+        );
+        metrics_read_bytes(
+            &segment_iden.namespace,
+            &segment_iden.shard_name,
+            read_bytes,
+        );
+
         results.push(shard_message);
     }
     Ok(results)
@@ -14,13 +14,14 @@
 
 use crate::core::cache::CacheManager;
 use crate::core::error::{get_journal_server_code, JournalServerError};
+use crate::core::metrics::{metrics_segment_last_offset, metrics_write_bytes, metrics_write_ms};
 use crate::core::segment_meta::{update_meta_end_timestamp, update_meta_start_timestamp};
 use crate::core::segment_status::sealup_segment;
 use crate::index::build::try_trigger_build_index;
 use crate::segment::file::{open_segment_write, SegmentFile};
 use crate::segment::manager::SegmentFileManager;
 use crate::segment::SegmentIdentity;
-use common_base::tools::now_second;
+use common_base::tools::{now_mills, now_second};
 use grpc_clients::pool::ClientPool;
 use metadata_struct::journal::segment::SegmentStatus;
 use protocol::journal_server::journal_engine::{
@@ -82,7 +83,9 @@ pub async fn write_data_req(
         );
 
         let mut record_list = Vec::new();
+        let mut write_bytes = 0;
         for message in shard_data.messages.iter() {
+            write_bytes += message.value.len() as u64;
             // todo data validator
             let record = JournalRecord {
                 content: message.value.clone(),
@@ -99,6 +102,7 @@ pub async fn write_data_req(
             record_list.push(record);
         }
 
+        let start_ms = now_mills();
         let resp = match write_data(
             cache_manager,
             rocksdb_engine_handler,
@@ -141,6 +145,14 @@ pub async fn write_data_req(
             return Err(e);
         }
 
+        metrics_write_ms(
+            &shard_data.namespace,
+            &shard_data.shard_name,
+            (now_mills() - start_ms) as f64,
+        );
+        metrics_write_bytes(&shard_data.namespace, &shard_data.shard_name, write_bytes);
+        metrics_segment_last_offset(&segment_iden, resp.last_offset);
+
         let mut resp_message_status = Vec::new();
         for (pkid, offset) in resp.offsets {
             let status = WriteRespMessageStatus {
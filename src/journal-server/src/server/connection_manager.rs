@@ -23,6 +23,7 @@
 use tracing::{debug, error, info};
 
 use super::connection::{NetworkConnection, NetworkConnectionType};
+use crate::core::metrics::{metrics_connection_decr, metrics_connection_incr};
 
 /// a struct that manages all TCP and TLS connections
 pub struct ConnectionManager {
@@ -55,6 +56,7 @@ pub fn new() -> ConnectionManager {
 
     pub fn add_connection(&self, connection: NetworkConnection) -> u64 {
         let connection_id = connection.connection_id();
+        metrics_connection_incr(&connection.connection_type);
         self.connections.insert(connection_id, connection);
         connection_id
     }
@@ -87,6 +89,7 @@ pub async fn _close_all_connect(&self) {
     /// stop a connection, note that we first stops all the connection threads, and then close the tcp stream
     pub async fn close_connect(&self, connection_id: u64) {
         if let Some((_, connection)) = self.connections.remove(&connection_id) {
+            metrics_connection_decr(&connection.connection_type);
             connection.stop_connection().await;
         }
 
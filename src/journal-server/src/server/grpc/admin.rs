@@ -12,11 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::admin::services::{list_segment_by_req, list_shard_by_req};
+use crate::admin::services::{
+    get_log_level_by_req, list_segment_by_req, list_shard_by_req, set_log_level_by_req,
+};
 use crate::core::cache::CacheManager;
 use protocol::journal_server::journal_admin::journal_server_admin_service_server::JournalServerAdminService;
 use protocol::journal_server::journal_admin::{
-    ListSegmentReply, ListSegmentRequest, ListShardReply, ListShardRequest,
+    GetLogLevelReply, GetLogLevelRequest, ListSegmentReply, ListSegmentRequest, ListShardReply,
+    ListShardRequest, SetLogLevelReply, SetLogLevelRequest,
 };
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
@@ -54,4 +57,23 @@ async fn list_segment(
             .map_err(|e| Status::internal(e.to_string()))
             .map(Response::new)
     }
+
+    async fn set_log_level(
+        &self,
+        request: Request<SetLogLevelRequest>,
+    ) -> Result<Response<SetLogLevelReply>, Status> {
+        let request = request.into_inner();
+        set_log_level_by_req(&request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn get_log_level(
+        &self,
+        _request: Request<GetLogLevelRequest>,
+    ) -> Result<Response<GetLogLevelReply>, Status> {
+        get_log_level_by_req()
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
 }
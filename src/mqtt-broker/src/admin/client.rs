@@ -61,6 +61,7 @@ fn merge_client_info(session: MqttSession, connection: Option<MQTTConnection>) -
     ClientRaw {
         client_id: session.client_id.clone(),
         username: conn_data.login_user.clone(),
+        tenant: conn_data.tenant.clone(),
         is_online,
         source_ip: conn_data.source_ip_addr.clone(),
         connected_at: conn_data.create_time,
@@ -76,6 +77,7 @@ fn get_field_str(&self, field: &str) -> Option<String> {
         match field {
             "client_id" => Some(self.client_id.clone()),
             "username" => Some(self.username.clone()),
+            "tenant" => Some(self.tenant.clone()),
             "is_online" => Some(self.is_online.to_string()),
             "source_ip" => Some(self.source_ip.clone()),
             "connected_at" => Some(self.connected_at.to_string()),
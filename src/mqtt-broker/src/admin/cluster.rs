@@ -16,9 +16,11 @@
 use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
 use crate::handler::error::MqttBrokerError;
 use common_base::enum_type::feature_type::FeatureType;
+use common_base::logging::{get_log_filter, set_log_filter};
 use grpc_clients::pool::ClientPool;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    GetClusterConfigReply, SetClusterConfigReply, SetClusterConfigRequest,
+    GetClusterConfigReply, GetLogLevelReply, SetClusterConfigReply, SetClusterConfigRequest,
+    SetLogLevelReply, SetLogLevelRequest,
 };
 use std::str::FromStr;
 use std::sync::Arc;
@@ -75,3 +77,21 @@ pub fn get_cluster_config_by_req(
         )?,
     })
 }
+
+pub fn set_log_level_by_req(
+    request: &SetLogLevelRequest,
+) -> Result<SetLogLevelReply, MqttBrokerError> {
+    let info = set_log_filter(&request.filter, request.revert_after_secs)?;
+    Ok(SetLogLevelReply {
+        filter: info.directives,
+        revert_at: info.revert_at,
+    })
+}
+
+pub fn get_log_level_by_req() -> Result<GetLogLevelReply, MqttBrokerError> {
+    let info = get_log_filter();
+    Ok(GetLogLevelReply {
+        filter: info.directives,
+        revert_at: info.revert_at,
+    })
+}
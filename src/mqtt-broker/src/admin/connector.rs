@@ -12,14 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::bridge::source::render_source_topic;
+use crate::bridge::template::{parse_sql_template, PlaceholderStyle};
 use crate::handler::error::MqttBrokerError;
 use crate::storage::connector::ConnectorStorage;
 use common_base::tools::now_second;
 use common_config::mqtt::broker_mqtt_conf;
 use grpc_clients::placement::mqtt::call::placement_list_connector;
 use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::bridge::config_http::HttpConnectorConfig;
 use metadata_struct::mqtt::bridge::config_kafka::KafkaConnectorConfig;
 use metadata_struct::mqtt::bridge::config_local_file::LocalFileConnectorConfig;
+use metadata_struct::mqtt::bridge::config_mqtt::MqttBridgeConnectorConfig;
+use metadata_struct::mqtt::bridge::config_rdb::RdbConnectorConfig;
+use metadata_struct::mqtt::bridge::config_source::{
+    FileSourceConnectorConfig, KafkaSourceConnectorConfig, MqttSourceConnectorConfig,
+    SourcePublishConfig,
+};
 use metadata_struct::mqtt::bridge::connector::MQTTConnector;
 use metadata_struct::mqtt::bridge::connector_type::ConnectorType;
 use metadata_struct::mqtt::bridge::status::MQTTStatus;
@@ -125,7 +134,45 @@ fn connector_config_validator(
         ConnectorType::Kafka => {
             let _kafka_config: KafkaConnectorConfig = serde_json::from_str(config)?;
         }
+        ConnectorType::Mqtt => {
+            let _mqtt_config: MqttBridgeConnectorConfig = serde_json::from_str(config)?;
+        }
+        ConnectorType::Http => {
+            let _http_config: HttpConnectorConfig = serde_json::from_str(config)?;
+        }
+        ConnectorType::MySql => {
+            let rdb_config: RdbConnectorConfig = serde_json::from_str(config)?;
+            parse_sql_template(&rdb_config.sql_template, PlaceholderStyle::MySql)?;
+        }
+        ConnectorType::Postgres => {
+            let rdb_config: RdbConnectorConfig = serde_json::from_str(config)?;
+            parse_sql_template(&rdb_config.sql_template, PlaceholderStyle::Postgres)?;
+        }
+        ConnectorType::KafkaSource => {
+            let source_config: KafkaSourceConnectorConfig = serde_json::from_str(config)?;
+            source_publish_validator(&source_config.publish)?;
+        }
+        ConnectorType::MqttSource => {
+            let source_config: MqttSourceConnectorConfig = serde_json::from_str(config)?;
+            source_publish_validator(&source_config.publish)?;
+        }
+        ConnectorType::FileSource => {
+            let source_config: FileSourceConnectorConfig = serde_json::from_str(config)?;
+            source_publish_validator(&source_config.publish)?;
+        }
+    }
+    Ok(())
+}
+
+fn source_publish_validator(publish: &SourcePublishConfig) -> Result<(), MqttBrokerError> {
+    if publish.qos > 2 {
+        return Err(MqttBrokerError::CommonError(format!(
+            "Invalid source connector qos {}",
+            publish.qos
+        )));
     }
+    // Render with a sample source topic so a plain or templated topic are both checked
+    render_source_topic(&publish.topic, "source")?;
     Ok(())
 }
 
@@ -133,5 +180,12 @@ fn parse_mqtt_connector_type(connector_type: MqttConnectorType) -> ConnectorType
     match connector_type {
         MqttConnectorType::File => ConnectorType::LocalFile,
         MqttConnectorType::Kafka => ConnectorType::Kafka,
+        MqttConnectorType::Mqtt => ConnectorType::Mqtt,
+        MqttConnectorType::Http => ConnectorType::Http,
+        MqttConnectorType::MySql => ConnectorType::MySql,
+        MqttConnectorType::Postgres => ConnectorType::Postgres,
+        MqttConnectorType::KafkaSource => ConnectorType::KafkaSource,
+        MqttConnectorType::MqttSource => ConnectorType::MqttSource,
+        MqttConnectorType::FileSource => ConnectorType::FileSource,
     }
 }
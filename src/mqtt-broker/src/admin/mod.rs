@@ -19,9 +19,12 @@
 pub mod connector;
 pub mod observability;
 pub mod query;
+pub mod request_response;
+pub mod rule;
 pub mod schema;
 pub mod session;
 pub mod subscribe;
+pub mod tenant;
 pub mod topic;
 pub mod user;
 
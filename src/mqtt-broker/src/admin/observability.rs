@@ -13,16 +13,28 @@
 // limitations under the License.
 
 use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::observability::live_trace::{TraceInfo, TraceRecord, TraceTarget};
 use crate::observability::slow::sub::{read_slow_sub_record, SlowSubData};
+use crate::observability::topic_metrics::{TopicMetricsData, TopicMetricsSortBy};
+use crate::observability::warn::check::report_alarm_transition;
+use crate::observability::warn::AlarmRecord;
+use crate::storage::alarm::AlarmStorage;
 
 use common_base::utils::file_utils::get_project_root;
 use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::pool::ClientPool;
 use protocol::broker_mqtt::broker_mqtt_admin::{
+    AlarmRaw, ClearAlarmReply, ClearAlarmRequest, ListAlarmReply, ListAlarmRequest,
     ListSlowSubScribeRaw, ListSlowSubscribeReply, ListSlowSubscribeRequest, ListSystemAlarmRaw,
-    ListSystemAlarmReply, ListSystemAlarmRequest, SetSystemAlarmConfigReply,
-    SetSystemAlarmConfigRequest,
+    ListSystemAlarmReply, ListSystemAlarmRequest, ListTopicMetricsReply, ListTopicMetricsRequest,
+    ListTraceRecordReply, ListTraceRecordRequest, ListTraceReply, ListTraceRequest,
+    SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest, StartTraceReply, StartTraceRequest,
+    StopTraceReply, StopTraceRequest, TopicMetricsRaw, TraceRaw, TraceRecordRaw,
 };
+use std::collections::HashSet;
 use std::sync::Arc;
+use storage_adapter::storage::StorageAdapter;
 use tonic::Status;
 
 // ---- slow subscribe ----
@@ -97,16 +109,14 @@ pub async fn list_system_alarm_by_req(
     _req: &ListSystemAlarmRequest,
 ) -> Result<ListSystemAlarmReply, Status> {
     let list_system_alarm_raw: Vec<ListSystemAlarmRaw> = cache_manager
-        .alarm_events
-        .iter()
-        .map(|entry| {
-            let system_alarm_message = entry.value();
-            ListSystemAlarmRaw {
-                name: system_alarm_message.name.clone(),
-                message: system_alarm_message.message.clone(),
-                activate_at: system_alarm_message.activate_at,
-                activated: system_alarm_message.activated,
-            }
+        .alarm_manager
+        .latest_states()
+        .into_iter()
+        .map(|record| ListSystemAlarmRaw {
+            name: record.name,
+            message: record.message,
+            activate_at: record.activate_at as i64,
+            activated: record.activated,
         })
         .collect();
 
@@ -115,15 +125,196 @@ pub async fn list_system_alarm_by_req(
     })
 }
 
+// ---- alarm ----
+// Alarms of the whole cluster, read from the transitions every broker persists
+pub async fn list_alarm_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    req: &ListAlarmRequest,
+) -> Result<ListAlarmReply, MqttBrokerError> {
+    let alarm_storage = AlarmStorage::new(client_pool.clone());
+    let stored = alarm_storage.list().await?;
+    Ok(build_list_alarm_reply(cache_manager, stored, req))
+}
+
+fn build_list_alarm_reply(
+    cache_manager: &Arc<CacheManager>,
+    stored: Vec<AlarmRecord>,
+    req: &ListAlarmRequest,
+) -> ListAlarmReply {
+    let broker_id = broker_mqtt_conf().broker_id;
+    let records = if req.history {
+        cache_manager
+            .alarm_manager
+            .cluster_history(broker_id, stored, req.limit as usize)
+    } else {
+        let alive_brokers: HashSet<u64> = cache_manager
+            .node_lists
+            .iter()
+            .map(|node| *node.key())
+            .collect();
+        cache_manager
+            .alarm_manager
+            .cluster_active_alarms(broker_id, stored, &alive_brokers)
+    };
+
+    ListAlarmReply {
+        alarms: records.into_iter().map(alarm_raw).collect(),
+    }
+}
+
+pub async fn clear_alarm_by_req<S>(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    message_storage_adapter: &Arc<S>,
+    req: &ClearAlarmRequest,
+) -> Result<ClearAlarmReply, MqttBrokerError>
+where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    let record = cache_manager
+        .alarm_manager
+        .clear(&req.name)
+        .ok_or_else(|| MqttBrokerError::AlarmNotActive(req.name.clone()))?;
+
+    report_alarm_transition(client_pool, cache_manager, message_storage_adapter, &record).await;
+    Ok(ClearAlarmReply {})
+}
+
+fn alarm_raw(record: AlarmRecord) -> AlarmRaw {
+    AlarmRaw {
+        broker_id: record.broker_id,
+        name: record.name,
+        severity: record.severity,
+        message: record.message,
+        value: record.value,
+        activate_at: record.activate_at,
+        deactivate_at: record.deactivate_at,
+        activated: record.activated,
+    }
+}
+
+// ---- live trace ----
+pub async fn start_trace_by_req(
+    cache_manager: &Arc<CacheManager>,
+    req: &StartTraceRequest,
+) -> Result<StartTraceReply, MqttBrokerError> {
+    let target = TraceTarget::parse(&req.target_type, &req.target)?;
+    cache_manager.live_trace_manager.start(
+        &req.name,
+        target,
+        req.duration_secs,
+        req.payload_max_len as usize,
+    )?;
+    Ok(StartTraceReply {})
+}
+
+pub async fn stop_trace_by_req(
+    cache_manager: &Arc<CacheManager>,
+    req: &StopTraceRequest,
+) -> Result<StopTraceReply, MqttBrokerError> {
+    cache_manager.live_trace_manager.stop(&req.name)?;
+    Ok(StopTraceReply {})
+}
+
+pub async fn list_trace_by_req(
+    cache_manager: &Arc<CacheManager>,
+    _: &ListTraceRequest,
+) -> Result<ListTraceReply, MqttBrokerError> {
+    Ok(ListTraceReply {
+        traces: cache_manager
+            .live_trace_manager
+            .list()
+            .into_iter()
+            .map(trace_raw)
+            .collect(),
+    })
+}
+
+pub async fn list_trace_record_by_req(
+    cache_manager: &Arc<CacheManager>,
+    req: &ListTraceRecordRequest,
+) -> Result<ListTraceRecordReply, MqttBrokerError> {
+    let records = cache_manager
+        .live_trace_manager
+        .records(&req.name, req.limit as usize)?;
+    Ok(ListTraceRecordReply {
+        records: records.into_iter().map(trace_record_raw).collect(),
+    })
+}
+
+fn trace_raw(info: TraceInfo) -> TraceRaw {
+    TraceRaw {
+        name: info.name,
+        target_type: info.target.target_type().to_string(),
+        target: info.target.value().to_string(),
+        start_at: info.start_at,
+        end_at: info.end_at,
+        running: info.running,
+        record_num: info.record_num as u64,
+    }
+}
+
+fn trace_record_raw(record: TraceRecord) -> TraceRecordRaw {
+    TraceRecordRaw {
+        time: record.time,
+        direction: record.direction.as_str().to_string(),
+        client_id: record.client_id,
+        username: record.username,
+        source_ip: record.source_ip,
+        packet_type: record.packet_type,
+        topic: record.topic,
+        detail: record.detail,
+        payload: record.payload,
+    }
+}
+
+// ---- topic metrics ----
+pub async fn list_topic_metrics_by_req(
+    cache_manager: &Arc<CacheManager>,
+    req: &ListTopicMetricsRequest,
+) -> Result<ListTopicMetricsReply, MqttBrokerError> {
+    let sort_by: TopicMetricsSortBy = req.sort_by.parse()?;
+    let topic_metrics = &cache_manager.topic_metrics_manager;
+    Ok(ListTopicMetricsReply {
+        enable: topic_metrics.is_enabled(),
+        topics: topic_metrics
+            .top(sort_by, req.limit as usize)
+            .into_iter()
+            .map(topic_metrics_raw)
+            .collect(),
+        overflow_num: topic_metrics.overflow_num(),
+    })
+}
+
+fn topic_metrics_raw(data: TopicMetricsData) -> TopicMetricsRaw {
+    TopicMetricsRaw {
+        topic: data.topic,
+        messages_in: data.messages_in,
+        messages_out: data.messages_out,
+        bytes_in: data.bytes_in,
+        bytes_out: data.bytes_out,
+        messages_dropped: data.messages_dropped,
+        qos0_in: data.qos0_in,
+        qos1_in: data.qos1_in,
+        qos2_in: data.qos2_in,
+        update_time: data.update_time,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::observability::system_topic::sysmon::SystemAlarmEventMessage;
     use crate::storage::message::cluster_name;
 
+    use crate::observability::system_topic::replace_topic_name;
+    use crate::observability::system_topic::sysmon::SYSTEM_TOPIC_BROKERS_ALARMS_DEACTIVATE;
+    use common_base::tools::unique_id;
     use common_config::mqtt::config::BrokerMqttConfig;
     use common_config::mqtt::{default_broker_mqtt, init_broker_mqtt_conf_by_path};
-    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::topic::MqttTopic;
+    use metadata_struct::placement::node::BrokerNode;
+    use storage_adapter::memory::MemoryStorageAdapter;
 
     #[tokio::test]
     pub async fn test_set_system_alarm_config_by_req() {
@@ -173,13 +364,20 @@ pub async fn test_list_system_alarm_by_req() {
 
         let req = ListSystemAlarmRequest {};
         let test_event = "test_event";
-        let message = SystemAlarmEventMessage {
-            name: test_event.to_string(),
-            message: test_event.to_string(),
-            activate_at: 0,
-            activated: false,
-        };
-        cache_manager.add_alarm_event(test_event.to_string(), message);
+        cache_manager.alarm_manager.set_alarm(
+            test_event,
+            "warning",
+            true,
+            1.0,
+            test_event.to_string(),
+        );
+        cache_manager.alarm_manager.set_alarm(
+            test_event,
+            "warning",
+            false,
+            0.0,
+            test_event.to_string(),
+        );
         let reply = list_system_alarm_by_req(&cache_manager, &req)
             .await
             .unwrap_or_else(|e| {
@@ -193,7 +391,113 @@ pub async fn test_list_system_alarm_by_req() {
             reply.list_system_alarm_raw[0].message,
             test_event.to_string()
         );
-        assert_eq!(reply.list_system_alarm_raw[0].activate_at, 0);
+        assert!(reply.list_system_alarm_raw[0].activate_at > 0);
         assert!(!reply.list_system_alarm_raw[0].activated);
     }
+
+    #[tokio::test]
+    pub async fn test_list_and_clear_alarm_by_req() {
+        let path = format!(
+            "{}/../../config/mqtt-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_broker_mqtt_conf_by_path(&path);
+        let client_pool = Arc::new(ClientPool::new(3));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), cluster_name()));
+        let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let topic_name = replace_topic_name(SYSTEM_TOPIC_BROKERS_ALARMS_DEACTIVATE.to_string());
+        let mqtt_topic = MqttTopic::new(unique_id(), cluster_name(), topic_name.clone());
+        cache_manager.add_topic(&topic_name, &mqtt_topic);
+
+        cache_manager
+            .alarm_manager
+            .evaluate("StorageWriteFailure", 2.0, "2 failures".to_string());
+
+        // an alarm persisted by another broker of the cluster
+        let remote_broker_id = broker_mqtt_conf().broker_id + 1;
+        cache_manager.node_lists.insert(
+            remote_broker_id,
+            BrokerNode {
+                node_id: remote_broker_id,
+                ..Default::default()
+            },
+        );
+        let mut remote = cache_manager.alarm_manager.active_alarms()[0].clone();
+        remote.broker_id = remote_broker_id;
+        let stored = vec![remote];
+
+        let req = ListAlarmRequest {
+            history: false,
+            limit: 0,
+        };
+        let reply = build_list_alarm_reply(&cache_manager, stored.clone(), &req);
+        assert_eq!(reply.alarms.len(), 2);
+        assert_eq!(reply.alarms[0].severity, "critical");
+        assert!(reply.alarms[0].activated);
+        assert!(reply
+            .alarms
+            .iter()
+            .any(|alarm| alarm.broker_id == remote_broker_id));
+
+        let req = ClearAlarmRequest {
+            name: "StorageWriteFailure".to_string(),
+        };
+        clear_alarm_by_req(&cache_manager, &client_pool, &message_storage_adapter, &req)
+            .await
+            .unwrap();
+        assert!(
+            clear_alarm_by_req(&cache_manager, &client_pool, &message_storage_adapter, &req)
+                .await
+                .is_err()
+        );
+
+        let req = ListAlarmRequest {
+            history: true,
+            limit: 10,
+        };
+        let reply = build_list_alarm_reply(&cache_manager, stored, &req);
+        assert_eq!(reply.alarms.len(), 3);
+        assert!(!reply.alarms[0].activated);
+    }
+
+    #[tokio::test]
+    pub async fn test_live_trace_by_req() {
+        let client_pool = Arc::new(ClientPool::new(3));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, cluster_name()));
+
+        let req = StartTraceRequest {
+            name: "device-1".to_string(),
+            target_type: "client_id".to_string(),
+            target: "c1".to_string(),
+            duration_secs: 60,
+            payload_max_len: 0,
+        };
+        start_trace_by_req(&cache_manager, &req).await.unwrap();
+        let mut wrong_target = req.clone();
+        wrong_target.target_type = "port".to_string();
+        assert!(start_trace_by_req(&cache_manager, &wrong_target)
+            .await
+            .is_err());
+
+        let reply = list_trace_by_req(&cache_manager, &ListTraceRequest {})
+            .await
+            .unwrap();
+        assert_eq!(reply.traces.len(), 1);
+        assert_eq!(reply.traces[0].target_type, "client_id");
+        assert!(reply.traces[0].running);
+
+        let req = StopTraceRequest {
+            name: "device-1".to_string(),
+        };
+        stop_trace_by_req(&cache_manager, &req).await.unwrap();
+
+        let req = ListTraceRecordRequest {
+            name: "device-1".to_string(),
+            limit: 10,
+        };
+        let reply = list_trace_record_by_req(&cache_manager, &req)
+            .await
+            .unwrap();
+        assert!(reply.records.is_empty());
+    }
 }
@@ -0,0 +1,122 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use common_base::tools::unique_id;
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::pool::ClientPool;
+use protocol::broker_mqtt::broker_mqtt_admin::{SendRequestReply, SendRequestRequest};
+use protocol::mqtt::common::{Publish, PublishProperties, QoS};
+use storage_adapter::storage::StorageAdapter;
+use tokio::time::timeout;
+
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::message::build_message_expire;
+use crate::handler::offline_message::save_simple_message;
+use crate::handler::request_response::{broker_response_topic, BROKER_RESPONSE_OWNER};
+use crate::handler::tenant::tenant_topic_name;
+use crate::handler::topic::{topic_name_validator, try_init_topic};
+use crate::subscribe::manager::SubscribeManager;
+
+const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 5000;
+
+// Publishes a request to the topic and waits for a client to publish the response
+// to the response topic of this broker with the same correlation data.
+pub async fn send_request_by_req<S>(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    message_storage_adapter: &Arc<S>,
+    req: &SendRequestRequest,
+) -> Result<SendRequestReply, MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    topic_name_validator(&req.topic_name)?;
+    if !req.tenant.is_empty() && cache_manager.get_tenant(&req.tenant).is_none() {
+        return Err(MqttBrokerError::TenantNotExist(req.tenant.clone()));
+    }
+    // Requests of a tenant only reach the clients of that tenant
+    let topic_name = tenant_topic_name(&req.tenant, &req.topic_name)?;
+
+    let correlation_id = unique_id();
+    let publish = Publish {
+        dup: false,
+        qos: QoS::AtLeastOnce,
+        pkid: 0,
+        retain: false,
+        topic: Bytes::from(topic_name.clone()),
+        payload: Bytes::from(req.payload.clone()),
+    };
+    let publish_properties = Some(PublishProperties {
+        response_topic: Some(broker_response_topic(broker_mqtt_conf().broker_id)),
+        correlation_data: Some(Bytes::from(correlation_id.clone())),
+        content_type: if req.content_type.is_empty() {
+            None
+        } else {
+            Some(req.content_type.clone())
+        },
+        ..Default::default()
+    });
+
+    let topic = try_init_topic(
+        &topic_name,
+        cache_manager,
+        message_storage_adapter,
+        client_pool,
+    )
+    .await?;
+
+    let rx = cache_manager.request_response.wait(&correlation_id);
+    let message_expire = build_message_expire(cache_manager, &publish_properties);
+    if let Err(e) = save_simple_message(
+        message_storage_adapter,
+        cache_manager,
+        subscribe_manager,
+        &publish,
+        &publish_properties,
+        BROKER_RESPONSE_OWNER,
+        &topic,
+        message_expire,
+    )
+    .await
+    {
+        cache_manager.request_response.cancel(&correlation_id);
+        return Err(e);
+    }
+
+    let timeout_ms = if req.timeout_ms == 0 {
+        DEFAULT_REQUEST_TIMEOUT_MS
+    } else {
+        req.timeout_ms
+    };
+    match timeout(Duration::from_millis(timeout_ms), rx).await {
+        Ok(Ok(message)) => Ok(SendRequestReply {
+            client_id: message.client_id,
+            payload: message.payload.to_vec(),
+            content_type: message.content_type.unwrap_or_default(),
+        }),
+        _ => {
+            cache_manager.request_response.cancel(&correlation_id);
+            Err(MqttBrokerError::OperationTimeout(
+                timeout_ms,
+                format!("request to topic {}", req.topic_name),
+            ))
+        }
+    }
+}
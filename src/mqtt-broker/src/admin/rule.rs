@@ -0,0 +1,144 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::handler::cache::CacheManager;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
+use crate::handler::error::MqttBrokerError;
+use crate::observability::metrics::rule::{
+    get_rule_counter, RULE_STATUS_ACTION_FAILED, RULE_STATUS_ACTION_SUCCESS, RULE_STATUS_FAILED,
+    RULE_STATUS_FILTERED, RULE_STATUS_MATCHED, RULE_STATUS_PASSED,
+};
+use crate::rule::sql::parse_rule_sql;
+use crate::rule::{rule_name_validator, CompiledRule};
+use common_base::tools::now_second;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::rule::{MqttRule, MqttRuleAction};
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    DeleteRuleReply, DeleteRuleRequest, ListRuleReply, ListRuleRequest, RuleRaw, SetRuleReply,
+    SetRuleRequest,
+};
+use std::sync::Arc;
+
+// List rules together with the counters collected on this broker node
+pub async fn list_rule_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: &ListRuleRequest,
+) -> Result<ListRuleReply, MqttBrokerError> {
+    let mut rules = Vec::new();
+    for rule in cache_manager.get_all_rule() {
+        if !request.rule_name.is_empty() && rule.rule_name != request.rule_name {
+            continue;
+        }
+
+        let name = rule.rule_name.as_str();
+        rules.push(RuleRaw {
+            matched: get_rule_counter(name, RULE_STATUS_MATCHED),
+            passed: get_rule_counter(name, RULE_STATUS_PASSED),
+            filtered: get_rule_counter(name, RULE_STATUS_FILTERED),
+            failed: get_rule_counter(name, RULE_STATUS_FAILED),
+            action_success: get_rule_counter(name, RULE_STATUS_ACTION_SUCCESS),
+            action_failed: get_rule_counter(name, RULE_STATUS_ACTION_FAILED),
+            actions: serde_json::to_string(&rule.actions)?,
+            rule_name: rule.rule_name,
+            sql: rule.sql,
+            enable: rule.enable,
+            desc: rule.desc,
+            create_time: rule.create_time,
+        });
+    }
+    rules.sort_by(|a, b| a.rule_name.cmp(&b.rule_name));
+
+    Ok(ListRuleReply {
+        total_count: rules.len() as u32,
+        rules,
+    })
+}
+
+// Create a rule, or replace the SQL and actions of an existing one
+pub async fn set_rule_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: &SetRuleRequest,
+) -> Result<SetRuleReply, MqttBrokerError> {
+    rule_name_validator(&request.rule_name)?;
+    parse_rule_sql(&request.sql)?;
+
+    let actions: Vec<MqttRuleAction> = serde_json::from_str(&request.actions)
+        .map_err(|e| MqttBrokerError::CommonError(format!("Invalid rule actions: {}", e)))?;
+    if actions.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "A rule requires at least one action".to_string(),
+        ));
+    }
+
+    let create_time = if let Some(rule) = cache_manager.get_rule(&request.rule_name) {
+        rule.create_time
+    } else {
+        now_second()
+    };
+
+    let rule = MqttRule {
+        rule_name: request.rule_name.clone(),
+        sql: request.sql.clone(),
+        actions,
+        enable: request.enable,
+        desc: request.desc.clone(),
+        create_time,
+    };
+    let compiled = CompiledRule::new(rule.clone())?;
+
+    let mut rules: Vec<MqttRule> = cache_manager
+        .get_all_rule()
+        .into_iter()
+        .filter(|raw| raw.rule_name != rule.rule_name)
+        .collect();
+    rules.push(rule);
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::Rule,
+        serde_json::to_vec(&rules)?,
+    )
+    .await?;
+    cache_manager
+        .rule_info
+        .insert(compiled.rule.rule_name.clone(), Arc::new(compiled));
+
+    Ok(SetRuleReply {})
+}
+
+pub async fn delete_rule_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: &DeleteRuleRequest,
+) -> Result<DeleteRuleReply, MqttBrokerError> {
+    if cache_manager.get_rule(&request.rule_name).is_none() {
+        return Err(MqttBrokerError::RuleNotExist(request.rule_name.clone()));
+    }
+
+    let rules: Vec<MqttRule> = cache_manager
+        .get_all_rule()
+        .into_iter()
+        .filter(|raw| raw.rule_name != request.rule_name)
+        .collect();
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::Rule,
+        serde_json::to_vec(&rules)?,
+    )
+    .await?;
+    cache_manager.remove_rule(&request.rule_name);
+
+    Ok(DeleteRuleReply {})
+}
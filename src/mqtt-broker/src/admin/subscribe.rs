@@ -13,17 +13,21 @@
 // limitations under the License.
 
 use crate::handler::cache::CacheManager;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
 use crate::handler::error::MqttBrokerError;
 use crate::storage::auto_subscribe::AutoSubscribeStorage;
+use crate::subscribe::share::strategy::ShareStrategy;
 
 use common_config::mqtt::broker_mqtt_conf;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
 use protocol::broker_mqtt::broker_mqtt_admin::{
     DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest, ListAutoSubscribeRuleReply,
-    SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest,
+    SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest, SetSharedSubscriptionStrategyReply,
+    SetSharedSubscriptionStrategyRequest,
 };
 use protocol::mqtt::common::{qos, retain_forward_rule, Error};
+use std::str::FromStr;
 use std::sync::Arc;
 
 pub async fn set_auto_subscribe_rule(
@@ -114,3 +118,44 @@ pub async fn list_auto_subscribe_rule_by_req(
         auto_subscribe_rules,
     })
 }
+
+// Set the dispatch strategy of a share group. An empty group name sets the default strategy,
+// an empty strategy removes the group's own strategy.
+pub async fn set_shared_subscription_strategy_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: &SetSharedSubscriptionStrategyRequest,
+) -> Result<SetSharedSubscriptionStrategyReply, MqttBrokerError> {
+    let mut config = cache_manager.get_shared_subscription_config();
+    if request.strategy.is_empty() {
+        if request.group_name.is_empty() {
+            return Err(MqttBrokerError::CommonError(
+                "The default shared subscription strategy cannot be empty".to_string(),
+            ));
+        }
+        config.group_strategy.remove(&request.group_name);
+    } else {
+        let strategy = ShareStrategy::from_str(&request.strategy).map_err(|_| {
+            MqttBrokerError::CommonError(format!(
+                "Unknown shared subscription strategy {}, supports round_robin, random, sticky, hash_client_id, hash_topic, least_inflight",
+                request.strategy
+            ))
+        })?;
+        if request.group_name.is_empty() {
+            config.strategy = strategy.to_string();
+        } else {
+            config
+                .group_strategy
+                .insert(request.group_name.clone(), strategy.to_string());
+        }
+    }
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::SharedSubscription,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_shared_subscription_config(config);
+    Ok(SetSharedSubscriptionStrategyReply {})
+}
@@ -0,0 +1,140 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::handler::cache::CacheManager;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
+use crate::handler::error::MqttBrokerError;
+use crate::handler::tenant::tenant_name_validator;
+use crate::subscribe::manager::SubscribeManager;
+use common_base::tools::now_second;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::tenant::MqttTenant;
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    DeleteTenantReply, DeleteTenantRequest, ListTenantReply, ListTenantRequest, SetTenantReply,
+    SetTenantRequest, TenantRaw,
+};
+use std::sync::Arc;
+
+// List tenants together with their usage in the cluster
+pub async fn list_tenant_by_req(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: &ListTenantRequest,
+) -> Result<ListTenantReply, MqttBrokerError> {
+    let mut tenants = Vec::new();
+    for tenant in cache_manager.get_all_tenant() {
+        if !request.tenant_name.is_empty() && tenant.tenant_name != request.tenant_name {
+            continue;
+        }
+
+        tenants.push(TenantRaw {
+            connection_num: cache_manager.tenant_connection_count(&tenant.tenant_name),
+            subscribe_num: subscribe_manager.tenant_subscribe_count(&tenant.tenant_name),
+            tenant_name: tenant.tenant_name,
+            desc: tenant.desc,
+            max_connections: tenant.max_connections,
+            max_subscriptions: tenant.max_subscriptions,
+            max_messages_per_second: tenant.max_messages_per_second,
+            max_storage_bytes_per_second: tenant.max_storage_bytes_per_second,
+            create_time: tenant.create_time,
+        });
+    }
+    tenants.sort_by(|a, b| a.tenant_name.cmp(&b.tenant_name));
+
+    Ok(ListTenantReply {
+        total_count: tenants.len() as u32,
+        tenants,
+    })
+}
+
+// Create a tenant, or update the quotas of an existing one
+pub async fn set_tenant_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: &SetTenantRequest,
+) -> Result<SetTenantReply, MqttBrokerError> {
+    tenant_name_validator(&request.tenant_name)?;
+
+    let create_time = if let Some(tenant) = cache_manager.get_tenant(&request.tenant_name) {
+        tenant.create_time
+    } else {
+        now_second()
+    };
+
+    let tenant = MqttTenant {
+        tenant_name: request.tenant_name.clone(),
+        desc: request.desc.clone(),
+        max_connections: request.max_connections,
+        max_subscriptions: request.max_subscriptions,
+        max_messages_per_second: request.max_messages_per_second,
+        max_storage_bytes_per_second: request.max_storage_bytes_per_second,
+        create_time,
+    };
+
+    let mut tenants: Vec<MqttTenant> = cache_manager
+        .get_all_tenant()
+        .into_iter()
+        .filter(|raw| raw.tenant_name != tenant.tenant_name)
+        .collect();
+    tenants.push(tenant.clone());
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::Tenant,
+        serde_json::to_vec(&tenants)?,
+    )
+    .await?;
+    cache_manager.add_tenant(tenant);
+
+    Ok(SetTenantReply {})
+}
+
+// Delete a tenant, only allowed when no user is bound to it
+pub async fn delete_tenant_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: &DeleteTenantRequest,
+) -> Result<DeleteTenantReply, MqttBrokerError> {
+    if cache_manager.get_tenant(&request.tenant_name).is_none() {
+        return Err(MqttBrokerError::TenantNotExist(request.tenant_name.clone()));
+    }
+
+    let user_num = cache_manager
+        .user_info
+        .iter()
+        .filter(|user| user.tenant == request.tenant_name)
+        .count();
+    if user_num > 0 {
+        return Err(MqttBrokerError::TenantInUse(
+            request.tenant_name.clone(),
+            user_num,
+        ));
+    }
+
+    let tenants: Vec<MqttTenant> = cache_manager
+        .get_all_tenant()
+        .into_iter()
+        .filter(|raw| raw.tenant_name != request.tenant_name)
+        .collect();
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::Tenant,
+        serde_json::to_vec(&tenants)?,
+    )
+    .await?;
+    cache_manager.remove_tenant(&request.tenant_name);
+
+    Ok(DeleteTenantReply {})
+}
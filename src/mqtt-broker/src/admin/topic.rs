@@ -55,7 +55,12 @@ fn extract_topic(cache_manager: &Arc<CacheManager>) -> Result<Vec<MqttTopicRaw>,
     let mut topics = Vec::new();
     for entry in cache_manager.topic_info.iter() {
         let topic = entry.value();
-        topics.push(MqttTopicRaw::from(topic.clone()));
+        let mut raw = MqttTopicRaw::from(topic.clone());
+        raw.is_contain_retain_message = cache_manager
+            .retain_message_manager
+            .get(&topic.topic_name)
+            .is_some();
+        topics.push(raw);
     }
     Ok(topics)
 }
@@ -39,6 +39,7 @@ pub async fn list_user_by_req(
         let user_raw = UserRaw {
             username: ele.1.username,
             is_superuser: ele.1.is_superuser,
+            tenant: ele.1.tenant,
         };
         users.push(user_raw);
     }
@@ -59,10 +60,15 @@ pub async fn create_user_by_req(
     client_pool: &Arc<ClientPool>,
     request: &CreateUserRequest,
 ) -> Result<CreateUserReply, MqttBrokerError> {
+    if !request.tenant.is_empty() && cache_manager.get_tenant(&request.tenant).is_none() {
+        return Err(MqttBrokerError::TenantNotExist(request.tenant.clone()));
+    }
+
     let mqtt_user = MqttUser {
         username: request.username.clone(),
         password: request.password.clone(),
         is_superuser: request.is_superuser,
+        tenant: request.tenant.clone(),
     };
 
     let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
@@ -89,6 +95,7 @@ fn get_field_str(&self, field: &str) -> Option<String> {
         match field {
             "username" => Some(self.username.clone()),
             "is_superuser" => Some(self.is_superuser.to_string()),
+            "tenant" => Some(self.tenant.clone()),
             _ => None,
         }
     }
@@ -13,19 +13,43 @@
 // limitations under the License.
 
 use crate::handler::error::MqttBrokerError;
+use crate::observability::trace::trace_connector_records;
+use crate::observability::warn::check::record_connector_failure;
+use crate::storage::message::{cluster_name, MessageStorage};
 use axum::async_trait;
+use common_base::error::common::CommonError;
 
 use common_config::mqtt::broker_mqtt_conf;
+use metadata_struct::adapter::record::Record;
 use metadata_struct::mqtt::bridge::{
-    config_local_file::LocalFileConnectorConfig, connector::MQTTConnector,
-    connector_type::ConnectorType, status::MQTTStatus,
+    config_http::HttpConnectorConfig,
+    config_local_file::LocalFileConnectorConfig,
+    config_mqtt::MqttBridgeConnectorConfig,
+    config_rdb::RdbConnectorConfig,
+    config_source::{
+        FileSourceConnectorConfig, KafkaSourceConnectorConfig, MqttSourceConnectorConfig,
+    },
+    connector::MQTTConnector,
+    connector_type::ConnectorType,
+    status::MQTTStatus,
 };
+use serde::de::DeserializeOwned;
 use std::{sync::Arc, time::Duration};
-use storage_adapter::storage::StorageAdapter;
+use storage_adapter::storage::{ShardInfo, StorageAdapter};
 use tokio::{select, sync::broadcast, time::sleep};
 use tracing::{error, info};
 
-use super::{file::FileBridgePlugin, manager::ConnectorManager};
+use super::{
+    file::FileBridgePlugin,
+    http::HttpBridgePlugin,
+    manager::ConnectorManager,
+    mqtt::MqttBridgePlugin,
+    mysql::MySqlBridgePlugin,
+    postgres::PostgresBridgePlugin,
+    source::{
+        file::FileSourcePlugin, kafka::KafkaSourcePlugin, mqtt::MqttSourcePlugin, SourcePublisher,
+    },
+};
 
 #[derive(Clone)]
 pub struct BridgePluginReadConfig {
@@ -44,9 +68,16 @@ pub trait BridgePlugin {
     async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerError>;
 }
 
+// A sink only writes a batch out, reading and offset management are done by run_bridge_sink
+#[async_trait]
+pub trait BridgeSink {
+    async fn append(&self, records: &[Record]) -> Result<(), MqttBrokerError>;
+}
+
 pub async fn start_connector_thread<S>(
     message_storage: Arc<S>,
     connector_manager: Arc<ConnectorManager>,
+    source_publisher: SourcePublisher<S>,
     stop_send: broadcast::Sender<bool>,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
@@ -65,6 +96,7 @@ pub async fn start_connector_thread<S>(
             _ = check_connector(
                 &message_storage,
                 &connector_manager,
+                &source_publisher,
             ) => {
                 sleep(Duration::from_secs(1)).await;
             }
@@ -72,8 +104,11 @@ pub async fn start_connector_thread<S>(
     }
 }
 
-async fn check_connector<S>(message_storage: &Arc<S>, connector_manager: &Arc<ConnectorManager>)
-where
+async fn check_connector<S>(
+    message_storage: &Arc<S>,
+    connector_manager: &Arc<ConnectorManager>,
+    source_publisher: &SourcePublisher<S>,
+) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
     let config = broker_mqtt_conf();
@@ -106,6 +141,7 @@ async fn check_connector<S>(message_storage: &Arc<S>, connector_manager: &Arc<Co
         start_thread(
             connector_manager.clone(),
             message_storage.clone(),
+            source_publisher.clone(),
             raw.clone(),
             thread,
         );
@@ -141,53 +177,338 @@ async fn check_connector<S>(message_storage: &Arc<S>, connector_manager: &Arc<Co
 fn start_thread<S>(
     connector_manager: Arc<ConnectorManager>,
     message_storage: Arc<S>,
+    source_publisher: SourcePublisher<S>,
     connector: MQTTConnector,
     thread: BridgePluginThread,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
     tokio::spawn(async move {
-        match connector.connector_type {
+        let connector_name = connector.connector_name.clone();
+        let stop_send = thread.stop_send.clone();
+        let mut read_config = BridgePluginReadConfig {
+            topic_id: connector.topic_id.clone(),
+            record_num: 100,
+        };
+
+        let bridge: Box<dyn BridgePlugin + Send + Sync> = match connector.connector_type {
             ConnectorType::LocalFile => {
-                let local_file_config = match serde_json::from_str::<LocalFileConnectorConfig>(
-                    &connector.config,
+                let Some(config) = parse_connector_config::<LocalFileConnectorConfig>(&connector)
+                else {
+                    return;
+                };
+                Box::new(FileBridgePlugin::new(
+                    connector_manager.clone(),
+                    message_storage.clone(),
+                    connector_name.clone(),
+                    config,
+                    stop_send,
+                ))
+            }
+            ConnectorType::Kafka => return,
+            ConnectorType::Mqtt => {
+                let Some(config) = parse_connector_config::<MqttBridgeConnectorConfig>(&connector)
+                else {
+                    return;
+                };
+                match MqttBridgePlugin::new(
+                    connector_manager.clone(),
+                    message_storage.clone(),
+                    connector_name.clone(),
+                    config,
+                    stop_send,
                 ) {
-                    Ok(config) => config,
+                    Ok(bridge) => Box::new(bridge),
                     Err(e) => {
-                        error!("Failed to parse LocalFileConnectorConfig file with error message :{}, configuration contents: {}", e, connector.config);
+                        error!(
+                            "Failed to create MqttBridgePlugin {} with error message: {}",
+                            connector_name, e
+                        );
                         return;
                     }
+                }
+            }
+            ConnectorType::Http => {
+                let Some(config) = parse_connector_config::<HttpConnectorConfig>(&connector) else {
+                    return;
                 };
-
-                let bridge = FileBridgePlugin::new(
+                read_config.record_num = config.batch_size;
+                match HttpBridgePlugin::new(
                     connector_manager.clone(),
                     message_storage.clone(),
-                    connector.connector_name.clone(),
-                    local_file_config,
-                    thread.stop_send.clone(),
-                );
-
-                connector_manager.add_connector_thread(&connector.connector_name, thread);
-
-                if let Err(e) = bridge
-                    .exec(BridgePluginReadConfig {
-                        topic_id: connector.topic_id,
-                        record_num: 100,
-                    })
-                    .await
+                    connector_name.clone(),
+                    config,
+                    stop_send,
+                ) {
+                    Ok(bridge) => Box::new(bridge),
+                    Err(e) => {
+                        error!(
+                            "Failed to create HttpBridgePlugin {} with error message: {}",
+                            connector_name, e
+                        );
+                        return;
+                    }
+                }
+            }
+            ConnectorType::MySql => {
+                let Some(config) = parse_connector_config::<RdbConnectorConfig>(&connector) else {
+                    return;
+                };
+                read_config.record_num = config.batch_size;
+                match MySqlBridgePlugin::new(
+                    connector_manager.clone(),
+                    message_storage.clone(),
+                    connector_name.clone(),
+                    config,
+                    stop_send,
+                )
+                .await
                 {
-                    connector_manager.remove_connector_thread(&connector.connector_name);
-                    error!(
-                        "Failed to start FileBridgePlugin with error message: {:?}",
-                        e
-                    );
+                    Ok(bridge) => Box::new(bridge),
+                    Err(e) => {
+                        error!(
+                            "Failed to create MySqlBridgePlugin {} with error message: {}",
+                            connector_name, e
+                        );
+                        return;
+                    }
+                }
+            }
+            ConnectorType::Postgres => {
+                let Some(config) = parse_connector_config::<RdbConnectorConfig>(&connector) else {
+                    return;
+                };
+                read_config.record_num = config.batch_size;
+                match PostgresBridgePlugin::new(
+                    connector_manager.clone(),
+                    message_storage.clone(),
+                    connector_name.clone(),
+                    config,
+                    stop_send,
+                ) {
+                    Ok(bridge) => Box::new(bridge),
+                    Err(e) => {
+                        error!(
+                            "Failed to create PostgresBridgePlugin {} with error message: {}",
+                            connector_name, e
+                        );
+                        return;
+                    }
                 }
             }
-            ConnectorType::Kafka => {}
+            ConnectorType::KafkaSource => {
+                let Some(config) = parse_connector_config::<KafkaSourceConnectorConfig>(&connector)
+                else {
+                    return;
+                };
+                Box::new(KafkaSourcePlugin::new(
+                    connector_manager.clone(),
+                    source_publisher,
+                    connector_name.clone(),
+                    config,
+                    stop_send,
+                ))
+            }
+            ConnectorType::MqttSource => {
+                let Some(config) = parse_connector_config::<MqttSourceConnectorConfig>(&connector)
+                else {
+                    return;
+                };
+                Box::new(MqttSourcePlugin::new(
+                    connector_manager.clone(),
+                    source_publisher,
+                    connector_name.clone(),
+                    config,
+                    stop_send,
+                ))
+            }
+            ConnectorType::FileSource => {
+                let Some(config) = parse_connector_config::<FileSourceConnectorConfig>(&connector)
+                else {
+                    return;
+                };
+                Box::new(FileSourcePlugin::new(
+                    connector_manager.clone(),
+                    source_publisher,
+                    connector_name.clone(),
+                    config,
+                    stop_send,
+                ))
+            }
+        };
+
+        connector_manager.add_connector_thread(&connector_name, thread);
+
+        if let Err(e) = bridge.exec(read_config).await {
+            record_connector_failure();
+            connector_manager.remove_connector_thread(&connector_name);
+            error!(
+                "Failed to start connector {} with error message: {:?}",
+                connector_name, e
+            );
         }
     });
 }
 
+fn parse_connector_config<T>(connector: &MQTTConnector) -> Option<T>
+where
+    T: DeserializeOwned,
+{
+    match serde_json::from_str::<T>(&connector.config) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            error!(
+                "Failed to parse {} connector config with error message :{}, configuration contents: {}",
+                connector.connector_type, e, connector.config
+            );
+            None
+        }
+    }
+}
+
+// Records a rule sends to a connector are written to a shard of the connector, so subscribers
+// of the topic the connector reads never receive them.
+pub fn connector_rule_shard_name(connector_name: &str) -> String {
+    format!("$connector_rule_{}", connector_name)
+}
+
+pub async fn try_init_connector_rule_shard<S>(
+    storage_adapter: &Arc<S>,
+    connector_manager: &Arc<ConnectorManager>,
+    connector_name: &str,
+) -> Result<String, CommonError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let shard_name = connector_rule_shard_name(connector_name);
+    if connector_manager.is_rule_shard_created(connector_name) {
+        return Ok(shard_name);
+    }
+
+    let namespace = cluster_name();
+    let list = storage_adapter
+        .list_shard(namespace.clone(), shard_name.clone())
+        .await?;
+    if list.is_empty() {
+        storage_adapter
+            .create_shard(ShardInfo {
+                namespace,
+                shard_name: shard_name.clone(),
+                replica_num: 1,
+            })
+            .await?;
+    }
+    connector_manager.add_rule_shard(connector_name);
+    Ok(shard_name)
+}
+
+// Read the records of the connector topic and the records rules sent to the connector, and hand
+// them to the sink. A group offset is only committed once the sink accepted a batch, so a batch
+// that failed to write is read again.
+pub async fn run_bridge_sink<S, T>(
+    message_storage: &Arc<S>,
+    connector_manager: &Arc<ConnectorManager>,
+    connector_name: &str,
+    stop_send: &broadcast::Sender<bool>,
+    config: &BridgePluginReadConfig,
+    sink: &T,
+) -> Result<(), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+    T: BridgeSink + Sync,
+{
+    let rule_shard_name =
+        try_init_connector_rule_shard(message_storage, connector_manager, connector_name).await?;
+    // (group name, shard name), the rule shard is read under its own group
+    let sources = [
+        (connector_name.to_owned(), config.topic_id.clone()),
+        (rule_shard_name.clone(), rule_shard_name),
+    ];
+
+    let message_storage = MessageStorage::new(message_storage.clone());
+    let mut recv = stop_send.subscribe();
+
+    loop {
+        select! {
+            val = recv.recv() =>{
+                if let Ok(flag) = val {
+                    if flag {
+                        info!("Connector {} thread exited successfully", connector_name);
+                        break;
+                    }
+                }
+            },
+
+            val = sink_sources(&message_storage, connector_manager, connector_name, &sources, config.record_num, sink) => {
+                if val? == 0 {
+                    sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Hand one batch of each source to the sink, returns the number of records written
+async fn sink_sources<S, T>(
+    message_storage: &MessageStorage<S>,
+    connector_manager: &Arc<ConnectorManager>,
+    connector_name: &str,
+    sources: &[(String, String)],
+    record_num: u64,
+    sink: &T,
+) -> Result<usize, MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+    T: BridgeSink + Sync,
+{
+    let mut written = 0;
+    for (group_name, shard_name) in sources {
+        let offset = message_storage.get_group_offset(group_name).await?;
+        let mut data = match message_storage
+            .read_topic_message(shard_name, offset, record_num)
+            .await
+        {
+            Ok(data) => data,
+            Err(e) => {
+                error!(
+                    "Connector {} failed to read Topic {} data with error message :{}",
+                    connector_name, shard_name, e
+                );
+                continue;
+            }
+        };
+
+        connector_manager.report_heartbeat(connector_name);
+        if data.is_empty() {
+            continue;
+        }
+
+        let traces = trace_connector_records(connector_name, &mut data);
+        if let Err(e) = sink.append(&data).await {
+            record_connector_failure();
+            traces
+                .iter()
+                .for_each(|trace| trace.set_error(e.to_string()));
+            error!(
+                "Connector {} failed to write {} records, they will be retried, error message: {}",
+                connector_name,
+                data.len(),
+                e
+            );
+            sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+
+        // commit offset
+        message_storage
+            .commit_group_offset(group_name, shard_name, offset + data.len() as u64)
+            .await?;
+        written += data.len();
+    }
+    Ok(written)
+}
+
 fn stop_thread(thread: BridgePluginThread) -> Result<(), MqttBrokerError> {
     thread.stop_send.send(true)?;
     Ok(())
@@ -12,11 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 
-use super::core::{BridgePlugin, BridgePluginReadConfig};
+use super::core::{run_bridge_sink, BridgePlugin, BridgePluginReadConfig, BridgeSink};
 use super::manager::ConnectorManager;
-use crate::{handler::error::MqttBrokerError, storage::message::MessageStorage};
+use crate::handler::error::MqttBrokerError;
 use axum::async_trait;
 use metadata_struct::{
     adapter::record::Record, mqtt::bridge::config_local_file::LocalFileConnectorConfig,
@@ -24,8 +24,7 @@
 use storage_adapter::storage::StorageAdapter;
 use tokio::fs::File;
 use tokio::io::{AsyncWriteExt, BufWriter};
-use tokio::{fs::OpenOptions, select, sync::broadcast, time::sleep};
-use tracing::error;
+use tokio::{fs::OpenOptions, sync::broadcast, sync::Mutex};
 
 pub struct FileBridgePlugin<S> {
     connector_manager: Arc<ConnectorManager>,
@@ -54,12 +53,17 @@ pub fn new(
             stop_send,
         }
     }
+}
+
+// The writer of the connector file, opened when the connector starts
+struct FileBridgeSink {
+    writer: Mutex<BufWriter<File>>,
+}
 
-    pub async fn append(
-        &self,
-        records: &Vec<Record>,
-        writer: &mut BufWriter<File>,
-    ) -> Result<(), MqttBrokerError> {
+#[async_trait]
+impl BridgeSink for FileBridgeSink {
+    async fn append(&self, records: &[Record]) -> Result<(), MqttBrokerError> {
+        let mut writer = self.writer.lock().await;
         for record in records {
             let data = serde_json::to_string(record)?;
             writer.write_all(data.as_ref()).await?;
@@ -75,53 +79,23 @@ impl<S> BridgePlugin for FileBridgePlugin<S>
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
     async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
-        let message_storage = MessageStorage::new(self.message_storage.clone());
-        let group_name = self.connector_name.clone();
-        let mut recv = self.stop_send.subscribe();
         let file = OpenOptions::new()
             .append(true)
             .open(self.config.local_file_path.clone())
             .await?;
-        let mut writer = tokio::io::BufWriter::new(file);
-
-        loop {
-            let offset = message_storage.get_group_offset(&group_name).await?;
-
-            select! {
-                val = recv.recv() =>{
-                    if let Ok(flag) = val {
-                        if flag {
-                            break;
-                        }
-                    }
-                },
-
-                val = message_storage.read_topic_message(&config.topic_id, offset, config.record_num) => {
-                    match val {
-                        Ok(data) => {
-                            self.connector_manager.report_heartbeat(&self.connector_name);
-                            if data.is_empty() {
-                                sleep(Duration::from_millis(100)).await;
-                                continue;
-                            }
-
-                            if let Err(e) = self.append(&data,&mut writer).await{
-                                error!("Connector {} failed to write data to {}, error message :{}", self.connector_name,self.config.local_file_path, e);
-                                sleep(Duration::from_millis(100)).await;
-                            }
-
-                            // commit offset
-                            message_storage.commit_group_offset(&group_name, &config.topic_id, offset + data.len() as u64).await?;
-                        },
-                        Err(e) => {
-                            error!("Connector {} failed to read Topic {} data with error message :{}", self.connector_name,config.topic_id,e);
-                            sleep(Duration::from_millis(100)).await;
-                        }
-                    }
-                }
-            }
-        }
-        Ok(())
+        let sink = FileBridgeSink {
+            writer: Mutex::new(BufWriter::new(file)),
+        };
+
+        run_bridge_sink(
+            &self.message_storage,
+            &self.connector_manager,
+            &self.connector_name,
+            &self.stop_send,
+            &config,
+            &sink,
+        )
+        .await
     }
 }
 
@@ -145,7 +119,7 @@ mod tests {
     use tokio::{fs::File, io::AsyncReadExt, sync::broadcast, time::sleep};
 
     use crate::bridge::{
-        core::{BridgePlugin, BridgePluginReadConfig},
+        core::{connector_rule_shard_name, BridgePlugin, BridgePluginReadConfig},
         file::FileBridgePlugin,
         manager::ConnectorManager,
     };
@@ -153,15 +127,15 @@ mod tests {
 
     #[tokio::test]
     async fn file_bridge_plugin_test() {
-        // init a dummy mqtt broker config
-        let namespace = unique_id();
-
+        // init a dummy mqtt broker config, another test may have initialized it first
         let mqtt_config = BrokerMqttConfig {
-            cluster_name: namespace.clone(),
+            cluster_name: unique_id(),
             ..Default::default()
         };
 
-        init_broker_mqtt_conf_by_config(mqtt_config);
+        let namespace = init_broker_mqtt_conf_by_config(mqtt_config)
+            .cluster_name
+            .clone();
 
         let storage_adapter = Arc::new(MemoryStorageAdapter::new());
 
@@ -203,6 +177,25 @@ async fn file_bridge_plugin_test() {
 
         let connector_name = "test_file_connector".to_string();
 
+        // a record a rule sent to the connector, written after the first batch of the topic
+        let rule_record = Record {
+            offset: Some(0),
+            header: vec![],
+            key: "rule_key".to_string(),
+            data: b"rule_data".to_vec(),
+            tags: vec![],
+            timestamp: now_second(),
+            crc_num: calc_crc32(b"rule_data"),
+        };
+        storage_adapter
+            .batch_write(
+                namespace.clone(),
+                connector_rule_shard_name(&connector_name),
+                vec![rule_record.clone()],
+            )
+            .await
+            .unwrap();
+
         let connector_manager = Arc::new(ConnectorManager::new());
 
         let dir_path = tempdir().unwrap().path().to_str().unwrap().to_string();
@@ -252,7 +245,9 @@ async fn file_bridge_plugin_test() {
         let mut res: String = String::new();
         file.read_to_string(&mut res).await.unwrap();
 
-        let expected = test_data.iter().fold(String::new(), |acc, record| {
+        let mut expected_records = test_data.clone();
+        expected_records.insert(100, rule_record);
+        let expected = expected_records.iter().fold(String::new(), |acc, record| {
             let data = serde_json::to_string(record).unwrap();
             acc + &data
         });
@@ -0,0 +1,323 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::{sync::Arc, time::Duration};
+
+use axum::async_trait;
+use metadata_struct::{
+    adapter::record::Record,
+    mqtt::{bridge::config_http::HttpConnectorConfig, message::MqttMessage},
+};
+use reqwest::Client;
+use serde::Serialize;
+use storage_adapter::storage::StorageAdapter;
+use tokio::{sync::broadcast, time::sleep};
+use tracing::warn;
+
+use super::{
+    core::{run_bridge_sink, BridgePlugin, BridgePluginReadConfig, BridgeSink},
+    manager::ConnectorManager,
+};
+use crate::handler::error::MqttBrokerError;
+
+// One element of the JSON array posted to the endpoint
+#[derive(Serialize, Debug, PartialEq)]
+pub struct HttpBridgeMessage {
+    pub client_id: String,
+    pub topic: String,
+    pub payload: String,
+    pub qos: u8,
+    pub retain: bool,
+    pub user_properties: Vec<(String, String)>,
+    pub timestamp: u64,
+}
+
+impl From<MqttMessage> for HttpBridgeMessage {
+    fn from(message: MqttMessage) -> Self {
+        HttpBridgeMessage {
+            client_id: message.client_id,
+            topic: String::from_utf8_lossy(&message.topic).to_string(),
+            payload: String::from_utf8_lossy(&message.payload).to_string(),
+            qos: message.qos as u8,
+            retain: message.retain,
+            user_properties: message.user_properties,
+            timestamp: message.create_time,
+        }
+    }
+}
+
+pub struct HttpBridgePlugin<S> {
+    connector_manager: Arc<ConnectorManager>,
+    message_storage: Arc<S>,
+    connector_name: String,
+    config: HttpConnectorConfig,
+    stop_send: broadcast::Sender<bool>,
+    client: Client,
+}
+
+impl<S> HttpBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        connector_manager: Arc<ConnectorManager>,
+        message_storage: Arc<S>,
+        connector_name: String,
+        config: HttpConnectorConfig,
+        stop_send: broadcast::Sender<bool>,
+    ) -> Result<Self, MqttBrokerError> {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+
+        Ok(HttpBridgePlugin {
+            connector_manager,
+            message_storage,
+            connector_name,
+            config,
+            stop_send,
+            client,
+        })
+    }
+
+    async fn post(&self, body: &[HttpBridgeMessage]) -> Result<(), MqttBrokerError> {
+        let mut request = self.client.post(&self.config.url).json(body);
+        for (key, value) in self.config.headers.iter() {
+            request = request.header(key, value);
+        }
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+pub fn build_http_body(records: &[Record]) -> Vec<HttpBridgeMessage> {
+    records
+        .iter()
+        .filter_map(|record| match MqttMessage::decode_record(record.clone()) {
+            Ok(message) => Some(HttpBridgeMessage::from(message)),
+            Err(e) => {
+                warn!(
+                    "HTTP connector skipped a record that is not an MQTT message, error message: {}",
+                    e
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+#[async_trait]
+impl<S> BridgeSink for HttpBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn append(&self, records: &[Record]) -> Result<(), MqttBrokerError> {
+        let body = build_http_body(records);
+        if body.is_empty() {
+            return Ok(());
+        }
+
+        let mut backoff = self.config.retry_backoff_ms;
+        let mut attempt = 0;
+        loop {
+            match self.post(&body).await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    if attempt >= self.config.max_retries {
+                        return Err(e);
+                    }
+                    warn!(
+                        "Connector {} failed to post to {}, retrying in {}ms, error message: {}",
+                        self.connector_name, self.config.url, backoff, e
+                    );
+                }
+            }
+            sleep(Duration::from_millis(backoff)).await;
+            backoff = backoff.saturating_mul(2);
+            attempt += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl<S> BridgePlugin for HttpBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
+        run_bridge_sink(
+            &self.message_storage,
+            &self.connector_manager,
+            &self.connector_name,
+            &self.stop_send,
+            &config,
+            self,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use axum::{http::StatusCode, routing::post, Json, Router};
+    use bytes::Bytes;
+    use common_base::tools::unique_id;
+    use common_config::mqtt::{config::BrokerMqttConfig, init_broker_mqtt_conf_by_config};
+    use metadata_struct::{
+        adapter::record::Record,
+        mqtt::{bridge::config_http::HttpConnectorConfig, message::MqttMessage},
+    };
+    use protocol::mqtt::common::QoS;
+    use serde_json::Value;
+    use storage_adapter::{
+        memory::MemoryStorageAdapter,
+        storage::{ShardInfo, StorageAdapter},
+    };
+    use tokio::{net::TcpListener, sync::broadcast, time::sleep};
+
+    use super::{build_http_body, HttpBridgePlugin};
+    use crate::bridge::{
+        core::{BridgePlugin, BridgePluginReadConfig},
+        manager::ConnectorManager,
+    };
+
+    #[test]
+    fn build_http_body_test() {
+        let message = MqttMessage {
+            client_id: "c1".to_string(),
+            qos: QoS::AtLeastOnce,
+            topic: Bytes::from("sensors/1/data"),
+            payload: Bytes::from("{\"temp\":81}"),
+            create_time: 1736234580,
+            ..Default::default()
+        };
+        let records = vec![
+            Record::build_byte(message.encode()),
+            Record::build_byte(b"not a message".to_vec()),
+        ];
+
+        let body = build_http_body(&records);
+        assert_eq!(body.len(), 1);
+        assert_eq!(body[0].client_id, "c1");
+        assert_eq!(body[0].topic, "sensors/1/data");
+        assert_eq!(body[0].payload, "{\"temp\":81}");
+        assert_eq!(body[0].qos, 1);
+        assert_eq!(body[0].timestamp, 1736234580);
+    }
+
+    #[tokio::test]
+    async fn http_bridge_plugin_test() {
+        let namespace = init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: unique_id(),
+            ..Default::default()
+        })
+        .cluster_name
+        .clone();
+
+        // an endpoint that keeps every posted message
+        let received: Arc<Mutex<Vec<Value>>> = Arc::new(Mutex::new(Vec::new()));
+        let state = received.clone();
+        let app = Router::new().route(
+            "/bridge",
+            post(move |Json(body): Json<Vec<Value>>| {
+                let state = state.clone();
+                async move {
+                    state.lock().unwrap().extend(body);
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let shard_name = "test_http_topic".to_string();
+        storage_adapter
+            .create_shard(ShardInfo {
+                namespace: namespace.clone(),
+                shard_name: shard_name.clone(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let mut records = Vec::new();
+        for i in 0..10 {
+            let message = MqttMessage {
+                client_id: "c1".to_string(),
+                qos: QoS::AtLeastOnce,
+                topic: Bytes::from("sensors/1/data"),
+                payload: Bytes::from(format!("{{\"seq\":{}}}", i)),
+                ..Default::default()
+            };
+            records.push(Record::build_byte(message.encode()));
+        }
+        storage_adapter
+            .batch_write(namespace.clone(), shard_name.clone(), records)
+            .await
+            .unwrap();
+
+        let config = HttpConnectorConfig {
+            url: format!("http://{}/bridge", addr),
+            headers: HashMap::new(),
+            timeout_ms: 1000,
+            batch_size: 4,
+            max_retries: 0,
+            retry_backoff_ms: 10,
+        };
+        let (stop_send, _) = broadcast::channel(1);
+        let plugin = HttpBridgePlugin::new(
+            Arc::new(ConnectorManager::new()),
+            storage_adapter.clone(),
+            "test_http_connector".to_string(),
+            config,
+            stop_send.clone(),
+        )
+        .unwrap();
+
+        let read_config = BridgePluginReadConfig {
+            topic_id: shard_name.clone(),
+            record_num: 4,
+        };
+        let handle = tokio::spawn(async move {
+            plugin.exec(read_config).await.unwrap();
+        });
+
+        for _ in 0..50 {
+            if received.lock().unwrap().len() >= 10 {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        stop_send.send(true).unwrap();
+        handle.await.unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 10);
+        for (i, message) in received.iter().enumerate() {
+            assert_eq!(message["client_id"], "c1");
+            assert_eq!(message["topic"], "sensors/1/data");
+            assert_eq!(message["payload"], format!("{{\"seq\":{}}}", i));
+            assert_eq!(message["qos"], 1);
+        }
+    }
+}
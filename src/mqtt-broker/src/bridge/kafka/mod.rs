@@ -21,7 +21,10 @@
 use tokio::{select, sync::broadcast, time::sleep};
 use tracing::{error, info};
 
-use crate::{handler::error::MqttBrokerError, storage::message::MessageStorage};
+use crate::{
+    handler::error::MqttBrokerError, observability::trace::trace_connector_records,
+    storage::message::MessageStorage,
+};
 
 use super::{
     core::{BridgePlugin, BridgePluginReadConfig},
@@ -107,14 +110,16 @@ async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerErr
 
                 val = message_storage.read_topic_message(&config.topic_id, offset, config.record_num) => {
                     match val {
-                        Ok(data) => {
+                        Ok(mut data) => {
                             self.connector_manager.report_heartbeat(&self.connector_name);
                             if data.is_empty() {
                                 tokio::time::sleep(Duration::from_millis(100)).await;
                                 continue;
                             }
 
+                            let traces = trace_connector_records(&self.connector_name, &mut data);
                             if let Err(e) = self.append(&data, producer.clone()).await{
+                                traces.iter().for_each(|trace| trace.set_error(e.to_string()));
                                 error!("Connector {} failed to write data to kafka topic {}, error message: {}", self.connector_name, self.config.topic, e);
                                 sleep(Duration::from_millis(100)).await;
                             }
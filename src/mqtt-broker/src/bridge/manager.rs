@@ -28,6 +28,9 @@ pub struct ConnectorManager {
 
     // (connector_name, u64)
     pub connector_heartbeat: DashMap<String, u64>,
+
+    // (connector_name, bool), connectors whose rule shard exists
+    pub connector_rule_shard: DashMap<String, bool>,
 }
 
 impl ConnectorManager {
@@ -36,6 +39,7 @@ pub fn new() -> Self {
             connector_list: DashMap::with_capacity(8),
             connector_thread: DashMap::with_capacity(8),
             connector_heartbeat: DashMap::with_capacity(8),
+            connector_rule_shard: DashMap::with_capacity(8),
         }
     }
 
@@ -63,6 +67,7 @@ pub fn get_all_connector(&self) -> Vec<MQTTConnector> {
 
     pub fn remove_connector(&self, connector_name: &str) {
         self.connector_list.remove(connector_name);
+        self.connector_rule_shard.remove(connector_name);
     }
 
     // Connector Thread
@@ -96,4 +101,14 @@ pub fn report_heartbeat(&self, connector_name: &str) {
         self.connector_heartbeat
             .insert(connector_name.to_owned(), now_second());
     }
+
+    // Connector Rule Shard
+    pub fn add_rule_shard(&self, connector_name: &str) {
+        self.connector_rule_shard
+            .insert(connector_name.to_owned(), true);
+    }
+
+    pub fn is_rule_shard_created(&self, connector_name: &str) -> bool {
+        self.connector_rule_shard.contains_key(connector_name)
+    }
 }
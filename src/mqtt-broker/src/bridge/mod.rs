@@ -15,5 +15,11 @@
 pub mod core;
 pub mod file;
 pub mod heartbeat;
+pub mod http;
 pub mod kafka;
 pub mod manager;
+pub mod mqtt;
+pub mod mysql;
+pub mod postgres;
+pub mod source;
+pub mod template;
@@ -0,0 +1,153 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::{sync::Arc, time::Duration};
+
+use axum::async_trait;
+use metadata_struct::{
+    adapter::record::Record,
+    mqtt::{bridge::config_mqtt::MqttBridgeConnectorConfig, message::MqttMessage},
+};
+use paho_mqtt::{AsyncClient, ConnectOptionsBuilder, CreateOptionsBuilder, MessageBuilder};
+use storage_adapter::storage::StorageAdapter;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+use super::{
+    core::{run_bridge_sink, BridgePlugin, BridgePluginReadConfig, BridgeSink},
+    manager::ConnectorManager,
+    template::render_template,
+};
+use crate::handler::error::MqttBrokerError;
+
+const DEFAULT_CLIENT_ID_PREFIX: &str = "robustmq_bridge_";
+
+// Forwards messages to a remote broker. Messages of a remote broker are brought in by
+// the MQTT source connector (bridge/source/mqtt.rs).
+pub struct MqttBridgePlugin<S> {
+    connector_manager: Arc<ConnectorManager>,
+    message_storage: Arc<S>,
+    connector_name: String,
+    config: MqttBridgeConnectorConfig,
+    stop_send: broadcast::Sender<bool>,
+    client: AsyncClient,
+}
+
+impl<S> MqttBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        connector_manager: Arc<ConnectorManager>,
+        message_storage: Arc<S>,
+        connector_name: String,
+        config: MqttBridgeConnectorConfig,
+        stop_send: broadcast::Sender<bool>,
+    ) -> Result<Self, MqttBrokerError> {
+        let prefix = if config.client_id_prefix.is_empty() {
+            DEFAULT_CLIENT_ID_PREFIX
+        } else {
+            config.client_id_prefix.as_str()
+        };
+        let create_opts = CreateOptionsBuilder::new()
+            .server_uri(config.server.as_str())
+            .client_id(format!("{}{}", prefix, connector_name))
+            .finalize();
+        let client = AsyncClient::new(create_opts)?;
+
+        Ok(MqttBridgePlugin {
+            connector_manager,
+            message_storage,
+            connector_name,
+            config,
+            stop_send,
+            client,
+        })
+    }
+
+    // (Re)connect lazily, so a remote broker that is down only delays the batch
+    async fn ensure_connected(&self) -> Result<(), MqttBrokerError> {
+        if self.client.is_connected() {
+            return Ok(());
+        }
+
+        let mut builder = ConnectOptionsBuilder::new();
+        builder
+            .keep_alive_interval(Duration::from_secs(self.config.keep_alive))
+            .clean_session(true);
+        if let Some(username) = &self.config.username {
+            builder.user_name(username.as_str());
+        }
+        if let Some(password) = &self.config.password {
+            builder.password(password.as_str());
+        }
+        self.client.connect(builder.finalize()).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S> BridgeSink for MqttBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn append(&self, records: &[Record]) -> Result<(), MqttBrokerError> {
+        self.ensure_connected().await?;
+
+        for record in records {
+            let message = match MqttMessage::decode_record(record.clone()) {
+                Ok(message) => message,
+                Err(e) => {
+                    warn!(
+                        "Connector {} skipped a record that is not an MQTT message, error message: {}",
+                        self.connector_name, e
+                    );
+                    continue;
+                }
+            };
+
+            let topic = render_template(&self.config.remote_topic, &message)?;
+            let msg = MessageBuilder::new()
+                .topic(topic)
+                .payload(message.payload.to_vec())
+                .qos(self.config.qos)
+                .retained(self.config.retain.unwrap_or(message.retain))
+                .finalize();
+            self.client.publish(msg).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S> BridgePlugin for MqttBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
+        let result = run_bridge_sink(
+            &self.message_storage,
+            &self.connector_manager,
+            &self.connector_name,
+            &self.stop_send,
+            &config,
+            self,
+        )
+        .await;
+
+        if self.client.is_connected() {
+            let _ = self.client.disconnect(None).await;
+        }
+        result
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Arc;
+
+use axum::async_trait;
+use metadata_struct::{
+    adapter::record::Record,
+    mqtt::{bridge::config_rdb::RdbConnectorConfig, message::MqttMessage},
+};
+use mysql::{prelude::Queryable, Params, Pool, TxOpts, Value as MySqlValue};
+use serde_json::Value;
+use storage_adapter::storage::StorageAdapter;
+use third_driver::mysql::build_mysql_conn_pool;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+use super::{
+    core::{run_bridge_sink, BridgePlugin, BridgePluginReadConfig, BridgeSink},
+    manager::ConnectorManager,
+    template::{message_field, parse_sql_template, PlaceholderStyle, SqlTemplate},
+};
+use crate::handler::error::MqttBrokerError;
+
+pub struct MySqlBridgePlugin<S> {
+    connector_manager: Arc<ConnectorManager>,
+    message_storage: Arc<S>,
+    connector_name: String,
+    template: SqlTemplate,
+    stop_send: broadcast::Sender<bool>,
+    pool: Pool,
+}
+
+impl<S> MySqlBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub async fn new(
+        connector_manager: Arc<ConnectorManager>,
+        message_storage: Arc<S>,
+        connector_name: String,
+        config: RdbConnectorConfig,
+        stop_send: broadcast::Sender<bool>,
+    ) -> Result<Self, MqttBrokerError> {
+        let template = parse_sql_template(&config.sql_template, PlaceholderStyle::MySql)?;
+        // Creating the pool opens its initial connections, which blocks
+        let url = config.url.clone();
+        let pool = tokio::task::spawn_blocking(move || build_mysql_conn_pool(&url)).await??;
+
+        Ok(MySqlBridgePlugin {
+            connector_manager,
+            message_storage,
+            connector_name,
+            template,
+            stop_send,
+            pool,
+        })
+    }
+}
+
+pub fn to_mysql_value(value: Value) -> MySqlValue {
+    match value {
+        Value::Null => MySqlValue::NULL,
+        Value::Bool(b) => MySqlValue::Int(b as i64),
+        Value::Number(num) => {
+            if let Some(n) = num.as_i64() {
+                MySqlValue::Int(n)
+            } else if let Some(n) = num.as_u64() {
+                MySqlValue::UInt(n)
+            } else {
+                MySqlValue::Double(num.as_f64().unwrap_or_default())
+            }
+        }
+        Value::String(s) => MySqlValue::Bytes(s.into_bytes()),
+        other => MySqlValue::Bytes(other.to_string().into_bytes()),
+    }
+}
+
+#[async_trait]
+impl<S> BridgeSink for MySqlBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn append(&self, records: &[Record]) -> Result<(), MqttBrokerError> {
+        let mut rows = Vec::with_capacity(records.len());
+        for record in records {
+            match MqttMessage::decode_record(record.clone()) {
+                Ok(message) => rows.push(Params::Positional(
+                    self.template
+                        .fields
+                        .iter()
+                        .map(|field| to_mysql_value(message_field(&message, field)))
+                        .collect(),
+                )),
+                Err(e) => warn!(
+                    "Connector {} skipped a record that is not an MQTT message, error message: {}",
+                    self.connector_name, e
+                ),
+            }
+        }
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        // The mysql driver is blocking, the batch is written in one transaction off the runtime
+        let pool = self.pool.clone();
+        let statement = self.template.statement.clone();
+        tokio::task::spawn_blocking(move || -> Result<(), MqttBrokerError> {
+            let mut conn = pool.get_conn()?;
+            let mut tx = conn.start_transaction(TxOpts::default())?;
+            tx.exec_batch(statement, rows)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl<S> BridgePlugin for MySqlBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
+        run_bridge_sink(
+            &self.message_storage,
+            &self.connector_manager,
+            &self.connector_name,
+            &self.stop_send,
+            &config,
+            self,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mysql::Value as MySqlValue;
+    use serde_json::json;
+
+    use super::to_mysql_value;
+
+    #[test]
+    fn to_mysql_value_test() {
+        assert_eq!(to_mysql_value(json!(null)), MySqlValue::NULL);
+        assert_eq!(to_mysql_value(json!(true)), MySqlValue::Int(1));
+        assert_eq!(to_mysql_value(json!(-3)), MySqlValue::Int(-3));
+        assert_eq!(to_mysql_value(json!(1.5)), MySqlValue::Double(1.5));
+        assert_eq!(
+            to_mysql_value(json!("abc")),
+            MySqlValue::Bytes(b"abc".to_vec())
+        );
+        assert_eq!(
+            to_mysql_value(json!({"a": 1})),
+            MySqlValue::Bytes(b"{\"a\":1}".to_vec())
+        );
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Arc;
+
+use axum::async_trait;
+use metadata_struct::{
+    adapter::record::Record,
+    mqtt::{bridge::config_rdb::RdbConnectorConfig, message::MqttMessage},
+};
+use serde_json::Value;
+use storage_adapter::storage::StorageAdapter;
+use third_driver::postgres::build_postgres_client;
+use tokio::sync::{broadcast, Mutex};
+use tokio_postgres::{
+    types::{ToSql, Type},
+    Client,
+};
+use tracing::warn;
+
+use super::{
+    core::{run_bridge_sink, BridgePlugin, BridgePluginReadConfig, BridgeSink},
+    manager::ConnectorManager,
+    template::{message_field, parse_sql_template, PlaceholderStyle, SqlTemplate},
+};
+use crate::handler::error::MqttBrokerError;
+
+type SqlParam = Box<dyn ToSql + Sync + Send>;
+
+pub struct PostgresBridgePlugin<S> {
+    connector_manager: Arc<ConnectorManager>,
+    message_storage: Arc<S>,
+    connector_name: String,
+    config: RdbConnectorConfig,
+    template: SqlTemplate,
+    stop_send: broadcast::Sender<bool>,
+    client: Mutex<Option<Client>>,
+}
+
+impl<S> PostgresBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        connector_manager: Arc<ConnectorManager>,
+        message_storage: Arc<S>,
+        connector_name: String,
+        config: RdbConnectorConfig,
+        stop_send: broadcast::Sender<bool>,
+    ) -> Result<Self, MqttBrokerError> {
+        let template = parse_sql_template(&config.sql_template, PlaceholderStyle::Postgres)?;
+
+        Ok(PostgresBridgePlugin {
+            connector_manager,
+            message_storage,
+            connector_name,
+            config,
+            template,
+            stop_send,
+            client: Mutex::new(None),
+        })
+    }
+}
+
+// Postgres does not convert parameter types implicitly, so every value is converted
+// to the type the server inferred for its placeholder
+pub fn to_postgres_param(value: Value, param_type: &Type) -> SqlParam {
+    let as_f64 = |value: &Value| match value {
+        Value::Number(num) => num.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        Value::Bool(b) => Some(*b as i64 as f64),
+        _ => None,
+    };
+
+    match *param_type {
+        Type::BOOL => Box::new(match &value {
+            Value::Bool(b) => Some(*b),
+            Value::Null => None,
+            other => as_f64(other).map(|n| n != 0.0),
+        }),
+        Type::INT2 => Box::new(as_f64(&value).map(|n| n as i16)),
+        Type::INT4 => Box::new(as_f64(&value).map(|n| n as i32)),
+        Type::INT8 => Box::new(as_f64(&value).map(|n| n as i64)),
+        Type::FLOAT4 => Box::new(as_f64(&value).map(|n| n as f32)),
+        Type::FLOAT8 => Box::new(as_f64(&value)),
+        Type::JSON | Type::JSONB => Box::new(match value {
+            Value::Null => None,
+            // A payload that is itself JSON text is stored as the document, not as a string
+            Value::String(s) => Some(serde_json::from_str::<Value>(&s).unwrap_or(Value::String(s))),
+            other => Some(other),
+        }),
+        _ => Box::new(match value {
+            Value::Null => None,
+            Value::String(s) => Some(s),
+            other => Some(other.to_string()),
+        }),
+    }
+}
+
+#[async_trait]
+impl<S> BridgeSink for PostgresBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn append(&self, records: &[Record]) -> Result<(), MqttBrokerError> {
+        let mut client = self.client.lock().await;
+        if client.as_ref().is_none_or(|c| c.is_closed()) {
+            *client = Some(build_postgres_client(&self.config.url).await?);
+        }
+        let client = client.as_mut().unwrap();
+
+        let tx = client.transaction().await?;
+        let statement = tx.prepare(&self.template.statement).await?;
+        for record in records {
+            let message = match MqttMessage::decode_record(record.clone()) {
+                Ok(message) => message,
+                Err(e) => {
+                    warn!(
+                        "Connector {} skipped a record that is not an MQTT message, error message: {}",
+                        self.connector_name, e
+                    );
+                    continue;
+                }
+            };
+
+            let params: Vec<SqlParam> = self
+                .template
+                .fields
+                .iter()
+                .zip(statement.params())
+                .map(|(field, param_type)| {
+                    to_postgres_param(message_field(&message, field), param_type)
+                })
+                .collect();
+            let params: Vec<&(dyn ToSql + Sync)> = params
+                .iter()
+                .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+                .collect();
+            tx.execute(&statement, &params).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S> BridgePlugin for PostgresBridgePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
+        run_bridge_sink(
+            &self.message_storage,
+            &self.connector_manager,
+            &self.connector_name,
+            &self.stop_send,
+            &config,
+            self,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use serde_json::json;
+    use tokio_postgres::types::{IsNull, Type};
+
+    use super::to_postgres_param;
+
+    fn encode(value: serde_json::Value, param_type: &Type) -> (IsNull, BytesMut) {
+        let mut buf = BytesMut::new();
+        let is_null = to_postgres_param(value, param_type)
+            .to_sql_checked(param_type, &mut buf)
+            .unwrap();
+        (is_null, buf)
+    }
+
+    #[test]
+    fn to_postgres_param_test() {
+        let (is_null, buf) = encode(json!(81), &Type::INT4);
+        assert!(matches!(is_null, IsNull::No));
+        assert_eq!(buf.as_ref(), 81i32.to_be_bytes());
+
+        // Numeric strings are accepted for numeric columns
+        let (_, buf) = encode(json!("2.5"), &Type::FLOAT8);
+        assert_eq!(buf.as_ref(), 2.5f64.to_be_bytes());
+
+        let (is_null, _) = encode(json!(null), &Type::INT8);
+        assert!(matches!(is_null, IsNull::Yes));
+
+        let (_, buf) = encode(json!(1), &Type::TEXT);
+        assert_eq!(buf.as_ref(), b"1");
+    }
+}
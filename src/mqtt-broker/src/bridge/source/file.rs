@@ -0,0 +1,279 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::{collections::VecDeque, fs::Metadata, io::SeekFrom, sync::Arc, time::Duration};
+
+use axum::async_trait;
+use bytes::Bytes;
+use metadata_struct::mqtt::bridge::config_source::FileSourceConnectorConfig;
+use storage_adapter::storage::StorageAdapter;
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+    select,
+    sync::broadcast,
+    time::sleep,
+};
+use tracing::{error, info};
+
+use super::{SourceMessage, SourcePublisher};
+use crate::{
+    bridge::{
+        core::{BridgePlugin, BridgePluginReadConfig},
+        manager::ConnectorManager,
+    },
+    handler::error::MqttBrokerError,
+};
+
+pub struct FileSourcePlugin<S> {
+    connector_manager: Arc<ConnectorManager>,
+    publisher: SourcePublisher<S>,
+    connector_name: String,
+    config: FileSourceConnectorConfig,
+    stop_send: broadcast::Sender<bool>,
+}
+
+// The file stays open between polls and is only reopened when the path points at a new file
+#[derive(Default)]
+struct TailState {
+    file: Option<File>,
+    file_id: Option<(u64, u64)>,
+    position: u64,
+    // Bytes after the last newline, completed by a later read
+    partial: Vec<u8>,
+    pending: VecDeque<Bytes>,
+}
+
+impl TailState {
+    async fn open(&mut self, path: &str, position: u64) -> Result<(), MqttBrokerError> {
+        let mut file = File::open(path).await?;
+        file.seek(SeekFrom::Start(position)).await?;
+        self.file_id = file_id(&file.metadata().await?);
+        self.file = Some(file);
+        self.position = position;
+        Ok(())
+    }
+
+    async fn read_appended(&mut self, path: &str) -> Result<(), MqttBrokerError> {
+        // A rotated file may not have been recreated yet, the open one is read until it is
+        let path_id = tokio::fs::metadata(path)
+            .await
+            .ok()
+            .and_then(|meta| file_id(&meta));
+
+        if self.file.is_none() {
+            self.open(path, self.position).await?;
+        }
+        let Some(file) = self.file.as_mut() else {
+            return Ok(());
+        };
+
+        // The file was truncated, start over from its beginning
+        if file.metadata().await?.len() < self.position {
+            file.seek(SeekFrom::Start(0)).await?;
+            self.position = 0;
+            self.partial.clear();
+        }
+
+        let read = file.read_to_end(&mut self.partial).await?;
+        self.position += read as u64;
+        self.pending.extend(take_lines(&mut self.partial));
+
+        // The file was rotated, the old one has been read to its end, continue with the new one from its beginning
+        if path_id.is_some() && path_id != self.file_id {
+            if !self.partial.is_empty() {
+                self.pending
+                    .push_back(Bytes::from(std::mem::take(&mut self.partial)));
+            }
+            self.file = None;
+            self.open(path, 0).await?;
+        }
+        Ok(())
+    }
+}
+
+// Files are told apart by device and inode, elsewhere only truncation is detected
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+impl<S> FileSourcePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        connector_manager: Arc<ConnectorManager>,
+        publisher: SourcePublisher<S>,
+        connector_name: String,
+        config: FileSourceConnectorConfig,
+        stop_send: broadcast::Sender<bool>,
+    ) -> Self {
+        FileSourcePlugin {
+            connector_manager,
+            publisher,
+            connector_name,
+            config,
+            stop_send,
+        }
+    }
+
+    async fn publish_pending(&self, state: &mut TailState) -> Result<(), MqttBrokerError> {
+        while let Some(line) = state.pending.front() {
+            let message = SourceMessage {
+                source_topic: String::new(),
+                payload: line.clone(),
+                retain: false,
+                user_properties: Vec::new(),
+            };
+            self.publisher
+                .publish(&self.connector_name, &self.config.publish, message)
+                .await?;
+            state.pending.pop_front();
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S> BridgePlugin for FileSourcePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn exec(&self, _config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
+        let mut state = TailState::default();
+        let position = if self.config.from_beginning {
+            0
+        } else {
+            tokio::fs::metadata(&self.config.local_file_path)
+                .await?
+                .len()
+        };
+        state.open(&self.config.local_file_path, position).await?;
+
+        let mut recv = self.stop_send.subscribe();
+        loop {
+            self.connector_manager
+                .report_heartbeat(&self.connector_name);
+
+            if let Err(e) = state.read_appended(&self.config.local_file_path).await {
+                error!(
+                    "Connector {} failed to read file {}, error message: {}",
+                    self.connector_name, self.config.local_file_path, e
+                );
+            }
+
+            // Lines that failed to publish stay pending and are retried on the next poll
+            if let Err(e) = self.publish_pending(&mut state).await {
+                error!(
+                    "Connector {} failed to publish a line of file {}, error message: {}",
+                    self.connector_name, self.config.local_file_path, e
+                );
+            }
+
+            select! {
+                val = recv.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("Connector {} thread exited successfully", self.connector_name);
+                            break;
+                        }
+                    }
+                }
+                _ = sleep(Duration::from_millis(self.config.poll_interval_ms)) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+// Split off every complete line, leaving an unterminated last line in the buffer
+fn take_lines(buf: &mut Vec<u8>) -> Vec<Bytes> {
+    let Some(end) = buf.iter().rposition(|b| *b == b'\n') else {
+        return Vec::new();
+    };
+    let rest = buf.split_off(end + 1);
+    let complete = std::mem::replace(buf, rest);
+
+    complete
+        .split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .map(Bytes::copy_from_slice)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+
+    use super::{take_lines, TailState};
+
+    #[test]
+    fn take_lines_test() {
+        let mut buf = b"first\r\n\nsecond\nthi".to_vec();
+        let lines = take_lines(&mut buf);
+        assert_eq!(lines, vec!["first", "second"]);
+        assert_eq!(buf, b"thi");
+
+        buf.extend_from_slice(b"rd\n");
+        assert_eq!(take_lines(&mut buf), vec!["third"]);
+        assert!(buf.is_empty());
+
+        let mut buf = b"no newline".to_vec();
+        assert!(take_lines(&mut buf).is_empty());
+        assert_eq!(buf, b"no newline");
+    }
+
+    #[tokio::test]
+    async fn tail_state_rotate_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("source.log");
+        let path_str = path.to_str().unwrap();
+        tokio::fs::write(&path, b"a\nb").await.unwrap();
+
+        let mut state = TailState::default();
+        state.open(path_str, 0).await.unwrap();
+        state.read_appended(path_str).await.unwrap();
+        assert_eq!(state.pending.drain(..).collect::<Vec<_>>(), vec!["a"]);
+
+        let mut file = OpenOptions::new().append(true).open(&path).await.unwrap();
+        file.write_all(b"\nc\n").await.unwrap();
+        file.flush().await.unwrap();
+        state.read_appended(path_str).await.unwrap();
+        assert_eq!(state.pending.drain(..).collect::<Vec<_>>(), vec!["b", "c"]);
+
+        // rotate: the old file gets a last line after the rename, then a new file is created
+        tokio::fs::rename(&path, dir.path().join("source.log.1"))
+            .await
+            .unwrap();
+        file.write_all(b"d\n").await.unwrap();
+        file.flush().await.unwrap();
+        tokio::fs::write(&path, b"eee\n").await.unwrap();
+        state.read_appended(path_str).await.unwrap();
+        assert_eq!(state.pending.drain(..).collect::<Vec<_>>(), vec!["d"]);
+        state.read_appended(path_str).await.unwrap();
+        assert_eq!(state.pending.drain(..).collect::<Vec<_>>(), vec!["eee"]);
+
+        // truncate in place
+        tokio::fs::write(&path, b"f\n").await.unwrap();
+        state.read_appended(path_str).await.unwrap();
+        assert_eq!(state.pending.drain(..).collect::<Vec<_>>(), vec!["f"]);
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::{sync::Arc, time::Duration};
+
+use axum::async_trait;
+use bytes::Bytes;
+use metadata_struct::mqtt::bridge::config_source::KafkaSourceConnectorConfig;
+use rdkafka::{
+    consumer::{CommitMode, Consumer, StreamConsumer},
+    message::{BorrowedMessage, Headers, Message},
+    Offset,
+};
+use storage_adapter::storage::StorageAdapter;
+use tokio::{select, sync::broadcast, time::sleep};
+use tracing::{error, info};
+
+use super::{SourceMessage, SourcePublisher};
+use crate::{
+    bridge::{
+        core::{BridgePlugin, BridgePluginReadConfig},
+        manager::ConnectorManager,
+    },
+    handler::error::MqttBrokerError,
+};
+
+pub struct KafkaSourcePlugin<S> {
+    connector_manager: Arc<ConnectorManager>,
+    publisher: SourcePublisher<S>,
+    connector_name: String,
+    config: KafkaSourceConnectorConfig,
+    stop_send: broadcast::Sender<bool>,
+}
+
+impl<S> KafkaSourcePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        connector_manager: Arc<ConnectorManager>,
+        publisher: SourcePublisher<S>,
+        connector_name: String,
+        config: KafkaSourceConnectorConfig,
+        stop_send: broadcast::Sender<bool>,
+    ) -> Self {
+        KafkaSourcePlugin {
+            connector_manager,
+            publisher,
+            connector_name,
+            config,
+            stop_send,
+        }
+    }
+
+    fn build_consumer(&self) -> Result<StreamConsumer, MqttBrokerError> {
+        let group_id = self
+            .config
+            .group_id
+            .clone()
+            .unwrap_or_else(|| self.connector_name.clone());
+        let consumer: StreamConsumer = rdkafka::ClientConfig::new()
+            .set("bootstrap.servers", self.config.bootstrap_servers.as_str())
+            .set("group.id", group_id.as_str())
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()?;
+        consumer.subscribe(&[self.config.topic.as_str()])?;
+        Ok(consumer)
+    }
+
+    async fn process(
+        &self,
+        consumer: &StreamConsumer,
+        message: &BorrowedMessage<'_>,
+    ) -> Result<(), MqttBrokerError> {
+        let mut user_properties = Vec::new();
+        if let Some(key) = message.key() {
+            user_properties.push((
+                "kafka_key".to_string(),
+                String::from_utf8_lossy(key).to_string(),
+            ));
+        }
+        if let Some(headers) = message.headers() {
+            for header in headers.iter() {
+                if let Some(value) = header.value {
+                    user_properties.push((
+                        header.key.to_string(),
+                        String::from_utf8_lossy(value).to_string(),
+                    ));
+                }
+            }
+        }
+
+        let source_message = SourceMessage {
+            source_topic: message.topic().to_string(),
+            payload: Bytes::copy_from_slice(message.payload().unwrap_or_default()),
+            retain: false,
+            user_properties,
+        };
+        self.publisher
+            .publish(&self.connector_name, &self.config.publish, source_message)
+            .await?;
+
+        // Only commit once the message is saved, a crash in between redelivers it
+        consumer.commit_message(message, CommitMode::Async)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S> BridgePlugin for KafkaSourcePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn exec(&self, _config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
+        let consumer = self.build_consumer()?;
+        let mut recv = self.stop_send.subscribe();
+
+        loop {
+            self.connector_manager
+                .report_heartbeat(&self.connector_name);
+            select! {
+                val = recv.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("Connector {} thread exited successfully", self.connector_name);
+                            break;
+                        }
+                    }
+                }
+
+                val = consumer.recv() => {
+                    match val {
+                        Ok(message) => {
+                            if let Err(e) = self.process(&consumer, &message).await {
+                                error!("Connector {} failed to publish a message from kafka topic {}, error message: {}", self.connector_name, self.config.topic, e);
+                                // Rewind the partition so the message is consumed again
+                                if let Err(e) = consumer.seek(message.topic(), message.partition(), Offset::Offset(message.offset()), Duration::from_secs(1)) {
+                                    error!("Connector {} failed to rewind kafka topic {}, error message: {}", self.connector_name, self.config.topic, e);
+                                }
+                                sleep(Duration::from_secs(1)).await;
+                            }
+                        }
+                        Err(e) => {
+                            error!("Connector {} failed to consume kafka topic {}, error message: {}", self.connector_name, self.config.topic, e);
+                            sleep(Duration::from_millis(100)).await;
+                        }
+                    }
+                }
+
+                _ = sleep(Duration::from_secs(1)) => {}
+            }
+        }
+        Ok(())
+    }
+}
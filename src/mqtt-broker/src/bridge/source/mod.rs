@@ -0,0 +1,153 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Arc;
+
+use bytes::Bytes;
+use delay_message::DelayMessageManager;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::bridge::config_source::SourcePublishConfig;
+use protocol::mqtt::common::{qos, Publish, PublishProperties};
+use storage_adapter::storage::StorageAdapter;
+
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::offline_message::save_message;
+use crate::handler::topic::{topic_name_validator, try_init_topic};
+use crate::subscribe::common::is_wildcards;
+use crate::subscribe::manager::SubscribeManager;
+
+pub mod file;
+pub mod kafka;
+pub mod mqtt;
+
+const SOURCE_TOPIC_PLACEHOLDER: &str = "${topic}";
+
+// A message read by a source connector, before it is published into RobustMQ
+pub struct SourceMessage {
+    // The Kafka topic or remote MQTT topic it came from, empty for file sources
+    pub source_topic: String,
+    pub payload: Bytes,
+    pub retain: bool,
+    pub user_properties: Vec<(String, String)>,
+}
+
+// Publishes the messages of source connectors the same way a client publish is saved,
+// so subscribers, retained messages and offline messages all see them.
+#[derive(Clone)]
+pub struct SourcePublisher<S> {
+    cache_manager: Arc<CacheManager>,
+    client_pool: Arc<ClientPool>,
+    message_storage_adapter: Arc<S>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
+    subscribe_manager: Arc<SubscribeManager>,
+}
+
+impl<S> SourcePublisher<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        cache_manager: Arc<CacheManager>,
+        client_pool: Arc<ClientPool>,
+        message_storage_adapter: Arc<S>,
+        delay_message_manager: Arc<DelayMessageManager<S>>,
+        subscribe_manager: Arc<SubscribeManager>,
+    ) -> Self {
+        SourcePublisher {
+            cache_manager,
+            client_pool,
+            message_storage_adapter,
+            delay_message_manager,
+            subscribe_manager,
+        }
+    }
+
+    pub async fn publish(
+        &self,
+        connector_name: &str,
+        config: &SourcePublishConfig,
+        message: SourceMessage,
+    ) -> Result<(), MqttBrokerError> {
+        let topic_name = render_source_topic(&config.topic, &message.source_topic)?;
+        let target_qos = qos(config.qos).ok_or_else(|| {
+            MqttBrokerError::CommonError(format!("Invalid source connector qos {}", config.qos))
+        })?;
+
+        let topic = try_init_topic(
+            &topic_name,
+            &self.cache_manager,
+            &self.message_storage_adapter,
+            &self.client_pool,
+        )
+        .await?;
+
+        let publish = Publish {
+            dup: false,
+            qos: target_qos,
+            pkid: 0,
+            retain: config.retain.unwrap_or(message.retain),
+            topic: Bytes::from(topic_name),
+            payload: message.payload,
+        };
+        let publish_properties = if message.user_properties.is_empty() {
+            None
+        } else {
+            Some(PublishProperties {
+                user_properties: message.user_properties,
+                ..Default::default()
+            })
+        };
+
+        save_message(
+            &self.message_storage_adapter,
+            &self.delay_message_manager,
+            &self.cache_manager,
+            &publish,
+            &publish_properties,
+            &self.subscribe_manager,
+            connector_name,
+            &topic,
+            &None,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+pub fn render_source_topic(template: &str, source_topic: &str) -> Result<String, MqttBrokerError> {
+    let topic_name = template.replace(SOURCE_TOPIC_PLACEHOLDER, source_topic);
+    topic_name_validator(&topic_name)?;
+    if is_wildcards(&topic_name) {
+        return Err(MqttBrokerError::TopicNameIncorrectlyFormatted(topic_name));
+    }
+    Ok(topic_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_source_topic;
+
+    #[test]
+    fn render_source_topic_test() {
+        assert_eq!(
+            render_source_topic("device/${topic}", "commands").unwrap(),
+            "device/commands"
+        );
+        assert_eq!(
+            render_source_topic("device/fixed", "commands").unwrap(),
+            "device/fixed"
+        );
+        assert!(render_source_topic("device/${topic}", "a/+").is_err());
+    }
+}
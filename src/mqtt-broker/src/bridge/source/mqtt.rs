@@ -0,0 +1,184 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::{sync::Arc, time::Duration};
+
+use axum::async_trait;
+use bytes::Bytes;
+use metadata_struct::mqtt::bridge::config_source::MqttSourceConnectorConfig;
+use paho_mqtt::{AsyncClient, ConnectOptionsBuilder, CreateOptionsBuilder, Message};
+use storage_adapter::storage::StorageAdapter;
+use tokio::{select, sync::broadcast, time::sleep};
+use tracing::{error, info, warn};
+
+use super::{SourceMessage, SourcePublisher};
+use crate::{
+    bridge::{
+        core::{BridgePlugin, BridgePluginReadConfig},
+        manager::ConnectorManager,
+    },
+    handler::error::MqttBrokerError,
+};
+
+const DEFAULT_CLIENT_ID_PREFIX: &str = "robustmq_source_";
+
+pub struct MqttSourcePlugin<S> {
+    connector_manager: Arc<ConnectorManager>,
+    publisher: SourcePublisher<S>,
+    connector_name: String,
+    config: MqttSourceConnectorConfig,
+    stop_send: broadcast::Sender<bool>,
+}
+
+impl<S> MqttSourcePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        connector_manager: Arc<ConnectorManager>,
+        publisher: SourcePublisher<S>,
+        connector_name: String,
+        config: MqttSourceConnectorConfig,
+        stop_send: broadcast::Sender<bool>,
+    ) -> Self {
+        MqttSourcePlugin {
+            connector_manager,
+            publisher,
+            connector_name,
+            config,
+            stop_send,
+        }
+    }
+
+    fn build_client(&self) -> Result<AsyncClient, MqttBrokerError> {
+        let prefix = if self.config.client_id_prefix.is_empty() {
+            DEFAULT_CLIENT_ID_PREFIX
+        } else {
+            self.config.client_id_prefix.as_str()
+        };
+        let create_opts = CreateOptionsBuilder::new()
+            .server_uri(self.config.server.as_str())
+            .client_id(format!("{}{}", prefix, self.connector_name))
+            .finalize();
+        Ok(AsyncClient::new(create_opts)?)
+    }
+
+    // The session is not kept by the remote broker, so the subscription is renewed on every connect
+    async fn connect(&self, client: &AsyncClient) -> Result<(), MqttBrokerError> {
+        let mut builder = ConnectOptionsBuilder::new();
+        builder
+            .keep_alive_interval(Duration::from_secs(self.config.keep_alive))
+            .clean_session(true);
+        if let Some(username) = &self.config.username {
+            builder.user_name(username.as_str());
+        }
+        if let Some(password) = &self.config.password {
+            builder.password(password.as_str());
+        }
+        client.connect(builder.finalize()).await?;
+        client
+            .subscribe(
+                self.config.remote_topic.as_str(),
+                self.config.remote_qos as i32,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn process(&self, message: Message) -> Result<(), MqttBrokerError> {
+        let source_message = SourceMessage {
+            source_topic: message.topic().to_string(),
+            payload: Bytes::copy_from_slice(message.payload()),
+            retain: message.retained(),
+            user_properties: message.properties().user_iter().collect(),
+        };
+        self.publisher
+            .publish(&self.connector_name, &self.config.publish, source_message)
+            .await
+    }
+}
+
+#[async_trait]
+impl<S> BridgePlugin for MqttSourcePlugin<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn exec(&self, _config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
+        let mut client = self.build_client()?;
+        let stream = client.get_stream(1024);
+        let mut recv = self.stop_send.subscribe();
+
+        loop {
+            self.connector_manager
+                .report_heartbeat(&self.connector_name);
+
+            if !client.is_connected() {
+                if let Err(e) = self.connect(&client).await {
+                    error!(
+                        "Connector {} failed to connect to remote broker {}, error message: {}",
+                        self.connector_name, self.config.server, e
+                    );
+                    select! {
+                        val = recv.recv() =>{
+                            if let Ok(flag) = val {
+                                if flag {
+                                    info!("Connector {} thread exited successfully", self.connector_name);
+                                    break;
+                                }
+                            }
+                        }
+                        _ = sleep(Duration::from_secs(1)) => {}
+                    }
+                    continue;
+                }
+            }
+
+            select! {
+                val = recv.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("Connector {} thread exited successfully", self.connector_name);
+                            break;
+                        }
+                    }
+                }
+
+                val = stream.recv() => {
+                    match val {
+                        Ok(Some(message)) => {
+                            let topic = message.topic().to_string();
+                            if let Err(e) = self.process(message).await {
+                                error!("Connector {} failed to publish a message from remote topic {}, error message: {}", self.connector_name, topic, e);
+                            }
+                        }
+                        // None is delivered when the connection to the remote broker is lost
+                        Ok(None) => {
+                            warn!("Connector {} lost the connection to remote broker {}", self.connector_name, self.config.server);
+                        }
+                        Err(e) => {
+                            error!("Connector {} message stream closed, error message: {}", self.connector_name, e);
+                            break;
+                        }
+                    }
+                }
+
+                _ = sleep(Duration::from_secs(1)) => {}
+            }
+        }
+
+        if client.is_connected() {
+            let _ = client.disconnect(None).await;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use metadata_struct::mqtt::message::MqttMessage;
+use regex::{Captures, Regex};
+use serde_json::{json, Value};
+
+use crate::handler::error::MqttBrokerError;
+
+// ${client_id}, ${payload.temp}, ${user_properties.site}
+const TEMPLATE_FIELD_REGEX: &str = r"\$\{([A-Za-z0-9_.]+)\}";
+
+pub enum PlaceholderStyle {
+    // ?
+    MySql,
+    // $1, $2 ...
+    Postgres,
+}
+
+/// An INSERT template with its placeholders replaced by statement parameters,
+/// `fields[i]` is the message field bound to the i-th parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SqlTemplate {
+    pub statement: String,
+    pub fields: Vec<String>,
+}
+
+pub fn parse_sql_template(
+    template: &str,
+    style: PlaceholderStyle,
+) -> Result<SqlTemplate, MqttBrokerError> {
+    let regex = Regex::new(TEMPLATE_FIELD_REGEX)?;
+    let mut fields = Vec::new();
+    let statement = regex
+        .replace_all(template, |caps: &Captures| {
+            fields.push(caps[1].to_string());
+            match style {
+                PlaceholderStyle::MySql => "?".to_string(),
+                PlaceholderStyle::Postgres => format!("${}", fields.len()),
+            }
+        })
+        .to_string();
+
+    if fields.is_empty() {
+        return Err(MqttBrokerError::CommonError(format!(
+            "SQL template {} does not reference any message field",
+            template
+        )));
+    }
+    Ok(SqlTemplate { statement, fields })
+}
+
+pub fn render_template(template: &str, message: &MqttMessage) -> Result<String, MqttBrokerError> {
+    let regex = Regex::new(TEMPLATE_FIELD_REGEX)?;
+    let rendered = regex.replace_all(template, |caps: &Captures| {
+        match message_field(message, &caps[1]) {
+            Value::Null => String::new(),
+            Value::String(s) => s,
+            other => other.to_string(),
+        }
+    });
+    Ok(rendered.to_string())
+}
+
+// Resolve a template field of a message, unknown fields resolve to null
+pub fn message_field(message: &MqttMessage, path: &str) -> Value {
+    let mut keys = path.split('.');
+    let root = keys.next().unwrap_or_default();
+    let value = match root {
+        "client_id" | "clientid" => Value::String(message.client_id.clone()),
+        "topic" => Value::String(String::from_utf8_lossy(&message.topic).to_string()),
+        "qos" => json!(message.qos as u8),
+        "retain" => Value::Bool(message.retain),
+        "timestamp" | "create_time" => json!(message.create_time),
+        "content_type" => json!(message.content_type),
+        "response_topic" => json!(message.response_topic),
+        "payload" => {
+            let rest: Vec<&str> = keys.by_ref().collect();
+            if rest.is_empty() {
+                return Value::String(String::from_utf8_lossy(&message.payload).to_string());
+            }
+            let Ok(payload) = serde_json::from_slice::<Value>(&message.payload) else {
+                return Value::Null;
+            };
+            return lookup(&payload, &rest);
+        }
+        "user_properties" => {
+            let Some(key) = keys.next() else {
+                return json!(message.user_properties);
+            };
+            return message
+                .user_properties
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(Value::Null, |(_, v)| Value::String(v.clone()));
+        }
+        _ => Value::Null,
+    };
+    if keys.next().is_some() {
+        return Value::Null;
+    }
+    value
+}
+
+fn lookup(value: &Value, path: &[&str]) -> Value {
+    let mut current = value;
+    for key in path {
+        let next = match current {
+            Value::Object(map) => map.get(*key),
+            Value::Array(list) => key.parse::<usize>().ok().and_then(|i| list.get(i)),
+            _ => None,
+        };
+        match next {
+            Some(value) => current = value,
+            None => return Value::Null,
+        }
+    }
+    current.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use protocol::mqtt::common::QoS;
+    use serde_json::json;
+
+    use super::{message_field, parse_sql_template, render_template, PlaceholderStyle};
+
+    fn message() -> MqttMessage {
+        MqttMessage {
+            client_id: "c1".to_string(),
+            qos: QoS::AtLeastOnce,
+            topic: Bytes::from("sensors/1/data"),
+            payload: Bytes::from(r#"{"temp": 81, "tags": ["a", "b"]}"#),
+            user_properties: vec![("site".to_string(), "sh".to_string())],
+            create_time: 1736234580,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn message_field_test() {
+        let message = message();
+        assert_eq!(message_field(&message, "client_id"), json!("c1"));
+        assert_eq!(message_field(&message, "qos"), json!(1));
+        assert_eq!(message_field(&message, "payload.temp"), json!(81));
+        assert_eq!(message_field(&message, "payload.tags.1"), json!("b"));
+        assert_eq!(message_field(&message, "payload.none"), json!(null));
+        assert_eq!(message_field(&message, "user_properties.site"), json!("sh"));
+        assert_eq!(message_field(&message, "timestamp"), json!(1736234580));
+        assert_eq!(message_field(&message, "client_id.x"), json!(null));
+        assert_eq!(message_field(&message, "unknown"), json!(null));
+    }
+
+    #[test]
+    fn parse_sql_template_test() {
+        let template = "INSERT INTO t(client_id, temp, ts) VALUES (${client_id}, ${payload.temp}, ${timestamp})";
+        let sql = parse_sql_template(template, PlaceholderStyle::MySql).unwrap();
+        assert_eq!(
+            sql.statement,
+            "INSERT INTO t(client_id, temp, ts) VALUES (?, ?, ?)"
+        );
+        assert_eq!(sql.fields, vec!["client_id", "payload.temp", "timestamp"]);
+
+        let sql = parse_sql_template(template, PlaceholderStyle::Postgres).unwrap();
+        assert_eq!(
+            sql.statement,
+            "INSERT INTO t(client_id, temp, ts) VALUES ($1, $2, $3)"
+        );
+
+        assert!(parse_sql_template("INSERT INTO t VALUES (1)", PlaceholderStyle::MySql).is_err());
+    }
+
+    #[test]
+    fn render_template_test() {
+        let message = message();
+        assert_eq!(
+            render_template("cloud/${topic}", &message).unwrap(),
+            "cloud/sensors/1/data"
+        );
+        assert_eq!(
+            render_template("site/${user_properties.site}/${payload.temp}", &message).unwrap(),
+            "site/sh/81"
+        );
+    }
+}
@@ -21,6 +21,7 @@
 };
 
 use common_base::tools::now_second;
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use protocol::mqtt::common::QoS;
 use tokio::time::sleep;
@@ -38,6 +39,9 @@ pub struct PkidManager {
     // (client_id_pkid, QosPkidData)
     pub client_pkid_data: DashMap<String, ClientPkidData>,
 
+    // (client_id, number of pushed packets waiting for an ack)
+    pub inflight_num: DashMap<String, u64>,
+
     pub pkid_atomic: Arc<AtomicU64>,
 }
 
@@ -53,6 +57,7 @@ pub fn new() -> Self {
             pkid_cache: DashMap::with_capacity(8),
             qos_ack_packet: DashMap::with_capacity(8),
             client_pkid_data: DashMap::with_capacity(8),
+            inflight_num: DashMap::with_capacity(8),
             pkid_atomic: Arc::new(AtomicU64::new(1)),
         }
     }
@@ -69,6 +74,7 @@ pub fn remove_by_client_id(&self, client_id: &str) {
                 self.qos_ack_packet.remove(&key);
             }
         }
+        self.inflight_num.remove(client_id);
     }
 
     // sub => pub push pkid generate
@@ -96,16 +102,43 @@ pub async fn generate_pkid(&self, client_id: &str, qos: &QoS) -> u16 {
         }
     }
 
+    // Take a pkid restored from the persisted inflight state so that it is not handed out
+    // again before the restored message is acknowledged. Returns false if it is in use.
+    pub fn reserve_pkid(&self, client_id: &str, pkid: u16) -> bool {
+        let key = self.key(client_id, pkid);
+        match self.pkid_cache.entry(key) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(now_second());
+                true
+            }
+        }
+    }
+
     // ack packet
     pub fn remove_ack_packet(&self, client_id: &str, pkid: u16) {
         let key = self.key(client_id, pkid);
-        self.qos_ack_packet.remove(&key);
+        if self.qos_ack_packet.remove(&key).is_some() {
+            if let Some(mut num) = self.inflight_num.get_mut(client_id) {
+                *num = num.saturating_sub(1);
+            }
+            self.inflight_num.remove_if(client_id, |_, num| *num == 0);
+        }
         self.pkid_cache.remove(&key);
     }
 
     pub fn add_ack_packet(&self, client_id: &str, pkid: u16, packet: QosAckPacketInfo) {
         let key = self.key(client_id, pkid);
-        self.qos_ack_packet.insert(key, packet);
+        if self.qos_ack_packet.insert(key, packet).is_none() {
+            *self.inflight_num.entry(client_id.to_owned()).or_insert(0) += 1;
+        }
+    }
+
+    pub fn inflight_num(&self, client_id: &str) -> u64 {
+        self.inflight_num
+            .get(client_id)
+            .map(|num| *num)
+            .unwrap_or_default()
     }
 
     pub fn get_ack_packet(&self, client_id: &str, pkid: u16) -> Option<QosAckPacketInfo> {
@@ -25,6 +25,17 @@
 
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::handler::inflight::is_persistent_session;
+
+// The inbound QoS2 state of a persistent session has to survive a reconnect to another
+// broker, otherwise a retransmitted PUBLISH would be delivered twice.
+fn is_pkid_persistent(cache_manager: &Arc<CacheManager>, client_id: &str) -> bool {
+    cache_manager
+        .get_cluster_config()
+        .mqtt_protocol_config
+        .client_pkid_persistent
+        || is_persistent_session(cache_manager, client_id)
+}
 
 pub async fn pkid_save(
     cache_manager: &Arc<CacheManager>,
@@ -32,11 +43,7 @@ pub async fn pkid_save(
     client_id: &str,
     pkid: u16,
 ) -> Result<(), MqttBrokerError> {
-    if cache_manager
-        .get_cluster_config()
-        .mqtt_protocol_config
-        .client_pkid_persistent
-    {
+    if is_pkid_persistent(cache_manager, client_id) {
         let conf = broker_mqtt_conf();
         let request = SetIdempotentDataRequest {
             cluster_name: conf.cluster_name.clone(),
@@ -63,11 +70,7 @@ pub async fn pkid_exists(
     client_id: &str,
     pkid: u16,
 ) -> Result<bool, MqttBrokerError> {
-    if cache_manager
-        .get_cluster_config()
-        .mqtt_protocol_config
-        .client_pkid_persistent
-    {
+    if is_pkid_persistent(cache_manager, client_id) {
         let conf = broker_mqtt_conf();
         let request = ExistsIdempotentDataRequest {
             cluster_name: conf.cluster_name.clone(),
@@ -92,11 +95,7 @@ pub async fn pkid_delete(
     client_id: &str,
     pkid: u16,
 ) -> Result<(), MqttBrokerError> {
-    if cache_manager
-        .get_cluster_config()
-        .mqtt_protocol_config
-        .client_pkid_persistent
-    {
+    if is_pkid_persistent(cache_manager, client_id) {
         let conf = broker_mqtt_conf();
         let request = DeleteIdempotentDataRequest {
             cluster_name: conf.cluster_name.clone(),
@@ -13,8 +13,18 @@
 // limitations under the License.
 
 use crate::common::pkid_manager::PkidManager;
-use crate::observability::system_topic::sysmon::SystemAlarmEventMessage;
+use crate::handler::inflight::InflightPersistManager;
+use crate::handler::offline_queue::OfflineQueueManager;
+use crate::handler::request_response::RequestResponseManager;
+use crate::handler::retain::RetainMessageManager;
+use crate::handler::tenant::TenantPublishWindow;
+use crate::hook::HookManager;
+use crate::observability::live_trace::LiveTraceManager;
+use crate::observability::topic_metrics::TopicMetricsManager;
+use crate::observability::warn::AlarmManager;
+use crate::rule::CompiledRule;
 use crate::security::acl::metadata::AclMetadata;
+use crate::subscribe::trie::TopicTrie;
 use common_base::tools::now_second;
 use common_config::mqtt::config::BrokerMqttConfig;
 use dashmap::DashMap;
@@ -24,6 +34,7 @@
 use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
 use metadata_struct::mqtt::connection::MQTTConnection;
 use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::tenant::MqttTenant;
 use metadata_struct::mqtt::topic::MqttTopic;
 use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
 use metadata_struct::mqtt::user::MqttUser;
@@ -32,7 +43,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::sync::Arc;
-use tokio::sync::broadcast::Sender;
+use tokio::sync::broadcast::{self, Sender};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub enum MetadataCacheAction {
@@ -117,6 +128,12 @@ pub struct CacheManager {
     // (topic_id, topic_name)
     pub topic_id_name: DashMap<String, String>,
 
+    // Topic names keyed by themselves, to find the topics a filter matches
+    pub topic_trie: TopicTrie<String>,
+
+    // Notified with the name of every topic added to the cache for the first time
+    pub topic_create_sender: Sender<String>,
+
     // (client_id, HeartbeatShard)
     pub heartbeat_data: DashMap<String, ConnectionLiveTime>,
 
@@ -132,14 +149,53 @@ pub struct CacheManager {
     // All auto subscribe rule
     pub auto_subscribe_rule: DashMap<String, MqttAutoSubscribeRule>,
 
-    // Alarm Info
-    pub alarm_events: DashMap<String, SystemAlarmEventMessage>,
+    // Alarm rules, active alarms and their history
+    pub alarm_manager: Arc<AlarmManager>,
+
+    // Hooks notified of client lifecycle and message events
+    pub hook_manager: Arc<HookManager>,
+
+    // Live traces of the packets of selected clients or topics
+    pub live_trace_manager: Arc<LiveTraceManager>,
+
+    // Message counters of single topics or topic filters
+    pub topic_metrics_manager: Arc<TopicMetricsManager>,
+
+    // (tenant_name, Tenant)
+    pub tenant_info: DashMap<String, MqttTenant>,
+
+    // (tenant_name, TenantPublishWindow)
+    pub tenant_publish_window: DashMap<String, TenantPublishWindow>,
+
+    // (tenant_name, connection_num of this broker)
+    pub tenant_connection_num: DashMap<String, u64>,
+
+    // (tenant_name, connection_num of the other brokers)
+    pub tenant_remote_connection_num: DashMap<String, u64>,
+
+    // (rule_name, CompiledRule)
+    pub rule_info: DashMap<String, Arc<CompiledRule>>,
+
+    // Retained messages of all topics
+    pub retain_message_manager: Arc<RetainMessageManager>,
+
+    // Requests of the admin API waiting for their response
+    pub request_response: Arc<RequestResponseManager>,
+
+    // Queues of the offline persistent sessions
+    pub offline_queue: Arc<OfflineQueueManager>,
+
+    // Inflight windows of the persistent sessions, written to the placement center in batches
+    pub inflight_persist: Arc<InflightPersistManager>,
 }
 
 impl CacheManager {
     pub fn new(client_pool: Arc<ClientPool>, cluster_name: String) -> Self {
         CacheManager {
             start_time: now_second(),
+            retain_message_manager: Arc::new(RetainMessageManager::placement(client_pool.clone())),
+            inflight_persist: Arc::new(InflightPersistManager::new(client_pool.clone())),
+            offline_queue: Arc::new(OfflineQueueManager::new(client_pool.clone())),
             client_pool,
             cluster_name,
             node_lists: DashMap::with_capacity(2),
@@ -148,13 +204,24 @@ pub fn new(client_pool: Arc<ClientPool>, cluster_name: String) -> Self {
             session_info: DashMap::with_capacity(8),
             topic_info: DashMap::with_capacity(8),
             topic_id_name: DashMap::with_capacity(8),
+            topic_trie: TopicTrie::new(),
+            topic_create_sender: broadcast::channel(10000).0,
             connection_info: DashMap::with_capacity(8),
             heartbeat_data: DashMap::with_capacity(8),
             acl_metadata: AclMetadata::new(),
             pkid_metadata: PkidManager::new(),
             topic_rewrite_rule: DashMap::with_capacity(8),
             auto_subscribe_rule: DashMap::with_capacity(8),
-            alarm_events: DashMap::with_capacity(8),
+            alarm_manager: Arc::new(AlarmManager::default()),
+            hook_manager: Arc::new(HookManager::new()),
+            live_trace_manager: Arc::new(LiveTraceManager::new()),
+            topic_metrics_manager: Arc::new(TopicMetricsManager::new()),
+            tenant_info: DashMap::with_capacity(8),
+            tenant_publish_window: DashMap::with_capacity(8),
+            tenant_connection_num: DashMap::with_capacity(8),
+            tenant_remote_connection_num: DashMap::with_capacity(8),
+            rule_info: DashMap::with_capacity(8),
+            request_response: Arc::new(RequestResponseManager::default()),
         }
     }
 
@@ -200,6 +267,7 @@ pub fn remove_session(&self, client_id: &str) {
         self.session_info.remove(client_id);
         self.heartbeat_data.remove(client_id);
         self.pkid_metadata.remove_by_client_id(client_id);
+        self.offline_queue.remove_session(client_id);
     }
 
     // user
@@ -220,12 +288,18 @@ pub fn retain_users(&self, usernames: HashSet<String>) {
     pub fn add_connection(&self, connect_id: u64, conn: MQTTConnection) {
         if let Some(mut session) = self.session_info.get_mut(&conn.client_id) {
             session.connection_id = Some(connect_id);
-            self.connection_info.insert(connect_id, conn);
+            let tenant = conn.tenant.clone();
+            if let Some(old) = self.connection_info.insert(connect_id, conn) {
+                self.decr_tenant_connection(&old.tenant);
+            }
+            self.incr_tenant_connection(&tenant);
         }
     }
 
     pub fn remove_connection(&self, connect_id: u64) {
-        self.connection_info.remove(&connect_id);
+        if let Some((_, conn)) = self.connection_info.remove(&connect_id) {
+            self.decr_tenant_connection(&conn.tenant);
+        }
     }
 
     pub fn get_connect_id(&self, client_id: &str) -> Option<u64> {
@@ -251,13 +325,27 @@ pub fn get_connection_count(&self) -> usize {
 
     // topic
     pub fn add_topic(&self, topic_name: &str, topic: &MqttTopic) {
-        self.topic_info.insert(topic_name.to_owned(), topic.clone());
+        let is_new = self
+            .topic_info
+            .insert(topic_name.to_owned(), topic.clone())
+            .is_none();
         self.topic_id_name
             .insert(topic.topic_id.clone(), topic_name.to_owned());
+        if self.retain_message_manager.follows_topic_metadata() {
+            self.retain_message_manager.apply_topic(topic);
+        }
+        if is_new {
+            self.topic_trie.insert(topic_name, topic_name.to_owned());
+            // No receiver only means the subscribe parse thread is not running yet
+            let _ = self.topic_create_sender.send(topic_name.to_owned());
+        }
     }
 
     pub fn delete_topic(&self, topic_name: &String, topic: &MqttTopic) {
-        self.topic_info.remove(topic_name);
+        if self.topic_info.remove(topic_name).is_some() {
+            self.topic_trie.remove(topic_name, topic_name);
+        }
+        self.retain_message_manager.remove(topic_name);
         self.topic_id_name.remove(&topic.topic_id);
     }
 
@@ -354,6 +442,19 @@ pub fn add_topic_alias(
         }
     }
 
+    pub fn outbound_topic_alias(&self, connect_id: u64, topic_name: &str) -> Option<(u16, bool)> {
+        if let Some(conn) = self.connection_info.get(&connect_id) {
+            return conn.outbound_topic_alias(topic_name);
+        }
+        None
+    }
+
+    pub fn establish_outbound_topic_alias(&self, connect_id: u64, topic_name: &str) {
+        if let Some(conn) = self.connection_info.get(&connect_id) {
+            conn.establish_outbound_topic_alias(topic_name);
+        }
+    }
+
     // heartbeat
     pub fn report_heartbeat(&self, client_id: String, live_time: ConnectionLiveTime) {
         self.heartbeat_data.insert(client_id, live_time);
@@ -415,51 +516,8 @@ pub fn delete_auto_subscribe_rule(&self, cluster: &str, topic: &str) {
         self.auto_subscribe_rule.remove(&key);
     }
 
-    pub fn add_alarm_event(&self, alarm_name: String, event: SystemAlarmEventMessage) {
-        self.alarm_events.insert(alarm_name, event);
-    }
-
-    pub fn get_alarm_event(&self, name: &str) -> Option<SystemAlarmEventMessage> {
-        if let Some(event) = self.alarm_events.get(name) {
-            return Some(event.clone());
-        }
-        None
-    }
-
     // get start time
     pub fn get_start_time(&self) -> u64 {
         self.start_time
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[tokio::test]
-    async fn test_get_a_alarm_event_is_empty() {
-        let client_pool = Arc::new(ClientPool::new(1));
-        let cache_manager = CacheManager::new(client_pool, "test_cluster".to_string());
-
-        let event = cache_manager.get_alarm_event("test_event");
-        assert!(event.is_none());
-    }
-
-    #[tokio::test]
-    async fn test_add_and_get_alarm_event() {
-        let client_pool = Arc::new(ClientPool::new(1));
-        let cache_manager = CacheManager::new(client_pool, "test_cluster".to_string());
-
-        let event = SystemAlarmEventMessage {
-            name: "test_event".to_string(),
-            message: "This is a test event".to_string(),
-            activate_at: chrono::Utc::now().timestamp(),
-            activated: true,
-        };
-
-        cache_manager.add_alarm_event("test_event".to_string(), event.clone());
-        let retrieved_event = cache_manager.get_alarm_event("test_event");
-
-        assert!(retrieved_event.is_some());
-        assert_eq!(event.name, retrieved_event.unwrap().name);
-    }
-}
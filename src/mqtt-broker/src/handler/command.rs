@@ -17,10 +17,12 @@
 
 use super::flow_control::is_qos_message;
 use super::mqtt::MqttService;
+use crate::bridge::manager::ConnectorManager;
 use crate::handler::cache::CacheManager;
 use crate::handler::response::{
     response_packet_mqtt_connect_fail, response_packet_mqtt_distinct_by_reason,
 };
+use crate::observability::live_trace::{trace_packet, TraceDirection};
 use crate::security::AuthDriver;
 use crate::server::connection::NetworkConnection;
 use crate::server::connection_manager::ConnectionManager;
@@ -57,6 +59,7 @@ pub fn new(
         connection_manager: Arc<ConnectionManager>,
         schema_manager: Arc<SchemaRegisterManager>,
         auth_driver: Arc<AuthDriver>,
+        connector_manager: Arc<ConnectorManager>,
     ) -> Self {
         let mqtt3_service = MqttService::new(
             MqttProtocol::Mqtt3,
@@ -68,6 +71,7 @@ pub fn new(
             schema_manager.clone(),
             client_pool.clone(),
             auth_driver.clone(),
+            connector_manager.clone(),
         );
         let mqtt4_service = MqttService::new(
             MqttProtocol::Mqtt4,
@@ -79,6 +83,7 @@ pub fn new(
             schema_manager.clone(),
             client_pool.clone(),
             auth_driver.clone(),
+            connector_manager.clone(),
         );
         let mqtt5_service = MqttService::new(
             MqttProtocol::Mqtt5,
@@ -90,6 +95,7 @@ pub fn new(
             schema_manager.clone(),
             client_pool.clone(),
             auth_driver.clone(),
+            connector_manager.clone(),
         );
         Command {
             mqtt3_service,
@@ -106,6 +112,14 @@ pub async fn apply(
         addr: &SocketAddr,
         packet: &MqttPacket,
     ) -> Option<MqttPacket> {
+        trace_packet(
+            &self.metadata_cache,
+            TraceDirection::In,
+            tcp_connection.connection_id,
+            Some(addr),
+            packet,
+        );
+
         let mut is_connect_pkg = false;
         if let MqttPacket::Connect(_, _, _, _, _, _) = packet {
             is_connect_pkg = true;
@@ -23,9 +23,12 @@
 
 use super::cache::CacheManager;
 use super::error::MqttBrokerError;
+use super::inflight::clear_inflight_messages;
 use super::keep_alive::client_keep_live_time;
+use super::request_response::{is_response_client_id, response_topic_prefix};
 use crate::handler::flow_control::is_connection_rate_exceeded;
 use crate::handler::response::response_packet_mqtt_distinct_by_reason;
+use crate::hook::{fire_client_event, HookEventType};
 use crate::server::connection_manager::ConnectionManager;
 use crate::storage::session::SessionStorage;
 use crate::subscribe::manager::SubscribeManager;
@@ -85,12 +88,19 @@ pub fn build_connection(
             )
         };
 
+    // Without the property the client does not accept topic aliases in the PUBLISH packets it receives
+    let client_topic_alias_max = connect_properties
+        .as_ref()
+        .and_then(|properties| properties.topic_alias_max)
+        .unwrap_or_default();
+
     let config = ConnectionConfig {
         connect_id,
         client_id: client_id.clone(),
         receive_maximum: client_receive_maximum,
         max_packet_size,
         topic_alias_max,
+        client_topic_alias_max,
         request_problem_info,
         keep_alive,
         source_ip_addr: addr.to_string(),
@@ -106,11 +116,15 @@ pub fn get_client_id(client_id: &str) -> (String, bool) {
     }
 }
 
-pub fn response_information(connect_properties: &Option<ConnectProperties>) -> Option<String> {
+// Each client gets its own response topic prefix, only that client may subscribe under it
+pub fn response_information(
+    client_id: &str,
+    connect_properties: &Option<ConnectProperties>,
+) -> Option<String> {
     if let Some(properties) = connect_properties {
         if let Some(request_response_info) = properties.request_response_info {
-            if request_response_info == 1 {
-                return Some(REQUEST_RESPONSE_PREFIX_NAME.to_string());
+            if request_response_info == 1 && is_response_client_id(client_id) {
+                return Some(response_topic_prefix(client_id));
             }
         }
     }
@@ -135,13 +149,32 @@ pub async fn disconnect_connection(
     subscribe_manager: &Arc<SubscribeManager>,
     delete_session: bool,
 ) -> Result<(), MqttBrokerError> {
+    let username = cache_manager
+        .get_connection(connect_id)
+        .map(|connection| connection.login_user)
+        .unwrap_or_default();
+
     let session_storage = SessionStorage::new(client_pool.clone());
     if delete_session {
         session_storage.delete_session(client_id.to_owned()).await?;
+        clear_inflight_messages(cache_manager, client_id).await?;
         cache_manager.remove_session(client_id);
         subscribe_manager.remove_client_id(client_id);
+        fire_client_event(
+            cache_manager,
+            HookEventType::SessionTerminated,
+            client_id,
+            &username,
+        );
     } else {
         cache_manager.update_session_connect_id(client_id, None);
+        cache_manager.offline_queue.session_offline(client_id);
+        if let Err(e) = cache_manager.inflight_persist.release(client_id).await {
+            error!(
+                "Failed to persist the inflight messages of client {} on disconnect, error: {}",
+                client_id, e
+            );
+        }
         session_storage
             .update_session(client_id.to_owned(), 0, 0, 0, now_second())
             .await?;
@@ -149,6 +182,12 @@ pub async fn disconnect_connection(
 
     connection_manager.close_connect(connect_id).await;
     cache_manager.remove_connection(connect_id);
+    fire_client_event(
+        cache_manager,
+        HookEventType::ClientDisconnected,
+        client_id,
+        &username,
+    );
     Ok(())
 }
 
@@ -289,6 +328,7 @@ pub async fn build_connection_test() {
         assert_eq!(conn.client_max_receive_maximum, 100);
         assert_eq!(conn.max_packet_size, 100);
         assert_eq!(conn.topic_alias_max, 100);
+        assert_eq!(conn.client_topic_alias_max, 100);
         assert_eq!(conn.request_problem_info, 0);
     }
 
@@ -312,17 +352,27 @@ pub async fn response_information_test() {
             request_response_info: Some(1),
             ..Default::default()
         };
-        let res = response_information(&Some(connect_properties));
-        assert_eq!(res.unwrap(), REQUEST_RESPONSE_PREFIX_NAME.to_string());
+        let res = response_information("client-1", &Some(connect_properties));
+        assert_eq!(
+            res.unwrap(),
+            format!("{}client-1/", REQUEST_RESPONSE_PREFIX_NAME)
+        );
 
-        let res = response_information(&Some(ConnectProperties::default()));
+        let res = response_information("client-1", &Some(ConnectProperties::default()));
         assert!(res.is_none());
 
         let connect_properties = ConnectProperties {
             request_response_info: Some(0),
             ..Default::default()
         };
-        let res = response_information(&Some(connect_properties));
+        let res = response_information("client-1", &Some(connect_properties));
+        assert!(res.is_none());
+
+        let connect_properties = ConnectProperties {
+            request_response_info: Some(1),
+            ..Default::default()
+        };
+        let res = response_information("client/1", &Some(connect_properties));
         assert!(res.is_none());
     }
 
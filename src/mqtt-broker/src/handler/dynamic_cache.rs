@@ -22,6 +22,7 @@
 
 use common_config::mqtt::broker_mqtt_conf;
 use grpc_clients::placement::inner::call::list_schema;
+use grpc_clients::placement::mqtt::call::placement_list_subscribe;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::bridge::connector::MQTTConnector;
 use metadata_struct::mqtt::session::MqttSession;
@@ -35,12 +36,13 @@
     MqttBrokerUpdateCacheActionType, MqttBrokerUpdateCacheResourceType, UpdateMqttCacheRequest,
 };
 use protocol::placement_center::placement_center_inner::ListSchemaRequest;
+use protocol::placement_center::placement_center_mqtt::ListSubscribeRequest;
 use schema_register::schema::SchemaRegisterManager;
 use std::sync::Arc;
 use tracing::{error, info};
 
 use super::cache::CacheManager;
-use super::dynamic_config::build_cluster_config;
+use super::dynamic_config::{build_cluster_config, get_rule_config, get_tenant_config};
 
 pub async fn load_metadata_cache(
     cache_manager: &Arc<CacheManager>,
@@ -48,30 +50,18 @@ pub async fn load_metadata_cache(
     auth_driver: &Arc<AuthDriver>,
     connector_manager: &Arc<ConnectorManager>,
     schema_manager: &Arc<SchemaRegisterManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
 ) {
-    // load cluster config
-    let cluster = match build_cluster_config(client_pool).await {
-        Ok(cluster) => cluster,
-        Err(e) => {
-            panic!(
-                "Failed to load the cluster configuration with error message:{}",
-                e
-            );
-        }
-    };
-    cache_manager.set_cluster_config(cluster);
-
-    // load all topic
-    let topic_storage = TopicStorage::new(client_pool.clone());
-    let topic_list = match topic_storage.all().await {
-        Ok(list) => list,
-        Err(e) => {
-            panic!("Failed to load the topic list with error message:{}", e);
-        }
-    };
-
-    for (_, topic) in topic_list {
-        cache_manager.add_topic(&topic.topic_name, &topic);
+    if let Err(e) = reload_metadata_cache(
+        cache_manager,
+        client_pool,
+        connector_manager,
+        schema_manager,
+        subscribe_manager,
+    )
+    .await
+    {
+        panic!("{}", e);
     }
 
     // load all user
@@ -107,30 +97,67 @@ pub async fn load_metadata_cache(
     for blacklist in blacklist_list {
         cache_manager.add_blacklist(blacklist);
     }
+}
 
-    // load All topic_rewrite rule
-    let topic_storage = TopicStorage::new(client_pool.clone());
-    let topic_rewrite_rules = match topic_storage.all_topic_rewrite_rule().await {
-        Ok(list) => list,
-        Err(e) => {
-            panic!(
-                "Failed to load the topic_rewrite_rule list with error message:{}",
-                e
-            );
-        }
+/// Loads the metadata kept by the placement center, at startup and again after the metadata
+/// was restored from a backup. Users, ACLs and blacklists are refreshed by their own threads.
+pub async fn reload_metadata_cache(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connector_manager: &Arc<ConnectorManager>,
+    schema_manager: &Arc<SchemaRegisterManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> Result<(), MqttBrokerError> {
+    let load_error = |resource: &str, e: String| {
+        MqttBrokerError::CommonError(format!(
+            "Failed to load the {} with error message:{}",
+            resource, e
+        ))
     };
+
+    // load cluster config
+    let cluster = build_cluster_config(client_pool)
+        .await
+        .map_err(|e| load_error("cluster configuration", e.to_string()))?;
+    cache_manager.set_cluster_config(cluster);
+
+    // load all tenant
+    let tenants = get_tenant_config(client_pool)
+        .await
+        .map_err(|e| load_error("tenant list", e.to_string()))?;
+    cache_manager.set_tenants(tenants);
+
+    // load all rule
+    let rules = get_rule_config(client_pool)
+        .await
+        .map_err(|e| load_error("rule list", e.to_string()))?;
+    cache_manager.set_rules(rules);
+
+    // load all topic
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let topic_list = topic_storage
+        .all()
+        .await
+        .map_err(|e| load_error("topic list", e.to_string()))?;
+    for (_, topic) in topic_list {
+        cache_manager.add_topic(&topic.topic_name, &topic);
+    }
+
+    // load All topic_rewrite rule
+    let topic_rewrite_rules = topic_storage
+        .all_topic_rewrite_rule()
+        .await
+        .map_err(|e| load_error("topic_rewrite_rule list", e.to_string()))?;
     for topic_rewrite_rule in topic_rewrite_rules {
         cache_manager.add_topic_rewrite_rule(topic_rewrite_rule);
     }
 
     // load all connectors
     let connector_storage = ConnectorStorage::new(client_pool.clone());
-    let connectors = match connector_storage.list_all_connectors().await {
-        Ok(list) => list,
-        Err(e) => {
-            panic!("Failed to load the connector list with error message:{}", e);
-        }
-    };
+    let connectors = connector_storage
+        .list_all_connectors()
+        .await
+        .map_err(|e| load_error("connector list", e.to_string()))?;
     for connector in connectors.iter() {
         connector_manager.add_connector(connector);
     }
@@ -141,43 +168,53 @@ pub async fn load_metadata_cache(
         cluster_name: config.cluster_name.clone(),
         schema_name: "".to_owned(),
     };
-
-    match list_schema(client_pool, &config.placement_center, request).await {
-        Ok(reply) => {
-            for raw in reply.schemas {
-                match serde_json::from_slice::<SchemaData>(raw.as_slice()) {
-                    Ok(schema) => {
-                        schema_manager.add_schema(schema);
-                    }
-                    Err(e) => {
-                        error!("{}", e);
-                    }
-                }
+    let reply = list_schema(client_pool, &config.placement_center, request)
+        .await
+        .map_err(|e| load_error("schema list", e.to_string()))?;
+    for raw in reply.schemas {
+        match serde_json::from_slice::<SchemaData>(raw.as_slice()) {
+            Ok(schema) => {
+                schema_manager.add_schema(schema);
+            }
+            Err(e) => {
+                error!("{}", e);
             }
-        }
-        Err(e) => {
-            panic!("Failed to load the schema list with error message:{}", e);
         }
     }
 
     // load all auto subscribe rule
     let auto_subscribe_storage = AutoSubscribeStorage::new(client_pool.clone());
-    let auto_subscribe_rules = match auto_subscribe_storage.list_auto_subscribe_rule().await {
-        Ok(list) => list,
-        Err(e) => {
-            panic!(
-                "Failed to load the auto subscribe list with error message:{}",
-                e
-            );
-        }
-    };
+    let auto_subscribe_rules = auto_subscribe_storage
+        .list_auto_subscribe_rule()
+        .await
+        .map_err(|e| load_error("auto subscribe list", e.to_string()))?;
     for auto_subscribe_rule in auto_subscribe_rules {
         cache_manager.add_auto_subscribe_rule(auto_subscribe_rule);
     }
+
+    // load all subscribe, later changes are broadcast so every broker knows the whole cluster
+    let request = ListSubscribeRequest {
+        cluster_name: config.cluster_name.clone(),
+    };
+    let reply = placement_list_subscribe(client_pool, &config.placement_center, request)
+        .await
+        .map_err(|e| load_error("subscribe list", e.to_string()))?;
+    for raw in reply.subscribes {
+        match serde_json::from_slice::<MqttSubscribe>(raw.as_slice()) {
+            Ok(subscribe) => {
+                subscribe_manager.add_subscribe(subscribe);
+            }
+            Err(e) => {
+                error!("{}", e);
+            }
+        }
+    }
+    Ok(())
 }
 
 pub async fn update_cache_metadata(
     cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
     connector_manager: &Arc<ConnectorManager>,
     subscribe_manager: &Arc<SubscribeManager>,
     schema_manager: &Arc<SchemaRegisterManager>,
@@ -286,6 +323,18 @@ pub async fn update_cache_metadata(
             }
             MqttBrokerUpdateCacheActionType::Delete => {}
         },
+
+        MqttBrokerUpdateCacheResourceType::Metadata => {
+            info!("Metadata was restored by the placement center, reloading it");
+            reload_metadata_cache(
+                cache_manager,
+                client_pool,
+                connector_manager,
+                schema_manager,
+                subscribe_manager,
+            )
+            .await?;
+        }
     }
     Ok(())
 }
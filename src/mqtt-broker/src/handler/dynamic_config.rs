@@ -20,9 +20,11 @@
 use common_config::mqtt::broker_mqtt_conf;
 use common_config::mqtt::config::{
     BrokerMqttConfig, Feature, FlappingDetect, MqttProtocolConfig, NetworkThread, OfflineMessage,
-    Schema, Security, SlowSub, SystemMonitor,
+    QueueSubscription, Schema, Security, SharedSubscription, SlowSub, SystemMonitor,
 };
 use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::rule::MqttRule;
+use metadata_struct::mqtt::tenant::MqttTenant;
 use strum_macros::{Display, EnumString};
 
 #[derive(Default, EnumString, Display)]
@@ -37,6 +39,9 @@ pub enum ClusterDynamicConfig {
     NetworkThread,
     SystemMonitor,
     Schema,
+    Tenant,
+    Rule,
+    SharedSubscription,
 }
 
 impl CacheManager {
@@ -128,6 +133,22 @@ pub fn get_security_config(&self) -> Security {
         self.get_cluster_config().security
     }
 
+    // shared subscription
+    pub fn update_shared_subscription_config(&self, shared_subscription: SharedSubscription) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.shared_subscription = shared_subscription;
+        }
+    }
+
+    pub fn get_shared_subscription_config(&self) -> SharedSubscription {
+        self.get_cluster_config().shared_subscription
+    }
+
+    // queue subscription
+    pub fn get_queue_subscription_config(&self) -> QueueSubscription {
+        self.get_cluster_config().queue_subscription
+    }
+
     // cluster config
     pub fn set_cluster_config(&self, cluster: BrokerMqttConfig) {
         self.cluster_info.insert(self.cluster_name.clone(), cluster);
@@ -178,6 +199,10 @@ pub async fn build_cluster_config(
         conf.system_monitor = data;
     }
 
+    if let Some(data) = get_shared_subscription(client_pool).await? {
+        conf.shared_subscription = data;
+    }
+
     Ok(conf)
 }
 
@@ -223,6 +248,18 @@ pub async fn update_cluster_dynamic_config(
             let security_config = serde_json::from_slice(&config)?;
             cache_manager.update_security_config(security_config);
         }
+        ClusterDynamicConfig::Tenant => {
+            let tenants = serde_json::from_slice(&config)?;
+            cache_manager.set_tenants(tenants);
+        }
+        ClusterDynamicConfig::Rule => {
+            let rules = serde_json::from_slice(&config)?;
+            cache_manager.set_rules(rules);
+        }
+        ClusterDynamicConfig::SharedSubscription => {
+            let shared_subscription = serde_json::from_slice(&config)?;
+            cache_manager.update_shared_subscription_config(shared_subscription);
+        }
     }
     Ok(())
 }
@@ -397,3 +434,57 @@ async fn get_system_monitor(
 
     Ok(None)
 }
+
+pub async fn get_tenant_config(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Vec<MqttTenant>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::Tenant.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(serde_json::from_slice::<Vec<MqttTenant>>(&data)?);
+    }
+
+    Ok(Vec::new())
+}
+
+pub async fn get_rule_config(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Vec<MqttRule>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(&conf.cluster_name, &ClusterDynamicConfig::Rule.to_string())
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(serde_json::from_slice::<Vec<MqttRule>>(&data)?);
+    }
+
+    Ok(Vec::new())
+}
+
+async fn get_shared_subscription(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<SharedSubscription>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::SharedSubscription.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<SharedSubscription>(&data)?));
+    }
+
+    Ok(None)
+}
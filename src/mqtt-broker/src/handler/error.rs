@@ -63,6 +63,18 @@ pub enum MqttBrokerError {
     #[error("{0}")]
     FromProtocolMQTTCommonError(#[from] protocol::mqtt::common::Error),
 
+    #[error("{0}")]
+    FromPostgresError(#[from] tokio_postgres::Error),
+
+    #[error("{0}")]
+    FromReqwestError(#[from] reqwest::Error),
+
+    #[error("{0}")]
+    FromPahoMqttError(#[from] paho_mqtt::Error),
+
+    #[error("{0}")]
+    TokioTaskJoinError(#[from] tokio::task::JoinError),
+
     #[error("Topic alias is too long. alias is {0}")]
     TopicAliasTooLong(u16),
 
@@ -176,6 +188,27 @@ pub enum MqttBrokerError {
     #[error("Operation timeout, timeout time :{0}, operation: {1}")]
     OperationTimeout(u64, String),
 
+    #[error("Tenant {0} does not exist")]
+    TenantNotExist(String),
+
+    #[error("Tenant {0} still has {1} users bound and cannot be deleted")]
+    TenantInUse(String, usize),
+
+    #[error("Tenant {0} exceeded the {1} quota, limit: {2}")]
+    TenantQuotaExceeded(String, String, u64),
+
+    #[error("Rule {0} does not exist")]
+    RuleNotExist(String),
+
+    #[error("Alarm {0} is not active")]
+    AlarmNotActive(String),
+
+    #[error("Rule SQL is invalid: {0}")]
+    RuleSqlInvalid(String),
+
+    #[error("Rule {0} failed to evaluate the message: {1}")]
+    RuleEvaluationFailed(String, String),
+
     #[error("gRPC error: {0}")]
     RpcError(#[from] Status),
 }
@@ -0,0 +1,427 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::error::common::CommonError;
+use common_base::tools::now_mills;
+use dashmap::{DashMap, DashSet};
+use futures::future::join_all;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::inflight::{InflightMessage, InflightState};
+use protocol::mqtt::common::{MqttPacket, MqttProtocol};
+use tokio::sync::{broadcast, oneshot};
+use tokio::time::timeout;
+use tracing::{debug, error, info, warn};
+
+use super::cache::{CacheManager, QosAckPacketInfo};
+use super::error::MqttBrokerError;
+use crate::observability::metrics::session::record_inflight_persist_failed;
+use crate::server::connection_manager::ConnectionManager;
+use crate::storage::inflight::InflightStorage;
+use crate::subscribe::common::{SubPublishParam, Subscriber};
+use crate::subscribe::push::{qos2_send_pubrel, send_publish_packet_to_client, wait_pub_comp};
+
+// Only sessions that outlive the network connection need their inflight window kept,
+// a session with a zero expiry interval is discarded together with the connection.
+pub fn is_persistent_session(cache_manager: &Arc<CacheManager>, client_id: &str) -> bool {
+    if let Some(session) = cache_manager.get_session_info(client_id) {
+        return session.session_expiry > 0;
+    }
+    false
+}
+
+const INFLIGHT_PERSIST_INTERVAL_MS: u64 = 500;
+
+// The inflight windows of the persistent sessions pushed to by this broker. Changes are kept
+// in memory and a background task writes the whole window of every changed session at once,
+// so pushing a message never waits for the placement center and a message acknowledged
+// within one interval is never written at all.
+pub struct InflightPersistManager {
+    storage: InflightStorage,
+    // (client_id, (pkid, InflightMessage))
+    windows: DashMap<String, HashMap<u16, InflightMessage>>,
+    // Sessions whose window changed since it was last written
+    dirty: DashSet<String>,
+}
+
+impl InflightPersistManager {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        InflightPersistManager {
+            storage: InflightStorage::new(client_pool),
+            windows: DashMap::with_capacity(8),
+            dirty: DashSet::with_capacity(8),
+        }
+    }
+
+    pub fn save(&self, message: InflightMessage) {
+        let client_id = message.client_id.clone();
+        self.windows
+            .entry(client_id.clone())
+            .or_default()
+            .insert(message.pkid, message);
+        self.dirty.insert(client_id);
+    }
+
+    pub fn delete(&self, client_id: &str, pkid: u16) {
+        if let Some(mut window) = self.windows.get_mut(client_id) {
+            if window.remove(&pkid).is_some() {
+                self.dirty.insert(client_id.to_owned());
+            }
+        }
+    }
+
+    // The window of a session, from memory when this broker still holds it
+    pub async fn load(&self, client_id: &str) -> Result<Vec<InflightMessage>, CommonError> {
+        if let Some(window) = self.windows.get(client_id) {
+            return Ok(window.values().cloned().collect());
+        }
+
+        let messages = self.storage.list(client_id).await?;
+        self.windows.insert(
+            client_id.to_owned(),
+            messages
+                .iter()
+                .map(|message| (message.pkid, message.clone()))
+                .collect(),
+        );
+        Ok(messages)
+    }
+
+    pub async fn clear(&self, client_id: &str) -> Result<(), CommonError> {
+        self.windows.remove(client_id);
+        self.dirty.remove(client_id);
+        if let Err(e) = self.storage.delete_session(client_id).await {
+            record_inflight_persist_failed("clear");
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub async fn flush_session(&self, client_id: &str) -> Result<(), CommonError> {
+        if self.dirty.remove(client_id).is_none() {
+            return Ok(());
+        }
+
+        let messages: Vec<InflightMessage> = self
+            .windows
+            .get(client_id)
+            .map(|window| window.values().cloned().collect())
+            .unwrap_or_default();
+        if let Err(e) = self.storage.save_session(client_id, &messages).await {
+            // Written again on the next flush
+            self.dirty.insert(client_id.to_owned());
+            record_inflight_persist_failed("save");
+            return Err(e);
+        }
+
+        if messages.is_empty() {
+            self.windows
+                .remove_if(client_id, |_, window| window.is_empty());
+        }
+        Ok(())
+    }
+
+    pub async fn flush(&self) {
+        let client_ids: Vec<String> = self.dirty.iter().map(|id| id.clone()).collect();
+        for client_id in client_ids {
+            if let Err(e) = self.flush_session(&client_id).await {
+                error!(
+                    "Failed to persist the inflight messages of client {}, error: {}",
+                    client_id, e
+                );
+            }
+        }
+    }
+
+    // The session went offline or was taken over, its window is written now and another
+    // broker may take it over. The window is dropped even when the write fails, a window
+    // kept here would later be written over the one of the broker holding the session.
+    pub async fn release(&self, client_id: &str) -> Result<(), CommonError> {
+        let result = self.flush_session(client_id).await;
+        self.windows.remove(client_id);
+        self.dirty.remove(client_id);
+        result
+    }
+}
+
+pub async fn start_inflight_persist_thread(
+    cache_manager: Arc<CacheManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let mut stop_rx = stop_send.subscribe();
+    let mut interval = tokio::time::interval(Duration::from_millis(INFLIGHT_PERSIST_INTERVAL_MS));
+    loop {
+        tokio::select! {
+            val = stop_rx.recv() => {
+                if let Ok(flag) = val {
+                    if flag {
+                        cache_manager.inflight_persist.flush().await;
+                        info!("{}","Inflight persist thread stopped successfully.");
+                        break;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                cache_manager.inflight_persist.flush().await;
+            }
+        }
+    }
+}
+
+pub fn save_inflight_message(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    packet: &MqttPacket,
+    state: InflightState,
+) {
+    let MqttPacket::Publish(publish, properties) = packet else {
+        return;
+    };
+
+    let message = InflightMessage::build(client_id, publish, properties, state);
+    cache_manager.inflight_persist.save(message);
+}
+
+pub fn delete_inflight_message(cache_manager: &Arc<CacheManager>, client_id: &str, pkid: u16) {
+    cache_manager.inflight_persist.delete(client_id, pkid);
+}
+
+pub async fn clear_inflight_messages(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+) -> Result<(), MqttBrokerError> {
+    cache_manager.inflight_persist.clear(client_id).await?;
+    Ok(())
+}
+
+// Load the persisted inflight window of a resumed session and reserve its pkids,
+// so that new pushes can not reuse them before the restored messages are acknowledged.
+pub async fn restore_inflight_messages(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+) -> Result<Vec<InflightMessage>, MqttBrokerError> {
+    let mut messages = cache_manager.inflight_persist.load(client_id).await?;
+    messages.sort_by_key(|message| message.create_time);
+
+    let mut results = Vec::with_capacity(messages.len());
+    for message in messages {
+        if cache_manager
+            .pkid_metadata
+            .reserve_pkid(client_id, message.pkid)
+        {
+            results.push(message);
+        } else {
+            debug!(
+                "Inflight message is already being pushed, client_id: {}, pkid: {}",
+                client_id, message.pkid
+            );
+        }
+    }
+    Ok(results)
+}
+
+// Resend the restored inflight window once the CONNACK has been written. At most
+// Receive Maximum messages are outstanding at the same time.
+pub async fn resend_inflight_messages(
+    cache_manager: Arc<CacheManager>,
+    connection_manager: Arc<ConnectionManager>,
+    connect_id: u64,
+    messages: Vec<InflightMessage>,
+    connack_sent: oneshot::Receiver<()>,
+) {
+    if !matches!(
+        timeout(Duration::from_millis(3000), connack_sent).await,
+        Ok(Ok(()))
+    ) {
+        warn!(
+            "CONNACK was not sent, inflight messages are not resent, connect_id: {}",
+            connect_id
+        );
+        release_inflight_pkids(&cache_manager, &messages);
+        return;
+    }
+
+    let receive_maximum = if let Some(conn) = cache_manager.get_connection(connect_id) {
+        conn.client_max_receive_maximum.max(1) as usize
+    } else {
+        release_inflight_pkids(&cache_manager, &messages);
+        return;
+    };
+
+    let is_mqtt5 = connection_manager
+        .get_connect_protocol(connect_id)
+        .map(|protocol| MqttProtocol::is_mqtt5(&protocol))
+        .unwrap_or(false);
+
+    let (stop_sx, _) = broadcast::channel(1);
+    for (i, batch) in messages.chunks(receive_maximum).enumerate() {
+        let tasks = batch.iter().map(|message| {
+            resend_inflight_message(
+                &cache_manager,
+                &connection_manager,
+                message,
+                is_mqtt5,
+                &stop_sx,
+            )
+        });
+
+        for (message, result) in batch.iter().zip(join_all(tasks).await) {
+            if let Err(e) = result {
+                warn!(
+                    "Failed to resend inflight message, client_id: {}, pkid: {}, error: {}",
+                    message.client_id, message.pkid, e
+                );
+                // The rest of the window stays persisted for the next reconnect
+                let offset = (i + 1) * receive_maximum;
+                if offset < messages.len() {
+                    release_inflight_pkids(&cache_manager, &messages[offset..]);
+                }
+                return;
+            }
+        }
+    }
+}
+
+async fn resend_inflight_message(
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    message: &InflightMessage,
+    is_mqtt5: bool,
+    stop_sx: &broadcast::Sender<bool>,
+) -> Result<(), MqttBrokerError> {
+    let (publish, properties) = message.to_publish();
+    let properties = if is_mqtt5 { properties } else { None };
+    let sub_pub_param = SubPublishParam::new(
+        Subscriber {
+            client_id: message.client_id.clone(),
+            ..Default::default()
+        },
+        MqttPacket::Publish(publish, properties),
+        0,
+        "".to_string(),
+        message.pkid,
+    );
+
+    match message.state {
+        InflightState::Publish => {
+            // Acks and the persisted state are handled the same way as a first delivery
+            send_publish_packet_to_client(
+                connection_manager,
+                cache_manager,
+                &sub_pub_param,
+                &message.qos,
+                stop_sx,
+            )
+            .await
+        }
+
+        InflightState::PubRel => {
+            let (wait_ack_sx, _) = broadcast::channel(1);
+            cache_manager.pkid_metadata.add_ack_packet(
+                &message.client_id,
+                message.pkid,
+                QosAckPacketInfo {
+                    sx: wait_ack_sx.clone(),
+                    create_time: now_mills(),
+                },
+            );
+
+            let result = async {
+                qos2_send_pubrel(cache_manager, &sub_pub_param, connection_manager, stop_sx)
+                    .await?;
+                wait_pub_comp(
+                    cache_manager,
+                    connection_manager,
+                    &sub_pub_param,
+                    stop_sx,
+                    &wait_ack_sx,
+                )
+                .await
+            }
+            .await;
+
+            cache_manager
+                .pkid_metadata
+                .remove_ack_packet(&message.client_id, message.pkid);
+            result?;
+            delete_inflight_message(cache_manager, &message.client_id, message.pkid);
+            Ok(())
+        }
+    }
+}
+
+fn release_inflight_pkids(cache_manager: &Arc<CacheManager>, messages: &[InflightMessage]) {
+    for message in messages {
+        cache_manager
+            .pkid_metadata
+            .remove_ack_packet(&message.client_id, message.pkid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::unique_id;
+    use common_config::mqtt::{config::BrokerMqttConfig, init_broker_mqtt_conf_by_config};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::inflight::InflightMessage;
+
+    use super::InflightPersistManager;
+
+    fn build_message(client_id: &str, pkid: u16) -> InflightMessage {
+        InflightMessage {
+            client_id: client_id.to_owned(),
+            pkid,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn window_dirty_test() {
+        let manager = InflightPersistManager::new(Arc::new(ClientPool::new(1)));
+        manager.delete("c1", 1);
+        assert!(manager.dirty.is_empty());
+
+        manager.save(build_message("c1", 1));
+        manager.save(build_message("c1", 2));
+        manager.save(build_message("c2", 1));
+        assert_eq!(manager.windows.get("c1").unwrap().len(), 2);
+        assert_eq!(manager.dirty.len(), 2);
+
+        manager.dirty.clear();
+        manager.delete("c1", 3);
+        assert!(manager.dirty.is_empty());
+        manager.delete("c1", 1);
+        manager.delete("c1", 2);
+        assert!(manager.dirty.contains("c1"));
+        assert!(manager.windows.get("c1").unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn release_drops_window_on_failed_flush_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: unique_id(),
+            ..Default::default()
+        });
+        let manager = InflightPersistManager::new(Arc::new(ClientPool::new(1)));
+        manager.save(build_message("c1", 1));
+
+        assert!(manager.release("c1").await.is_err());
+        assert!(manager.windows.get("c1").is_none());
+        assert!(!manager.dirty.contains("c1"));
+    }
+}
@@ -330,6 +330,7 @@ pub async fn get_expire_connection_test() {
             receive_maximum: 100,
             max_packet_size: 100,
             topic_alias_max: 100,
+            client_topic_alias_max: 0,
             request_problem_info: 100,
             keep_alive,
             source_ip_addr: addr,
@@ -60,7 +60,6 @@ pub async fn send_last_will_message<S>(
 
     save_retain_message(
         cache_manager,
-        client_pool,
         topic_name,
         client_id,
         &publish,
@@ -25,11 +25,14 @@
 pub mod flapping_detect;
 pub mod flow_control;
 pub mod heartbreat;
+pub mod inflight;
 pub mod keep_alive;
 pub mod lastwill;
 pub mod message;
 pub mod mqtt;
 pub mod offline_message;
+pub mod offline_queue;
+pub mod request_response;
 pub mod response;
 pub mod retain;
 pub mod session;
@@ -38,6 +41,8 @@
 pub mod sub_option;
 pub mod sub_parse_topic;
 pub mod subscribe;
+pub mod takeover;
+pub mod tenant;
 pub mod topic;
 mod topic_rewrite;
 pub mod unsubscribe;
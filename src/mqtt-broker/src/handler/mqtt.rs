@@ -18,6 +18,8 @@
 use common_base::tools::{now_mills, now_second};
 use delay_message::DelayMessageManager;
 use grpc_clients::pool::ClientPool;
+use opentelemetry::trace::SpanKind;
+use opentelemetry::KeyValue;
 use protocol::mqtt::common::{
     qos, Connect, ConnectProperties, ConnectReturnCode, Disconnect, DisconnectProperties,
     DisconnectReasonCode, LastWill, LastWillProperties, Login, MqttPacket, MqttProtocol, PingReq,
@@ -38,13 +40,19 @@
 use super::sub_auto::try_auto_subscribe;
 use super::subscribe::save_subscribe;
 use super::unsubscribe::remove_subscribe;
+use crate::bridge::manager::ConnectorManager;
 use crate::common::pkid_storage::{pkid_delete, pkid_exists, pkid_save};
 use crate::handler::cache::{
     CacheManager, ConnectionLiveTime, QosAckPackageData, QosAckPackageType,
 };
 use crate::handler::connection::{build_connection, get_client_id};
+use crate::handler::error::MqttBrokerError;
 use crate::handler::flapping_detect::check_flapping_detect;
+use crate::handler::inflight::{
+    clear_inflight_messages, resend_inflight_messages, restore_inflight_messages,
+};
 use crate::handler::lastwill::save_last_will_message;
+use crate::handler::request_response::capture_broker_response;
 use crate::handler::response::{
     build_puback, build_pubrec, response_packet_mqtt_connect_fail,
     response_packet_mqtt_connect_success, response_packet_mqtt_distinct_by_reason,
@@ -53,14 +61,24 @@
     response_packet_mqtt_unsuback,
 };
 use crate::handler::session::{build_session, save_session};
+use crate::handler::takeover::{rebind_subscribe, takeover_session};
+use crate::handler::tenant::{
+    check_tenant_connection_quota, check_tenant_publish_quota, check_tenant_subscription_quota,
+    tenant_last_will, tenant_subscribe, tenant_topic_name, tenant_unsubscribe,
+};
 use crate::handler::topic::{get_topic_name, try_init_topic};
 use crate::handler::validator::{
-    connect_validator, publish_validator, subscribe_validator, un_subscribe_validator,
+    connect_validator, publish_validator, subscribe_validator, topic_alias_validator,
+    un_subscribe_validator,
 };
+use crate::hook::{fire_client_event, fire_topic_event, HookEventType};
 use crate::observability::system_topic::event::{
     st_report_connected_event, st_report_disconnected_event, st_report_subscribed_event,
     st_report_unsubscribed_event,
 };
+use crate::observability::trace::MessageTrace;
+use crate::observability::warn::check::record_storage_write_failure;
+use crate::rule::engine::RuleEngine;
 use crate::security::AuthDriver;
 use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::common::min_qos;
@@ -77,6 +95,7 @@ pub struct MqttService<S> {
     schema_manager: Arc<SchemaRegisterManager>,
     client_pool: Arc<ClientPool>,
     auth_driver: Arc<AuthDriver>,
+    rule_engine: RuleEngine<S>,
 }
 
 impl<S> MqttService<S>
@@ -94,7 +113,16 @@ pub fn new(
         schema_manager: Arc<SchemaRegisterManager>,
         client_pool: Arc<ClientPool>,
         auth_driver: Arc<AuthDriver>,
+        connector_manager: Arc<ConnectorManager>,
     ) -> Self {
+        let rule_engine = RuleEngine::new(
+            cache_manager.clone(),
+            client_pool.clone(),
+            message_storage_adapter.clone(),
+            delay_message_manager.clone(),
+            subscribe_manager.clone(),
+            connector_manager,
+        );
         MqttService {
             protocol,
             cache_manager,
@@ -105,6 +133,7 @@ pub fn new(
             client_pool,
             auth_driver,
             schema_manager,
+            rule_engine,
         }
     }
 
@@ -136,7 +165,7 @@ pub async fn connect(
 
         // blacklist check
         let (client_id, new_client_id) = get_client_id(&connect.client_id);
-        let connection = build_connection(
+        let mut connection = build_connection(
             connect_id,
             client_id.clone(),
             &cluster,
@@ -145,7 +174,20 @@ pub async fn connect(
             addr,
         );
 
+        // Clients can trace their connection with the trace context in the CONNECT user properties
+        let trace = MessageTrace::start(
+            "mqtt.connect",
+            SpanKind::Server,
+            connect_properties
+                .as_ref()
+                .map(|properties| properties.user_properties.as_slice())
+                .unwrap_or_default(),
+            vec![KeyValue::new("mqtt.client_id", client_id.clone())],
+        );
+
+        let span = trace.step("mqtt.connect.auth");
         if self.auth_driver.allow_connect(&connection).await {
+            span.set_error("client is banned".to_string());
             return response_packet_mqtt_connect_fail(
                 &self.protocol,
                 ConnectReturnCode::Banned,
@@ -162,6 +204,7 @@ pub async fn connect(
         {
             Ok(flag) => {
                 if !flag {
+                    span.set_error("client is not authorized".to_string());
                     return response_packet_mqtt_connect_fail(
                         &self.protocol,
                         ConnectReturnCode::NotAuthorized,
@@ -171,6 +214,7 @@ pub async fn connect(
                 }
             }
             Err(e) => {
+                span.set_error(e.to_string());
                 return response_packet_mqtt_connect_fail(
                     &self.protocol,
                     ConnectReturnCode::UnspecifiedError,
@@ -179,12 +223,67 @@ pub async fn connect(
                 );
             }
         }
+        drop(span);
+
+        // tenant quota check
+        let tenant = if let Some(info) = login {
+            self.cache_manager.get_tenant_by_user(&info.username)
+        } else {
+            String::new()
+        };
+
+        if let Err(e) = check_tenant_connection_quota(&self.cache_manager, &tenant) {
+            let code = if let MqttBrokerError::TenantQuotaExceeded(..) = e {
+                ConnectReturnCode::QuotaExceeded
+            } else {
+                ConnectReturnCode::NotAuthorized
+            };
+            return response_packet_mqtt_connect_fail(
+                &self.protocol,
+                code,
+                connect_properties,
+                Some(e.to_string()),
+            );
+        }
+        connection.tenant = tenant.clone();
+
+        let last_will = match tenant_last_will(&tenant, last_will) {
+            Ok(data) => data,
+            Err(e) => {
+                return response_packet_mqtt_connect_fail(
+                    &self.protocol,
+                    ConnectReturnCode::TopicNameInvalid,
+                    connect_properties,
+                    Some(e.to_string()),
+                );
+            }
+        };
+        let last_will = &last_will;
 
         // flapping detect check
         if cluster.flapping_detect.enable {
             check_flapping_detect(connect.client_id.clone(), &self.cache_manager);
         }
 
+        // A connection elsewhere in the cluster that still holds the session is closed first
+        if let Err(e) = takeover_session(
+            &self.client_pool,
+            &self.cache_manager,
+            &self.connection_manager,
+            &self.subscribe_manager,
+            &client_id,
+            connect_id,
+        )
+        .await
+        {
+            return response_packet_mqtt_connect_fail(
+                &self.protocol,
+                ConnectReturnCode::UnspecifiedError,
+                connect_properties,
+                Some(e.to_string()),
+            );
+        }
+
         let (session, new_session) = match build_session(
             connect_id,
             client_id.clone(),
@@ -225,6 +324,20 @@ pub async fn connect(
             );
         }
 
+        if let Err(e) = self
+            .cache_manager
+            .offline_queue
+            .session_online(&client_id, &self.cache_manager.get_offline_message_config())
+            .await
+        {
+            return response_packet_mqtt_connect_fail(
+                &self.protocol,
+                ConnectReturnCode::UnspecifiedError,
+                connect_properties,
+                Some(e.to_string()),
+            );
+        }
+
         if let Err(e) = save_last_will_message(
             client_id.clone(),
             last_will,
@@ -243,6 +356,7 @@ pub async fn connect(
 
         if let Err(e) = try_auto_subscribe(
             client_id.clone(),
+            &tenant,
             login,
             &self.protocol,
             &self.client_pool,
@@ -259,6 +373,53 @@ pub async fn connect(
             );
         }
 
+        // A replaced session drops the unacknowledged messages of the previous one, a resumed
+        // persistent session picks them up again, possibly from another broker.
+        let inflight_messages = if new_session {
+            if let Err(e) = clear_inflight_messages(&self.cache_manager, &client_id).await {
+                return response_packet_mqtt_connect_fail(
+                    &self.protocol,
+                    ConnectReturnCode::UnspecifiedError,
+                    connect_properties,
+                    Some(e.to_string()),
+                );
+            }
+            Vec::new()
+        } else if session.session_expiry > 0 {
+            match restore_inflight_messages(&self.cache_manager, &client_id).await {
+                Ok(messages) => messages,
+                Err(e) => {
+                    return response_packet_mqtt_connect_fail(
+                        &self.protocol,
+                        ConnectReturnCode::UnspecifiedError,
+                        connect_properties,
+                        Some(e.to_string()),
+                    );
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        if !new_session {
+            if let Err(e) = rebind_subscribe(
+                &self.client_pool,
+                &self.cache_manager,
+                &self.subscribe_manager,
+                &client_id,
+                &self.protocol,
+            )
+            .await
+            {
+                return response_packet_mqtt_connect_fail(
+                    &self.protocol,
+                    ConnectReturnCode::UnspecifiedError,
+                    connect_properties,
+                    Some(e.to_string()),
+                );
+            }
+        }
+
         let live_time = ConnectionLiveTime {
             protocol: self.protocol.clone(),
             keep_live: connection.keep_alive as u16,
@@ -270,6 +431,18 @@ pub async fn connect(
         self.cache_manager.add_session(&client_id, &session);
         self.cache_manager
             .add_connection(connect_id, connection.clone());
+
+        if !inflight_messages.is_empty() {
+            let connack_sent = self.connection_manager.wait_connack(connect_id);
+            tokio::spawn(resend_inflight_messages(
+                self.cache_manager.clone(),
+                self.connection_manager.clone(),
+                connect_id,
+                inflight_messages,
+                connack_sent,
+            ));
+        }
+
         st_report_connected_event(
             &self.message_storage_adapter,
             &self.cache_manager,
@@ -281,6 +454,21 @@ pub async fn connect(
         )
         .await;
 
+        if new_session {
+            fire_client_event(
+                &self.cache_manager,
+                HookEventType::SessionCreated,
+                &client_id,
+                &connection.login_user,
+            );
+        }
+        fire_client_event(
+            &self.cache_manager,
+            HookEventType::ClientConnected,
+            &client_id,
+            &connection.login_user,
+        );
+
         response_packet_mqtt_connect_success(
             &self.protocol,
             &cluster,
@@ -308,6 +496,30 @@ pub async fn publish(
             ));
         };
 
+        // The span of the message in the broker, a child of the trace context of the publisher
+        let trace = MessageTrace::start(
+            "mqtt.publish",
+            SpanKind::Server,
+            publish_properties
+                .as_ref()
+                .map(|properties| properties.user_properties.as_slice())
+                .unwrap_or_default(),
+            vec![KeyValue::new(
+                "mqtt.client_id",
+                connection.client_id.clone(),
+            )],
+        );
+
+        if let Some(pkg) = topic_alias_validator(
+            &self.protocol,
+            &self.cache_manager,
+            &connection,
+            publish_properties,
+        ) {
+            return Some(pkg);
+        }
+
+        let span = trace.step("mqtt.publish.validate");
         if let Some(pkg) = publish_validator(
             &self.protocol,
             &self.cache_manager,
@@ -318,12 +530,14 @@ pub async fn publish(
         )
         .await
         {
+            span.set_error("publish rejected by validation".to_string());
             if publish.qos == QoS::AtMostOnce {
                 return None;
             } else {
                 return Some(pkg);
             }
         }
+        drop(span);
 
         let is_puback = publish.qos != QoS::ExactlyOnce;
 
@@ -336,6 +550,13 @@ pub async fn publish(
         .await
         {
             Ok(topic_name) => topic_name,
+            // An alias the client never mapped to a topic name is a protocol error
+            Err(MqttBrokerError::TopicAliasInvalid(_)) => {
+                return Some(response_packet_mqtt_distinct_by_reason(
+                    &self.protocol,
+                    Some(DisconnectReasonCode::ProtocolError),
+                ));
+            }
             Err(e) => {
                 return Some(build_pub_ack_fail(
                     &self.protocol,
@@ -367,11 +588,15 @@ pub async fn publish(
             None
         };
 
+        trace.set_attribute(KeyValue::new("mqtt.topic", topic_name.clone()));
+
+        let span = trace.step("mqtt.publish.acl");
         if !self
             .auth_driver
             .allow_publish(&connection, &topic_name, publish.retain, publish.qos)
             .await
         {
+            span.set_error("publish not authorized".to_string());
             if is_puback {
                 return Some(build_puback(
                     &self.protocol,
@@ -392,6 +617,51 @@ pub async fn publish(
                 ));
             }
         }
+        drop(span);
+
+        if let Err(e) = check_tenant_publish_quota(
+            &self.cache_manager,
+            &connection.tenant,
+            publish.payload.len(),
+        ) {
+            warn!("{}", e);
+            if publish.qos == QoS::AtMostOnce {
+                return None;
+            }
+            if is_puback {
+                return Some(build_puback(
+                    &self.protocol,
+                    &connection,
+                    publish.pkid,
+                    PubAckReason::QuotaExceeded,
+                    Some(e.to_string()),
+                    Vec::new(),
+                ));
+            } else {
+                return Some(build_pubrec(
+                    &self.protocol,
+                    &connection,
+                    publish.pkid,
+                    PubRecReason::QuotaExceeded,
+                    Some(e.to_string()),
+                    Vec::new(),
+                ));
+            }
+        }
+
+        // Topics are stored in the tenant namespace, the client only sees its own topic name
+        let topic_name = match tenant_topic_name(&connection.tenant, &topic_name) {
+            Ok(name) => name,
+            Err(e) => {
+                return Some(build_pub_ack_fail(
+                    &self.protocol,
+                    &connection,
+                    publish.pkid,
+                    Some(e.to_string()),
+                    is_puback,
+                ))
+            }
+        };
 
         let topic = match try_init_topic(
             &topic_name,
@@ -420,7 +690,9 @@ pub async fn publish(
         }
 
         if self.schema_manager.is_check_schema(&topic_name) {
+            let span = trace.step("mqtt.publish.schema");
             if let Err(e) = self.schema_manager.validate(&topic_name, &publish.payload) {
+                span.set_error(e.to_string());
                 return Some(build_pub_ack_fail(
                     &self.protocol,
                     &connection,
@@ -433,39 +705,69 @@ pub async fn publish(
 
         let client_id = connection.client_id.clone();
 
-        // Persisting stores message data
-        let offset = match save_message(
-            &self.message_storage_adapter,
-            &self.delay_message_manager,
+        // Responses to requests of the admin API go to the waiting request instead of the storage.
+        // A drop action of a rule discards the message, the publisher is still acknowledged
+        let is_response = capture_broker_response(
             &self.cache_manager,
-            &self.client_pool,
+            &topic_name,
+            &client_id,
             publish,
             publish_properties,
-            &self.subscribe_manager,
-            &client_id,
-            &topic,
-            &delay_info,
-        )
-        .await
-        {
-            Ok(da) => {
-                format!("{:?}", da)
-            }
-            Err(e) => {
-                return Some(build_pub_ack_fail(
-                    &self.protocol,
-                    &connection,
-                    publish.pkid,
-                    Some(e.to_string()),
-                    is_puback,
-                ))
+        );
+        let is_drop = is_response
+            || self.rule_engine.apply(
+                &client_id,
+                &connection.login_user,
+                &topic_name,
+                publish,
+                publish_properties,
+            );
+
+        // Subscribers and connectors continue the trace from the span of the broker
+        let traced_properties = trace.inject_publish_properties(publish_properties);
+        let publish_properties = traced_properties.as_ref().unwrap_or(publish_properties);
+
+        // Persisting stores message data
+        let offset = if is_drop {
+            None
+        } else {
+            let span = trace.step("mqtt.publish.storage");
+            match save_message(
+                &self.message_storage_adapter,
+                &self.delay_message_manager,
+                &self.cache_manager,
+                publish,
+                publish_properties,
+                &self.subscribe_manager,
+                &client_id,
+                &topic,
+                &delay_info,
+            )
+            .await
+            {
+                Ok(da) => da,
+                Err(e) => {
+                    record_storage_write_failure();
+                    span.set_error(e.to_string());
+                    return Some(build_pub_ack_fail(
+                        &self.protocol,
+                        &connection,
+                        publish.pkid,
+                        Some(e.to_string()),
+                        is_puback,
+                    ));
+                }
             }
         };
 
-        let user_properties: Vec<(String, String)> = vec![("offset".to_string(), offset)];
+        let topic_metrics = &self.cache_manager.topic_metrics_manager;
+        topic_metrics.record_in(&topic_name, publish.qos, publish.payload.len());
+        if is_drop && !is_response {
+            topic_metrics.record_dropped(&topic_name);
+        }
 
-        self.cache_manager
-            .add_topic_alias(connect_id, &topic_name, publish_properties);
+        let user_properties: Vec<(String, String)> =
+            vec![("offset".to_string(), format!("{:?}", offset))];
 
         match publish.qos {
             QoS::AtMostOnce => None,
@@ -693,6 +995,36 @@ pub async fn subscribe(
             return packet;
         }
 
+        // Subscriptions are converted into the tenant namespace after the ACL check
+        let subscribe = &match tenant_subscribe(&connection.tenant, subscribe) {
+            Ok(data) => data,
+            Err(e) => {
+                return response_packet_mqtt_suback(
+                    &self.protocol,
+                    &connection,
+                    subscribe.packet_identifier,
+                    vec![SubscribeReasonCode::TopicFilterInvalid; subscribe.filters.len()],
+                    Some(e.to_string()),
+                );
+            }
+        };
+
+        if let Err(e) = check_tenant_subscription_quota(
+            &self.cache_manager,
+            &self.subscribe_manager,
+            &connection.tenant,
+            &connection.client_id,
+            subscribe,
+        ) {
+            return response_packet_mqtt_suback(
+                &self.protocol,
+                &connection,
+                subscribe.packet_identifier,
+                vec![SubscribeReasonCode::QuotaExceeded; subscribe.filters.len()],
+                Some(e.to_string()),
+            );
+        }
+
         let new_subs = is_new_sub(&connection.client_id, subscribe, &self.subscribe_manager).await;
 
         if let Err(e) = save_subscribe(
@@ -726,12 +1058,27 @@ pub async fn subscribe(
         )
         .await;
 
+        for filter in subscribe.filters.iter() {
+            fire_topic_event(
+                &self.cache_manager,
+                HookEventType::ClientSubscribed,
+                &connection.client_id,
+                &connection.login_user,
+                &filter.path,
+                Some(filter.qos),
+            );
+        }
+
+        let suback_sent = self
+            .connection_manager
+            .wait_suback(connect_id, subscribe.packet_identifier);
         try_send_retain_message(
             self.protocol.clone(),
             connection.client_id.clone(),
             subscribe.clone(),
             subscribe_properties.clone(),
-            self.client_pool.clone(),
+            connect_id,
+            suback_sent,
             self.cache_manager.clone(),
             self.connection_manager.clone(),
             new_subs,
@@ -801,6 +1148,18 @@ pub async fn un_subscribe(
             );
         };
 
+        let un_subscribe = &match tenant_unsubscribe(&connection.tenant, un_subscribe) {
+            Ok(data) => data,
+            Err(e) => {
+                return response_packet_mqtt_unsuback(
+                    &connection,
+                    un_subscribe.pkid,
+                    vec![UnsubAckReason::TopicFilterInvalid],
+                    Some(e.to_string()),
+                );
+            }
+        };
+
         if let Some(packet) = un_subscribe_validator(
             &connection.client_id,
             &self.subscribe_manager,
@@ -839,6 +1198,17 @@ pub async fn un_subscribe(
         )
         .await;
 
+        for filter in un_subscribe.filters.iter() {
+            fire_topic_event(
+                &self.cache_manager,
+                HookEventType::ClientUnsubscribed,
+                &connection.client_id,
+                &connection.login_user,
+                filter,
+                None,
+            );
+        }
+
         response_packet_mqtt_unsuback(
             &connection,
             un_subscribe.pkid,
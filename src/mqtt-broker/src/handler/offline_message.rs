@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::{
@@ -25,11 +26,19 @@
 };
 use crate::{
     observability::metrics::packets::record_messages_dropped_discard_metrics,
-    storage::message::MessageStorage, subscribe::manager::SubscribeManager,
+    observability::metrics::session::{
+        record_offline_messages_dropped, record_offline_queue_overflow,
+    },
+    observability::system_topic::warn::st_report_offline_queue_overflow,
+    storage::message::MessageStorage,
+    subscribe::{
+        common::{is_queue_sub, is_share_sub},
+        manager::SubscribeManager,
+    },
 };
 use common_base::tools::now_second;
+use common_config::mqtt::broker_mqtt_conf;
 use delay_message::DelayMessageManager;
-use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::{message::MqttMessage, topic::MqttTopic};
 use protocol::mqtt::common::{Publish, PublishProperties};
 use storage_adapter::storage::StorageAdapter;
@@ -43,7 +52,6 @@ pub async fn save_message<S>(
     message_storage_adapter: &Arc<S>,
     delay_message_manager: &Arc<DelayMessageManager<S>>,
     cache_manager: &Arc<CacheManager>,
-    client_pool: &Arc<ClientPool>,
     publish: &Publish,
     publish_properties: &Option<PublishProperties>,
     subscribe_manager: &Arc<SubscribeManager>,
@@ -78,7 +86,6 @@ pub async fn save_message<S>(
     // Persisting retain message data
     save_retain_message(
         cache_manager,
-        client_pool,
         topic.topic_name.clone(),
         client_id,
         publish,
@@ -88,6 +95,8 @@ pub async fn save_message<S>(
 
     return save_simple_message(
         message_storage_adapter,
+        cache_manager,
+        subscribe_manager,
         publish,
         publish_properties,
         client_id,
@@ -148,8 +157,11 @@ async fn save_delay_message<S>(
     Err(MqttBrokerError::FailedToBuildMessage)
 }
 
-async fn save_simple_message<S>(
+#[allow(clippy::too_many_arguments)]
+pub async fn save_simple_message<S>(
     message_storage_adapter: &Arc<S>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
     publish: &Publish,
     publish_properties: &Option<PublishProperties>,
     client_id: &str,
@@ -164,10 +176,96 @@ async fn save_simple_message<S>(
     {
         let message_storage = MessageStorage::new(message_storage_adapter.clone());
         let offsets = message_storage
-            .append_topic_message(&topic.topic_id, vec![record])
+            .append_topic_message(&topic.topic_id, vec![record.clone()])
             .await?;
+
+        if let Some(offset) = offsets.first() {
+            let offline_sessions = offline_subscribers(cache_manager, subscribe_manager, topic);
+            enqueue_offline_sessions(
+                message_storage_adapter,
+                cache_manager,
+                &offline_sessions,
+                publish,
+                topic,
+                *offset,
+            )
+            .await;
+        }
+
+        if broker_mqtt_conf().cluster_route.enable {
+            if let Some(offset) = offsets.first() {
+                let mut record = record;
+                record.offset = Some(*offset);
+                subscribe_manager.message_route.route(
+                    cache_manager,
+                    &topic.topic_id,
+                    &topic.topic_name,
+                    &record,
+                );
+            }
+        }
         return Ok(Some(format!("{:?}", offsets)));
     }
 
     Err(MqttBrokerError::FailedToBuildMessage)
 }
+
+// The offline persistent sessions subscribed to the topic with the paths of their matching
+// subscriptions. A message is stored even when all of them drop it, connectors and the
+// subscribers of other brokers read the topic as well.
+fn offline_subscribers(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    topic: &MqttTopic,
+) -> HashMap<String, Vec<String>> {
+    let mut sessions: HashMap<String, Vec<String>> = HashMap::new();
+    for info in subscribe_manager.match_subscribe_by_topic(&topic.topic_name) {
+        if is_share_sub(&info.path)
+            || is_queue_sub(&info.path)
+            || !cache_manager.offline_queue.is_offline(&info.client_id)
+        {
+            continue;
+        }
+        sessions.entry(info.client_id).or_default().push(info.path);
+    }
+    sessions
+}
+
+// Counts the message against the queues of the offline persistent sessions subscribed to the topic
+async fn enqueue_offline_sessions<S>(
+    message_storage_adapter: &Arc<S>,
+    cache_manager: &Arc<CacheManager>,
+    subscribers: &HashMap<String, Vec<String>>,
+    publish: &Publish,
+    topic: &MqttTopic,
+    offset: u64,
+) where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let config = cache_manager.get_offline_message_config();
+    for (client_id, paths) in subscribers.iter() {
+        let result = cache_manager.offline_queue.enqueue(
+            &config,
+            client_id,
+            paths,
+            &topic.topic_id,
+            offset,
+            publish.payload.len() as u64,
+            publish.qos,
+        );
+        if result.dropped > 0 {
+            record_offline_messages_dropped(&config.drop_policy, result.dropped);
+        }
+        if result.first_overflow {
+            record_offline_queue_overflow(&config.drop_policy);
+            st_report_offline_queue_overflow(
+                &cache_manager.client_pool,
+                cache_manager,
+                message_storage_adapter,
+                client_id,
+                &config.drop_policy,
+            )
+            .await;
+        }
+    }
+}
@@ -0,0 +1,492 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::error::common::CommonError;
+use common_base::tools::now_second;
+use common_config::mqtt::broker_mqtt_conf;
+use common_config::mqtt::config::OfflineMessage;
+use dashmap::{DashMap, DashSet};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::offline_queue::{
+    MqttOfflineDroppedRange, MqttOfflineQueue, MqttOfflineQueuedMessage,
+};
+use protocol::mqtt::common::QoS;
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+use super::cache::CacheManager;
+use crate::storage::offline_queue::OfflineQueueStorage;
+
+pub const OFFLINE_DROP_POLICY_OLDEST: &str = "drop_oldest";
+pub const OFFLINE_DROP_POLICY_NEWEST: &str = "drop_newest";
+
+const OFFLINE_QUEUE_SYNC_INTERVAL_MS: u64 = 1000;
+
+fn is_full(
+    queue: &MqttOfflineQueue,
+    config: &OfflineMessage,
+    extra_messages: usize,
+    extra_bytes: u64,
+) -> bool {
+    (config.max_messages_num > 0
+        && queue.messages.len() + extra_messages > config.max_messages_num as usize)
+        || (config.max_bytes > 0 && queue.bytes + extra_bytes > config.max_bytes)
+}
+
+// Every subscription of the session pushes the topic on its own, so each one skips the
+// dropped offsets separately
+fn mark_dropped(queue: &mut MqttOfflineQueue, paths: &[String], topic_id: &str, offset: u64) {
+    let period = queue.period;
+    for path in paths {
+        let ranges = queue
+            .dropped
+            .entry(path.to_owned())
+            .or_default()
+            .entry(topic_id.to_owned())
+            .or_default();
+        match ranges.last_mut() {
+            Some(range) if range.period == period => {
+                range.start = range.start.min(offset);
+                range.end = range.end.max(offset + 1);
+            }
+            _ => ranges.push(MqttOfflineDroppedRange {
+                start: offset,
+                end: offset + 1,
+                period,
+            }),
+        }
+    }
+}
+
+#[derive(Default, Debug, PartialEq)]
+pub struct EnqueueResult {
+    // Number of messages dropped from the queue of the session
+    pub dropped: u64,
+    // The queue of the session overflowed for the first time since it went offline
+    pub first_overflow: bool,
+}
+
+// The queues of the offline persistent sessions. When a queue is over its limits, the
+// dropped offsets are remembered per subscription and topic and skipped once the session is
+// back online. Changes are written to the placement center by a background task.
+pub struct OfflineQueueManager {
+    storage: OfflineQueueStorage,
+    sessions: DashMap<String, MqttOfflineQueue>,
+    // Sessions whose queue changed since it was last written
+    dirty: DashSet<String>,
+}
+
+impl OfflineQueueManager {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        OfflineQueueManager {
+            storage: OfflineQueueStorage::new(client_pool),
+            sessions: DashMap::with_capacity(8),
+            dirty: DashSet::with_capacity(8),
+        }
+    }
+
+    pub fn session_offline(&self, client_id: &str) {
+        let mut queue = self
+            .sessions
+            .entry(client_id.to_owned())
+            .or_insert_with(|| MqttOfflineQueue {
+                client_id: client_id.to_owned(),
+                ..Default::default()
+            });
+        queue.broker_id = broker_mqtt_conf().broker_id;
+        queue.offline_since = now_second();
+        queue.period += 1;
+        queue.messages.clear();
+        queue.bytes = 0;
+        queue.overflowed = false;
+        self.dirty.insert(client_id.to_owned());
+    }
+
+    // The session may have gone offline on another broker, or before this broker restarted
+    pub async fn session_online(
+        &self,
+        client_id: &str,
+        config: &OfflineMessage,
+    ) -> Result<(), CommonError> {
+        if !self.sessions.contains_key(client_id) {
+            let Some(queue) = self.storage.get(client_id).await? else {
+                return Ok(());
+            };
+            self.sessions.entry(client_id.to_owned()).or_insert(queue);
+        }
+
+        if let Some(mut queue) = self.sessions.get_mut(client_id) {
+            if queue.offline_since > 0 && config.drop_qos0 {
+                queue.qos0_drop_window = Some((queue.offline_since, now_second()));
+            }
+            queue.broker_id = broker_mqtt_conf().broker_id;
+            queue.offline_since = 0;
+            queue.messages.clear();
+            queue.bytes = 0;
+            self.dirty.insert(client_id.to_owned());
+        }
+        Ok(())
+    }
+
+    pub fn remove_session(&self, client_id: &str) {
+        self.sessions.remove(client_id);
+        self.dirty.insert(client_id.to_owned());
+    }
+
+    pub fn is_offline(&self, client_id: &str) -> bool {
+        self.sessions
+            .get(client_id)
+            .map(|queue| queue.offline_since > 0)
+            .unwrap_or(false)
+    }
+
+    pub fn queued(&self, client_id: &str) -> (usize, u64) {
+        self.sessions
+            .get(client_id)
+            .map(|queue| (queue.messages.len(), queue.bytes))
+            .unwrap_or((0, 0))
+    }
+
+    // Counts a message of the topic against the queue of the session, `paths` are the
+    // subscriptions of the session matching the topic.
+    #[allow(clippy::too_many_arguments)]
+    pub fn enqueue(
+        &self,
+        config: &OfflineMessage,
+        client_id: &str,
+        paths: &[String],
+        topic_id: &str,
+        offset: u64,
+        size: u64,
+        qos: QoS,
+    ) -> EnqueueResult {
+        let mut result = EnqueueResult::default();
+        let Some(mut queue) = self.sessions.get_mut(client_id) else {
+            return result;
+        };
+        if queue.offline_since == 0 || (qos == QoS::AtMostOnce && config.drop_qos0) {
+            return result;
+        }
+
+        if config.drop_policy != OFFLINE_DROP_POLICY_NEWEST
+            || !(queue.overflowed || is_full(&queue, config, 1, size))
+        {
+            queue.bytes += size;
+            queue.messages.push_back(MqttOfflineQueuedMessage {
+                topic_id: topic_id.to_owned(),
+                offset,
+                size,
+            });
+            while is_full(&queue, config, 0, 0) {
+                let Some(oldest) = queue.messages.pop_front() else {
+                    break;
+                };
+                queue.bytes -= oldest.size;
+                mark_dropped(&mut queue, paths, &oldest.topic_id, oldest.offset);
+                result.dropped += 1;
+            }
+        } else {
+            // Once the queue is full everything newer is dropped until the session is back
+            mark_dropped(&mut queue, paths, topic_id, offset);
+            result.dropped = 1;
+        }
+
+        if result.dropped > 0 && !queue.overflowed {
+            queue.overflowed = true;
+            result.first_overflow = true;
+        }
+        self.dirty.insert(client_id.to_owned());
+        result
+    }
+
+    // Whether a message of a subscription was dropped from the queue of the session while it
+    // was offline
+    pub fn is_dropped(
+        &self,
+        client_id: &str,
+        path: &str,
+        topic_id: &str,
+        offset: Option<u64>,
+        qos: QoS,
+        create_time: u64,
+    ) -> bool {
+        let Some(mut queue) = self.sessions.get_mut(client_id) else {
+            return false;
+        };
+
+        if qos == QoS::AtMostOnce {
+            if let Some((start, end)) = queue.qos0_drop_window {
+                if create_time >= start && create_time <= end {
+                    return true;
+                }
+            }
+        }
+
+        let Some(offset) = offset else {
+            return false;
+        };
+        let Some(topics) = queue.dropped.get_mut(path) else {
+            return false;
+        };
+        let Some(ranges) = topics.get_mut(topic_id) else {
+            return false;
+        };
+        let dropped = ranges
+            .iter()
+            .any(|range| offset >= range.start && offset < range.end);
+
+        // The push of the subscription has passed these ranges
+        let len = ranges.len();
+        ranges.retain(|range| offset + 1 < range.end);
+        if ranges.len() != len {
+            if ranges.is_empty() {
+                topics.remove(topic_id);
+                if topics.is_empty() {
+                    queue.dropped.remove(path);
+                }
+            }
+            self.dirty.insert(client_id.to_owned());
+        }
+        dropped
+    }
+
+    // Continue counting the sessions that went offline on this broker before it restarted
+    pub async fn load(&self) -> Result<(), CommonError> {
+        let broker_id = broker_mqtt_conf().broker_id;
+        for queue in self.storage.list().await? {
+            if queue.broker_id == broker_id && !self.sessions.contains_key(&queue.client_id) {
+                self.sessions.insert(queue.client_id.clone(), queue);
+            }
+        }
+        Ok(())
+    }
+
+    // Sessions connected to another broker now belong to that broker and are only dropped here
+    pub async fn flush<F>(&self, is_connected_elsewhere: F)
+    where
+        F: Fn(&str) -> bool,
+    {
+        let client_ids: Vec<String> = self.dirty.iter().map(|id| id.clone()).collect();
+        for client_id in client_ids {
+            self.dirty.remove(&client_id);
+            if is_connected_elsewhere(&client_id) {
+                self.sessions.remove(&client_id);
+                continue;
+            }
+
+            let queue = self.sessions.get(&client_id).map(|queue| queue.clone());
+            let result = match &queue {
+                Some(queue) if !queue.is_empty() => self.storage.save(queue).await,
+                _ => self.storage.delete(&client_id).await,
+            };
+            if let Err(e) = result {
+                self.dirty.insert(client_id.clone());
+                error!(
+                    "Failed to persist the offline queue of client {}, error: {}",
+                    client_id, e
+                );
+                continue;
+            }
+            if queue.is_some_and(|queue| queue.is_empty()) {
+                self.sessions
+                    .remove_if(&client_id, |_, queue| queue.is_empty());
+            }
+        }
+    }
+}
+
+pub async fn start_offline_queue_sync_thread(
+    cache_manager: Arc<CacheManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    if let Err(e) = cache_manager.offline_queue.load().await {
+        error!("Failed to load the offline queues, error: {}", e);
+    }
+
+    let broker_id = broker_mqtt_conf().broker_id;
+    let is_connected_elsewhere = |client_id: &str| {
+        cache_manager
+            .get_session_info(client_id)
+            .is_some_and(|session| {
+                session.connection_id.is_some()
+                    && session.broker_id.is_some_and(|id| id != broker_id)
+            })
+    };
+
+    let mut stop_rx = stop_send.subscribe();
+    let mut interval = tokio::time::interval(Duration::from_millis(OFFLINE_QUEUE_SYNC_INTERVAL_MS));
+    loop {
+        tokio::select! {
+            val = stop_rx.recv() => {
+                if let Ok(flag) = val {
+                    if flag {
+                        cache_manager.offline_queue.flush(&is_connected_elsewhere).await;
+                        info!("{}","Offline queue sync thread stopped successfully.");
+                        break;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                cache_manager.offline_queue.flush(&is_connected_elsewhere).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_config::mqtt::default::default_offline_message;
+    use protocol::mqtt::common::QoS;
+
+    use super::*;
+
+    fn manager() -> OfflineQueueManager {
+        OfflineQueueManager::new(Arc::new(ClientPool::new(1)))
+    }
+
+    fn paths() -> Vec<String> {
+        vec!["t/#".to_string()]
+    }
+
+    fn config(max_messages_num: u32, drop_policy: &str) -> OfflineMessage {
+        OfflineMessage {
+            enable: true,
+            max_messages_num,
+            drop_policy: drop_policy.to_string(),
+            ..default_offline_message()
+        }
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_test() {
+        let manager = manager();
+        let config = config(2, OFFLINE_DROP_POLICY_OLDEST);
+
+        // Online sessions are not queued
+        manager.session_offline("c1");
+        manager.session_online("c1", &config).await.unwrap();
+        assert_eq!(
+            manager.enqueue(&config, "c1", &paths(), "t1", 0, 10, QoS::AtLeastOnce),
+            EnqueueResult::default()
+        );
+
+        manager.session_offline("c1");
+        assert_eq!(
+            manager
+                .enqueue(&config, "c1", &paths(), "t1", 1, 10, QoS::AtLeastOnce)
+                .dropped,
+            0
+        );
+        manager.enqueue(&config, "c1", &paths(), "t1", 2, 10, QoS::AtLeastOnce);
+        let res = manager.enqueue(&config, "c1", &paths(), "t1", 3, 10, QoS::AtLeastOnce);
+        assert_eq!(res.dropped, 1);
+        assert!(res.first_overflow);
+        let res = manager.enqueue(&config, "c1", &paths(), "t1", 4, 10, QoS::AtLeastOnce);
+        assert_eq!(res.dropped, 1);
+        assert!(!res.first_overflow);
+        assert_eq!(manager.queued("c1"), (2, 20));
+
+        manager.session_online("c1", &config).await.unwrap();
+        assert!(!manager.is_dropped("c1", "t/#", "t1", Some(0), QoS::AtLeastOnce, 0));
+        assert!(manager.is_dropped("c1", "t/#", "t1", Some(1), QoS::AtLeastOnce, 0));
+        assert!(manager.is_dropped("c1", "t/#", "t1", Some(2), QoS::AtLeastOnce, 0));
+        assert!(!manager.is_dropped("c1", "t/#", "t1", Some(3), QoS::AtLeastOnce, 0));
+        assert!(!manager.is_dropped("c1", "t/#", "t2", Some(1), QoS::AtLeastOnce, 0));
+    }
+
+    #[tokio::test]
+    async fn drop_newest_test() {
+        let manager = manager();
+        let mut config = config(0, OFFLINE_DROP_POLICY_NEWEST);
+        config.max_bytes = 25;
+
+        manager.session_offline("c1");
+        manager.enqueue(&config, "c1", &paths(), "t1", 0, 10, QoS::AtLeastOnce);
+        manager.enqueue(&config, "c1", &paths(), "t1", 1, 10, QoS::AtLeastOnce);
+        let res = manager.enqueue(&config, "c1", &paths(), "t1", 2, 10, QoS::AtLeastOnce);
+        assert_eq!(res.dropped, 1);
+        assert!(res.first_overflow);
+        // A smaller message would fit, but everything newer is dropped once the queue is full
+        assert_eq!(
+            manager
+                .enqueue(&config, "c1", &paths(), "t1", 3, 1, QoS::AtLeastOnce)
+                .dropped,
+            1
+        );
+
+        manager.session_online("c1", &config).await.unwrap();
+        assert!(!manager.is_dropped("c1", "t/#", "t1", Some(1), QoS::AtLeastOnce, 0));
+        assert!(manager.is_dropped("c1", "t/#", "t1", Some(2), QoS::AtLeastOnce, 0));
+        assert!(manager.is_dropped("c1", "t/#", "t1", Some(3), QoS::AtLeastOnce, 0));
+        // The range is released once the push has passed it
+        assert!(!manager.is_dropped("c1", "t/#", "t1", Some(2), QoS::AtLeastOnce, 0));
+    }
+
+    #[tokio::test]
+    async fn drop_qos0_test() {
+        let manager = manager();
+        let mut config = config(0, OFFLINE_DROP_POLICY_OLDEST);
+        config.drop_qos0 = true;
+
+        manager.session_offline("c1");
+        let offline_at = now_second();
+        assert_eq!(
+            manager.enqueue(&config, "c1", &paths(), "t1", 0, 10, QoS::AtMostOnce),
+            EnqueueResult::default()
+        );
+        assert_eq!(manager.queued("c1"), (0, 0));
+        assert!(manager.is_offline("c1"));
+
+        manager.session_online("c1", &config).await.unwrap();
+        assert!(!manager.is_offline("c1"));
+        assert!(manager.is_dropped("c1", "t/#", "t1", Some(0), QoS::AtMostOnce, offline_at));
+        assert!(!manager.is_dropped("c1", "t/#", "t1", Some(0), QoS::AtLeastOnce, offline_at));
+
+        manager.remove_session("c1");
+        assert!(!manager.is_dropped("c1", "t/#", "t1", Some(0), QoS::AtMostOnce, offline_at));
+    }
+
+    #[tokio::test]
+    async fn drop_per_subscription_test() {
+        let manager = manager();
+        let config = config(1, OFFLINE_DROP_POLICY_NEWEST);
+        let paths = vec!["t/#".to_string(), "t/+".to_string()];
+
+        manager.session_offline("c1");
+        manager.enqueue(&config, "c1", &paths, "t1", 0, 10, QoS::AtLeastOnce);
+        manager.enqueue(&config, "c1", &paths, "t1", 1, 10, QoS::AtLeastOnce);
+        // Only the first message past the limit reports the overflow
+        let res = manager.enqueue(&config, "c1", &paths, "t1", 2, 10, QoS::AtLeastOnce);
+        assert_eq!(res.dropped, 1);
+        assert!(!res.first_overflow);
+
+        // A second offline period starts a new range
+        manager.session_online("c1", &config).await.unwrap();
+        manager.session_offline("c1");
+        manager.enqueue(&config, "c1", &paths, "t1", 5, 10, QoS::AtLeastOnce);
+        manager.enqueue(&config, "c1", &paths, "t1", 6, 10, QoS::AtLeastOnce);
+        manager.session_online("c1", &config).await.unwrap();
+
+        // Passing the range on one subscription does not release it for the other
+        assert!(manager.is_dropped("c1", "t/#", "t1", Some(1), QoS::AtLeastOnce, 0));
+        assert!(!manager.is_dropped("c1", "t/#", "t1", Some(3), QoS::AtLeastOnce, 0));
+        assert!(manager.is_dropped("c1", "t/+", "t1", Some(1), QoS::AtLeastOnce, 0));
+        assert!(!manager.is_dropped("c1", "t/+", "t1", Some(5), QoS::AtLeastOnce, 0));
+        assert!(manager.is_dropped("c1", "t/#", "t1", Some(6), QoS::AtLeastOnce, 0));
+        assert!(!manager.is_dropped("c1", "t/#", "t1", Some(6), QoS::AtLeastOnce, 0));
+    }
+}
@@ -0,0 +1,258 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_config::mqtt::broker_mqtt_conf;
+use dashmap::DashMap;
+use grpc_clients::mqtt::inner::call::broker_mqtt_forward_message;
+use metadata_struct::mqtt::message::MqttMessage;
+use metadata_struct::mqtt::route::MqttForwardRecord;
+use protocol::broker_mqtt::broker_mqtt_inner::ForwardMessageRequest;
+use protocol::mqtt::common::{Publish, PublishProperties};
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+use super::cache::CacheManager;
+use super::connection::REQUEST_RESPONSE_PREFIX_NAME;
+use super::tenant::strip_tenant_namespace;
+
+// Owner segment of the response topics used by the broker itself, no client may subscribe to it
+pub const BROKER_RESPONSE_OWNER: &str = "$broker";
+
+pub fn response_topic_prefix(client_id: &str) -> String {
+    format!("{}{}/", REQUEST_RESPONSE_PREFIX_NAME, client_id)
+}
+
+pub fn broker_response_topic(broker_id: u64) -> String {
+    format!(
+        "{}{}/{}",
+        REQUEST_RESPONSE_PREFIX_NAME, BROKER_RESPONSE_OWNER, broker_id
+    )
+}
+
+// A client id with a level separator or a wildcard would make its response topics overlap
+// with those of other clients, such clients are not given a response topic prefix.
+pub fn is_response_client_id(client_id: &str) -> bool {
+    !client_id.is_empty()
+        && client_id != BROKER_RESPONSE_OWNER
+        && !client_id.contains(['/', '+', '#'])
+}
+
+fn parse_broker_response_topic(topic_name: &str) -> Option<u64> {
+    let rest = topic_name.strip_prefix(REQUEST_RESPONSE_PREFIX_NAME)?;
+    let broker_id = rest
+        .strip_prefix(BROKER_RESPONSE_OWNER)?
+        .strip_prefix('/')?;
+    broker_id.parse::<u64>().ok()
+}
+
+// The response topics under the prefix of a client can only be subscribed by that client.
+// A wildcard in place of the client id would match the response topics of every client,
+// so it is rejected as well. Topics and filters outside the prefix are not restricted.
+pub fn is_response_topic_allowed(client_id: &str, topic_name: &str) -> bool {
+    if !topic_name.starts_with(REQUEST_RESPONSE_PREFIX_NAME) {
+        return true;
+    }
+    is_response_client_id(client_id) && topic_name.starts_with(&response_topic_prefix(client_id))
+}
+
+// Requests published by the admin API, waiting for the response of the client
+// that handled them. Responses are matched by their correlation data.
+#[derive(Default)]
+pub struct RequestResponseManager {
+    waiters: DashMap<String, oneshot::Sender<MqttMessage>>,
+}
+
+impl RequestResponseManager {
+    pub fn wait(&self, correlation_id: &str) -> oneshot::Receiver<MqttMessage> {
+        let (sx, rx) = oneshot::channel();
+        self.waiters.insert(correlation_id.to_owned(), sx);
+        rx
+    }
+
+    pub fn cancel(&self, correlation_id: &str) {
+        self.waiters.remove(correlation_id);
+    }
+
+    pub fn complete(&self, message: MqttMessage) -> bool {
+        let Some(correlation_data) = message.correlation_data.as_ref() else {
+            return false;
+        };
+        let correlation_id = String::from_utf8_lossy(correlation_data).to_string();
+        if let Some((_, sx)) = self.waiters.remove(&correlation_id) {
+            return sx.send(message).is_ok();
+        }
+        false
+    }
+}
+
+// Hands a message published to a broker response topic to the request waiting for it,
+// on this broker or on the broker that published the request. Returns false when the
+// topic is not a broker response topic and the message should be processed as usual.
+pub fn capture_broker_response(
+    cache_manager: &Arc<CacheManager>,
+    topic_name: &str,
+    client_id: &str,
+    publish: &Publish,
+    publish_properties: &Option<PublishProperties>,
+) -> bool {
+    let topic_name = strip_tenant_namespace(topic_name);
+    let Some(broker_id) = parse_broker_response_topic(&topic_name) else {
+        return false;
+    };
+
+    let conf = broker_mqtt_conf();
+    let message = MqttMessage::build_message(client_id, publish, publish_properties, 0);
+    if broker_id == conf.broker_id {
+        if !cache_manager.request_response.complete(message) {
+            debug!(
+                "Response of client {} does not match any pending request",
+                client_id
+            );
+        }
+        return true;
+    }
+
+    let Some(addr) = cache_manager
+        .node_lists
+        .get(&broker_id)
+        .map(|node| node.node_inner_addr.clone())
+    else {
+        debug!(
+            "Broker {} is not in the node list, response of client {} was dropped",
+            broker_id, client_id
+        );
+        return true;
+    };
+    let Some(record) = MqttMessage::build_record(client_id, publish, publish_properties, 0) else {
+        return true;
+    };
+
+    let request = ForwardMessageRequest {
+        cluster_name: conf.cluster_name.clone(),
+        source_broker_id: conf.broker_id,
+        records: vec![MqttForwardRecord { topic_name, record }.encode()],
+    };
+    let client_pool = cache_manager.client_pool.clone();
+    tokio::spawn(async move {
+        if let Err(e) = broker_mqtt_forward_message(&client_pool, &[addr], request).await {
+            warn!(
+                "Failed to forward response to broker {}, error message: {}",
+                broker_id, e
+            );
+        }
+    });
+    true
+}
+
+// Responses forwarded by another broker for requests published on this broker
+pub fn complete_forwarded_response(
+    cache_manager: &Arc<CacheManager>,
+    message: &MqttForwardRecord,
+) -> bool {
+    if parse_broker_response_topic(&message.topic_name) != Some(broker_mqtt_conf().broker_id) {
+        return false;
+    }
+    match MqttMessage::decode_record(message.record.clone()) {
+        Ok(msg) => {
+            cache_manager.request_response.complete(msg);
+        }
+        Err(e) => {
+            warn!("Failed to decode forwarded response, error message: {}", e);
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[test]
+    fn response_topic_allowed_test() {
+        assert_eq!(
+            response_topic_prefix("c1"),
+            "/sys/request_response/c1/".to_string()
+        );
+        assert!(is_response_topic_allowed(
+            "c1",
+            "/sys/request_response/c1/a"
+        ));
+        assert!(is_response_topic_allowed(
+            "c1",
+            "/sys/request_response/c1/#"
+        ));
+        assert!(!is_response_topic_allowed(
+            "c1",
+            "/sys/request_response/c2/a"
+        ));
+        assert!(!is_response_topic_allowed(
+            "c1",
+            "/sys/request_response/+/a"
+        ));
+        assert!(!is_response_topic_allowed("c1", "/sys/request_response/#"));
+        assert!(is_response_topic_allowed("c1", "/sys/other/c2"));
+        assert!(is_response_topic_allowed("c1", "a/b"));
+
+        // The response topics of "c1/x" are not under the prefix of "c1" and the other way round
+        assert!(!is_response_client_id("c1/x"));
+        assert!(!is_response_client_id("c+"));
+        assert!(!is_response_client_id(BROKER_RESPONSE_OWNER));
+        assert!(is_response_client_id("c1"));
+        assert!(!is_response_topic_allowed(
+            "c1/x",
+            "/sys/request_response/c1/x/a"
+        ));
+        assert!(!is_response_topic_allowed(
+            "c1",
+            "/sys/request_response/c1x/a"
+        ));
+
+        let topic = broker_response_topic(3);
+        assert_eq!(parse_broker_response_topic(&topic), Some(3));
+        assert!(!is_response_topic_allowed(BROKER_RESPONSE_OWNER, &topic));
+        assert_eq!(
+            parse_broker_response_topic("/sys/request_response/c1/3"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn request_response_manager_test() {
+        let manager = RequestResponseManager::default();
+        let rx = manager.wait("id-1");
+
+        let other = MqttMessage {
+            correlation_data: Some(Bytes::from("id-2")),
+            ..Default::default()
+        };
+        assert!(!manager.complete(other));
+        assert!(!manager.complete(MqttMessage::default()));
+
+        let message = MqttMessage {
+            correlation_data: Some(Bytes::from("id-1")),
+            payload: Bytes::from("pong"),
+            ..Default::default()
+        };
+        assert!(manager.complete(message));
+        assert_eq!(rx.await.unwrap().payload, Bytes::from("pong"));
+
+        let _rx = manager.wait("id-3");
+        manager.cancel("id-3");
+        assert!(manager.waiters.is_empty());
+    }
+}
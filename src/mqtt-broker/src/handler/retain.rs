@@ -20,28 +20,210 @@
     get_retain_flag_by_retain_as_published, is_send_msg_by_bo_local,
     is_send_retain_msg_by_retain_handling,
 };
+use crate::handler::tenant::is_same_tenant;
+use crate::handler::tenant::strip_tenant_namespace;
 use crate::observability::metrics::packets::{
     record_retain_recv_metrics, record_retain_sent_metrics,
 };
 use crate::server::connection_manager::ConnectionManager;
-use crate::storage::topic::TopicStorage;
+use crate::storage::retain::{
+    PlacementRetainMessageStorage, RetainMessageEntry, RetainMessageSnapshot, RetainMessageStorage,
+};
 use crate::subscribe::common::Subscriber;
-use crate::subscribe::common::{get_sub_topic_id_list, min_qos};
+use crate::subscribe::common::{decode_sub_path, is_queue_sub, is_share_sub, min_qos};
 use crate::subscribe::common::{is_ignore_push_error, SubPublishParam};
 use crate::subscribe::manager::SubscribeManager;
 use crate::subscribe::push::send_publish_packet_to_client;
+use crate::subscribe::trie::TopicTrie;
 use bytes::Bytes;
+use common_base::tools::now_second;
+use common_config::mqtt::broker_mqtt_conf;
 use dashmap::DashMap;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::message::MqttMessage;
+use metadata_struct::mqtt::topic::MqttTopic;
 use protocol::mqtt::common::{
     qos, MqttPacket, MqttProtocol, Publish, PublishProperties, Subscribe, SubscribeProperties,
 };
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
-use tokio::sync::broadcast;
-use tokio::time::sleep;
-use tracing::{info, warn};
+use tokio::sync::{broadcast, oneshot};
+use tokio::time::timeout;
+use tracing::{error, info, warn};
+
+// Upper bound on how long retained messages wait for the SUBACK to be written
+const RETAIN_MESSAGE_SUBACK_WAIT_MS: u64 = 3000;
+
+// In-memory index of the retained messages of all topics, kept in sync with the configured
+// RetainMessageStorage. Topics are indexed in a trie so that wildcard filters only visit the
+// topics they match.
+pub struct RetainMessageManager {
+    storage: RwLock<Arc<dyn RetainMessageStorage>>,
+    // (topic_name, RetainMessageEntry)
+    messages: DashMap<String, RetainMessageEntry>,
+    topic_trie: TopicTrie<String>,
+    position: AtomicU64,
+}
+
+impl RetainMessageManager {
+    pub fn new(storage: Arc<dyn RetainMessageStorage>) -> Self {
+        RetainMessageManager {
+            storage: RwLock::new(storage),
+            messages: DashMap::with_capacity(8),
+            topic_trie: TopicTrie::new(),
+            position: AtomicU64::new(0),
+        }
+    }
+
+    pub fn placement(client_pool: Arc<ClientPool>) -> Self {
+        Self::new(Arc::new(PlacementRetainMessageStorage::new(client_pool)))
+    }
+
+    pub fn set_storage(&self, storage: Arc<dyn RetainMessageStorage>) {
+        *self.storage.write().unwrap() = storage;
+        self.position.store(0, Ordering::SeqCst);
+    }
+
+    fn storage(&self) -> Arc<dyn RetainMessageStorage> {
+        self.storage.read().unwrap().clone()
+    }
+
+    pub fn follows_topic_metadata(&self) -> bool {
+        self.storage().follows_topic_metadata()
+    }
+
+    pub async fn save(&self, entry: RetainMessageEntry) -> Result<(), MqttBrokerError> {
+        self.storage().save(&entry).await?;
+        self.apply(entry);
+        Ok(())
+    }
+
+    // Pull the changes made by other brokers into the local index. The snapshot writer also
+    // saves the index once enough changes have been read since the latest snapshot.
+    pub async fn sync(&self, is_snapshot_writer: bool) -> Result<(), MqttBrokerError> {
+        let storage = self.storage();
+        let position = self.position.load(Ordering::SeqCst);
+        let (entries, next) = storage.read_changes(position).await?;
+        for entry in entries {
+            self.apply(entry);
+        }
+        self.position.store(next, Ordering::SeqCst);
+
+        if is_snapshot_writer && storage.need_snapshot(next) {
+            let snapshot = RetainMessageSnapshot {
+                position: next,
+                entries: self
+                    .messages
+                    .iter()
+                    .map(|entry| entry.value().clone())
+                    .filter(|entry| !is_expired(entry.expired_at))
+                    .collect(),
+            };
+            storage.save_snapshot(&snapshot).await?;
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, entry: RetainMessageEntry) {
+        if entry.message.is_none() || is_expired(entry.expired_at) {
+            self.remove(&entry.topic_name);
+            return;
+        }
+
+        let topic_name = entry.topic_name.clone();
+        if self.messages.insert(topic_name.clone(), entry).is_none() {
+            self.topic_trie.insert(&topic_name, topic_name.clone());
+        }
+    }
+
+    // Retained messages carried by the topic metadata of the placement center
+    pub fn apply_topic(&self, topic: &MqttTopic) {
+        let message = topic
+            .retain_message
+            .as_ref()
+            .filter(|data| !data.is_empty())
+            .and_then(|data| serde_json::from_slice::<MqttMessage>(data).ok());
+        self.apply(RetainMessageEntry {
+            topic_name: topic.topic_name.clone(),
+            message,
+            expired_at: topic.retain_message_expired_at.unwrap_or_default(),
+        });
+    }
+
+    pub fn remove(&self, topic_name: &str) {
+        if self.messages.remove(topic_name).is_some() {
+            self.topic_trie.remove(topic_name, &topic_name.to_owned());
+        }
+    }
+
+    pub fn get(&self, topic_name: &str) -> Option<RetainMessageEntry> {
+        self.messages
+            .get(topic_name)
+            .map(|entry| entry.clone())
+            .filter(|entry| !is_expired(entry.expired_at))
+    }
+
+    // The unexpired retained messages of all topics matching the filter
+    pub fn match_filter(&self, filter: &str) -> Vec<RetainMessageEntry> {
+        let mut results = Vec::new();
+        for topic_name in self.topic_trie.match_filter(filter) {
+            let Some(entry) = self.messages.get(&topic_name).map(|entry| entry.clone()) else {
+                continue;
+            };
+            if is_expired(entry.expired_at) {
+                self.remove(&topic_name);
+                continue;
+            }
+            results.push(entry);
+        }
+        results
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+fn is_expired(expired_at: u64) -> bool {
+    expired_at > 0 && expired_at <= now_second()
+}
+
+pub async fn start_retain_message_sync_thread(
+    cache_manager: Arc<CacheManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let mut stop_rx = stop_send.subscribe();
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        tokio::select! {
+            val = stop_rx.recv() => {
+                if let Ok(flag) = val {
+                    if flag {
+                        info!("{}","Retain message sync thread stopped successfully.");
+                        break;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                // One broker is enough to write the snapshots, the one with the smallest id
+                let is_snapshot_writer = cache_manager
+                    .node_lists
+                    .iter()
+                    .map(|node| node.node_id)
+                    .min()
+                    .is_none_or(|node_id| node_id == broker_mqtt_conf().broker_id);
+                if let Err(e) = cache_manager.retain_message_manager.sync(is_snapshot_writer).await {
+                    error!("Failed to sync retain messages, error message: {}", e);
+                }
+            }
+        }
+    }
+}
 
 pub async fn is_new_sub(
     client_id: &str,
@@ -60,7 +242,6 @@ pub async fn is_new_sub(
 
 pub async fn save_retain_message(
     cache_manager: &Arc<CacheManager>,
-    client_pool: &Arc<ClientPool>,
     topic_name: String,
     client_id: &str,
     publish: &Publish,
@@ -70,23 +251,34 @@ pub async fn save_retain_message(
         return Ok(());
     }
 
-    let topic_storage = TopicStorage::new(client_pool.clone());
-
+    let manager = &cache_manager.retain_message_manager;
     if publish.payload.is_empty() {
-        topic_storage
-            .delete_retain_message(topic_name.clone())
+        manager
+            .save(RetainMessageEntry {
+                topic_name: topic_name.clone(),
+                message: None,
+                expired_at: 0,
+            })
             .await?;
-        cache_manager.update_topic_retain_message(&topic_name, Some(Vec::new()));
+        if manager.follows_topic_metadata() {
+            cache_manager.update_topic_retain_message(&topic_name, Some(Vec::new()));
+        }
     } else {
         record_retain_recv_metrics(publish.qos);
         let message_expire = build_message_expire(cache_manager, publish_properties);
         let retain_message =
             MqttMessage::build_message(client_id, publish, publish_properties, message_expire);
-        topic_storage
-            .set_retain_message(topic_name.clone(), &retain_message, message_expire)
+        let encode = retain_message.encode();
+        manager
+            .save(RetainMessageEntry {
+                topic_name: topic_name.clone(),
+                message: Some(retain_message),
+                expired_at: message_expire,
+            })
             .await?;
-
-        cache_manager.update_topic_retain_message(&topic_name, Some(retain_message.encode()));
+        if manager.follows_topic_metadata() {
+            cache_manager.update_topic_retain_message(&topic_name, Some(encode));
+        }
     }
 
     Ok(())
@@ -98,24 +290,41 @@ pub async fn try_send_retain_message(
     client_id: String,
     subscribe: Subscribe,
     subscribe_properties: Option<SubscribeProperties>,
-    client_pool: Arc<ClientPool>,
+    connect_id: u64,
+    suback_sent: oneshot::Receiver<()>,
     cache_manager: Arc<CacheManager>,
     connection_manager: Arc<ConnectionManager>,
     is_new_subs: DashMap<String, bool>,
 ) {
     tokio::spawn(async move {
-        // Do not send messages immediately. Avoid publishing and subing in the same connection successively.
-        // At this point, the network model is processed in parallel.
-        // There may be a situation where the subscription starts before the reserved message is successfully retained,
-        // resulting in the subscription end not receiving the reserved message.
-        sleep(Duration::from_secs(3)).await;
+        // Retained messages must follow the SUBACK on the wire, so wait until it has been
+        // written. The timeout only guards against a SUBACK that is never written.
+        match timeout(
+            Duration::from_millis(RETAIN_MESSAGE_SUBACK_WAIT_MS),
+            suback_sent,
+        )
+        .await
+        {
+            Ok(Ok(())) => {}
+            Ok(Err(_)) => {
+                // The connection was closed before the SUBACK was written
+                return;
+            }
+            Err(_) => {
+                connection_manager.remove_suback_waiter(connect_id, subscribe.packet_identifier);
+                warn!(
+                    "Timed out waiting for the SUBACK to be written before sending retain messages, client_id:{}",
+                    client_id
+                );
+            }
+        }
+
         let (stop_sx, _) = broadcast::channel(1);
         if let Err(e) = send_retain_message(
             &protocol,
             &client_id,
             &subscribe,
             &subscribe_properties,
-            &client_pool,
             &cache_manager,
             &connection_manager,
             &stop_sx,
@@ -139,7 +348,6 @@ async fn send_retain_message(
     client_id: &str,
     subscribe: &Subscribe,
     subscribe_properties: &Option<SubscribeProperties>,
-    client_pool: &Arc<ClientPool>,
     cache_manager: &Arc<CacheManager>,
     connection_manager: &Arc<ConnectionManager>,
     stop_sx: &broadcast::Sender<bool>,
@@ -153,6 +361,11 @@ async fn send_retain_message(
     }
 
     for filter in subscribe.filters.iter() {
+        // Retained messages are not sent for shared subscriptions
+        if is_share_sub(&filter.path) || is_queue_sub(&filter.path) {
+            continue;
+        }
+
         if !is_send_retain_msg_by_retain_handling(
             &filter.path,
             &filter.retain_handling,
@@ -162,20 +375,17 @@ async fn send_retain_message(
             continue;
         }
 
-        let topic_id_list = get_sub_topic_id_list(cache_manager, &filter.path).await;
-        let topic_storage = TopicStorage::new(client_pool.clone());
         let cluster = cache_manager.get_cluster_config();
+        let entries = cache_manager
+            .retain_message_manager
+            .match_filter(&decode_sub_path(&filter.path));
 
-        for topic_id in topic_id_list.iter() {
-            let topic_name = if let Some(topic_name) = cache_manager.topic_name_by_id(topic_id) {
-                topic_name
-            } else {
+        for entry in entries {
+            if !is_same_tenant(&filter.path, &entry.topic_name) {
                 continue;
-            };
-
-            let msg = if let Some(message) = topic_storage.get_retain_message(&topic_name).await? {
-                message
-            } else {
+            }
+            let topic_name = entry.topic_name;
+            let Some(msg) = entry.message else {
                 continue;
             };
 
@@ -198,7 +408,7 @@ async fn send_retain_message(
 
             let properties = PublishProperties {
                 payload_format_indicator: msg.format_indicator,
-                message_expiry_interval: Some(msg.expiry_interval as u32),
+                message_expiry_interval: remaining_expiry_interval(entry.expired_at),
                 topic_alias: None,
                 response_topic: msg.response_topic,
                 correlation_data: msg.correlation_data,
@@ -217,7 +427,7 @@ async fn send_retain_message(
                 qos,
                 pkid,
                 retain,
-                topic: Bytes::from(topic_name.clone()),
+                topic: Bytes::from(strip_tenant_namespace(&topic_name)),
                 payload: msg.payload,
             };
 
@@ -244,8 +454,8 @@ async fn send_retain_message(
             )
             .await?;
             info!(
-                "retain the successful message sending: client_id: {}, topi_id: {}",
-                client_id, topic_id
+                "retain the successful message sending: client_id: {}, topic_name: {}",
+                client_id, topic_name
             );
 
             record_retain_sent_metrics(qos);
@@ -253,3 +463,98 @@ async fn send_retain_message(
     }
     Ok(())
 }
+
+// Seconds the retained message has left to live, as forwarded to the subscriber
+fn remaining_expiry_interval(expired_at: u64) -> Option<u32> {
+    if expired_at == 0 {
+        return None;
+    }
+    Some(expired_at.saturating_sub(now_second()) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::now_second;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use metadata_struct::mqtt::topic::MqttTopic;
+
+    use super::{remaining_expiry_interval, RetainMessageManager};
+    use crate::storage::retain::RetainMessageEntry;
+
+    fn build_entry(topic_name: &str, expired_at: u64) -> RetainMessageEntry {
+        RetainMessageEntry {
+            topic_name: topic_name.to_owned(),
+            message: Some(MqttMessage::default()),
+            expired_at,
+        }
+    }
+
+    #[test]
+    fn match_filter_test() {
+        let manager = RetainMessageManager::placement(Arc::new(ClientPool::new(1)));
+        let expired_at = now_second() + 60;
+        manager.apply(build_entry("site/1/status/online", expired_at));
+        manager.apply(build_entry("site/2/status/battery/level", expired_at));
+        manager.apply(build_entry("site/3/config", expired_at));
+
+        let mut topics: Vec<String> = manager
+            .match_filter("site/+/status/#")
+            .into_iter()
+            .map(|entry| entry.topic_name)
+            .collect();
+        topics.sort();
+        assert_eq!(
+            topics,
+            vec!["site/1/status/online", "site/2/status/battery/level"]
+        );
+        assert_eq!(manager.match_filter("site/3/config").len(), 1);
+        assert!(manager.match_filter("site/4/#").is_empty());
+    }
+
+    #[test]
+    fn clear_and_expire_test() {
+        let manager = RetainMessageManager::placement(Arc::new(ClientPool::new(1)));
+        manager.apply(build_entry("a/b", now_second() + 60));
+        manager.apply(build_entry("a/c", now_second() - 1));
+        manager.apply(build_entry("a/d", 0));
+        assert_eq!(manager.len(), 2);
+        assert!(manager.get("a/c").is_none());
+
+        manager.apply(RetainMessageEntry {
+            topic_name: "a/b".to_owned(),
+            message: None,
+            expired_at: 0,
+        });
+        let topics: Vec<String> = manager
+            .match_filter("a/#")
+            .into_iter()
+            .map(|entry| entry.topic_name)
+            .collect();
+        assert_eq!(topics, vec!["a/d"]);
+    }
+
+    #[test]
+    fn apply_topic_test() {
+        let manager = RetainMessageManager::placement(Arc::new(ClientPool::new(1)));
+        let mut topic = MqttTopic::new("id".to_owned(), "c1".to_owned(), "x/y".to_owned());
+        topic.retain_message = Some(MqttMessage::default().encode());
+        topic.retain_message_expired_at = Some(now_second() + 60);
+        manager.apply_topic(&topic);
+        assert!(manager.get("x/y").is_some());
+
+        topic.retain_message = Some(Vec::new());
+        manager.apply_topic(&topic);
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn remaining_expiry_interval_test() {
+        assert_eq!(remaining_expiry_interval(0), None);
+        assert_eq!(remaining_expiry_interval(now_second() - 10), Some(0));
+        let remaining = remaining_expiry_interval(now_second() + 30).unwrap();
+        assert!(remaining > 0 && remaining <= 30);
+    }
+}
@@ -20,10 +20,13 @@
 
 use crate::subscribe::manager::SubscribeManager;
 
-use super::{cache::CacheManager, error::MqttBrokerError, subscribe::save_subscribe};
+use super::{
+    cache::CacheManager, error::MqttBrokerError, subscribe::save_subscribe, tenant::tenant_sub_path,
+};
 
 pub async fn try_auto_subscribe(
     client_id: String,
+    tenant: &str,
     login: &Option<Login>,
     protocol: &MqttProtocol,
     client_pool: &Arc<ClientPool>,
@@ -51,7 +54,7 @@ pub async fn try_auto_subscribe(
         }
 
         filters.push(Filter {
-            path,
+            path: tenant_sub_path(tenant, &path)?,
             qos: auto_subscribe_rule.qos,
             nolocal: auto_subscribe_rule.no_local,
             preserve_retain: auto_subscribe_rule.retain_as_published,
@@ -11,19 +11,28 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-
 use super::{
-    cache::CacheManager, subscribe::parse_subscribe,
+    cache::CacheManager,
+    subscribe::{parse_subscribe, subscribe_match_path},
     topic_rewrite::convert_sub_path_by_rewrite_rule,
 };
+use crate::subscribe::common::is_match_sub_and_topic;
 use crate::subscribe::manager::SubscribeManager;
 use common_base::tools::now_second;
 use common_config::mqtt::broker_mqtt_conf;
 use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+use metadata_struct::mqtt::topic::MqttTopic;
 use std::{sync::Arc, time::Duration};
-use tokio::{select, sync::broadcast, time::sleep};
-use tracing::{error, info};
+use tokio::{
+    select,
+    sync::broadcast::{self, error::RecvError},
+};
+use tracing::{error, info, warn};
 
+// New topics are routed as soon as they are added to the cache. The periodic pass also
+// routes topics created since the last pass, in case a notification was missed, and
+// refreshes the routes of subscriptions whose rewrite rule changed.
 pub async fn start_parse_subscribe_by_new_topic_thread(
     client_pool: &Arc<ClientPool>,
     metadata_cache: &Arc<CacheManager>,
@@ -31,9 +40,11 @@ pub async fn start_parse_subscribe_by_new_topic_thread(
     stop_send: broadcast::Sender<bool>,
 ) {
     info!("Subscribe manager thread started successfully.");
+    let mut stop_rx = stop_send.subscribe();
+    let mut topic_rx = metadata_cache.topic_create_sender.subscribe();
+    let mut interval = tokio::time::interval(Duration::from_secs(10));
     let mut last_update_time: u64 = 0;
     loop {
-        let mut stop_rx = stop_send.subscribe();
         select! {
             val = stop_rx.recv() =>{
                 if let Ok(flag) = val {
@@ -43,13 +54,30 @@ pub async fn start_parse_subscribe_by_new_topic_thread(
                     }
                 }
             }
-            _ = parse_subscribe_by_new_topic(
-                client_pool,
-                metadata_cache,
-                subscribe_manager,
-                last_update_time) =>{
-                    last_update_time = now_second();
-                    sleep(Duration::from_secs(10)).await;
+            val = topic_rx.recv() => {
+                match val {
+                    Ok(topic_name) => {
+                        if let Some(topic) = metadata_cache.get_topic_by_name(&topic_name) {
+                            parse_subscribe_by_topic(client_pool, metadata_cache, subscribe_manager, &topic).await;
+                        }
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        warn!("Subscribe manager thread missed {} new topics, they are parsed by the next periodic pass.", num);
+                    }
+                    Err(RecvError::Closed) => {
+                        break;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                let now = now_second();
+                parse_subscribe_by_new_topic(
+                    client_pool,
+                    metadata_cache,
+                    subscribe_manager,
+                    last_update_time,
+                ).await;
+                last_update_time = now;
             }
         }
     }
@@ -78,25 +106,199 @@ async fn parse_subscribe_by_new_topic(
                     continue;
                 }
             };
-        for (_, topic) in cache_manager.topic_info.clone() {
-            if topic.create_time < last_update_time {
-                continue;
-            }
-            if let Err(e) = parse_subscribe(
+        let match_path = subscribe_match_path(&subscribe.path, &rewrite_sub_path);
+        if subscribe_manager.add_subscribe_route(&subscribe.client_id, &subscribe.path, &match_path)
+        {
+            reparse_subscribe(
                 client_pool,
+                cache_manager,
                 subscribe_manager,
-                &subscribe.client_id,
-                &topic,
-                &subscribe.protocol,
-                subscribe.pkid,
-                &subscribe.filter,
-                &subscribe.subscribe_properties,
+                &subscribe,
+                &match_path,
                 &rewrite_sub_path,
             )
-            .await
-            {
-                error!("Failed to parse subscribe, error message: {}", e);
-            }
+            .await;
+        }
+    }
+
+    for (_, topic) in cache_manager.topic_info.clone() {
+        if topic.create_time < last_update_time {
+            continue;
         }
+        parse_subscribe_by_topic(client_pool, cache_manager, subscribe_manager, &topic).await;
+    }
+}
+
+// The rewrite rule of a subscription changed, the topics it no longer matches stop being pushed
+// and the topics it matches now are pushed with the new rewritten path
+async fn reparse_subscribe(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    subscribe: &MqttSubscribe,
+    match_path: &str,
+    rewrite_sub_path: &Option<String>,
+) {
+    subscribe_manager.remove_exclusive_push_by_unmatched_topic(
+        &subscribe.client_id,
+        &subscribe.path,
+        match_path,
+    );
+
+    for (_, topic) in cache_manager.topic_info.clone() {
+        if is_match_sub_and_topic(match_path, &topic.topic_name).is_err() {
+            continue;
+        }
+        if let Err(e) = parse_subscribe(
+            client_pool,
+            subscribe_manager,
+            &subscribe.client_id,
+            &topic,
+            &subscribe.protocol,
+            subscribe.pkid,
+            &subscribe.filter,
+            &subscribe.subscribe_properties,
+            rewrite_sub_path,
+        )
+        .await
+        {
+            error!("Failed to parse subscribe, error message: {}", e);
+        }
+    }
+}
+
+// Route a topic to the local subscriptions whose filter matches it
+async fn parse_subscribe_by_topic(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    topic: &MqttTopic,
+) {
+    let conf = broker_mqtt_conf();
+
+    for info in subscribe_manager.match_subscribe_by_topic(&topic.topic_name) {
+        let Some(subscribe) = subscribe_manager.get_subscribe(&info.client_id, &info.path) else {
+            continue;
+        };
+        if subscribe.broker_id != conf.broker_id {
+            continue;
+        }
+        let rewrite_sub_path =
+            match convert_sub_path_by_rewrite_rule(cache_manager, &subscribe.path) {
+                Ok(rewrite_sub_path) => rewrite_sub_path,
+                Err(e) => {
+                    error!(
+                        "Failed to convert sub path by rewrite rule, error message: {}",
+                        e
+                    );
+                    continue;
+                }
+            };
+        if let Err(e) = parse_subscribe(
+            client_pool,
+            subscribe_manager,
+            &subscribe.client_id,
+            topic,
+            &subscribe.protocol,
+            subscribe.pkid,
+            &subscribe.filter,
+            &subscribe.subscribe_properties,
+            &rewrite_sub_path,
+        )
+        .await
+        {
+            error!("Failed to parse subscribe, error message: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_subscribe_by_new_topic;
+    use crate::handler::cache::CacheManager;
+    use crate::subscribe::manager::SubscribeManager;
+    use common_base::enum_type::topic_rewrite_action_enum::TopicRewriteActionEnum;
+    use common_base::tools::{now_nanos, now_second, unique_id};
+    use common_config::mqtt::{config::BrokerMqttConfig, init_broker_mqtt_conf_by_config};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+    use metadata_struct::mqtt::topic::MqttTopic;
+    use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
+    use protocol::mqtt::common::{Filter, MqttProtocol};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn reparse_subscribe_by_rewrite_rule_test() {
+        let conf = init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: unique_id(),
+            ..Default::default()
+        });
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(
+            client_pool.clone(),
+            conf.cluster_name.clone(),
+        ));
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+
+        for (topic_name, topic_id) in [("x/y/1", "t1"), ("z/y/1", "t2")] {
+            let topic = MqttTopic {
+                topic_id: topic_id.to_string(),
+                topic_name: topic_name.to_string(),
+                ..Default::default()
+            };
+            cache_manager.add_topic(topic_name, &topic);
+        }
+
+        let path = "x/y/1".to_string();
+        subscribe_manager.add_subscribe(MqttSubscribe {
+            client_id: "c1".to_string(),
+            path: path.clone(),
+            cluster_name: conf.cluster_name.clone(),
+            broker_id: conf.broker_id,
+            protocol: MqttProtocol::Mqtt5,
+            filter: Filter {
+                path: path.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        subscribe_manager.add_subscribe_route("c1", &path, &path);
+
+        parse_subscribe_by_new_topic(&client_pool, &cache_manager, &subscribe_manager, 0).await;
+        let mut pushed: Vec<String> = subscribe_manager
+            .exclusive_push
+            .iter()
+            .map(|raw| raw.topic_id.clone())
+            .collect();
+        assert_eq!(pushed, vec!["t1".to_string()]);
+
+        cache_manager.add_topic_rewrite_rule(MqttTopicRewriteRule {
+            cluster: conf.cluster_name.clone(),
+            action: TopicRewriteActionEnum::All.to_string(),
+            source_topic: "x/y/+".to_string(),
+            dest_topic: "z/y/$1".to_string(),
+            regex: r"^x/y/(\d+)$".to_string(),
+            timestamp: now_nanos(),
+        });
+
+        // No new topic since the last pass, only the changed rule moves the subscription
+        parse_subscribe_by_new_topic(
+            &client_pool,
+            &cache_manager,
+            &subscribe_manager,
+            now_second() + 10,
+        )
+        .await;
+        pushed = subscribe_manager
+            .exclusive_push
+            .iter()
+            .map(|raw| raw.topic_id.clone())
+            .collect();
+        assert_eq!(pushed, vec!["t2".to_string()]);
+        assert!(subscribe_manager
+            .topic_subscribe_list
+            .get("x/y/1")
+            .map(|list| list.is_empty())
+            .unwrap_or(true));
     }
 }
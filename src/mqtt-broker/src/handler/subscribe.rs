@@ -30,8 +30,9 @@
 
 use crate::subscribe::{
     common::{
-        decode_queue_info, decode_share_info, get_share_sub_leader, is_match_sub_and_topic,
-        is_queue_sub, is_share_sub, Subscriber, SHARE_QUEUE_DEFAULT_GROUP_NAME,
+        decode_queue_info, decode_share_info, decode_sub_path, get_share_sub_leader,
+        is_match_sub_and_topic, is_queue_sub, is_share_sub, Subscriber,
+        SHARE_QUEUE_DEFAULT_GROUP_NAME,
     },
     manager::{ShareSubShareSub, SubscribeManager},
 };
@@ -106,41 +107,84 @@ pub async fn save_subscribe(
     let new_filters = filters.to_owned();
     let new_cache_manager = cache_manager.to_owned();
     tokio::spawn(async move {
-        for filter in new_filters.clone() {
-            let rewrite_sub_path =
-                match convert_sub_path_by_rewrite_rule(&new_cache_manager, &filter.path) {
-                    Ok(rewrite_sub_path) => rewrite_sub_path,
-                    Err(e) => {
-                        error!(
-                            "Failed to convert sub path by rewrite rule, error message: {}",
-                            e
-                        );
-                        continue;
-                    }
-                };
-
-            for (_, topic) in new_cache_manager.topic_info.clone() {
-                if let Err(e) = parse_subscribe(
-                    &new_client_pool,
-                    &new_subscribe_manager,
-                    &new_client_id,
-                    &topic,
-                    &new_protocol,
-                    new_subscribe.packet_identifier,
-                    &filter,
-                    &new_subscribe_properties,
-                    &rewrite_sub_path,
-                )
-                .await
-                {
-                    error!("Failed to parse subscribe, error message: {}", e);
-                }
-            }
+        for filter in new_filters.iter() {
+            route_subscribe(
+                &new_client_pool,
+                &new_cache_manager,
+                &new_subscribe_manager,
+                &new_client_id,
+                &new_protocol,
+                new_subscribe.packet_identifier,
+                filter,
+                &new_subscribe_properties,
+            )
+            .await;
         }
     });
     Ok(())
 }
 
+// Route a subscription of a client connected to this broker and start pushing the topics
+// its filter currently matches.
+#[allow(clippy::too_many_arguments)]
+pub async fn route_subscribe(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_id: &str,
+    protocol: &MqttProtocol,
+    pkid: u16,
+    filter: &Filter,
+    subscribe_properties: &Option<SubscribeProperties>,
+) {
+    let rewrite_sub_path = match convert_sub_path_by_rewrite_rule(cache_manager, &filter.path) {
+        Ok(rewrite_sub_path) => rewrite_sub_path,
+        Err(e) => {
+            error!(
+                "Failed to convert sub path by rewrite rule, error message: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    let match_path = subscribe_match_path(&filter.path, &rewrite_sub_path);
+    subscribe_manager.add_subscribe_route(client_id, &filter.path, &match_path);
+
+    // Only the topics the filter matches are parsed, found through the topic trie
+    for topic_name in cache_manager.topic_trie.match_filter(&match_path) {
+        let Some(topic) = cache_manager.get_topic_by_name(&topic_name) else {
+            continue;
+        };
+        if let Err(e) = parse_subscribe(
+            client_pool,
+            subscribe_manager,
+            client_id,
+            &topic,
+            protocol,
+            pkid,
+            filter,
+            subscribe_properties,
+            &rewrite_sub_path,
+        )
+        .await
+        {
+            error!("Failed to parse subscribe, error message: {}", e);
+        }
+    }
+}
+
+// The filter a subscription matches topic names with. Shared and queue subscriptions
+// match with the filter after their prefix, exclusive ones with the rewritten path if any.
+pub fn subscribe_match_path(path: &str, rewrite_sub_path: &Option<String>) -> String {
+    if is_share_sub(path) || is_queue_sub(path) {
+        return decode_sub_path(path);
+    }
+    rewrite_sub_path
+        .clone()
+        .unwrap_or_else(|| decode_sub_path(path))
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn parse_subscribe(
     client_pool: &Arc<ClientPool>,
@@ -312,7 +356,7 @@ fn add_exclusive_push(
 
 #[cfg(test)]
 mod tests {
-    use super::add_exclusive_push;
+    use super::{add_exclusive_push, subscribe_match_path};
     use crate::subscribe::manager::SubscribeManager;
     use common_base::tools::unique_id;
     use metadata_struct::mqtt::topic::MqttTopic;
@@ -362,4 +406,22 @@ fn add_exclusive_push_test() {
             }
         }
     }
+
+    #[test]
+    fn subscribe_match_path_test() {
+        assert_eq!(subscribe_match_path("/sensor/+", &None), "/sensor/+");
+        assert_eq!(
+            subscribe_match_path("$share/g1/sensor/#", &None),
+            "/sensor/#"
+        );
+        assert_eq!(subscribe_match_path("$queue/jobs/+", &None), "/jobs/+");
+        assert_eq!(
+            subscribe_match_path("$exclusive/sensor/1", &None),
+            "/sensor/1"
+        );
+        assert_eq!(
+            subscribe_match_path("/x/y/1", &Some("z/y/1".to_string())),
+            "z/y/1"
+        );
+    }
 }
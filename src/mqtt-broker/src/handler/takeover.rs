@@ -0,0 +1,277 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Arc;
+
+use axum::extract::ws::Message;
+use bytes::BytesMut;
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::mqtt::inner::call::broker_mqtt_session_takeover;
+use grpc_clients::placement::mqtt::call::placement_set_subscribe;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+use protocol::broker_mqtt::broker_mqtt_inner::SessionTakeoverRequest;
+use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
+use protocol::mqtt::common::{DisconnectReasonCode, MqttProtocol};
+use protocol::placement_center::placement_center_mqtt::SetSubscribeRequest;
+use tracing::{error, info, warn};
+
+use super::cache::CacheManager;
+use super::error::MqttBrokerError;
+use super::response::response_packet_mqtt_distinct_by_reason;
+use super::subscribe::route_subscribe;
+use crate::server::connection_manager::ConnectionManager;
+use crate::storage::session::SessionStorage;
+use crate::subscribe::manager::SubscribeManager;
+
+// Make sure no other connection in the cluster still holds the session of the client id.
+// The stored session records the broker and connection it is bound to, a connection on
+// another broker is closed by that broker through the inner service.
+pub async fn takeover_session(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_id: &str,
+    connect_id: u64,
+) -> Result<(), MqttBrokerError> {
+    let session_storage = SessionStorage::new(client_pool.clone());
+    let Some(session) = session_storage.get_session(client_id.to_owned()).await? else {
+        return Ok(());
+    };
+
+    // The session is not bound to any connection
+    let (Some(broker_id), Some(old_connect_id)) = (session.broker_id, session.connection_id) else {
+        return Ok(());
+    };
+
+    let conf = broker_mqtt_conf();
+    if broker_id == conf.broker_id {
+        if old_connect_id != connect_id {
+            kick_taken_over_connection(
+                cache_manager,
+                connection_manager,
+                subscribe_manager,
+                client_id,
+                old_connect_id,
+                conf.broker_id,
+            )
+            .await;
+        }
+        return Ok(());
+    }
+
+    let Some(node) = cache_manager
+        .node_lists
+        .get(&broker_id)
+        .map(|node| node.clone())
+    else {
+        // The broker has left the cluster, its connections are gone with it
+        return Ok(());
+    };
+
+    let request = SessionTakeoverRequest {
+        cluster_name: conf.cluster_name.clone(),
+        client_id: client_id.to_owned(),
+        connection_id: old_connect_id,
+        broker_id: conf.broker_id,
+    };
+    if let Err(e) =
+        broker_mqtt_session_takeover(client_pool, &[node.node_inner_addr], request).await
+    {
+        // An unreachable broker can not push to the client either, the takeover goes on
+        warn!(
+            "Failed to notify broker {} that session {} was taken over, error: {}",
+            broker_id, client_id, e
+        );
+    }
+    Ok(())
+}
+
+// Close the local connection that held a session which is now used by a new connection on
+// broker_id. A new connection on this broker keeps pushing through the same subscriptions,
+// otherwise pushing stops here and the subscriptions are routed again by the broker taking over.
+// The inflight window is written first, the new connection restores it from storage.
+pub async fn kick_taken_over_connection(
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_id: &str,
+    connect_id: u64,
+    broker_id: u64,
+) {
+    if let Err(e) = cache_manager.inflight_persist.release(client_id).await {
+        error!(
+            "Failed to persist the inflight messages of client {} on takeover, error: {}",
+            client_id, e
+        );
+    }
+
+    if broker_id != broker_mqtt_conf().broker_id {
+        subscribe_manager.takeover_client_id(client_id, broker_id);
+    }
+    cache_manager.pkid_metadata.remove_by_client_id(client_id);
+
+    // Connection ids are only unique per broker, a stale id may already belong to another client
+    let Some(connection) = cache_manager.get_connection(connect_id) else {
+        return;
+    };
+    if connection.client_id != client_id {
+        return;
+    }
+
+    if let Some(network) = connection_manager.get_connect(connect_id) {
+        let protocol = network.protocol.clone().unwrap_or(MqttProtocol::Mqtt5);
+        let wrap = MqttPacketWrapper {
+            protocol_version: protocol.clone().into(),
+            packet: response_packet_mqtt_distinct_by_reason(
+                &protocol,
+                Some(DisconnectReasonCode::SessionTakenOver),
+            ),
+        };
+
+        let result = if network.is_tcp() {
+            connection_manager.write_tcp_frame(connect_id, wrap).await
+        } else {
+            let mut codec = MqttCodec::new(Some(protocol.into()));
+            let mut buff = BytesMut::new();
+            match codec.encode_data(wrap.clone(), &mut buff) {
+                Ok(()) => {
+                    connection_manager
+                        .write_websocket_frame(connect_id, wrap, Message::Binary(buff.to_vec()))
+                        .await
+                }
+                Err(e) => Err(MqttBrokerError::WebsocketEncodePacketFailed(e.to_string())),
+            }
+        };
+        if let Err(e) = result {
+            warn!(
+                "Failed to send session taken over to connection {}, error: {}",
+                connect_id, e
+            );
+        }
+    }
+
+    // The session itself is not touched, it already belongs to the new connection
+    connection_manager.close_connect(connect_id).await;
+    cache_manager.remove_connection(connect_id);
+    info!(
+        "Session {} was taken over, connection {} closed",
+        client_id, connect_id
+    );
+}
+
+// Bind the stored subscriptions of a resumed session to this broker and route them here,
+// so that pushing continues from the broker the client is connected to now.
+pub async fn rebind_subscribe(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_id: &str,
+    protocol: &MqttProtocol,
+) -> Result<(), MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    for mut subscribe in subscribes_bound_elsewhere(subscribe_manager, client_id) {
+        subscribe.broker_id = conf.broker_id;
+        subscribe.protocol = protocol.to_owned();
+        let request = SetSubscribeRequest {
+            cluster_name: conf.cluster_name.clone(),
+            client_id: client_id.to_owned(),
+            path: subscribe.path.clone(),
+            subscribe: subscribe.encode(),
+        };
+        placement_set_subscribe(client_pool, &conf.placement_center, request).await?;
+
+        subscribe_manager.add_subscribe(subscribe.clone());
+        route_subscribe(
+            client_pool,
+            cache_manager,
+            subscribe_manager,
+            client_id,
+            &subscribe.protocol,
+            subscribe.pkid,
+            &subscribe.filter,
+            &subscribe.subscribe_properties,
+        )
+        .await;
+    }
+    Ok(())
+}
+
+// Every broker caches the subscriptions of the whole cluster, only those still bound to
+// another broker have to be moved to this one.
+fn subscribes_bound_elsewhere(
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_id: &str,
+) -> Vec<MqttSubscribe> {
+    let broker_id = broker_mqtt_conf().broker_id;
+    subscribe_manager
+        .list_subscribe_by_client_id(client_id)
+        .into_iter()
+        .filter(|subscribe| subscribe.broker_id != broker_id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_config::mqtt::{broker_mqtt_conf, init_broker_mqtt_conf_by_path};
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+
+    use super::subscribes_bound_elsewhere;
+    use crate::subscribe::manager::SubscribeManager;
+
+    #[test]
+    fn subscribes_bound_elsewhere_test() {
+        let path = format!(
+            "{}/../../config/mqtt-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_broker_mqtt_conf_by_path(&path);
+        let broker_id = broker_mqtt_conf().broker_id;
+
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        assert!(subscribes_bound_elsewhere(&subscribe_manager, "c1").is_empty());
+
+        subscribe_manager.add_subscribe(MqttSubscribe {
+            client_id: "c1".to_string(),
+            path: "a/#".to_string(),
+            broker_id,
+            ..Default::default()
+        });
+        subscribe_manager.add_subscribe(MqttSubscribe {
+            client_id: "c1".to_string(),
+            path: "b/#".to_string(),
+            broker_id: broker_id + 1,
+            ..Default::default()
+        });
+        subscribe_manager.add_subscribe(MqttSubscribe {
+            client_id: "c2".to_string(),
+            path: "a/#".to_string(),
+            broker_id: broker_id + 1,
+            ..Default::default()
+        });
+        let subscribes = subscribes_bound_elsewhere(&subscribe_manager, "c1");
+        assert_eq!(subscribes.len(), 1);
+        assert_eq!(subscribes[0].path, "b/#");
+
+        // Taken over by another broker, the cache follows the session
+        subscribe_manager.takeover_client_id("c1", broker_id + 2);
+        assert_eq!(
+            subscribes_bound_elsewhere(&subscribe_manager, "c1").len(),
+            2
+        );
+        assert_eq!(subscribe_manager.list_subscribe_by_client_id("c2").len(), 1);
+    }
+}
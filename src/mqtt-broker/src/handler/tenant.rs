@@ -0,0 +1,601 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use bytes::Bytes;
+use common_base::tools::now_second;
+use common_base::utils::topic_util::is_exclusive_sub;
+use common_config::mqtt::broker_mqtt_conf;
+use metadata_struct::mqtt::tenant::{MqttTenant, MqttTenantUsage};
+use protocol::mqtt::common::{LastWill, Subscribe, Unsubscribe};
+use regex::Regex;
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+use super::cache::CacheManager;
+use super::error::MqttBrokerError;
+use crate::storage::tenant::TenantUsageStorage;
+use crate::subscribe::common::{decode_share_info, decode_sub_path, is_queue_sub, is_share_sub};
+use crate::subscribe::manager::SubscribeManager;
+
+// Topics of a tenant are stored as /_tenant/{tenant_name}/{topic}, the prefix is never visible to clients.
+pub const TENANT_TOPIC_ROOT: &str = "/_tenant/";
+
+static TENANT_NAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_-]{1,64}$").unwrap());
+
+const TENANT_USAGE_SYNC_INTERVAL_MS: u64 = 1000;
+
+#[derive(Clone, Default, Debug)]
+pub struct TenantPublishWindow {
+    pub second: u64,
+    pub messages: u64,
+    pub bytes: u64,
+}
+
+impl CacheManager {
+    pub fn add_tenant(&self, tenant: MqttTenant) {
+        self.tenant_info.insert(tenant.tenant_name.clone(), tenant);
+    }
+
+    pub fn remove_tenant(&self, tenant_name: &str) {
+        self.tenant_info.remove(tenant_name);
+        self.tenant_publish_window.remove(tenant_name);
+    }
+
+    pub fn get_tenant(&self, tenant_name: &str) -> Option<MqttTenant> {
+        if let Some(tenant) = self.tenant_info.get(tenant_name) {
+            return Some(tenant.clone());
+        }
+        None
+    }
+
+    pub fn get_all_tenant(&self) -> Vec<MqttTenant> {
+        self.tenant_info
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    pub fn set_tenants(&self, tenants: Vec<MqttTenant>) {
+        self.tenant_info.clear();
+        for tenant in tenants {
+            self.add_tenant(tenant);
+        }
+        self.tenant_publish_window
+            .retain(|name, _| self.tenant_info.contains_key(name));
+    }
+
+    pub fn get_tenant_by_user(&self, username: &str) -> String {
+        if let Some(user) = self.user_info.get(username) {
+            return user.tenant.clone();
+        }
+        String::new()
+    }
+
+    pub fn incr_tenant_connection(&self, tenant_name: &str) {
+        if tenant_name.is_empty() {
+            return;
+        }
+        *self
+            .tenant_connection_num
+            .entry(tenant_name.to_owned())
+            .or_default() += 1;
+    }
+
+    pub fn decr_tenant_connection(&self, tenant_name: &str) {
+        if let Some(mut num) = self.tenant_connection_num.get_mut(tenant_name) {
+            *num = num.saturating_sub(1);
+        }
+        self.tenant_connection_num
+            .remove_if(tenant_name, |_, num| *num == 0);
+    }
+
+    // Connections of the tenant on all brokers, the part of the other brokers is
+    // at most one sync interval old.
+    pub fn tenant_connection_count(&self, tenant_name: &str) -> u64 {
+        let local = self
+            .tenant_connection_num
+            .get(tenant_name)
+            .map(|num| *num)
+            .unwrap_or(0);
+        let remote = self
+            .tenant_remote_connection_num
+            .get(tenant_name)
+            .map(|num| *num)
+            .unwrap_or(0);
+        local + remote
+    }
+
+    pub fn set_tenant_remote_connections(&self, connections: HashMap<String, u64>) {
+        self.tenant_remote_connection_num
+            .retain(|name, _| connections.contains_key(name));
+        for (name, num) in connections {
+            self.tenant_remote_connection_num.insert(name, num);
+        }
+    }
+
+    pub fn local_tenant_connections(&self) -> HashMap<String, u64> {
+        self.tenant_connection_num
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+}
+
+pub fn tenant_name_validator(tenant_name: &str) -> Result<(), MqttBrokerError> {
+    if !TENANT_NAME_REGEX.is_match(tenant_name) {
+        return Err(MqttBrokerError::CommonError(format!(
+            "Tenant name {} is invalid, only letters, digits, '_' and '-' are allowed",
+            tenant_name
+        )));
+    }
+    Ok(())
+}
+
+pub fn is_tenant_topic(topic_name: &str) -> bool {
+    topic_name.starts_with(TENANT_TOPIC_ROOT)
+}
+
+pub fn topic_tenant(topic_name: &str) -> Option<&str> {
+    topic_name
+        .strip_prefix(TENANT_TOPIC_ROOT)
+        .and_then(|rest| rest.split('/').next())
+}
+
+// Whether a subscription path and a topic belong to the same tenant namespace.
+pub fn is_same_tenant(sub_path: &str, topic_name: &str) -> bool {
+    topic_tenant(&decode_sub_path(sub_path)) == topic_tenant(topic_name)
+}
+
+pub fn tenant_topic_name(tenant: &str, topic_name: &str) -> Result<String, MqttBrokerError> {
+    if is_tenant_topic(topic_name) {
+        return Err(MqttBrokerError::TopicNameIncorrectlyFormatted(
+            topic_name.to_owned(),
+        ));
+    }
+
+    if tenant.is_empty() {
+        return Ok(topic_name.to_owned());
+    }
+
+    // A leading '/' is not significant inside a tenant namespace, "/a/b" and "a/b" are the same topic.
+    let topic_name = topic_name.strip_prefix('/').unwrap_or(topic_name);
+    Ok(format!("{}{}/{}", TENANT_TOPIC_ROOT, tenant, topic_name))
+}
+
+pub fn strip_tenant_namespace(topic_name: &str) -> String {
+    if let Some(rest) = topic_name.strip_prefix(TENANT_TOPIC_ROOT) {
+        if let Some(index) = rest.find('/') {
+            return rest[index + 1..].to_string();
+        }
+    }
+    topic_name.to_owned()
+}
+
+pub fn tenant_sub_path(tenant: &str, sub_path: &str) -> Result<String, MqttBrokerError> {
+    // Share, queue and exclusive paths are decoded to a topic with a leading '/',
+    // so the namespace is applied to the decoded path and the prefix is put back.
+    let path = tenant_topic_name(tenant, &decode_sub_path(sub_path))?;
+    if tenant.is_empty() {
+        return Ok(sub_path.to_owned());
+    }
+
+    if is_share_sub(sub_path) {
+        let (group_name, _) = decode_share_info(sub_path);
+        return Ok(format!("$share/{}/{}", group_name, &path[1..]));
+    }
+
+    if is_queue_sub(sub_path) {
+        return Ok(format!("$queue/{}", &path[1..]));
+    }
+
+    if is_exclusive_sub(sub_path) {
+        return Ok(format!("$exclusive{}", path));
+    }
+
+    Ok(path)
+}
+
+pub fn tenant_subscribe(tenant: &str, subscribe: &Subscribe) -> Result<Subscribe, MqttBrokerError> {
+    let mut new_subscribe = subscribe.clone();
+    for filter in new_subscribe.filters.iter_mut() {
+        filter.path = tenant_sub_path(tenant, &filter.path)?;
+    }
+    Ok(new_subscribe)
+}
+
+pub fn tenant_unsubscribe(
+    tenant: &str,
+    un_subscribe: &Unsubscribe,
+) -> Result<Unsubscribe, MqttBrokerError> {
+    let mut filters = Vec::with_capacity(un_subscribe.filters.len());
+    for path in un_subscribe.filters.iter() {
+        filters.push(tenant_sub_path(tenant, path)?);
+    }
+    Ok(Unsubscribe {
+        pkid: un_subscribe.pkid,
+        filters,
+    })
+}
+
+pub fn tenant_last_will(
+    tenant: &str,
+    last_will: &Option<LastWill>,
+) -> Result<Option<LastWill>, MqttBrokerError> {
+    if let Some(will) = last_will {
+        let topic_name = String::from_utf8(will.topic.to_vec())?;
+        let mut new_will = will.clone();
+        new_will.topic = Bytes::from(tenant_topic_name(tenant, &topic_name)?);
+        return Ok(Some(new_will));
+    }
+    Ok(None)
+}
+
+fn get_tenant_or_err(
+    cache_manager: &Arc<CacheManager>,
+    tenant: &str,
+) -> Result<MqttTenant, MqttBrokerError> {
+    cache_manager
+        .get_tenant(tenant)
+        .ok_or_else(|| MqttBrokerError::TenantNotExist(tenant.to_owned()))
+}
+
+// Connection quotas are enforced over the connections of all brokers.
+pub fn check_tenant_connection_quota(
+    cache_manager: &Arc<CacheManager>,
+    tenant: &str,
+) -> Result<(), MqttBrokerError> {
+    if tenant.is_empty() {
+        return Ok(());
+    }
+
+    let tenant_info = get_tenant_or_err(cache_manager, tenant)?;
+    if tenant_info.max_connections > 0
+        && cache_manager.tenant_connection_count(tenant) >= tenant_info.max_connections
+    {
+        return Err(MqttBrokerError::TenantQuotaExceeded(
+            tenant.to_owned(),
+            "connections".to_string(),
+            tenant_info.max_connections,
+        ));
+    }
+    Ok(())
+}
+
+// The subscribe packet is expected to be already converted into the tenant namespace.
+pub fn check_tenant_subscription_quota(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    tenant: &str,
+    client_id: &str,
+    subscribe: &Subscribe,
+) -> Result<(), MqttBrokerError> {
+    if tenant.is_empty() {
+        return Ok(());
+    }
+
+    let tenant_info = get_tenant_or_err(cache_manager, tenant)?;
+    if tenant_info.max_subscriptions == 0 {
+        return Ok(());
+    }
+
+    let new_subs = subscribe
+        .filters
+        .iter()
+        .filter(|filter| {
+            subscribe_manager
+                .get_subscribe(client_id, &filter.path)
+                .is_none()
+        })
+        .count() as u64;
+
+    if subscribe_manager.tenant_subscribe_count(tenant) + new_subs > tenant_info.max_subscriptions {
+        return Err(MqttBrokerError::TenantQuotaExceeded(
+            tenant.to_owned(),
+            "subscriptions".to_string(),
+            tenant_info.max_subscriptions,
+        ));
+    }
+    Ok(())
+}
+
+// The share of a cluster wide per-second quota one broker may use, so the sum over all brokers stays within the quota.
+fn broker_quota_share(cache_manager: &Arc<CacheManager>, quota: u64) -> u64 {
+    let broker_num = cache_manager.node_lists.len().max(1) as u64;
+    (quota / broker_num).max(1)
+}
+
+// Message rate and storage write quotas are counted per second, each broker enforces its share of them.
+pub fn check_tenant_publish_quota(
+    cache_manager: &Arc<CacheManager>,
+    tenant: &str,
+    payload_size: usize,
+) -> Result<(), MqttBrokerError> {
+    if tenant.is_empty() {
+        return Ok(());
+    }
+
+    let tenant_info = get_tenant_or_err(cache_manager, tenant)?;
+    let now = now_second();
+    let mut window = cache_manager
+        .tenant_publish_window
+        .entry(tenant.to_owned())
+        .or_default();
+
+    if window.second != now {
+        window.second = now;
+        window.messages = 0;
+        window.bytes = 0;
+    }
+
+    if tenant_info.max_messages_per_second > 0
+        && window.messages >= broker_quota_share(cache_manager, tenant_info.max_messages_per_second)
+    {
+        return Err(MqttBrokerError::TenantQuotaExceeded(
+            tenant.to_owned(),
+            "messages per second".to_string(),
+            tenant_info.max_messages_per_second,
+        ));
+    }
+
+    if tenant_info.max_storage_bytes_per_second > 0
+        && window.bytes + payload_size as u64
+            > broker_quota_share(cache_manager, tenant_info.max_storage_bytes_per_second)
+    {
+        return Err(MqttBrokerError::TenantQuotaExceeded(
+            tenant.to_owned(),
+            "storage bytes per second".to_string(),
+            tenant_info.max_storage_bytes_per_second,
+        ));
+    }
+
+    window.messages += 1;
+    window.bytes += payload_size as u64;
+    Ok(())
+}
+
+async fn sync_tenant_usage(
+    cache_manager: &Arc<CacheManager>,
+    usage_storage: &TenantUsageStorage,
+    last_connections: &mut Option<HashMap<String, u64>>,
+) {
+    let conf = broker_mqtt_conf();
+    let connections = cache_manager.local_tenant_connections();
+    if last_connections.as_ref() != Some(&connections) {
+        let usage = MqttTenantUsage {
+            broker_id: conf.broker_id,
+            connections: connections.clone(),
+            update_time: now_second(),
+        };
+        match usage_storage.save(&usage).await {
+            Ok(()) => *last_connections = Some(connections),
+            Err(e) => error!(
+                "Failed to save the tenant usage of this broker, error message: {}",
+                e
+            ),
+        }
+    }
+
+    match usage_storage.list().await {
+        Ok(usages) => {
+            let mut remote: HashMap<String, u64> = HashMap::new();
+            // Brokers that left the cluster keep their key until they come back
+            for usage in usages.iter().filter(|usage| {
+                usage.broker_id != conf.broker_id
+                    && cache_manager.node_lists.contains_key(&usage.broker_id)
+            }) {
+                for (tenant, num) in usage.connections.iter() {
+                    *remote.entry(tenant.clone()).or_default() += num;
+                }
+            }
+            cache_manager.set_tenant_remote_connections(remote);
+        }
+        Err(e) => error!("Failed to load the tenant usage, error message: {}", e),
+    }
+}
+
+pub async fn start_tenant_usage_sync_thread(
+    cache_manager: Arc<CacheManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let usage_storage = TenantUsageStorage::new(cache_manager.client_pool.clone());
+    let mut last_connections = None;
+    let mut stop_rx = stop_send.subscribe();
+    let mut interval = tokio::time::interval(Duration::from_millis(TENANT_USAGE_SYNC_INTERVAL_MS));
+    loop {
+        tokio::select! {
+            val = stop_rx.recv() => {
+                if let Ok(flag) = val {
+                    if flag {
+                        info!("{}","Tenant usage sync thread stopped successfully.");
+                        break;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                if cache_manager.tenant_info.is_empty() && last_connections.is_none() {
+                    continue;
+                }
+                sync_tenant_usage(&cache_manager, &usage_storage, &mut last_connections).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+    use metadata_struct::mqtt::tenant::MqttTenant;
+    use protocol::mqtt::common::{Filter, QoS, RetainHandling, Subscribe};
+
+    use super::*;
+
+    fn build_filter(path: &str) -> Filter {
+        Filter {
+            path: path.to_string(),
+            qos: QoS::AtLeastOnce,
+            nolocal: false,
+            preserve_retain: false,
+            retain_handling: RetainHandling::OnEverySubscribe,
+        }
+    }
+
+    #[test]
+    fn tenant_topic_name_test() {
+        assert_eq!(tenant_topic_name("", "/a/b").unwrap(), "/a/b");
+        assert_eq!(
+            tenant_topic_name("acme", "/a/b").unwrap(),
+            "/_tenant/acme/a/b"
+        );
+        assert_eq!(
+            tenant_topic_name("acme", "a/b").unwrap(),
+            "/_tenant/acme/a/b"
+        );
+        assert!(tenant_topic_name("", "/_tenant/acme/a").is_err());
+        assert!(tenant_topic_name("other", "/_tenant/acme/a").is_err());
+
+        assert_eq!(strip_tenant_namespace("/_tenant/acme/a/b"), "a/b");
+        assert_eq!(strip_tenant_namespace("/a/b"), "/a/b");
+
+        assert_eq!(topic_tenant("/_tenant/acme/a/b"), Some("acme"));
+        assert_eq!(topic_tenant("/a/b"), None);
+    }
+
+    #[test]
+    fn tenant_sub_path_test() {
+        assert_eq!(tenant_sub_path("", "/a/+").unwrap(), "/a/+");
+        assert_eq!(
+            tenant_sub_path("acme", "/a/+").unwrap(),
+            "/_tenant/acme/a/+"
+        );
+        assert_eq!(tenant_sub_path("acme", "a/+").unwrap(), "/_tenant/acme/a/+");
+
+        let share = tenant_sub_path("acme", "$share/g1/a/b").unwrap();
+        assert_eq!(share, "$share/g1/_tenant/acme/a/b");
+        assert_eq!(decode_sub_path(&share), "/_tenant/acme/a/b");
+
+        let queue = tenant_sub_path("acme", "$queue/a/b").unwrap();
+        assert_eq!(decode_sub_path(&queue), "/_tenant/acme/a/b");
+
+        let exclusive = tenant_sub_path("acme", "$exclusive/a/b").unwrap();
+        assert_eq!(decode_sub_path(&exclusive), "/_tenant/acme/a/b");
+
+        assert!(tenant_sub_path("", "$share/g1/_tenant/acme/a").is_err());
+
+        assert!(is_same_tenant("/_tenant/acme/a/+", "/_tenant/acme/a/b"));
+        assert!(!is_same_tenant("/a/+", "/_tenant/acme/a/b"));
+        assert!(!is_same_tenant(
+            "$share/g1/_tenant/other/a/b",
+            "/_tenant/acme/a/b"
+        ));
+    }
+
+    #[test]
+    fn tenant_name_validator_test() {
+        assert!(tenant_name_validator("acme-01_prod").is_ok());
+        assert!(tenant_name_validator("").is_err());
+        assert!(tenant_name_validator("a/b").is_err());
+        assert!(tenant_name_validator("a$b").is_err());
+    }
+
+    #[tokio::test]
+    async fn tenant_quota_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+
+        assert!(check_tenant_publish_quota(&cache_manager, "acme", 10).is_err());
+
+        cache_manager.add_tenant(MqttTenant {
+            tenant_name: "acme".to_string(),
+            max_subscriptions: 1,
+            max_messages_per_second: 2,
+            max_storage_bytes_per_second: 100,
+            ..Default::default()
+        });
+
+        assert!(check_tenant_connection_quota(&cache_manager, "acme").is_ok());
+        assert!(check_tenant_publish_quota(&cache_manager, "acme", 10).is_ok());
+        assert!(check_tenant_publish_quota(&cache_manager, "acme", 200).is_err());
+
+        let subscribe = tenant_subscribe(
+            "acme",
+            &Subscribe {
+                packet_identifier: 1,
+                filters: vec![build_filter("/a/1"), build_filter("/a/2")],
+            },
+        )
+        .unwrap();
+        assert!(check_tenant_subscription_quota(
+            &cache_manager,
+            &subscribe_manager,
+            "acme",
+            "c1",
+            &subscribe
+        )
+        .is_err());
+        assert!(check_tenant_subscription_quota(
+            &cache_manager,
+            &subscribe_manager,
+            "",
+            "c1",
+            &subscribe
+        )
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn tenant_usage_count_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+
+        cache_manager.incr_tenant_connection("acme");
+        cache_manager.incr_tenant_connection("acme");
+        cache_manager.incr_tenant_connection("");
+        cache_manager.set_tenant_remote_connections(HashMap::from([("acme".to_string(), 3)]));
+        assert_eq!(cache_manager.tenant_connection_count("acme"), 5);
+        cache_manager.decr_tenant_connection("acme");
+        cache_manager.decr_tenant_connection("acme");
+        cache_manager.set_tenant_remote_connections(HashMap::new());
+        assert_eq!(cache_manager.tenant_connection_count("acme"), 0);
+        assert!(cache_manager.local_tenant_connections().is_empty());
+
+        for path in ["/_tenant/acme/a", "$share/g1/_tenant/acme/b", "/a"] {
+            subscribe_manager.add_subscribe(MqttSubscribe {
+                client_id: "c1".to_string(),
+                path: path.to_string(),
+                ..Default::default()
+            });
+        }
+        subscribe_manager.add_subscribe(MqttSubscribe {
+            client_id: "c1".to_string(),
+            path: "/_tenant/acme/a".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(subscribe_manager.tenant_subscribe_count("acme"), 2);
+        subscribe_manager.remove_subscribe("c1", "/_tenant/acme/a");
+        subscribe_manager.remove_subscribe("c1", "/_tenant/acme/a");
+        assert_eq!(subscribe_manager.tenant_subscribe_count("acme"), 1);
+        subscribe_manager.remove_subscriber_by_client_id("c1");
+        assert_eq!(subscribe_manager.tenant_subscribe_count("acme"), 0);
+    }
+}
@@ -97,6 +97,10 @@ pub async fn get_topic_name(
     let topic_name = if topic.is_empty() {
         get_topic_alias(cache_manager, connect_id, topic_alias).await?
     } else {
+        topic_name_validator(&topic)?;
+        // A topic name sent together with an alias maps the alias to it for the connection.
+        // The name is stored as sent, before any rewrite, so later uses resolve the same way.
+        cache_manager.add_topic_alias(connect_id, &topic, publish_properties);
         topic
     };
 
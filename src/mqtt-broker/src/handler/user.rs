@@ -76,6 +76,7 @@ pub async fn init_system_user(cache_manager: &Arc<CacheManager>, client_pool: &A
         username: conf.system.default_user.clone(),
         password: conf.system.default_password.clone(),
         is_superuser: true,
+        tenant: String::new(),
     };
     let user_storage = UserStorage::new(client_pool.clone());
     match user_storage.save_user(system_user_info.clone()).await {
@@ -16,9 +16,9 @@
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::connection::MQTTConnection;
 use protocol::mqtt::common::{
-    Connect, ConnectProperties, ConnectReturnCode, LastWill, LastWillProperties, Login, MqttPacket,
-    MqttProtocol, PubAckReason, PubRecReason, Publish, PublishProperties, QoS, Subscribe,
-    SubscribeReasonCode, UnsubAckReason, Unsubscribe,
+    Connect, ConnectProperties, ConnectReturnCode, DisconnectReasonCode, LastWill,
+    LastWillProperties, Login, MqttPacket, MqttProtocol, PubAckReason, PubRecReason, Publish,
+    PublishProperties, QoS, Subscribe, SubscribeReasonCode, UnsubAckReason, Unsubscribe,
 };
 use std::cmp::min;
 use std::sync::Arc;
@@ -30,7 +30,8 @@
 use super::error::MqttBrokerError;
 use super::flow_control::{is_qos_message, is_subscribe_rate_exceeded};
 use super::response::{
-    response_packet_mqtt_connect_fail, response_packet_mqtt_suback, response_packet_mqtt_unsuback,
+    response_packet_mqtt_connect_fail, response_packet_mqtt_distinct, response_packet_mqtt_suback,
+    response_packet_mqtt_unsuback,
 };
 use super::sub_exclusive::{allow_exclusive_subscribe, already_exclusive_subscribe};
 use super::topic::topic_name_validator;
@@ -310,36 +311,34 @@ pub async fn publish_validator(
         }
     }
 
-    if let Some(properties) = publish_properties {
-        if let Some(alias) = properties.topic_alias {
-            let cluster = cache_manager.get_cluster_config();
-            if alias > cluster.mqtt_protocol_config.topic_alias_max {
-                if is_puback {
-                    return Some(build_puback(
-                        protocol,
-                        connection,
-                        publish.pkid,
-                        PubAckReason::UnspecifiedError,
-                        Some(MqttBrokerError::TopicAliasTooLong(alias).to_string()),
-                        Vec::new(),
-                    ));
-                } else {
-                    return Some(build_pubrec(
-                        protocol,
-                        connection,
-                        publish.pkid,
-                        PubRecReason::UnspecifiedError,
-                        Some(MqttBrokerError::TopicAliasTooLong(alias).to_string()),
-                        Vec::new(),
-                    ));
-                }
-            }
-        }
-    }
-
     None
 }
 
+// A topic alias of 0 or above the Topic Alias Maximum the broker sent in CONNACK is a protocol
+// violation that closes the connection, whatever the QoS of the message
+pub fn topic_alias_validator(
+    protocol: &MqttProtocol,
+    cache_manager: &Arc<CacheManager>,
+    connection: &MQTTConnection,
+    publish_properties: &Option<PublishProperties>,
+) -> Option<MqttPacket> {
+    let alias = publish_properties.as_ref()?.topic_alias?;
+    let cluster = cache_manager.get_cluster_config();
+    let reason = if alias == 0 {
+        MqttBrokerError::TopicAliasInvalid(Some(alias))
+    } else if alias > cluster.mqtt_protocol_config.topic_alias_max {
+        MqttBrokerError::TopicAliasTooLong(alias)
+    } else {
+        return None;
+    };
+    Some(response_packet_mqtt_distinct(
+        protocol,
+        Some(DisconnectReasonCode::TopicAliasInvalid),
+        connection,
+        Some(reason.to_string()),
+    ))
+}
+
 pub async fn subscribe_validator(
     protocol: &MqttProtocol,
     auth_driver: &Arc<AuthDriver>,
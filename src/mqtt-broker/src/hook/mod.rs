@@ -0,0 +1,292 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use common_base::tools::now_mills;
+use dashmap::DashMap;
+use protocol::mqtt::common::QoS;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::handler::cache::CacheManager;
+use crate::handler::tenant::strip_tenant_namespace;
+
+pub mod webhook;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEventType {
+    ClientConnected,
+    ClientDisconnected,
+    ClientSubscribed,
+    ClientUnsubscribed,
+    SessionCreated,
+    SessionTerminated,
+    MessageDelivered,
+    MessageAcked,
+    MessageDropped,
+}
+
+impl HookEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEventType::ClientConnected => "client_connected",
+            HookEventType::ClientDisconnected => "client_disconnected",
+            HookEventType::ClientSubscribed => "client_subscribed",
+            HookEventType::ClientUnsubscribed => "client_unsubscribed",
+            HookEventType::SessionCreated => "session_created",
+            HookEventType::SessionTerminated => "session_terminated",
+            HookEventType::MessageDelivered => "message_delivered",
+            HookEventType::MessageAcked => "message_acked",
+            HookEventType::MessageDropped => "message_dropped",
+        }
+    }
+}
+
+impl fmt::Display for HookEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HookEventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "client_connected" => Ok(HookEventType::ClientConnected),
+            "client_disconnected" => Ok(HookEventType::ClientDisconnected),
+            "client_subscribed" => Ok(HookEventType::ClientSubscribed),
+            "client_unsubscribed" => Ok(HookEventType::ClientUnsubscribed),
+            "session_created" => Ok(HookEventType::SessionCreated),
+            "session_terminated" => Ok(HookEventType::SessionTerminated),
+            "message_delivered" => Ok(HookEventType::MessageDelivered),
+            "message_acked" => Ok(HookEventType::MessageAcked),
+            "message_dropped" => Ok(HookEventType::MessageDropped),
+            _ => Err(format!("unknown hook event type {}", s)),
+        }
+    }
+}
+
+// One client lifecycle or message event. Fields that do not apply to the event are left empty.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookEvent {
+    pub event: HookEventType,
+    pub node_id: u64,
+    pub client_id: String,
+    pub username: String,
+    // The topic of message events, the topic filter of subscription events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qos: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub timestamp: u64,
+}
+
+impl HookEvent {
+    pub fn new(event: HookEventType, client_id: &str, username: &str) -> Self {
+        HookEvent {
+            event,
+            node_id: 0,
+            client_id: client_id.to_owned(),
+            username: username.to_owned(),
+            topic: None,
+            qos: None,
+            reason: None,
+            timestamp: now_mills() as u64,
+        }
+    }
+
+    pub fn with_topic(mut self, topic: &str, qos: Option<QoS>) -> Self {
+        self.topic = Some(strip_tenant_namespace(topic));
+        self.qos = qos.map(|qos| qos as u8);
+        self
+    }
+
+    pub fn with_reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_owned());
+        self
+    }
+}
+
+/// Receives the events fired by the broker.
+///
+/// `on_event` is called on the connection and push paths, implementations must not block and
+/// should hand the event over to a task of their own, as the webhook does.
+pub trait EventHook: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn on_event(&self, event: &HookEvent);
+}
+
+#[derive(Default)]
+pub struct HookManager {
+    node_id: AtomicU64,
+    // (hook_name, hook)
+    hooks: DashMap<String, Arc<dyn EventHook>>,
+}
+
+impl HookManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_node_id(&self, node_id: u64) {
+        self.node_id.store(node_id, Ordering::Relaxed);
+    }
+
+    pub fn register(&self, hook: Arc<dyn EventHook>) {
+        info!("Event hook {} registered", hook.name());
+        self.hooks.insert(hook.name().to_owned(), hook);
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.hooks.remove(name);
+    }
+
+    /// Callers check this before building an event, so the hot paths cost nothing when no
+    /// hook is registered.
+    pub fn is_enabled(&self) -> bool {
+        !self.hooks.is_empty()
+    }
+
+    pub fn fire(&self, mut event: HookEvent) {
+        event.node_id = self.node_id.load(Ordering::Relaxed);
+        for hook in self.hooks.iter() {
+            hook.on_event(&event);
+        }
+    }
+}
+
+pub fn fire_client_event(
+    cache_manager: &Arc<CacheManager>,
+    event: HookEventType,
+    client_id: &str,
+    username: &str,
+) {
+    if !cache_manager.hook_manager.is_enabled() {
+        return;
+    }
+    cache_manager
+        .hook_manager
+        .fire(HookEvent::new(event, client_id, username));
+}
+
+pub fn fire_topic_event(
+    cache_manager: &Arc<CacheManager>,
+    event: HookEventType,
+    client_id: &str,
+    username: &str,
+    topic: &str,
+    qos: Option<QoS>,
+) {
+    if !cache_manager.hook_manager.is_enabled() {
+        return;
+    }
+    cache_manager
+        .hook_manager
+        .fire(HookEvent::new(event, client_id, username).with_topic(topic, qos));
+}
+
+// Message events happen on the push path, where only the client id is at hand
+pub fn fire_message_event(
+    cache_manager: &Arc<CacheManager>,
+    event: HookEventType,
+    client_id: &str,
+    topic: &str,
+    qos: QoS,
+    reason: Option<&str>,
+) {
+    if !cache_manager.hook_manager.is_enabled() {
+        return;
+    }
+    let username = cache_manager
+        .get_connect_id(client_id)
+        .and_then(|connect_id| cache_manager.get_connection(connect_id))
+        .map(|connection| connection.login_user)
+        .unwrap_or_default();
+
+    let mut hook_event = HookEvent::new(event, client_id, &username).with_topic(topic, Some(qos));
+    if let Some(reason) = reason {
+        hook_event = hook_event.with_reason(reason);
+    }
+    cache_manager.hook_manager.fire(hook_event);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    use protocol::mqtt::common::QoS;
+
+    use super::{EventHook, HookEvent, HookEventType, HookManager};
+
+    struct CollectHook {
+        events: Mutex<Vec<HookEvent>>,
+    }
+
+    impl EventHook for CollectHook {
+        fn name(&self) -> &str {
+            "collect"
+        }
+
+        fn on_event(&self, event: &HookEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn hook_event_type_test() {
+        let event = HookEventType::from_str("message_dropped").unwrap();
+        assert_eq!(event, HookEventType::MessageDropped);
+        assert_eq!(event.as_str(), "message_dropped");
+        assert!(HookEventType::from_str("message_lost").is_err());
+
+        let data = serde_json::to_string(&HookEventType::SessionCreated).unwrap();
+        assert_eq!(data, "\"session_created\"");
+    }
+
+    #[test]
+    fn hook_manager_fire_test() {
+        let manager = HookManager::new();
+        assert!(!manager.is_enabled());
+
+        let hook = Arc::new(CollectHook {
+            events: Mutex::new(Vec::new()),
+        });
+        manager.register(hook.clone());
+        manager.set_node_id(3);
+        assert!(manager.is_enabled());
+
+        manager.fire(
+            HookEvent::new(HookEventType::ClientSubscribed, "c1", "u1")
+                .with_topic("/t/+", Some(QoS::AtLeastOnce)),
+        );
+        let events = hook.events.lock().unwrap().clone();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].node_id, 3);
+        assert_eq!(events[0].topic, Some("/t/+".to_string()));
+        assert_eq!(events[0].qos, Some(1));
+
+        manager.remove("collect");
+        assert!(!manager.is_enabled());
+    }
+}
@@ -0,0 +1,645 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashSet, VecDeque};
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_config::mqtt::broker_mqtt_conf;
+use common_config::mqtt::config::WebhookEndpoint;
+use reqwest::Client;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, AsyncWriteExt, BufReader};
+use tokio::select;
+use tokio::sync::{broadcast, mpsc, Mutex, Notify};
+use tokio::time::{interval, sleep};
+use tracing::{error, info, warn};
+
+use super::{EventHook, HookEvent, HookEventType};
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::subscribe::common::TopicFilterMatcher;
+
+// Events waiting to be batched, events fired while the queue is full are dropped
+const WEBHOOK_QUEUE_SIZE: usize = 10000;
+const MAX_RETRY_BACKOFF_MS: u64 = 30000;
+// The spool is split into this many segments, the oldest one is removed once it was posted
+const SPOOL_SEGMENT_NUM: u64 = 8;
+
+pub fn start_webhook(cache_manager: &Arc<CacheManager>, stop_send: &broadcast::Sender<bool>) {
+    let conf = broker_mqtt_conf();
+    if !conf.webhook.enable {
+        return;
+    }
+
+    cache_manager.hook_manager.set_node_id(conf.broker_id);
+    for endpoint in conf.webhook.endpoints.iter() {
+        match WebhookHook::new(endpoint) {
+            Ok((hook, sender)) => {
+                cache_manager.hook_manager.register(Arc::new(hook));
+                tokio::spawn(sender.start(stop_send.clone()));
+            }
+            Err(e) => {
+                error!(
+                    "Failed to start webhook {}, error message: {}",
+                    endpoint.name, e
+                );
+            }
+        }
+    }
+}
+
+/// Filters the events of one endpoint and queues them for the [`WebhookSender`].
+pub struct WebhookHook {
+    name: String,
+    // Empty means all event types
+    events: HashSet<HookEventType>,
+    // Empty means all topics
+    topics: Vec<TopicFilterMatcher>,
+    queue: mpsc::Sender<HookEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl WebhookHook {
+    pub fn new(config: &WebhookEndpoint) -> Result<(Self, WebhookSender), MqttBrokerError> {
+        if config.name.is_empty() || config.url.is_empty() {
+            return Err(MqttBrokerError::CommonError(
+                "webhook name and url must not be empty".to_string(),
+            ));
+        }
+
+        let mut events = HashSet::new();
+        for event in config.events.iter() {
+            events.insert(HookEventType::from_str(event).map_err(MqttBrokerError::CommonError)?);
+        }
+
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+        let (queue, receiver) = mpsc::channel(WEBHOOK_QUEUE_SIZE);
+        let dropped = Arc::new(AtomicU64::new(0));
+
+        let hook = WebhookHook {
+            name: config.name.clone(),
+            events,
+            topics: config
+                .topics
+                .iter()
+                .map(|filter| TopicFilterMatcher::new(filter))
+                .collect(),
+            queue,
+            dropped: dropped.clone(),
+        };
+        let sender = WebhookSender {
+            spool: Arc::new(Mutex::new(WebhookSpool::new(
+                &config.spool_dir,
+                &config.name,
+                config.spool_max_bytes,
+            ))),
+            retry_notify: Arc::new(Notify::new()),
+            config: config.clone(),
+            client,
+            receiver,
+            dropped,
+        };
+        Ok((hook, sender))
+    }
+
+    // Events without a topic pass the topic filters
+    pub fn is_match(&self, event: &HookEvent) -> bool {
+        if !self.events.is_empty() && !self.events.contains(&event.event) {
+            return false;
+        }
+
+        match &event.topic {
+            Some(topic) if !self.topics.is_empty() => {
+                self.topics.iter().any(|filter| filter.is_match(topic))
+            }
+            _ => true,
+        }
+    }
+}
+
+impl EventHook for WebhookHook {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_event(&self, event: &HookEvent) {
+        if !self.is_match(event) {
+            return;
+        }
+        if self.queue.try_send(event.clone()).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Posts the queued events of one endpoint in batches. A batch that fails is spooled to disk
+/// and handed to the [`WebhookRetry`] task, newer batches queue up behind it in the spool to
+/// keep the order until the retry task has drained it.
+pub struct WebhookSender {
+    config: WebhookEndpoint,
+    client: Client,
+    receiver: mpsc::Receiver<HookEvent>,
+    spool: Arc<Mutex<WebhookSpool>>,
+    retry_notify: Arc<Notify>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl WebhookSender {
+    pub async fn start(mut self, stop_send: broadcast::Sender<bool>) {
+        if let Err(e) = self.spool.lock().await.open().await {
+            error!(
+                "Webhook {} failed to open its spool, error message: {}",
+                self.config.name, e
+            );
+        }
+        let retry = WebhookRetry {
+            config: self.config.clone(),
+            client: self.client.clone(),
+            spool: self.spool.clone(),
+            notify: self.retry_notify.clone(),
+        };
+        tokio::spawn(retry.start(stop_send.clone()));
+
+        let mut stop_recv = stop_send.subscribe();
+        let mut batch_interval =
+            interval(Duration::from_millis(self.config.batch_interval_ms.max(1)));
+        let mut batch: Vec<HookEvent> = Vec::with_capacity(self.config.batch_size);
+        info!(
+            "Webhook {} started, url: {}",
+            self.config.name, self.config.url
+        );
+
+        loop {
+            select! {
+                val = stop_recv.recv() => {
+                    if let Ok(flag) = val {
+                        if flag {
+                            self.flush(&mut batch).await;
+                            info!("Webhook {} exited successfully", self.config.name);
+                            break;
+                        }
+                    }
+                }
+                val = self.receiver.recv() => {
+                    let Some(event) = val else {
+                        break;
+                    };
+                    batch.push(event);
+                    if batch.len() >= self.config.batch_size {
+                        self.flush(&mut batch).await;
+                    }
+                }
+                _ = batch_interval.tick() => {
+                    self.flush(&mut batch).await;
+                }
+            }
+        }
+    }
+
+    async fn flush(&self, batch: &mut Vec<HookEvent>) {
+        let dropped = self.dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!(
+                "Webhook {} dropped {} events because its queue was full",
+                self.config.name, dropped
+            );
+        }
+
+        let events = std::mem::take(batch);
+        if events.is_empty() {
+            return;
+        }
+
+        // Newer events queue up behind the spooled ones to keep the order
+        if !self.spool.lock().await.is_empty() {
+            self.spool_events(&events).await;
+            return;
+        }
+
+        if let Err(e) = post_events(&self.client, &self.config, &events).await {
+            warn!(
+                "Webhook {} failed to post {} events, spooling them, error message: {}",
+                self.config.name,
+                events.len(),
+                e
+            );
+            self.spool_events(&events).await;
+        }
+    }
+
+    async fn spool_events(&self, events: &[HookEvent]) {
+        if let Err(e) = self.spool.lock().await.append(events).await {
+            error!(
+                "Webhook {} lost {} events, error message: {}",
+                self.config.name,
+                events.len(),
+                e
+            );
+        }
+        self.retry_notify.notify_one();
+    }
+}
+
+/// Posts the spooled events of one endpoint in order. After a failure it waits
+/// `retry_backoff_ms`, doubled on every further failure, and once `max_retries` attempts
+/// failed it keeps retrying every [`MAX_RETRY_BACKOFF_MS`].
+pub struct WebhookRetry {
+    config: WebhookEndpoint,
+    client: Client,
+    spool: Arc<Mutex<WebhookSpool>>,
+    notify: Arc<Notify>,
+}
+
+impl WebhookRetry {
+    pub async fn start(self, stop_send: broadcast::Sender<bool>) {
+        let mut stop_recv = stop_send.subscribe();
+        let mut failures = 0;
+        loop {
+            let wait = match self.replay().await {
+                Ok(()) => {
+                    failures = 0;
+                    None
+                }
+                Err(e) => {
+                    warn!(
+                        "Webhook {} failed to post its spooled events, error message: {}",
+                        self.config.name, e
+                    );
+                    failures += 1;
+                    Some(retry_backoff(&self.config, failures))
+                }
+            };
+
+            select! {
+                val = stop_recv.recv() => {
+                    if let Ok(flag) = val {
+                        if flag {
+                            break;
+                        }
+                    }
+                }
+                _ = self.notify.notified(), if wait.is_none() => {}
+                _ = sleep(Duration::from_millis(wait.unwrap_or_default())), if wait.is_some() => {}
+            }
+        }
+    }
+
+    // Posts the spooled events batch by batch until the spool is empty or a post fails. The
+    // spool is only locked while reading and committing, not while posting.
+    async fn replay(&self) -> Result<(), MqttBrokerError> {
+        let mut posted = 0;
+        loop {
+            let (events, offset) = {
+                let spool = self.spool.lock().await;
+                if spool.is_empty() {
+                    break;
+                }
+                spool.read(self.config.batch_size.max(1)).await?
+            };
+
+            if !events.is_empty() {
+                post_events(&self.client, &self.config, &events).await?;
+                posted += events.len();
+            }
+            self.spool.lock().await.commit(offset).await?;
+        }
+
+        if posted > 0 {
+            info!(
+                "Webhook {} posted {} spooled events",
+                self.config.name, posted
+            );
+        }
+        Ok(())
+    }
+}
+
+fn retry_backoff(config: &WebhookEndpoint, failures: u32) -> u64 {
+    if failures > config.max_retries {
+        return MAX_RETRY_BACKOFF_MS;
+    }
+    let factor = 1u64 << (failures - 1).min(16);
+    config
+        .retry_backoff_ms
+        .saturating_mul(factor)
+        .min(MAX_RETRY_BACKOFF_MS)
+}
+
+async fn post_events(
+    client: &Client,
+    config: &WebhookEndpoint,
+    events: &[HookEvent],
+) -> Result<(), MqttBrokerError> {
+    let mut request = client.post(&config.url).json(events);
+    for (key, value) in config.headers.iter() {
+        request = request.header(key, value);
+    }
+    request.send().await?.error_for_status()?;
+    Ok(())
+}
+
+/// Events that could not be posted, stored as JSON lines in segments
+/// `{spool_dir}/{name}.{segment}.spool`. The position of the next event to post in the oldest
+/// segment is kept in `{spool_dir}/{name}.offset`, a segment is removed once it was posted.
+pub struct WebhookSpool {
+    dir: PathBuf,
+    name: String,
+    max_bytes: u64,
+    segment_max_bytes: u64,
+    // Id and size of every segment, from the oldest to the newest
+    segments: VecDeque<(u64, u64)>,
+    // Position of the next event to post in the oldest segment
+    read_offset: u64,
+}
+
+impl WebhookSpool {
+    pub fn new(spool_dir: &str, name: &str, max_bytes: u64) -> Self {
+        WebhookSpool {
+            dir: PathBuf::from(spool_dir),
+            name: name.to_string(),
+            max_bytes,
+            segment_max_bytes: (max_bytes / SPOOL_SEGMENT_NUM).max(1),
+            segments: VecDeque::new(),
+            read_offset: 0,
+        }
+    }
+
+    // Picks up the segments left by a previous run
+    pub async fn open(&mut self) -> Result<(), MqttBrokerError> {
+        self.segments.clear();
+        self.read_offset = 0;
+        if !fs::try_exists(&self.dir).await? {
+            return Ok(());
+        }
+
+        let prefix = format!("{}.", self.name);
+        let mut entries = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(id) = file_name
+                .strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_suffix(".spool"))
+                .and_then(|id| id.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            self.segments.push_back((id, entry.metadata().await?.len()));
+        }
+        self.segments.make_contiguous().sort_by_key(|(id, _)| *id);
+
+        if let Ok(data) = fs::read_to_string(self.offset_path()).await {
+            if let Some((id, offset)) = data.trim().split_once(' ') {
+                let id = id.parse::<u64>().unwrap_or_default();
+                if self.segments.front().map(|(first, _)| *first) == Some(id) {
+                    self.read_offset = offset.parse().unwrap_or_default();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    // Bytes spooled and not posted yet
+    pub fn pending_bytes(&self) -> u64 {
+        let total: u64 = self.segments.iter().map(|(_, size)| size).sum();
+        total.saturating_sub(self.read_offset)
+    }
+
+    pub async fn append(&mut self, events: &[HookEvent]) -> Result<(), MqttBrokerError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let data = encode_events(events)?;
+        if self.pending_bytes() + data.len() as u64 > self.max_bytes {
+            return Err(MqttBrokerError::CommonError(format!(
+                "webhook spool {} of {} is full, max bytes {}",
+                self.dir.display(),
+                self.name,
+                self.max_bytes
+            )));
+        }
+
+        let id = match self.segments.back() {
+            Some((id, size)) if *size < self.segment_max_bytes => *id,
+            Some((id, _)) => *id + 1,
+            None => 0,
+        };
+        fs::create_dir_all(&self.dir).await?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.segment_path(id))
+            .await?;
+        file.write_all(&data).await?;
+        file.flush().await?;
+
+        match self.segments.back_mut() {
+            Some((last, size)) if *last == id => *size += data.len() as u64,
+            _ => self.segments.push_back((id, data.len() as u64)),
+        }
+        Ok(())
+    }
+
+    /// Reads up to `max_num` events from the read offset of the oldest segment, returns them
+    /// with the offset to commit once they are posted.
+    pub async fn read(&self, max_num: usize) -> Result<(Vec<HookEvent>, u64), MqttBrokerError> {
+        let Some((id, size)) = self.segments.front() else {
+            return Ok((Vec::new(), 0));
+        };
+        let mut file = fs::File::open(self.segment_path(*id)).await?;
+        file.seek(SeekFrom::Start(self.read_offset)).await?;
+        let mut reader = BufReader::new(file);
+
+        let mut events = Vec::new();
+        let mut offset = self.read_offset;
+        let mut line = String::new();
+        while events.len() < max_num {
+            line.clear();
+            let len = reader.read_line(&mut line).await?;
+            if len == 0 {
+                // A segment cut short is skipped instead of being read forever
+                offset = offset.max(*size);
+                break;
+            }
+            offset += len as u64;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(event) => events.push(event),
+                Err(e) => error!(
+                    "Skipping an unreadable event in webhook spool {}, error message: {}",
+                    self.name, e
+                ),
+            }
+        }
+        Ok((events, offset))
+    }
+
+    // Moves the read offset of the oldest segment, removes the segment once it was read
+    pub async fn commit(&mut self, offset: u64) -> Result<(), MqttBrokerError> {
+        let Some((id, size)) = self.segments.front().copied() else {
+            return Ok(());
+        };
+        if offset < size {
+            self.read_offset = offset;
+            fs::write(self.offset_path(), format!("{} {}", id, offset)).await?;
+            return Ok(());
+        }
+
+        fs::remove_file(self.segment_path(id)).await?;
+        self.segments.pop_front();
+        self.read_offset = 0;
+        match self.segments.front() {
+            Some((next, _)) => fs::write(self.offset_path(), format!("{} 0", next)).await?,
+            None => {
+                if fs::try_exists(self.offset_path()).await? {
+                    fs::remove_file(self.offset_path()).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn segment_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{}.{}.spool", self.name, id))
+    }
+
+    fn offset_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.offset", self.name))
+    }
+}
+
+fn encode_events(events: &[HookEvent]) -> Result<Vec<u8>, MqttBrokerError> {
+    let mut data = Vec::new();
+    for event in events {
+        serde_json::to_writer(&mut data, event)?;
+        data.push(b'\n');
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use common_config::mqtt::config::WebhookEndpoint;
+    use protocol::mqtt::common::QoS;
+
+    use super::{retry_backoff, WebhookHook, WebhookSpool, MAX_RETRY_BACKOFF_MS};
+    use crate::hook::{HookEvent, HookEventType};
+
+    fn endpoint(events: Vec<&str>, topics: Vec<&str>) -> WebhookEndpoint {
+        WebhookEndpoint {
+            name: "presence".to_string(),
+            url: "http://127.0.0.1:8080/events".to_string(),
+            events: events.into_iter().map(|e| e.to_string()).collect(),
+            topics: topics.into_iter().map(|t| t.to_string()).collect(),
+            batch_size: 10,
+            batch_interval_ms: 100,
+            timeout_ms: 1000,
+            max_retries: 0,
+            retry_backoff_ms: 10,
+            spool_max_bytes: 1024,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn webhook_filter_test() {
+        let (hook, _) = WebhookHook::new(&endpoint(
+            vec!["client_connected", "message_delivered"],
+            vec!["device/+/status"],
+        ))
+        .unwrap();
+
+        let connected = HookEvent::new(HookEventType::ClientConnected, "c1", "u1");
+        assert!(hook.is_match(&connected));
+
+        let disconnected = HookEvent::new(HookEventType::ClientDisconnected, "c1", "u1");
+        assert!(!hook.is_match(&disconnected));
+
+        let delivered = HookEvent::new(HookEventType::MessageDelivered, "c1", "u1")
+            .with_topic("device/d1/status", Some(QoS::AtMostOnce));
+        assert!(hook.is_match(&delivered));
+
+        let other_topic = HookEvent::new(HookEventType::MessageDelivered, "c1", "u1")
+            .with_topic("device/d1/data", Some(QoS::AtMostOnce));
+        assert!(!hook.is_match(&other_topic));
+
+        assert!(WebhookHook::new(&endpoint(vec!["client_lost"], vec![])).is_err());
+    }
+
+    #[tokio::test]
+    async fn webhook_spool_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool_dir = dir.path().to_str().unwrap();
+        let mut spool = WebhookSpool::new(spool_dir, "presence", 1024);
+        spool.open().await.unwrap();
+        assert!(spool.is_empty());
+        assert!(spool.read(10).await.unwrap().0.is_empty());
+
+        let events: Vec<HookEvent> = (0..3)
+            .map(|i| HookEvent::new(HookEventType::ClientConnected, &format!("c{}", i), "u1"))
+            .collect();
+        spool.append(&events[..2]).await.unwrap();
+        spool.append(&events[2..]).await.unwrap();
+        // every append fills a segment of 128 bytes
+        assert_eq!(spool.segments.len(), 2);
+
+        let (read, offset) = spool.read(1).await.unwrap();
+        assert_eq!(read, events[..1].to_vec());
+        spool.commit(offset).await.unwrap();
+
+        // The read offset survives a restart
+        let mut reopened = WebhookSpool::new(spool_dir, "presence", 1024);
+        reopened.open().await.unwrap();
+        let (read, offset) = reopened.read(10).await.unwrap();
+        assert_eq!(read, events[1..2].to_vec());
+        reopened.commit(offset).await.unwrap();
+        assert_eq!(reopened.segments.len(), 1);
+
+        let (read, offset) = reopened.read(10).await.unwrap();
+        assert_eq!(read, events[2..].to_vec());
+        reopened.commit(offset).await.unwrap();
+        assert!(reopened.is_empty());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        // A spool that would grow beyond its limit refuses the events
+        let many: Vec<HookEvent> = (0..100)
+            .map(|i| HookEvent::new(HookEventType::ClientConnected, &format!("c{}", i), "u1"))
+            .collect();
+        assert!(reopened.append(&many).await.is_err());
+    }
+
+    #[test]
+    fn retry_backoff_test() {
+        let config = endpoint(vec![], vec![]);
+        let config = WebhookEndpoint {
+            max_retries: 3,
+            ..config
+        };
+        assert_eq!(retry_backoff(&config, 1), 10);
+        assert_eq!(retry_backoff(&config, 3), 40);
+        assert_eq!(retry_backoff(&config, 4), MAX_RETRY_BACKOFF_MS);
+    }
+}
@@ -16,22 +16,30 @@
 use crate::handler::cache::CacheManager;
 use crate::handler::dynamic_cache::update_cache_metadata;
 use crate::handler::error::MqttBrokerError;
+use crate::handler::inflight::clear_inflight_messages;
 use crate::handler::lastwill::send_last_will_message;
+use crate::handler::request_response::complete_forwarded_response;
+use crate::handler::takeover::kick_taken_over_connection;
+use crate::hook::{HookEvent, HookEventType};
+use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::manager::SubscribeManager;
 use common_config::mqtt::broker_mqtt_conf;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::lastwill::LastWillData;
+use metadata_struct::mqtt::route::MqttForwardRecord;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
-    UpdateMqttCacheReply, UpdateMqttCacheRequest,
+    DeleteSessionReply, DeleteSessionRequest, ForwardMessageReply, ForwardMessageRequest,
+    SendLastWillMessageReply, SendLastWillMessageRequest, SessionTakeoverReply,
+    SessionTakeoverRequest, UpdateMqttCacheReply, UpdateMqttCacheRequest,
 };
 use schema_register::schema::SchemaRegisterManager;
 use std::sync::Arc;
 use storage_adapter::storage::StorageAdapter;
-use tracing::info;
+use tracing::{info, warn};
 
 pub async fn update_cache_by_req(
     cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
     connector_manager: &Arc<ConnectorManager>,
     subscribe_manager: &Arc<SubscribeManager>,
     schema_manager: &Arc<SchemaRegisterManager>,
@@ -44,6 +52,7 @@ pub async fn update_cache_by_req(
 
     update_cache_metadata(
         cache_manager,
+        client_pool,
         connector_manager,
         subscribe_manager,
         schema_manager,
@@ -71,13 +80,81 @@ pub async fn delete_session_by_req(
     }
 
     for client_id in req.client_id.iter() {
+        if cache_manager.hook_manager.is_enabled()
+            && cache_manager.get_session_info(client_id).is_some()
+        {
+            cache_manager.hook_manager.fire(
+                HookEvent::new(HookEventType::SessionTerminated, client_id, "")
+                    .with_reason("expired"),
+            );
+        }
         subscribe_manager.remove_client_id(client_id);
         cache_manager.remove_session(client_id);
+        if let Err(e) = clear_inflight_messages(cache_manager, client_id).await {
+            warn!(
+                "Failed to delete the inflight messages of expired session {}, error: {}",
+                client_id, e
+            );
+        }
     }
 
     Ok(DeleteSessionReply::default())
 }
 
+pub async fn session_takeover_by_req(
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    req: &SessionTakeoverRequest,
+) -> Result<SessionTakeoverReply, MqttBrokerError> {
+    info!(
+        "Session {} was taken over by broker {}, connection {} is closed",
+        req.client_id, req.broker_id, req.connection_id
+    );
+    if cache_manager.cluster_name != req.cluster_name {
+        return Err(MqttBrokerError::ClusterNotMatch(req.cluster_name.clone()));
+    }
+
+    if req.client_id.is_empty() {
+        return Err(MqttBrokerError::ClientIDIsEmpty);
+    }
+
+    kick_taken_over_connection(
+        cache_manager,
+        connection_manager,
+        subscribe_manager,
+        &req.client_id,
+        req.connection_id,
+        req.broker_id,
+    )
+    .await;
+    Ok(SessionTakeoverReply::default())
+}
+
+pub fn forward_message_by_req(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    req: &ForwardMessageRequest,
+) -> Result<ForwardMessageReply, MqttBrokerError> {
+    if cache_manager.cluster_name != req.cluster_name {
+        return Err(MqttBrokerError::ClusterNotMatch(req.cluster_name.clone()));
+    }
+
+    for raw in req.records.iter() {
+        let message = MqttForwardRecord::decode(raw)?;
+        if complete_forwarded_response(cache_manager, &message) {
+            continue;
+        }
+        let Some(topic) = cache_manager.get_topic_by_name(&message.topic_name) else {
+            continue;
+        };
+        subscribe_manager
+            .message_route
+            .dispatch_local(&topic.topic_id, &message.record);
+    }
+    Ok(ForwardMessageReply::default())
+}
+
 pub async fn send_last_will_message_by_req<S>(
     cache_manager: &Arc<CacheManager>,
     client_pool: &Arc<ClientPool>,
@@ -18,8 +18,10 @@
 use crate::server::server::Server;
 use bridge::core::start_connector_thread;
 use bridge::manager::ConnectorManager;
+use bridge::source::SourcePublisher;
 use common_base::metrics::register_prometheus_export;
 use common_base::runtime::create_runtime;
+use common_base::telemetry::trace::{init_tracer_provider, stop_tracer_provider};
 use common_base::tools::now_second;
 use common_config::mqtt::broker_mqtt_conf;
 use delay_message::{start_delay_message_manager, DelayMessageManager};
@@ -28,9 +30,14 @@
 use handler::cache::CacheManager;
 use handler::dynamic_cache::load_metadata_cache;
 use handler::heartbreat::{register_node, report_heartbeat};
+use handler::inflight::start_inflight_persist_thread;
 use handler::keep_alive::ClientKeepAlive;
+use handler::offline_queue::start_offline_queue_sync_thread;
+use handler::retain::start_retain_message_sync_thread;
 use handler::sub_parse_topic::start_parse_subscribe_by_new_topic_thread;
+use handler::tenant::start_tenant_usage_sync_thread;
 use handler::user::{init_system_user, UpdateUserCache};
+use hook::webhook::start_webhook;
 use lazy_static::lazy_static;
 use observability::start_opservability;
 use pprof_monitor::pprof_monitor::start_pprof_monitor;
@@ -43,6 +50,7 @@
 use std::sync::Arc;
 use std::time::Duration;
 use storage::cluster::ClusterStorage;
+use storage::retain::AdapterRetainMessageStorage;
 use storage_adapter::memory::MemoryStorageAdapter;
 use tracing::{error, info};
 // use storage_adapter::mysql::MySQLStorageAdapter;
@@ -53,6 +61,7 @@
 use storage_adapter::StorageType;
 use subscribe::exclusive::ExclusivePush;
 use subscribe::manager::SubscribeManager;
+use subscribe::route::start_cluster_route_sync_thread;
 use subscribe::share::follower::ShareFollowerResub;
 use subscribe::share::leader::ShareLeaderPush;
 use tokio::runtime::Runtime;
@@ -68,8 +77,10 @@
 pub mod bridge;
 pub mod common;
 pub mod handler;
+pub mod hook;
 pub mod inner;
 pub mod observability;
+pub mod rule;
 pub mod security;
 pub mod server;
 pub mod storage;
@@ -162,6 +173,12 @@ pub fn new(
             create_runtime("subscribe-runtime", conf.system.runtime_worker_threads);
         let grpc_runtime = create_runtime("grpc-runtime", conf.system.runtime_worker_threads);
 
+        if conf.retain_message_storage.storage_type == "message_storage" {
+            cache_manager.retain_message_manager.set_storage(Arc::new(
+                AdapterRetainMessageStorage::new(message_storage_adapter.clone()),
+            ));
+        }
+
         let subscribe_manager = Arc::new(SubscribeManager::new());
         let connector_manager = Arc::new(ConnectorManager::new());
         let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
@@ -183,6 +200,7 @@ pub fn new(
             client_pool.clone(),
             stop_sx,
             auth_driver.clone(),
+            connector_manager.clone(),
         ));
         MqttBroker {
             daemon_runtime,
@@ -212,6 +230,7 @@ pub fn start(&self, stop_send: broadcast::Sender<bool>) {
         self.start_delay_message_thread();
         self.start_update_cache_thread(stop_send.clone());
         self.start_system_topic_thread(stop_send.clone());
+        self.start_webhook_thread(stop_send.clone());
         self.metrics_cache_thread(stop_send.clone());
         self.start_prometheus();
         self.start_pprof_monitor();
@@ -254,8 +273,18 @@ fn start_prometheus(&self) {
     fn start_pprof_monitor(&self) {
         let conf = broker_mqtt_conf();
         if conf.pprof.enable {
+            let runtimes = [
+                ("daemon-runtime", &self.daemon_runtime),
+                ("connector-runtime", &self.connector_runtime),
+                ("publish-runtime", &self.publish_runtime),
+                ("subscribe-runtime", &self.subscribe_runtime),
+                ("grpc-runtime", &self.grpc_runtime),
+            ]
+            .into_iter()
+            .map(|(name, runtime)| (name.to_string(), runtime.handle().clone()))
+            .collect();
             self.daemon_runtime.spawn(async move {
-                start_pprof_monitor(conf.pprof.port, conf.pprof.frequency).await;
+                start_pprof_monitor(conf.pprof.clone(), runtimes).await;
             });
         }
     }
@@ -269,6 +298,7 @@ fn start_quic_server(&self, stop_send: broadcast::Sender<bool>) {
         let auth_driver = self.auth_driver.clone();
         let delay_message_manager = self.delay_message_manager.clone();
         let schema_manager = self.schema_manager.clone();
+        let connector_manager = self.connector_manager.clone();
         self.publish_runtime.spawn(async move {
             start_quic_server(
                 subscribe_manager,
@@ -280,6 +310,7 @@ fn start_quic_server(&self, stop_send: broadcast::Sender<bool>) {
                 stop_send,
                 auth_driver,
                 schema_manager,
+                connector_manager,
             )
             .await
         });
@@ -315,6 +346,7 @@ fn start_websocket_server(&self, stop_send: broadcast::Sender<bool>) {
             self.schema_manager.clone(),
             self.client_pool.clone(),
             self.auth_driver.clone(),
+            self.connector_manager.clone(),
             stop_send.clone(),
         );
         self.daemon_runtime
@@ -329,6 +361,7 @@ fn start_websocket_server(&self, stop_send: broadcast::Sender<bool>) {
             self.schema_manager.clone(),
             self.client_pool.clone(),
             self.auth_driver.clone(),
+            self.connector_manager.clone(),
             stop_send.clone(),
         );
 
@@ -354,8 +387,21 @@ fn start_cluster_heartbeat_report(&self, stop_send: broadcast::Sender<bool>) {
     fn start_connector_thread(&self, stop_send: broadcast::Sender<bool>) {
         let message_storage = self.message_storage_adapter.clone();
         let connector_manager = self.connector_manager.clone();
+        let source_publisher = SourcePublisher::new(
+            self.cache_manager.clone(),
+            self.client_pool.clone(),
+            self.message_storage_adapter.clone(),
+            self.delay_message_manager.clone(),
+            self.subscribe_manager.clone(),
+        );
         self.connector_runtime.spawn(async move {
-            start_connector_thread(message_storage, connector_manager, stop_send).await;
+            start_connector_thread(
+                message_storage,
+                connector_manager,
+                source_publisher,
+                stop_send,
+            )
+            .await;
         });
     }
 
@@ -457,15 +503,47 @@ fn start_update_cache_thread(&self, stop_send: broadcast::Sender<bool>) {
         self.daemon_runtime.spawn(async move {
             update_flapping_detect_cache.start_update().await;
         });
+
+        let cache_manager = self.cache_manager.clone();
+        let retain_stop_send = stop_send.clone();
+        self.daemon_runtime.spawn(async move {
+            start_retain_message_sync_thread(cache_manager, retain_stop_send).await;
+        });
+
+        let cache_manager = self.cache_manager.clone();
+        let tenant_stop_send = stop_send.clone();
+        self.daemon_runtime.spawn(async move {
+            start_tenant_usage_sync_thread(cache_manager, tenant_stop_send).await;
+        });
+
+        let cache_manager = self.cache_manager.clone();
+        let inflight_stop_send = stop_send.clone();
+        self.daemon_runtime.spawn(async move {
+            start_inflight_persist_thread(cache_manager, inflight_stop_send).await;
+        });
+
+        let cache_manager = self.cache_manager.clone();
+        let offline_queue_stop_send = stop_send.clone();
+        self.daemon_runtime.spawn(async move {
+            start_offline_queue_sync_thread(cache_manager, offline_queue_stop_send).await;
+        });
+
+        let cache_manager = self.cache_manager.clone();
+        let subscribe_manager = self.subscribe_manager.clone();
+        self.daemon_runtime.spawn(async move {
+            start_cluster_route_sync_thread(cache_manager, subscribe_manager, stop_send).await;
+        });
     }
 
     fn start_system_topic_thread(&self, stop_send: broadcast::Sender<bool>) {
         let cache_manager = self.cache_manager.clone();
+        let subscribe_manager = self.subscribe_manager.clone();
         let message_storage_adapter = self.message_storage_adapter.clone();
         let client_pool = self.client_pool.clone();
         self.daemon_runtime.spawn(async move {
             start_opservability(
                 cache_manager,
+                subscribe_manager,
                 message_storage_adapter,
                 client_pool,
                 stop_send,
@@ -474,6 +552,13 @@ fn start_system_topic_thread(&self, stop_send: broadcast::Sender<bool>) {
         });
     }
 
+    fn start_webhook_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let cache_manager = self.cache_manager.clone();
+        self.daemon_runtime.spawn(async move {
+            start_webhook(&cache_manager, &stop_send);
+        });
+    }
+
     fn metrics_cache_thread(&self, stop_send: broadcast::Sender<bool>) {
         let metrics_cache_manager = self.metrics_cache_manager.clone();
         let cache_manager = self.cache_manager.clone();
@@ -528,6 +613,18 @@ pub fn awaiting_stop(&self, stop_send: broadcast::Sender<bool>) {
 
     fn start_init(&self) {
         self.daemon_runtime.block_on(async move {
+            let telemetry = &broker_mqtt_conf().telemetry;
+            init_tracer_provider(
+                telemetry.enable,
+                &telemetry.exporter_type,
+                &telemetry.exporter_endpoint,
+            )
+            .await;
+
+            self.cache_manager
+                .topic_metrics_manager
+                .load_config(&broker_mqtt_conf().topic_metrics);
+
             init_system_user(&self.cache_manager, &self.client_pool).await;
             load_metadata_cache(
                 &self.cache_manager,
@@ -535,9 +632,14 @@ fn start_init(&self) {
                 &self.auth_driver,
                 &self.connector_manager,
                 &self.schema_manager,
+                &self.subscribe_manager,
             )
             .await;
 
+            if let Err(e) = self.cache_manager.retain_message_manager.sync(false).await {
+                error!("Failed to load retain messages, error message: {}", e);
+            }
+
             let config = broker_mqtt_conf();
             match register_node(&self.client_pool, &self.cache_manager).await {
                 Ok(()) => {
@@ -569,6 +671,7 @@ async fn stop_server(&self) -> Result<(), MqttBrokerError> {
         );
         self.connection_manager.close_all_connect().await;
         info!("All TCP, TLS, WS, and WSS network connections have been successfully closed.");
+        stop_tracer_provider().await;
         Ok(())
     }
 }
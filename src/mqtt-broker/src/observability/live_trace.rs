@@ -0,0 +1,598 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use common_base::tools::{now_mills, now_second};
+use dashmap::DashMap;
+use protocol::mqtt::common::{mqtt_packet_to_string, MqttPacket};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::subscribe::common::TopicFilterMatcher;
+
+const MAX_TRACE_SESSION_NUM: usize = 16;
+const MAX_TRACE_DURATION_SECS: u64 = 3600;
+const MAX_TRACE_RECORD_NUM: usize = 10000;
+const DEFAULT_PAYLOAD_MAX_LEN: usize = 256;
+const MAX_PACKET_DETAIL_LEN: usize = 1024;
+// How long the records of a finished trace stay queryable
+const FINISHED_TRACE_RETENTION_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TraceTarget {
+    ClientId(String),
+    Username(String),
+    Ip(String),
+    TopicFilter(String),
+}
+
+impl TraceTarget {
+    pub fn parse(target_type: &str, target: &str) -> Result<Self, MqttBrokerError> {
+        if target.is_empty() {
+            return Err(MqttBrokerError::CommonError(
+                "trace target must not be empty".to_string(),
+            ));
+        }
+        match target_type {
+            "client_id" => Ok(TraceTarget::ClientId(target.to_owned())),
+            "username" => Ok(TraceTarget::Username(target.to_owned())),
+            "ip" => Ok(TraceTarget::Ip(target.to_owned())),
+            "topic" => Ok(TraceTarget::TopicFilter(target.to_owned())),
+            _ => Err(MqttBrokerError::CommonError(format!(
+                "unknown trace target type {}, supports client_id, username, ip, topic",
+                target_type
+            ))),
+        }
+    }
+
+    pub fn target_type(&self) -> &'static str {
+        match self {
+            TraceTarget::ClientId(_) => "client_id",
+            TraceTarget::Username(_) => "username",
+            TraceTarget::Ip(_) => "ip",
+            TraceTarget::TopicFilter(_) => "topic",
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            TraceTarget::ClientId(value)
+            | TraceTarget::Username(value)
+            | TraceTarget::Ip(value)
+            | TraceTarget::TopicFilter(value) => value,
+        }
+    }
+
+    // Builds the regex of a topic filter once, when the trace starts
+    fn matcher(&self) -> TraceMatcher {
+        let topic_filter = match self {
+            TraceTarget::TopicFilter(filter) => Some(TopicFilterMatcher::new(filter)),
+            _ => None,
+        };
+        TraceMatcher {
+            target: self.clone(),
+            topic_filter,
+        }
+    }
+}
+
+struct TraceMatcher {
+    target: TraceTarget,
+    topic_filter: Option<TopicFilterMatcher>,
+}
+
+impl TraceMatcher {
+    fn is_match(&self, identity: &TraceIdentity, topics: &[String]) -> bool {
+        if let Some(topic_filter) = &self.topic_filter {
+            return topics.iter().any(|topic| topic_filter.is_match(topic));
+        }
+        match &self.target {
+            TraceTarget::ClientId(client_id) => identity.client_id == *client_id,
+            TraceTarget::Username(username) => identity.username == *username,
+            TraceTarget::Ip(ip) => {
+                identity.source_ip == *ip
+                    || SocketAddr::from_str(&identity.source_ip)
+                        .map(|addr| addr.ip().to_string() == *ip)
+                        .unwrap_or(false)
+            }
+            TraceTarget::TopicFilter(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceDirection {
+    // Received from the client
+    In,
+    // Sent to the client
+    Out,
+}
+
+impl TraceDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TraceDirection::In => "in",
+            TraceDirection::Out => "out",
+        }
+    }
+}
+
+// Who a packet belongs to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TraceIdentity {
+    pub client_id: String,
+    pub username: String,
+    pub source_ip: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    pub time: u64,
+    pub direction: TraceDirection,
+    pub client_id: String,
+    pub username: String,
+    pub source_ip: String,
+    pub packet_type: String,
+    pub topic: String,
+    // The packet without its payload
+    pub detail: String,
+    // The payload of PUBLISH packets, cut to the payload_max_len of the trace
+    pub payload: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceInfo {
+    pub name: String,
+    pub target: TraceTarget,
+    pub start_at: u64,
+    pub end_at: u64,
+    pub running: bool,
+    pub record_num: usize,
+}
+
+struct TraceSession {
+    matcher: TraceMatcher,
+    start_at: u64,
+    end_at: u64,
+    payload_max_len: usize,
+    records: Mutex<VecDeque<TraceRecord>>,
+}
+
+impl TraceSession {
+    fn is_running(&self, now: u64) -> bool {
+        now < self.end_at
+    }
+}
+
+/// Time-bounded traces of the packets exchanged with the clients that match a client id,
+/// username, IP or topic filter. Each trace keeps its latest records in memory, where they can
+/// be queried while it runs and for a while after it ended.
+#[derive(Default)]
+pub struct LiveTraceManager {
+    // (trace_name, TraceSession)
+    sessions: DashMap<String, TraceSession>,
+    // The time the last running trace ends, packets are only inspected before it
+    running_until: AtomicU64,
+}
+
+impl LiveTraceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(
+        &self,
+        name: &str,
+        target: TraceTarget,
+        duration_secs: u64,
+        payload_max_len: usize,
+    ) -> Result<(), MqttBrokerError> {
+        if name.is_empty() {
+            return Err(MqttBrokerError::CommonError(
+                "trace name must not be empty".to_string(),
+            ));
+        }
+        if duration_secs == 0 || duration_secs > MAX_TRACE_DURATION_SECS {
+            return Err(MqttBrokerError::CommonError(format!(
+                "trace duration must be between 1 and {} seconds",
+                MAX_TRACE_DURATION_SECS
+            )));
+        }
+
+        let now = now_second();
+        self.remove_finished(now);
+        if let Some(session) = self.sessions.get(name) {
+            if session.is_running(now) {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "trace {} is already running",
+                    name
+                )));
+            }
+        }
+        if !self.sessions.contains_key(name) && self.sessions.len() >= MAX_TRACE_SESSION_NUM {
+            return Err(MqttBrokerError::CommonError(format!(
+                "at most {} traces can be kept at the same time",
+                MAX_TRACE_SESSION_NUM
+            )));
+        }
+
+        info!(
+            "Live trace {} started on {} {} for {}s",
+            name,
+            target.target_type(),
+            target.value(),
+            duration_secs
+        );
+        let payload_max_len = if payload_max_len == 0 {
+            DEFAULT_PAYLOAD_MAX_LEN
+        } else {
+            payload_max_len
+        };
+        self.sessions.insert(
+            name.to_owned(),
+            TraceSession {
+                matcher: target.matcher(),
+                start_at: now,
+                end_at: now + duration_secs,
+                payload_max_len,
+                records: Mutex::new(VecDeque::new()),
+            },
+        );
+        self.update_running_until();
+        Ok(())
+    }
+
+    /// Ends a running trace before its time, its records stay queryable.
+    pub fn stop(&self, name: &str) -> Result<(), MqttBrokerError> {
+        let now = now_second();
+        match self.sessions.get_mut(name) {
+            Some(mut session) => {
+                if session.is_running(now) {
+                    session.end_at = now;
+                }
+            }
+            None => {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "trace {} does not exist",
+                    name
+                )));
+            }
+        }
+        info!("Live trace {} stopped", name);
+        self.update_running_until();
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<TraceInfo> {
+        let now = now_second();
+        self.remove_finished(now);
+        let mut list: Vec<TraceInfo> = self
+            .sessions
+            .iter()
+            .map(|entry| TraceInfo {
+                name: entry.key().clone(),
+                target: entry.matcher.target.clone(),
+                start_at: entry.start_at,
+                end_at: entry.end_at,
+                running: entry.is_running(now),
+                record_num: entry.records.lock().unwrap().len(),
+            })
+            .collect();
+        list.sort_by_key(|info| info.start_at);
+        list
+    }
+
+    /// The latest `limit` records of a trace, oldest first. A `limit` of 0 returns all of them.
+    pub fn records(&self, name: &str, limit: usize) -> Result<Vec<TraceRecord>, MqttBrokerError> {
+        let Some(session) = self.sessions.get(name) else {
+            return Err(MqttBrokerError::CommonError(format!(
+                "trace {} does not exist",
+                name
+            )));
+        };
+        let records = session.records.lock().unwrap();
+        let skip = if limit == 0 {
+            0
+        } else {
+            records.len().saturating_sub(limit)
+        };
+        Ok(records.iter().skip(skip).cloned().collect())
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        now_second() < self.running_until.load(Ordering::Relaxed)
+    }
+
+    pub fn record(&self, direction: TraceDirection, identity: &TraceIdentity, packet: &MqttPacket) {
+        let now = now_second();
+        let topics = packet_topics(packet);
+        for session in self.sessions.iter() {
+            if !session.is_running(now) || !session.matcher.is_match(identity, &topics) {
+                continue;
+            }
+
+            let record = build_trace_record(
+                direction,
+                identity,
+                packet,
+                &topics,
+                session.payload_max_len,
+            );
+            let mut records = session.records.lock().unwrap();
+            if records.len() >= MAX_TRACE_RECORD_NUM {
+                records.pop_front();
+            }
+            records.push_back(record);
+        }
+    }
+
+    fn update_running_until(&self) {
+        let now = now_second();
+        let until = self
+            .sessions
+            .iter()
+            .filter(|session| session.is_running(now))
+            .map(|session| session.end_at)
+            .max()
+            .unwrap_or(0);
+        self.running_until.store(until, Ordering::Relaxed);
+    }
+
+    fn remove_finished(&self, now: u64) {
+        self.sessions
+            .retain(|_, session| session.end_at + FINISHED_TRACE_RETENTION_SECS > now);
+    }
+}
+
+pub fn trace_packet(
+    cache_manager: &Arc<CacheManager>,
+    direction: TraceDirection,
+    connect_id: u64,
+    addr: Option<&SocketAddr>,
+    packet: &MqttPacket,
+) {
+    if !cache_manager.live_trace_manager.is_tracing() {
+        return;
+    }
+
+    let identity = if let Some(connection) = cache_manager.get_connection(connect_id) {
+        TraceIdentity {
+            client_id: connection.client_id,
+            username: connection.login_user,
+            source_ip: connection.source_ip_addr,
+        }
+    } else {
+        // The CONNECT packet arrives before the connection is known
+        let mut identity = TraceIdentity {
+            source_ip: addr.map(|addr| addr.to_string()).unwrap_or_default(),
+            ..Default::default()
+        };
+        if let MqttPacket::Connect(_, connect, _, _, _, login) = packet {
+            identity.client_id = connect.client_id.clone();
+            if let Some(login) = login {
+                identity.username = login.username.clone();
+            }
+        }
+        identity
+    };
+
+    cache_manager
+        .live_trace_manager
+        .record(direction, &identity, packet);
+}
+
+fn packet_topics(packet: &MqttPacket) -> Vec<String> {
+    match packet {
+        MqttPacket::Publish(publish, _) => {
+            vec![String::from_utf8_lossy(&publish.topic).to_string()]
+        }
+        MqttPacket::Subscribe(subscribe, _) => subscribe
+            .filters
+            .iter()
+            .map(|filter| filter.path.clone())
+            .collect(),
+        MqttPacket::Unsubscribe(unsubscribe, _) => unsubscribe.filters.clone(),
+        _ => Vec::new(),
+    }
+}
+
+fn build_trace_record(
+    direction: TraceDirection,
+    identity: &TraceIdentity,
+    packet: &MqttPacket,
+    topics: &[String],
+    payload_max_len: usize,
+) -> TraceRecord {
+    let (detail, payload) = match packet {
+        MqttPacket::Publish(publish, properties) => (
+            format!(
+                "dup: {}, qos: {:?}, pkid: {}, retain: {}, payload_len: {}, properties: {:?}",
+                publish.dup,
+                publish.qos,
+                publish.pkid,
+                publish.retain,
+                publish.payload.len(),
+                properties
+            ),
+            truncate(&String::from_utf8_lossy(&publish.payload), payload_max_len),
+        ),
+        // The password of the CONNECT packet must not end up in the trace
+        MqttPacket::Connect(protocol_version, connect, properties, last_will, _, _) => (
+            format!(
+                "protocol_version: {}, {:?}, properties: {:?}, has_last_will: {}",
+                protocol_version,
+                connect,
+                properties,
+                last_will.is_some()
+            ),
+            String::new(),
+        ),
+        packet => (format!("{:?}", packet), String::new()),
+    };
+
+    TraceRecord {
+        time: now_mills() as u64,
+        direction,
+        client_id: identity.client_id.clone(),
+        username: identity.username.clone(),
+        source_ip: identity.source_ip.clone(),
+        packet_type: mqtt_packet_to_string(packet),
+        topic: topics.join(","),
+        detail: truncate(&detail, MAX_PACKET_DETAIL_LEN),
+        payload,
+    }
+}
+
+fn truncate(data: &str, max_len: usize) -> String {
+    if data.chars().count() <= max_len {
+        return data.to_owned();
+    }
+    let mut result: String = data.chars().take(max_len).collect();
+    result.push_str("...");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use protocol::mqtt::common::{MqttPacket, PingReq, Publish, QoS};
+
+    use super::{
+        truncate, LiveTraceManager, TraceDirection, TraceIdentity, TraceTarget,
+        MAX_TRACE_DURATION_SECS,
+    };
+
+    fn identity(client_id: &str) -> TraceIdentity {
+        TraceIdentity {
+            client_id: client_id.to_string(),
+            username: "u1".to_string(),
+            source_ip: "10.0.0.8:52110".to_string(),
+        }
+    }
+
+    fn publish(topic: &str, payload: &str) -> MqttPacket {
+        MqttPacket::Publish(
+            Publish {
+                dup: false,
+                qos: QoS::AtLeastOnce,
+                pkid: 1,
+                retain: false,
+                topic: Bytes::from(topic.to_string()),
+                payload: Bytes::from(payload.to_string()),
+            },
+            None,
+        )
+    }
+
+    #[test]
+    fn trace_target_test() {
+        let target = TraceTarget::parse("ip", "10.0.0.8").unwrap();
+        assert!(target.matcher().is_match(&identity("c1"), &[]));
+        assert!(!TraceTarget::parse("ip", "10.0.0.9")
+            .unwrap()
+            .matcher()
+            .is_match(&identity("c1"), &[]));
+
+        let matcher = TraceTarget::parse("topic", "device/+/status")
+            .unwrap()
+            .matcher();
+        assert!(matcher.is_match(&identity("c1"), &["device/d1/status".to_string()]));
+        assert!(!matcher.is_match(&identity("c1"), &["device/d1/data".to_string()]));
+        assert!(!matcher.is_match(&identity("c1"), &[]));
+
+        assert!(TraceTarget::parse("port", "1883").is_err());
+        assert!(TraceTarget::parse("client_id", "").is_err());
+    }
+
+    #[test]
+    fn live_trace_record_test() {
+        let manager = LiveTraceManager::new();
+        assert!(!manager.is_tracing());
+        assert!(manager
+            .start("t1", TraceTarget::ClientId("c1".to_string()), 0, 0)
+            .is_err());
+        assert!(manager
+            .start(
+                "t1",
+                TraceTarget::ClientId("c1".to_string()),
+                MAX_TRACE_DURATION_SECS + 1,
+                0
+            )
+            .is_err());
+
+        manager
+            .start("t1", TraceTarget::ClientId("c1".to_string()), 60, 4)
+            .unwrap();
+        assert!(manager.is_tracing());
+        assert!(manager
+            .start("t1", TraceTarget::ClientId("c1".to_string()), 60, 4)
+            .is_err());
+
+        manager.record(
+            TraceDirection::In,
+            &identity("c1"),
+            &publish("a/b", "hello"),
+        );
+        manager.record(
+            TraceDirection::In,
+            &identity("c2"),
+            &publish("a/b", "hello"),
+        );
+        manager.record(
+            TraceDirection::Out,
+            &identity("c1"),
+            &MqttPacket::PingReq(PingReq),
+        );
+
+        let records = manager.records("t1", 0).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].packet_type, "Publish");
+        assert_eq!(records[0].topic, "a/b");
+        assert_eq!(records[0].payload, "hell...");
+        assert_eq!(records[1].direction, TraceDirection::Out);
+        assert_eq!(manager.records("t1", 1).unwrap(), records[1..].to_vec());
+
+        manager.stop("t1").unwrap();
+        assert!(!manager.is_tracing());
+        manager.record(
+            TraceDirection::In,
+            &identity("c1"),
+            &publish("a/b", "hello"),
+        );
+        assert_eq!(manager.records("t1", 0).unwrap().len(), 2);
+
+        let list = manager.list();
+        assert_eq!(list.len(), 1);
+        assert!(!list[0].running);
+        assert_eq!(list[0].record_num, 2);
+
+        assert!(manager.stop("t2").is_err());
+        assert!(manager.records("t2", 0).is_err());
+    }
+
+    #[test]
+    fn truncate_test() {
+        assert_eq!(truncate("abc", 3), "abc");
+        assert_eq!(truncate("abcdef", 3), "abc...");
+        assert_eq!(truncate("温度传感器", 2), "温度...");
+    }
+}
@@ -16,6 +16,7 @@
 pub mod event_metrics;
 pub mod packets;
 pub mod publish;
+pub mod rule;
 pub mod server;
 pub mod session;
 pub mod time;
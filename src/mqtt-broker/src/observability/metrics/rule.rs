@@ -0,0 +1,52 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use prometheus_client::encoding::EncodeLabelSet;
+
+pub const RULE_STATUS_MATCHED: &str = "matched";
+pub const RULE_STATUS_PASSED: &str = "passed";
+pub const RULE_STATUS_FILTERED: &str = "filtered";
+pub const RULE_STATUS_FAILED: &str = "failed";
+pub const RULE_STATUS_ACTION_SUCCESS: &str = "action_success";
+pub const RULE_STATUS_ACTION_FAILED: &str = "action_failed";
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct RuleLabels {
+    rule_name: String,
+    status: String,
+}
+
+common_base::register_counter_metric!(
+    RULE_MESSAGES_COUNTER,
+    "rule_messages",
+    "Number of messages handled by a rule, by processing status",
+    RuleLabels
+);
+
+pub fn incr_rule_counter(rule_name: &str, status: &str) {
+    let labels = RuleLabels {
+        rule_name: rule_name.to_string(),
+        status: status.to_string(),
+    };
+    common_base::counter_metric_inc!(RULE_MESSAGES_COUNTER, labels)
+}
+
+pub fn get_rule_counter(rule_name: &str, status: &str) -> u64 {
+    let labels = RuleLabels {
+        rule_name: rule_name.to_string(),
+        status: status.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(RULE_MESSAGES_COUNTER, labels, res);
+    res
+}
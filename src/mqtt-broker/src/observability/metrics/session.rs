@@ -11,3 +11,61 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct OfflineQueueLabel {
+    policy: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct InflightPersistLabel {
+    operation: String,
+}
+
+common_base::register_counter_metric!(
+    SESSION_OFFLINE_MESSAGES_DROPPED,
+    "session_offline_messages_dropped",
+    "Number of messages dropped from the queues of offline sessions, by drop policy",
+    OfflineQueueLabel
+);
+
+common_base::register_counter_metric!(
+    SESSION_OFFLINE_QUEUE_OVERFLOW,
+    "session_offline_queue_overflow",
+    "Number of times the queue of an offline session overflowed, by drop policy",
+    OfflineQueueLabel
+);
+
+common_base::register_counter_metric!(
+    SESSION_INFLIGHT_PERSIST_FAILED,
+    "session_inflight_persist_failed",
+    "Number of times the inflight window of a persistent session failed to be written, by operation",
+    InflightPersistLabel
+);
+
+pub fn record_offline_messages_dropped(policy: &str, num: u64) {
+    let labels = OfflineQueueLabel {
+        policy: policy.to_string(),
+    };
+    SESSION_OFFLINE_MESSAGES_DROPPED
+        .read()
+        .unwrap()
+        .get_or_create(&labels)
+        .inc_by(num);
+}
+
+pub fn record_offline_queue_overflow(policy: &str) {
+    let labels = OfflineQueueLabel {
+        policy: policy.to_string(),
+    };
+    common_base::counter_metric_inc!(SESSION_OFFLINE_QUEUE_OVERFLOW, labels)
+}
+
+pub fn record_inflight_persist_failed(operation: &str) {
+    let labels = InflightPersistLabel {
+        operation: operation.to_string(),
+    };
+    common_base::counter_metric_inc!(SESSION_INFLIGHT_PERSIST_FAILED, labels)
+}
@@ -20,20 +20,41 @@
 use tokio::sync::broadcast;
 
 use crate::handler::cache::CacheManager;
+use crate::subscribe::manager::SubscribeManager;
+use warn::check::start_alarm_check_thread;
 
+pub mod live_trace;
 pub mod metrics;
 pub mod slow;
 pub mod system_topic;
+pub mod topic_metrics;
+pub mod trace;
 pub mod warn;
 
 pub async fn start_opservability<S>(
     cache_manager: Arc<CacheManager>,
+    subscribe_manager: Arc<SubscribeManager>,
     message_storage_adapter: Arc<S>,
     client_pool: Arc<ClientPool>,
     stop_send: broadcast::Sender<bool>,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
+    let alarm_cache_manager = cache_manager.clone();
+    let alarm_storage_adapter = message_storage_adapter.clone();
+    let alarm_client_pool = client_pool.clone();
+    let alarm_stop_send = stop_send.clone();
+    tokio::spawn(async move {
+        start_alarm_check_thread(
+            alarm_cache_manager,
+            subscribe_manager,
+            alarm_storage_adapter,
+            alarm_client_pool,
+            alarm_stop_send,
+        )
+        .await;
+    });
+
     let system_topic = SystemTopic::new(
         cache_manager.clone(),
         message_storage_adapter.clone(),
@@ -139,12 +139,16 @@ async fn is_send_a_new_system_event<S>(
         message.activated = current_usage > config_usage;
     }
 
-    let is_send_message = match metadata_cache.get_alarm_event(alarm_type.as_str()) {
-        None => true,
-        Some(alarm_message) => alarm_message.activated != message.activated,
-    };
-
-    if is_send_message {
+    // Only the transitions of the alarm are published
+    let transition = metadata_cache.alarm_manager.set_alarm(
+        alarm_type.as_str(),
+        "warning",
+        message.activated,
+        current_usage as f64,
+        message.message.clone(),
+    );
+
+    if transition.is_some() {
         st_report_system_alarm_event(
             client_pool,
             metadata_cache,
@@ -153,8 +157,6 @@ async fn is_send_a_new_system_event<S>(
         )
         .await;
     }
-
-    metadata_cache.add_alarm_event(alarm_type.to_string(), message);
 }
 
 pub async fn st_report_system_alarm_event<S>(
@@ -242,6 +244,7 @@ pub fn get_process_memory_usage() -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::observability::warn::AlarmRecord;
     use crate::storage::message::cluster_name;
 
     use common_base::tools::unique_id;
@@ -250,6 +253,14 @@ mod tests {
     use metadata_struct::mqtt::topic::MqttTopic;
     use storage_adapter::memory::MemoryStorageAdapter;
 
+    fn latest_alarm(metadata_cache: &Arc<CacheManager>, name: &str) -> Option<AlarmRecord> {
+        metadata_cache
+            .alarm_manager
+            .latest_states()
+            .into_iter()
+            .find(|record| record.name == name)
+    }
+
     #[tokio::test]
     async fn test_alarm_type_to_string() {
         let high_cpu_alarm = AlarmType::HighCpuUsage;
@@ -367,7 +378,7 @@ async fn test_is_send_a_new_system_event_current_usage_gt_config_usage() {
         )
         .await;
 
-        let need_check_message = metadata_cache.get_alarm_event(except_key.as_str()).unwrap();
+        let need_check_message = latest_alarm(&metadata_cache, except_key.as_str()).unwrap();
 
         assert_eq!(need_check_message.name, except_value.name);
         assert_eq!(need_check_message.message, except_value.message);
@@ -389,15 +400,6 @@ async fn test_is_send_a_new_system_event_current_usage_le_config_usage() {
         let config_cpu_usage = broker_mqtt_conf().system_monitor.os_cpu_high_watermark;
 
         let except_key = AlarmType::HighCpuUsage;
-        let except_value = SystemAlarmEventMessage {
-            name: except_key.to_string(),
-            message: format!(
-                "{} is {}%, but config is {}%",
-                except_key, current_cpu_usage, config_cpu_usage
-            ),
-            activate_at: chrono::Utc::now().timestamp(),
-            activated: false,
-        };
 
         is_send_a_new_system_event(
             &client_pool,
@@ -409,11 +411,8 @@ async fn test_is_send_a_new_system_event_current_usage_le_config_usage() {
         )
         .await;
 
-        let need_check_message = metadata_cache.get_alarm_event(except_key.as_str()).unwrap();
-
-        assert_eq!(need_check_message.name, except_value.name);
-        assert_eq!(need_check_message.message, except_value.message);
-        assert_eq!(need_check_message.activated, except_value.activated);
+        // An alarm that was never activated has no transition
+        assert!(latest_alarm(&metadata_cache, except_key.as_str()).is_none());
     }
 
     #[tokio::test]
@@ -451,7 +450,7 @@ async fn test_is_send_a_new_system_event_metadata_exist_value_but_the_value_is_d
         )
         .await;
 
-        let first_check_message = metadata_cache.get_alarm_event(except_key.as_str()).unwrap();
+        let first_check_message = latest_alarm(&metadata_cache, except_key.as_str()).unwrap();
 
         assert_eq!(first_check_message.name, except_value.name);
         assert_eq!(first_check_message.message, except_value.message);
@@ -478,7 +477,7 @@ async fn test_is_send_a_new_system_event_metadata_exist_value_but_the_value_is_d
         )
         .await;
 
-        let twice_check_message = metadata_cache.get_alarm_event(except_key.as_str()).unwrap();
+        let twice_check_message = latest_alarm(&metadata_cache, except_key.as_str()).unwrap();
         assert_eq!(twice_check_message.name, except_value.name);
         assert_eq!(twice_check_message.message, except_value.message);
         assert_eq!(twice_check_message.activated, except_value.activated);
@@ -519,7 +518,7 @@ async fn test_is_send_a_new_system_event_metadata_exist_value_and_the_value_is_s
         )
         .await;
 
-        let first_check_message = metadata_cache.get_alarm_event(except_key.as_str()).unwrap();
+        let first_check_message = latest_alarm(&metadata_cache, except_key.as_str()).unwrap();
 
         assert_eq!(first_check_message.name, except_value.name);
         assert_eq!(first_check_message.message, except_value.message);
@@ -537,7 +536,7 @@ async fn test_is_send_a_new_system_event_metadata_exist_value_and_the_value_is_s
         )
         .await;
 
-        let twice_check_message = metadata_cache.get_alarm_event(except_key.as_str()).unwrap();
+        let twice_check_message = latest_alarm(&metadata_cache, except_key.as_str()).unwrap();
         assert_eq!(twice_check_message.name, except_value.name);
         assert_eq!(twice_check_message.message, except_value.message);
         assert_eq!(twice_check_message.activated, except_value.activated);
@@ -578,9 +577,7 @@ async fn test_is_send_a_new_system_event_metadata_param_is_different() {
         )
         .await;
 
-        let cpu_check_message = metadata_cache
-            .get_alarm_event(except_cpu_key.as_str())
-            .unwrap();
+        let cpu_check_message = latest_alarm(&metadata_cache, except_cpu_key.as_str()).unwrap();
 
         assert_eq!(cpu_check_message.name, except_memory_value.name);
         assert_eq!(cpu_check_message.message, except_memory_value.message);
@@ -610,9 +607,8 @@ async fn test_is_send_a_new_system_event_metadata_param_is_different() {
         )
         .await;
 
-        let memory_check_message = metadata_cache
-            .get_alarm_event(except_memory_key.as_str())
-            .unwrap();
+        let memory_check_message =
+            latest_alarm(&metadata_cache, except_memory_key.as_str()).unwrap();
 
         assert_eq!(memory_check_message.name, except_memory_value.name);
         assert_eq!(memory_check_message.message, except_memory_value.message);
@@ -11,3 +11,82 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::sync::Arc;
+
+use grpc_clients::pool::ClientPool;
+use storage_adapter::storage::StorageAdapter;
+
+use metadata_struct::mqtt::message::MqttMessage;
+use tracing::error;
+
+use super::sysmon::{
+    st_report_system_alarm_event, SystemAlarmEventMessage, SYSTEM_TOPIC_BROKERS_ALARMS_ACTIVATE,
+    SYSTEM_TOPIC_BROKERS_ALARMS_DEACTIVATE,
+};
+use super::{replace_topic_name, write_topic_data};
+use crate::handler::cache::CacheManager;
+use crate::observability::warn::AlarmRecord;
+
+const OFFLINE_QUEUE_OVERFLOW_ALARM: &str = "OfflineMessageQueueOverflow";
+
+pub async fn st_report_offline_queue_overflow<S>(
+    client_pool: &Arc<ClientPool>,
+    metadata_cache: &Arc<CacheManager>,
+    message_storage_adapter: &Arc<S>,
+    client_id: &str,
+    drop_policy: &str,
+) where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    let (messages, bytes) = metadata_cache.offline_queue.queued(client_id);
+    let message = SystemAlarmEventMessage {
+        name: OFFLINE_QUEUE_OVERFLOW_ALARM.to_string(),
+        message: format!(
+            "offline message queue of client {} is full with {} messages and {} bytes, messages are dropped by {}",
+            client_id, messages, bytes, drop_policy
+        ),
+        activate_at: chrono::Utc::now().timestamp(),
+        activated: true,
+    };
+    st_report_system_alarm_event(
+        client_pool,
+        metadata_cache,
+        message_storage_adapter,
+        &message,
+    )
+    .await;
+}
+
+pub async fn st_report_alarm_record<S>(
+    client_pool: &Arc<ClientPool>,
+    metadata_cache: &Arc<CacheManager>,
+    message_storage_adapter: &Arc<S>,
+    record: &AlarmRecord,
+) where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    let data = match serde_json::to_string(record) {
+        Ok(data) => data,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+
+    let topic_name = if record.activated {
+        replace_topic_name(SYSTEM_TOPIC_BROKERS_ALARMS_ACTIVATE.to_string())
+    } else {
+        replace_topic_name(SYSTEM_TOPIC_BROKERS_ALARMS_DEACTIVATE.to_string())
+    };
+    if let Some(message) = MqttMessage::build_system_topic_message(topic_name.clone(), data) {
+        write_topic_data(
+            message_storage_adapter,
+            metadata_cache,
+            client_pool,
+            topic_name,
+            message,
+        )
+        .await;
+    }
+}
@@ -0,0 +1,381 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use common_base::tools::now_second;
+use common_config::mqtt::config::TopicMetrics;
+use dashmap::DashMap;
+use protocol::mqtt::common::QoS;
+use tracing::info;
+
+use crate::handler::error::MqttBrokerError;
+use crate::handler::tenant::strip_tenant_namespace;
+use crate::subscribe::common::TopicFilterMatcher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicMetricsSortBy {
+    MessagesIn,
+    MessagesOut,
+    BytesIn,
+    BytesOut,
+    MessagesDropped,
+}
+
+impl FromStr for TopicMetricsSortBy {
+    type Err = MqttBrokerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" | "messages_in" => Ok(TopicMetricsSortBy::MessagesIn),
+            "messages_out" => Ok(TopicMetricsSortBy::MessagesOut),
+            "bytes_in" => Ok(TopicMetricsSortBy::BytesIn),
+            "bytes_out" => Ok(TopicMetricsSortBy::BytesOut),
+            "messages_dropped" => Ok(TopicMetricsSortBy::MessagesDropped),
+            _ => Err(MqttBrokerError::CommonError(format!(
+                "unknown sort field {}, supports messages_in, messages_out, bytes_in, bytes_out, messages_dropped",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TopicCounter {
+    messages_in: AtomicU64,
+    messages_out: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    messages_dropped: AtomicU64,
+    // Messages received, by the QoS they were published with
+    qos_in: [AtomicU64; 3],
+    update_time: AtomicU64,
+    // Order of the last update among all counters, the least recently updated counters are
+    // evicted first when their activity is the same
+    update_seq: AtomicU64,
+}
+
+impl TopicCounter {
+    fn activity(&self) -> u64 {
+        self.messages_in.load(Ordering::Relaxed)
+            + self.messages_out.load(Ordering::Relaxed)
+            + self.messages_dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopicMetricsData {
+    // The topic, or the topic filter when the messages are counted per filter
+    pub topic: String,
+    pub messages_in: u64,
+    pub messages_out: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub messages_dropped: u64,
+    pub qos0_in: u64,
+    pub qos1_in: u64,
+    pub qos2_in: u64,
+    pub update_time: u64,
+}
+
+impl TopicMetricsData {
+    fn sort_value(&self, sort_by: TopicMetricsSortBy) -> u64 {
+        match sort_by {
+            TopicMetricsSortBy::MessagesIn => self.messages_in,
+            TopicMetricsSortBy::MessagesOut => self.messages_out,
+            TopicMetricsSortBy::BytesIn => self.bytes_in,
+            TopicMetricsSortBy::BytesOut => self.bytes_out,
+            TopicMetricsSortBy::MessagesDropped => self.messages_dropped,
+        }
+    }
+}
+
+/// Message counters of single topics or topic filters, kept in memory by each broker since it
+/// started. The number of counted topics is bounded, so a flood of distinct topic names can not
+/// exhaust the memory. Once the limit is reached the least active counters are evicted to make
+/// room for new topics, so the busiest topics stay counted.
+#[derive(Default)]
+pub struct TopicMetricsManager {
+    enable: AtomicBool,
+    max_topic_num: AtomicUsize,
+    // Empty means the messages are counted per topic
+    topic_filters: RwLock<Vec<(String, TopicFilterMatcher)>>,
+    // (topic or topic filter, TopicCounter)
+    counters: DashMap<String, Arc<TopicCounter>>,
+    update_seq: AtomicU64,
+    // Only one thread evicts at a time, the others keep counting
+    evicting: Mutex<()>,
+    // Counters evicted because max_topic_num was reached
+    overflow_num: AtomicU64,
+}
+
+impl TopicMetricsManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load_config(&self, config: &TopicMetrics) {
+        *self.topic_filters.write().unwrap() = config
+            .topic_filters
+            .iter()
+            .map(|filter| (filter.clone(), TopicFilterMatcher::new(filter)))
+            .collect();
+        self.max_topic_num
+            .store(config.max_topic_num, Ordering::Relaxed);
+        self.enable.store(config.enable, Ordering::Relaxed);
+        if config.enable {
+            info!(
+                "Topic metrics enabled, counting at most {} {}",
+                config.max_topic_num,
+                if config.topic_filters.is_empty() {
+                    "topics".to_string()
+                } else {
+                    format!("of the topic filters {:?}", config.topic_filters)
+                }
+            );
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enable.load(Ordering::Relaxed)
+    }
+
+    /// A message accepted from a publisher.
+    pub fn record_in(&self, topic_name: &str, qos: QoS, bytes: usize) {
+        self.record(topic_name, |counter| {
+            counter.messages_in.fetch_add(1, Ordering::Relaxed);
+            counter.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+            counter.qos_in[qos as usize].fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    /// A message sent to a subscriber.
+    pub fn record_out(&self, topic_name: &str, bytes: usize) {
+        self.record(topic_name, |counter| {
+            counter.messages_out.fetch_add(1, Ordering::Relaxed);
+            counter.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+        });
+    }
+
+    /// A message discarded instead of being stored or sent to a subscriber.
+    pub fn record_dropped(&self, topic_name: &str) {
+        self.record(topic_name, |counter| {
+            counter.messages_dropped.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    /// The `limit` topics with the highest `sort_by` value. A `limit` of 0 returns all of them.
+    pub fn top(&self, sort_by: TopicMetricsSortBy, limit: usize) -> Vec<TopicMetricsData> {
+        let mut list: Vec<TopicMetricsData> = self
+            .counters
+            .iter()
+            .map(|entry| {
+                let counter = entry.value();
+                TopicMetricsData {
+                    topic: entry.key().clone(),
+                    messages_in: counter.messages_in.load(Ordering::Relaxed),
+                    messages_out: counter.messages_out.load(Ordering::Relaxed),
+                    bytes_in: counter.bytes_in.load(Ordering::Relaxed),
+                    bytes_out: counter.bytes_out.load(Ordering::Relaxed),
+                    messages_dropped: counter.messages_dropped.load(Ordering::Relaxed),
+                    qos0_in: counter.qos_in[0].load(Ordering::Relaxed),
+                    qos1_in: counter.qos_in[1].load(Ordering::Relaxed),
+                    qos2_in: counter.qos_in[2].load(Ordering::Relaxed),
+                    update_time: counter.update_time.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+        list.sort_by(|a, b| {
+            b.sort_value(sort_by)
+                .cmp(&a.sort_value(sort_by))
+                .then_with(|| a.topic.cmp(&b.topic))
+        });
+        if limit > 0 {
+            list.truncate(limit);
+        }
+        list
+    }
+
+    pub fn overflow_num(&self) -> u64 {
+        self.overflow_num.load(Ordering::Relaxed)
+    }
+
+    fn record<F>(&self, topic_name: &str, update: F)
+    where
+        F: Fn(&TopicCounter),
+    {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let filters = self.topic_filters.read().unwrap();
+        if filters.is_empty() {
+            self.update_counter(topic_name, &update);
+            return;
+        }
+
+        // The filters are written without the namespace of a tenant
+        let topic_name = strip_tenant_namespace(topic_name);
+        for (filter, matcher) in filters.iter() {
+            if matcher.is_match(&topic_name) {
+                self.update_counter(filter, &update);
+            }
+        }
+    }
+
+    fn update_counter<F>(&self, key: &str, update: &F)
+    where
+        F: Fn(&TopicCounter),
+    {
+        let counter = if let Some(counter) = self.counters.get(key) {
+            counter.clone()
+        } else {
+            let max_topic_num = self.max_topic_num.load(Ordering::Relaxed);
+            if max_topic_num == 0 {
+                return;
+            }
+            if self.counters.len() >= max_topic_num {
+                self.evict(max_topic_num);
+            }
+            self.counters.entry(key.to_owned()).or_default().clone()
+        };
+        update(&counter);
+        counter.update_time.store(now_second(), Ordering::Relaxed);
+        counter.update_seq.store(
+            self.update_seq.fetch_add(1, Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+    }
+
+    // Evicts the least active tenth of the counters at once, so the counters are not scanned
+    // for every new topic
+    fn evict(&self, max_topic_num: usize) {
+        let Ok(_guard) = self.evicting.try_lock() else {
+            return;
+        };
+
+        let mut candidates: Vec<(u64, u64, String)> = self
+            .counters
+            .iter()
+            .map(|entry| {
+                (
+                    entry.activity(),
+                    entry.update_seq.load(Ordering::Relaxed),
+                    entry.key().clone(),
+                )
+            })
+            .collect();
+        let evict_num = (candidates.len() + 1).saturating_sub(max_topic_num) + max_topic_num / 10;
+        candidates.sort_unstable();
+        for (_, _, key) in candidates.into_iter().take(evict_num.max(1)) {
+            self.counters.remove(&key);
+            self.overflow_num.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_topic_num: usize, topic_filters: Vec<&str>) -> TopicMetrics {
+        TopicMetrics {
+            enable: true,
+            max_topic_num,
+            topic_filters: topic_filters.into_iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn per_topic_counters_test() {
+        let manager = TopicMetricsManager::new();
+        manager.record_in("a/1", QoS::AtMostOnce, 10);
+        assert!(manager.top(TopicMetricsSortBy::MessagesIn, 0).is_empty());
+
+        manager.load_config(&config(2, Vec::new()));
+        manager.record_in("a/1", QoS::AtMostOnce, 10);
+        manager.record_in("a/1", QoS::ExactlyOnce, 10);
+        manager.record_in("a/2", QoS::AtLeastOnce, 100);
+        // a/2 is the least active counter and makes room for a/3
+        manager.record_in("a/3", QoS::AtLeastOnce, 100);
+        manager.record_out("a/3", 100);
+        manager.record_dropped("a/1");
+
+        let top = manager.top(TopicMetricsSortBy::MessagesIn, 0);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].topic, "a/1");
+        assert_eq!(top[0].messages_in, 2);
+        assert_eq!(top[0].qos0_in, 1);
+        assert_eq!(top[0].qos2_in, 1);
+        assert_eq!(top[0].messages_dropped, 1);
+        assert_eq!(manager.overflow_num(), 1);
+
+        let top = manager.top(TopicMetricsSortBy::BytesIn, 1);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].topic, "a/3");
+        assert_eq!(top[0].bytes_out, 100);
+    }
+
+    #[test]
+    fn counter_eviction_test() {
+        let manager = TopicMetricsManager::new();
+        manager.load_config(&config(20, Vec::new()));
+        for i in 0..20 {
+            manager.record_in(&format!("a/{}", i), QoS::AtMostOnce, 1);
+        }
+        manager.record_in("a/19", QoS::AtMostOnce, 1);
+
+        // Makes room for the new topic and a tenth of the limit at once
+        manager.record_in("b/1", QoS::AtMostOnce, 1);
+        assert_eq!(manager.overflow_num(), 3);
+        let top = manager.top(TopicMetricsSortBy::MessagesIn, 0);
+        assert_eq!(top.len(), 18);
+        assert_eq!(top[0].topic, "a/19");
+        assert!(top.iter().any(|data| data.topic == "b/1"));
+        assert!(!top.iter().any(|data| data.topic == "a/0"));
+    }
+
+    #[test]
+    fn per_filter_counters_test() {
+        let manager = TopicMetricsManager::new();
+        manager.load_config(&config(10, vec!["sensor/+/temp", "sensor/#"]));
+        manager.record_in("sensor/1/temp", QoS::AtLeastOnce, 4);
+        manager.record_in("sensor/2/humidity", QoS::AtLeastOnce, 4);
+        manager.record_in("device/1", QoS::AtLeastOnce, 4);
+        manager.record_in("/_tenant/t1/sensor/3/temp", QoS::AtLeastOnce, 4);
+
+        let top = manager.top(TopicMetricsSortBy::MessagesIn, 0);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].topic, "sensor/#");
+        assert_eq!(top[0].messages_in, 3);
+        assert_eq!(top[1].topic, "sensor/+/temp");
+        assert_eq!(top[1].messages_in, 2);
+        assert_eq!(manager.overflow_num(), 0);
+    }
+
+    #[test]
+    fn parse_sort_by_test() {
+        assert_eq!(
+            "".parse::<TopicMetricsSortBy>().unwrap(),
+            TopicMetricsSortBy::MessagesIn
+        );
+        assert_eq!(
+            "bytes_out".parse::<TopicMetricsSortBy>().unwrap(),
+            TopicMetricsSortBy::BytesOut
+        );
+        assert!("qos".parse::<TopicMetricsSortBy>().is_err());
+    }
+}
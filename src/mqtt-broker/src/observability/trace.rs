@@ -0,0 +1,203 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::telemetry::trace::{is_tracer_enabled, CustomContext};
+use common_base::utils::crc::calc_crc32;
+use metadata_struct::adapter::record::{Header, Record};
+use metadata_struct::mqtt::message::MqttMessage;
+use opentelemetry::global;
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{Context, KeyValue};
+use protocol::mqtt::common::PublishProperties;
+
+const TRACER_NAME: &str = "robustmq-mqtt";
+pub const TRACE_PARENT: &str = "traceparent";
+pub const TRACE_STATE: &str = "tracestate";
+
+fn trace_carrier(user_properties: &[(String, String)]) -> CustomContext {
+    let mut carrier = CustomContext::new();
+    for (key, value) in user_properties {
+        if key == TRACE_PARENT || key == TRACE_STATE {
+            carrier.inner.insert(key.clone(), value.clone());
+        }
+    }
+    carrier
+}
+
+fn start_span(
+    name: &'static str,
+    kind: SpanKind,
+    parent: &Context,
+    attributes: Vec<KeyValue>,
+) -> Context {
+    let tracer = global::tracer(TRACER_NAME);
+    let span = tracer
+        .span_builder(name)
+        .with_kind(kind)
+        .with_attributes(attributes)
+        .start_with_context(&tracer, parent);
+    parent.with_span(span)
+}
+
+// Span of a message in the broker. The parent is the W3C trace context carried in the
+// MQTT 5 user properties of the message, the span is ended when it is dropped.
+// Nothing is recorded when no tracer is exported.
+#[derive(Debug)]
+pub struct MessageTrace {
+    cx: Option<Context>,
+}
+
+impl MessageTrace {
+    pub fn start(
+        name: &'static str,
+        kind: SpanKind,
+        user_properties: &[(String, String)],
+        attributes: Vec<KeyValue>,
+    ) -> Self {
+        if !is_tracer_enabled() {
+            return MessageTrace { cx: None };
+        }
+        let parent = trace_carrier(user_properties).extract();
+        MessageTrace {
+            cx: Some(start_span(name, kind, &parent, attributes)),
+        }
+    }
+
+    // A step of the processing of the message, ended when the returned span is dropped
+    pub fn step(&self, name: &'static str) -> MessageTrace {
+        MessageTrace {
+            cx: self
+                .cx
+                .as_ref()
+                .map(|cx| start_span(name, SpanKind::Internal, cx, Vec::new())),
+        }
+    }
+
+    pub fn set_attribute(&self, attribute: KeyValue) {
+        if let Some(cx) = &self.cx {
+            cx.span().set_attribute(attribute);
+        }
+    }
+
+    pub fn set_error(&self, message: String) {
+        if let Some(cx) = &self.cx {
+            cx.span().set_status(Status::error(message));
+        }
+    }
+
+    // Replaces the trace context in the user properties with the context of this span,
+    // so the next hop continues the trace from the broker
+    pub fn inject(&self, user_properties: &mut Vec<(String, String)>) {
+        let Some(cx) = &self.cx else {
+            return;
+        };
+        let carrier = CustomContext::inject(cx);
+        user_properties.retain(|(key, _)| key != TRACE_PARENT && key != TRACE_STATE);
+        for key in [TRACE_PARENT, TRACE_STATE] {
+            if let Some(value) = carrier.inner.get(key) {
+                user_properties.push((key.to_string(), value.clone()));
+            }
+        }
+    }
+
+    // Publish properties that carry the context of this span, MQTT 3 messages get properties
+    // for it. None when nothing is recorded and the properties are used as they are.
+    pub fn inject_publish_properties(
+        &self,
+        publish_properties: &Option<PublishProperties>,
+    ) -> Option<Option<PublishProperties>> {
+        self.cx.as_ref()?;
+        let mut properties = publish_properties.clone().unwrap_or_default();
+        self.inject(&mut properties.user_properties);
+        Some(Some(properties))
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.cx.is_some()
+    }
+}
+
+impl Drop for MessageTrace {
+    fn drop(&mut self) {
+        if let Some(cx) = &self.cx {
+            cx.span().end();
+        }
+    }
+}
+
+// Records written by a connector continue the trace of their message. The trace context is
+// injected into the user properties of the message and into the record headers, the spans
+// are ended when the returned traces are dropped.
+pub fn trace_connector_records(connector_name: &str, records: &mut [Record]) -> Vec<MessageTrace> {
+    if !is_tracer_enabled() {
+        return Vec::new();
+    }
+    records
+        .iter_mut()
+        .filter_map(|record| {
+            let mut message = MqttMessage::decode_record(record.clone()).ok()?;
+            let trace = MessageTrace::start(
+                "mqtt.connector",
+                SpanKind::Producer,
+                &message.user_properties,
+                vec![KeyValue::new("mqtt.connector", connector_name.to_owned())],
+            );
+            trace.inject(&mut message.user_properties);
+
+            let data = serde_json::to_vec(&message).ok()?;
+            record.crc_num = calc_crc32(&data);
+            record.data = data;
+            record
+                .header
+                .retain(|header| header.name != TRACE_PARENT && header.name != TRACE_STATE);
+            for (name, value) in message.user_properties.iter() {
+                if name == TRACE_PARENT || name == TRACE_STATE {
+                    record.header.push(Header {
+                        name: name.clone(),
+                        value: value.clone(),
+                    });
+                }
+            }
+            Some(trace)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::SpanKind;
+
+    use super::{MessageTrace, TRACE_PARENT};
+
+    #[test]
+    fn message_trace_disabled_test() {
+        let user_properties = vec![(
+            TRACE_PARENT.to_string(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+        )];
+        let trace = MessageTrace::start(
+            "mqtt.publish",
+            SpanKind::Server,
+            &user_properties,
+            Vec::new(),
+        );
+        assert!(!trace.is_recording());
+        assert!(!trace.step("mqtt.publish.acl").is_recording());
+
+        // Without a tracer the trace context of the publisher is passed on unchanged
+        let mut properties = user_properties.clone();
+        trace.inject(&mut properties);
+        assert_eq!(properties, user_properties);
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::pool::ClientPool;
+use storage_adapter::storage::StorageAdapter;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+use super::{
+    AlarmRecord, ALARM_CONNECTION_COUNT_HIGH, ALARM_CONNECTOR_FAILURE, ALARM_INFLIGHT_HIGH,
+    ALARM_STORAGE_WRITE_FAILURE, ALARM_SUBSCRIPTION_COUNT_HIGH,
+};
+use crate::handler::cache::CacheManager;
+use crate::observability::system_topic::warn::st_report_alarm_record;
+use crate::storage::alarm::AlarmStorage;
+use crate::subscribe::manager::SubscribeManager;
+
+// Failures counted since the last check
+static STORAGE_WRITE_FAILURE_NUM: AtomicU64 = AtomicU64::new(0);
+static CONNECTOR_FAILURE_NUM: AtomicU64 = AtomicU64::new(0);
+
+pub fn record_storage_write_failure() {
+    STORAGE_WRITE_FAILURE_NUM.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_connector_failure() {
+    CONNECTOR_FAILURE_NUM.fetch_add(1, Ordering::Relaxed);
+}
+
+pub async fn start_alarm_check_thread<S>(
+    cache_manager: Arc<CacheManager>,
+    subscribe_manager: Arc<SubscribeManager>,
+    message_storage_adapter: Arc<S>,
+    client_pool: Arc<ClientPool>,
+    stop_send: broadcast::Sender<bool>,
+) where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    let conf = broker_mqtt_conf();
+    if !conf.alarm.enable {
+        return;
+    }
+
+    cache_manager.alarm_manager.load_config(&conf.alarm);
+    let mut history_loaded = load_alarm_history(&client_pool, &cache_manager).await;
+
+    let mut stop_recv = stop_send.subscribe();
+    loop {
+        select! {
+            val = stop_recv.recv() => {
+                if let Ok(flag) = val {
+                    if flag {
+                        info!("Alarm check thread exited successfully");
+                        break;
+                    }
+                }
+            }
+            _ = sleep(Duration::from_millis(conf.alarm.check_interval_ms)) => {
+                if !history_loaded {
+                    history_loaded = load_alarm_history(&client_pool, &cache_manager).await;
+                }
+                check_alarms(
+                    &cache_manager,
+                    &subscribe_manager,
+                    &message_storage_adapter,
+                    &client_pool,
+                    history_loaded,
+                )
+                .await;
+            }
+        }
+    }
+}
+
+async fn check_alarms<S>(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    message_storage_adapter: &Arc<S>,
+    client_pool: &Arc<ClientPool>,
+    history_loaded: bool,
+) where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    let connection_num = cache_manager.connection_info.len();
+    let subscription_num = subscribe_manager.subscribe_list.len();
+    let storage_write_failure_num = STORAGE_WRITE_FAILURE_NUM.swap(0, Ordering::Relaxed);
+    let connector_failure_num = CONNECTOR_FAILURE_NUM.swap(0, Ordering::Relaxed);
+    let max_inflight = cache_manager
+        .pkid_metadata
+        .inflight_num
+        .iter()
+        .map(|entry| (entry.key().clone(), *entry.value()))
+        .max_by_key(|(_, num)| *num);
+
+    let mut checks = vec![
+        (
+            ALARM_CONNECTION_COUNT_HIGH,
+            connection_num as f64,
+            format!("{} client connections", connection_num),
+        ),
+        (
+            ALARM_SUBSCRIPTION_COUNT_HIGH,
+            subscription_num as f64,
+            format!("{} subscriptions", subscription_num),
+        ),
+        (
+            ALARM_STORAGE_WRITE_FAILURE,
+            storage_write_failure_num as f64,
+            format!(
+                "{} messages failed to be written to the message storage",
+                storage_write_failure_num
+            ),
+        ),
+        (
+            ALARM_CONNECTOR_FAILURE,
+            connector_failure_num as f64,
+            format!("{} connector failures", connector_failure_num),
+        ),
+    ];
+    let (client_id, inflight_num) = max_inflight.unwrap_or_default();
+    checks.push((
+        ALARM_INFLIGHT_HIGH,
+        inflight_num as f64,
+        format!(
+            "client {} has {} messages waiting for an acknowledgement",
+            client_id, inflight_num
+        ),
+    ));
+
+    for (name, value, message) in checks {
+        if let Some(record) = cache_manager.alarm_manager.evaluate(name, value, message) {
+            report_alarm_transition(client_pool, cache_manager, message_storage_adapter, &record)
+                .await;
+        }
+    }
+
+    // Also covers the transitions of the system monitor alarms and manual clears. Until the
+    // stored history is loaded the ids of the new transitions may collide with stored ones,
+    // they are kept unsaved and numbered after the stored history once it is loaded.
+    if history_loaded {
+        save_alarm_history(client_pool, cache_manager).await;
+    }
+}
+
+/// Publishes the transition on the alarm system topics. The alarm history is persisted by
+/// the next check.
+pub async fn report_alarm_transition<S>(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    message_storage_adapter: &Arc<S>,
+    record: &AlarmRecord,
+) where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    if record.activated {
+        warn!(
+            "Alarm {} [{}] activated: {}",
+            record.name, record.severity, record.message
+        );
+    } else {
+        info!("Alarm {} deactivated: {}", record.name, record.message);
+    }
+    st_report_alarm_record(client_pool, cache_manager, message_storage_adapter, record).await;
+}
+
+async fn save_alarm_history(client_pool: &Arc<ClientPool>, cache_manager: &Arc<CacheManager>) {
+    let conf = broker_mqtt_conf();
+    let alarm_storage = AlarmStorage::new(client_pool.clone());
+    let (records, expired) = cache_manager.alarm_manager.take_unsaved();
+
+    let mut failed_records = Vec::new();
+    for record in records {
+        if let Err(e) = alarm_storage.save(conf.broker_id, &record).await {
+            error!("Failed to persist the alarm history, error message: {}", e);
+            failed_records.push(record);
+        }
+    }
+
+    let mut failed_expired = Vec::new();
+    for id in expired {
+        if let Err(e) = alarm_storage.delete(conf.broker_id, id).await {
+            error!(
+                "Failed to delete the expired alarm history, error message: {}",
+                e
+            );
+            failed_expired.push(id);
+        }
+    }
+
+    cache_manager
+        .alarm_manager
+        .restore_unsaved(failed_records, failed_expired);
+}
+
+async fn load_alarm_history(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+) -> bool {
+    let conf = broker_mqtt_conf();
+    let alarm_storage = AlarmStorage::new(client_pool.clone());
+    match alarm_storage.list_by_broker(conf.broker_id).await {
+        Ok(records) => {
+            cache_manager.alarm_manager.load_history(records);
+            true
+        }
+        Err(e) => {
+            error!(
+                "Failed to load the alarm history, retried by the next check, error message: {}",
+                e
+            );
+            false
+        }
+    }
+}
@@ -11,3 +11,537 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+
+use common_base::tools::now_second;
+use common_config::mqtt::config::{Alarm, AlarmRule};
+use common_config::mqtt::default::default_alarm_rules;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+pub mod check;
+
+pub const ALARM_CONNECTION_COUNT_HIGH: &str = "ConnectionCountHigh";
+pub const ALARM_SUBSCRIPTION_COUNT_HIGH: &str = "SubscriptionCountHigh";
+pub const ALARM_STORAGE_WRITE_FAILURE: &str = "StorageWriteFailure";
+pub const ALARM_CONNECTOR_FAILURE: &str = "ConnectorFailure";
+pub const ALARM_INFLIGHT_HIGH: &str = "InflightHigh";
+
+const ALARM_SEVERITY_LIST: [&str; 3] = ["info", "warning", "critical"];
+const DEFAULT_HISTORY_MAX_NUM: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlarmRecord {
+    // Sequence number of the transition on its broker
+    #[serde(default)]
+    pub id: u64,
+    #[serde(default)]
+    pub broker_id: u64,
+    pub name: String,
+    pub severity: String,
+    pub message: String,
+    // The checked value that caused the last transition
+    pub value: f64,
+    pub activate_at: u64,
+    // 0 while the alarm is active
+    pub deactivate_at: u64,
+    pub activated: bool,
+}
+
+/// Keeps the alarm rules, the currently active alarms and the history of their transitions.
+///
+/// Alarms driven by a rule are activated when the checked value reaches the activate
+/// threshold and deactivated when it falls back to the deactivate threshold. Alarms without
+/// a rule, such as the CPU and memory alarms, are switched directly by their owner.
+///
+/// Every transition is persisted on its own, the alarms of the whole cluster are listed from
+/// the persisted transitions of the other brokers merged with the local state.
+pub struct AlarmManager {
+    rules: DashMap<String, AlarmRule>,
+    active: DashMap<String, AlarmRecord>,
+    history: RwLock<VecDeque<AlarmRecord>>,
+    history_max_num: AtomicUsize,
+    next_id: AtomicU64,
+    // Transitions not persisted yet
+    unsaved: Mutex<Vec<AlarmRecord>>,
+    // Persisted transitions that were pushed out of the history
+    expired: Mutex<Vec<u64>>,
+}
+
+impl Default for AlarmManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlarmManager {
+    pub fn new() -> Self {
+        AlarmManager {
+            rules: default_alarm_rules().into_iter().collect(),
+            active: DashMap::with_capacity(8),
+            history: RwLock::new(VecDeque::new()),
+            history_max_num: AtomicUsize::new(DEFAULT_HISTORY_MAX_NUM),
+            next_id: AtomicU64::new(0),
+            unsaved: Mutex::new(Vec::new()),
+            expired: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn load_config(&self, config: &Alarm) {
+        for (name, rule) in config.rules.iter() {
+            let mut rule = rule.clone();
+            if !ALARM_SEVERITY_LIST.contains(&rule.severity.as_str()) {
+                warn!(
+                    "Unknown severity {} of alarm rule {}, supports info, warning, critical",
+                    rule.severity, name
+                );
+                rule.severity = "warning".to_string();
+            }
+            self.rules.insert(name.clone(), rule);
+        }
+        if config.history_max_num > 0 {
+            self.history_max_num
+                .store(config.history_max_num, Ordering::Relaxed);
+        }
+    }
+
+    pub fn get_rule(&self, name: &str) -> Option<AlarmRule> {
+        self.rules.get(name).map(|rule| rule.clone())
+    }
+
+    /// Checks `value` against the rule of the alarm and returns the record of the transition
+    /// if the alarm was activated or deactivated by it.
+    pub fn evaluate(&self, name: &str, value: f64, message: String) -> Option<AlarmRecord> {
+        let rule = self.get_rule(name)?;
+        let activated = self.active.contains_key(name);
+
+        if !rule.enable {
+            return if activated {
+                self.deactivate(name, value, message)
+            } else {
+                None
+            };
+        }
+
+        if !activated && value >= rule.activate_threshold {
+            return self.activate(name, &rule.severity, value, message);
+        }
+
+        if activated && value <= rule.deactivate_threshold {
+            return self.deactivate(name, value, message);
+        }
+
+        None
+    }
+
+    /// Switches an alarm that is not driven by a rule, returns the record of the transition
+    /// if the state of the alarm changed.
+    pub fn set_alarm(
+        &self,
+        name: &str,
+        severity: &str,
+        activated: bool,
+        value: f64,
+        message: String,
+    ) -> Option<AlarmRecord> {
+        match (self.active.contains_key(name), activated) {
+            (false, true) => self.activate(name, severity, value, message),
+            (true, false) => self.deactivate(name, value, message),
+            _ => None,
+        }
+    }
+
+    /// Deactivates an alarm by hand. An alarm whose condition still holds is raised again by
+    /// the next check.
+    pub fn clear(&self, name: &str) -> Option<AlarmRecord> {
+        let value = self.active.get(name).map(|record| record.value)?;
+        self.deactivate(name, value, "cleared manually".to_string())
+    }
+
+    pub fn active_alarms(&self) -> Vec<AlarmRecord> {
+        let mut list: Vec<AlarmRecord> = self
+            .active
+            .iter()
+            .map(|record| record.value().clone())
+            .collect();
+        list.sort_by_key(|record| record.activate_at);
+        list
+    }
+
+    /// The latest `limit` transitions, newest first. A `limit` of 0 returns the whole history.
+    pub fn history(&self, limit: usize) -> Vec<AlarmRecord> {
+        let history = self.history.read().unwrap();
+        let limit = if limit == 0 { history.len() } else { limit };
+        history.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// The latest state of every alarm that was raised, active or not.
+    pub fn latest_states(&self) -> Vec<AlarmRecord> {
+        let mut list = self.active_alarms();
+        let mut names: HashSet<String> = list.iter().map(|record| record.name.clone()).collect();
+        let history = self.history.read().unwrap();
+        for record in history.iter().rev() {
+            if names.insert(record.name.clone()) {
+                list.push(record.clone());
+            }
+        }
+        list
+    }
+
+    /// Takes the transitions to persist and the ids of the persisted transitions to delete.
+    pub fn take_unsaved(&self) -> (Vec<AlarmRecord>, Vec<u64>) {
+        let records = std::mem::take(&mut *self.unsaved.lock().unwrap());
+        let expired = std::mem::take(&mut *self.expired.lock().unwrap());
+        (records, expired)
+    }
+
+    /// Hands back the changes that failed to be persisted, they are retried by the next check.
+    pub fn restore_unsaved(&self, records: Vec<AlarmRecord>, expired: Vec<u64>) {
+        self.unsaved.lock().unwrap().extend(records);
+        self.expired.lock().unwrap().extend(expired);
+    }
+
+    /// Restores the transitions persisted by a previous run of this broker. Alarms that were
+    /// active then are active again until the next check decides otherwise.
+    pub fn load_history(&self, mut records: Vec<AlarmRecord>) {
+        records.sort_by_key(|record| record.id);
+        let base = records.last().map(|record| record.id + 1).unwrap_or(0);
+
+        let mut history = self.history.write().unwrap();
+        let mut unsaved = self.unsaved.lock().unwrap();
+        let mut expired = self.expired.lock().unwrap();
+
+        // Transitions recorded since startup are newer than the persisted ones
+        self.next_id.fetch_add(base, Ordering::Relaxed);
+        for record in history.iter_mut().chain(unsaved.iter_mut()) {
+            record.id += base;
+        }
+        for id in expired.iter_mut() {
+            *id += base;
+        }
+
+        let changed: HashSet<&str> = history.iter().map(|record| record.name.as_str()).collect();
+        let mut latest: HashMap<String, AlarmRecord> = HashMap::new();
+        for record in records.iter() {
+            latest.insert(record.name.clone(), record.clone());
+        }
+        for (name, record) in latest {
+            if record.activated && !changed.contains(name.as_str()) {
+                self.active.entry(name).or_insert(record);
+            }
+        }
+
+        let max_num = self.history_max_num.load(Ordering::Relaxed);
+        let mut records: VecDeque<AlarmRecord> = records.into();
+        records.extend(history.drain(..));
+        while records.len() > max_num {
+            if let Some(record) = records.pop_front() {
+                expired.push(record.id);
+            }
+        }
+        *history = records;
+    }
+
+    /// Active alarms of the cluster. The persisted transitions of this broker are replaced by
+    /// its local state and brokers that are no longer alive are left out.
+    pub fn cluster_active_alarms(
+        &self,
+        broker_id: u64,
+        stored: Vec<AlarmRecord>,
+        alive_brokers: &HashSet<u64>,
+    ) -> Vec<AlarmRecord> {
+        let mut latest: HashMap<(u64, String), AlarmRecord> = HashMap::new();
+        for record in stored {
+            if record.broker_id == broker_id || !alive_brokers.contains(&record.broker_id) {
+                continue;
+            }
+            let key = (record.broker_id, record.name.clone());
+            match latest.get(&key) {
+                Some(current) if current.id > record.id => {}
+                _ => {
+                    latest.insert(key, record);
+                }
+            }
+        }
+
+        let mut list: Vec<AlarmRecord> = latest
+            .into_values()
+            .filter(|record| record.activated)
+            .chain(self.active_alarms().into_iter().map(|mut record| {
+                record.broker_id = broker_id;
+                record
+            }))
+            .collect();
+        list.sort_by_key(|record| record.activate_at);
+        list
+    }
+
+    /// The latest `limit` transitions of the cluster, newest first. A `limit` of 0 returns
+    /// the whole history.
+    pub fn cluster_history(
+        &self,
+        broker_id: u64,
+        stored: Vec<AlarmRecord>,
+        limit: usize,
+    ) -> Vec<AlarmRecord> {
+        let mut list: Vec<AlarmRecord> = stored
+            .into_iter()
+            .filter(|record| record.broker_id != broker_id)
+            .chain(self.history(0).into_iter().map(|mut record| {
+                record.broker_id = broker_id;
+                record
+            }))
+            .collect();
+        list.sort_by(|a, b| {
+            transition_time(b)
+                .cmp(&transition_time(a))
+                .then(b.id.cmp(&a.id))
+        });
+        if limit > 0 {
+            list.truncate(limit);
+        }
+        list
+    }
+
+    fn activate(
+        &self,
+        name: &str,
+        severity: &str,
+        value: f64,
+        message: String,
+    ) -> Option<AlarmRecord> {
+        let record = AlarmRecord {
+            id: 0,
+            broker_id: 0,
+            name: name.to_string(),
+            severity: severity.to_string(),
+            message,
+            value,
+            activate_at: now_second(),
+            deactivate_at: 0,
+            activated: true,
+        };
+        let record = self.push_history(record);
+        self.active.insert(name.to_string(), record.clone());
+        Some(record)
+    }
+
+    fn deactivate(&self, name: &str, value: f64, message: String) -> Option<AlarmRecord> {
+        let (_, mut record) = self.active.remove(name)?;
+        record.message = message;
+        record.value = value;
+        record.deactivate_at = now_second();
+        record.activated = false;
+        Some(self.push_history(record))
+    }
+
+    fn push_history(&self, mut record: AlarmRecord) -> AlarmRecord {
+        let max_num = self.history_max_num.load(Ordering::Relaxed);
+        let mut history = self.history.write().unwrap();
+        record.id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        history.push_back(record.clone());
+        self.unsaved.lock().unwrap().push(record.clone());
+        while history.len() > max_num {
+            if let Some(expired) = history.pop_front() {
+                self.expired.lock().unwrap().push(expired.id);
+            }
+        }
+        record
+    }
+}
+
+fn transition_time(record: &AlarmRecord) -> u64 {
+    if record.activated {
+        record.activate_at
+    } else {
+        record.deactivate_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate_with_hysteresis_test() {
+        let manager = AlarmManager::new();
+        let rule = manager.get_rule(ALARM_CONNECTION_COUNT_HIGH).unwrap();
+
+        assert!(manager
+            .evaluate(
+                ALARM_CONNECTION_COUNT_HIGH,
+                rule.activate_threshold - 1.0,
+                "".to_string()
+            )
+            .is_none());
+
+        let record = manager
+            .evaluate(
+                ALARM_CONNECTION_COUNT_HIGH,
+                rule.activate_threshold,
+                "".to_string(),
+            )
+            .unwrap();
+        assert!(record.activated);
+        assert_eq!(record.severity, "warning");
+        assert_eq!(manager.active_alarms().len(), 1);
+
+        // Falling below the activate threshold is not enough to deactivate
+        assert!(manager
+            .evaluate(
+                ALARM_CONNECTION_COUNT_HIGH,
+                rule.deactivate_threshold + 1.0,
+                "".to_string()
+            )
+            .is_none());
+
+        let record = manager
+            .evaluate(
+                ALARM_CONNECTION_COUNT_HIGH,
+                rule.deactivate_threshold,
+                "".to_string(),
+            )
+            .unwrap();
+        assert!(!record.activated);
+        assert!(record.deactivate_at > 0);
+        assert!(manager.active_alarms().is_empty());
+        assert_eq!(manager.history(0).len(), 2);
+        assert!(!manager.history(1)[0].activated);
+
+        assert!(manager.evaluate("unknown", 1.0, "".to_string()).is_none());
+    }
+
+    #[test]
+    fn load_config_test() {
+        let manager = AlarmManager::new();
+        let config = Alarm {
+            enable: true,
+            check_interval_ms: 1000,
+            history_max_num: 2,
+            rules: HashMap::from([(
+                ALARM_INFLIGHT_HIGH.to_string(),
+                AlarmRule {
+                    enable: true,
+                    severity: "fatal".to_string(),
+                    activate_threshold: 10.0,
+                    deactivate_threshold: 5.0,
+                },
+            )]),
+        };
+        manager.load_config(&config);
+
+        let rule = manager.get_rule(ALARM_INFLIGHT_HIGH).unwrap();
+        assert_eq!(rule.activate_threshold, 10.0);
+        assert_eq!(rule.severity, "warning");
+        // built-in rules that are not configured are kept
+        assert!(manager.get_rule(ALARM_STORAGE_WRITE_FAILURE).is_some());
+
+        for _ in 0..3 {
+            manager.evaluate(ALARM_INFLIGHT_HIGH, 10.0, "".to_string());
+            manager.evaluate(ALARM_INFLIGHT_HIGH, 0.0, "".to_string());
+        }
+        assert_eq!(manager.history(0).len(), 2);
+        assert_eq!(manager.take_unsaved().1, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn clear_and_restore_test() {
+        let manager = AlarmManager::new();
+        manager.set_alarm("HighCpuUsage", "warning", true, 90.0, "".to_string());
+        manager.evaluate(ALARM_STORAGE_WRITE_FAILURE, 3.0, "".to_string());
+        assert_eq!(manager.active_alarms().len(), 2);
+
+        let record = manager.clear("HighCpuUsage").unwrap();
+        assert!(!record.activated);
+        assert!(manager.clear("HighCpuUsage").is_none());
+
+        let (records, expired) = manager.take_unsaved();
+        assert_eq!(records.len(), 3);
+        assert!(expired.is_empty());
+        assert!(manager.take_unsaved().0.is_empty());
+
+        // Transitions recorded before the history is loaded are numbered after it
+        let restored = AlarmManager::new();
+        restored.set_alarm("MemoryUsage", "warning", true, 80.0, "".to_string());
+        restored.load_history(records);
+        let active = restored.active_alarms();
+        assert_eq!(active.len(), 2);
+        assert!(active
+            .iter()
+            .any(|record| record.name == ALARM_STORAGE_WRITE_FAILURE));
+        assert_eq!(restored.history(0).len(), 4);
+        assert_eq!(restored.history(1)[0].id, 3);
+        let (records, _) = restored.take_unsaved();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, 3);
+        assert_eq!(restored.latest_states().len(), 3);
+    }
+
+    #[test]
+    fn load_history_after_expired_test() {
+        let stored = AlarmManager::new();
+        stored.set_alarm("HighCpuUsage", "warning", true, 90.0, "".to_string());
+        stored.set_alarm("HighCpuUsage", "warning", false, 10.0, "".to_string());
+        let (records, _) = stored.take_unsaved();
+
+        // The load failed at startup, the transitions since then are numbered after it
+        let manager = AlarmManager::new();
+        manager.load_config(&Alarm {
+            history_max_num: 1,
+            ..Default::default()
+        });
+        manager.set_alarm("MemoryUsage", "warning", true, 80.0, "".to_string());
+        manager.set_alarm("MemoryUsage", "warning", false, 50.0, "".to_string());
+        manager.load_history(records);
+
+        let (records, expired) = manager.take_unsaved();
+        assert_eq!(
+            records.iter().map(|record| record.id).collect::<Vec<u64>>(),
+            vec![2, 3]
+        );
+        assert_eq!(expired, vec![2, 0, 1]);
+        assert_eq!(manager.history(0)[0].id, 3);
+    }
+
+    #[test]
+    fn cluster_alarms_test() {
+        let manager = AlarmManager::new();
+        manager.set_alarm("HighCpuUsage", "warning", true, 90.0, "".to_string());
+
+        let remote = AlarmManager::new();
+        remote.set_alarm("HighCpuUsage", "warning", true, 95.0, "".to_string());
+        remote.set_alarm("MemoryUsage", "warning", true, 85.0, "".to_string());
+        remote.set_alarm("MemoryUsage", "warning", false, 50.0, "".to_string());
+        let mut stored: Vec<AlarmRecord> = remote
+            .take_unsaved()
+            .0
+            .into_iter()
+            .map(|mut record| {
+                record.broker_id = 2;
+                record
+            })
+            .collect();
+        let mut left = stored[0].clone();
+        left.broker_id = 3;
+        stored.push(left);
+        // stale copy of the local state
+        let mut local = stored[0].clone();
+        local.broker_id = 1;
+        stored.push(local);
+
+        let active = manager.cluster_active_alarms(1, stored.clone(), &HashSet::from([1, 2]));
+        assert_eq!(active.len(), 2);
+        assert!(active
+            .iter()
+            .all(|record| record.name == "HighCpuUsage" && record.broker_id != 3));
+
+        let history = manager.cluster_history(1, stored.clone(), 0);
+        assert_eq!(history.len(), 5);
+        assert_eq!(history.iter().filter(|r| r.broker_id == 1).count(), 1);
+        assert_eq!(manager.cluster_history(1, stored, 2).len(), 2);
+    }
+}
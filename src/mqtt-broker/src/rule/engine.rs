@@ -0,0 +1,432 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::{Arc, LazyLock};
+
+use bytes::Bytes;
+use common_base::tools::now_mills;
+use common_config::mqtt::broker_mqtt_conf;
+use delay_message::DelayMessageManager;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::message::MqttMessage;
+use metadata_struct::mqtt::rule::MqttRuleAction;
+use protocol::mqtt::common::{qos, Publish, PublishProperties};
+use regex::{Captures, Regex};
+use serde_json::{json, Map, Value};
+use storage_adapter::storage::StorageAdapter;
+use tracing::warn;
+
+use super::expr::is_truthy;
+use super::sql::RuleSql;
+use crate::bridge::core::try_init_connector_rule_shard;
+use crate::bridge::manager::ConnectorManager;
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::message::build_message_expire;
+use crate::handler::offline_message::save_message;
+use crate::handler::tenant::{tenant_topic_name, topic_tenant};
+use crate::handler::topic::{topic_name_validator, try_init_topic};
+use crate::observability::metrics::rule::{
+    incr_rule_counter, RULE_STATUS_ACTION_FAILED, RULE_STATUS_ACTION_SUCCESS, RULE_STATUS_FAILED,
+    RULE_STATUS_FILTERED, RULE_STATUS_MATCHED, RULE_STATUS_PASSED,
+};
+use crate::storage::message::MessageStorage;
+use crate::subscribe::common::is_wildcards;
+use crate::subscribe::manager::SubscribeManager;
+
+static TOPIC_TEMPLATE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$\{([A-Za-z0-9_.]+)\}").unwrap());
+
+#[derive(Clone)]
+pub struct RuleEngine<S> {
+    cache_manager: Arc<CacheManager>,
+    client_pool: Arc<ClientPool>,
+    message_storage_adapter: Arc<S>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
+    subscribe_manager: Arc<SubscribeManager>,
+    connector_manager: Arc<ConnectorManager>,
+}
+
+impl<S> RuleEngine<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        cache_manager: Arc<CacheManager>,
+        client_pool: Arc<ClientPool>,
+        message_storage_adapter: Arc<S>,
+        delay_message_manager: Arc<DelayMessageManager<S>>,
+        subscribe_manager: Arc<SubscribeManager>,
+        connector_manager: Arc<ConnectorManager>,
+    ) -> Self {
+        RuleEngine {
+            cache_manager,
+            client_pool,
+            message_storage_adapter,
+            delay_message_manager,
+            subscribe_manager,
+            connector_manager,
+        }
+    }
+
+    // Evaluate all enabled rules against a published message and return whether
+    // the message was dropped by one of them. Republish and connector actions run
+    // in the background, so they never delay the acknowledgement of the publisher.
+    pub fn apply(
+        &self,
+        client_id: &str,
+        username: &str,
+        topic_name: &str,
+        publish: &Publish,
+        publish_properties: &Option<PublishProperties>,
+    ) -> bool {
+        let rules = self.cache_manager.get_enable_compiled_rules();
+        if rules.is_empty() {
+            return false;
+        }
+
+        let mut context = None;
+        let mut is_drop = false;
+        for compiled in rules {
+            if !compiled.is_match(topic_name) {
+                continue;
+            }
+
+            let rule_name = compiled.rule.rule_name.clone();
+            incr_rule_counter(&rule_name, RULE_STATUS_MATCHED);
+
+            let context = context.get_or_insert_with(|| {
+                build_rule_context(client_id, username, topic_name, publish, publish_properties)
+            });
+
+            let output = match evaluate_rule(&compiled.sql, context) {
+                Ok(Some(output)) => output,
+                Ok(None) => {
+                    incr_rule_counter(&rule_name, RULE_STATUS_FILTERED);
+                    continue;
+                }
+                Err(e) => {
+                    incr_rule_counter(&rule_name, RULE_STATUS_FAILED);
+                    warn!(
+                        "{}",
+                        MqttBrokerError::RuleEvaluationFailed(rule_name.clone(), e)
+                    );
+                    continue;
+                }
+            };
+            incr_rule_counter(&rule_name, RULE_STATUS_PASSED);
+
+            for action in compiled.rule.actions.iter() {
+                if let MqttRuleAction::Drop = action {
+                    is_drop = true;
+                    incr_rule_counter(&rule_name, RULE_STATUS_ACTION_SUCCESS);
+                    continue;
+                }
+
+                let engine = self.clone();
+                let action = action.clone();
+                let rule_name = rule_name.clone();
+                let client_id = client_id.to_owned();
+                let topic_name = topic_name.to_owned();
+                let context = context.clone();
+                let output = output.clone();
+                tokio::spawn(async move {
+                    match engine
+                        .exec_action(&action, &client_id, &topic_name, &context, &output)
+                        .await
+                    {
+                        Ok(()) => incr_rule_counter(&rule_name, RULE_STATUS_ACTION_SUCCESS),
+                        Err(e) => {
+                            incr_rule_counter(&rule_name, RULE_STATUS_ACTION_FAILED);
+                            warn!(
+                                "Rule {} failed to execute action {:?}, error message: {}",
+                                rule_name, action, e
+                            );
+                        }
+                    }
+                });
+            }
+        }
+        is_drop
+    }
+
+    async fn exec_action(
+        &self,
+        action: &MqttRuleAction,
+        client_id: &str,
+        topic_name: &str,
+        context: &Value,
+        output: &Value,
+    ) -> Result<(), MqttBrokerError> {
+        let payload = Bytes::from(serde_json::to_vec(output)?);
+        match action {
+            MqttRuleAction::Republish {
+                topic,
+                qos: target_qos,
+                retain,
+            } => {
+                let target_topic = republish_topic_name(
+                    topic_name,
+                    &render_topic_template(topic, output, context)?,
+                )?;
+                topic_name_validator(&target_topic)?;
+                if is_wildcards(&target_topic) {
+                    return Err(MqttBrokerError::TopicNameIncorrectlyFormatted(target_topic));
+                }
+                let target_qos = qos(*target_qos).ok_or_else(|| {
+                    MqttBrokerError::CommonError(format!("Invalid republish qos {}", target_qos))
+                })?;
+
+                let topic = try_init_topic(
+                    &target_topic,
+                    &self.cache_manager,
+                    &self.message_storage_adapter,
+                    &self.client_pool,
+                )
+                .await?;
+
+                let publish = Publish {
+                    dup: false,
+                    qos: target_qos,
+                    pkid: 0,
+                    retain: *retain,
+                    topic: Bytes::from(target_topic),
+                    payload,
+                };
+                save_message(
+                    &self.message_storage_adapter,
+                    &self.delay_message_manager,
+                    &self.cache_manager,
+                    &publish,
+                    &None,
+                    &self.subscribe_manager,
+                    client_id,
+                    &topic,
+                    &None,
+                )
+                .await?;
+            }
+            MqttRuleAction::Connector { connector_name } => {
+                if self
+                    .connector_manager
+                    .get_connector(connector_name)
+                    .is_none()
+                {
+                    return Err(MqttBrokerError::CommonError(format!(
+                        "Connector {} does not exist",
+                        connector_name
+                    )));
+                }
+
+                let publish = Publish {
+                    topic: Bytes::from(topic_name.to_owned()),
+                    payload,
+                    ..Default::default()
+                };
+                let message_expire = build_message_expire(&self.cache_manager, &None);
+                let record = MqttMessage::build_record(client_id, &publish, &None, message_expire)
+                    .ok_or(MqttBrokerError::FailedToBuildMessage)?;
+
+                // Written to the shard of the connector, the topic it reads belongs to subscribers
+                let shard_name = try_init_connector_rule_shard(
+                    &self.message_storage_adapter,
+                    &self.connector_manager,
+                    connector_name,
+                )
+                .await?;
+                let message_storage = MessageStorage::new(self.message_storage_adapter.clone());
+                message_storage
+                    .append_topic_message(&shard_name, vec![record])
+                    .await?;
+            }
+            MqttRuleAction::Drop => {}
+        }
+        Ok(())
+    }
+}
+
+// The fields a rule can reference: payload is decoded as JSON when possible, otherwise kept as a string
+pub fn build_rule_context(
+    client_id: &str,
+    username: &str,
+    topic_name: &str,
+    publish: &Publish,
+    publish_properties: &Option<PublishProperties>,
+) -> Value {
+    let payload = match serde_json::from_slice::<Value>(&publish.payload) {
+        Ok(value) => value,
+        Err(_) => Value::String(String::from_utf8_lossy(&publish.payload).to_string()),
+    };
+
+    let mut user_properties = Map::new();
+    if let Some(properties) = publish_properties {
+        for (key, value) in properties.user_properties.iter() {
+            user_properties.insert(key.clone(), Value::String(value.clone()));
+        }
+    }
+
+    json!({
+        "payload": payload,
+        "clientid": client_id,
+        "username": username,
+        "topic": topic_name,
+        "qos": publish.qos as u8,
+        "retain": publish.retain,
+        "timestamp": now_mills() as u64,
+        "node": broker_mqtt_conf().broker_id,
+        "user_properties": user_properties,
+    })
+}
+
+// Returns None when the WHERE condition does not hold, otherwise the selected fields
+pub fn evaluate_rule(sql: &RuleSql, context: &Value) -> Result<Option<Value>, String> {
+    if let Some(condition) = &sql.condition {
+        if !is_truthy(&condition.eval(context)?) {
+            return Ok(None);
+        }
+    }
+
+    let Some(fields) = &sql.fields else {
+        return Ok(Some(context.clone()));
+    };
+
+    let mut output = Map::new();
+    for field in fields.iter() {
+        output.insert(field.alias.clone(), field.expr.eval(context)?);
+    }
+    Ok(Some(Value::Object(output)))
+}
+
+// Replace ${field} placeholders, selected fields take precedence over the message context
+pub fn render_topic_template(
+    template: &str,
+    output: &Value,
+    context: &Value,
+) -> Result<String, MqttBrokerError> {
+    let rendered = TOPIC_TEMPLATE_REGEX.replace_all(template, |caps: &Captures| {
+        let path: Vec<&str> = caps[1].split('.').collect();
+        let value = lookup(output, &path)
+            .or_else(|| lookup(context, &path))
+            .unwrap_or(Value::Null);
+        match value {
+            Value::Null => String::new(),
+            Value::String(s) => s,
+            other => other.to_string(),
+        }
+    });
+    Ok(rendered.to_string())
+}
+
+// Messages of a tenant are republished inside the namespace of the same tenant
+pub fn republish_topic_name(
+    source_topic: &str,
+    target_topic: &str,
+) -> Result<String, MqttBrokerError> {
+    let Some(tenant) = topic_tenant(source_topic) else {
+        return Ok(target_topic.to_owned());
+    };
+    if topic_tenant(target_topic) == Some(tenant) {
+        return Ok(target_topic.to_owned());
+    }
+    tenant_topic_name(tenant, target_topic)
+}
+
+fn lookup(value: &Value, path: &[&str]) -> Option<Value> {
+    let mut current = value;
+    for key in path {
+        current = current.get(key)?;
+    }
+    Some(current.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use protocol::mqtt::common::{Publish, PublishProperties, QoS};
+    use serde_json::json;
+
+    use super::{build_rule_context, evaluate_rule, render_topic_template, republish_topic_name};
+    use crate::rule::sql::parse_rule_sql;
+
+    #[test]
+    fn evaluate_rule_test() {
+        let publish = Publish {
+            qos: QoS::AtLeastOnce,
+            topic: Bytes::from("sensors/1/data"),
+            payload: Bytes::from(r#"{"temp": 85, "hum": 40}"#),
+            ..Default::default()
+        };
+        let properties = Some(PublishProperties {
+            user_properties: vec![("site".to_string(), "sh".to_string())],
+            ..Default::default()
+        });
+        let context = build_rule_context("c1", "u1", "sensors/1/data", &publish, &properties);
+
+        let sql = parse_rule_sql(
+            r#"SELECT payload.temp AS t, clientid, user_properties.site AS site FROM "sensors/+/data" WHERE payload.temp > 80"#,
+        )
+        .unwrap();
+        let output = evaluate_rule(&sql, &context).unwrap().unwrap();
+        assert_eq!(output, json!({"t": 85, "clientid": "c1", "site": "sh"}));
+
+        let sql = parse_rule_sql(r#"SELECT * FROM "sensors/#" WHERE payload.hum > 50"#).unwrap();
+        assert!(evaluate_rule(&sql, &context).unwrap().is_none());
+
+        let sql = parse_rule_sql(r#"SELECT * FROM "sensors/#" WHERE qos = 1"#).unwrap();
+        let output = evaluate_rule(&sql, &context).unwrap().unwrap();
+        assert_eq!(output["payload"]["hum"], json!(40));
+
+        // Non JSON payloads are exposed as strings
+        let publish = Publish {
+            payload: Bytes::from("on"),
+            ..Default::default()
+        };
+        let context = build_rule_context("c1", "u1", "t", &publish, &None);
+        let sql =
+            parse_rule_sql(r#"SELECT payload AS state FROM "t" WHERE payload = 'on'"#).unwrap();
+        let output = evaluate_rule(&sql, &context).unwrap().unwrap();
+        assert_eq!(output, json!({"state": "on"}));
+    }
+
+    #[test]
+    fn render_topic_template_test() {
+        let output = json!({"t": 85});
+        let context = json!({"clientid": "c1", "payload": {"id": 7}});
+        assert_eq!(
+            render_topic_template("alerts/${clientid}/${payload.id}/${t}", &output, &context)
+                .unwrap(),
+            "alerts/c1/7/85"
+        );
+        assert_eq!(
+            render_topic_template("alerts/${missing}", &output, &context).unwrap(),
+            "alerts/"
+        );
+    }
+
+    #[test]
+    fn republish_topic_name_test() {
+        assert_eq!(
+            republish_topic_name("/a/b", "alerts/1").unwrap(),
+            "alerts/1"
+        );
+        assert_eq!(
+            republish_topic_name("/_tenant/acme/a/b", "alerts/1").unwrap(),
+            "/_tenant/acme/alerts/1"
+        );
+        assert_eq!(
+            republish_topic_name("/_tenant/acme/a/b", "/_tenant/acme/a/b/out").unwrap(),
+            "/_tenant/acme/a/b/out"
+        );
+        assert!(republish_topic_name("/_tenant/acme/a/b", "/_tenant/other/a").is_err());
+    }
+}
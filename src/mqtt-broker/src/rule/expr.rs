@@ -0,0 +1,269 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::cmp::Ordering;
+
+use serde_json::{Number, Value};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    NotEq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    And,
+    Or,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    // payload.temp, payload.list[0], clientid
+    Path(Vec<PathSegment>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self, context: &Value) -> Result<Value, String> {
+        match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Path(segments) => Ok(resolve_path(context, segments)),
+            Expr::Not(expr) => Ok(Value::Bool(!is_truthy(&expr.eval(context)?))),
+            Expr::Neg(expr) => match as_f64(&expr.eval(context)?) {
+                Some(num) => Ok(number_value(-num)),
+                None => Err("operand of '-' is not a number".to_string()),
+            },
+            Expr::Binary(left, op, right) => {
+                // AND / OR short-circuit, the right side is only evaluated when needed
+                match op {
+                    BinaryOp::And => {
+                        if !is_truthy(&left.eval(context)?) {
+                            return Ok(Value::Bool(false));
+                        }
+                        return Ok(Value::Bool(is_truthy(&right.eval(context)?)));
+                    }
+                    BinaryOp::Or => {
+                        if is_truthy(&left.eval(context)?) {
+                            return Ok(Value::Bool(true));
+                        }
+                        return Ok(Value::Bool(is_truthy(&right.eval(context)?)));
+                    }
+                    _ => {}
+                }
+
+                let left = left.eval(context)?;
+                let right = right.eval(context)?;
+                eval_binary(&left, op, &right)
+            }
+        }
+    }
+}
+
+fn resolve_path(context: &Value, segments: &[PathSegment]) -> Value {
+    let mut current = context;
+    for segment in segments {
+        let next = match (segment, current) {
+            (PathSegment::Key(key), Value::Object(map)) => map.get(key),
+            (PathSegment::Index(index), Value::Array(list)) => list.get(*index),
+            _ => None,
+        };
+        match next {
+            Some(value) => current = value,
+            None => return Value::Null,
+        }
+    }
+    current.clone()
+}
+
+fn eval_binary(left: &Value, op: &BinaryOp, right: &Value) -> Result<Value, String> {
+    match op {
+        BinaryOp::Eq => Ok(Value::Bool(
+            compare_values(left, right) == Some(Ordering::Equal),
+        )),
+        BinaryOp::NotEq => Ok(Value::Bool(
+            compare_values(left, right) != Some(Ordering::Equal),
+        )),
+        BinaryOp::Gt => Ok(Value::Bool(
+            compare_values(left, right) == Some(Ordering::Greater),
+        )),
+        BinaryOp::Gte => Ok(Value::Bool(matches!(
+            compare_values(left, right),
+            Some(Ordering::Greater) | Some(Ordering::Equal)
+        ))),
+        BinaryOp::Lt => Ok(Value::Bool(
+            compare_values(left, right) == Some(Ordering::Less),
+        )),
+        BinaryOp::Lte => Ok(Value::Bool(matches!(
+            compare_values(left, right),
+            Some(Ordering::Less) | Some(Ordering::Equal)
+        ))),
+        BinaryOp::Add => {
+            if let (Value::String(l), Value::String(r)) = (left, right) {
+                return Ok(Value::String(format!("{}{}", l, r)));
+            }
+            arithmetic(left, right, "+", |l, r| Ok(l + r))
+        }
+        BinaryOp::Sub => arithmetic(left, right, "-", |l, r| Ok(l - r)),
+        BinaryOp::Mul => arithmetic(left, right, "*", |l, r| Ok(l * r)),
+        BinaryOp::Div => arithmetic(left, right, "/", |l, r| {
+            if r == 0.0 {
+                return Err("division by zero".to_string());
+            }
+            Ok(l / r)
+        }),
+        BinaryOp::Mod => arithmetic(left, right, "%", |l, r| {
+            if r == 0.0 {
+                return Err("division by zero".to_string());
+            }
+            Ok(l % r)
+        }),
+        BinaryOp::And | BinaryOp::Or => unreachable!("logical operators are short-circuited"),
+    }
+}
+
+fn arithmetic<F>(left: &Value, right: &Value, op: &str, f: F) -> Result<Value, String>
+where
+    F: Fn(f64, f64) -> Result<f64, String>,
+{
+    match (as_f64(left), as_f64(right)) {
+        (Some(l), Some(r)) => Ok(number_value(f(l, r)?)),
+        _ => Err(format!(
+            "operands of '{}' are not numbers: {}, {}",
+            op, left, right
+        )),
+    }
+}
+
+// Numbers and numeric strings compare by value, strings compare lexicographically.
+// Values of different kinds are not comparable, so every comparison except != is false.
+fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        (Value::Number(_), Value::Number(_))
+        | (Value::Number(_), Value::String(_))
+        | (Value::String(_), Value::Number(_)) => as_f64(left)?.partial_cmp(&as_f64(right)?),
+        (Value::Array(_), Value::Array(_)) | (Value::Object(_), Value::Object(_)) => {
+            if left == right {
+                Some(Ordering::Equal)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(num) => num.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+// Keep integral results as integers so that 1 + 1 renders as 2 rather than 2.0
+pub fn number_value(num: f64) -> Value {
+    if num.fract() == 0.0 && num.abs() < i64::MAX as f64 {
+        return Value::Number(Number::from(num as i64));
+    }
+    Number::from_f64(num).map_or(Value::Null, Value::Number)
+}
+
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(num) => num.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(list) => !list.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{BinaryOp, Expr, PathSegment};
+
+    fn path(keys: &[&str]) -> Expr {
+        Expr::Path(
+            keys.iter()
+                .map(|k| PathSegment::Key(k.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn expr_eval_test() {
+        let context = json!({"payload": {"temp": 81.5, "tags": ["a", "b"]}, "clientid": "c1"});
+
+        let expr = Expr::Binary(
+            Box::new(path(&["payload", "temp"])),
+            BinaryOp::Gt,
+            Box::new(Expr::Literal(json!(80))),
+        );
+        assert_eq!(expr.eval(&context).unwrap(), json!(true));
+
+        let expr = Expr::Path(vec![
+            PathSegment::Key("payload".to_string()),
+            PathSegment::Key("tags".to_string()),
+            PathSegment::Index(1),
+        ]);
+        assert_eq!(expr.eval(&context).unwrap(), json!("b"));
+
+        assert_eq!(
+            path(&["payload", "none"]).eval(&context).unwrap(),
+            json!(null)
+        );
+
+        let expr = Expr::Binary(
+            Box::new(Expr::Literal(json!(3))),
+            BinaryOp::Mul,
+            Box::new(Expr::Literal(json!("2"))),
+        );
+        assert_eq!(expr.eval(&context).unwrap(), json!(6));
+
+        let expr = Expr::Binary(
+            Box::new(Expr::Literal(json!(1))),
+            BinaryOp::Div,
+            Box::new(Expr::Literal(json!(0))),
+        );
+        assert!(expr.eval(&context).is_err());
+
+        // A missing field is never greater than anything
+        let expr = Expr::Binary(
+            Box::new(path(&["payload", "none"])),
+            BinaryOp::Gt,
+            Box::new(Expr::Literal(json!(0))),
+        );
+        assert_eq!(expr.eval(&context).unwrap(), json!(false));
+    }
+}
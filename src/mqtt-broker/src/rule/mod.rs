@@ -0,0 +1,118 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::{Arc, LazyLock};
+
+use metadata_struct::mqtt::rule::MqttRule;
+use regex::Regex;
+use tracing::error;
+
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::rule::sql::{parse_rule_sql, RuleSql};
+use crate::subscribe::common::TopicFilterMatcher;
+
+pub mod engine;
+pub mod expr;
+pub mod sql;
+
+static RULE_NAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_-]{1,64}$").unwrap());
+
+// A rule together with its parsed SQL and topic filters, so they are only compiled when the rule changes
+#[derive(Clone, Debug)]
+pub struct CompiledRule {
+    pub rule: MqttRule,
+    pub sql: RuleSql,
+    pub matchers: Vec<TopicFilterMatcher>,
+}
+
+impl CompiledRule {
+    pub fn new(rule: MqttRule) -> Result<Self, MqttBrokerError> {
+        let sql = parse_rule_sql(&rule.sql)?;
+        let matchers = sql
+            .topic_filters
+            .iter()
+            .map(|filter| TopicFilterMatcher::new(filter))
+            .collect();
+        Ok(CompiledRule {
+            rule,
+            sql,
+            matchers,
+        })
+    }
+
+    pub fn is_match(&self, topic_name: &str) -> bool {
+        self.matchers
+            .iter()
+            .any(|matcher| matcher.is_match(topic_name))
+    }
+}
+
+impl CacheManager {
+    pub fn add_rule(&self, rule: MqttRule) -> Result<(), MqttBrokerError> {
+        let compiled = CompiledRule::new(rule)?;
+        self.rule_info
+            .insert(compiled.rule.rule_name.clone(), Arc::new(compiled));
+        Ok(())
+    }
+
+    pub fn remove_rule(&self, rule_name: &str) {
+        self.rule_info.remove(rule_name);
+    }
+
+    pub fn get_rule(&self, rule_name: &str) -> Option<MqttRule> {
+        if let Some(compiled) = self.rule_info.get(rule_name) {
+            return Some(compiled.rule.clone());
+        }
+        None
+    }
+
+    pub fn get_all_rule(&self) -> Vec<MqttRule> {
+        self.rule_info
+            .iter()
+            .map(|entry| entry.value().rule.clone())
+            .collect()
+    }
+
+    pub fn get_enable_compiled_rules(&self) -> Vec<Arc<CompiledRule>> {
+        self.rule_info
+            .iter()
+            .filter(|entry| entry.value().rule.enable)
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    pub fn set_rules(&self, rules: Vec<MqttRule>) {
+        self.rule_info.clear();
+        for rule in rules {
+            let rule_name = rule.rule_name.clone();
+            if let Err(e) = self.add_rule(rule) {
+                error!(
+                    "Rule {} was skipped because its SQL failed to parse, error message: {}",
+                    rule_name, e
+                );
+            }
+        }
+    }
+}
+
+pub fn rule_name_validator(rule_name: &str) -> Result<(), MqttBrokerError> {
+    if !RULE_NAME_REGEX.is_match(rule_name) {
+        return Err(MqttBrokerError::CommonError(format!(
+            "Rule name {} is invalid, only letters, digits, '_' and '-' are allowed",
+            rule_name
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,507 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use serde_json::Value;
+
+use super::expr::{number_value, BinaryOp, Expr, PathSegment};
+use crate::handler::error::MqttBrokerError;
+use crate::subscribe::common::{build_sub_path_regex, is_wildcards, sub_path_validator};
+
+/// A parsed rule statement:
+/// SELECT <* | expr [AS alias], ...> FROM "<topic filter>"[, ...] [WHERE <expr>]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleSql {
+    // None means SELECT *
+    pub fields: Option<Vec<SelectField>>,
+    pub topic_filters: Vec<String>,
+    pub condition: Option<Expr>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectField {
+    pub expr: Expr,
+    pub alias: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    // 'literal'
+    Str(String),
+    // "topic/filter"
+    QuotedStr(String),
+    Symbol(&'static str),
+}
+
+pub fn parse_rule_sql(sql: &str) -> Result<RuleSql, MqttBrokerError> {
+    let tokens = tokenize(sql)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let rule_sql = parser.parse_select()?;
+    if let Some(token) = parser.peek() {
+        return Err(MqttBrokerError::RuleSqlInvalid(format!(
+            "unexpected token {:?} at the end of the statement",
+            token
+        )));
+    }
+
+    for filter in rule_sql.topic_filters.iter() {
+        sub_path_validator(filter).map_err(|e| MqttBrokerError::RuleSqlInvalid(e.to_string()))?;
+        if is_wildcards(filter) {
+            build_sub_path_regex(filter)
+                .map_err(|e| MqttBrokerError::RuleSqlInvalid(e.to_string()))?;
+        }
+    }
+    Ok(rule_sql)
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>, MqttBrokerError> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '\'' || c == '"' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                if i >= chars.len() {
+                    return Err(MqttBrokerError::RuleSqlInvalid(
+                        "unterminated string literal".to_string(),
+                    ));
+                }
+                // A doubled quote inside a string is an escaped quote
+                if chars[i] == c {
+                    if i + 1 < chars.len() && chars[i + 1] == c {
+                        value.push(c);
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    break;
+                }
+                value.push(chars[i]);
+                i += 1;
+            }
+            tokens.push(if c == '"' {
+                Token::QuotedStr(value)
+            } else {
+                Token::Str(value)
+            });
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let num = text
+                .parse::<f64>()
+                .map_err(|_| MqttBrokerError::RuleSqlInvalid(format!("invalid number {}", text)))?;
+            tokens.push(Token::Number(num));
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+        let symbol = match two.as_str() {
+            ">=" => Some(">="),
+            "<=" => Some("<="),
+            "!=" => Some("!="),
+            "<>" => Some("!="),
+            _ => None,
+        };
+        if let Some(symbol) = symbol {
+            tokens.push(Token::Symbol(symbol));
+            i += 2;
+            continue;
+        }
+
+        let symbol = match c {
+            '=' => "=",
+            '>' => ">",
+            '<' => "<",
+            '+' => "+",
+            '-' => "-",
+            '*' => "*",
+            '/' => "/",
+            '%' => "%",
+            '(' => "(",
+            ')' => ")",
+            '[' => "[",
+            ']' => "]",
+            ',' => ",",
+            '.' => ".",
+            _ => {
+                return Err(MqttBrokerError::RuleSqlInvalid(format!(
+                    "unexpected character '{}'",
+                    c
+                )))
+            }
+        };
+        tokens.push(Token::Symbol(symbol));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), MqttBrokerError> {
+        if !self.eat_keyword(keyword) {
+            return Err(MqttBrokerError::RuleSqlInvalid(format!(
+                "expected {}, found {:?}",
+                keyword,
+                self.peek()
+            )));
+        }
+        Ok(())
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), MqttBrokerError> {
+        if !self.eat_symbol(symbol) {
+            return Err(MqttBrokerError::RuleSqlInvalid(format!(
+                "expected '{}', found {:?}",
+                symbol,
+                self.peek()
+            )));
+        }
+        Ok(())
+    }
+
+    fn parse_select(&mut self) -> Result<RuleSql, MqttBrokerError> {
+        self.expect_keyword("SELECT")?;
+
+        let fields = if self.eat_symbol("*") {
+            None
+        } else {
+            let mut fields = vec![self.parse_field()?];
+            while self.eat_symbol(",") {
+                fields.push(self.parse_field()?);
+            }
+            Some(fields)
+        };
+
+        self.expect_keyword("FROM")?;
+        let mut topic_filters = vec![self.parse_topic_filter()?];
+        while self.eat_symbol(",") {
+            topic_filters.push(self.parse_topic_filter()?);
+        }
+
+        let condition = if self.eat_keyword("WHERE") {
+            Some(self.parse_or()?)
+        } else {
+            None
+        };
+
+        Ok(RuleSql {
+            fields,
+            topic_filters,
+            condition,
+        })
+    }
+
+    fn parse_field(&mut self) -> Result<SelectField, MqttBrokerError> {
+        let expr = self.parse_or()?;
+        let alias = if self.eat_keyword("AS") {
+            match self.next() {
+                Some(Token::Ident(alias)) => alias,
+                token => {
+                    return Err(MqttBrokerError::RuleSqlInvalid(format!(
+                        "expected an alias after AS, found {:?}",
+                        token
+                    )))
+                }
+            }
+        } else {
+            // Without an alias a path is named after its last key, payload.temp -> temp
+            match &expr {
+                Expr::Path(segments) => match segments.iter().rev().find_map(|s| match s {
+                    PathSegment::Key(key) => Some(key.clone()),
+                    PathSegment::Index(_) => None,
+                }) {
+                    Some(key) => key,
+                    None => {
+                        return Err(MqttBrokerError::RuleSqlInvalid(
+                            "selected field requires an alias".to_string(),
+                        ))
+                    }
+                },
+                _ => {
+                    return Err(MqttBrokerError::RuleSqlInvalid(
+                        "selected expression requires an alias, use <expr> AS <name>".to_string(),
+                    ))
+                }
+            }
+        };
+        Ok(SelectField { expr, alias })
+    }
+
+    fn parse_topic_filter(&mut self) -> Result<String, MqttBrokerError> {
+        match self.next() {
+            Some(Token::QuotedStr(filter)) => Ok(filter),
+            token => Err(MqttBrokerError::RuleSqlInvalid(format!(
+                "expected a double quoted topic filter after FROM, found {:?}",
+                token
+            ))),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, MqttBrokerError> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            let right = self.parse_and()?;
+            left = Expr::Binary(Box::new(left), BinaryOp::Or, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, MqttBrokerError> {
+        let mut left = self.parse_not()?;
+        while self.eat_keyword("AND") {
+            let right = self.parse_not()?;
+            left = Expr::Binary(Box::new(left), BinaryOp::And, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, MqttBrokerError> {
+        if self.eat_keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, MqttBrokerError> {
+        let left = self.parse_additive()?;
+        let op = match self.peek() {
+            Some(Token::Symbol("=")) => BinaryOp::Eq,
+            Some(Token::Symbol("!=")) => BinaryOp::NotEq,
+            Some(Token::Symbol(">")) => BinaryOp::Gt,
+            Some(Token::Symbol(">=")) => BinaryOp::Gte,
+            Some(Token::Symbol("<")) => BinaryOp::Lt,
+            Some(Token::Symbol("<=")) => BinaryOp::Lte,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let right = self.parse_additive()?;
+        Ok(Expr::Binary(Box::new(left), op, Box::new(right)))
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, MqttBrokerError> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol("+")) => BinaryOp::Add,
+                Some(Token::Symbol("-")) => BinaryOp::Sub,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_multiplicative()?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, MqttBrokerError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol("*")) => BinaryOp::Mul,
+                Some(Token::Symbol("/")) => BinaryOp::Div,
+                Some(Token::Symbol("%")) => BinaryOp::Mod,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, MqttBrokerError> {
+        if self.eat_symbol("-") {
+            return Ok(Expr::Neg(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, MqttBrokerError> {
+        match self.next() {
+            Some(Token::Number(num)) => Ok(Expr::Literal(number_value(num))),
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::Symbol("(")) => {
+                let expr = self.parse_or()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(ident)) => {
+                if ident.eq_ignore_ascii_case("true") {
+                    return Ok(Expr::Literal(Value::Bool(true)));
+                }
+                if ident.eq_ignore_ascii_case("false") {
+                    return Ok(Expr::Literal(Value::Bool(false)));
+                }
+                if ident.eq_ignore_ascii_case("null") {
+                    return Ok(Expr::Literal(Value::Null));
+                }
+                self.parse_path(ident)
+            }
+            token => Err(MqttBrokerError::RuleSqlInvalid(format!(
+                "unexpected token {:?} in expression",
+                token
+            ))),
+        }
+    }
+
+    fn parse_path(&mut self, first: String) -> Result<Expr, MqttBrokerError> {
+        let mut segments = vec![PathSegment::Key(first)];
+        loop {
+            if self.eat_symbol(".") {
+                match self.next() {
+                    Some(Token::Ident(key)) => segments.push(PathSegment::Key(key)),
+                    token => {
+                        return Err(MqttBrokerError::RuleSqlInvalid(format!(
+                            "expected a field name after '.', found {:?}",
+                            token
+                        )))
+                    }
+                }
+            } else if self.eat_symbol("[") {
+                match self.next() {
+                    Some(Token::Number(num)) if num >= 0.0 && num.fract() == 0.0 => {
+                        segments.push(PathSegment::Index(num as usize))
+                    }
+                    token => {
+                        return Err(MqttBrokerError::RuleSqlInvalid(format!(
+                            "expected an array index, found {:?}",
+                            token
+                        )))
+                    }
+                }
+                self.expect_symbol("]")?;
+            } else {
+                return Ok(Expr::Path(segments));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse_rule_sql;
+
+    #[test]
+    fn parse_rule_sql_test() {
+        let sql = parse_rule_sql(
+            r#"SELECT payload.temp AS t, clientid FROM "sensors/+/data" WHERE payload.temp > 80"#,
+        )
+        .unwrap();
+        assert_eq!(sql.topic_filters, vec!["sensors/+/data".to_string()]);
+        let fields = sql.fields.unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].alias, "t");
+        assert_eq!(fields[1].alias, "clientid");
+
+        let context = json!({"payload": {"temp": 81}, "clientid": "c1"});
+        let condition = sql.condition.unwrap();
+        assert_eq!(condition.eval(&context).unwrap(), json!(true));
+        let context = json!({"payload": {"temp": 79}, "clientid": "c1"});
+        assert_eq!(condition.eval(&context).unwrap(), json!(false));
+
+        let sql = parse_rule_sql(r#"select * from "a/#", "b/c""#).unwrap();
+        assert!(sql.fields.is_none());
+        assert_eq!(sql.topic_filters.len(), 2);
+        assert!(sql.condition.is_none());
+    }
+
+    #[test]
+    fn parse_rule_sql_condition_test() {
+        let sql = parse_rule_sql(
+            r#"SELECT payload.v * 2 AS double FROM "t/#" WHERE (qos = 1 OR username = 'it''s') AND NOT retain AND payload.list[1] <> 'x'"#,
+        )
+        .unwrap();
+        let condition = sql.condition.unwrap();
+        let context = json!({"qos": 1, "username": "u", "retain": false, "payload": {"v": 2, "list": ["x", "y"]}});
+        assert_eq!(condition.eval(&context).unwrap(), json!(true));
+        let context =
+            json!({"qos": 0, "username": "it's", "retain": true, "payload": {"list": []}});
+        assert_eq!(condition.eval(&context).unwrap(), json!(false));
+
+        let fields = sql.fields.unwrap();
+        assert_eq!(
+            fields[0].expr.eval(&json!({"payload": {"v": 2}})).unwrap(),
+            json!(4)
+        );
+    }
+
+    #[test]
+    fn parse_rule_sql_error_test() {
+        assert!(parse_rule_sql("").is_err());
+        assert!(parse_rule_sql(r#"SELECT * FROM 'a/b'"#).is_err());
+        assert!(parse_rule_sql(r#"SELECT payload.a + 1 FROM "a/b""#).is_err());
+        assert!(parse_rule_sql(r#"SELECT * FROM "a/#/b""#).is_err());
+        assert!(parse_rule_sql(r#"SELECT * FROM "a/b" WHERE"#).is_err());
+        assert!(parse_rule_sql(r#"SELECT * FROM "a/b" WHERE qos = 1 extra"#).is_err());
+        assert!(parse_rule_sql(r#"SELECT * FROM "a/b" WHERE name = 'abc"#).is_err());
+    }
+}
@@ -26,6 +26,8 @@
 
 use crate::handler::cache::CacheManager;
 use crate::handler::constant::WILDCARD_RESOURCE;
+use crate::handler::request_response::is_response_topic_allowed;
+use crate::handler::tenant::strip_tenant_namespace;
 
 pub fn is_allow_acl(
     cache_manager: &Arc<CacheManager>,
@@ -35,6 +37,13 @@ pub fn is_allow_acl(
     retain: bool,
     _: QoS,
 ) -> bool {
+    // response topics are private to the client they were assigned to
+    if action == MqttAclAction::Subscribe
+        && !is_response_topic_allowed(&connection.client_id, &strip_tenant_namespace(topic_name))
+    {
+        return false;
+    }
+
     // check super user
     if is_super_user(cache_manager, &connection.login_user) {
         return true;
@@ -246,6 +255,7 @@ pub async fn check_super_user_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            tenant: String::new(),
         };
         cache_manager.add_user(user.clone());
 
@@ -261,6 +271,7 @@ pub async fn check_super_user_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: false,
+            tenant: String::new(),
         };
         cache_manager.add_user(user.clone());
         assert!(!is_super_user(&cache_manager, &user.username));
@@ -275,6 +286,7 @@ pub async fn check_black_list_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            tenant: String::new(),
         };
 
         cache_manager.add_user(user.clone());
@@ -284,6 +296,7 @@ pub async fn check_black_list_test() {
             receive_maximum: 3,
             max_packet_size: 3,
             topic_alias_max: 3,
+            client_topic_alias_max: 0,
             request_problem_info: 1,
             keep_alive: 2,
             source_ip_addr: local_hostname(),
@@ -362,6 +375,7 @@ pub async fn check_empty_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            tenant: String::new(),
         };
 
         cache_manager.add_user(user.clone());
@@ -371,6 +385,7 @@ pub async fn check_empty_acl_test() {
             receive_maximum: 3,
             max_packet_size: 3,
             topic_alias_max: 3,
+            client_topic_alias_max: 0,
             request_problem_info: 1,
             keep_alive: 2,
             source_ip_addr: local_hostname(),
@@ -403,6 +418,7 @@ pub async fn check_user_wildcard_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            tenant: String::new(),
         };
 
         cache_manager.add_user(user.clone());
@@ -412,6 +428,7 @@ pub async fn check_user_wildcard_acl_test() {
             receive_maximum: 3,
             max_packet_size: 3,
             topic_alias_max: 3,
+            client_topic_alias_max: 0,
             request_problem_info: 1,
             keep_alive: 2,
             source_ip_addr: local_hostname(),
@@ -469,6 +486,7 @@ pub async fn check_user_match_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            tenant: String::new(),
         };
 
         cache_manager.add_user(user.clone());
@@ -478,6 +496,7 @@ pub async fn check_user_match_acl_test() {
             receive_maximum: 3,
             max_packet_size: 3,
             topic_alias_max: 3,
+            client_topic_alias_max: 0,
             request_problem_info: 1,
             keep_alive: 2,
             source_ip_addr: local_hostname(),
@@ -535,6 +554,7 @@ pub async fn check_client_id_wildcard_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            tenant: String::new(),
         };
 
         cache_manager.add_user(user.clone());
@@ -544,6 +564,7 @@ pub async fn check_client_id_wildcard_acl_test() {
             receive_maximum: 3,
             max_packet_size: 3,
             topic_alias_max: 3,
+            client_topic_alias_max: 0,
             request_problem_info: 1,
             keep_alive: 2,
             source_ip_addr: local_hostname(),
@@ -601,6 +622,7 @@ pub async fn check_client_id_match_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            tenant: String::new(),
         };
 
         cache_manager.add_user(user.clone());
@@ -610,6 +632,7 @@ pub async fn check_client_id_match_acl_test() {
             receive_maximum: 3,
             max_packet_size: 3,
             topic_alias_max: 3,
+            client_topic_alias_max: 0,
             request_problem_info: 1,
             keep_alive: 2,
             source_ip_addr: local_hostname(),
@@ -76,6 +76,7 @@ pub async fn plaintext_test() {
             username: username.clone(),
             password: password.clone(),
             is_superuser: true,
+            tenant: String::new(),
         };
         cache_manager.add_user(user);
 
@@ -37,6 +37,7 @@
 
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::handler::request_response::is_response_topic_allowed;
 use crate::security::acl::auth::is_blacklist;
 use crate::subscribe::common::get_sub_topic_id_list;
 
@@ -236,6 +237,9 @@ pub async fn allow_subscribe(
         subscribe: &Subscribe,
     ) -> bool {
         for filter in subscribe.filters.iter() {
+            if !is_response_topic_allowed(&connection.client_id, &filter.path) {
+                return false;
+            }
             let topic_list = get_sub_topic_id_list(&self.cache_manager, &filter.path).await;
             for topic in topic_list {
                 if !is_allow_acl(
@@ -66,6 +66,7 @@ async fn read_all_user(&self) -> Result<DashMap<String, MqttUser>, MqttBrokerErr
                 username: raw.0.clone(),
                 password: raw.1.clone(),
                 is_superuser: raw.3 == 1,
+                tenant: String::new(),
             };
             results.insert(raw.0.clone(), user);
         }
@@ -129,6 +130,7 @@ async fn get_user(&self, username: String) -> Result<Option<MqttUser>, MqttBroke
                 username: value.0.clone(),
                 password: value.1.clone(),
                 is_superuser: value.3 == 1,
+                tenant: String::new(),
             }));
         }
         return Ok(None);
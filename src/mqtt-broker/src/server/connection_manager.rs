@@ -20,7 +20,8 @@
 use futures::stream::SplitSink;
 use futures::SinkExt;
 use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
-use protocol::mqtt::common::MqttProtocol;
+use protocol::mqtt::common::{MqttPacket, MqttProtocol};
+use tokio::sync::oneshot;
 use tokio::time::sleep;
 use tokio_util::codec::FramedWrite;
 use tracing::{debug, info};
@@ -29,6 +30,7 @@
 use crate::common::tool::is_ignore_print;
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::observability::live_trace::{trace_packet, TraceDirection};
 use crate::observability::metrics::packets::record_sent_metrics;
 use crate::server::quic::quic_stream_wrapper::QuicFramedWriteStream;
 
@@ -45,6 +47,10 @@ pub struct ConnectionManager {
     >,
     pub websocket_write_list: DashMap<u64, SplitSink<WebSocket, Message>>,
     pub quic_write_list: DashMap<u64, QuicFramedWriteStream>,
+    // ((connection_id, pkid), sender) notified once the SUBACK has been written
+    suback_waiters: DashMap<(u64, u16), oneshot::Sender<()>>,
+    // (connection_id, sender) notified once the CONNACK has been written
+    connack_waiters: DashMap<u64, oneshot::Sender<()>>,
     cache_manager: Arc<CacheManager>,
 }
 
@@ -62,6 +68,57 @@ pub fn new(cache_manager: Arc<CacheManager>) -> ConnectionManager {
             cache_manager,
             websocket_write_list,
             quic_write_list,
+            suback_waiters: DashMap::with_capacity(8),
+            connack_waiters: DashMap::with_capacity(8),
+        }
+    }
+
+    // Resolves after the SUBACK with the given packet identifier has been written to the
+    // connection, so that follow-up packets are guaranteed to be sent after it.
+    pub fn wait_suback(&self, connection_id: u64, pkid: u16) -> oneshot::Receiver<()> {
+        let (sx, rx) = oneshot::channel();
+        self.suback_waiters.insert((connection_id, pkid), sx);
+        rx
+    }
+
+    pub fn remove_suback_waiter(&self, connection_id: u64, pkid: u16) {
+        self.suback_waiters.remove(&(connection_id, pkid));
+    }
+
+    // Resolves after the CONNACK has been written to the connection, packets of a resumed
+    // session must not be sent before it.
+    pub fn wait_connack(&self, connection_id: u64) -> oneshot::Receiver<()> {
+        let (sx, rx) = oneshot::channel();
+        self.connack_waiters.insert(connection_id, sx);
+        rx
+    }
+
+    fn notify_packet_sent(&self, connection_id: u64, packet: &MqttPacket) {
+        if self.cache_manager.live_trace_manager.is_tracing() {
+            let addr = self
+                .get_connect(connection_id)
+                .map(|connection| connection.addr);
+            trace_packet(
+                &self.cache_manager,
+                TraceDirection::Out,
+                connection_id,
+                addr.as_ref(),
+                packet,
+            );
+        }
+
+        match packet {
+            MqttPacket::SubAck(suback, _) => {
+                if let Some((_, sx)) = self.suback_waiters.remove(&(connection_id, suback.pkid)) {
+                    let _ = sx.send(());
+                }
+            }
+            MqttPacket::ConnAck(_, _) => {
+                if let Some((_, sx)) = self.connack_waiters.remove(&connection_id) {
+                    let _ = sx.send(());
+                }
+            }
+            _ => {}
         }
     }
 
@@ -113,6 +170,9 @@ pub async fn close_all_connect(&self) {
     }
 
     pub async fn close_connect(&self, connection_id: u64) {
+        self.suback_waiters.retain(|key, _| key.0 != connection_id);
+        self.connack_waiters.remove(&connection_id);
+
         if let Some((_, connection)) = self.connections.remove(&connection_id) {
             connection.stop_connection().await;
         }
@@ -143,6 +203,13 @@ pub async fn close_connect(&self, connection_id: u64) {
                 );
             }
         }
+
+        if let Some((id, _)) = self.quic_write_list.remove(&connection_id) {
+            debug!(
+                "server closes the quic connection actively, connection id [{}]",
+                id
+            );
+        }
     }
 
     pub async fn write_websocket_frame(
@@ -170,6 +237,7 @@ pub async fn write_websocket_frame(
                                 };
 
                             record_sent_metrics(&packet_wrapper, network_type);
+                            self.notify_packet_sent(connection_id, &packet_wrapper.packet);
                             break;
                         }
                         Err(e) => {
@@ -219,6 +287,9 @@ pub async fn write_tcp_frame(
             if connection.connection_type == NetworkConnectionType::Tls {
                 return self.write_tcp_tls_frame(connection_id, resp).await;
             }
+            if connection.connection_type == NetworkConnectionType::Quic {
+                return self.write_quic_frame(connection_id, resp).await;
+            }
         }
 
         let mut times = 0;
@@ -237,6 +308,7 @@ pub async fn write_tcp_frame(
                                 };
 
                             record_sent_metrics(&resp, network_type);
+                            self.notify_packet_sent(connection_id, &resp.packet);
                             break;
                         }
                         Err(e) => {
@@ -291,6 +363,7 @@ async fn write_tcp_tls_frame(
                                 };
 
                             record_sent_metrics(&resp, network_type);
+                            self.notify_packet_sent(connection_id, &resp.packet);
                             break;
                         }
                         Err(e) => {
@@ -322,6 +395,51 @@ async fn write_tcp_tls_frame(
         Ok(())
     }
 
+    async fn write_quic_frame(
+        &self,
+        connection_id: u64,
+        resp: MqttPacketWrapper,
+    ) -> Result<(), MqttBrokerError> {
+        let mut times = 0;
+        let cluster = self.cache_manager.get_cluster_config();
+        loop {
+            match self.quic_write_list.try_get_mut(&connection_id) {
+                dashmap::try_result::TryResult::Present(mut da) => {
+                    match da.send(resp.clone()).await {
+                        Ok(_) => {
+                            record_sent_metrics(&resp, NetworkConnectionType::Quic.to_string());
+                            self.notify_packet_sent(connection_id, &resp.packet);
+                            break;
+                        }
+                        Err(e) => {
+                            if times > cluster.network_thread.lock_max_try_mut_times {
+                                return Err(MqttBrokerError::FailedToWriteClient(
+                                    "quic".to_string(),
+                                    e.to_string(),
+                                ));
+                            }
+                        }
+                    }
+                }
+                dashmap::try_result::TryResult::Absent => {
+                    if times > cluster.network_thread.lock_max_try_mut_times {
+                        return Err(MqttBrokerError::NotObtainAvailableConnection(
+                            "quic".to_string(),
+                            connection_id,
+                        ));
+                    }
+                }
+                dashmap::try_result::TryResult::Locked => {}
+            }
+            times += 1;
+            sleep(Duration::from_millis(
+                cluster.network_thread.lock_try_mut_sleep_time_ms,
+            ))
+            .await
+        }
+        Ok(())
+    }
+
     pub fn tcp_connect_num_check(&self) -> bool {
         let cluster = self.cache_manager.get_cluster_config();
         if self.connections.len() >= cluster.network_thread.max_connection_num {
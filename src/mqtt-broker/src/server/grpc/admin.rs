@@ -17,14 +17,21 @@
     create_blacklist_by_req, delete_blacklist_by_req, list_blacklist_by_req,
 };
 use crate::admin::client::list_client_by_req;
-use crate::admin::cluster::{get_cluster_config_by_req, set_cluster_config_by_req};
+use crate::admin::cluster::{
+    get_cluster_config_by_req, get_log_level_by_req, set_cluster_config_by_req,
+    set_log_level_by_req,
+};
 use crate::admin::connector::{
     create_connector_by_req, delete_connector_by_req, list_connector_by_req,
     update_connector_by_req,
 };
 use crate::admin::observability::{
-    list_slow_subscribe_by_req, list_system_alarm_by_req, set_system_alarm_config_by_req,
+    clear_alarm_by_req, list_alarm_by_req, list_slow_subscribe_by_req, list_system_alarm_by_req,
+    list_topic_metrics_by_req, list_trace_by_req, list_trace_record_by_req,
+    set_system_alarm_config_by_req, start_trace_by_req, stop_trace_by_req,
 };
+use crate::admin::request_response::send_request_by_req;
+use crate::admin::rule::{delete_rule_by_req, list_rule_by_req, set_rule_by_req};
 use crate::admin::schema::{
     bind_schema_by_req, create_schema_by_req, delete_schema_by_req, list_bind_schema_by_req,
     list_schema_by_req, unbind_schema_by_req, update_schema_by_req,
@@ -32,7 +39,9 @@
 use crate::admin::session::list_session_by_req;
 use crate::admin::subscribe::{
     delete_auto_subscribe_rule, list_auto_subscribe_rule_by_req, set_auto_subscribe_rule,
+    set_shared_subscription_strategy_by_req,
 };
+use crate::admin::tenant::{delete_tenant_by_req, list_tenant_by_req, set_tenant_by_req};
 use crate::admin::topic::{
     create_topic_rewrite_rule_by_req, delete_topic_rewrite_rule_by_req,
     get_all_topic_rewrite_rule_by_req, list_topic_by_req,
@@ -49,47 +58,58 @@
 use grpc_clients::pool::ClientPool;
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_server::MqttBrokerAdminService;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterOverviewMetricsReply, ClusterOverviewMetricsRequest, ClusterStatusReply,
-    ClusterStatusRequest, CreateAclReply, CreateAclRequest, CreateBlacklistReply,
-    CreateBlacklistRequest, CreateTopicRewriteRuleReply, CreateTopicRewriteRuleRequest,
-    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest,
-    DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest, DeleteBlacklistReply,
-    DeleteBlacklistRequest, DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest,
-    DeleteUserReply, DeleteUserRequest, EnableFlappingDetectReply, EnableFlappingDetectRequest,
-    GetClusterConfigReply, GetClusterConfigRequest, ListAclReply, ListAclRequest,
+    ClearAlarmReply, ClearAlarmRequest, ClusterOverviewMetricsReply, ClusterOverviewMetricsRequest,
+    ClusterStatusReply, ClusterStatusRequest, CreateAclReply, CreateAclRequest,
+    CreateBlacklistReply, CreateBlacklistRequest, CreateTopicRewriteRuleReply,
+    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
+    DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
+    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteRuleReply, DeleteRuleRequest,
+    DeleteTenantReply, DeleteTenantRequest, DeleteTopicRewriteRuleReply,
+    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, EnableFlappingDetectReply,
+    EnableFlappingDetectRequest, GetClusterConfigReply, GetClusterConfigRequest, GetLogLevelReply,
+    GetLogLevelRequest, ListAclReply, ListAclRequest, ListAlarmReply, ListAlarmRequest,
     ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListBlacklistReply,
     ListBlacklistRequest, ListClientReply, ListClientRequest, ListConnectionReply,
-    ListConnectionRequest, ListRewriteTopicRuleReply, ListRewriteTopicRuleRequest,
-    ListSessionReply, ListSessionRequest, ListSlowSubscribeReply, ListSlowSubscribeRequest,
-    ListSystemAlarmReply, ListSystemAlarmRequest, ListTopicReply, ListTopicRequest, ListUserReply,
-    ListUserRequest, MqttBindSchemaReply, MqttBindSchemaRequest, MqttCreateConnectorReply,
-    MqttCreateConnectorRequest, MqttCreateSchemaReply, MqttCreateSchemaRequest,
-    MqttDeleteConnectorReply, MqttDeleteConnectorRequest, MqttDeleteSchemaReply,
-    MqttDeleteSchemaRequest, MqttListBindSchemaReply, MqttListBindSchemaRequest,
-    MqttListConnectorReply, MqttListConnectorRequest, MqttListSchemaReply, MqttListSchemaRequest,
-    MqttUnbindSchemaReply, MqttUnbindSchemaRequest, MqttUpdateConnectorReply,
-    MqttUpdateConnectorRequest, MqttUpdateSchemaReply, MqttUpdateSchemaRequest,
-    SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest, SetClusterConfigReply,
-    SetClusterConfigRequest, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    ListConnectionRequest, ListRewriteTopicRuleReply, ListRewriteTopicRuleRequest, ListRuleReply,
+    ListRuleRequest, ListSessionReply, ListSessionRequest, ListSlowSubscribeReply,
+    ListSlowSubscribeRequest, ListSystemAlarmReply, ListSystemAlarmRequest, ListTenantReply,
+    ListTenantRequest, ListTopicMetricsReply, ListTopicMetricsRequest, ListTopicReply,
+    ListTopicRequest, ListTraceRecordReply, ListTraceRecordRequest, ListTraceReply,
+    ListTraceRequest, ListUserReply, ListUserRequest, MqttBindSchemaReply, MqttBindSchemaRequest,
+    MqttCreateConnectorReply, MqttCreateConnectorRequest, MqttCreateSchemaReply,
+    MqttCreateSchemaRequest, MqttDeleteConnectorReply, MqttDeleteConnectorRequest,
+    MqttDeleteSchemaReply, MqttDeleteSchemaRequest, MqttListBindSchemaReply,
+    MqttListBindSchemaRequest, MqttListConnectorReply, MqttListConnectorRequest,
+    MqttListSchemaReply, MqttListSchemaRequest, MqttUnbindSchemaReply, MqttUnbindSchemaRequest,
+    MqttUpdateConnectorReply, MqttUpdateConnectorRequest, MqttUpdateSchemaReply,
+    MqttUpdateSchemaRequest, SendRequestReply, SendRequestRequest, SetAutoSubscribeRuleReply,
+    SetAutoSubscribeRuleRequest, SetClusterConfigReply, SetClusterConfigRequest, SetLogLevelReply,
+    SetLogLevelRequest, SetRuleReply, SetRuleRequest, SetSharedSubscriptionStrategyReply,
+    SetSharedSubscriptionStrategyRequest, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    SetTenantReply, SetTenantRequest, StartTraceReply, StartTraceRequest, StopTraceReply,
+    StopTraceRequest,
 };
 use std::sync::Arc;
+use storage_adapter::storage::StorageAdapter;
 use tonic::{Request, Response, Status};
 
-pub struct GrpcAdminServices {
+pub struct GrpcAdminServices<S> {
     client_pool: Arc<ClientPool>,
     cache_manager: Arc<CacheManager>,
     connection_manager: Arc<ConnectionManager>,
     subscribe_manager: Arc<SubscribeManager>,
     metrics_cache_manager: Arc<MetricsCacheManager>,
+    message_storage_adapter: Arc<S>,
 }
 
-impl GrpcAdminServices {
+impl<S> GrpcAdminServices<S> {
     pub fn new(
         client_pool: Arc<ClientPool>,
         cache_manager: Arc<CacheManager>,
         connection_manager: Arc<ConnectionManager>,
         subscribe_manager: Arc<SubscribeManager>,
         metrics_cache_manager: Arc<MetricsCacheManager>,
+        message_storage_adapter: Arc<S>,
     ) -> Self {
         GrpcAdminServices {
             client_pool,
@@ -97,12 +117,16 @@ pub fn new(
             connection_manager,
             subscribe_manager,
             metrics_cache_manager,
+            message_storage_adapter,
         }
     }
 }
 
 #[tonic::async_trait]
-impl MqttBrokerAdminService for GrpcAdminServices {
+impl<S> MqttBrokerAdminService for GrpcAdminServices<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
     async fn mqtt_broker_set_cluster_config(
         &self,
         request: Request<SetClusterConfigRequest>,
@@ -123,6 +147,25 @@ async fn mqtt_broker_get_cluster_config(
             .map(Response::new)
     }
 
+    async fn mqtt_broker_set_log_level(
+        &self,
+        request: Request<SetLogLevelRequest>,
+    ) -> Result<Response<SetLogLevelReply>, Status> {
+        let request = request.into_inner();
+        set_log_level_by_req(&request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_get_log_level(
+        &self,
+        _request: Request<GetLogLevelRequest>,
+    ) -> Result<Response<GetLogLevelReply>, Status> {
+        get_log_level_by_req()
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
     // --- cluster ---
     async fn cluster_status(
         &self,
@@ -303,6 +346,89 @@ async fn mqtt_broker_list_system_alarm(
             .map(Response::new)
     }
 
+    async fn mqtt_broker_list_alarm(
+        &self,
+        request: Request<ListAlarmRequest>,
+    ) -> Result<Response<ListAlarmReply>, Status> {
+        let request = request.into_inner();
+        list_alarm_by_req(&self.cache_manager, &self.client_pool, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_clear_alarm(
+        &self,
+        request: Request<ClearAlarmRequest>,
+    ) -> Result<Response<ClearAlarmReply>, Status> {
+        let request = request.into_inner();
+        clear_alarm_by_req(
+            &self.cache_manager,
+            &self.client_pool,
+            &self.message_storage_adapter,
+            &request,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
+    async fn mqtt_broker_start_trace(
+        &self,
+        request: Request<StartTraceRequest>,
+    ) -> Result<Response<StartTraceReply>, Status> {
+        let request = request.into_inner();
+        start_trace_by_req(&self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_stop_trace(
+        &self,
+        request: Request<StopTraceRequest>,
+    ) -> Result<Response<StopTraceReply>, Status> {
+        let request = request.into_inner();
+        stop_trace_by_req(&self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_list_trace(
+        &self,
+        request: Request<ListTraceRequest>,
+    ) -> Result<Response<ListTraceReply>, Status> {
+        let request = request.into_inner();
+        list_trace_by_req(&self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_list_trace_record(
+        &self,
+        request: Request<ListTraceRecordRequest>,
+    ) -> Result<Response<ListTraceRecordReply>, Status> {
+        let request = request.into_inner();
+        list_trace_record_by_req(&self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    // --- topic metrics ---
+    async fn mqtt_broker_list_topic_metrics(
+        &self,
+        request: Request<ListTopicMetricsRequest>,
+    ) -> Result<Response<ListTopicMetricsReply>, Status> {
+        let request = request.into_inner();
+        list_topic_metrics_by_req(&self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
     // --- connection ---
     async fn mqtt_broker_list_connection(
         &self,
@@ -336,6 +462,23 @@ async fn mqtt_broker_list_topic(
             .map(Response::new)
     }
 
+    async fn mqtt_broker_send_request(
+        &self,
+        request: Request<SendRequestRequest>,
+    ) -> Result<Response<SendRequestReply>, Status> {
+        let request = request.into_inner();
+        send_request_by_req(
+            &self.cache_manager,
+            &self.client_pool,
+            &self.subscribe_manager,
+            &self.message_storage_adapter,
+            &request,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
     async fn mqtt_broker_delete_topic_rewrite_rule(
         &self,
         request: Request<DeleteTopicRewriteRuleRequest>,
@@ -521,4 +664,81 @@ async fn mqtt_broker_list_auto_subscribe_rule(
             .map_err(|e| Status::internal(e.to_string()))
             .map(Response::new)
     }
+
+    async fn mqtt_broker_list_tenant(
+        &self,
+        request: Request<ListTenantRequest>,
+    ) -> Result<Response<ListTenantReply>, Status> {
+        let request = request.into_inner();
+        list_tenant_by_req(&self.cache_manager, &self.subscribe_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_set_tenant(
+        &self,
+        request: Request<SetTenantRequest>,
+    ) -> Result<Response<SetTenantReply>, Status> {
+        let request = request.into_inner();
+        set_tenant_by_req(&self.client_pool, &self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_delete_tenant(
+        &self,
+        request: Request<DeleteTenantRequest>,
+    ) -> Result<Response<DeleteTenantReply>, Status> {
+        let request = request.into_inner();
+        delete_tenant_by_req(&self.client_pool, &self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_list_rule(
+        &self,
+        request: Request<ListRuleRequest>,
+    ) -> Result<Response<ListRuleReply>, Status> {
+        let request = request.into_inner();
+        list_rule_by_req(&self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_set_rule(
+        &self,
+        request: Request<SetRuleRequest>,
+    ) -> Result<Response<SetRuleReply>, Status> {
+        let request = request.into_inner();
+        set_rule_by_req(&self.client_pool, &self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_delete_rule(
+        &self,
+        request: Request<DeleteRuleRequest>,
+    ) -> Result<Response<DeleteRuleReply>, Status> {
+        let request = request.into_inner();
+        delete_rule_by_req(&self.client_pool, &self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_set_shared_subscription_strategy(
+        &self,
+        request: Request<SetSharedSubscriptionStrategyRequest>,
+    ) -> Result<Response<SetSharedSubscriptionStrategyReply>, Status> {
+        let request = request.into_inner();
+        set_shared_subscription_strategy_by_req(&self.client_pool, &self.cache_manager, &request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
 }
@@ -17,8 +17,9 @@
 use grpc_clients::pool::ClientPool;
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_server::MqttBrokerInnerService;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
-    UpdateMqttCacheReply, UpdateMqttCacheRequest,
+    DeleteSessionReply, DeleteSessionRequest, ForwardMessageReply, ForwardMessageRequest,
+    SendLastWillMessageReply, SendLastWillMessageRequest, SessionTakeoverReply,
+    SessionTakeoverRequest, UpdateMqttCacheReply, UpdateMqttCacheRequest,
 };
 use schema_register::schema::SchemaRegisterManager;
 use storage_adapter::storage::StorageAdapter;
@@ -27,12 +28,15 @@
 use crate::bridge::manager::ConnectorManager;
 use crate::handler::cache::CacheManager;
 use crate::inner::services::{
-    delete_session_by_req, send_last_will_message_by_req, update_cache_by_req,
+    delete_session_by_req, forward_message_by_req, send_last_will_message_by_req,
+    session_takeover_by_req, update_cache_by_req,
 };
+use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::manager::SubscribeManager;
 
 pub struct GrpcInnerServices<S> {
     cache_manager: Arc<CacheManager>,
+    connection_manager: Arc<ConnectionManager>,
     connector_manager: Arc<ConnectorManager>,
     subscribe_manager: Arc<SubscribeManager>,
     schema_manager: Arc<SchemaRegisterManager>,
@@ -43,6 +47,7 @@ pub struct GrpcInnerServices<S> {
 impl<S> GrpcInnerServices<S> {
     pub fn new(
         cache_manager: Arc<CacheManager>,
+        connection_manager: Arc<ConnectionManager>,
         subscribe_manager: Arc<SubscribeManager>,
         connector_manager: Arc<ConnectorManager>,
         schema_manager: Arc<SchemaRegisterManager>,
@@ -51,6 +56,7 @@ pub fn new(
     ) -> Self {
         GrpcInnerServices {
             cache_manager,
+            connection_manager,
             subscribe_manager,
             connector_manager,
             client_pool,
@@ -72,6 +78,7 @@ async fn update_cache(
         let req = request.into_inner();
         update_cache_by_req(
             &self.cache_manager,
+            &self.client_pool,
             &self.connector_manager,
             &self.subscribe_manager,
             &self.schema_manager,
@@ -93,6 +100,32 @@ async fn delete_session(
             .map(Response::new)
     }
 
+    async fn session_takeover(
+        &self,
+        request: Request<SessionTakeoverRequest>,
+    ) -> Result<Response<SessionTakeoverReply>, Status> {
+        let req = request.into_inner();
+        session_takeover_by_req(
+            &self.cache_manager,
+            &self.connection_manager,
+            &self.subscribe_manager,
+            &req,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
+    async fn forward_message(
+        &self,
+        request: Request<ForwardMessageRequest>,
+    ) -> Result<Response<ForwardMessageReply>, Status> {
+        let req = request.into_inner();
+        forward_message_by_req(&self.cache_manager, &self.subscribe_manager, &req)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
     async fn send_last_will_message(
         &self,
         request: Request<SendLastWillMessageRequest>,
@@ -76,6 +76,7 @@ pub async fn start(&self) -> Result<(), CommonError> {
         info!("Broker Grpc Server start success. port:{}", self.port);
         let inner_handler = GrpcInnerServices::new(
             self.metadata_cache.clone(),
+            self.connection_manager.clone(),
             self.subscribe_manager.clone(),
             self.connector_manager.clone(),
             self.schema_manager.clone(),
@@ -88,6 +89,7 @@ pub async fn start(&self) -> Result<(), CommonError> {
             self.connection_manager.clone(),
             self.subscribe_manager.clone(),
             self.metrics_cache_manager.clone(),
+            self.message_storage_adapter.clone(),
         );
         Server::builder()
             .accept_http1(true)
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::bridge::manager::ConnectorManager;
 use crate::handler::cache::CacheManager;
 use crate::handler::command::Command;
 use crate::handler::error::MqttBrokerError;
@@ -54,6 +55,7 @@ pub async fn start_quic_server<S>(
     stop_sx: broadcast::Sender<bool>,
     auth_driver: Arc<AuthDriver>,
     schema_register_manager: Arc<SchemaRegisterManager>,
+    connector_manager: Arc<ConnectorManager>,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
@@ -67,6 +69,7 @@ pub async fn start_quic_server<S>(
         connection_manager.clone(),
         schema_register_manager.clone(),
         auth_driver.clone(),
+        connector_manager.clone(),
     );
 
     let mut server = QuicServer::new(SocketAddr::new(
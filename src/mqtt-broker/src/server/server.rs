@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::{
+    bridge::manager::ConnectorManager,
     handler::{cache::CacheManager, command::Command, error::MqttBrokerError},
     security::AuthDriver,
     server::{
@@ -50,6 +51,7 @@ pub fn new(
         client_pool: Arc<ClientPool>,
         stop_sx: broadcast::Sender<bool>,
         auth_driver: Arc<AuthDriver>,
+        connector_manager: Arc<ConnectorManager>,
     ) -> Self {
         let conf = broker_mqtt_conf();
         let command = Command::new(
@@ -61,6 +63,7 @@ pub fn new(
             connection_manager.clone(),
             schema_manager.clone(),
             auth_driver.clone(),
+            connector_manager.clone(),
         );
 
         let proc_config = ProcessorConfig {
@@ -16,6 +16,7 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use crate::bridge::manager::ConnectorManager;
 use crate::handler::cache::CacheManager;
 use crate::handler::command::Command;
 use crate::handler::error::MqttBrokerError;
@@ -60,6 +61,7 @@ pub struct WebSocketServerState<S> {
     connection_manager: Arc<ConnectionManager>,
     schema_manager: Arc<SchemaRegisterManager>,
     auth_driver: Arc<AuthDriver>,
+    connector_manager: Arc<ConnectorManager>,
 }
 
 impl<S> WebSocketServerState<S>
@@ -76,6 +78,7 @@ pub fn new(
         schema_manager: Arc<SchemaRegisterManager>,
         client_pool: Arc<ClientPool>,
         auth_driver: Arc<AuthDriver>,
+        connector_manager: Arc<ConnectorManager>,
         stop_sx: broadcast::Sender<bool>,
     ) -> Self {
         Self {
@@ -87,6 +90,7 @@ pub fn new(
             schema_manager,
             client_pool,
             auth_driver,
+            connector_manager,
             stop_sx,
         }
     }
@@ -183,6 +187,7 @@ async fn ws_handler<S>(
         state.connection_manager.clone(),
         state.schema_manager.clone(),
         state.auth_driver.clone(),
+        state.connector_manager.clone(),
     );
     let codec = MqttCodec::new(None);
     ws.protocols(["mqtt", "mqttv3.1"])
@@ -0,0 +1,91 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::placement::kv::call::{placement_delete, placement_get_prefix, placement_set};
+use grpc_clients::pool::ClientPool;
+use protocol::placement_center::placement_center_kv::{
+    DeleteRequest, GetPrefixRequest, SetRequest,
+};
+
+use crate::observability::warn::AlarmRecord;
+
+// Keeps every alarm transition of every broker as its own entry in the placement center.
+pub struct AlarmStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl AlarmStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        AlarmStorage { client_pool }
+    }
+
+    pub async fn save(&self, broker_id: u64, record: &AlarmRecord) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let mut record = record.clone();
+        record.broker_id = broker_id;
+        let request = SetRequest {
+            key: self.key(broker_id, record.id),
+            value: serde_json::to_string(&record)?,
+        };
+        placement_set(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, broker_id: u64, id: u64) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteRequest {
+            key: self.key(broker_id, id),
+        };
+        placement_delete(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    // The transitions of all brokers of the cluster
+    pub async fn list(&self) -> Result<Vec<AlarmRecord>, CommonError> {
+        self.list_by_prefix(self.key_prefix()).await
+    }
+
+    pub async fn list_by_broker(&self, broker_id: u64) -> Result<Vec<AlarmRecord>, CommonError> {
+        self.list_by_prefix(self.broker_key_prefix(broker_id)).await
+    }
+
+    async fn list_by_prefix(&self, prefix: String) -> Result<Vec<AlarmRecord>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = GetPrefixRequest { prefix };
+        let reply =
+            placement_get_prefix(&self.client_pool, &config.placement_center, request).await?;
+
+        let mut results = Vec::new();
+        for raw in reply.values {
+            results.push(serde_json::from_str::<AlarmRecord>(&raw)?);
+        }
+        Ok(results)
+    }
+
+    fn key_prefix(&self) -> String {
+        let config = broker_mqtt_conf();
+        format!("/mqtt/alarm/{}/", config.cluster_name)
+    }
+
+    fn broker_key_prefix(&self, broker_id: u64) -> String {
+        format!("{}{}/", self.key_prefix(), broker_id)
+    }
+
+    fn key(&self, broker_id: u64, id: u64) -> String {
+        format!("{}{}", self.broker_key_prefix(broker_id), id)
+    }
+}
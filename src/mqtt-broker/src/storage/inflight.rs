@@ -0,0 +1,79 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::placement::kv::call::{placement_delete, placement_get, placement_set};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::inflight::InflightMessage;
+use protocol::placement_center::placement_center_kv::{DeleteRequest, GetRequest, SetRequest};
+
+// Keeps the unacknowledged outbound messages of persistent sessions in the placement center,
+// the whole window of a session under one key, so that any broker can resend them after a
+// reconnect.
+pub struct InflightStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl InflightStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        InflightStorage { client_pool }
+    }
+
+    // An empty window deletes the key of the session
+    pub async fn save_session(
+        &self,
+        client_id: &str,
+        messages: &[InflightMessage],
+    ) -> Result<(), CommonError> {
+        if messages.is_empty() {
+            return self.delete_session(client_id).await;
+        }
+
+        let config = broker_mqtt_conf();
+        let request = SetRequest {
+            key: self.key(client_id),
+            value: serde_json::to_string(messages)?,
+        };
+        placement_set(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn list(&self, client_id: &str) -> Result<Vec<InflightMessage>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = GetRequest {
+            key: self.key(client_id),
+        };
+        let reply = placement_get(&self.client_pool, &config.placement_center, request).await?;
+        if reply.value.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(serde_json::from_str::<Vec<InflightMessage>>(&reply.value)?)
+    }
+
+    pub async fn delete_session(&self, client_id: &str) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteRequest {
+            key: self.key(client_id),
+        };
+        placement_delete(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    fn key(&self, client_id: &str) -> String {
+        let config = broker_mqtt_conf();
+        format!("/mqtt/inflight/{}/{}", config.cluster_name, client_id)
+    }
+}
@@ -76,6 +76,19 @@ pub async fn read_topic_message(
         Ok(records)
     }
 
+    // Removes the messages of the topic before `offset`
+    pub async fn delete_topic_message(
+        &self,
+        topic_id: &str,
+        offset: u64,
+    ) -> Result<(), CommonError> {
+        let shard_name = topic_id;
+        let namespace = cluster_name();
+        self.storage_adapter
+            .delete_by_offset(namespace, shard_name.to_owned(), offset)
+            .await
+    }
+
     pub async fn get_group_offset(&self, group_id: &str) -> Result<u64, CommonError> {
         let offset_data = self
             .storage_adapter
@@ -13,11 +13,17 @@
 // limitations under the License.
 
 pub mod acl;
+pub mod alarm;
 pub mod auto_subscribe;
 pub mod blacklist;
 pub mod cluster;
 pub mod connector;
+pub mod inflight;
 pub mod message;
+pub mod offline_queue;
+pub mod retain;
+pub mod route;
 pub mod session;
+pub mod tenant;
 pub mod topic;
 pub mod user;
@@ -0,0 +1,93 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::placement::kv::call::{
+    placement_delete, placement_get, placement_get_prefix, placement_set,
+};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::offline_queue::MqttOfflineQueue;
+use protocol::placement_center::placement_center_kv::{
+    DeleteRequest, GetPrefixRequest, GetRequest, SetRequest,
+};
+
+// Keeps the offline queue of every persistent session in the placement center, so that the
+// dropped messages are still skipped after a restart or a reconnect to another broker.
+pub struct OfflineQueueStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl OfflineQueueStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        OfflineQueueStorage { client_pool }
+    }
+
+    pub async fn save(&self, queue: &MqttOfflineQueue) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = SetRequest {
+            key: self.key(&queue.client_id),
+            value: queue.encode(),
+        };
+        placement_set(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, client_id: &str) -> Result<Option<MqttOfflineQueue>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = GetRequest {
+            key: self.key(client_id),
+        };
+        let reply = placement_get(&self.client_pool, &config.placement_center, request).await?;
+        if reply.value.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(MqttOfflineQueue::decode(&reply.value)?))
+    }
+
+    pub async fn list(&self) -> Result<Vec<MqttOfflineQueue>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = GetPrefixRequest {
+            prefix: self.key_prefix(),
+        };
+        let reply =
+            placement_get_prefix(&self.client_pool, &config.placement_center, request).await?;
+
+        let mut results = Vec::new();
+        for raw in reply.values {
+            results.push(MqttOfflineQueue::decode(&raw)?);
+        }
+        Ok(results)
+    }
+
+    pub async fn delete(&self, client_id: &str) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteRequest {
+            key: self.key(client_id),
+        };
+        placement_delete(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    fn key_prefix(&self) -> String {
+        let config = broker_mqtt_conf();
+        format!("/mqtt/offline_queue/{}/", config.cluster_name)
+    }
+
+    fn key(&self, client_id: &str) -> String {
+        format!("{}{}", self.key_prefix(), client_id)
+    }
+}
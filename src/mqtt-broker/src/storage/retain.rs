@@ -0,0 +1,356 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use axum::async_trait;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::adapter::record::Record;
+use metadata_struct::mqtt::message::MqttMessage;
+use serde::{Deserialize, Serialize};
+use storage_adapter::storage::{ShardInfo, StorageAdapter};
+use tokio::sync::OnceCell;
+use tracing::warn;
+
+use super::message::{cluster_name, MessageStorage};
+use super::topic::TopicStorage;
+use crate::handler::error::MqttBrokerError;
+
+const RETAIN_MESSAGE_SHARD_NAME: &str = "$retain_message";
+const RETAIN_MESSAGE_READ_BATCH: u64 = 1000;
+// Also the consumer group whose offset points at the latest snapshot
+const RETAIN_MESSAGE_SNAPSHOT_SHARD_NAME: &str = "$retain_message_snapshot";
+// Changes saved after the latest snapshot before a new one is written
+const RETAIN_MESSAGE_SNAPSHOT_INTERVAL: u64 = 10000;
+
+// The retained message of a topic, a None message clears it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RetainMessageEntry {
+    pub topic_name: String,
+    pub message: Option<MqttMessage>,
+    pub expired_at: u64,
+}
+
+impl RetainMessageEntry {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// All retained messages as of `position` of the changelog
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RetainMessageSnapshot {
+    pub position: u64,
+    pub entries: Vec<RetainMessageEntry>,
+}
+
+#[async_trait]
+pub trait RetainMessageStorage: Send + Sync {
+    async fn save(&self, entry: &RetainMessageEntry) -> Result<(), MqttBrokerError>;
+
+    // Entries changed after `position`, in the order they were saved, and the position to
+    // read from next time. Position 0 returns all retained messages.
+    async fn read_changes(
+        &self,
+        position: u64,
+    ) -> Result<(Vec<RetainMessageEntry>, u64), MqttBrokerError>;
+
+    // Whether changes are also carried by the topic metadata updates
+    fn follows_topic_metadata(&self) -> bool {
+        false
+    }
+
+    // Whether enough changes were saved before `position` since the latest snapshot
+    fn need_snapshot(&self, _position: u64) -> bool {
+        false
+    }
+
+    // Reading all retained messages starts from the latest snapshot instead of the first change
+    async fn save_snapshot(
+        &self,
+        _snapshot: &RetainMessageSnapshot,
+    ) -> Result<(), MqttBrokerError> {
+        Ok(())
+    }
+}
+
+// Keeps the retained message on the topic metadata of the placement center. Changes made on
+// other brokers arrive with the topic cache updates instead of being read back.
+pub struct PlacementRetainMessageStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl PlacementRetainMessageStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        PlacementRetainMessageStorage { client_pool }
+    }
+}
+
+#[async_trait]
+impl RetainMessageStorage for PlacementRetainMessageStorage {
+    async fn save(&self, entry: &RetainMessageEntry) -> Result<(), MqttBrokerError> {
+        let topic_storage = TopicStorage::new(self.client_pool.clone());
+        if let Some(message) = &entry.message {
+            topic_storage
+                .set_retain_message(entry.topic_name.clone(), message, entry.expired_at)
+                .await
+        } else {
+            topic_storage
+                .delete_retain_message(entry.topic_name.clone())
+                .await
+        }
+    }
+
+    async fn read_changes(
+        &self,
+        position: u64,
+    ) -> Result<(Vec<RetainMessageEntry>, u64), MqttBrokerError> {
+        // Loaded and updated together with the topic metadata cache
+        Ok((Vec::new(), position))
+    }
+
+    fn follows_topic_metadata(&self) -> bool {
+        true
+    }
+}
+
+// Appends every change to a shard of the message storage adapter. Brokers follow the shard
+// like a changelog, which keeps all of them in sync without the placement center. Snapshots
+// of all retained messages are written to a second shard, so a starting broker only reads
+// the latest snapshot and the changes after it.
+pub struct AdapterRetainMessageStorage<S> {
+    message_storage: MessageStorage<S>,
+    storage_adapter: Arc<S>,
+    shard_init: OnceCell<()>,
+    snapshot_position: AtomicU64,
+}
+
+impl<S> AdapterRetainMessageStorage<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(storage_adapter: Arc<S>) -> Self {
+        AdapterRetainMessageStorage {
+            message_storage: MessageStorage::new(storage_adapter.clone()),
+            storage_adapter,
+            shard_init: OnceCell::new(),
+            snapshot_position: AtomicU64::new(0),
+        }
+    }
+
+    async fn try_init_shard(&self) -> Result<(), MqttBrokerError> {
+        self.shard_init
+            .get_or_try_init(|| async {
+                let namespace = cluster_name();
+                for shard_name in [
+                    RETAIN_MESSAGE_SHARD_NAME,
+                    RETAIN_MESSAGE_SNAPSHOT_SHARD_NAME,
+                ] {
+                    let list = self
+                        .storage_adapter
+                        .list_shard(namespace.clone(), shard_name.to_owned())
+                        .await?;
+                    if list.is_empty() {
+                        self.storage_adapter
+                            .create_shard(ShardInfo {
+                                namespace: namespace.clone(),
+                                shard_name: shard_name.to_owned(),
+                                replica_num: 1,
+                            })
+                            .await?;
+                    }
+                }
+                Ok::<(), MqttBrokerError>(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn read_snapshot(&self) -> Result<Option<RetainMessageSnapshot>, MqttBrokerError> {
+        let offset = self
+            .message_storage
+            .get_group_offset(RETAIN_MESSAGE_SNAPSHOT_SHARD_NAME)
+            .await?;
+        let records = self
+            .message_storage
+            .read_topic_message(RETAIN_MESSAGE_SNAPSHOT_SHARD_NAME, offset, 1)
+            .await?;
+        match records.first() {
+            Some(record) => Ok(Some(serde_json::from_slice::<RetainMessageSnapshot>(
+                &record.data,
+            )?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> RetainMessageStorage for AdapterRetainMessageStorage<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    async fn save(&self, entry: &RetainMessageEntry) -> Result<(), MqttBrokerError> {
+        self.try_init_shard().await?;
+        let mut record = Record::build_byte(entry.encode());
+        record.set_key(entry.topic_name.clone());
+        self.message_storage
+            .append_topic_message(RETAIN_MESSAGE_SHARD_NAME, vec![record])
+            .await?;
+        Ok(())
+    }
+
+    async fn read_changes(
+        &self,
+        position: u64,
+    ) -> Result<(Vec<RetainMessageEntry>, u64), MqttBrokerError> {
+        self.try_init_shard().await?;
+        let mut results = Vec::new();
+        let mut offset = position;
+        if position == 0 {
+            if let Some(snapshot) = self.read_snapshot().await? {
+                results = snapshot.entries;
+                offset = snapshot.position;
+                self.snapshot_position
+                    .store(snapshot.position, Ordering::SeqCst);
+            }
+        }
+        loop {
+            let records = self
+                .message_storage
+                .read_topic_message(RETAIN_MESSAGE_SHARD_NAME, offset, RETAIN_MESSAGE_READ_BATCH)
+                .await?;
+            if records.is_empty() {
+                break;
+            }
+            offset += records.len() as u64;
+            for record in records {
+                results.push(serde_json::from_slice::<RetainMessageEntry>(&record.data)?);
+            }
+        }
+        Ok((results, offset))
+    }
+
+    fn need_snapshot(&self, position: u64) -> bool {
+        position >= self.snapshot_position.load(Ordering::SeqCst) + RETAIN_MESSAGE_SNAPSHOT_INTERVAL
+    }
+
+    async fn save_snapshot(&self, snapshot: &RetainMessageSnapshot) -> Result<(), MqttBrokerError> {
+        self.try_init_shard().await?;
+        let record = Record::build_byte(serde_json::to_vec(snapshot)?);
+        let offsets = self
+            .message_storage
+            .append_topic_message(RETAIN_MESSAGE_SNAPSHOT_SHARD_NAME, vec![record])
+            .await?;
+        if let Some(offset) = offsets.first() {
+            self.message_storage
+                .commit_group_offset(
+                    RETAIN_MESSAGE_SNAPSHOT_SHARD_NAME,
+                    RETAIN_MESSAGE_SNAPSHOT_SHARD_NAME,
+                    *offset,
+                )
+                .await?;
+
+            // Reading starts from the latest snapshot, the changes and snapshots before it are
+            // not read again
+            if let Err(e) = self
+                .message_storage
+                .delete_topic_message(RETAIN_MESSAGE_SHARD_NAME, snapshot.position)
+                .await
+            {
+                warn!(
+                    "Failed to remove the retained message changes before {}, error message: {}",
+                    snapshot.position, e
+                );
+            }
+            if let Err(e) = self
+                .message_storage
+                .delete_topic_message(RETAIN_MESSAGE_SNAPSHOT_SHARD_NAME, *offset)
+                .await
+            {
+                warn!(
+                    "Failed to remove the retained message snapshots before {}, error message: {}",
+                    offset, e
+                );
+            }
+        }
+        self.snapshot_position
+            .store(snapshot.position, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::unique_id;
+    use common_config::mqtt::{config::BrokerMqttConfig, init_broker_mqtt_conf_by_config};
+    use metadata_struct::mqtt::message::MqttMessage;
+    use storage_adapter::memory::MemoryStorageAdapter;
+
+    use super::{
+        AdapterRetainMessageStorage, RetainMessageEntry, RetainMessageSnapshot,
+        RetainMessageStorage, RETAIN_MESSAGE_SHARD_NAME, RETAIN_MESSAGE_SNAPSHOT_INTERVAL,
+    };
+
+    fn build_entry(topic_name: &str) -> RetainMessageEntry {
+        RetainMessageEntry {
+            topic_name: topic_name.to_owned(),
+            message: Some(MqttMessage::default()),
+            expired_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn adapter_snapshot_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: unique_id(),
+            ..Default::default()
+        });
+        let storage = AdapterRetainMessageStorage::new(Arc::new(MemoryStorageAdapter::new()));
+
+        storage.save(&build_entry("a")).await.unwrap();
+        storage.save(&build_entry("b")).await.unwrap();
+        let (entries, position) = storage.read_changes(0).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(position, 2);
+        assert!(!storage.need_snapshot(position));
+
+        // "b" was cleared before the snapshot was taken
+        storage
+            .save_snapshot(&RetainMessageSnapshot {
+                position,
+                entries: vec![build_entry("a")],
+            })
+            .await
+            .unwrap();
+        storage.save(&build_entry("c")).await.unwrap();
+
+        let (entries, position) = storage.read_changes(0).await.unwrap();
+        let topics: Vec<String> = entries.into_iter().map(|entry| entry.topic_name).collect();
+        assert_eq!(topics, vec!["a", "c"]);
+        assert_eq!(position, 3);
+
+        // The changes before the snapshot are removed
+        let records = storage
+            .message_storage
+            .read_topic_message(RETAIN_MESSAGE_SHARD_NAME, 0, 10)
+            .await
+            .unwrap();
+        let offsets: Vec<Option<u64>> = records.iter().map(|record| record.offset).collect();
+        assert_eq!(offsets, vec![Some(2)]);
+        assert!(!storage.need_snapshot(position));
+        assert!(storage.need_snapshot(2 + RETAIN_MESSAGE_SNAPSHOT_INTERVAL));
+    }
+}
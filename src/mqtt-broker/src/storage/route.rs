@@ -0,0 +1,68 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::placement::kv::call::{placement_get_prefix, placement_set};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::route::MqttBrokerRoute;
+use protocol::placement_center::placement_center_kv::{GetPrefixRequest, SetRequest};
+
+// Keeps the topics every broker has subscribers for in the placement center,
+// one key per broker, so that publishers know which brokers to forward messages to.
+pub struct RouteStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl RouteStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        RouteStorage { client_pool }
+    }
+
+    pub async fn save(&self, route: &MqttBrokerRoute) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = SetRequest {
+            key: self.key(route.broker_id),
+            value: route.encode(),
+        };
+        placement_set(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn list(&self) -> Result<Vec<MqttBrokerRoute>, CommonError> {
+        let config = broker_mqtt_conf();
+        let request = GetPrefixRequest {
+            prefix: self.key_prefix(),
+        };
+        let reply =
+            placement_get_prefix(&self.client_pool, &config.placement_center, request).await?;
+
+        let mut results = Vec::new();
+        for raw in reply.values {
+            results.push(MqttBrokerRoute::decode(&raw)?);
+        }
+        Ok(results)
+    }
+
+    fn key_prefix(&self) -> String {
+        let config = broker_mqtt_conf();
+        format!("/mqtt/route/{}/", config.cluster_name)
+    }
+
+    fn key(&self, broker_id: u64) -> String {
+        format!("{}{}", self.key_prefix(), broker_id)
+    }
+}
@@ -14,6 +14,8 @@
 
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::handler::tenant::is_same_tenant;
+use crate::observability::trace::MessageTrace;
 use crate::storage::message::MessageStorage;
 
 use common_base::error::common::CommonError;
@@ -70,6 +72,8 @@ pub(crate) struct SubPublishParam {
     pub create_time: u128,
     pub pkid: u16,
     pub group_id: String,
+    // Span of the delivery, ended once the message was pushed
+    pub trace: Option<Arc<MessageTrace>>,
 }
 
 impl SubPublishParam {
@@ -86,8 +90,16 @@ pub fn new(
             create_time,
             pkid,
             group_id,
+            trace: None,
         }
     }
+
+    pub fn with_trace(mut self, trace: MessageTrace) -> Self {
+        if trace.is_recording() {
+            self.trace = Some(Arc::new(trace));
+        }
+        self
+    }
 }
 
 pub fn is_ignore_push_error(e: &MqttBrokerError) -> bool {
@@ -131,6 +143,10 @@ pub fn is_match_sub_and_topic(sub_path: &str, topic: &str) -> Result<(), MqttBro
     let path = decode_sub_path(sub_path);
     let topic_name = decode_sub_path(topic);
 
+    if !is_same_tenant(&path, &topic_name) {
+        return Err(MqttBrokerError::InvalidSubPath(sub_path.to_owned()));
+    }
+
     if *path == topic_name {
         return Ok(());
     }
@@ -146,6 +162,46 @@ pub fn is_match_sub_and_topic(sub_path: &str, topic: &str) -> Result<(), MqttBro
     Err(MqttBrokerError::InvalidSubPath(sub_path.to_owned()))
 }
 
+// A subscription path with its regex built once, for matching many topics against the same
+// filter. Matches the same topics as is_match_sub_and_topic.
+#[derive(Clone, Debug)]
+pub struct TopicFilterMatcher {
+    path: String,
+    regex: Option<Regex>,
+}
+
+impl TopicFilterMatcher {
+    pub fn new(sub_path: &str) -> Self {
+        let path = decode_sub_path(sub_path);
+        let regex = if is_wildcards(&path) {
+            build_sub_path_regex(&path).ok()
+        } else {
+            None
+        };
+        TopicFilterMatcher { path, regex }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn is_match(&self, topic: &str) -> bool {
+        let topic_name = decode_sub_path(topic);
+        if !is_same_tenant(&self.path, &topic_name) {
+            return false;
+        }
+
+        if self.path == topic_name {
+            return true;
+        }
+
+        if let Some(regex) = &self.regex {
+            return regex.is_match(&topic_name);
+        }
+        false
+    }
+}
+
 pub fn build_sub_path_regex(sub_path: &str) -> Result<Regex, MqttBrokerError> {
     let path = decode_sub_path(sub_path);
 
@@ -197,24 +253,19 @@ pub async fn get_sub_topic_id_list(
     metadata_cache: &Arc<CacheManager>,
     sub_path: &str,
 ) -> Vec<String> {
-    let mut result = Vec::new();
-    if is_wildcards(sub_path) {
-        if let Ok(regex) = build_sub_path_regex(sub_path) {
-            for (topic_id, topic_name) in metadata_cache.topic_id_name.clone() {
-                if regex.is_match(&topic_name) {
-                    result.push(topic_id);
-                }
-            }
-        };
-    } else {
-        for (topic_id, topic_name) in metadata_cache.topic_id_name.clone() {
-            if topic_name == *sub_path {
-                result.push(topic_id);
-            }
-        }
-    }
-
-    result
+    let path = decode_sub_path(sub_path);
+    metadata_cache
+        .topic_trie
+        .match_filter(&path)
+        .into_iter()
+        .filter(|topic_name| is_same_tenant(sub_path, topic_name))
+        .filter_map(|topic_name| {
+            metadata_cache
+                .topic_info
+                .get(&topic_name)
+                .map(|topic| topic.topic_id.clone())
+        })
+        .collect()
 }
 
 pub fn is_share_sub(sub_name: &str) -> bool {
@@ -225,6 +276,11 @@ pub fn is_queue_sub(sub_name: &str) -> bool {
     sub_name.starts_with(QUEUE_SUB_PREFIX)
 }
 
+// Share groups created for `$queue/` subscriptions are named after SHARE_QUEUE_DEFAULT_GROUP_NAME
+pub fn is_queue_group(group_name: &str) -> bool {
+    group_name.starts_with(SHARE_QUEUE_DEFAULT_GROUP_NAME)
+}
+
 pub fn decode_share_info(sub_name: &str) -> (String, String) {
     let mut str_slice: Vec<&str> = sub_name.split("/").collect();
     str_slice.remove(0);
@@ -278,8 +334,9 @@ mod tests {
     use crate::handler::cache::CacheManager;
     use crate::subscribe::common::{
         build_sub_path_regex, decode_queue_info, decode_share_info, decode_sub_path,
-        get_sub_topic_id_list, is_match_sub_and_topic, is_queue_sub, is_share_sub, is_wildcards,
-        min_qos, sub_path_validator,
+        get_sub_topic_id_list, is_match_sub_and_topic, is_queue_group, is_queue_sub, is_share_sub,
+        is_wildcards, min_qos, sub_path_validator, TopicFilterMatcher,
+        SHARE_QUEUE_DEFAULT_GROUP_NAME,
     };
 
     #[tokio::test]
@@ -354,6 +411,33 @@ async fn is_match_sub_and_topic_test() {
         assert!(is_match_sub_and_topic(sub_regex, topic_name).is_ok());
     }
 
+    #[test]
+    fn topic_filter_matcher_test() {
+        let cases = [
+            ("/loboxu/#", "/loboxu/test"),
+            ("/topic/test", "/topic/test"),
+            (r"/sensor/+/temperature", r"/sensor/1/temperature"),
+            (r"/sensor/+/temperature", r"/sensor/1/2/temperature3"),
+            (r"/sensor/+/temperature", r"/sensor/temperature3"),
+            (r"$share/groupname/sensor/#", r"/sensor/temperature3/tmpq"),
+            (r"y/+/z/#", r"y/a/z/b"),
+            ("/_tenant/acme/a/#", "/_tenant/acme/a/b"),
+            ("/_tenant/acme/#", "/_tenant/other/a"),
+            ("#", "/_tenant/acme/a"),
+        ];
+        for (sub_path, topic_name) in cases {
+            let matcher = TopicFilterMatcher::new(sub_path);
+            assert_eq!(
+                matcher.is_match(topic_name),
+                is_match_sub_and_topic(sub_path, topic_name).is_ok(),
+                "{} {}",
+                sub_path,
+                topic_name
+            );
+        }
+        assert_eq!(TopicFilterMatcher::new("$share/g1/a/+").path(), "/a/+");
+    }
+
     #[tokio::test]
     async fn build_sub_path_regex_test() {
         let topic_name = "/loboxu/test";
@@ -416,6 +500,15 @@ async fn is_queue_sub_test() {
         assert!(is_queue_sub("$queue/vvv/v1"));
     }
 
+    #[test]
+    fn is_queue_group_test() {
+        assert!(is_queue_group(&format!(
+            "{}_{}",
+            SHARE_QUEUE_DEFAULT_GROUP_NAME, "/vvv/v1"
+        )));
+        assert!(!is_queue_group("consumer1_/vvv/v1"));
+    }
+
     #[tokio::test]
     #[ignore]
     async fn decode_share_info_test() {
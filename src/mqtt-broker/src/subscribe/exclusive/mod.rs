@@ -145,6 +145,10 @@ async fn start_push_thread(&self) {
                     }
                 };
 
+                let mut route_rx = subscribe_manager
+                    .message_route
+                    .subscribe_topic(&subscriber.topic_id);
+
                 loop {
                     select! {
                         val = sub_thread_stop_rx.recv() =>{
@@ -179,8 +183,41 @@ async fn start_push_thread(&self) {
                                     Ok(offset_op) => {
                                         if let Some(off) = offset_op{
                                             offset = off + 1;
-                                        } else {
-                                            sleep(Duration::from_millis(100)).await;
+                                            continue;
+                                        }
+
+                                        // Nothing new in the storage, wait for a routed record instead
+                                        if let Some(record) = wait_route_record(&mut route_rx, offset).await {
+                                            let (success_num, error_num) = match push_record(
+                                                &connection_manager,
+                                                &message_storage,
+                                                &cache_manager,
+                                                &subscriber,
+                                                &group_id,
+                                                &qos,
+                                                &sub_ids,
+                                                &record,
+                                                &sub_thread_stop_sx,
+                                            )
+                                            .await
+                                            {
+                                                Ok(_) => (1, 0),
+                                                Err(e) => {
+                                                    if !is_ignore_push_error(&e) {
+                                                        warn!(
+                                                            "Exclusive push fail, offset [{:?}], error message:{},",
+                                                            record.offset, e
+                                                        );
+                                                    }
+                                                    (0, 1)
+                                                }
+                                            };
+                                            subscribe_manager.update_exclusive_push_thread_info(
+                                                &exclusive_key,
+                                                success_num,
+                                                error_num,
+                                            );
+                                            offset += 1;
                                         }
                                     }
                                     Err(e) => {
@@ -223,57 +260,22 @@ async fn pub_message<S>(
         .read_topic_message(&subscriber.topic_id, offset, record_num)
         .await?;
 
-    let push_fn = async |record: &Record| -> Result<(), MqttBrokerError> {
-        let record_offset = if let Some(offset) = record.offset {
-            offset
-        } else {
-            return Ok(());
-        };
-
-        // build publish params
-        let sub_pub_param = if let Some(params) = build_publish_message(
-            cache_manager,
+    let mut success_num = 0;
+    let mut error_num = 0;
+    for record in results.iter() {
+        match push_record(
             connection_manager,
-            &subscriber.client_id,
-            record.to_owned(),
+            message_storage,
+            cache_manager,
+            subscriber,
             group_id,
             qos,
-            subscriber,
             sub_ids,
-        )
-        .await?
-        {
-            params
-        } else {
-            return Ok(());
-        };
-
-        // publish data to client
-        send_publish_packet_to_client(
-            connection_manager,
-            cache_manager,
-            &sub_pub_param,
-            qos,
+            record,
             sub_thread_stop_sx,
         )
-        .await?;
-
-        // commit offset
-        loop_commit_offset(
-            message_storage,
-            &subscriber.topic_id,
-            group_id,
-            record_offset,
-        )
-        .await?;
-
-        Ok(())
-    };
-
-    let mut success_num = 0;
-    let mut error_num = 0;
-    for record in results.iter() {
-        match push_fn(record).await {
+        .await
+        {
             Ok(_) => {
                 success_num += 1;
             }
@@ -302,6 +304,97 @@ async fn pub_message<S>(
     Ok(Some(results.last().unwrap().offset.unwrap()))
 }
 
+#[allow(clippy::too_many_arguments)]
+async fn push_record<S>(
+    connection_manager: &Arc<ConnectionManager>,
+    message_storage: &MessageStorage<S>,
+    cache_manager: &Arc<CacheManager>,
+    subscriber: &Subscriber,
+    group_id: &str,
+    qos: &QoS,
+    sub_ids: &[usize],
+    record: &Record,
+    sub_thread_stop_sx: &broadcast::Sender<bool>,
+) -> Result<(), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let record_offset = if let Some(offset) = record.offset {
+        offset
+    } else {
+        return Ok(());
+    };
+
+    // build publish params
+    let sub_pub_param = if let Some(params) = build_publish_message(
+        cache_manager,
+        connection_manager,
+        &subscriber.client_id,
+        record.to_owned(),
+        group_id,
+        qos,
+        subscriber,
+        sub_ids,
+    )
+    .await?
+    {
+        params
+    } else {
+        return Ok(());
+    };
+
+    // publish data to client
+    send_publish_packet_to_client(
+        connection_manager,
+        cache_manager,
+        &sub_pub_param,
+        qos,
+        sub_thread_stop_sx,
+    )
+    .await?;
+
+    // commit offset
+    loop_commit_offset(
+        message_storage,
+        &subscriber.topic_id,
+        group_id,
+        record_offset,
+    )
+    .await?;
+
+    Ok(())
+}
+
+// Wait up to the storage poll interval for the routed record stored at `offset`.
+// Records below `offset` were already read from the storage and are skipped. Anything
+// else means a record was missed, and the caller goes back to reading the storage.
+async fn wait_route_record(
+    route_rx: &mut broadcast::Receiver<Record>,
+    offset: u64,
+) -> Option<Record> {
+    let deadline = sleep(Duration::from_millis(100));
+    tokio::pin!(deadline);
+    loop {
+        select! {
+            val = route_rx.recv() => {
+                match val {
+                    Ok(record) => match record.offset {
+                        Some(record_offset) if record_offset == offset => return Some(record),
+                        Some(record_offset) if record_offset < offset => continue,
+                        _ => return None,
+                    },
+                    Err(broadcast::error::RecvError::Lagged(_)) => return None,
+                    Err(broadcast::error::RecvError::Closed) => {
+                        deadline.as_mut().await;
+                        return None;
+                    }
+                }
+            },
+            _ = deadline.as_mut() => return None,
+        }
+    }
+}
+
 fn build_group_name(subscriber: &Subscriber) -> String {
     format!(
         "system_sub_{}_{}_{}",
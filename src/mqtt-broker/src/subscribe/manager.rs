@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::subscribe::common::Subscriber;
+use crate::handler::tenant::{is_same_tenant, topic_tenant};
+use crate::subscribe::common::{decode_sub_path, is_match_sub_and_topic, Subscriber};
+use crate::subscribe::route::MessageRoute;
+use crate::subscribe::trie::TopicTrie;
 use common_base::tools::now_second;
 use dashmap::DashMap;
 use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
@@ -48,7 +51,7 @@ pub struct ShareLeaderSubscribeData {
     pub sub_list: DashMap<String, Subscriber>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct TopicSubscribeInfo {
     pub client_id: String,
     pub path: String,
@@ -89,6 +92,18 @@ pub struct SubscribeManager {
 
     //(topic_id, Vec<TopicSubscribeInfo>)
     pub topic_subscribe_list: DashMap<String, Vec<TopicSubscribeInfo>>,
+
+    // Subscriptions keyed by the filter they match topics with
+    pub subscribe_trie: TopicTrie<TopicSubscribeInfo>,
+
+    // (client_id_path, filter the subscription is indexed under in subscribe_trie)
+    pub subscribe_route: DashMap<String, String>,
+
+    // Delivers newly persisted messages to the push threads of this and other brokers
+    pub message_route: MessageRoute,
+
+    // (tenant_name, subscribe_num), subscriptions are known to every broker so the count is cluster wide
+    pub tenant_subscribe_num: DashMap<String, u64>,
 }
 
 impl Default for SubscribeManager {
@@ -108,13 +123,25 @@ pub fn new() -> Self {
             share_leader_push_thread: DashMap::with_capacity(8),
             share_follower_resub_thread: DashMap::with_capacity(8),
             topic_subscribe_list: DashMap::with_capacity(8),
+            subscribe_trie: TopicTrie::new(),
+            subscribe_route: DashMap::with_capacity(8),
+            message_route: MessageRoute::new(),
+            tenant_subscribe_num: DashMap::with_capacity(8),
         }
     }
 
     // subscribe info
     pub fn add_subscribe(&self, subscribe: MqttSubscribe) {
         let key = self.subscribe_key(&subscribe.client_id, &subscribe.path);
-        self.subscribe_list.insert(key, subscribe);
+        let path = subscribe.path.clone();
+        if self.subscribe_list.insert(key, subscribe).is_none() {
+            if let Some(tenant) = topic_tenant(&decode_sub_path(&path)) {
+                *self
+                    .tenant_subscribe_num
+                    .entry(tenant.to_owned())
+                    .or_default() += 1;
+            }
+        }
     }
 
     pub fn get_subscribe(&self, client_id: &str, path: &str) -> Option<MqttSubscribe> {
@@ -127,17 +154,105 @@ pub fn get_subscribe(&self, client_id: &str, path: &str) -> Option<MqttSubscribe
 
     pub fn remove_subscribe(&self, client_id: &str, path: &str) {
         let key = self.subscribe_key(client_id, path);
-        self.subscribe_list.remove(&key);
+        if self.subscribe_list.remove(&key).is_some() {
+            self.decr_tenant_subscribe(path);
+        }
+        self.remove_subscribe_route(client_id, path);
+    }
+
+    pub fn list_subscribe_by_client_id(&self, client_id: &str) -> Vec<MqttSubscribe> {
+        self.subscribe_list
+            .iter()
+            .filter(|subscribe| subscribe.client_id == client_id)
+            .map(|subscribe| subscribe.clone())
+            .collect()
+    }
+
+    // The session of the client was taken over by a connection on another broker, pushing
+    // from this broker stops and the cached subscriptions are bound to the new broker.
+    pub fn takeover_client_id(&self, client_id: &str, broker_id: u64) {
+        self.remove_exclusive_push_by_client_id(client_id);
+        self.remove_share_subscribe_leader_by_client_id(client_id);
+        self.remove_share_subscribe_follower_by_client_id(client_id);
+        for mut subscribe in self.subscribe_list.iter_mut() {
+            if subscribe.client_id == client_id {
+                subscribe.broker_id = broker_id;
+                self.remove_subscribe_route(client_id, &subscribe.path);
+            }
+        }
     }
 
     pub fn remove_subscriber_by_client_id(&self, client_id: &str) {
         for (key, subscribe) in self.subscribe_list.clone() {
             if subscribe.client_id == *client_id {
-                self.subscribe_list.remove(&key);
+                if self.subscribe_list.remove(&key).is_some() {
+                    self.decr_tenant_subscribe(&subscribe.path);
+                }
+                self.remove_subscribe_route(client_id, &subscribe.path);
             }
         }
     }
 
+    pub fn tenant_subscribe_count(&self, tenant_name: &str) -> u64 {
+        self.tenant_subscribe_num
+            .get(tenant_name)
+            .map(|num| *num)
+            .unwrap_or(0)
+    }
+
+    fn decr_tenant_subscribe(&self, path: &str) {
+        if let Some(tenant) = topic_tenant(&decode_sub_path(path)) {
+            if let Some(mut num) = self.tenant_subscribe_num.get_mut(tenant) {
+                *num = num.saturating_sub(1);
+            }
+            self.tenant_subscribe_num
+                .remove_if(tenant, |_, num| *num == 0);
+        }
+    }
+
+    // subscribe route, returns whether the filter of the subscription changed
+    pub fn add_subscribe_route(&self, client_id: &str, path: &str, match_path: &str) -> bool {
+        let key = self.subscribe_key(client_id, path);
+        if let Some(old) = self.subscribe_route.get(&key) {
+            if *old == match_path {
+                return false;
+            }
+        }
+        self.remove_subscribe_route(client_id, path);
+
+        self.subscribe_trie.insert(
+            match_path,
+            TopicSubscribeInfo {
+                client_id: client_id.to_owned(),
+                path: path.to_owned(),
+            },
+        );
+        self.subscribe_route.insert(key, match_path.to_owned());
+        true
+    }
+
+    fn remove_subscribe_route(&self, client_id: &str, path: &str) {
+        let key = self.subscribe_key(client_id, path);
+        if let Some((_, match_path)) = self.subscribe_route.remove(&key) {
+            self.subscribe_trie.remove(
+                &match_path,
+                &TopicSubscribeInfo {
+                    client_id: client_id.to_owned(),
+                    path: path.to_owned(),
+                },
+            );
+        }
+    }
+
+    // All subscriptions a topic is routed to
+    pub fn match_subscribe_by_topic(&self, topic_name: &str) -> Vec<TopicSubscribeInfo> {
+        self.subscribe_trie
+            .match_topic(topic_name)
+            .into_iter()
+            .filter(|info| is_same_tenant(&info.path, topic_name))
+            .collect()
+    }
+
     // push by exclusive subscribe
     pub fn add_exclusive_push(&self, client_id: &str, path: &str, topic_id: &str, sub: Subscriber) {
         let key = self.exclusive_key(client_id, path, topic_id);
@@ -160,6 +275,24 @@ pub fn update_exclusive_push_thread_info(
         }
     }
 
+    // The filter of a subscription changed, the topics it no longer matches stop being pushed
+    pub fn remove_exclusive_push_by_unmatched_topic(
+        &self,
+        client_id: &str,
+        path: &str,
+        match_path: &str,
+    ) {
+        for (key, subscriber) in self.exclusive_push.clone() {
+            if subscriber.client_id == *client_id
+                && subscriber.sub_path == *path
+                && is_match_sub_and_topic(match_path, &subscriber.topic_name).is_err()
+            {
+                self.exclusive_push.remove(&key);
+                self.remove_topic_subscribe_by_path(&subscriber.topic_name, client_id, path);
+            }
+        }
+    }
+
     fn remove_exclusive_push_by_client_id(&self, client_id: &str) {
         for (key, subscriber) in self.exclusive_push.clone() {
             if subscriber.client_id == *client_id {
@@ -268,6 +401,13 @@ pub fn update_subscribe_follower_push_thread_info(
     // topic subscribe
     pub fn add_topic_subscribe(&self, topic_name: &str, client_id: &str, path: &str) {
         if let Some(mut list) = self.topic_subscribe_list.get_mut(topic_name) {
+            // A topic can be parsed again for the same subscription, keep one entry
+            if list
+                .iter()
+                .any(|x| x.client_id == client_id && x.path == path)
+            {
+                return;
+            }
             list.push(TopicSubscribeInfo {
                 client_id: client_id.to_owned(),
                 path: path.to_owned(),
@@ -309,7 +449,7 @@ fn remove_topic_subscribe_by_client_id(&self, topic_name: &str, client_id: &str)
 
     pub fn remove_topic_subscribe_by_path(&self, topic_name: &str, client_id: &str, path: &str) {
         if let Some(mut list) = self.topic_subscribe_list.get_mut(topic_name) {
-            list.retain(|x| x.path != *path && x.client_id != *client_id);
+            list.retain(|x| !(x.path == *path && x.client_id == *client_id));
         }
     }
 
@@ -436,6 +576,41 @@ fn topic_subscribe_test() {
         assert!(!subscribe_manager.is_exclusive_subscribe(topic_name));
     }
 
+    #[test]
+    fn subscribe_route_test() {
+        let subscribe_manager = SubscribeManager::new();
+        subscribe_manager.add_subscribe_route("c1", "/sensor/+/temp", "/sensor/+/temp");
+        subscribe_manager.add_subscribe_route("c2", "$share/g1/sensor/#", "/sensor/#");
+
+        let mut clients: Vec<String> = subscribe_manager
+            .match_subscribe_by_topic("/sensor/1/temp")
+            .into_iter()
+            .map(|info| info.client_id)
+            .collect();
+        clients.sort();
+        assert_eq!(clients, vec!["c1".to_string(), "c2".to_string()]);
+
+        // A new match path, e.g. after a rewrite rule changed, replaces the old one
+        subscribe_manager.add_subscribe_route("c1", "/sensor/+/temp", "/other/temp");
+        assert_eq!(
+            subscribe_manager
+                .match_subscribe_by_topic("/sensor/1/temp")
+                .len(),
+            1
+        );
+        assert_eq!(
+            subscribe_manager
+                .match_subscribe_by_topic("/other/temp")
+                .len(),
+            1
+        );
+
+        subscribe_manager.remove_subscribe("c1", "/sensor/+/temp");
+        subscribe_manager.remove_subscribe("c2", "$share/g1/sensor/#");
+        assert!(subscribe_manager.subscribe_trie.is_empty());
+        assert!(subscribe_manager.subscribe_route.is_empty());
+    }
+
     #[test]
     fn share_subscribe_leader_test() {
         let subscribe_manager = Arc::new(SubscribeManager::new());
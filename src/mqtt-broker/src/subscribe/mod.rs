@@ -16,4 +16,6 @@
 pub mod exclusive;
 pub mod manager;
 pub mod push;
+pub mod route;
 pub mod share;
+pub mod trie;
@@ -20,9 +20,16 @@
 use super::common::Subscriber;
 use crate::handler::cache::{CacheManager, QosAckPackageData, QosAckPackageType, QosAckPacketInfo};
 use crate::handler::error::MqttBrokerError;
+use crate::handler::inflight::{
+    delete_inflight_message, is_persistent_session, save_inflight_message,
+};
 use crate::handler::message::is_message_expire;
+use crate::handler::request_response::is_response_topic_allowed;
 use crate::handler::sub_option::{get_retain_flag_by_retain_as_published, is_send_msg_by_bo_local};
+use crate::handler::tenant::strip_tenant_namespace;
+use crate::hook::{fire_message_event, HookEventType};
 use crate::observability::slow::sub::{record_slow_sub_data, SlowSubData};
+use crate::observability::trace::MessageTrace;
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::packet::ResponsePackage;
 use crate::subscribe::common::{is_ignore_push_error, SubPublishParam};
@@ -30,7 +37,10 @@
 use bytes::{Bytes, BytesMut};
 use common_base::tools::now_mills;
 use metadata_struct::adapter::record::Record;
+use metadata_struct::mqtt::inflight::InflightState;
 use metadata_struct::mqtt::message::MqttMessage;
+use opentelemetry::trace::SpanKind;
+use opentelemetry::KeyValue;
 use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
 use protocol::mqtt::common::qos;
 use protocol::mqtt::common::{MqttPacket, MqttProtocol, PubRel, Publish, PublishProperties, QoS};
@@ -54,6 +64,17 @@ pub async fn build_publish_message(
 
     if is_message_expire(&msg) {
         debug!("Message dropping: message expires, is not pushed to the client, and is discarded");
+        fire_message_event(
+            cache_manager,
+            HookEventType::MessageDropped,
+            client_id,
+            &subscriber.topic_name,
+            msg.qos,
+            Some("expired"),
+        );
+        cache_manager
+            .topic_metrics_manager
+            .record_dropped(&subscriber.topic_name);
         return Ok(None);
     }
 
@@ -65,6 +86,40 @@ pub async fn build_publish_message(
         return Ok(None);
     }
 
+    if cache_manager.offline_queue.is_dropped(
+        &subscriber.client_id,
+        &subscriber.sub_path,
+        &subscriber.topic_id,
+        record.offset,
+        msg.qos,
+        msg.create_time,
+    ) {
+        debug!(
+            "Message dropping: message was dropped from the offline queue of client {}, topic_id: {}",
+            subscriber.client_id, subscriber.topic_id
+        );
+        fire_message_event(
+            cache_manager,
+            HookEventType::MessageDropped,
+            client_id,
+            &subscriber.topic_name,
+            msg.qos,
+            Some("offline_queue_full"),
+        );
+        cache_manager
+            .topic_metrics_manager
+            .record_dropped(&subscriber.topic_name);
+        return Ok(None);
+    }
+
+    // A wildcard subscription must not receive the responses meant for another client
+    if !is_response_topic_allowed(
+        &subscriber.client_id,
+        &strip_tenant_namespace(&subscriber.topic_name),
+    ) {
+        return Ok(None);
+    }
+
     let connect_id = if let Some(id) = cache_manager.get_connect_id(client_id) {
         id
     } else {
@@ -83,6 +138,17 @@ pub async fn build_publish_message(
                     conn.max_packet_size
                 )
             );
+            fire_message_event(
+                cache_manager,
+                HookEventType::MessageDropped,
+                client_id,
+                &subscriber.topic_name,
+                msg.qos,
+                Some("packet_too_large"),
+            );
+            cache_manager
+                .topic_metrics_manager
+                .record_dropped(&subscriber.topic_name);
             return Ok(None);
         }
     }
@@ -101,12 +167,27 @@ pub async fn build_publish_message(
 
     let retain = get_retain_flag_by_retain_as_published(subscriber.preserve_retain, msg.retain);
 
+    let topic_name = strip_tenant_namespace(&subscriber.topic_name);
+
+    // Each delivery is a span of the trace the message was published with
+    let trace = MessageTrace::start(
+        "mqtt.deliver",
+        SpanKind::Producer,
+        &msg.user_properties,
+        vec![
+            KeyValue::new("mqtt.client_id", client_id.to_owned()),
+            KeyValue::new("mqtt.topic", topic_name.clone()),
+        ],
+    );
+    let mut user_properties = msg.user_properties;
+    trace.inject(&mut user_properties);
+
     let publish = Publish {
         dup: false,
         qos: qos.to_owned(),
         pkid,
         retain,
-        topic: Bytes::from(subscriber.topic_name.clone()),
+        topic: Bytes::from(topic_name),
         payload: msg.payload,
     };
 
@@ -117,7 +198,7 @@ pub async fn build_publish_message(
             topic_alias: None,
             response_topic: msg.response_topic,
             correlation_data: msg.correlation_data,
-            user_properties: msg.user_properties,
+            user_properties,
             subscription_identifiers: sub_ids.into(),
             content_type: msg.content_type,
         })
@@ -132,7 +213,8 @@ pub async fn build_publish_message(
         record.timestamp as u128,
         group_id.to_string(),
         pkid,
-    );
+    )
+    .with_trace(trace);
     Ok(Some(sub_pub_param))
 }
 
@@ -147,12 +229,27 @@ pub async fn send_publish_packet_to_client(
         QoS::AtMostOnce => {
             push_packet_to_client(cache_manager, connection_manager, sub_pub_param, stop_sx)
                 .await?;
+            record_publish_out(cache_manager, sub_pub_param);
+            fire_publish_event(
+                cache_manager,
+                sub_pub_param,
+                HookEventType::MessageDelivered,
+            );
         }
 
         QoS::AtLeastOnce => {
             let (wait_puback_sx, _) = broadcast::channel(1);
             let client_id = sub_pub_param.subscribe.client_id.clone();
             let pkid: u16 = sub_pub_param.pkid;
+            let persistent = is_persistent_session(cache_manager, &client_id);
+            if persistent {
+                save_inflight_message(
+                    cache_manager,
+                    &client_id,
+                    &sub_pub_param.packet,
+                    InflightState::Publish,
+                );
+            }
             cache_manager.pkid_metadata.add_ack_packet(
                 &client_id,
                 pkid,
@@ -162,24 +259,41 @@ pub async fn send_publish_packet_to_client(
                 },
             );
 
-            exclusive_publish_message_qos1(
+            let result = exclusive_publish_message_qos1(
                 cache_manager,
                 connection_manager,
                 sub_pub_param,
                 stop_sx,
                 &wait_puback_sx,
             )
-            .await?;
+            .await;
 
+            // Release the ack slot even when the push failed, so the inflight count stays exact
             cache_manager
                 .pkid_metadata
                 .remove_ack_packet(&client_id, pkid);
+            result?;
+            fire_publish_event(cache_manager, sub_pub_param, HookEventType::MessageAcked);
+
+            // A failed push keeps the persisted state, it is resent when the client reconnects
+            if persistent {
+                delete_inflight_message(cache_manager, &client_id, pkid);
+            }
         }
 
         QoS::ExactlyOnce => {
             let (wait_ack_sx, _) = broadcast::channel(1);
             let client_id = sub_pub_param.subscribe.client_id.clone();
             let pkid = sub_pub_param.pkid;
+            let persistent = is_persistent_session(cache_manager, &client_id);
+            if persistent {
+                save_inflight_message(
+                    cache_manager,
+                    &client_id,
+                    &sub_pub_param.packet,
+                    InflightState::Publish,
+                );
+            }
             cache_manager.pkid_metadata.add_ack_packet(
                 &client_id,
                 pkid,
@@ -189,23 +303,59 @@ pub async fn send_publish_packet_to_client(
                 },
             );
 
-            exclusive_publish_message_qos2(
+            let result = exclusive_publish_message_qos2(
                 cache_manager,
                 connection_manager,
                 sub_pub_param,
                 stop_sx,
                 &wait_ack_sx,
             )
-            .await?;
+            .await;
 
+            // Release the ack slot even when the push failed, so the inflight count stays exact
             cache_manager
                 .pkid_metadata
                 .remove_ack_packet(&client_id, pkid);
+            result?;
+            fire_publish_event(cache_manager, sub_pub_param, HookEventType::MessageAcked);
+
+            // A failed push keeps the persisted state, it is resent when the client reconnects
+            if persistent {
+                delete_inflight_message(cache_manager, &client_id, pkid);
+            }
         }
     }
     Ok(())
 }
 
+fn fire_publish_event(
+    cache_manager: &Arc<CacheManager>,
+    sub_pub_param: &SubPublishParam,
+    event: HookEventType,
+) {
+    if !cache_manager.hook_manager.is_enabled() {
+        return;
+    }
+    if let MqttPacket::Publish(publish, _) = &sub_pub_param.packet {
+        fire_message_event(
+            cache_manager,
+            event,
+            &sub_pub_param.subscribe.client_id,
+            &String::from_utf8_lossy(&publish.topic),
+            publish.qos,
+            None,
+        );
+    }
+}
+
+fn record_publish_out(cache_manager: &Arc<CacheManager>, sub_pub_param: &SubPublishParam) {
+    if let MqttPacket::Publish(publish, _) = &sub_pub_param.packet {
+        cache_manager
+            .topic_metrics_manager
+            .record_out(&sub_pub_param.subscribe.topic_name, publish.payload.len());
+    }
+}
+
 pub fn build_pub_qos(cache_manager: &Arc<CacheManager>, subscriber: &Subscriber) -> QoS {
     let cluster_qos = cache_manager
         .get_cluster_config()
@@ -270,6 +420,12 @@ pub async fn exclusive_publish_message_qos1(
 ) -> Result<(), MqttBrokerError> {
     // 1. send Publish to Client
     push_packet_to_client(metadata_cache, connection_manager, sub_pub_param, stop_sx).await?;
+    record_publish_out(metadata_cache, sub_pub_param);
+    fire_publish_event(
+        metadata_cache,
+        sub_pub_param,
+        HookEventType::MessageDelivered,
+    );
 
     // 2. wait PubAck ack
     wait_pub_ack(
@@ -297,6 +453,12 @@ pub async fn exclusive_publish_message_qos2(
 ) -> Result<(), MqttBrokerError> {
     // 1. send Publish to Client
     push_packet_to_client(metadata_cache, connection_manager, sub_pub_param, stop_sx).await?;
+    record_publish_out(metadata_cache, sub_pub_param);
+    fire_publish_event(
+        metadata_cache,
+        sub_pub_param,
+        HookEventType::MessageDelivered,
+    );
 
     // 2. wait PubRec ack
     wait_pub_rec(
@@ -308,6 +470,17 @@ pub async fn exclusive_publish_message_qos2(
     )
     .await?;
 
+    // The message has been received by the client, only the PUBREL is left to be resent
+    let client_id = &sub_pub_param.subscribe.client_id;
+    if is_persistent_session(metadata_cache, client_id) {
+        save_inflight_message(
+            metadata_cache,
+            client_id,
+            &sub_pub_param.packet,
+            InflightState::PubRel,
+        );
+    }
+
     // 3. send PubRel to Client
     qos2_send_pubrel(metadata_cache, sub_pub_param, connection_manager, stop_sx).await?;
 
@@ -356,9 +529,11 @@ pub async fn send_message_to_client(
             MqttProtocol::Mqtt3
         };
 
+    let (packet, alias_topic_name) =
+        apply_outbound_topic_alias(metadata_cache, resp.connection_id, &protocol, resp.packet);
     let response: MqttPacketWrapper = MqttPacketWrapper {
         protocol_version: protocol.clone().into(),
-        packet: resp.packet,
+        packet,
     };
 
     if connection_manager.is_websocket(resp.connection_id) {
@@ -376,6 +551,10 @@ pub async fn send_message_to_client(
             .await?
     }
 
+    if let Some(topic_name) = alias_topic_name {
+        metadata_cache.establish_outbound_topic_alias(resp.connection_id, &topic_name);
+    }
+
     // record slow sub data
     if metadata_cache.get_slow_sub_config().enable && sub_pub_param.create_time > 0 {
         let slow_data = SlowSubData::build(
@@ -389,6 +568,41 @@ pub async fn send_message_to_client(
     Ok(())
 }
 
+// Sends the PUBLISH with the topic alias the broker assigned for its topic on the connection,
+// leaving out the topic name once the alias is known to the client. Returns the topic name
+// whose alias the client learns from this packet. Only MQTT 5 clients get aliases, a PUBLISH
+// without properties gets them here.
+fn apply_outbound_topic_alias(
+    cache_manager: &Arc<CacheManager>,
+    connect_id: u64,
+    protocol: &MqttProtocol,
+    packet: MqttPacket,
+) -> (MqttPacket, Option<String>) {
+    match packet {
+        MqttPacket::Publish(mut publish, properties) if protocol.is_mqtt5() => {
+            let topic_name = String::from_utf8_lossy(&publish.topic).to_string();
+            let Some((alias, established)) =
+                cache_manager.outbound_topic_alias(connect_id, &topic_name)
+            else {
+                return (MqttPacket::Publish(publish, properties), None);
+            };
+
+            let mut properties = properties.unwrap_or_default();
+            properties.topic_alias = Some(alias);
+            if established {
+                publish.topic = Bytes::new();
+                (MqttPacket::Publish(publish, Some(properties)), None)
+            } else {
+                (
+                    MqttPacket::Publish(publish, Some(properties)),
+                    Some(topic_name),
+                )
+            }
+        }
+        packet => (packet, None),
+    }
+}
+
 pub async fn wait_pub_ack(
     metadata_cache: &Arc<CacheManager>,
     connection_manager: &Arc<ConnectionManager>,
@@ -573,6 +787,85 @@ async fn retry_tool_fn<F, Fut>(
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
+    use protocol::mqtt::common::{MqttPacket, MqttProtocol, Publish, QoS};
+
+    use super::apply_outbound_topic_alias;
+    use crate::handler::cache::CacheManager;
+
     #[test]
     fn topic_subscribe_test() {}
+
+    #[tokio::test]
+    async fn apply_outbound_topic_alias_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test-cluster".to_string()));
+        let connect_id = 1;
+        cache_manager.connection_info.insert(
+            connect_id,
+            MQTTConnection::new(ConnectionConfig {
+                connect_id,
+                client_id: "c1".to_string(),
+                receive_maximum: 10,
+                max_packet_size: 1024,
+                topic_alias_max: 10,
+                client_topic_alias_max: 5,
+                request_problem_info: 0,
+                keep_alive: 60,
+                source_ip_addr: "127.0.0.1".to_string(),
+            }),
+        );
+
+        let packet = MqttPacket::Publish(
+            Publish {
+                dup: false,
+                qos: QoS::AtLeastOnce,
+                pkid: 1,
+                retain: false,
+                topic: Bytes::from("/device/1/telemetry"),
+                payload: Bytes::from("data"),
+            },
+            None,
+        );
+
+        // MQTT 3 clients never get an alias
+        let (packet_v3, alias_topic_name) = apply_outbound_topic_alias(
+            &cache_manager,
+            connect_id,
+            &MqttProtocol::Mqtt3,
+            packet.clone(),
+        );
+        assert!(matches!(packet_v3, MqttPacket::Publish(_, None)));
+        assert!(alias_topic_name.is_none());
+
+        // The first packet carries both the topic name and the alias, even without properties
+        let (first, alias_topic_name) = apply_outbound_topic_alias(
+            &cache_manager,
+            connect_id,
+            &MqttProtocol::Mqtt5,
+            packet.clone(),
+        );
+        let MqttPacket::Publish(publish, Some(properties)) = first else {
+            panic!("not a publish packet");
+        };
+        assert_eq!(publish.topic, Bytes::from("/device/1/telemetry"));
+        assert_eq!(properties.topic_alias, Some(1));
+        assert_eq!(alias_topic_name, Some("/device/1/telemetry".to_string()));
+
+        cache_manager.establish_outbound_topic_alias(connect_id, "/device/1/telemetry");
+
+        // Later packets only carry the alias
+        let (second, alias_topic_name) =
+            apply_outbound_topic_alias(&cache_manager, connect_id, &MqttProtocol::Mqtt5, packet);
+        let MqttPacket::Publish(publish, Some(properties)) = second else {
+            panic!("not a publish packet");
+        };
+        assert!(publish.topic.is_empty());
+        assert_eq!(properties.topic_alias, Some(1));
+        assert!(alias_topic_name.is_none());
+    }
 }
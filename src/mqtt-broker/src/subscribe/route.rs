@@ -0,0 +1,317 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::tools::now_second;
+use common_config::mqtt::broker_mqtt_conf;
+use dashmap::DashMap;
+use grpc_clients::mqtt::inner::call::broker_mqtt_forward_message;
+use metadata_struct::adapter::record::Record;
+use metadata_struct::mqtt::route::{MqttBrokerRoute, MqttForwardRecord};
+use protocol::broker_mqtt::broker_mqtt_inner::ForwardMessageRequest;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error, info, warn};
+
+use super::manager::SubscribeManager;
+use crate::handler::cache::CacheManager;
+use crate::storage::route::RouteStorage;
+
+const TOPIC_DISPATCH_CAPACITY: usize = 1024;
+const FORWARD_QUEUE_CAPACITY: usize = 10000;
+
+// Routes persisted messages straight to the push threads that are waiting for them,
+// on this broker and on the other brokers that have subscribers for the topic.
+// The message storage is still the source of truth: anything that is dropped here
+// is picked up by the push threads on their next read of the storage.
+#[derive(Clone, Default)]
+pub struct MessageRoute {
+    // (topic_id, Sender) wakes up the local push threads of a topic
+    pub topic_dispatch: DashMap<String, broadcast::Sender<Record>>,
+
+    // (topic_name, broker ids that have subscribers for the topic)
+    pub cluster_route: DashMap<String, HashSet<u64>>,
+
+    // (broker_id, Sender) queue of the messages waiting to be forwarded to a broker
+    pub forward_queue: DashMap<u64, mpsc::Sender<MqttForwardRecord>>,
+}
+
+impl MessageRoute {
+    pub fn new() -> Self {
+        MessageRoute {
+            topic_dispatch: DashMap::with_capacity(8),
+            cluster_route: DashMap::with_capacity(8),
+            forward_queue: DashMap::with_capacity(2),
+        }
+    }
+
+    pub fn subscribe_topic(&self, topic_id: &str) -> broadcast::Receiver<Record> {
+        self.topic_dispatch
+            .entry(topic_id.to_owned())
+            .or_insert_with(|| broadcast::channel(TOPIC_DISPATCH_CAPACITY).0)
+            .subscribe()
+    }
+
+    pub fn dispatch_local(&self, topic_id: &str, record: &Record) {
+        if let Some(sx) = self.topic_dispatch.get(topic_id) {
+            if sx.receiver_count() == 0 {
+                return;
+            }
+            // A lagging or closed receiver falls back to reading the storage
+            let _ = sx.send(record.clone());
+        }
+    }
+
+    pub fn route_brokers(&self, topic_name: &str) -> Vec<u64> {
+        if let Some(brokers) = self.cluster_route.get(topic_name) {
+            return brokers.iter().copied().collect();
+        }
+        Vec::new()
+    }
+
+    pub fn update_cluster_route(&self, routes: &[MqttBrokerRoute], local_broker_id: u64) {
+        let mut table: HashMap<String, HashSet<u64>> = HashMap::with_capacity(routes.len());
+        for route in routes {
+            if route.broker_id == local_broker_id {
+                continue;
+            }
+            for topic_name in route.topics.iter() {
+                table
+                    .entry(topic_name.to_owned())
+                    .or_default()
+                    .insert(route.broker_id);
+            }
+        }
+
+        self.cluster_route
+            .retain(|topic_name, _| table.contains_key(topic_name));
+        for (topic_name, brokers) in table {
+            self.cluster_route.insert(topic_name, brokers);
+        }
+    }
+
+    // Deliver a message that was just persisted at `record.offset` to everyone waiting for it
+    pub fn route(
+        &self,
+        cache_manager: &Arc<CacheManager>,
+        topic_id: &str,
+        topic_name: &str,
+        record: &Record,
+    ) {
+        self.dispatch_local(topic_id, record);
+
+        for broker_id in self.route_brokers(topic_name) {
+            let sx = self
+                .forward_queue
+                .entry(broker_id)
+                .or_insert_with(|| start_forward_thread(cache_manager.clone(), broker_id))
+                .clone();
+
+            let message = MqttForwardRecord {
+                topic_name: topic_name.to_owned(),
+                record: record.clone(),
+            };
+            if let Err(e) = sx.try_send(message) {
+                debug!(
+                    "Message of topic {} was not forwarded to broker {}, error message: {}",
+                    topic_name, broker_id, e
+                );
+                if matches!(e, mpsc::error::TrySendError::Closed(_)) {
+                    self.forward_queue.remove(&broker_id);
+                }
+            }
+        }
+    }
+}
+
+fn start_forward_thread(
+    cache_manager: Arc<CacheManager>,
+    broker_id: u64,
+) -> mpsc::Sender<MqttForwardRecord> {
+    let (sx, mut rx) = mpsc::channel::<MqttForwardRecord>(FORWARD_QUEUE_CAPACITY);
+    tokio::spawn(async move {
+        let conf = broker_mqtt_conf();
+        let batch_size = conf.cluster_route.forward_batch_size.max(1);
+        let mut batch = Vec::with_capacity(batch_size);
+        while rx.recv_many(&mut batch, batch_size).await > 0 {
+            let Some(addr) = cache_manager
+                .node_lists
+                .get(&broker_id)
+                .map(|node| node.node_inner_addr.clone())
+            else {
+                debug!(
+                    "Broker {} is not in the node list, {} forwarded messages were dropped",
+                    broker_id,
+                    batch.len()
+                );
+                batch.clear();
+                continue;
+            };
+
+            let request = ForwardMessageRequest {
+                cluster_name: conf.cluster_name.clone(),
+                source_broker_id: conf.broker_id,
+                records: batch.drain(..).map(|message| message.encode()).collect(),
+            };
+            if let Err(e) =
+                broker_mqtt_forward_message(&cache_manager.client_pool, &[addr], request).await
+            {
+                warn!(
+                    "Failed to forward messages to broker {}, error message: {}",
+                    broker_id, e
+                );
+            }
+        }
+        info!("Message forward thread for broker {} stopped", broker_id);
+    });
+    sx
+}
+
+fn local_route_topics(subscribe_manager: &Arc<SubscribeManager>) -> Vec<String> {
+    let mut topics = HashSet::new();
+    for subscriber in subscribe_manager.exclusive_push.iter() {
+        topics.insert(subscriber.topic_name.clone());
+    }
+    let mut topics: Vec<String> = topics.into_iter().collect();
+    topics.sort();
+    topics
+}
+
+async fn sync_cluster_route(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    route_storage: &RouteStorage,
+    last_topics: &mut Option<Vec<String>>,
+) {
+    let conf = broker_mqtt_conf();
+    let topics = local_route_topics(subscribe_manager);
+    if last_topics.as_ref() != Some(&topics) {
+        let route = MqttBrokerRoute {
+            broker_id: conf.broker_id,
+            topics: topics.clone(),
+            update_time: now_second(),
+        };
+        match route_storage.save(&route).await {
+            Ok(()) => *last_topics = Some(topics),
+            Err(e) => error!(
+                "Failed to save the route of this broker, error message: {}",
+                e
+            ),
+        }
+    }
+
+    match route_storage.list().await {
+        Ok(routes) => {
+            // Brokers that left the cluster keep their key until they come back
+            let routes: Vec<MqttBrokerRoute> = routes
+                .into_iter()
+                .filter(|route| cache_manager.node_lists.contains_key(&route.broker_id))
+                .collect();
+            subscribe_manager
+                .message_route
+                .update_cluster_route(&routes, conf.broker_id);
+        }
+        Err(e) => error!("Failed to load the cluster route, error message: {}", e),
+    }
+}
+
+pub async fn start_cluster_route_sync_thread(
+    cache_manager: Arc<CacheManager>,
+    subscribe_manager: Arc<SubscribeManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let conf = broker_mqtt_conf();
+    if !conf.cluster_route.enable {
+        return;
+    }
+
+    let route_storage = RouteStorage::new(cache_manager.client_pool.clone());
+    let mut last_topics = None;
+    let mut stop_rx = stop_send.subscribe();
+    let mut interval = tokio::time::interval(Duration::from_millis(
+        conf.cluster_route.sync_interval_ms.max(100),
+    ));
+    loop {
+        tokio::select! {
+            val = stop_rx.recv() => {
+                if let Ok(flag) = val {
+                    if flag {
+                        info!("{}","Cluster route sync thread stopped successfully.");
+                        break;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                sync_cluster_route(&cache_manager, &subscribe_manager, &route_storage, &mut last_topics).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::adapter::record::Record;
+    use metadata_struct::mqtt::route::MqttBrokerRoute;
+
+    use super::MessageRoute;
+
+    fn route(broker_id: u64, topics: &[&str]) -> MqttBrokerRoute {
+        MqttBrokerRoute {
+            broker_id,
+            topics: topics.iter().map(|t| t.to_string()).collect(),
+            update_time: 0,
+        }
+    }
+
+    #[test]
+    fn update_cluster_route_test() {
+        let message_route = MessageRoute::new();
+        message_route.update_cluster_route(
+            &[
+                route(1, &["t1"]),
+                route(2, &["t1", "t2"]),
+                route(3, &["t2"]),
+            ],
+            1,
+        );
+
+        let mut brokers = message_route.route_brokers("t1");
+        brokers.sort();
+        assert_eq!(brokers, vec![2]);
+        let mut brokers = message_route.route_brokers("t2");
+        brokers.sort();
+        assert_eq!(brokers, vec![2, 3]);
+
+        message_route.update_cluster_route(&[route(3, &["t2"])], 1);
+        assert!(message_route.route_brokers("t1").is_empty());
+        assert_eq!(message_route.route_brokers("t2"), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn dispatch_local_test() {
+        let message_route = MessageRoute::new();
+        let mut record = Record::build_byte(b"data".to_vec());
+        record.offset = Some(3);
+
+        // Nobody is waiting yet
+        message_route.dispatch_local("topic_id", &record);
+
+        let mut rx = message_route.subscribe_topic("topic_id");
+        message_route.dispatch_local("topic_id", &record);
+        let received = rx.recv().await.unwrap();
+        assert_eq!(received.offset, Some(3));
+    }
+}
@@ -438,7 +438,7 @@ async fn process_publish_packet(
                 },
             );
 
-            resub_publish_message_qos1(
+            let result = resub_publish_message_qos1(
                 cache_manager,
                 connection_manager,
                 &sub_pub_param,
@@ -446,11 +446,12 @@ async fn process_publish_packet(
                 &wait_puback_sx,
                 write_stream,
             )
-            .await?;
+            .await;
 
             cache_manager
                 .pkid_metadata
                 .remove_ack_packet(mqtt_client_id, publish_to_client_pkid);
+            result?;
         }
 
         protocol::mqtt::common::QoS::ExactlyOnce => {
@@ -475,7 +476,7 @@ async fn process_publish_packet(
                 },
             );
 
-            resub_publish_message_qos2(
+            let result = resub_publish_message_qos2(
                 cache_manager,
                 &sub_pub_param,
                 connection_manager,
@@ -487,7 +488,7 @@ async fn process_publish_packet(
                 mqtt_client_id,
                 publish.pkid,
             )
-            .await?;
+            .await;
 
             cache_manager
                 .pkid_metadata
@@ -495,6 +496,7 @@ async fn process_publish_packet(
             cache_manager
                 .pkid_metadata
                 .remove_ack_packet(follower_sub_leader_client_id, publish.pkid);
+            result?;
         }
     }
     Ok(())
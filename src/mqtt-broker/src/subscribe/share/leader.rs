@@ -16,16 +16,22 @@
 use crate::handler::error::MqttBrokerError;
 use crate::server::connection_manager::ConnectionManager;
 use crate::storage::message::MessageStorage;
-use crate::subscribe::common::is_ignore_push_error;
 use crate::subscribe::common::loop_commit_offset;
-use crate::subscribe::common::Subscriber;
+use crate::subscribe::common::{is_ignore_push_error, is_queue_group};
+use crate::subscribe::common::{SubPublishParam, Subscriber};
 use crate::subscribe::manager::SubPushThreadData;
 use crate::subscribe::manager::{ShareLeaderSubscribeData, SubscribeManager};
 use crate::subscribe::push::{
     build_pub_qos, build_publish_message, build_sub_ids, send_publish_packet_to_client,
 };
+use crate::subscribe::share::queue::{deliver_queue_records, QueueDelivery};
+use crate::subscribe::share::strategy::{ShareDispatcher, ShareStrategy};
 use common_base::tools::now_second;
 use metadata_struct::adapter::record::Record;
+use metadata_struct::mqtt::message::MqttMessage;
+use protocol::mqtt::common::QoS;
+use std::collections::HashSet;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use storage_adapter::storage::StorageAdapter;
@@ -122,14 +128,14 @@ pub async fn start_push_thread(&self) {
                 .share_leader_push_thread
                 .contains_key(&share_leader_key)
             {
-                if let Err(e) = self.push_by_round_robin(share_leader_key, sub_data).await {
+                if let Err(e) = self.push_by_strategy(share_leader_key, sub_data).await {
                     error!("{:?}", e);
                 }
             }
         }
     }
 
-    async fn push_by_round_robin(
+    async fn push_by_strategy(
         &self,
         share_leader_key: String,
         sub_data: ShareLeaderSubscribeData,
@@ -144,6 +150,13 @@ async fn push_by_round_robin(
         let message_storage = MessageStorage::new(self.message_storage.clone());
         let mut offset = message_storage.get_group_offset(&group_id).await?;
 
+        // Queue groups track the delivery of every message until it is acknowledged
+        let mut queue_delivery = if is_queue_group(&sub_data.group_name) {
+            Some(QueueDelivery::load(&self.message_storage, &group_id, offset).await?)
+        } else {
+            None
+        };
+
         // save push thread
         self.subscribe_manager.share_leader_push_thread.insert(
             share_leader_key.clone(),
@@ -160,6 +173,7 @@ async fn push_by_round_robin(
         let connection_manager = self.connection_manager.clone();
         let cache_manager = self.cache_manager.clone();
         let subscribe_manager = self.subscribe_manager.clone();
+        let storage_adapter = self.message_storage.clone();
 
         tokio::spawn(async move {
             info!(
@@ -167,7 +181,8 @@ async fn push_by_round_robin(
                 sub_data.group_name, sub_data.sub_name, sub_data.topic_name
             );
 
-            let mut seq = 1;
+            let mut dispatcher =
+                ShareDispatcher::new(share_group_strategy(&cache_manager, &sub_data.group_name));
             loop {
                 select! {
                     val = sub_thread_stop_rx.recv() =>{
@@ -184,20 +199,21 @@ async fn push_by_round_robin(
                     res = read_message_process(
                         &connection_manager,
                         &cache_manager,
+                        &storage_adapter,
                         &message_storage,
                         &subscribe_manager,
                         &share_leader_key,
                         &sub_data,
                         &group_id,
                         offset,
-                        seq,
+                        &mut dispatcher,
+                        &mut queue_delivery,
                         &sub_thread_stop_sx,
                     ) =>{
                         match res {
-                            Ok((data,seq_num)) => {
+                            Ok(data) => {
                                 if let Some(offset_cur) = data{
                                     offset = offset_cur + 1;
-                                    seq = seq_num;
                                 }else{
                                     sleep(Duration::from_millis(100)).await;
                                 }
@@ -232,15 +248,17 @@ async fn push_by_round_robin(
 async fn read_message_process<S>(
     connection_manager: &Arc<ConnectionManager>,
     cache_manager: &Arc<CacheManager>,
+    storage_adapter: &Arc<S>,
     message_storage: &MessageStorage<S>,
     subscribe_manager: &Arc<SubscribeManager>,
     share_leader_key: &str,
     sub_data: &ShareLeaderSubscribeData,
     group_id: &str,
     offset: u64,
-    mut seq: u64,
+    dispatcher: &mut ShareDispatcher,
+    queue_delivery: &mut Option<QueueDelivery<S>>,
     stop_sx: &Sender<bool>,
-) -> Result<(Option<u64>, u64), MqttBrokerError>
+) -> Result<Option<u64>, MqttBrokerError>
 where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
@@ -248,6 +266,27 @@ async fn read_message_process<S>(
         .read_topic_message(&sub_data.topic_id, offset, 100)
         .await?;
 
+    // Strategy changes take effect from the next batch
+    dispatcher.set_strategy(share_group_strategy(cache_manager, &sub_data.group_name));
+
+    if let Some(delivery) = queue_delivery {
+        return Ok(deliver_queue_records(
+            connection_manager,
+            cache_manager,
+            subscribe_manager,
+            storage_adapter,
+            message_storage,
+            delivery,
+            share_leader_key,
+            sub_data,
+            group_id,
+            dispatcher,
+            &results,
+            stop_sx,
+        )
+        .await);
+    }
+
     let mut push_fn = async |record: &Record| -> Result<(), MqttBrokerError> {
         let record_offset = if let Some(offset) = record.offset {
             offset
@@ -255,20 +294,40 @@ async fn read_message_process<S>(
             return Ok(());
         };
 
-        let mut times = 0;
-        loop {
-            seq += 1;
-            times += 1;
-            if times > 3 {
-                warn!("Shared subscription failed to send messages {} times and the messages were discarded,, offset: {:?}", times, record.offset);
-                break;
-            }
+        let publisher_client_id = if dispatcher.strategy() == ShareStrategy::HashClientId {
+            MqttMessage::decode_record(record.clone())?.client_id
+        } else {
+            String::new()
+        };
 
-            let subscriber = if let Some(subscrbie) =
-                get_subscribe_by_random(subscribe_manager, share_leader_key, seq)
-            {
-                subscrbie
+        // Members that failed to take the message, or disconnected before acknowledging it.
+        // The message is redispatched to one of the other members.
+        let mut excluded = HashSet::new();
+        let mut wait_times = 0;
+        loop {
+            let candidates = share_candidates(
+                subscribe_manager,
+                cache_manager,
+                share_leader_key,
+                &excluded,
+            );
+            let subscriber = if let Some(subscriber) = dispatcher.select(
+                &candidates,
+                &publisher_client_id,
+                &sub_data.topic_name,
+                |client_id| cache_manager.pkid_metadata.inflight_num(client_id),
+            ) {
+                subscriber
             } else {
+                if !excluded.is_empty() {
+                    warn!("All members of the share group failed to receive the message and the message was discarded, group: {}, offset: {:?}", sub_data.group_name, record.offset);
+                    break;
+                }
+                wait_times += 1;
+                if wait_times > 3 {
+                    warn!("Shared subscription has no available subscribers and the message was discarded, group: {}, offset: {:?}", sub_data.group_name, record.offset);
+                    break;
+                }
                 warn!("No available subscribers were obtained. Continue looking for the next one, , offset: {:?}", record.offset);
                 sleep(Duration::from_secs(1)).await;
                 continue;
@@ -303,11 +362,12 @@ async fn read_message_process<S>(
                         "Build message error. Error message : {}, offset: {:?}",
                         e, record.offset
                     );
-                    break;
+                    excluded.insert(subscriber.client_id);
+                    continue;
                 }
             };
 
-            if let Err(e) = send_publish_packet_to_client(
+            if let Err(e) = send_to_share_member(
                 connection_manager,
                 cache_manager,
                 &sub_pub_param,
@@ -317,10 +377,10 @@ async fn read_message_process<S>(
             .await
             {
                 warn!(
-                    "Shared subscription failed to send a message. I attempted to 
-                    send it to the next client. Error message :{}, offset: {:?}",
-                    e, record.offset
+                    "Shared subscription failed to send a message to {}, redispatching it to another member. Error message :{}, offset: {:?}",
+                    subscriber.client_id, e, record.offset
                 );
+                excluded.insert(subscriber.client_id);
                 continue;
             };
 
@@ -358,34 +418,91 @@ async fn read_message_process<S>(
     );
 
     if results.is_empty() {
-        return Ok((None, seq));
+        return Ok(None);
     }
 
-    Ok((results.last().unwrap().offset, seq))
+    Ok(results.last().unwrap().offset)
 }
 
-fn get_subscribe_by_random(
+fn share_group_strategy(cache_manager: &Arc<CacheManager>, group_name: &str) -> ShareStrategy {
+    let config = cache_manager.get_shared_subscription_config();
+    let strategy = config.group_strategy(group_name);
+    match ShareStrategy::from_str(strategy) {
+        Ok(strategy) => strategy,
+        Err(_) => {
+            warn!(
+                "Unknown shared subscription strategy {} for group {}, fall back to round_robin",
+                strategy, group_name
+            );
+            ShareStrategy::RoundRobin
+        }
+    }
+}
+
+// The connected members of the group, sorted by client id
+pub(super) fn share_candidates(
     subscribe_manager: &Arc<SubscribeManager>,
+    cache_manager: &Arc<CacheManager>,
     share_leader_key: &str,
-    seq: u64,
-) -> Option<Subscriber> {
-    if let Some(sub_list) = subscribe_manager.share_leader_push.get(share_leader_key) {
-        let index = seq % (sub_list.sub_list.len() as u64);
-        let keys: Vec<String> = sub_list
-            .sub_list
-            .iter()
-            .map(|entry| entry.key().clone())
-            .collect();
-
-        if let Some(key) = keys.get(index as usize) {
-            if let Some(subscribe) = sub_list.sub_list.get(key) {
-                return Some(subscribe.clone());
-            }
+    excluded: &HashSet<String>,
+) -> Vec<Subscriber> {
+    let mut candidates: Vec<Subscriber> =
+        if let Some(sub_list) = subscribe_manager.share_leader_push.get(share_leader_key) {
+            sub_list
+                .sub_list
+                .iter()
+                .filter(|entry| !excluded.contains(entry.key()))
+                .filter(|entry| cache_manager.get_connect_id(entry.key()).is_some())
+                .map(|entry| entry.value().clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+    candidates.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+    candidates
+}
+
+// Sends the message and waits for its ack. If the member disconnects before acknowledging,
+// the send is abandoned so the caller can redispatch the message to another member.
+pub(super) async fn send_to_share_member(
+    connection_manager: &Arc<ConnectionManager>,
+    cache_manager: &Arc<CacheManager>,
+    sub_pub_param: &SubPublishParam,
+    qos: &QoS,
+    stop_sx: &Sender<bool>,
+) -> Result<(), MqttBrokerError> {
+    let client_id = &sub_pub_param.subscribe.client_id;
+    let connect_id = cache_manager.get_connect_id(client_id);
+
+    select! {
+        res = send_publish_packet_to_client(
+            connection_manager,
+            cache_manager,
+            sub_pub_param,
+            qos,
+            stop_sx,
+        ) => res,
+        _ = wait_member_offline(cache_manager, client_id, connect_id) => {
+            cache_manager
+                .pkid_metadata
+                .remove_ack_packet(client_id, sub_pub_param.pkid);
+            Err(MqttBrokerError::CommonError(format!(
+                "share subscriber {} disconnected before acknowledging the message",
+                client_id
+            )))
         }
     }
-
-    None
 }
 
-#[cfg(test)]
-mod tests {}
+async fn wait_member_offline(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    connect_id: Option<u64>,
+) {
+    loop {
+        sleep(Duration::from_millis(100)).await;
+        if cache_manager.get_connect_id(client_id) != connect_id {
+            return;
+        }
+    }
+}
@@ -14,4 +14,6 @@
 
 pub mod follower;
 pub mod leader;
+pub mod queue;
+pub mod strategy;
 pub mod write;
@@ -0,0 +1,745 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::tools::now_second;
+use futures::future::join_all;
+use metadata_struct::adapter::record::{Header, Record};
+use metadata_struct::mqtt::message::MqttMessage;
+use serde::{Deserialize, Serialize};
+use storage_adapter::storage::{ShardInfo, StorageAdapter};
+use tokio::sync::broadcast::Sender;
+use tokio::time::{sleep, timeout};
+use tracing::warn;
+
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::topic::try_init_topic;
+use crate::server::connection_manager::ConnectionManager;
+use crate::storage::message::{cluster_name, MessageStorage};
+use crate::subscribe::common::{is_ignore_push_error, loop_commit_offset, Subscriber};
+use crate::subscribe::manager::{ShareLeaderSubscribeData, SubscribeManager};
+use crate::subscribe::push::{build_pub_qos, build_publish_message, build_sub_ids};
+use crate::subscribe::share::leader::{send_to_share_member, share_candidates};
+use crate::subscribe::share::strategy::{ShareDispatcher, ShareStrategy};
+
+// Changelog of the delivery attempts of one queue group, followed by the group id. A group only
+// replays its own changes after its latest snapshot.
+const QUEUE_DELIVERY_SHARD_PREFIX: &str = "$queue_delivery_";
+// Snapshots of the delivery attempts of single queue groups
+const QUEUE_DELIVERY_SNAPSHOT_SHARD_NAME: &str = "$queue_delivery_snapshot";
+const QUEUE_DELIVERY_READ_BATCH: u64 = 1000;
+// Changelog entries appended after the latest snapshot of a group before a new one is written
+const QUEUE_DELIVERY_SNAPSHOT_INTERVAL: u64 = 10000;
+
+pub const DEAD_LETTER_HEADER_TOPIC: &str = "dead_letter_original_topic";
+pub const DEAD_LETTER_HEADER_OFFSET: &str = "dead_letter_original_offset";
+pub const DEAD_LETTER_HEADER_ATTEMPTS: &str = "dead_letter_delivery_attempts";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueueDeliveryEntry {
+    pub group_id: String,
+    pub offset: u64,
+    pub attempts: u32,
+    pub update_time: u64,
+}
+
+impl QueueDeliveryEntry {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<QueueDeliveryEntry, MqttBrokerError> {
+        Ok(serde_json::from_slice::<QueueDeliveryEntry>(data)?)
+    }
+}
+
+// The delivery attempts of a group as of `position` of the changelog
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueueDeliverySnapshot {
+    pub group_id: String,
+    pub position: u64,
+    // (offset, delivery attempts)
+    pub attempts: Vec<(u64, u32)>,
+}
+
+// Delivery attempts of the messages of a queue group that are not acknowledged yet.
+// Every attempt is appended to the message storage before the message is sent,
+// so the count survives a restart or a move of the group leader. Snapshots of the
+// attempts are written to a second shard, so loading a group only reads the changes
+// after its latest snapshot.
+pub struct QueueDelivery<S> {
+    message_storage: MessageStorage<S>,
+    group_id: String,
+    shard_name: String,
+    // (offset, delivery attempts)
+    attempts: HashMap<u64, u32>,
+    snapshot_position: u64,
+}
+
+impl<S> QueueDelivery<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub async fn load(
+        storage_adapter: &Arc<S>,
+        group_id: &str,
+        committed_offset: u64,
+    ) -> Result<Self, MqttBrokerError> {
+        let namespace = cluster_name();
+        let shard_name = delivery_shard_name(group_id);
+        for shard_name in [shard_name.as_str(), QUEUE_DELIVERY_SNAPSHOT_SHARD_NAME] {
+            let list = storage_adapter
+                .list_shard(namespace.clone(), shard_name.to_owned())
+                .await?;
+            if list.is_empty() {
+                storage_adapter
+                    .create_shard(ShardInfo {
+                        namespace: namespace.clone(),
+                        shard_name: shard_name.to_owned(),
+                        replica_num: 1,
+                    })
+                    .await?;
+            }
+        }
+
+        let message_storage = MessageStorage::new(storage_adapter.clone());
+        let snapshot = read_snapshot(&message_storage, group_id)
+            .await?
+            .unwrap_or_default();
+        let mut entries = Vec::new();
+        let mut offset = snapshot.position;
+        loop {
+            let records = message_storage
+                .read_topic_message(&shard_name, offset, QUEUE_DELIVERY_READ_BATCH)
+                .await?;
+            if records.is_empty() {
+                break;
+            }
+            for record in records.iter() {
+                entries.push(QueueDeliveryEntry::decode(&record.data)?);
+            }
+            offset = records
+                .last()
+                .and_then(|record| record.offset)
+                .unwrap_or(offset)
+                + 1;
+        }
+
+        Ok(QueueDelivery {
+            message_storage,
+            group_id: group_id.to_owned(),
+            shard_name,
+            attempts: fold_delivery_entries(
+                snapshot.attempts.into_iter().collect(),
+                entries,
+                committed_offset,
+            ),
+            snapshot_position: snapshot.position,
+        })
+    }
+
+    pub fn attempts(&self, offset: u64) -> u32 {
+        self.attempts.get(&offset).copied().unwrap_or_default()
+    }
+
+    // One more attempt for each of the messages, appended to the changelog at once
+    pub async fn record_attempts(&mut self, offsets: &[u64]) -> Result<(), MqttBrokerError> {
+        let mut records = Vec::with_capacity(offsets.len());
+        let mut attempts = Vec::with_capacity(offsets.len());
+        for offset in offsets {
+            let entry = QueueDeliveryEntry {
+                group_id: self.group_id.clone(),
+                offset: *offset,
+                attempts: self.attempts(*offset) + 1,
+                update_time: now_second(),
+            };
+            let mut record = Record::build_byte(entry.encode());
+            record.set_key(self.group_id.clone());
+            records.push(record);
+            attempts.push((entry.offset, entry.attempts));
+        }
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let positions = self
+            .message_storage
+            .append_topic_message(&self.shard_name, records)
+            .await?;
+        self.attempts.extend(attempts);
+
+        if let Some(position) = positions.last() {
+            if need_snapshot(self.snapshot_position, *position + 1) {
+                if let Err(e) = self.save_snapshot(*position + 1).await {
+                    warn!(
+                        "Failed to save the delivery snapshot of queue group {}, error message: {}",
+                        self.group_id, e
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn settle(&mut self, offset: u64) {
+        self.attempts
+            .retain(|record_offset, _| *record_offset > offset);
+    }
+
+    // Every attempt of the group before `position` is already in memory, the group leader
+    // is the only one appending them.
+    async fn save_snapshot(&mut self, position: u64) -> Result<(), MqttBrokerError> {
+        let snapshot = QueueDeliverySnapshot {
+            group_id: self.group_id.clone(),
+            position,
+            attempts: self
+                .attempts
+                .iter()
+                .map(|(offset, attempts)| (*offset, *attempts))
+                .collect(),
+        };
+        let mut record = Record::build_byte(serde_json::to_vec(&snapshot)?);
+        record.set_key(self.group_id.clone());
+        let offsets = self
+            .message_storage
+            .append_topic_message(QUEUE_DELIVERY_SNAPSHOT_SHARD_NAME, vec![record])
+            .await?;
+        if let Some(offset) = offsets.first() {
+            self.message_storage
+                .commit_group_offset(
+                    &snapshot_group_id(&self.group_id),
+                    QUEUE_DELIVERY_SNAPSHOT_SHARD_NAME,
+                    *offset,
+                )
+                .await?;
+        }
+        self.snapshot_position = position;
+
+        // Loading starts from the snapshot, the changes before it are not read again
+        if let Err(e) = self
+            .message_storage
+            .delete_topic_message(&self.shard_name, position)
+            .await
+        {
+            warn!(
+                "Failed to remove the delivery changes of queue group {} before {}, error message: {}",
+                self.group_id, position, e
+            );
+        }
+        Ok(())
+    }
+}
+
+fn delivery_shard_name(group_id: &str) -> String {
+    format!("{}{}", QUEUE_DELIVERY_SHARD_PREFIX, group_id)
+}
+
+// The consumer group whose offset points at the latest snapshot of a queue group
+fn snapshot_group_id(group_id: &str) -> String {
+    format!("{}_delivery_snapshot", group_id)
+}
+
+fn need_snapshot(snapshot_position: u64, position: u64) -> bool {
+    position >= snapshot_position + QUEUE_DELIVERY_SNAPSHOT_INTERVAL
+}
+
+async fn read_snapshot<S>(
+    message_storage: &MessageStorage<S>,
+    group_id: &str,
+) -> Result<Option<QueueDeliverySnapshot>, MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let offset = message_storage
+        .get_group_offset(&snapshot_group_id(group_id))
+        .await?;
+    let records = message_storage
+        .read_topic_message(QUEUE_DELIVERY_SNAPSHOT_SHARD_NAME, offset, 1)
+        .await?;
+    match records.first() {
+        // A group without a snapshot reads the first one of the shard, which may be of another group
+        Some(record) if record.key == group_id => Ok(Some(serde_json::from_slice(&record.data)?)),
+        _ => Ok(None),
+    }
+}
+
+// The latest attempt count of every message at or after the committed offset
+fn fold_delivery_entries(
+    mut attempts: HashMap<u64, u32>,
+    entries: Vec<QueueDeliveryEntry>,
+    committed_offset: u64,
+) -> HashMap<u64, u32> {
+    attempts.retain(|offset, _| *offset >= committed_offset);
+    for entry in entries {
+        if entry.offset < committed_offset {
+            continue;
+        }
+        let value = attempts.entry(entry.offset).or_insert(0);
+        *value = (*value).max(entry.attempts);
+    }
+    attempts
+}
+
+async fn move_to_dead_letter<S>(
+    cache_manager: &Arc<CacheManager>,
+    storage_adapter: &Arc<S>,
+    topic_name: &str,
+    record: &Record,
+    attempts: u32,
+) -> Result<(), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let config = cache_manager.get_queue_subscription_config();
+    let dead_letter_topic_name = config.dead_letter_topic(topic_name);
+    let topic = try_init_topic(
+        &dead_letter_topic_name,
+        cache_manager,
+        storage_adapter,
+        &cache_manager.client_pool,
+    )
+    .await?;
+
+    let mut dead_letter = record.clone();
+    let original_offset = dead_letter.offset.take().unwrap_or_default();
+    dead_letter.header.extend([
+        Header {
+            name: DEAD_LETTER_HEADER_TOPIC.to_string(),
+            value: topic_name.to_owned(),
+        },
+        Header {
+            name: DEAD_LETTER_HEADER_OFFSET.to_string(),
+            value: original_offset.to_string(),
+        },
+        Header {
+            name: DEAD_LETTER_HEADER_ATTEMPTS.to_string(),
+            value: attempts.to_string(),
+        },
+    ]);
+    MessageStorage::new(storage_adapter.clone())
+        .append_topic_message(&topic.topic_id, vec![dead_letter])
+        .await?;
+
+    warn!(
+        "Queue message of topic {} at offset {} was not acknowledged after {} deliveries and was moved to the dead letter topic {}",
+        topic_name, original_offset, attempts, dead_letter_topic_name
+    );
+    Ok(())
+}
+
+// A message of the window that is not settled yet
+struct PendingQueueRecord<'a> {
+    record: &'a Record,
+    offset: u64,
+    publisher_client_id: String,
+    // The member of the previous delivery
+    last_member: Option<String>,
+}
+
+// A redelivery goes to another member if there is one
+fn select_queue_member(
+    subscribe_manager: &Arc<SubscribeManager>,
+    cache_manager: &Arc<CacheManager>,
+    share_leader_key: &str,
+    sub_data: &ShareLeaderSubscribeData,
+    dispatcher: &mut ShareDispatcher,
+    pending: &PendingQueueRecord,
+) -> Option<Subscriber> {
+    let mut excluded: HashSet<String> = pending.last_member.iter().cloned().collect();
+    let mut candidates = share_candidates(
+        subscribe_manager,
+        cache_manager,
+        share_leader_key,
+        &excluded,
+    );
+    if candidates.is_empty() && !excluded.is_empty() {
+        excluded.clear();
+        candidates = share_candidates(
+            subscribe_manager,
+            cache_manager,
+            share_leader_key,
+            &excluded,
+        );
+    }
+
+    dispatcher.select(
+        &candidates,
+        &pending.publisher_client_id,
+        &sub_data.topic_name,
+        |client_id| cache_manager.pkid_metadata.inflight_num(client_id),
+    )
+}
+
+// Whether the message is settled, that is acknowledged by the member or expired
+async fn send_queue_record(
+    connection_manager: &Arc<ConnectionManager>,
+    cache_manager: &Arc<CacheManager>,
+    group_id: &str,
+    pending: &PendingQueueRecord<'_>,
+    subscriber: &Subscriber,
+    visibility_timeout: Duration,
+    stop_sx: &Sender<bool>,
+) -> bool {
+    let qos = build_pub_qos(cache_manager, subscriber);
+    let sub_ids = build_sub_ids(subscriber);
+    let sub_pub_param = match build_publish_message(
+        cache_manager,
+        connection_manager,
+        &subscriber.client_id,
+        pending.record.to_owned(),
+        group_id,
+        &qos,
+        subscriber,
+        &sub_ids,
+    )
+    .await
+    {
+        Ok(Some(param)) => param,
+        // The message has expired
+        Ok(None) => return true,
+        Err(e) => {
+            warn!(
+                "Build queue message error. Error message : {}, offset: {}",
+                e, pending.offset
+            );
+            return false;
+        }
+    };
+
+    match timeout(
+        visibility_timeout,
+        send_to_share_member(
+            connection_manager,
+            cache_manager,
+            &sub_pub_param,
+            &qos,
+            stop_sx,
+        ),
+    )
+    .await
+    {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            warn!(
+                "Queue message was not delivered to {}, it will be delivered again. Error message :{}, offset: {}",
+                subscriber.client_id, e, pending.offset
+            );
+            false
+        }
+        Err(_) => {
+            cache_manager
+                .pkid_metadata
+                .remove_ack_packet(&subscriber.client_id, sub_pub_param.pkid);
+            warn!(
+                "Queue message was not acknowledged by {} within {}ms, it will be delivered again. offset: {}",
+                subscriber.client_id,
+                visibility_timeout.as_millis(),
+                pending.offset
+            );
+            false
+        }
+    }
+}
+
+// Delivers a window of messages of a queue group at the same time. Every message is delivered
+// until a member acknowledges it, or it runs out of delivery attempts and is moved to the dead
+// letter topic. Only then is the offset of the whole window committed, which is returned.
+#[allow(clippy::too_many_arguments)]
+async fn deliver_queue_window<S>(
+    connection_manager: &Arc<ConnectionManager>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    storage_adapter: &Arc<S>,
+    message_storage: &MessageStorage<S>,
+    delivery: &mut QueueDelivery<S>,
+    share_leader_key: &str,
+    sub_data: &ShareLeaderSubscribeData,
+    group_id: &str,
+    dispatcher: &mut ShareDispatcher,
+    records: &[Record],
+    stop_sx: &Sender<bool>,
+) -> Result<Option<u64>, MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let Some(last_offset) = records.iter().filter_map(|record| record.offset).max() else {
+        return Ok(None);
+    };
+
+    let mut pending = Vec::with_capacity(records.len());
+    for record in records.iter() {
+        let Some(offset) = record.offset else {
+            continue;
+        };
+        let publisher_client_id = if dispatcher.strategy() == ShareStrategy::HashClientId {
+            MqttMessage::decode_record(record.clone())?.client_id
+        } else {
+            String::new()
+        };
+        pending.push(PendingQueueRecord {
+            record,
+            offset,
+            publisher_client_id,
+            last_member: None,
+        });
+    }
+
+    while !pending.is_empty() {
+        let config = cache_manager.get_queue_subscription_config();
+        let mut waiting = Vec::new();
+        let mut assigned = Vec::new();
+        for mut item in pending {
+            let attempts = delivery.attempts(item.offset);
+            if attempts >= config.max_delivery_attempts.max(1) {
+                move_to_dead_letter(
+                    cache_manager,
+                    storage_adapter,
+                    &sub_data.topic_name,
+                    item.record,
+                    attempts,
+                )
+                .await?;
+                continue;
+            }
+
+            match select_queue_member(
+                subscribe_manager,
+                cache_manager,
+                share_leader_key,
+                sub_data,
+                dispatcher,
+                &item,
+            ) {
+                Some(subscriber) => {
+                    item.last_member = Some(subscriber.client_id.clone());
+                    assigned.push((item, subscriber));
+                }
+                None => waiting.push(item),
+            }
+        }
+
+        if assigned.is_empty() {
+            if !waiting.is_empty() {
+                // Queue messages are kept until a member is available again
+                sleep(Duration::from_secs(1)).await;
+            }
+            pending = waiting;
+            continue;
+        }
+
+        let offsets: Vec<u64> = assigned.iter().map(|(item, _)| item.offset).collect();
+        delivery.record_attempts(&offsets).await?;
+
+        let visibility_timeout = Duration::from_millis(config.visibility_timeout_ms.max(1));
+        let results = join_all(assigned.iter().map(|(item, subscriber)| {
+            send_queue_record(
+                connection_manager,
+                cache_manager,
+                group_id,
+                item,
+                subscriber,
+                visibility_timeout,
+                stop_sx,
+            )
+        }))
+        .await;
+
+        pending = waiting;
+        for ((item, _), settled) in assigned.into_iter().zip(results) {
+            if !settled {
+                pending.push(item);
+            }
+        }
+    }
+
+    loop_commit_offset(message_storage, &sub_data.topic_id, group_id, last_offset).await?;
+    delivery.settle(last_offset);
+    Ok(Some(last_offset))
+}
+
+// Returns the offset of the last message that was settled. Messages are delivered in windows
+// of at most max_inflight. A window that could not be settled stops the batch, so it is read
+// and delivered again instead of being skipped.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn deliver_queue_records<S>(
+    connection_manager: &Arc<ConnectionManager>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    storage_adapter: &Arc<S>,
+    message_storage: &MessageStorage<S>,
+    delivery: &mut QueueDelivery<S>,
+    share_leader_key: &str,
+    sub_data: &ShareLeaderSubscribeData,
+    group_id: &str,
+    dispatcher: &mut ShareDispatcher,
+    records: &[Record],
+    stop_sx: &Sender<bool>,
+) -> Option<u64>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let window = cache_manager
+        .get_queue_subscription_config()
+        .max_inflight
+        .max(1) as usize;
+    let mut success_num = 0;
+    let mut error_num = 0;
+    let mut settled_offset = None;
+    for window_records in records.chunks(window) {
+        match deliver_queue_window(
+            connection_manager,
+            cache_manager,
+            subscribe_manager,
+            storage_adapter,
+            message_storage,
+            delivery,
+            share_leader_key,
+            sub_data,
+            group_id,
+            dispatcher,
+            window_records,
+            stop_sx,
+        )
+        .await
+        {
+            Ok(offset) => {
+                success_num += window_records.len() as u64;
+                settled_offset = offset.or(settled_offset);
+            }
+            Err(e) => {
+                error_num += window_records.len() as u64;
+                if !is_ignore_push_error(&e) {
+                    warn!(
+                        "Queue push fail, offsets [{:?}, {:?}], error message:{},",
+                        window_records.first().and_then(|record| record.offset),
+                        window_records.last().and_then(|record| record.offset),
+                        e
+                    );
+                }
+                break;
+            }
+        }
+    }
+
+    subscribe_manager.update_subscribe_leader_push_thread_info(
+        share_leader_key,
+        success_num,
+        error_num,
+    );
+    settled_offset
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use common_base::tools::unique_id;
+    use common_config::mqtt::{config::BrokerMqttConfig, init_broker_mqtt_conf_by_config};
+    use storage_adapter::memory::MemoryStorageAdapter;
+
+    use super::{
+        fold_delivery_entries, need_snapshot, QueueDelivery, QueueDeliveryEntry,
+        QUEUE_DELIVERY_SNAPSHOT_INTERVAL,
+    };
+
+    fn entry(offset: u64, attempts: u32) -> QueueDeliveryEntry {
+        QueueDeliveryEntry {
+            group_id: "g1".to_string(),
+            offset,
+            attempts,
+            update_time: 0,
+        }
+    }
+
+    #[test]
+    fn fold_delivery_entries_test() {
+        let attempts = fold_delivery_entries(
+            HashMap::from([(2, 4), (4, 2), (5, 1)]),
+            vec![
+                entry(1, 1),
+                entry(3, 1),
+                entry(3, 2),
+                entry(4, 1),
+                entry(3, 1),
+            ],
+            3,
+        );
+        assert_eq!(attempts.len(), 3);
+        assert_eq!(attempts.get(&3), Some(&2));
+        assert_eq!(attempts.get(&4), Some(&2));
+        assert_eq!(attempts.get(&5), Some(&1));
+        assert!(!attempts.contains_key(&1));
+        assert!(!attempts.contains_key(&2));
+    }
+
+    #[test]
+    fn delivery_entry_encode_decode_test() {
+        let data = entry(7, 3);
+        assert_eq!(QueueDeliveryEntry::decode(&data.encode()).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn delivery_snapshot_test() {
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: unique_id(),
+            ..Default::default()
+        });
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+
+        let mut other = QueueDelivery::load(&storage_adapter, "g2", 0)
+            .await
+            .unwrap();
+        other.record_attempts(&[1]).await.unwrap();
+        other.save_snapshot(1).await.unwrap();
+
+        let mut delivery = QueueDelivery::load(&storage_adapter, "g1", 0)
+            .await
+            .unwrap();
+        assert_eq!(delivery.attempts(1), 0);
+        delivery.record_attempts(&[1, 2]).await.unwrap();
+        delivery.record_attempts(&[2]).await.unwrap();
+        delivery.settle(1);
+        // The changelog of g1 holds its own three entries only
+        delivery.save_snapshot(3).await.unwrap();
+
+        // Changes after the snapshot are read from the changelog
+        delivery.record_attempts(&[3]).await.unwrap();
+        // The changes before the snapshot are removed
+        let records = delivery
+            .message_storage
+            .read_topic_message(&delivery.shard_name, 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        let delivery = QueueDelivery::load(&storage_adapter, "g1", 2)
+            .await
+            .unwrap();
+        assert_eq!(delivery.snapshot_position, 3);
+        assert_eq!(delivery.attempts(1), 0);
+        assert_eq!(delivery.attempts(2), 2);
+        assert_eq!(delivery.attempts(3), 1);
+
+        let other = QueueDelivery::load(&storage_adapter, "g2", 0)
+            .await
+            .unwrap();
+        assert_eq!(other.attempts(1), 1);
+        assert!(!need_snapshot(4, 5));
+        assert!(need_snapshot(4, 4 + QUEUE_DELIVERY_SNAPSHOT_INTERVAL));
+    }
+}
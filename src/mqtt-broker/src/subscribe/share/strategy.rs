@@ -0,0 +1,239 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use rand::Rng;
+use strum_macros::{Display, EnumString};
+
+use crate::subscribe::common::Subscriber;
+
+// How the leader of a share group picks the member that receives a message
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ShareStrategy {
+    #[default]
+    RoundRobin,
+    Random,
+    // Keep sending to the same member until it is no longer available
+    Sticky,
+    // Messages from the same publisher go to the same member
+    HashClientId,
+    // Messages of the same topic go to the same member, which keeps them in order
+    HashTopic,
+    // The member with the fewest messages waiting for an ack
+    LeastInflight,
+}
+
+// The dispatch state of one share group on one topic
+#[derive(Default)]
+pub struct ShareDispatcher {
+    strategy: ShareStrategy,
+    seq: u64,
+    sticky_client_id: Option<String>,
+}
+
+impl ShareDispatcher {
+    pub fn new(strategy: ShareStrategy) -> Self {
+        ShareDispatcher {
+            strategy,
+            ..Default::default()
+        }
+    }
+
+    pub fn strategy(&self) -> ShareStrategy {
+        self.strategy
+    }
+
+    pub fn set_strategy(&mut self, strategy: ShareStrategy) {
+        if self.strategy != strategy {
+            self.strategy = strategy;
+            self.sticky_client_id = None;
+        }
+    }
+
+    // Picks one of the candidates, which must be sorted by client id so that the hash
+    // strategies choose the same member for the same key.
+    pub fn select<F>(
+        &mut self,
+        candidates: &[Subscriber],
+        publisher_client_id: &str,
+        topic_name: &str,
+        inflight_num: F,
+    ) -> Option<Subscriber>
+    where
+        F: Fn(&str) -> u64,
+    {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let subscriber = match self.strategy {
+            ShareStrategy::RoundRobin => {
+                self.seq = self.seq.wrapping_add(1);
+                &candidates[(self.seq % candidates.len() as u64) as usize]
+            }
+            ShareStrategy::Random => &candidates[rand::thread_rng().gen_range(0..candidates.len())],
+            ShareStrategy::Sticky => {
+                let current = self.sticky_client_id.as_ref().and_then(|client_id| {
+                    candidates
+                        .iter()
+                        .find(|subscriber| subscriber.client_id == *client_id)
+                });
+                let subscriber = current.unwrap_or_else(|| {
+                    &candidates[rand::thread_rng().gen_range(0..candidates.len())]
+                });
+                self.sticky_client_id = Some(subscriber.client_id.clone());
+                subscriber
+            }
+            ShareStrategy::HashClientId => rendezvous_hash(candidates, publisher_client_id),
+            ShareStrategy::HashTopic => rendezvous_hash(candidates, topic_name),
+            ShareStrategy::LeastInflight => {
+                self.seq = self.seq.wrapping_add(1);
+                let len = candidates.len();
+                // Start from a rotating position so that ties are spread across members
+                (0..len)
+                    .map(|i| &candidates[(self.seq as usize + i) % len])
+                    .min_by_key(|subscriber| inflight_num(&subscriber.client_id))
+                    .unwrap()
+            }
+        };
+        Some(subscriber.clone())
+    }
+}
+
+// Highest random weight hashing: a key keeps its member as long as that member stays in the
+// group, and only the keys of a leaving member move.
+fn rendezvous_hash<'a>(candidates: &'a [Subscriber], key: &str) -> &'a Subscriber {
+    candidates
+        .iter()
+        .max_by_key(|subscriber| {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            subscriber.client_id.hash(&mut hasher);
+            hasher.finish()
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use super::{ShareDispatcher, ShareStrategy};
+    use crate::subscribe::common::Subscriber;
+
+    fn build_candidates(client_ids: &[&str]) -> Vec<Subscriber> {
+        client_ids
+            .iter()
+            .map(|client_id| Subscriber {
+                client_id: client_id.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn select(dispatcher: &mut ShareDispatcher, candidates: &[Subscriber], key: &str) -> String {
+        dispatcher
+            .select(candidates, key, key, |_| 0)
+            .unwrap()
+            .client_id
+    }
+
+    #[test]
+    fn strategy_parse_test() {
+        assert_eq!(
+            ShareStrategy::from_str("hash_topic").unwrap(),
+            ShareStrategy::HashTopic
+        );
+        assert_eq!(
+            ShareStrategy::from_str("least_inflight").unwrap(),
+            ShareStrategy::LeastInflight
+        );
+        assert_eq!(ShareStrategy::RoundRobin.to_string(), "round_robin");
+        assert!(ShareStrategy::from_str("unknown").is_err());
+    }
+
+    #[test]
+    fn round_robin_test() {
+        let candidates = build_candidates(&["c1", "c2", "c3"]);
+        let mut dispatcher = ShareDispatcher::new(ShareStrategy::RoundRobin);
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for _ in 0..30 {
+            *counts
+                .entry(select(&mut dispatcher, &candidates, "t"))
+                .or_default() += 1;
+        }
+        assert_eq!(counts.len(), 3);
+        assert!(counts.values().all(|num| *num == 10));
+        assert!(dispatcher.select(&[], "c", "t", |_| 0).is_none());
+    }
+
+    #[test]
+    fn sticky_test() {
+        let candidates = build_candidates(&["c1", "c2", "c3"]);
+        let mut dispatcher = ShareDispatcher::new(ShareStrategy::Sticky);
+        let first = select(&mut dispatcher, &candidates, "t");
+        for _ in 0..10 {
+            assert_eq!(select(&mut dispatcher, &candidates, "t"), first);
+        }
+
+        let remaining: Vec<_> = candidates
+            .iter()
+            .filter(|subscriber| subscriber.client_id != first)
+            .cloned()
+            .collect();
+        let second = select(&mut dispatcher, &remaining, "t");
+        assert_ne!(second, first);
+        assert_eq!(select(&mut dispatcher, &candidates, "t"), second);
+    }
+
+    #[test]
+    fn hash_test() {
+        let candidates = build_candidates(&["c1", "c2", "c3", "c4"]);
+        let mut dispatcher = ShareDispatcher::new(ShareStrategy::HashTopic);
+        let mut owners = HashMap::new();
+        for i in 0..50 {
+            let topic = format!("device/{}/data", i);
+            let owner = select(&mut dispatcher, &candidates, &topic);
+            assert_eq!(select(&mut dispatcher, &candidates, &topic), owner);
+            owners.insert(topic, owner);
+        }
+
+        // Only the topics of the member that left move to another member
+        let remaining = build_candidates(&["c1", "c2", "c4"]);
+        for (topic, owner) in owners {
+            let new_owner = select(&mut dispatcher, &remaining, &topic);
+            if owner != "c3" {
+                assert_eq!(new_owner, owner);
+            } else {
+                assert_ne!(new_owner, "c3");
+            }
+        }
+    }
+
+    #[test]
+    fn least_inflight_test() {
+        let candidates = build_candidates(&["c1", "c2", "c3"]);
+        let mut dispatcher = ShareDispatcher::new(ShareStrategy::LeastInflight);
+        let inflight: HashMap<&str, u64> = HashMap::from([("c1", 5), ("c2", 0), ("c3", 2)]);
+        for _ in 0..5 {
+            let subscriber = dispatcher
+                .select(&candidates, "p", "t", |client_id| inflight[client_id])
+                .unwrap();
+            assert_eq!(subscriber.client_id, "c2");
+        }
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::{Arc, RwLock};
+
+const TOPIC_LEVEL_SEPARATOR: char = '/';
+const SINGLE_LEVEL_WILDCARD: &str = "+";
+const MULTI_LEVEL_WILDCARD: &str = "#";
+const SYSTEM_TOPIC_PREFIX: char = '$';
+
+struct TrieNode<V> {
+    children: HashMap<String, TrieNode<V>>,
+    values: HashSet<V>,
+}
+
+impl<V> Default for TrieNode<V> {
+    fn default() -> Self {
+        TrieNode {
+            children: HashMap::new(),
+            values: HashSet::new(),
+        }
+    }
+}
+
+impl<V> TrieNode<V> {
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.values.is_empty()
+    }
+}
+
+/// A trie over topic levels, shared by all clones.
+///
+/// The same structure is used two ways: with subscription filters as keys, `match_topic`
+/// returns the subscriptions a published topic is routed to; with topic names as keys,
+/// `match_filter` returns the topics a new subscription covers. Both walk at most one
+/// branch per wildcard and level, so the cost depends on the topic depth rather than on the
+/// number of keys. Wildcards follow the MQTT rules: `#` also matches the parent level and
+/// neither wildcard matches a first level starting with `$`.
+pub struct TopicTrie<V> {
+    root: Arc<RwLock<TrieNode<V>>>,
+}
+
+impl<V> Clone for TopicTrie<V> {
+    fn clone(&self) -> Self {
+        TopicTrie {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<V> Default for TopicTrie<V> {
+    fn default() -> Self {
+        TopicTrie {
+            root: Arc::new(RwLock::new(TrieNode::default())),
+        }
+    }
+}
+
+impl<V> TopicTrie<V>
+where
+    V: Clone + Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, key: &str, value: V) -> bool {
+        let mut root = self.root.write().unwrap();
+        let mut node = &mut *root;
+        for level in key.split(TOPIC_LEVEL_SEPARATOR) {
+            node = node.children.entry(level.to_owned()).or_default();
+        }
+        node.values.insert(value)
+    }
+
+    pub fn remove(&self, key: &str, value: &V) -> bool {
+        let levels: Vec<&str> = key.split(TOPIC_LEVEL_SEPARATOR).collect();
+        let mut root = self.root.write().unwrap();
+        remove_value(&mut root, &levels, value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.read().unwrap().is_empty()
+    }
+
+    // Keys are filters, returns the values of every filter that matches the topic name
+    pub fn match_topic(&self, topic_name: &str) -> Vec<V> {
+        let levels: Vec<&str> = topic_name.split(TOPIC_LEVEL_SEPARATOR).collect();
+        let root = self.root.read().unwrap();
+        let mut results = Vec::new();
+        collect_by_topic(&root, &levels, 0, &mut results);
+        results
+    }
+
+    // Keys are topic names, returns the values of every topic the filter matches
+    pub fn match_filter(&self, filter: &str) -> Vec<V> {
+        let levels: Vec<&str> = filter.split(TOPIC_LEVEL_SEPARATOR).collect();
+        let root = self.root.read().unwrap();
+        let mut results = Vec::new();
+        collect_by_filter(&root, &levels, 0, &mut results);
+        results
+    }
+}
+
+fn remove_value<V>(node: &mut TrieNode<V>, levels: &[&str], value: &V) -> bool
+where
+    V: Eq + Hash,
+{
+    let Some((level, rest)) = levels.split_first() else {
+        return node.values.remove(value);
+    };
+    let Some(child) = node.children.get_mut(*level) else {
+        return false;
+    };
+    let removed = remove_value(child, rest, value);
+    if child.is_empty() {
+        node.children.remove(*level);
+    }
+    removed
+}
+
+fn collect_by_topic<V>(node: &TrieNode<V>, levels: &[&str], depth: usize, results: &mut Vec<V>)
+where
+    V: Clone,
+{
+    let wildcard_allowed = depth > 0 || !is_system_level(levels);
+
+    if wildcard_allowed {
+        if let Some(child) = node.children.get(MULTI_LEVEL_WILDCARD) {
+            results.extend(child.values.iter().cloned());
+        }
+    }
+
+    let Some((level, rest)) = levels.split_first() else {
+        results.extend(node.values.iter().cloned());
+        return;
+    };
+
+    if wildcard_allowed {
+        if let Some(child) = node.children.get(SINGLE_LEVEL_WILDCARD) {
+            collect_by_topic(child, rest, depth + 1, results);
+        }
+    }
+    if let Some(child) = node.children.get(*level) {
+        collect_by_topic(child, rest, depth + 1, results);
+    }
+}
+
+fn collect_by_filter<V>(node: &TrieNode<V>, levels: &[&str], depth: usize, results: &mut Vec<V>)
+where
+    V: Clone,
+{
+    let Some((level, rest)) = levels.split_first() else {
+        results.extend(node.values.iter().cloned());
+        return;
+    };
+
+    match *level {
+        MULTI_LEVEL_WILDCARD => {
+            results.extend(node.values.iter().cloned());
+            for (name, child) in node.children.iter() {
+                if depth == 0 && name.starts_with(SYSTEM_TOPIC_PREFIX) {
+                    continue;
+                }
+                collect_subtree(child, results);
+            }
+        }
+        SINGLE_LEVEL_WILDCARD => {
+            for (name, child) in node.children.iter() {
+                if depth == 0 && name.starts_with(SYSTEM_TOPIC_PREFIX) {
+                    continue;
+                }
+                collect_by_filter(child, rest, depth + 1, results);
+            }
+        }
+        _ => {
+            if let Some(child) = node.children.get(*level) {
+                collect_by_filter(child, rest, depth + 1, results);
+            }
+        }
+    }
+}
+
+fn collect_subtree<V>(node: &TrieNode<V>, results: &mut Vec<V>)
+where
+    V: Clone,
+{
+    results.extend(node.values.iter().cloned());
+    for child in node.children.values() {
+        collect_subtree(child, results);
+    }
+}
+
+fn is_system_level(levels: &[&str]) -> bool {
+    levels
+        .first()
+        .is_some_and(|level| level.starts_with(SYSTEM_TOPIC_PREFIX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TopicTrie;
+
+    fn sorted(mut values: Vec<&'static str>) -> Vec<&'static str> {
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn match_topic_test() {
+        let trie = TopicTrie::new();
+        trie.insert("/sensor/+/temperature", "plus");
+        trie.insert("/sensor/#", "hash");
+        trie.insert("/sensor/1/temperature", "exact");
+        trie.insert("#", "all");
+        trie.insert("+/+", "two_levels");
+
+        assert_eq!(
+            sorted(trie.match_topic("/sensor/1/temperature")),
+            vec!["all", "exact", "hash", "plus"]
+        );
+        assert_eq!(
+            sorted(trie.match_topic("/sensor")),
+            vec!["all", "hash", "two_levels"]
+        );
+        assert_eq!(
+            sorted(trie.match_topic("/sensor/1/2/temperature")),
+            vec!["all", "hash"]
+        );
+        assert_eq!(sorted(trie.match_topic("a/b")), vec!["all", "two_levels"]);
+
+        // Wildcards at the first level do not match system topics
+        assert!(trie.match_topic("$SYS/brokers").is_empty());
+        trie.insert("$SYS/#", "sys");
+        assert_eq!(trie.match_topic("$SYS/brokers"), vec!["sys"]);
+    }
+
+    #[test]
+    fn match_filter_test() {
+        let trie = TopicTrie::new();
+        for topic in [
+            "/sensor/1/temperature",
+            "/sensor/2/temperature",
+            "/sensor/2/humidity",
+            "/sensor",
+            "$SYS/brokers",
+        ] {
+            trie.insert(topic, topic);
+        }
+
+        assert_eq!(
+            sorted(trie.match_filter("/sensor/+/temperature")),
+            vec!["/sensor/1/temperature", "/sensor/2/temperature"]
+        );
+        assert_eq!(trie.match_filter("/sensor/#").len(), 4);
+        assert_eq!(trie.match_filter("/sensor"), vec!["/sensor"]);
+        assert_eq!(trie.match_filter("#").len(), 4);
+        assert_eq!(trie.match_filter("$SYS/+"), vec!["$SYS/brokers"]);
+        assert!(trie.match_filter("/sensor/3/+").is_empty());
+    }
+
+    #[test]
+    fn remove_test() {
+        let trie = TopicTrie::new();
+        trie.insert("/a/+/c", "c1");
+        trie.insert("/a/+/c", "c2");
+        assert!(!trie.remove("/a/+/d", &"c1"));
+
+        assert!(trie.remove("/a/+/c", &"c1"));
+        assert_eq!(trie.match_topic("/a/b/c"), vec!["c2"]);
+
+        assert!(trie.remove("/a/+/c", &"c2"));
+        assert!(trie.match_topic("/a/b/c").is_empty());
+        assert!(trie.is_empty());
+    }
+}
@@ -0,0 +1,396 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use bincode::{deserialize, serialize};
+use common_base::tools::now_second;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::core::error::PlacementCenterError;
+use crate::storage::keys::key_node_prefix_all;
+use crate::storage::rocksdb::{RocksDBEngine, DB_COLUMN_FAMILY_CLUSTER};
+
+/// Magic bytes at the head of every backup archive.
+pub const BACKUP_MAGIC: &[u8; 4] = b"RMQB";
+
+/// Version of the archive layout. Bump it whenever [`MetadataBackup`] changes shape.
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const BACKUP_HEADER_LEN: usize = 12;
+
+/// Upper bound of the keys and values restored by one Raft entry.
+pub const RESTORE_CHUNK_MAX_BYTES: usize = 1024 * 1024;
+
+/// Groups of metadata that can be selected when taking a backup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupResource {
+    Cluster,
+    Mqtt,
+    Journal,
+    Offset,
+    Idempotent,
+}
+
+impl BackupResource {
+    pub fn all() -> Vec<BackupResource> {
+        vec![
+            BackupResource::Cluster,
+            BackupResource::Mqtt,
+            BackupResource::Journal,
+            BackupResource::Offset,
+            BackupResource::Idempotent,
+        ]
+    }
+
+    pub fn key_prefixes(&self) -> Vec<&'static str> {
+        match self {
+            BackupResource::Cluster => vec!["/clusters/", "/config/"],
+            BackupResource::Mqtt => vec!["/mqtt/"],
+            BackupResource::Journal => vec!["/journal/"],
+            BackupResource::Offset => vec!["/offset/"],
+            BackupResource::Idempotent => vec!["/idempotent/"],
+        }
+    }
+}
+
+impl FromStr for BackupResource {
+    type Err = PlacementCenterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cluster" => Ok(BackupResource::Cluster),
+            "mqtt" => Ok(BackupResource::Mqtt),
+            "journal" => Ok(BackupResource::Journal),
+            "offset" => Ok(BackupResource::Offset),
+            "idempotent" => Ok(BackupResource::Idempotent),
+            _ => Err(PlacementCenterError::CommonError(format!(
+                "Unsupported backup resource {}, optional: cluster, mqtt, journal, offset, idempotent",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataBackup {
+    pub version: u32,
+    pub create_time: u64,
+    pub resources: Vec<BackupResource>,
+    pub records: Vec<(String, Vec<u8>)>,
+}
+
+impl MetadataBackup {
+    /// Layout: magic(4) + version(4, BE) + crc32 of payload(4, BE) + bincode payload.
+    pub fn encode(&self) -> Result<Vec<u8>, PlacementCenterError> {
+        let payload = serialize(self)?;
+        let mut data = Vec::with_capacity(BACKUP_HEADER_LEN + payload.len());
+        data.extend_from_slice(BACKUP_MAGIC);
+        data.extend_from_slice(&self.version.to_be_bytes());
+        data.extend_from_slice(&crc32fast::hash(&payload).to_be_bytes());
+        data.extend_from_slice(&payload);
+        Ok(data)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<MetadataBackup, PlacementCenterError> {
+        if data.len() < BACKUP_HEADER_LEN || &data[0..4] != BACKUP_MAGIC {
+            return Err(PlacementCenterError::InvalidBackupArchive(
+                "missing archive header".to_string(),
+            ));
+        }
+
+        let version = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        if version > BACKUP_FORMAT_VERSION {
+            return Err(PlacementCenterError::InvalidBackupArchive(format!(
+                "archive version {} is newer than supported version {}",
+                version, BACKUP_FORMAT_VERSION
+            )));
+        }
+
+        let crc = u32::from_be_bytes([data[8], data[9], data[10], data[11]]);
+        let payload = &data[BACKUP_HEADER_LEN..];
+        if crc32fast::hash(payload) != crc {
+            return Err(PlacementCenterError::InvalidBackupArchive(
+                "checksum mismatch".to_string(),
+            ));
+        }
+
+        Ok(deserialize::<MetadataBackup>(payload)?)
+    }
+}
+
+/// Build a backup from all records of the selected resources.
+///
+/// The records are read by a single iterator, which RocksDB pins to an implicit snapshot,
+/// so the archive reflects one consistent point in time.
+pub fn build_backup(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    resources: &[BackupResource],
+) -> Result<MetadataBackup, PlacementCenterError> {
+    let cf = if let Some(cf) = rocksdb_engine_handler.cf_handle(DB_COLUMN_FAMILY_CLUSTER) {
+        cf
+    } else {
+        return Err(PlacementCenterError::RocksDBFamilyNotAvailable(
+            DB_COLUMN_FAMILY_CLUSTER.to_string(),
+        ));
+    };
+
+    let prefixes: Vec<&str> = resources.iter().flat_map(|r| r.key_prefixes()).collect();
+    // Broker node registrations are tied to running processes and are rebuilt by heartbeats.
+    let node_prefix = key_node_prefix_all();
+
+    let records = rocksdb_engine_handler
+        .read_all_by_cf(cf)?
+        .into_iter()
+        .filter(|(key, _)| {
+            !key.starts_with(&node_prefix) && prefixes.iter().any(|p| key.starts_with(p))
+        })
+        .collect();
+
+    Ok(MetadataBackup {
+        version: BACKUP_FORMAT_VERSION,
+        create_time: now_second(),
+        resources: resources.to_vec(),
+        records,
+    })
+}
+
+/// Split a backup into backups whose keys and values add up to at most `max_bytes`, so each
+/// of them fits a single Raft entry. A record larger than `max_bytes` gets a chunk of its own,
+/// a backup without records still yields one chunk.
+pub fn split_backup(backup: &MetadataBackup, max_bytes: usize) -> Vec<MetadataBackup> {
+    let new_chunk = || MetadataBackup {
+        version: backup.version,
+        create_time: backup.create_time,
+        resources: backup.resources.clone(),
+        records: Vec::new(),
+    };
+
+    let mut chunks = Vec::new();
+    let mut chunk = new_chunk();
+    let mut chunk_bytes = 0;
+    for (key, value) in backup.records.iter() {
+        let size = key.len() + value.len();
+        if !chunk.records.is_empty() && chunk_bytes + size > max_bytes {
+            chunks.push(std::mem::replace(&mut chunk, new_chunk()));
+            chunk_bytes = 0;
+        }
+        chunk.records.push((key.clone(), value.clone()));
+        chunk_bytes += size;
+    }
+    chunks.push(chunk);
+    chunks
+}
+
+/// Whether the storage already holds records of any of the given resources.
+pub fn has_metadata(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    resources: &[BackupResource],
+) -> Result<bool, PlacementCenterError> {
+    let cf = if let Some(cf) = rocksdb_engine_handler.cf_handle(DB_COLUMN_FAMILY_CLUSTER) {
+        cf
+    } else {
+        return Err(PlacementCenterError::RocksDBFamilyNotAvailable(
+            DB_COLUMN_FAMILY_CLUSTER.to_string(),
+        ));
+    };
+
+    for prefix in resources.iter().flat_map(|r| r.key_prefixes()) {
+        if rocksdb_engine_handler.exist_prefix(cf.clone(), prefix)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Write the records of a backup into the storage. Unless `force` is set, the storage must not
+/// hold any records of the resources contained in the backup.
+pub fn restore_backup(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    backup: &MetadataBackup,
+    force: bool,
+) -> Result<(), PlacementCenterError> {
+    let cf = if let Some(cf) = rocksdb_engine_handler.cf_handle(DB_COLUMN_FAMILY_CLUSTER) {
+        cf
+    } else {
+        return Err(PlacementCenterError::RocksDBFamilyNotAvailable(
+            DB_COLUMN_FAMILY_CLUSTER.to_string(),
+        ));
+    };
+
+    if !force && has_metadata(rocksdb_engine_handler, &backup.resources)? {
+        return Err(PlacementCenterError::RestoreTargetNotEmpty);
+    }
+
+    let now = Instant::now();
+    for (key, value) in backup.records.iter() {
+        rocksdb_engine_handler.write_raw(cf.clone(), key, value)?;
+    }
+    info!(
+        "Restored {} records from backup created at {}, time: {}",
+        backup.records.len(),
+        backup.create_time,
+        now.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tempfile::tempdir;
+
+    use super::{
+        build_backup, has_metadata, restore_backup, split_backup, BackupResource, MetadataBackup,
+        BACKUP_FORMAT_VERSION,
+    };
+    use crate::storage::rocksdb::{
+        column_family_list, storage_data_fold, RocksDBEngine, DB_COLUMN_FAMILY_CLUSTER,
+    };
+
+    fn new_engine(path: &str) -> Arc<RocksDBEngine> {
+        Arc::new(RocksDBEngine::new(
+            &storage_data_fold(path),
+            100,
+            column_family_list(),
+        ))
+    }
+
+    fn write_sample(engine: &Arc<RocksDBEngine>) {
+        let cf = engine.cf_handle(DB_COLUMN_FAMILY_CLUSTER).unwrap();
+        engine
+            .write(cf.clone(), "/mqtt/user/c1/u1", &"user".to_string())
+            .unwrap();
+        engine
+            .write(cf.clone(), "/journal/shard/c1/n1/s1", &"shard".to_string())
+            .unwrap();
+        engine
+            .write(cf.clone(), "/clusters/node/c1/1", &"node".to_string())
+            .unwrap();
+        engine
+            .write(cf, "/config/c1/feature", &"config".to_string())
+            .unwrap();
+    }
+
+    #[test]
+    fn build_backup_filter_test() {
+        let path = tempdir().unwrap();
+        let engine = new_engine(path.path().to_str().unwrap());
+        write_sample(&engine);
+
+        let backup = build_backup(&engine, &BackupResource::all()).unwrap();
+        assert_eq!(backup.version, BACKUP_FORMAT_VERSION);
+        assert_eq!(backup.records.len(), 3);
+        assert!(!backup
+            .records
+            .iter()
+            .any(|(key, _)| key.starts_with("/clusters/node/")));
+
+        let backup = build_backup(&engine, &[BackupResource::Mqtt]).unwrap();
+        assert_eq!(backup.records.len(), 1);
+        assert_eq!(backup.records[0].0, "/mqtt/user/c1/u1");
+    }
+
+    #[test]
+    fn encode_decode_test() {
+        let backup = MetadataBackup {
+            version: BACKUP_FORMAT_VERSION,
+            create_time: 1,
+            resources: vec![BackupResource::Mqtt],
+            records: vec![("/mqtt/user/c1/u1".to_string(), b"v".to_vec())],
+        };
+        let mut data = backup.encode().unwrap();
+        assert_eq!(MetadataBackup::decode(&data).unwrap(), backup);
+
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        assert!(MetadataBackup::decode(&data).is_err());
+        assert!(MetadataBackup::decode(b"RMQ").is_err());
+    }
+
+    #[test]
+    fn split_backup_test() {
+        let mut backup = MetadataBackup {
+            version: BACKUP_FORMAT_VERSION,
+            create_time: 1,
+            resources: vec![BackupResource::Mqtt],
+            records: Vec::new(),
+        };
+        assert_eq!(split_backup(&backup, 10), vec![backup.clone()]);
+
+        backup.records = vec![
+            ("/mqtt/a".to_string(), b"1".to_vec()),
+            ("/mqtt/b".to_string(), b"2".to_vec()),
+            ("/mqtt/c".to_string(), b"3333333333".to_vec()),
+            ("/mqtt/d".to_string(), b"4".to_vec()),
+        ];
+        let chunks = split_backup(&backup, 16);
+        let sizes: Vec<usize> = chunks.iter().map(|chunk| chunk.records.len()).collect();
+        assert_eq!(sizes, vec![2, 1, 1]);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.resources == backup.resources && chunk.create_time == 1));
+        let records: Vec<(String, Vec<u8>)> =
+            chunks.into_iter().flat_map(|chunk| chunk.records).collect();
+        assert_eq!(records, backup.records);
+    }
+
+    #[test]
+    fn restore_backup_test() {
+        let source = tempdir().unwrap();
+        let engine = new_engine(source.path().to_str().unwrap());
+        write_sample(&engine);
+        let backup = build_backup(&engine, &BackupResource::all()).unwrap();
+
+        let target = tempdir().unwrap();
+        let new_engine = new_engine(target.path().to_str().unwrap());
+        restore_backup(&new_engine, &backup, false).unwrap();
+
+        let cf = new_engine.cf_handle(DB_COLUMN_FAMILY_CLUSTER).unwrap();
+        let value = new_engine
+            .read::<String>(cf, "/mqtt/user/c1/u1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(value, "user");
+
+        // the target is no longer empty
+        assert!(restore_backup(&new_engine, &backup, false).is_err());
+        assert!(restore_backup(&new_engine, &backup, true).is_ok());
+    }
+
+    #[test]
+    fn restore_partial_backup_test() {
+        let source = tempdir().unwrap();
+        let engine = new_engine(source.path().to_str().unwrap());
+        write_sample(&engine);
+        let backup = build_backup(&engine, &[BackupResource::Mqtt]).unwrap();
+
+        // only the resources in the backup have to be empty in the target
+        let target = tempdir().unwrap();
+        let new_engine = new_engine(target.path().to_str().unwrap());
+        let cf = new_engine.cf_handle(DB_COLUMN_FAMILY_CLUSTER).unwrap();
+        new_engine
+            .write(cf, "/journal/shard/c1/n1/s1", &"shard".to_string())
+            .unwrap();
+        assert!(!has_metadata(&new_engine, &[BackupResource::Mqtt]).unwrap());
+        assert!(has_metadata(&new_engine, &BackupResource::all()).unwrap());
+
+        restore_backup(&new_engine, &backup, false).unwrap();
+        assert!(has_metadata(&new_engine, &[BackupResource::Mqtt]).unwrap());
+    }
+}
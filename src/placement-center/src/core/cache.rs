@@ -39,7 +39,7 @@ pub struct PlacementCacheManager {
 
 impl PlacementCacheManager {
     pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> PlacementCacheManager {
-        let mut cache = PlacementCacheManager {
+        let cache = PlacementCacheManager {
             cluster_list: DashMap::with_capacity(2),
             node_heartbeat: DashMap::with_capacity(2),
             node_list: DashMap::with_capacity(2),
@@ -156,7 +156,7 @@ pub fn get_broker_heart(&self, cluster_name: &str, node_id: u64) -> Option<NodeH
         None
     }
 
-    pub fn load_cache(&mut self, rocksdb_engine_handler: Arc<RocksDBEngine>) {
+    pub fn load_cache(&self, rocksdb_engine_handler: Arc<RocksDBEngine>) {
         let cluster = ClusterStorage::new(rocksdb_engine_handler.clone());
         if let Ok(result) = cluster.list(None) {
             for cluster in result {
@@ -175,4 +175,12 @@ pub fn load_cache(&mut self, rocksdb_engine_handler: Arc<RocksDBEngine>) {
     fn node_key(&self, cluster_name: &str, node_id: u64) -> String {
         format!("{}_{}", cluster_name, node_id)
     }
+
+    pub fn resource_sizes(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("cluster", self.cluster_list.len()),
+            ("node", self.node_list.iter().map(|list| list.len()).sum()),
+            ("node_heartbeat", self.node_heartbeat.len()),
+        ]
+    }
 }
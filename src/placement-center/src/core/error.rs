@@ -155,4 +155,12 @@ pub enum PlacementCenterError {
 
     #[error("Schema [{0}] already exist")]
     SchemaAlreadyExist(String),
+
+    #[error("Invalid backup archive, {0}")]
+    InvalidBackupArchive(String),
+
+    #[error(
+        "Restore target is not empty, metadata can only be restored into a fresh placement center"
+    )]
+    RestoreTargetNotEmpty,
 }
@@ -167,3 +167,179 @@ pub fn metrics_rocksdb_storage_err_inc(labels: RocksDBLabels) {
 pub fn metrics_rocksdb_stroge_total_ms(labels: RocksDBLabels, ms: f64) {
     common_base::histogram_metric_observe!(ROCKSDB_STORAGE_TOTAL_MS, ms, labels)
 }
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq, Default)]
+pub struct RaftLabel {
+    pub node_id: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq, Default)]
+pub struct RaftReplicationLabel {
+    pub node_id: String,
+    pub follower_id: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq, Default)]
+pub struct RaftApplyLabel {
+    pub entry_type: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq, Default)]
+pub struct CacheLabel {
+    pub cache: String,
+    pub resource: String,
+}
+
+common_base::register_gauge_metric!(
+    RAFT_CURRENT_TERM,
+    "raft.current.term",
+    "Current raft term of the node",
+    RaftLabel
+);
+
+common_base::register_gauge_metric!(
+    RAFT_LAST_LOG_INDEX,
+    "raft.last.log.index",
+    "Index of the last log appended to the local raft log",
+    RaftLabel
+);
+
+common_base::register_gauge_metric!(
+    RAFT_LAST_APPLIED_INDEX,
+    "raft.last.applied.index",
+    "Index of the last log applied to the state machine",
+    RaftLabel
+);
+
+common_base::register_gauge_metric!(
+    RAFT_APPLY_LAG,
+    "raft.apply.lag",
+    "Number of logs appended locally but not yet applied to the state machine",
+    RaftLabel
+);
+
+common_base::register_gauge_metric!(
+    RAFT_COMMIT_LAG,
+    "raft.commit.lag",
+    "Number of logs the leader has appended but a follower has not yet matched",
+    RaftReplicationLabel
+);
+
+common_base::register_histogram_metric!(
+    RAFT_APPLY_MS,
+    "raft.state.machine.apply.ms",
+    "TotalMs of applying a raft log entry to the state machine",
+    RaftApplyLabel,
+    0.1,
+    2.0,
+    12
+);
+
+common_base::register_gauge_metric!(
+    CACHE_SIZE,
+    "cache.size",
+    "Number of entries held in the placement center caches",
+    CacheLabel
+);
+
+pub fn metrics_raft_status(
+    node_id: u64,
+    current_term: u64,
+    last_log_index: u64,
+    last_applied_index: u64,
+) {
+    let label = RaftLabel {
+        node_id: node_id.to_string(),
+    };
+    RAFT_CURRENT_TERM
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .set(current_term as i64);
+    RAFT_LAST_LOG_INDEX
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .set(last_log_index as i64);
+    RAFT_LAST_APPLIED_INDEX
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .set(last_applied_index as i64);
+    RAFT_APPLY_LAG
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .set(last_log_index.saturating_sub(last_applied_index) as i64);
+}
+
+pub fn metrics_raft_commit_lag(node_id: u64, follower_id: u64, lag: u64) {
+    let label = RaftReplicationLabel {
+        node_id: node_id.to_string(),
+        follower_id: follower_id.to_string(),
+    };
+    RAFT_COMMIT_LAG
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .set(lag as i64);
+}
+
+pub fn metrics_raft_commit_lag_clear() {
+    RAFT_COMMIT_LAG.write().unwrap().clear();
+}
+
+pub fn metrics_raft_apply_ms(entry_type: &str, ms: f64) {
+    let label = RaftApplyLabel {
+        entry_type: entry_type.to_string(),
+    };
+    common_base::histogram_metric_observe!(RAFT_APPLY_MS, ms, label)
+}
+
+pub fn metrics_cache_size(cache: &str, resource: &str, size: usize) {
+    let label = CacheLabel {
+        cache: cache.to_string(),
+        resource: resource.to_string(),
+    };
+    CACHE_SIZE
+        .read()
+        .unwrap()
+        .get_or_create(&label)
+        .set(size as i64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raft_status_metrics_test() {
+        metrics_raft_status(1, 3, 120, 100);
+        let label = RaftLabel {
+            node_id: "1".to_string(),
+        };
+        let mut lag = 0;
+        common_base::gauge_metric_get!(RAFT_APPLY_LAG, label, lag);
+        assert_eq!(lag, 20);
+
+        // a follower that is ahead of the leader's view never reports a negative lag
+        metrics_raft_status(1, 3, 100, 120);
+        let label = RaftLabel {
+            node_id: "1".to_string(),
+        };
+        common_base::gauge_metric_get!(RAFT_APPLY_LAG, label, lag);
+        assert_eq!(lag, 0);
+    }
+
+    #[test]
+    fn cache_size_metrics_test() {
+        metrics_cache_size("mqtt", "topic", 12);
+        let label = CacheLabel {
+            cache: "mqtt".to_string(),
+            resource: "topic".to_string(),
+        };
+        let mut size = 0;
+        common_base::gauge_metric_get!(CACHE_SIZE, label, size);
+        assert_eq!(size, 12);
+    }
+}
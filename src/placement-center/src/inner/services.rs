@@ -12,28 +12,34 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::core::backup::{
+    build_backup, has_metadata, split_backup, BackupResource, MetadataBackup,
+    RESTORE_CHUNK_MAX_BYTES,
+};
 use crate::core::cache::PlacementCacheManager;
 use crate::core::error::PlacementCenterError;
 use crate::mqtt::controller::call_broker::{
-    update_cache_by_set_resource_config, MQTTInnerCallManager,
+    update_cache_by_reload_metadata, update_cache_by_set_resource_config, MQTTInnerCallManager,
 };
 use crate::route::apply::RaftMachineApply;
 use crate::route::data::{StorageData, StorageDataType};
 use crate::storage::placement::config::ResourceConfigStorage;
 use crate::storage::placement::idempotent::IdempotentStorage;
 use crate::storage::placement::offset::OffsetStorage;
+use common_base::logging::{get_log_filter, set_log_filter};
 use common_base::tools::now_second;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::resource_config::ClusterResourceConfig;
 use prost::Message;
 use protocol::placement_center::placement_center_inner::{
-    ClusterStatusReply, DeleteIdempotentDataReply, DeleteIdempotentDataRequest,
-    DeleteResourceConfigReply, DeleteResourceConfigRequest, ExistsIdempotentDataReply,
-    ExistsIdempotentDataRequest, GetOffsetDataReply, GetOffsetDataReplyOffset,
-    GetOffsetDataRequest, GetResourceConfigReply, GetResourceConfigRequest, HeartbeatReply,
-    HeartbeatRequest, NodeListReply, NodeListRequest, SaveOffsetDataReply, SaveOffsetDataRequest,
-    SetIdempotentDataReply, SetIdempotentDataRequest, SetResourceConfigReply,
-    SetResourceConfigRequest,
+    BackupMetadataReply, BackupMetadataRequest, ClusterStatusReply, ClusterType,
+    DeleteIdempotentDataReply, DeleteIdempotentDataRequest, DeleteResourceConfigReply,
+    DeleteResourceConfigRequest, ExistsIdempotentDataReply, ExistsIdempotentDataRequest,
+    GetLogLevelReply, GetOffsetDataReply, GetOffsetDataReplyOffset, GetOffsetDataRequest,
+    GetResourceConfigReply, GetResourceConfigRequest, HeartbeatReply, HeartbeatRequest,
+    NodeListReply, NodeListRequest, RestoreMetadataReply, RestoreMetadataRequest,
+    SaveOffsetDataReply, SaveOffsetDataRequest, SetIdempotentDataReply, SetIdempotentDataRequest,
+    SetLogLevelReply, SetLogLevelRequest, SetResourceConfigReply, SetResourceConfigRequest,
 };
 use rocksdb_engine::RocksDBEngine;
 use std::sync::Arc;
@@ -55,6 +61,95 @@ pub async fn cluster_status_by_req(
     Ok(reply)
 }
 
+pub fn set_log_level_by_req(
+    req: &SetLogLevelRequest,
+) -> Result<SetLogLevelReply, PlacementCenterError> {
+    let info = set_log_filter(&req.filter, req.revert_after_secs)?;
+    Ok(SetLogLevelReply {
+        filter: info.directives,
+        revert_at: info.revert_at,
+    })
+}
+
+pub fn get_log_level_by_req() -> Result<GetLogLevelReply, PlacementCenterError> {
+    let info = get_log_filter();
+    Ok(GetLogLevelReply {
+        filter: info.directives,
+        revert_at: info.revert_at,
+    })
+}
+
+pub fn backup_metadata_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &BackupMetadataRequest,
+) -> Result<BackupMetadataReply, PlacementCenterError> {
+    let resources = if req.resources.is_empty() {
+        BackupResource::all()
+    } else {
+        req.resources
+            .iter()
+            .map(|raw| raw.parse::<BackupResource>())
+            .collect::<Result<Vec<BackupResource>, PlacementCenterError>>()?
+    };
+
+    let backup = build_backup(rocksdb_engine_handler, &resources)?;
+    Ok(BackupMetadataReply {
+        records: backup.records.len() as u64,
+        create_time: backup.create_time,
+        data: backup.encode()?,
+    })
+}
+
+pub async fn restore_metadata_by_req(
+    raft_machine_apply: &Arc<RaftMachineApply>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    cluster_cache: &Arc<PlacementCacheManager>,
+    call_manager: &Arc<MQTTInnerCallManager>,
+    client_pool: &Arc<ClientPool>,
+    req: &RestoreMetadataRequest,
+) -> Result<RestoreMetadataReply, PlacementCenterError> {
+    let backup = MetadataBackup::decode(&req.data)?;
+    if !req.force && has_metadata(rocksdb_engine_handler, &backup.resources)? {
+        return Err(PlacementCenterError::RestoreTargetNotEmpty);
+    }
+
+    // The archive is proposed in chunks that each fit a Raft entry. The state machine checks
+    // the target again with the first chunk, the following chunks are written next to it. A
+    // failed chunk leaves the chunks before it written, the restore is repeated with force.
+    let chunks = split_backup(&backup, RESTORE_CHUNK_MAX_BYTES);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let chunk_req = RestoreMetadataRequest {
+            data: chunk.encode()?,
+            force: req.force || i > 0,
+        };
+        let data = StorageData::new(
+            StorageDataType::ClusterRestoreMetadata,
+            RestoreMetadataRequest::encode_to_vec(&chunk_req),
+        );
+        // A chunk refused by the state machine replies nothing, the reason is in its log
+        let reply = raft_machine_apply.client_write(data).await?;
+        if reply.and_then(|resp| resp.data.value).is_none() {
+            return Err(PlacementCenterError::ExecutionResultIsEmpty);
+        }
+    }
+
+    let data = StorageData::new(StorageDataType::ClusterReloadMetadataCache, Vec::new());
+    raft_machine_apply.client_write(data).await?;
+
+    // The brokers keep their own cache of the metadata
+    for cluster in cluster_cache.get_all_cluster() {
+        if cluster.cluster_type == *ClusterType::MqttBrokerServer.as_str_name() {
+            update_cache_by_reload_metadata(&cluster.cluster_name, call_manager, client_pool)
+                .await?;
+        }
+    }
+
+    Ok(RestoreMetadataReply {
+        records: backup.records.len() as u64,
+        create_time: backup.create_time,
+    })
+}
+
 pub async fn node_list_by_req(
     cluster_cache: &Arc<PlacementCacheManager>,
     req: &NodeListRequest,
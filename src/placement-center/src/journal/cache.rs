@@ -289,6 +289,22 @@ fn segment_key(
             cluster_name, namespace, shard_name, segment_seq
         )
     }
+
+    pub fn resource_sizes(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("shard", self.shard_list.len()),
+            (
+                "segment",
+                self.segment_list.iter().map(|list| list.len()).sum(),
+            ),
+            (
+                "segment_meta",
+                self.segment_meta_list.iter().map(|list| list.len()).sum(),
+            ),
+            ("wait_delete_shard", self.wait_delete_shard_list.len()),
+            ("wait_delete_segment", self.wait_delete_segment_list.len()),
+        ]
+    }
 }
 
 pub fn load_journal_cache(
@@ -23,16 +23,19 @@
 use mqtt::connector::scheduler::start_connector_scheduler;
 use mqtt::controller::call_broker::{mqtt_call_thread_manager, MQTTInnerCallManager};
 use openraft::Raft;
+use pprof_monitor::pprof_monitor::start_pprof_monitor;
 use raft::leadership::monitoring_leader_transition;
 use server::grpc::server::start_grpc_server;
 use storage::rocksdb::{column_family_list, storage_data_fold, RocksDBEngine};
-use tokio::signal;
+use tokio::runtime::Handle;
 use tokio::sync::broadcast::Sender;
 use tokio::time::sleep;
+use tokio::{select, signal};
 use tracing::info;
 
 use crate::core::cache::PlacementCacheManager;
 use crate::core::controller::ClusterController;
+use crate::core::metrics::metrics_cache_size;
 use crate::journal::cache::{load_journal_cache, JournalCacheManager};
 use crate::journal::controller::call_node::{journal_call_thread_manager, JournalInnerCallManager};
 use crate::mqtt::cache::MqttCacheManager;
@@ -124,6 +127,10 @@ pub async fn start(&mut self, stop_send: Sender<bool>) {
 
         self.start_prometheus();
 
+        self.start_pprof_monitor();
+
+        self.start_cache_metrics(stop_send.clone());
+
         self.start_grpc_server(placement_center_storage.clone());
 
         self.monitoring_leader_transition(openraft_node.clone(), placement_center_storage.clone());
@@ -227,6 +234,48 @@ fn start_prometheus(&self) {
         }
     }
 
+    fn start_pprof_monitor(&self) {
+        let conf = placement_center_conf();
+        if conf.pprof.enable {
+            let runtimes = vec![("main-runtime".to_string(), Handle::current())];
+            tokio::spawn(async move {
+                start_pprof_monitor(conf.pprof.clone(), runtimes).await;
+            });
+        }
+    }
+
+    // Periodically export the number of entries held in each cache
+    fn start_cache_metrics(&self, stop_send: Sender<bool>) {
+        let cluster_cache = self.cluster_cache.clone();
+        let mqtt_cache = self.mqtt_cache.clone();
+        let engine_cache = self.engine_cache.clone();
+        let mut stop_recv = stop_send.subscribe();
+        tokio::spawn(async move {
+            loop {
+                select! {
+                    val = stop_recv.recv() => {
+                        if let Ok(flag) = val {
+                            if flag {
+                                break;
+                            }
+                        }
+                    }
+                    _ = sleep(Duration::from_secs(15)) => {
+                        for (cache, sizes) in [
+                            ("cluster", cluster_cache.resource_sizes()),
+                            ("mqtt", mqtt_cache.resource_sizes()),
+                            ("journal", engine_cache.resource_sizes()),
+                        ] {
+                            for (resource, size) in sizes {
+                                metrics_cache_size(cache, resource, size);
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+
     fn start_call_thread(&self) {
         let client_pool = self.client_pool.clone();
         let journal_all_manager = self.journal_call_manager.clone();
@@ -189,6 +189,22 @@ pub fn get_all_connector_heartbeat(&self) -> Vec<ConnectorHeartbeat> {
         }
         results
     }
+
+    pub fn resource_sizes(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("topic", self.topic_list.iter().map(|list| list.len()).sum()),
+            ("user", self.user_list.iter().map(|list| list.len()).sum()),
+            (
+                "expire_last_will",
+                self.expire_last_wills.iter().map(|list| list.len()).sum(),
+            ),
+            (
+                "connector",
+                self.connector_list.iter().map(|list| list.len()).sum(),
+            ),
+            ("connector_heartbeat", self.connector_heartbeat.len()),
+        ]
+    }
 }
 
 pub fn load_mqtt_cache(
@@ -429,6 +429,22 @@ pub async fn update_cache_by_set_resource_config(
     Ok(())
 }
 
+// The metadata of the cluster was restored from a backup, its brokers load it again
+pub async fn update_cache_by_reload_metadata(
+    cluster_name: &str,
+    call_manager: &Arc<MQTTInnerCallManager>,
+    client_pool: &Arc<ClientPool>,
+) -> Result<(), PlacementCenterError> {
+    let message = MQTTInnerCallMessage {
+        action_type: MqttBrokerUpdateCacheActionType::Set,
+        resource_type: MqttBrokerUpdateCacheResourceType::Metadata,
+        cluster_name: cluster_name.to_string(),
+        data: "".to_string(),
+    };
+    add_call_message(call_manager, cluster_name, client_pool, message).await?;
+    Ok(())
+}
+
 async fn start_call_thread(
     cluster_name: String,
     node: BrokerNode,
@@ -22,8 +22,11 @@
 };
 
 use super::typeconfig::TypeConfig;
+use crate::core::metrics::{
+    metrics_raft_commit_lag, metrics_raft_commit_lag_clear, metrics_raft_status,
+};
 use grpc_clients::pool::ClientPool;
-use openraft::Raft;
+use openraft::{Raft, RaftMetrics};
 use rocksdb_engine::RocksDBEngine;
 use tokio::sync::broadcast::{self, Sender};
 use tracing::{error, info};
@@ -46,6 +49,7 @@ pub fn monitoring_leader_transition(
             match metrics_rx.changed().await {
                 Ok(_) => {
                     let mm = metrics_rx.borrow().clone();
+                    record_raft_metrics(&mm);
 
                     if let Some(current_leader) = mm.current_leader {
                         if last_leader != Some(current_leader) {
@@ -121,3 +125,25 @@ pub fn stop_controller(stop_send: Sender<bool>) {
         );
     }
 }
+
+fn record_raft_metrics(mm: &RaftMetrics<TypeConfig>) {
+    let last_log_index = mm.last_log_index.unwrap_or(0);
+    let last_applied_index = mm.last_applied.as_ref().map(|id| id.index).unwrap_or(0);
+    metrics_raft_status(mm.id, mm.current_term, last_log_index, last_applied_index);
+
+    // Only the leader tracks replication progress; followers drop their stale series.
+    metrics_raft_commit_lag_clear();
+    if let Some(replication) = &mm.replication {
+        for (follower_id, matched) in replication.iter() {
+            if *follower_id == mm.id {
+                continue;
+            }
+            let matched_index = matched.as_ref().map(|id| id.index).unwrap_or(0);
+            metrics_raft_commit_lag(
+                mm.id,
+                *follower_id,
+                last_log_index.saturating_sub(matched_index),
+            );
+        }
+    }
+}
@@ -26,7 +26,7 @@
 
 use super::{cf_raft_store, StorageResult, StoredSnapshot};
 use crate::core::metrics::{
-    metrics_raft_storage_error_incr, metrics_raft_storage_total_incr,
+    metrics_raft_apply_ms, metrics_raft_storage_error_incr, metrics_raft_storage_total_incr,
     metrics_raft_storage_total_ms, metrics_rocksdb_storage_err_inc,
     metrics_rocksdb_storage_total_inc, metrics_rocksdb_stroge_total_ms, RocksDBLabels,
 };
@@ -215,6 +215,12 @@ async fn apply<I>(
             self.data.last_applied_log_id = Some(ent.log_id);
 
             let mut resp_value = None;
+            let apply_start_ms = now_mills();
+            let entry_type = match ent.payload {
+                EntryPayload::Blank => "blank",
+                EntryPayload::Normal(_) => "normal",
+                EntryPayload::Membership(_) => "membership",
+            };
 
             match ent.payload {
                 EntryPayload::Blank => {}
@@ -239,6 +245,7 @@ async fn apply<I>(
                     self.data.last_membership = StoredMembership::new(Some(ent.log_id), mem);
                 }
             }
+            metrics_raft_apply_ms(entry_type, (now_mills() - apply_start_ms) as f64);
 
             replies.push(AppResponseData { value: resp_value });
         }
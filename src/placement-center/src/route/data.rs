@@ -87,4 +87,8 @@ pub enum StorageDataType {
     MqttDeleteConnector,
     MqttSetAutoSubscribeRule,
     MqttDeleteAutoSubscribeRule,
+
+    // Backup
+    ClusterRestoreMetadata,
+    ClusterReloadMetadataCache,
 }
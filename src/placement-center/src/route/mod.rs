@@ -24,12 +24,15 @@
 
 use bincode::{deserialize, serialize};
 use data::{StorageData, StorageDataType};
+use prost::Message;
+use protocol::placement_center::placement_center_inner::RestoreMetadataRequest;
 use tracing::{error, info};
 
+use crate::core::backup::{restore_backup, MetadataBackup};
 use crate::core::cache::PlacementCacheManager;
 use crate::core::error::PlacementCenterError;
-use crate::journal::cache::JournalCacheManager;
-use crate::mqtt::cache::MqttCacheManager;
+use crate::journal::cache::{load_journal_cache, JournalCacheManager};
+use crate::mqtt::cache::{load_mqtt_cache, MqttCacheManager};
 use crate::route::common::DataRouteCluster;
 use crate::route::journal::DataRouteJournal;
 use crate::route::kv::DataRouteKv;
@@ -43,6 +46,9 @@ pub struct DataRoute {
     route_journal: DataRouteJournal,
     route_cluster: DataRouteCluster,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
+    cluster_cache: Arc<PlacementCacheManager>,
+    engine_cache: Arc<JournalCacheManager>,
+    mqtt_cache: Arc<MqttCacheManager>,
 }
 
 impl DataRoute {
@@ -64,6 +70,9 @@ pub fn new(
             route_journal,
             route_cluster,
             rocksdb_engine_handler,
+            cluster_cache,
+            engine_cache,
+            mqtt_cache,
         }
     }
 
@@ -258,9 +267,40 @@ pub async fn route(
                     .delete_auto_subscribe_rule(storage_data.value)?;
                 Ok(None)
             }
+
+            // Backup
+            // The empty reply marks the chunk as written
+            StorageDataType::ClusterRestoreMetadata => {
+                self.restore_metadata(storage_data.value)?;
+                Ok(Some(Vec::new()))
+            }
+            StorageDataType::ClusterReloadMetadataCache => {
+                self.reload_metadata_cache()?;
+                Ok(None)
+            }
         }
     }
 
+    // Write the records of one chunk of a backup archive
+    fn restore_metadata(&self, value: Vec<u8>) -> Result<(), PlacementCenterError> {
+        let req = RestoreMetadataRequest::decode(value.as_ref())?;
+        let backup = MetadataBackup::decode(&req.data)?;
+        restore_backup(&self.rocksdb_engine_handler, &backup, req.force)
+    }
+
+    // Reload the caches that are built from the metadata, once all chunks of a restore are written
+    fn reload_metadata_cache(&self) -> Result<(), PlacementCenterError> {
+        self.cluster_cache
+            .load_cache(self.rocksdb_engine_handler.clone());
+        load_journal_cache(&self.engine_cache, &self.rocksdb_engine_handler)?;
+        load_mqtt_cache(
+            &self.mqtt_cache,
+            &self.rocksdb_engine_handler,
+            &self.cluster_cache,
+        )?;
+        Ok(())
+    }
+
     pub fn build_snapshot(&self) -> Vec<u8> {
         info!("Start building snapshots");
         let cf = if let Some(cf) = self
@@ -21,10 +21,11 @@
     un_bind_schema_req, update_schema_req,
 };
 use crate::inner::services::{
-    cluster_status_by_req, delete_idempotent_data_by_req, delete_resource_config_by_req,
-    exists_idempotent_data_by_req, get_offset_data_by_req, get_resource_config_by_req,
-    heartbeat_by_req, node_list_by_req, save_offset_data_by_req, set_idempotent_data_by_req,
-    set_resource_config_by_req,
+    backup_metadata_by_req, cluster_status_by_req, delete_idempotent_data_by_req,
+    delete_resource_config_by_req, exists_idempotent_data_by_req, get_log_level_by_req,
+    get_offset_data_by_req, get_resource_config_by_req, heartbeat_by_req, node_list_by_req,
+    restore_metadata_by_req, save_offset_data_by_req, set_idempotent_data_by_req,
+    set_log_level_by_req, set_resource_config_by_req,
 };
 use crate::journal::controller::call_node::JournalInnerCallManager;
 use crate::mqtt::controller::call_broker::MQTTInnerCallManager;
@@ -34,17 +35,19 @@
 use prost_validate::Validator;
 use protocol::placement_center::placement_center_inner::placement_center_service_server::PlacementCenterService;
 use protocol::placement_center::placement_center_inner::{
-    BindSchemaReply, BindSchemaRequest, ClusterStatusReply, ClusterStatusRequest,
-    CreateSchemaReply, CreateSchemaRequest, DeleteIdempotentDataReply, DeleteIdempotentDataRequest,
-    DeleteResourceConfigReply, DeleteResourceConfigRequest, DeleteSchemaReply, DeleteSchemaRequest,
-    ExistsIdempotentDataReply, ExistsIdempotentDataRequest, GetOffsetDataReply,
+    BackupMetadataReply, BackupMetadataRequest, BindSchemaReply, BindSchemaRequest,
+    ClusterStatusReply, ClusterStatusRequest, CreateSchemaReply, CreateSchemaRequest,
+    DeleteIdempotentDataReply, DeleteIdempotentDataRequest, DeleteResourceConfigReply,
+    DeleteResourceConfigRequest, DeleteSchemaReply, DeleteSchemaRequest, ExistsIdempotentDataReply,
+    ExistsIdempotentDataRequest, GetLogLevelReply, GetLogLevelRequest, GetOffsetDataReply,
     GetOffsetDataRequest, GetResourceConfigReply, GetResourceConfigRequest, HeartbeatReply,
     HeartbeatRequest, ListBindSchemaReply, ListBindSchemaRequest, ListSchemaReply,
     ListSchemaRequest, NodeListReply, NodeListRequest, RegisterNodeReply, RegisterNodeRequest,
-    ReportMonitorReply, ReportMonitorRequest, SaveOffsetDataReply, SaveOffsetDataRequest,
-    SetIdempotentDataReply, SetIdempotentDataRequest, SetResourceConfigReply,
-    SetResourceConfigRequest, UnBindSchemaReply, UnBindSchemaRequest, UnRegisterNodeReply,
-    UnRegisterNodeRequest, UpdateSchemaReply, UpdateSchemaRequest,
+    ReportMonitorReply, ReportMonitorRequest, RestoreMetadataReply, RestoreMetadataRequest,
+    SaveOffsetDataReply, SaveOffsetDataRequest, SetIdempotentDataReply, SetIdempotentDataRequest,
+    SetLogLevelReply, SetLogLevelRequest, SetResourceConfigReply, SetResourceConfigRequest,
+    UnBindSchemaReply, UnBindSchemaRequest, UnRegisterNodeReply, UnRegisterNodeRequest,
+    UpdateSchemaReply, UpdateSchemaRequest,
 };
 use tonic::{Request, Response, Status};
 use tracing::info;
@@ -90,6 +93,53 @@ async fn cluster_status(
             .map(Response::new)
     }
 
+    async fn set_log_level(
+        &self,
+        request: Request<SetLogLevelRequest>,
+    ) -> Result<Response<SetLogLevelReply>, Status> {
+        let req = request.into_inner();
+        set_log_level_by_req(&req)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn get_log_level(
+        &self,
+        _: Request<GetLogLevelRequest>,
+    ) -> Result<Response<GetLogLevelReply>, Status> {
+        get_log_level_by_req()
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn backup_metadata(
+        &self,
+        request: Request<BackupMetadataRequest>,
+    ) -> Result<Response<BackupMetadataReply>, Status> {
+        let req = request.into_inner();
+        backup_metadata_by_req(&self.rocksdb_engine_handler, &req)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn restore_metadata(
+        &self,
+        request: Request<RestoreMetadataRequest>,
+    ) -> Result<Response<RestoreMetadataReply>, Status> {
+        let req = request.into_inner();
+        restore_metadata_by_req(
+            &self.raft_machine_apply,
+            &self.rocksdb_engine_handler,
+            &self.cluster_cache,
+            &self.mqtt_call_manager,
+            &self.client_pool,
+            &req,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
     async fn node_list(
         &self,
         request: Request<NodeListRequest>,
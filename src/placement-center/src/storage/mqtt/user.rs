@@ -96,6 +96,7 @@ async fn user_storage_test() {
             username: username.clone(),
             password: "pwd123".to_string(),
             is_superuser: true,
+            tenant: String::new(),
         };
         user_storage.save(&cluster_name, &username, user).unwrap();
 
@@ -104,6 +105,7 @@ async fn user_storage_test() {
             username: username.clone(),
             password: "pwd1231".to_string(),
             is_superuser: true,
+            tenant: String::new(),
         };
         user_storage.save(&cluster_name, &username, user).unwrap();
 
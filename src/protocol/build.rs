@@ -12,7 +12,31 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::path::PathBuf;
+
+const PROTOS: [&str; 11] = [
+    "placement_center/inner.proto",
+    "placement_center/journal.proto",
+    "placement_center/kv.proto",
+    "placement_center/mqtt.proto",
+    "placement_center/openraft.proto",
+    "broker_mqtt/admin.proto",
+    "broker_mqtt/inner.proto",
+    "journal_server/admin.proto",
+    "journal_server/engine.proto",
+    "journal_server/inner.proto",
+    "journal_server/record.proto",
+];
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    robustmq_proto_build::setup()?;
+    let proto_root = PathBuf::from("proto");
+    let protos: Vec<PathBuf> = PROTOS.iter().map(|proto| proto_root.join(proto)).collect();
+
+    // Every message derives prost_validate::Validator
+    let mut config = prost_build::Config::new();
+    prost_validate_build::Builder::new().configure(&mut config, &protos, &[&proto_root])?;
+    tonic_build::configure()
+        .build_server(true)
+        .compile_protos_with_config(config, &protos, &[&proto_root])?;
     Ok(())
 }
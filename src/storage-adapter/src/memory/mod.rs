@@ -26,6 +26,8 @@
 pub struct MemoryStorageAdapter {
     pub shard_info: DashMap<String, ShardInfo>,
     pub shard_data: DashMap<String, Vec<Record>>,
+    // shard_key, offset of the first record kept in shard_data
+    pub shard_start_offset: DashMap<String, u64>,
     //group, (namespace_shard_name,offset)
     pub group_data: DashMap<String, DashMap<String, u64>>,
 }
@@ -40,6 +42,7 @@ impl MemoryStorageAdapter {
     pub fn new() -> Self {
         MemoryStorageAdapter {
             shard_data: DashMap::with_capacity(256),
+            shard_start_offset: DashMap::with_capacity(256),
             group_data: DashMap::with_capacity(256),
             shard_info: DashMap::with_capacity(2),
         }
@@ -48,6 +51,13 @@ pub fn new() -> Self {
     pub fn shard_key(&self, namespace: &str, shard_name: &str) -> String {
         format!("{}_{}", namespace, shard_name)
     }
+
+    fn start_offset(&self, shard_key: &str) -> u64 {
+        self.shard_start_offset
+            .get(shard_key)
+            .map(|offset| *offset)
+            .unwrap_or(0)
+    }
 }
 
 impl MemoryStorageAdapter {}
@@ -77,8 +87,9 @@ async fn list_shard(
     }
 
     async fn delete_shard(&self, namespace: String, shard_name: String) -> Result<(), CommonError> {
-        self.shard_data
-            .remove(&self.shard_key(&namespace, &shard_name));
+        let shard_key = self.shard_key(&namespace, &shard_name);
+        self.shard_data.remove(&shard_key);
+        self.shard_start_offset.remove(&shard_key);
         return Ok(());
     }
 
@@ -92,7 +103,7 @@ async fn batch_write(
         let mut offset_res = Vec::new();
 
         if let Some(mut data_list) = self.shard_data.get_mut(&shard_key) {
-            let mut start_offset = data_list.len();
+            let mut start_offset = self.start_offset(&shard_key) as usize + data_list.len();
             for mut msg in messages {
                 offset_res.push(start_offset as u64);
                 msg.offset = Some(start_offset as u64);
@@ -122,7 +133,7 @@ async fn write(
         let shard_key = self.shard_key(&namespace, &shard_name);
 
         let offset = if let Some(mut data_list) = self.shard_data.get_mut(&shard_key) {
-            let start_offset = data_list.len();
+            let start_offset = self.start_offset(&shard_key) as usize + data_list.len();
 
             data.offset = Some(start_offset as u64);
             data_list.push(data);
@@ -147,6 +158,8 @@ async fn read_by_offset(
         let shard_key = self.shard_key(&namespace, &shard_name);
 
         if let Some(data_list) = self.shard_data.get(&shard_key) {
+            let start_offset = self.start_offset(&shard_key);
+            let offset = offset.max(start_offset) - start_offset;
             if data_list.len() < offset as usize {
                 return Ok(Vec::new());
             }
@@ -176,6 +189,8 @@ async fn read_by_tag(
         let shard_key = self.shard_key(&namespace, &shard_name);
 
         if let Some(record_list) = self.shard_data.get(&shard_key) {
+            let start_offset = self.start_offset(&shard_key);
+            let offset = offset.max(start_offset) - start_offset;
             if record_list.len() < offset as usize {
                 return Ok(Vec::new());
             }
@@ -207,6 +222,8 @@ async fn read_by_key(
         let shard_key = self.shard_key(&namespace, &shard_name);
 
         if let Some(record_list) = self.shard_data.get(&shard_key) {
+            let start_offset = self.start_offset(&shard_key);
+            let offset = offset.max(start_offset) - start_offset;
             if record_list.len() < offset as usize {
                 return Ok(Vec::new());
             }
@@ -291,6 +308,26 @@ async fn commit_offset(
         Ok(())
     }
 
+    async fn delete_by_offset(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+    ) -> Result<(), CommonError> {
+        let shard_key = self.shard_key(&namespace, &shard_name);
+        if let Some(mut data_list) = self.shard_data.get_mut(&shard_key) {
+            let start_offset = self.start_offset(&shard_key);
+            if offset <= start_offset {
+                return Ok(());
+            }
+            let num = ((offset - start_offset) as usize).min(data_list.len());
+            data_list.drain(..num);
+            self.shard_start_offset
+                .insert(shard_key.clone(), start_offset + num as u64);
+        }
+        Ok(())
+    }
+
     async fn close(&self) -> Result<(), CommonError> {
         Ok(())
     }
@@ -464,4 +501,52 @@ async fn stream_read_write() {
             .await
             .unwrap();
     }
+
+    #[tokio::test]
+    async fn delete_by_offset_test() {
+        let storage_adapter = MemoryStorageAdapter::new();
+        let namespace = unique_id();
+        let shard_name = "test-delete".to_string();
+        let data = (0..4)
+            .map(|i| Record::build_byte(format!("test{}", i).as_bytes().to_vec()))
+            .collect();
+        storage_adapter
+            .batch_write(namespace.clone(), shard_name.clone(), data)
+            .await
+            .unwrap();
+
+        storage_adapter
+            .delete_by_offset(namespace.clone(), shard_name.clone(), 2)
+            .await
+            .unwrap();
+        let mut read_config = ReadConfig::new();
+        read_config.max_record_num = 10;
+        let res = storage_adapter
+            .read_by_offset(
+                namespace.clone(),
+                shard_name.clone(),
+                0,
+                read_config.clone(),
+            )
+            .await
+            .unwrap();
+        let offsets: Vec<Option<u64>> = res.iter().map(|record| record.offset).collect();
+        assert_eq!(offsets, vec![Some(2), Some(3)]);
+
+        // Later records keep counting from the last offset
+        let offset = storage_adapter
+            .write(
+                namespace.clone(),
+                shard_name.clone(),
+                Record::build_byte(b"test4".to_vec()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(offset, 4);
+        let res = storage_adapter
+            .read_by_offset(namespace, shard_name, 3, read_config)
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 2);
+    }
 }
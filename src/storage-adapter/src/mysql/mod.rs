@@ -632,6 +632,23 @@ async fn commit_offset(
         Ok(())
     }
 
+    async fn delete_by_offset(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+    ) -> Result<(), CommonError> {
+        let mut conn = self.pool.get_conn()?;
+
+        let sql = format!(
+            "DELETE FROM `{}` WHERE `offset` < :offset;",
+            Self::record_table_name(&namespace, &shard_name)
+        );
+        conn.exec_drop(sql, params! { "offset" => offset })?;
+
+        Ok(())
+    }
+
     async fn close(&self) -> Result<(), CommonError> {
         self.stop_send.send(true).await.map_err(|err| {
             CommonError::CommonError(format!("Failed to send stop signal: {}", err))
@@ -619,6 +619,31 @@ async fn commit_offset(
         Ok(())
     }
 
+    async fn delete_by_offset(
+        &self,
+        namespace: String,
+        shard_name: String,
+        offset: u64,
+    ) -> Result<(), CommonError> {
+        self.ensure_shard_exists(&namespace, &shard_name)?;
+
+        let cf = self.db.cf_handle(DB_COLUMN_FAMILY).unwrap();
+
+        // records before an already removed one were removed as well
+        for i in (0..offset).rev() {
+            let shard_record_key = Self::shard_record_key(&namespace, &shard_name, i);
+            if self
+                .db
+                .read::<Record>(cf.clone(), &shard_record_key)?
+                .is_none()
+            {
+                break;
+            }
+            self.db.delete(cf.clone(), &shard_record_key)?;
+        }
+        Ok(())
+    }
+
     async fn close(&self) -> Result<(), CommonError> {
         let write_handles = self.get_all_write_handles().await;
 
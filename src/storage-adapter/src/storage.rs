@@ -106,5 +106,16 @@ async fn commit_offset(
         offset: HashMap<String, u64>,
     ) -> Result<(), CommonError>;
 
+    // Removes the records of the shard before `offset`, the records after it keep their
+    // offsets. Storages that cannot remove records keep them.
+    async fn delete_by_offset(
+        &self,
+        _namespace: String,
+        _shard_name: String,
+        _offset: u64,
+    ) -> Result<(), CommonError> {
+        Ok(())
+    }
+
     async fn close(&self) -> Result<(), CommonError>;
 }
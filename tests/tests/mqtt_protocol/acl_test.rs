@@ -342,6 +342,7 @@ async fn create_user(
             username,
             password,
             is_superuser: false,
+            tenant: String::new(),
         };
         let res = mqtt_broker_create_user(&client_pool, &grpc_addr, user.clone()).await;
         assert!(res.is_ok());
@@ -243,6 +243,7 @@ async fn create_user(
             username: username.to_owned(),
             password: password.to_owned(),
             is_superuser: false,
+            tenant: String::new(),
         };
 
         let res = mqtt_broker_create_user(client_pool, addrs, user.clone()).await;